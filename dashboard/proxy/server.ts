@@ -3,10 +3,12 @@ import cors from 'cors';
 import { WebSocketServer, WebSocket } from 'ws';
 import http from 'http';
 import path from 'path';
+import { timingSafeEqual } from 'crypto';
 import { fileURLToPath } from 'url';
 
 import {
   pingDaemon,
+  checkHealth,
   getDiagnostics,
   queryTimeline,
   search,
@@ -18,6 +20,8 @@ import {
 
 const __dirname = path.dirname(fileURLToPath(import.meta.url));
 const PORT = process.env.PORT || 3947;
+// Set by `diachron dashboard start` when dashboard.require_auth is on
+const TOKEN = process.env.DIACHRON_DASHBOARD_TOKEN || '';
 
 const app = express();
 app.use(cors());
@@ -26,6 +30,33 @@ app.use(express.json());
 // Serve static files in production
 app.use(express.static(path.join(__dirname, '../dist')));
 
+// Whether a request token matches the dashboard token (always, without auth)
+function tokenMatches(candidate: string | null | undefined): boolean {
+  if (!TOKEN) return true;
+  if (!candidate) return false;
+  const given = Buffer.from(candidate);
+  const expected = Buffer.from(TOKEN);
+  return given.length === expected.length && timingSafeEqual(given, expected);
+}
+
+// Token from `Authorization: Bearer <token>` or a `?token=` query parameter
+function requestToken(req: Request): string | undefined {
+  const header = req.headers.authorization;
+  if (header?.startsWith('Bearer ')) return header.slice('Bearer '.length);
+  return typeof req.query.token === 'string' ? req.query.token : undefined;
+}
+
+app.use('/api', (req, res, next) => {
+  if (tokenMatches(requestToken(req))) {
+    next();
+    return;
+  }
+  res.status(401).json({
+    error: 'Unauthorized',
+    message: 'Missing or invalid dashboard token (see ~/.diachron/dashboard.token)',
+  });
+});
+
 // Error wrapper for async handlers
 const asyncHandler = (fn: (req: Request, res: Response, next: NextFunction) => Promise<void>) =>
   (req: Request, res: Response, next: NextFunction) => {
@@ -35,12 +66,13 @@ const asyncHandler = (fn: (req: Request, res: Response, next: NextFunction) => P
 // Health check
 app.get('/api/health', asyncHandler(async (_req, res) => {
   try {
-    const pong = await pingDaemon();
+    const [pong, health] = await Promise.all([pingDaemon(), checkHealth()]);
     res.json({
       status: 'ok',
       daemon: 'connected',
       uptime_secs: pong.uptime_secs,
       events_count: pong.events_count,
+      ...health,
     });
   } catch (err) {
     res.status(503).json({
@@ -245,7 +277,14 @@ const wss = new WebSocketServer({ server, path: '/ws/events' });
 // Store connected clients
 const clients = new Set<WebSocket>();
 
-wss.on('connection', (ws) => {
+wss.on('connection', (ws, req) => {
+  // Browsers can't set headers on WebSockets, so the token comes in the URL
+  const token = new URL(req.url ?? '', 'http://localhost').searchParams.get('token');
+  if (!tokenMatches(token)) {
+    ws.close(1008, 'Unauthorized');
+    return;
+  }
+
   clients.add(ws);
   console.log('WebSocket client connected');
 
@@ -99,6 +99,19 @@ export async function pingDaemon(): Promise<{ uptime_secs: number; events_count:
   throw new Error('Unexpected response from daemon');
 }
 
+/**
+ * Check whether the daemon is ready to serve semantic search
+ */
+export async function checkHealth(): Promise<HealthReport> {
+  const response = await sendToDaemon({ type: 'Health' });
+
+  if (response.type === 'Health' && response.payload) {
+    return response.payload as HealthReport;
+  }
+
+  throw new Error('Unexpected response from daemon');
+}
+
 /**
  * Get diagnostic info from daemon
  */
@@ -129,8 +142,8 @@ export async function queryTimeline(options: {
     },
   });
 
-  if (response.type === 'Events' && response.payload) {
-    return response.payload as StoredEvent[];
+  if (response.type === 'EventsPage' && response.payload) {
+    return (response.payload as { events: StoredEvent[] }).events;
   }
 
   throw new Error('Failed to query timeline');
@@ -275,6 +288,14 @@ export interface BlameMatch {
   intent?: string;
 }
 
+export interface HealthReport {
+  ready: boolean;
+  model_loaded: boolean;
+  events_index_ok: boolean;
+  exchanges_index_ok: boolean;
+  warnings: string[];
+}
+
 export interface DiagnosticInfo {
   uptime_secs: number;
   events_count: number;
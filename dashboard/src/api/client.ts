@@ -14,6 +14,20 @@ import type {
 } from '@/types/diachron';
 
 const API_BASE = '/api';
+const TOKEN_KEY = 'diachron-dashboard-token';
+
+// Dashboard token, if auth is on. `diachron dashboard start` opens the page
+// with `?token=`; it is kept for the tab and removed from the address bar.
+function authToken(): string | null {
+  const url = new URL(window.location.href);
+  const fromUrl = url.searchParams.get('token');
+  if (fromUrl) {
+    sessionStorage.setItem(TOKEN_KEY, fromUrl);
+    url.searchParams.delete('token');
+    window.history.replaceState(window.history.state, '', url);
+  }
+  return sessionStorage.getItem(TOKEN_KEY);
+}
 
 class ApiError extends Error {
   constructor(
@@ -26,12 +40,12 @@ class ApiError extends Error {
 }
 
 async function fetchApi<T>(path: string, options?: RequestInit): Promise<T> {
-  const response = await fetch(`${API_BASE}${path}`, {
-    headers: {
-      'Content-Type': 'application/json',
-    },
-    ...options,
-  });
+  const token = authToken();
+  // Merge rather than spread, so a caller's headers keep the defaults
+  const headers = new Headers(options?.headers);
+  if (!headers.has('Content-Type')) headers.set('Content-Type', 'application/json');
+  if (token && !headers.has('Authorization')) headers.set('Authorization', `Bearer ${token}`);
+  const response = await fetch(`${API_BASE}${path}`, { ...options, headers });
 
   if (!response.ok) {
     const error = await response.json().catch(() => ({ message: 'Unknown error' }));
@@ -160,7 +174,9 @@ export function connectEventStream(
   onError?: (error: Event) => void
 ): WebSocket {
   const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-  const ws = new WebSocket(`${protocol}//${window.location.host}/ws/events`);
+  const token = authToken();
+  const query = token ? `?token=${encodeURIComponent(token)}` : '';
+  const ws = new WebSocket(`${protocol}//${window.location.host}/ws/events${query}`);
 
   ws.onmessage = (event) => {
     try {
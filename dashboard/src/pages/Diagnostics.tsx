@@ -104,6 +104,11 @@ export function DiagnosticsPage() {
                   Uptime: {formatDuration(diagnostics.uptime_secs)}
                 </p>
               )}
+              {isConnected && health?.ready === false && (
+                <p className="text-xs text-confidence-medium mt-1">
+                  Not ready: {health.warnings?.join('; ')}
+                </p>
+              )}
             </div>
             <div className="p-3 rounded-lg bg-noir-800/50">
               <Activity size={24} className={isConnected ? 'text-confidence-high' : 'text-op-delete'} />
@@ -111,6 +111,11 @@ export interface HealthStatus {
   daemon: 'connected' | 'disconnected';
   uptime_secs?: number;
   events_count?: number;
+  ready?: boolean;
+  model_loaded?: boolean;
+  events_index_ok?: boolean;
+  exchanges_index_ok?: boolean;
+  warnings?: string[];
   message?: string;
 }
 
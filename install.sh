@@ -365,7 +365,7 @@ if "PostToolUse" not in settings["hooks"]:
 
 # Define the Diachron hook
 diachron_hook = {
-    "matcher": "Write|Edit|Bash",
+    "matcher": "Write|Edit|MultiEdit|NotebookEdit|Bash",
     "hooks": [{
         "type": "command",
         "command": "$HOOK_CMD",
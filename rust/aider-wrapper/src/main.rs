@@ -0,0 +1,477 @@
+//! Diachron Aider Wrapper
+//! ======================
+//!
+//! Standalone wrapper for Aider that captures applied edits for Diachron
+//! provenance tracking.
+//!
+//! Usage:
+//!     diachron-aider --model sonnet src/auth.py
+//!     diachron-aider --import .aider.chat.history.md
+//!
+//! Aider edits files directly and appends the conversation to
+//! `.aider.chat.history.md`. The wrapper notes the history length before
+//! running `aider`, then parses only the appended part for edit blocks
+//! (SEARCH/REPLACE blocks or unified ```` ```diff ```` fences) that Aider
+//! reports as applied, and sends them as `Capture` events.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use diachron_core::{send_to_daemon, CaptureEvent, DiffStats, IpcError, Operation};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// Aider's default chat history file (relative to the working directory)
+const DEFAULT_HISTORY_FILE: &str = ".aider.chat.history.md";
+
+/// Diachron wrapper for Aider - tracks applied edits for provenance
+#[derive(Parser, Debug)]
+#[command(
+    name = "diachron-aider",
+    about = "Aider wrapper with Diachron provenance tracking",
+    version,
+    trailing_var_arg = true
+)]
+struct Args {
+    /// Arguments to pass through to aider
+    #[arg(trailing_var_arg = true)]
+    aider_args: Vec<String>,
+
+    /// Chat history file Aider writes to
+    #[arg(long, default_value = DEFAULT_HISTORY_FILE)]
+    history_file: PathBuf,
+
+    /// Import edits from an existing history file instead of running aider
+    #[arg(long, value_name = "PATH")]
+    import: Option<PathBuf>,
+
+    /// Skip sending events to Diachron (useful for testing)
+    #[arg(long, hide = true)]
+    no_diachron: bool,
+
+    /// Verbose output
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// An edit extracted from Aider's chat history
+#[derive(Debug, Clone, PartialEq)]
+struct AiderEdit {
+    file_path: String,
+    operation: Operation,
+    stats: DiffStats,
+    raw_input: String,
+}
+
+/// Parsed chat history: session start time plus edits Aider applied
+#[derive(Debug, Default)]
+struct AiderHistory {
+    /// Value of the last "# aider chat started at ..." header
+    session_started: Option<String>,
+    edits: Vec<AiderEdit>,
+}
+
+/// Where the parser is within a fenced block
+enum Fence {
+    /// Outside any fence
+    None,
+    /// Inside a ```diff fence, collecting unified diff lines
+    Diff(Vec<String>),
+    /// Inside another fence, which may hold SEARCH/REPLACE blocks
+    Other,
+}
+
+/// Parse Aider chat history markdown.
+///
+/// Edits are kept only if a later "> Applied edit to <file>" line confirms
+/// them, so rejected or malformed blocks don't show up as changes.
+fn parse_history(text: &str) -> AiderHistory {
+    let mut history = AiderHistory::default();
+    let mut candidates: Vec<AiderEdit> = Vec::new();
+    let mut applied: HashSet<String> = HashSet::new();
+
+    let mut fence = Fence::None;
+    let mut last_text_line: Option<String> = None;
+    // (file, search lines, replace lines, in replace section)
+    let mut block: Option<(String, Vec<String>, Vec<String>, bool)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some(started) = trimmed.strip_prefix("# aider chat started at ") {
+            history.session_started = Some(started.trim().to_string());
+            continue;
+        }
+        if let Some(file) = trimmed.strip_prefix("> Applied edit to ") {
+            applied.insert(file.trim().to_string());
+            continue;
+        }
+
+        // SEARCH/REPLACE blocks, in or out of a fence
+        if trimmed == "<<<<<<< SEARCH" {
+            if let Some(file) = last_text_line.take() {
+                block = Some((file, Vec::new(), Vec::new(), false));
+            }
+            continue;
+        }
+        if let Some((file, search, replace, in_replace)) = block.as_mut() {
+            if trimmed == "=======" {
+                *in_replace = true;
+            } else if trimmed.starts_with(">>>>>>> REPLACE") {
+                candidates.push(search_replace_edit(file, search, replace));
+                block = None;
+            } else if *in_replace {
+                replace.push(line.to_string());
+            } else {
+                search.push(line.to_string());
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            fence = match fence {
+                Fence::None if trimmed == "```diff" => Fence::Diff(Vec::new()),
+                Fence::None => Fence::Other,
+                Fence::Diff(lines) => {
+                    candidates.extend(parse_unified_diff(&lines));
+                    Fence::None
+                }
+                Fence::Other => Fence::None,
+            };
+            continue;
+        }
+
+        match &mut fence {
+            Fence::Diff(lines) => lines.push(line.to_string()),
+            _ if !trimmed.is_empty() => last_text_line = Some(trimmed.to_string()),
+            _ => {}
+        }
+    }
+
+    history.edits = candidates
+        .into_iter()
+        .filter(|edit| applied.iter().any(|a| same_file(a, &edit.file_path)))
+        .collect();
+    history
+}
+
+/// Build an edit from one SEARCH/REPLACE block (empty SEARCH creates the file).
+fn search_replace_edit(file: &str, search: &[String], replace: &[String]) -> AiderEdit {
+    let operation = if search.iter().all(|l| l.trim().is_empty()) {
+        Operation::Create
+    } else {
+        Operation::Modify
+    };
+
+    // Express the block as a diff so it shares the line counting with patches
+    let patch: String = search
+        .iter()
+        .map(|l| format!("-{}\n", l))
+        .chain(replace.iter().map(|l| format!("+{}\n", l)))
+        .collect();
+    let stats = if operation == Operation::Create {
+        DiffStats {
+            added: replace.len(),
+            removed: 0,
+        }
+    } else {
+        DiffStats::from_patch(&patch)
+    };
+
+    AiderEdit {
+        file_path: file.to_string(),
+        operation,
+        stats,
+        raw_input: replace.join("\n"),
+    }
+}
+
+/// Split a unified diff into per-file edits.
+fn parse_unified_diff(lines: &[String]) -> Vec<AiderEdit> {
+    let mut edits = Vec::new();
+    let mut old_path: Option<String> = None;
+    let mut current: Option<(String, Operation, Vec<String>)> = None;
+
+    let mut finish = |current: Option<(String, Operation, Vec<String>)>| {
+        if let Some((file_path, operation, body)) = current {
+            let patch = body.join("\n");
+            edits.push(AiderEdit {
+                file_path,
+                operation,
+                stats: DiffStats::from_patch(&patch),
+                raw_input: patch,
+            });
+        }
+    };
+
+    for line in lines {
+        if let Some(path) = line.strip_prefix("--- ") {
+            finish(current.take());
+            old_path = Some(path.trim().to_string());
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            let new_path = path.trim();
+            let old = old_path.take().unwrap_or_default();
+            current = if new_path == "/dev/null" {
+                Some((strip_diff_prefix(&old), Operation::Delete, Vec::new()))
+            } else if old == "/dev/null" {
+                Some((strip_diff_prefix(new_path), Operation::Create, Vec::new()))
+            } else {
+                Some((strip_diff_prefix(new_path), Operation::Modify, Vec::new()))
+            };
+        } else if let Some((_, _, body)) = current.as_mut() {
+            if !line.starts_with("@@") {
+                body.push(line.clone());
+            }
+        }
+    }
+    finish(current);
+
+    edits
+}
+
+/// Drop the `a/` / `b/` prefix git-style diffs put on paths
+fn strip_diff_prefix(path: &str) -> String {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Whether two paths name the same file (one may be relative to the other)
+fn same_file(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!("/{}", b)) || b.ends_with(&format!("/{}", a))
+}
+
+/// Read the part of the history file written after `offset` bytes.
+///
+/// If the file shrank (e.g., it was truncated or rotated while Aider ran),
+/// the whole file is read.
+fn read_history_since(path: &Path, offset: u64) -> Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let start = if (offset as usize) <= bytes.len() {
+        offset as usize
+    } else {
+        0
+    };
+    Ok(String::from_utf8_lossy(&bytes[start..]).into_owned())
+}
+
+/// Get current git branch
+fn get_git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["branch", "--show-current"])
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !branch.is_empty() {
+            return Some(branch);
+        }
+    }
+    None
+}
+
+/// Send edits to the Diachron daemon
+fn send_edits(history: &AiderHistory, git_branch: Option<&str>) -> Result<usize> {
+    let mut metadata = serde_json::json!({});
+    if let Some(started) = &history.session_started {
+        metadata["aider_session"] = serde_json::json!(started);
+    }
+    if let Some(branch) = git_branch {
+        metadata["git_branch"] = serde_json::json!(branch);
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        metadata["cwd"] = serde_json::json!(cwd.to_string_lossy());
+    }
+
+    let mut success_count = 0;
+    for edit in &history.edits {
+        let event = CaptureEvent {
+            tool_name: "Aider".to_string(),
+            file_path: Some(edit.file_path.clone()),
+            operation: edit.operation,
+            diff_summary: edit.stats.summary(),
+            raw_input: Some(edit.raw_input.chars().take(500).collect()),
+            metadata: Some(metadata.to_string()),
+            git_commit_sha: None,
+            command_category: None,
+            outcome: None,
+            error_excerpt: None,
+            old_content: None,
+            new_content: None,
+            raw_input_truncated: edit.raw_input.chars().nth(500).is_some(),
+        };
+
+        match send_to_daemon(event) {
+            Ok(()) => success_count += 1,
+            Err(IpcError::DaemonNotRunning) => {
+                warn!("Diachron daemon not running");
+                break;
+            }
+            Err(e) => warn!("Failed to send event: {}", e),
+        }
+    }
+
+    Ok(success_count)
+}
+
+/// Parse history text and send its applied edits
+fn capture(text: &str, git_branch: Option<&str>) {
+    let history = parse_history(text);
+    if history.edits.is_empty() {
+        info!("No applied edits found in Aider history");
+        return;
+    }
+
+    match send_edits(&history, git_branch) {
+        Ok(count) => info!(
+            "Captured {}/{} Aider edits for Diachron",
+            count,
+            history.edits.len()
+        ),
+        Err(e) => warn!("Failed to send to Diachron: {}", e),
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // Initialize logging
+    if args.verbose {
+        tracing_subscriber::fmt().with_env_filter("debug").init();
+    }
+
+    let git_branch = get_git_branch();
+    debug!("Git branch: {:?}", git_branch);
+
+    if let Some(path) = &args.import {
+        let text = read_history_since(path, 0)?;
+        if !args.no_diachron {
+            capture(&text, git_branch.as_deref());
+        }
+        return Ok(());
+    }
+
+    // Remember where the history ends so only this run's edits are captured
+    let offset = std::fs::metadata(&args.history_file)
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    info!("Running aider with args: {:?}", args.aider_args);
+    let status = Command::new("aider")
+        .args(&args.aider_args)
+        .status()
+        .context("Failed to run aider. Is it installed?")?;
+
+    if !args.no_diachron {
+        match read_history_since(&args.history_file, offset) {
+            Ok(text) => capture(&text, git_branch.as_deref()),
+            Err(e) => warn!("Failed to read Aider history: {}", e),
+        }
+    }
+
+    // Exit with aider's exit code
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCH_REPLACE_HISTORY: &str = "\
+# aider chat started at 2026-01-12 09:30:00
+
+#### rename the login helper
+
+src/auth.py
+```python
+<<<<<<< SEARCH
+def login(user):
+    return check(user)
+=======
+def sign_in(user):
+    check_rate_limit(user)
+    return check(user)
+>>>>>>> REPLACE
+```
+
+> Applied edit to src/auth.py
+";
+
+    #[test]
+    fn test_parse_search_replace_block() {
+        let history = parse_history(SEARCH_REPLACE_HISTORY);
+        assert_eq!(
+            history.session_started.as_deref(),
+            Some("2026-01-12 09:30:00")
+        );
+        assert_eq!(history.edits.len(), 1);
+
+        let edit = &history.edits[0];
+        assert_eq!(edit.file_path, "src/auth.py");
+        assert_eq!(edit.operation, Operation::Modify);
+        assert_eq!(
+            edit.stats,
+            DiffStats {
+                added: 3,
+                removed: 2
+            }
+        );
+        assert!(edit.raw_input.contains("check_rate_limit"));
+    }
+
+    #[test]
+    fn test_parse_new_file_block() {
+        let text = "\
+notes/todo.md
+```
+<<<<<<< SEARCH
+=======
+- ship it
+>>>>>>> REPLACE
+```
+
+> Applied edit to notes/todo.md
+";
+        let history = parse_history(text);
+        assert_eq!(history.edits.len(), 1);
+        assert_eq!(history.edits[0].operation, Operation::Create);
+        assert_eq!(
+            history.edits[0].stats.summary().as_deref(),
+            Some("+1 lines")
+        );
+    }
+
+    #[test]
+    fn test_parse_unified_diff_fence() {
+        let text = "\
+```diff
+--- src/app.py
++++ src/app.py
+@@ ... @@
+-import os
++import os, sys
++import json
+```
+
+> Applied edit to src/app.py
+";
+        let history = parse_history(text);
+        assert_eq!(history.edits.len(), 1);
+        assert_eq!(history.edits[0].file_path, "src/app.py");
+        assert_eq!(history.edits[0].operation, Operation::Modify);
+        assert_eq!(
+            history.edits[0].stats.summary().as_deref(),
+            Some("+2 -1 lines")
+        );
+    }
+
+    #[test]
+    fn test_unapplied_edits_are_ignored() {
+        let text = SEARCH_REPLACE_HISTORY.replace("> Applied edit to src/auth.py\n", "");
+        assert!(parse_history(&text).edits.is_empty());
+    }
+}
@@ -0,0 +1,350 @@
+//! Prior-context formatting for session start (`search --context` and MCP)
+//!
+//! The noise lists track what Claude Code happens to print, so they change
+//! as its output evolves. They live in the `[context]` section of
+//! `~/.diachron/config.toml`, with the values below as defaults.
+
+use std::collections::HashSet;
+
+use diachron_core::{SearchResult, SearchSource};
+use serde::Deserialize;
+
+use crate::safe_truncate;
+
+/// `[context]` settings for injected prior context.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Results scoring below this are left out. The default suits the
+    /// daemon's default fusion, RRF at k=60, where each retriever that found
+    /// a hit adds 1 / (60 + rank): it keeps the hits both retrievers found
+    /// and the top two of a single one
+    pub min_score: f32,
+    /// Budget for the whole block in characters (~4 per token)
+    pub max_chars: usize,
+    /// Characters kept of each snippet
+    pub snippet_chars: usize,
+    /// Snippets starting with any of these are skipped
+    pub noise_prefixes: Vec<String>,
+    /// Snippets containing any of these are skipped
+    pub noise_patterns: Vec<String>,
+    /// Text from any of these to the end of its line is cut from snippets
+    pub strip_patterns: Vec<String>,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        Self {
+            min_score: 0.016,
+            max_chars: 6000,
+            snippet_chars: 200,
+            noise_prefixes: strings(&[
+                "[Result:",
+                "[Tool:",
+                "Warmup",
+                "Stop hook feedback",
+                "Analyze this conversation",
+                "You MUST call",
+                "This session is being continued",
+                "<function_calls>",
+                "```json",
+                "I'm Claude Code",
+                "I'm ready to help",
+            ]),
+            noise_patterns: strings(&[
+                "Shell cwd was reset",
+                "401 {\"type\":\"error\"",
+                "authentication_error",
+                "Failed to find element",
+                "Permission denied",
+                "No such file",
+                "command not found",
+                "<system-reminder>",
+                "hookSpecificOutput",
+            ]),
+            strip_patterns: strings(&[
+                "Shell cwd was reset to",
+                "Shell cwd: ",
+                "<system-reminder>",
+                "</system-reminder>",
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    context: ContextConfig,
+}
+
+impl ContextConfig {
+    /// Load `[context]` from `~/.diachron/config.toml`.
+    ///
+    /// A missing file or section reads as the defaults. An unreadable
+    /// config is reported on stderr and also falls back to the defaults,
+    /// so context injection never fails a session start.
+    pub fn load() -> Self {
+        let Some(path) = dirs::home_dir().map(|h| h.join(".diachron").join("config.toml")) else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(file) => file.context,
+            Err(e) => {
+                eprintln!("warning: ignoring [context] in {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Format search results for context injection at session start.
+///
+/// Produces token-conscious output:
+/// - At most `max_chars` (6000 by default, ~1500 tokens)
+/// - Summarized snippets (first `snippet_chars` each)
+/// - Formatted as markdown for Claude to parse
+///
+/// T4 Quality Fixes (01/10/2026):
+/// - T4-1: Strip HTML tags (<b>, </b>)
+/// - T4-2: Clean line prefixes (N→)
+/// - T4-3: Filter tool wrappers ([Result:, Shell cwd)
+/// - T4-4: Deduplicate results
+/// - T4-5: Quality threshold (`min_score`)
+pub fn format_context_output(results: &[SearchResult], config: &ContextConfig) -> Option<String> {
+    let mut output = String::new();
+    let mut char_count = 0;
+    let mut included_count = 0;
+    let mut seen_snippets: HashSet<String> = HashSet::new(); // T4-4: Deduplication
+
+    // Header
+    let header = "## Prior Context from This Project\n\n";
+    output.push_str(header);
+    char_count += header.len();
+
+    for result in results {
+        // T4-5: Skip low-quality results
+        if result.score < config.min_score {
+            continue;
+        }
+
+        // T4-3: Skip results that are primarily tool output noise
+        if is_tool_noise(&result.snippet, config) {
+            continue;
+        }
+
+        // Clean the snippet (T4-1, T4-2, T4-3)
+        let cleaned = clean_snippet(&result.snippet, config);
+
+        // Skip if cleaned snippet is too short (likely all noise)
+        if cleaned.len() < 20 {
+            continue;
+        }
+
+        // T4-4: Skip duplicates (check first 50 chars for similarity)
+        let dedup_key = safe_truncate(&cleaned, 50).to_lowercase();
+        if seen_snippets.contains(&dedup_key) {
+            continue;
+        }
+        seen_snippets.insert(dedup_key);
+
+        // Format each result as a compact entry
+        let date = if result.timestamp.len() >= 10 {
+            &result.timestamp[..10] // YYYY-MM-DD
+        } else {
+            &result.timestamp
+        };
+
+        let source_str = match result.source {
+            SearchSource::Event => "Code change",
+            SearchSource::Exchange => "Discussion",
+        };
+
+        // Truncate snippet safely (UTF-8 aware)
+        let snippet_final = safe_truncate(&cleaned, config.snippet_chars);
+
+        let entry = format!("### {} - {}\n{}\n\n", date, source_str, snippet_final);
+
+        // Check token budget
+        if char_count + entry.len() > config.max_chars {
+            break;
+        }
+
+        output.push_str(&entry);
+        char_count += entry.len();
+        included_count += 1;
+    }
+
+    // Only output if we have meaningful content
+    if included_count == 0 {
+        return None; // Silent - no quality context found
+    }
+
+    // Footer with stats (helps user understand what was injected)
+    let word_count = output.split_whitespace().count();
+    let approx_tokens = word_count * 4 / 3; // Rough approximation
+    let footer = format!("_({} items, ~{} tokens)_\n", included_count, approx_tokens);
+
+    if char_count + footer.len() <= config.max_chars + 100 {
+        output.push_str(&footer);
+    }
+
+    Some(output)
+}
+
+/// Clean a snippet by removing artifacts and noise.
+/// T4-1: Strip HTML tags
+/// T4-2: Clean line prefixes
+/// T4-3: Filter tool wrappers
+fn clean_snippet(s: &str, config: &ContextConfig) -> String {
+    let mut result = s.to_string();
+
+    // T4-1: Strip HTML tags from FTS highlighting
+    result = result.replace("<b>", "");
+    result = result.replace("</b>", "");
+    result = result.replace("<em>", "");
+    result = result.replace("</em>", "");
+
+    // T4-2: Remove line number prefixes (e.g., "1→", "42→")
+    // Pattern: digits followed by → at start of line or after whitespace
+    let re_line_nums = regex::Regex::new(r"(\s|^)\d+→").unwrap_or_else(|_| {
+        // Fallback: simple replacement
+        regex::Regex::new(r"\d+→").unwrap()
+    });
+    result = re_line_nums.replace_all(&result, " ").to_string();
+
+    // T4-3: Remove tool output wrappers
+    // Remove [Result: prefix
+    if result.starts_with("[Result:") {
+        if let Some(pos) = result.find(']') {
+            result = result[pos + 1..].to_string();
+        }
+    }
+    result = result.replace("[Result:", "");
+    result = result.replace("...]", "");
+
+    // T4-3: Remove shell noise
+    for pattern in &config.strip_patterns {
+        if let Some(pos) = result.find(pattern.as_str()) {
+            // Remove from pattern to end of line
+            if let Some(newline) = result[pos..].find('\n') {
+                result = format!("{}{}", &result[..pos], &result[pos + newline..]);
+            } else {
+                result = result[..pos].to_string();
+            }
+        }
+    }
+
+    // Normalize whitespace
+    result = result.replace('\n', " ");
+    result = result.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    result.trim().to_string()
+}
+
+/// Check if a snippet is primarily tool output noise.
+fn is_tool_noise(s: &str, config: &ContextConfig) -> bool {
+    // Tool result wrappers and internal/system messages
+    if config
+        .noise_prefixes
+        .iter()
+        .any(|prefix| s.starts_with(prefix.as_str()))
+    {
+        return true;
+    }
+
+    // Error indicators
+    if config
+        .noise_patterns
+        .iter()
+        .any(|pattern| s.contains(pattern.as_str()))
+    {
+        return true;
+    }
+
+    // Skip if mostly line numbers (file content dump)
+    let arrow_count = s.matches('→').count();
+    let char_count = s.len();
+    if arrow_count > 3 && (arrow_count * 15) > char_count {
+        return true;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(score: f32, snippet: &str) -> SearchResult {
+        SearchResult {
+            id: "exchange:1".to_string(),
+            score,
+            source: SearchSource::Exchange,
+            snippet: snippet.to_string(),
+            timestamp: "2026-01-10T12:00:00Z".to_string(),
+            project: None,
+            explain: None,
+        }
+    }
+
+    /// Fused score at the daemon's default k=60 of a hit at these ranks
+    fn rrf(ranks: &[usize]) -> f32 {
+        ranks.iter().map(|&rank| 1.0 / (60.0 + rank as f32)).sum()
+    }
+
+    #[test]
+    fn test_default_min_score_filters_weak_fused_hits() {
+        let results = [
+            result(rrf(&[14, 19]), "Both found: token refresh moved to client"),
+            result(rrf(&[1]), "Best vector hit: refresh tokens expire hourly"),
+            result(rrf(&[3]), "Third keyword hit: token bucket rate limits"),
+            result(rrf(&[20]), "Last vector hit: tokenizer for embeddings"),
+        ];
+
+        let output = format_context_output(&results, &ContextConfig::default()).unwrap();
+        assert!(output.contains("Both found"));
+        assert!(output.contains("Best vector hit"));
+        assert!(!output.contains("Third keyword hit"));
+        assert!(!output.contains("Last vector hit"));
+        assert!(output.contains("_(2 items"));
+    }
+
+    #[test]
+    fn test_custom_min_score_excludes_result() {
+        let results = [result(rrf(&[1, 1]), "Switched auth to refresh tokens")];
+
+        let default = format_context_output(&results, &ContextConfig::default()).unwrap();
+        assert!(default.contains("refresh tokens"));
+
+        let strict = ContextConfig {
+            min_score: 0.04,
+            ..Default::default()
+        };
+        assert!(format_context_output(&results, &strict).is_none());
+    }
+
+    #[test]
+    fn test_noise_patterns_from_config() {
+        let config: ConfigFile =
+            toml::from_str("[context]\nnoise_patterns = [\"DEBUG:\"]\nmax_chars = 4000\n").unwrap();
+        let config = config.context;
+        assert_eq!(config.max_chars, 4000);
+        // Unset keys keep their defaults
+        assert_eq!(config.snippet_chars, 200);
+        assert!(!config.noise_prefixes.is_empty());
+
+        let results = [
+            result(0.05, "DEBUG: retrying the token refresh request"),
+            result(0.05, "Shell cwd was reset while refreshing tokens"),
+        ];
+        // Replacing the patterns drops the default "Shell cwd was reset"
+        let output = format_context_output(&results, &config).unwrap();
+        assert!(!output.contains("retrying"));
+        assert!(output.contains("while refreshing tokens"));
+    }
+}
@@ -0,0 +1,142 @@
+//! Bearer-token auth for the dashboard proxy
+//!
+//! The proxy listens on a local port and answers with anything the daemon
+//! knows, so anyone who can reach the port can read the provenance
+//! database. With `require_auth = true` in the `[dashboard]` section of
+//! `~/.diachron/config.toml`, `dashboard start` generates a fresh token,
+//! writes it to `~/.diachron/dashboard.token` (readable only by the owner)
+//! and passes it to the Node proxy in [`TOKEN_ENV_VAR`]. The proxy then
+//! rejects `/api/*` and `/ws/events` requests that don't carry it, either
+//! as `Authorization: Bearer <token>` or as a `?token=` query parameter
+//! (the URL the browser is opened with).
+
+use std::path::{Path, PathBuf};
+
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+
+/// Environment variable the proxy reads its token from.
+pub const TOKEN_ENV_VAR: &str = "DIACHRON_DASHBOARD_TOKEN";
+
+/// Random bytes in a token (hex-encoded to twice as many characters).
+const TOKEN_BYTES: usize = 32;
+
+/// `[dashboard]` settings.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DashboardConfig {
+    /// Require a token on the proxy's API and event stream
+    pub require_auth: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    dashboard: DashboardConfig,
+}
+
+impl DashboardConfig {
+    /// Load `[dashboard]` from `~/.diachron/config.toml`.
+    ///
+    /// A missing file or section reads as the defaults (no auth). An
+    /// unreadable config is reported on stderr and also falls back to the
+    /// defaults.
+    pub fn load() -> Self {
+        let Some(path) = dirs::home_dir().map(|h| h.join(".diachron").join("config.toml")) else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(file) => file.dashboard,
+            Err(e) => {
+                eprintln!("warning: ignoring [dashboard] in {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Path of the token file in the diachron home directory.
+pub fn token_path(diachron_home: &Path) -> PathBuf {
+    diachron_home.join("dashboard.token")
+}
+
+/// Generate a random token.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Write `token` to `path`, readable and writable only by the owner.
+///
+/// # Errors
+/// Returns `std::io::Error` if the file cannot be written.
+pub fn write_token(path: &Path, token: &str) -> std::io::Result<()> {
+    // Remove first so an existing file's looser mode is not kept
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(path)?, token.as_bytes())
+}
+
+/// Read the token of the running dashboard, if it requires one.
+pub fn read_token(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// Whether the dashboard on `port` answers its health check.
+///
+/// # Arguments
+/// - `port`: Port the proxy listens on.
+/// - `token`: Bearer token to send, if any.
+pub fn is_healthy(port: u16, token: Option<&str>) -> bool {
+    let url = format!("http://localhost:{}/api/health", port);
+    let mut request = reqwest::blocking::Client::new().get(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request
+        .send()
+        .is_ok_and(|response| response.status().is_success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_round_trip_is_owner_only() {
+        let dir = std::env::temp_dir().join(format!("diachron-dashboard-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = token_path(&dir);
+
+        let token = generate_token();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_ne!(token, generate_token());
+
+        write_token(&path, &token).unwrap();
+        write_token(&path, &token).unwrap();
+        assert_eq!(read_token(&path).as_deref(), Some(token.as_str()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let config: ConfigFile = toml::from_str("[dashboard]\nrequire_auth = true\n").unwrap();
+        assert!(config.dashboard.require_auth);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,472 @@
+//! Checks behind `diachron doctor` and the repairs of `doctor --fix`
+//!
+//! Each [`Check`] looks at one thing that commonly goes wrong and, when it
+//! finds a problem, knows how to repair it:
+//! - a socket file left behind by a daemon that is no longer running
+//! - no `config.toml` in the diachron home directory
+//! - the embedding model not loaded (downloaded if missing)
+//! - vector indexes that drifted from the stored embeddings
+//!
+//! Checks reach the daemon only through [`DoctorEnv::send`], so they can be
+//! exercised against a fake daemon. Every repair is appended to
+//! `logs/doctor.log` in the diachron home directory.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use diachron_core::{IpcMessage, IpcResponse, CAP_DOCTOR_INFO, CAP_RECONCILE};
+
+use crate::handshake::DaemonCapabilities;
+
+/// Config written when none exists: every setting commented out at its
+/// default, so creating it changes nothing until a line is uncommented.
+const DEFAULT_CONFIG: &str = r#"# Diachron configuration
+#
+# Every setting below is shown at its default. Uncomment a line to change
+# it; restart the daemon afterwards (`diachron daemon stop && diachron daemon start`).
+
+# [summarization]
+# enabled = true
+# model = "claude-3-haiku-20240307"
+# max_tokens = 300
+
+# [search]
+# rrf_k = 60.0
+# rerank = false
+
+# [embedding]
+# pool_size = 2
+# allow_degraded = false
+
+# [capture]
+# batch_size = 32
+# flush_interval_ms = 50
+# skip_noop_edits = true
+
+# [index]
+# autosave_secs = 60
+# reconcile_on_start = true
+
+# [retention]
+# events_days = 0
+# exchanges_days = 0
+
+# [fingerprint]
+# normalize_whitespace = false
+# strip_comments = false
+
+# [dashboard]
+# require_auth = false
+"#;
+
+/// What `doctor` found out about one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// Nothing to repair
+    Ok(String),
+    /// Something `--fix` can repair
+    Problem(String),
+    /// The check could not run (daemon down, old daemon, other platform)
+    Skipped(String),
+}
+
+/// What checks need from the outside world.
+pub struct DoctorEnv<'a> {
+    /// Diachron home directory (`~/.diachron`)
+    pub diachron_home: PathBuf,
+    /// Unix socket the daemon listens on, if it uses one
+    pub socket_path: Option<PathBuf>,
+    /// Capabilities the daemon announced
+    pub capabilities: DaemonCapabilities,
+    /// Send a request to the daemon and wait for its answer
+    pub send: &'a dyn Fn(&IpcMessage) -> Result<IpcResponse>,
+}
+
+impl DoctorEnv<'_> {
+    fn config_path(&self) -> PathBuf {
+        self.diachron_home.join("config.toml")
+    }
+
+    /// Append `line` to the doctor log, with a timestamp.
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the log cannot be written.
+    pub fn log(&self, line: &str) -> Result<()> {
+        let logs_dir = self.diachron_home.join("logs");
+        std::fs::create_dir_all(&logs_dir)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(logs_dir.join("doctor.log"))?;
+        writeln!(file, "{} {}", chrono::Utc::now().to_rfc3339(), line)?;
+        Ok(())
+    }
+}
+
+/// One diagnosable, repairable problem.
+pub trait Check {
+    /// Short name shown in the report
+    fn name(&self) -> &'static str;
+
+    /// Whether the repair deletes something, and so needs confirmation
+    /// unless `--yes` is given
+    fn destructive(&self) -> bool {
+        false
+    }
+
+    /// Look for the problem without changing anything.
+    fn diagnose(&self, env: &DoctorEnv) -> Diagnosis;
+
+    /// Repair the problem found by [`Check::diagnose`].
+    ///
+    /// # Returns
+    /// A one-line description of what was done, for the report and log
+    ///
+    /// # Errors
+    /// Returns `anyhow::Error` if the repair failed.
+    fn fix(&self, env: &DoctorEnv) -> Result<String>;
+}
+
+/// All checks, in the order they are run and fixed.
+///
+/// The socket comes first: the daemon checks after it only make sense
+/// once a stale socket no longer hides whether a daemon is running.
+pub fn checks() -> Vec<Box<dyn Check>> {
+    vec![
+        Box::new(StaleSocket),
+        Box::new(MissingConfig),
+        Box::new(ModelNotLoaded),
+        Box::new(IndexDrift),
+    ]
+}
+
+/// Ask `question` on stderr and read a yes/no answer from stdin.
+///
+/// Anything but `y` or `yes` (including end of input) is a no.
+pub fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Socket file with no daemon listening on it.
+///
+/// The daemon removes its socket on a clean shutdown, so one left behind
+/// means it crashed or was killed. Clients then fail with "connection
+/// refused" instead of "not running", and a new daemon cannot bind.
+pub struct StaleSocket;
+
+impl Check for StaleSocket {
+    fn name(&self) -> &'static str {
+        "Stale socket"
+    }
+
+    fn destructive(&self) -> bool {
+        true
+    }
+
+    fn diagnose(&self, env: &DoctorEnv) -> Diagnosis {
+        let Some(path) = &env.socket_path else {
+            return Diagnosis::Skipped("daemon does not use a Unix socket".to_string());
+        };
+        if !path.exists() {
+            return Diagnosis::Ok("no socket file".to_string());
+        }
+        if is_stale_socket(path) {
+            Diagnosis::Problem(format!(
+                "{} exists but no daemon is listening",
+                path.display()
+            ))
+        } else {
+            Diagnosis::Ok("daemon is listening".to_string())
+        }
+    }
+
+    fn fix(&self, env: &DoctorEnv) -> Result<String> {
+        let Some(path) = &env.socket_path else {
+            bail!("daemon does not use a Unix socket");
+        };
+        // Checked again right before deleting, in case a daemon started
+        if !is_stale_socket(path) {
+            bail!("a daemon is now listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        Ok(format!("removed stale socket {}", path.display()))
+    }
+}
+
+/// Whether `path` is a socket nobody accepts connections on.
+///
+/// Only a refused connection counts: a busy daemon that is slow to accept
+/// still owns its socket.
+#[cfg(unix)]
+fn is_stale_socket(path: &Path) -> bool {
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => false,
+        Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_stale_socket(_path: &Path) -> bool {
+    false
+}
+
+/// No `config.toml`, so there is nothing to edit with `config set`.
+pub struct MissingConfig;
+
+impl Check for MissingConfig {
+    fn name(&self) -> &'static str {
+        "Config file"
+    }
+
+    fn diagnose(&self, env: &DoctorEnv) -> Diagnosis {
+        let path = env.config_path();
+        if path.exists() {
+            Diagnosis::Ok(path.display().to_string())
+        } else {
+            Diagnosis::Problem(format!("{} not found (using defaults)", path.display()))
+        }
+    }
+
+    fn fix(&self, env: &DoctorEnv) -> Result<String> {
+        let path = env.config_path();
+        std::fs::create_dir_all(&env.diachron_home)?;
+        // Never overwrites a config created since the diagnosis
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        file.write_all(DEFAULT_CONFIG.as_bytes())?;
+        Ok(format!("created default config {}", path.display()))
+    }
+}
+
+/// Daemon running without its embedding model, so search is full-text
+/// only and new captures are not embedded.
+pub struct ModelNotLoaded;
+
+impl Check for ModelNotLoaded {
+    fn name(&self) -> &'static str {
+        "Embedding model"
+    }
+
+    fn diagnose(&self, env: &DoctorEnv) -> Diagnosis {
+        if !env.capabilities.supports(CAP_DOCTOR_INFO) {
+            return Diagnosis::Skipped("daemon not running or too old".to_string());
+        }
+        match (env.send)(&IpcMessage::DoctorInfo) {
+            Ok(IpcResponse::Doctor(info)) if info.model_loaded => {
+                Diagnosis::Ok("loaded".to_string())
+            }
+            Ok(IpcResponse::Doctor(info)) if info.model_size_bytes == 0 => {
+                Diagnosis::Problem("not loaded (model files missing)".to_string())
+            }
+            Ok(IpcResponse::Doctor(_)) => Diagnosis::Problem("not loaded".to_string()),
+            Ok(other) => Diagnosis::Skipped(format!("unexpected response: {:?}", other)),
+            Err(e) => Diagnosis::Skipped(format!("daemon unreachable: {}", e)),
+        }
+    }
+
+    fn fix(&self, env: &DoctorEnv) -> Result<String> {
+        // Loads the default model, downloading it first if it is missing
+        match (env.send)(&IpcMessage::ReloadModel { model_dir: None })? {
+            IpcResponse::ModelReloaded {
+                swapped: true,
+                dim,
+                pool_size,
+                ..
+            } => Ok(format!(
+                "loaded embedding model ({} dimensions, {} engines)",
+                dim, pool_size
+            )),
+            IpcResponse::ModelReloaded {
+                dim, index_dim, ..
+            } => bail!(
+                "model loaded with {} dimensions but the indexes hold {}; run 'diachron memory reindex --force'",
+                dim,
+                index_dim
+            ),
+            IpcResponse::Error(e) => bail!("{}", e),
+            other => bail!("unexpected response: {:?}", other),
+        }
+    }
+}
+
+/// Vector indexes missing stored embeddings or holding vectors of deleted
+/// rows, as left behind by a crash between a write and the next autosave.
+pub struct IndexDrift;
+
+impl Check for IndexDrift {
+    fn name(&self) -> &'static str {
+        "Index drift"
+    }
+
+    fn diagnose(&self, env: &DoctorEnv) -> Diagnosis {
+        if env.capabilities.daemon_version.is_none() {
+            return Diagnosis::Skipped("daemon not running or too old".to_string());
+        }
+        match (env.send)(&IpcMessage::Integrity) {
+            Ok(IpcResponse::IntegrityReport(report)) => {
+                let drift =
+                    |t: &diachron_core::TableIntegrity| t.missing_from_index + t.orphaned_vectors;
+                let (events, exchanges) = (drift(&report.events), drift(&report.exchanges));
+                if events + exchanges == 0 {
+                    Diagnosis::Ok("indexes match the database".to_string())
+                } else {
+                    Diagnosis::Problem(format!(
+                        "{} event and {} exchange vectors out of sync",
+                        events, exchanges
+                    ))
+                }
+            }
+            Ok(IpcResponse::Error(e)) => Diagnosis::Skipped(e.to_string()),
+            Ok(other) => Diagnosis::Skipped(format!("unexpected response: {:?}", other)),
+            Err(e) => Diagnosis::Skipped(format!("daemon unreachable: {}", e)),
+        }
+    }
+
+    fn fix(&self, env: &DoctorEnv) -> Result<String> {
+        if !env.capabilities.supports(CAP_RECONCILE) {
+            bail!("daemon cannot reconcile on demand; restart it to reconcile on start");
+        }
+        match (env.send)(&IpcMessage::Reconcile)? {
+            IpcResponse::ReconcileStats {
+                restored,
+                removed,
+                deferred,
+            } => {
+                let mut done = format!(
+                    "reconciled indexes: restored {} vectors, removed {} stale entries",
+                    restored, removed
+                );
+                if deferred > 0 {
+                    done.push_str(&format!(
+                        " ({} left; run 'diachron doctor --fix' again)",
+                        deferred
+                    ));
+                }
+                Ok(done)
+            }
+            IpcResponse::Error(e) => bail!("{}", e),
+            other => bail!("unexpected response: {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diachron_core::{ChainVerificationResult, IntegrityReport, TableIntegrity};
+    use std::cell::RefCell;
+
+    fn capabilities(caps: &[&str]) -> DaemonCapabilities {
+        DaemonCapabilities::from_response(IpcResponse::Hello {
+            daemon_version: "0.0.1".to_string(),
+            protocol_version: diachron_core::PROTOCOL_VERSION,
+            capabilities: caps.iter().map(|c| c.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_missing_config_is_created_once() {
+        let dir = std::env::temp_dir().join(format!("diachron-doctor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let send = |_: &IpcMessage| -> Result<IpcResponse> { bail!("no daemon") };
+        let env = DoctorEnv {
+            diachron_home: dir.clone(),
+            socket_path: None,
+            capabilities: DaemonCapabilities::legacy(),
+            send: &send,
+        };
+
+        let check = MissingConfig;
+        assert!(matches!(check.diagnose(&env), Diagnosis::Problem(_)));
+        check.fix(&env).unwrap();
+        assert!(matches!(check.diagnose(&env), Diagnosis::Ok(_)));
+        // Parses, and only as defaults
+        let config: toml::Value =
+            toml::from_str(&std::fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert!(config.as_table().unwrap().is_empty());
+        // A config that now exists is left alone
+        assert!(check.fix(&env).is_err());
+
+        // Daemon checks skip without a daemon
+        assert!(matches!(
+            ModelNotLoaded.diagnose(&env),
+            Diagnosis::Skipped(_)
+        ));
+        assert!(matches!(IndexDrift.diagnose(&env), Diagnosis::Skipped(_)));
+
+        env.log("created default config").unwrap();
+        let log = std::fs::read_to_string(dir.join("logs").join("doctor.log")).unwrap();
+        assert!(log.trim_end().ends_with("created default config"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_index_drift_is_reconciled() {
+        let sent = RefCell::new(Vec::new());
+        let send = |msg: &IpcMessage| -> Result<IpcResponse> {
+            sent.borrow_mut().push(format!("{:?}", msg));
+            Ok(match msg {
+                IpcMessage::Integrity => IpcResponse::IntegrityReport(IntegrityReport {
+                    events: TableIntegrity {
+                        rows: 3,
+                        missing_from_index: 2,
+                        ..Default::default()
+                    },
+                    exchanges: TableIntegrity {
+                        orphaned_vectors: 1,
+                        ..Default::default()
+                    },
+                    events_without_hash: 0,
+                    chain: ChainVerificationResult {
+                        valid: true,
+                        events_checked: 3,
+                        checkpoints_checked: 0,
+                        first_event: None,
+                        last_event: None,
+                        chain_root: None,
+                        break_point: None,
+                        merkle_mismatch: None,
+                    },
+                }),
+                IpcMessage::Reconcile => IpcResponse::ReconcileStats {
+                    restored: 2,
+                    removed: 1,
+                    deferred: 0,
+                },
+                _ => unreachable!(),
+            })
+        };
+        let mut env = DoctorEnv {
+            diachron_home: std::env::temp_dir(),
+            socket_path: None,
+            capabilities: capabilities(&[CAP_RECONCILE]),
+            send: &send,
+        };
+
+        assert_eq!(
+            IndexDrift.diagnose(&env),
+            Diagnosis::Problem("2 event and 1 exchange vectors out of sync".to_string())
+        );
+        assert_eq!(
+            IndexDrift.fix(&env).unwrap(),
+            "reconciled indexes: restored 2 vectors, removed 1 stale entries"
+        );
+        assert_eq!(*sent.borrow(), ["Integrity", "Reconcile"]);
+
+        // A daemon without `Reconcile` is not sent it
+        env.capabilities = capabilities(&[]);
+        assert!(IndexDrift.fix(&env).is_err());
+        assert_eq!(sent.borrow().len(), 2);
+    }
+}
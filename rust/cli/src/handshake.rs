@@ -0,0 +1,127 @@
+//! Version handshake with the daemon
+//!
+//! The first request of a session that needs an optional feature sends
+//! `Hello`, and the answer is kept for the rest of the process. Daemons
+//! that predate the handshake reject `Hello` as an invalid message; they
+//! are treated as supporting none of the capabilities, and callers take
+//! their fallback paths instead of guessing from response variants.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use diachron_core::{IpcMessage, IpcResponse, CAP_DOCTOR_INFO};
+
+/// What the connected daemon reported about itself.
+#[derive(Debug, Clone)]
+pub struct DaemonCapabilities {
+    /// Daemon version, or `None` for a daemon without the handshake
+    pub daemon_version: Option<String>,
+    /// Daemon protocol version (0 for a daemon without the handshake)
+    pub protocol_version: u32,
+    /// Capabilities announced by the daemon
+    capabilities: HashSet<String>,
+}
+
+impl DaemonCapabilities {
+    /// Capabilities of a daemon that predates the handshake.
+    pub fn legacy() -> Self {
+        Self {
+            daemon_version: None,
+            protocol_version: 0,
+            capabilities: HashSet::new(),
+        }
+    }
+
+    /// Read the daemon's answer to `Hello`.
+    ///
+    /// Any answer other than `Hello` comes from a daemon too old to know
+    /// the message.
+    pub fn from_response(response: IpcResponse) -> Self {
+        match response {
+            IpcResponse::Hello {
+                daemon_version,
+                protocol_version,
+                capabilities,
+            } => Self {
+                daemon_version: Some(daemon_version),
+                protocol_version,
+                capabilities: capabilities.into_iter().collect(),
+            },
+            _ => Self::legacy(),
+        }
+    }
+
+    /// Whether the daemon announced `capability` (one of the `CAP_*` names).
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.contains(capability)
+    }
+
+    /// The request for daemon diagnostics: `DoctorInfo`, or `Ping` for
+    /// daemons without it (answered with the limited `Pong`).
+    pub fn diagnostics_request(&self) -> IpcMessage {
+        if self.supports(CAP_DOCTOR_INFO) {
+            IpcMessage::DoctorInfo
+        } else {
+            IpcMessage::Ping
+        }
+    }
+}
+
+static CAPABILITIES: OnceLock<DaemonCapabilities> = OnceLock::new();
+
+/// Capabilities of the running daemon, asked once per process.
+///
+/// A failed connection is not cached, so a later call (after
+/// `daemon start`, say) asks again; until then the daemon is treated as
+/// legacy.
+pub fn daemon_capabilities() -> DaemonCapabilities {
+    if let Some(capabilities) = CAPABILITIES.get() {
+        return capabilities.clone();
+    }
+    let hello = IpcMessage::Hello {
+        client_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    match crate::send_message(&hello) {
+        Ok(response) => CAPABILITIES
+            .get_or_init(|| DaemonCapabilities::from_response(response))
+            .clone(),
+        Err(_) => DaemonCapabilities::legacy(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diachron_core::{DaemonError, CAP_STATS, CAP_SUBSCRIBE, PROTOCOL_VERSION};
+
+    #[test]
+    fn test_daemon_without_handshake_takes_fallback_path() {
+        // What a daemon that predates `Hello` answers
+        let rejected = IpcResponse::Error(DaemonError::InvalidRequest(
+            "Invalid message: unknown variant `Hello`".to_string(),
+        ));
+        let capabilities = DaemonCapabilities::from_response(rejected);
+        assert_eq!(capabilities.protocol_version, 0);
+        assert!(!capabilities.supports(CAP_STATS));
+        assert!(matches!(
+            capabilities.diagnostics_request(),
+            IpcMessage::Ping
+        ));
+    }
+
+    #[test]
+    fn test_missing_capability_is_not_supported() {
+        let hello = IpcResponse::Hello {
+            daemon_version: "0.9.0".to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: vec![CAP_DOCTOR_INFO.to_string()],
+        };
+        let capabilities = DaemonCapabilities::from_response(hello);
+        assert_eq!(capabilities.daemon_version.as_deref(), Some("0.9.0"));
+        assert!(!capabilities.supports(CAP_SUBSCRIBE));
+        assert!(matches!(
+            capabilities.diagnostics_request(),
+            IpcMessage::DoctorInfo
+        ));
+    }
+}
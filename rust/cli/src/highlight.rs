@@ -0,0 +1,209 @@
+//! Minimal ANSI syntax highlighting for captured content
+//!
+//! `diachron diff` prints the raw content an event captured. A full grammar
+//! is overkill for that, so lines are scanned for comments, strings,
+//! numbers, and a per-language keyword list chosen from the file extension.
+//! Unknown languages print uncolored.
+
+const RESET: &str = "\x1b[0m";
+const KEYWORD: &str = "\x1b[35m";
+const STRING: &str = "\x1b[32m";
+const COMMENT: &str = "\x1b[2;37m";
+const NUMBER: &str = "\x1b[33m";
+
+/// Keywords and comment markers for one language family.
+pub struct Language {
+    keywords: &'static [&'static str],
+    line_comment: &'static str,
+}
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "false",
+        "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+        "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    line_comment: "//",
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "if", "import", "in", "is",
+        "lambda", "None", "not", "or", "pass", "raise", "return", "True", "try", "while", "with",
+        "yield",
+    ],
+    line_comment: "#",
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "return",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "undefined",
+        "var",
+        "while",
+    ],
+    line_comment: "//",
+};
+
+const GO: Language = Language {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "for",
+        "func",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+    line_comment: "//",
+};
+
+const SHELL: Language = Language {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "while",
+    ],
+    line_comment: "#",
+};
+
+/// Pick a language from a file path's extension.
+///
+/// # Arguments
+/// - `path`: File path, or `None` for shell commands.
+///
+/// # Returns
+/// The matching language, or `None` to print without highlighting.
+pub fn language_for(path: Option<&str>) -> Option<&'static Language> {
+    let Some(path) = path else {
+        return Some(&SHELL);
+    };
+    let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    match ext.as_str() {
+        "rs" => Some(&RUST),
+        "py" | "pyi" => Some(&PYTHON),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Some(&JAVASCRIPT),
+        "go" => Some(&GO),
+        "sh" | "bash" | "zsh" => Some(&SHELL),
+        _ => None,
+    }
+}
+
+/// Color one line of source.
+///
+/// Strings do not carry across lines, so a multi-line string literal only
+/// colors its first and last lines.
+///
+/// # Arguments
+/// - `line`: Source line without its newline.
+/// - `lang`: Language to highlight as.
+///
+/// # Returns
+/// The line with ANSI color codes inserted.
+pub fn highlight_line(line: &str, lang: &Language) -> String {
+    let mut out = String::with_capacity(line.len() + 16);
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with(lang.line_comment) {
+            paint(&mut out, COMMENT, rest);
+            break;
+        }
+
+        let len = if c == '"' || c == '\'' || c == '`' {
+            let end = rest[1..]
+                .char_indices()
+                .scan(false, |escaped, (i, ch)| {
+                    let closes = !*escaped && ch == c;
+                    *escaped = !*escaped && ch == '\\';
+                    Some((i, closes))
+                })
+                .find(|(_, closes)| *closes)
+                .map_or(rest.len(), |(i, _)| i + 2);
+            paint(&mut out, STRING, &rest[..end]);
+            end
+        } else if c.is_ascii_digit() {
+            let end = token_end(rest, |ch| {
+                ch.is_ascii_alphanumeric() || ch == '.' || ch == '_'
+            });
+            paint(&mut out, NUMBER, &rest[..end]);
+            end
+        } else if c.is_alphabetic() || c == '_' {
+            let end = token_end(rest, |ch| ch.is_alphanumeric() || ch == '_');
+            let word = &rest[..end];
+            if lang.keywords.contains(&word) {
+                paint(&mut out, KEYWORD, word);
+            } else {
+                out.push_str(word);
+            }
+            end
+        } else {
+            out.push(c);
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+
+    out
+}
+
+fn token_end(s: &str, is_part: impl Fn(char) -> bool) -> usize {
+    s.char_indices()
+        .find(|&(_, ch)| !is_part(ch))
+        .map_or(s.len(), |(i, _)| i)
+}
+
+fn paint(out: &mut String, color: &str, text: &str) {
+    out.push_str(color);
+    out.push_str(text);
+    out.push_str(RESET);
+}
@@ -4,22 +4,44 @@
 //! Designed for <5ms execution time.
 //!
 //! Commands:
-//! - diachron timeline [--since "1h"] [--file src/]
+//! - diachron timeline [--since "1h"] [--file src/] [--stats]
 //! - diachron capture <json>         # Called by hook
 //! - diachron memory search "query"
 //! - diachron memory index
-//! - diachron daemon start|stop|status
-//! - diachron doctor
-
-use std::io::{BufRead, BufReader, Write};
-use std::os::unix::net::UnixStream;
+//! - diachron memory reindex [--force]
+//! - diachron daemon start|stop|status|reload-model
+//! - diachron diff <event_id>
+//! - diachron revert-preview <event_id>
+//! - diachron mcp                   # MCP stdio server for editors
+//! - diachron doctor [--fix]
+//! - diachron completions <shell>   # Shell completion script
+
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-
-use diachron_core::{verify_chain, IpcMessage, IpcResponse};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+mod context;
+mod dashboard;
+mod doctor;
+mod handshake;
+mod highlight;
+mod mcp;
+mod timeline_stats;
+mod vcs_host;
+mod watch;
+
+use diachron_core::{
+    default_transport, read_frame, verify_chain, write_frame, BlameConfidence, CommandCategory,
+    DaemonError, FileMatch, IpcMessage, IpcResponse, IpcStream, Operation, Outcome, SearchCursor,
+    SearchPreference, VectorExportFormat, ADDR_ENV_VAR, CAP_STREAMING_SEARCH, PROTOCOL_VERSION,
+};
+
+use context::ContextConfig;
 
 #[derive(Parser)]
 #[command(name = "diachron")]
@@ -28,6 +50,10 @@ use diachron_core::{verify_chain, IpcMessage, IpcResponse};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Seconds to wait for the daemon's response (default depends on the command)
+    #[arg(long, global = true)]
+    timeout: Option<u64>,
 }
 
 #[derive(Subcommand)]
@@ -38,27 +64,64 @@ enum Commands {
         #[arg(long)]
         since: Option<String>,
 
-        /// Filter by file path
+        /// Filter by file path (substring; a glob with --glob)
         #[arg(long)]
         file: Option<String>,
 
+        /// Match --file as a glob (e.g. 'src/**/*.rs'), relative to the
+        /// directory each event was captured in
+        #[arg(long, requires = "file")]
+        glob: bool,
+
+        /// Filter by a regular expression over the full file path
+        #[arg(long, value_name = "REGEX", conflicts_with = "file")]
+        file_regex: Option<String>,
+
+        /// Filter by project name (substring, case-insensitive)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Filter by operation
+        #[arg(long, value_parser = ["create", "modify", "delete", "move", "copy", "commit", "execute", "unknown"])]
+        operation: Option<String>,
+
+        /// Filter Bash commands by category
+        #[arg(long, value_parser = ["git", "test", "build", "deploy", "file_ops", "package", "unknown"])]
+        category: Option<String>,
+
         /// Maximum number of events to show
         #[arg(long, default_value = "20")]
         limit: usize,
 
-        /// Output format: text, json, csv, markdown
+        /// Show the page after this cursor (printed at the end of the previous page)
+        #[arg(long, value_name = "ID")]
+        before: Option<i64>,
+
+        /// Output format: text, json, jsonl, csv, markdown
         #[arg(long, default_value = "text")]
         format: String,
 
         /// Watch for new events in real-time (Ctrl+C to stop)
         #[arg(long)]
         watch: bool,
+
+        /// Group events under headers: session, file, day (text/markdown only)
+        #[arg(long, value_parser = ["session", "file", "day"])]
+        group_by: Option<String>,
+
+        /// Append totals for the listed events: files, sessions, date span,
+        /// and events per tool (text, markdown, json)
+        #[arg(long, conflicts_with = "watch")]
+        stats: bool,
     },
 
     /// Capture an event (called by hook)
+    ///
+    /// Without an argument, reads a JSON array of events or one event per
+    /// line from stdin and sends them in batches over one connection.
     Capture {
         /// JSON event data
-        json: String,
+        json: Option<String>,
     },
 
     /// Memory operations
@@ -82,6 +145,10 @@ enum Commands {
         #[arg(long, default_value = "10")]
         limit: usize,
 
+        /// Continue after this cursor (printed at the end of the previous page)
+        #[arg(long, value_name = "CURSOR")]
+        after: Option<SearchCursor>,
+
         /// Filter by source: event, exchange, or all
         #[arg(long, value_name = "TYPE")]
         r#type: Option<String>,
@@ -90,11 +157,28 @@ enum Commands {
         #[arg(long)]
         since: Option<String>,
 
+        /// Only results before this time (same formats as --since)
+        #[arg(long)]
+        until: Option<String>,
+
         /// Filter by project name
         #[arg(long)]
         project: Option<String>,
 
-        /// Output format: text, json, csv, markdown
+        /// Only events from this tool (e.g., Write, Edit, Bash); excludes exchanges
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Ranking: semantic, keyword, or balanced (default: daemon config)
+        #[arg(long, value_parser = ["semantic", "keyword", "balanced"])]
+        prefer: Option<String>,
+
+        /// Show which retrievers (vector, full-text) found each result and
+        /// their scores; json and jsonl output gain an `explain` object
+        #[arg(long)]
+        explain: bool,
+
+        /// Output format: text, json, jsonl, csv, markdown
         #[arg(long, default_value = "text")]
         format: String,
 
@@ -105,7 +189,16 @@ enum Commands {
     },
 
     /// Run diagnostics
-    Doctor,
+    Doctor {
+        /// Repair the problems found: stale socket, missing config,
+        /// unloaded model, vector index drift
+        #[arg(long)]
+        fix: bool,
+
+        /// Don't ask before destructive repairs
+        #[arg(long, short = 'y', requires = "fix")]
+        yes: bool,
+    },
 
     /// Configuration management
     Config {
@@ -114,18 +207,58 @@ enum Commands {
     },
 
     /// Verify hash-chain integrity
-    Verify,
+    Verify {
+        /// Only verify events recorded after the latest checkpoint
+        #[arg(long)]
+        since_checkpoint: bool,
+
+        /// Output format: text, json (exit code is 1 on failure either way)
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Generate an ed25519 key for signing chain checkpoints
+    Keygen {
+        /// Overwrite an existing key
+        #[arg(long)]
+        force: bool,
+    },
 
-    /// Export evidence pack for a PR
+    /// Export all events and exchanges as portable JSON
+    Export {
+        /// Output file path
+        #[arg(long, default_value = "diachron-export.json")]
+        output: PathBuf,
+
+        /// Only export history since this time (e.g., "30d", "2024-01-01")
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Import events and exchanges from a `diachron export` file
+    Import {
+        /// Export file to read
+        input: PathBuf,
+
+        /// Verify hash-chain integrity after importing
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Export evidence pack for a PR/MR
     ExportEvidence {
         /// Output file path (default: diachron.evidence.json)
         #[arg(long, default_value = "diachron.evidence.json")]
         output: String,
 
-        /// PR number (if not specified, uses current branch's PR)
+        /// PR/MR number (if not specified, uses current branch's PR/MR)
         #[arg(long)]
         pr: Option<u64>,
 
+        /// Code review host: github, gitlab (default: detect from origin remote)
+        #[arg(long)]
+        host: Option<String>,
+
         /// Branch name (defaults to current branch)
         #[arg(long)]
         branch: Option<String>,
@@ -133,22 +266,52 @@ enum Commands {
         /// Time window start (e.g., "7d", "2024-01-01")
         #[arg(long, default_value = "7d")]
         since: String,
+
+        /// Output format: json, sarif (code-scanning annotations), html (archivable page),
+        /// markdown (PR comment narrative)
+        #[arg(long, default_value = "json")]
+        format: String,
+
+        /// Markdown template for --format markdown (default: the built-in PR comment layout)
+        #[arg(long, value_name = "FILE")]
+        output_template: Option<PathBuf>,
+
+        /// Seconds before a commit that an event may occur and still match it (default: 300)
+        #[arg(long)]
+        time_window: Option<i64>,
+
+        /// Only time-match events whose file the commit changed
+        #[arg(long)]
+        require_file_match: bool,
+
+        /// What the PR is for (default: derived from the conversations
+        /// behind the commits)
+        #[arg(long)]
+        intent: Option<String>,
     },
 
-    /// Post PR narrative comment via gh CLI
+    /// Post PR/MR narrative comment via gh or glab CLI
     PrComment {
-        /// PR number
+        /// PR/MR number
         #[arg(long)]
         pr: u64,
 
         /// Evidence file path (default: diachron.evidence.json)
         #[arg(long, default_value = "diachron.evidence.json")]
         evidence: String,
+
+        /// Code review host: github, gitlab (default: detect from origin remote)
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Markdown template for the comment (default: the built-in layout)
+        #[arg(long, value_name = "FILE")]
+        output_template: Option<PathBuf>,
     },
 
-    /// Semantic blame for a file:line
+    /// Semantic blame for a file:line or file:start-end
     Blame {
-        /// File and line (e.g., src/auth.rs:42)
+        /// File and line or line range (e.g., src/auth.rs:42 or src/auth.rs:40-70)
         target: String,
 
         /// Output format: text, json
@@ -158,6 +321,10 @@ enum Commands {
         /// Blame mode: strict (HIGH only), best-effort, inferred
         #[arg(long, default_value = "strict")]
         mode: String,
+
+        /// Also show N events before and after the match in its session
+        #[arg(long, default_value = "0")]
+        context: usize,
     },
 
     /// Run database maintenance (VACUUM, ANALYZE, prune old data)
@@ -165,6 +332,31 @@ enum Commands {
         /// Prune events/exchanges older than N days (0 = no pruning)
         #[arg(long, default_value = "0")]
         retention_days: u32,
+
+        /// Prune events older than N days, overriding --retention-days
+        /// (0 = keep forever)
+        #[arg(long)]
+        events_retention_days: Option<u32>,
+
+        /// Prune exchanges older than N days, overriding --retention-days
+        /// (0 = keep forever)
+        #[arg(long)]
+        exchanges_retention_days: Option<u32>,
+
+        /// Keep a project's events and exchanges N days (0 = forever),
+        /// matched by project name substring; repeatable
+        #[arg(long, value_name = "PROJECT=DAYS", value_parser = parse_project_retention)]
+        project_retention: Vec<(String, u32)>,
+
+        /// Report missing embeddings, index drift, chain breaks, and stale
+        /// full-text indexes without modifying anything
+        #[arg(long, conflicts_with_all = [
+            "retention_days",
+            "events_retention_days",
+            "exchanges_retention_days",
+            "project_retention",
+        ])]
+        check: bool,
     },
 
     /// Web dashboard management
@@ -172,6 +364,95 @@ enum Commands {
         #[command(subcommand)]
         command: DashboardCommands,
     },
+
+    /// Summarize contribution by source (tool and command category)
+    Stats {
+        /// Only count events since this time (e.g., "1h", "7d", "2024-01-01")
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Show the events that went into a git commit
+    Show {
+        /// Commit SHA (full or abbreviated)
+        sha: String,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Run a Model Context Protocol server on stdio for editors
+    Mcp,
+
+    /// Live terminal monitor of captured events
+    Watch {
+        /// Only show events whose file path contains this text
+        #[arg(long)]
+        file: Option<String>,
+    },
+
+    /// Show the full content captured for one event
+    Diff {
+        /// Event ID (as shown by `diachron timeline --format json`)
+        id: i64,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Preview the patch that would undo an edit (needs capture.store_edit_content)
+    RevertPreview {
+        /// Event ID of the edit
+        id: i64,
+    },
+
+    /// Show the sequence of edits to a file in a session, up to an event
+    Lineage {
+        /// Event ID to trace back from
+        id: i64,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Summarize one coding session
+    Session {
+        /// Session ID or a unique prefix (as shown by `diachron timeline`)
+        id: String,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// List recent coding sessions with one-line summaries
+    Sessions {
+        /// Only sessions active since this time (e.g., "1h", "7d", "2024-01-01")
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Maximum number of sessions
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// Output format: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Print a shell completion script (e.g. `source <(diachron completions zsh)`)
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
 }
 
 #[derive(Subcommand)]
@@ -213,6 +494,68 @@ enum MemoryCommands {
     /// Index pending conversations
     Index,
 
+    /// Rebuild vector indexes by re-embedding stored events and exchanges
+    Reindex {
+        /// Rebuild even if the indexes already match the embedding model
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Rebuild vector indexes from stored embeddings, dropping removed vectors
+    Compact,
+
+    /// Delete conversation exchanges and their vectors
+    ///
+    /// Filters combine; at least one is required.
+    Forget {
+        /// Project name substring
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Exact session ID
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only exchanges older than this (e.g. "30d", "2024-01-15")
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Full-text query the exchange must match
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Show what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Report near-duplicate exchanges in the vector index
+    DedupReport {
+        /// Minimum cosine similarity for two exchanges to count as duplicates
+        #[arg(long, default_value = "0.98")]
+        threshold: f32,
+
+        /// Probe only this many vectors (faster on large indexes)
+        #[arg(long)]
+        sample: Option<usize>,
+
+        /// Clusters to list (0 = all)
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Write the vector indexes to files for analysis (e.g. NumPy, pandas)
+    ExportVectors {
+        /// File format: npy (matrix + ids.txt) or arrow
+        #[arg(long, value_parser = ["npy", "arrow"], default_value = "npy")]
+        format: String,
+
+        /// Output directory under ~/.diachron/exports; events/ and
+        /// exchanges/ are written inside it
+        #[arg(long, default_value = "vectors")]
+        out: PathBuf,
+    },
+
     /// Summarize exchanges (requires Anthropic API key)
     Summarize {
         /// Maximum exchanges to summarize
@@ -227,7 +570,11 @@ enum MemoryCommands {
 #[derive(Subcommand)]
 enum DaemonCommands {
     /// Start the daemon
-    Start,
+    Start {
+        /// Seconds to wait for the daemon to report ready (0 = don't wait)
+        #[arg(long, default_value = "30")]
+        wait: u64,
+    },
 
     /// Stop the daemon
     Stop,
@@ -235,6 +582,28 @@ enum DaemonCommands {
     /// Check daemon status
     Status,
 
+    /// Load the embedding model again without restarting the daemon
+    ReloadModel {
+        /// Directory with model.onnx and tokenizer.json (default: embedding.model_dir)
+        #[arg(long)]
+        model_dir: Option<PathBuf>,
+    },
+
+    /// Show the daemon's log output
+    Logs {
+        /// Keep printing lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Read daemon.err (warnings and errors) instead of daemon.log
+        #[arg(long)]
+        err: bool,
+
+        /// Number of trailing lines to print
+        #[arg(short = 'n', long, default_value = "50")]
+        lines: usize,
+    },
+
     /// Enable daemon auto-start at login
     AutostartEnable,
 
@@ -265,32 +634,331 @@ enum DashboardCommands {
     Status,
 }
 
+#[cfg(unix)]
 fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(diachron_core::SOCKET_ENV_VAR).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
     dirs::home_dir()
         .map(|h| h.join(".diachron/diachron.sock"))
         .unwrap_or_else(|| PathBuf::from("/tmp/.diachron/diachron.sock"))
 }
 
+/// TCP daemon address from `DIACHRON_ADDR`, if set.
+fn tcp_addr() -> Option<String> {
+    std::env::var(ADDR_ENV_VAR).ok().filter(|a| !a.is_empty())
+}
+
+/// Response timeout from `--timeout`, overriding the per-message defaults.
+static TIMEOUT_OVERRIDE: OnceLock<Duration> = OnceLock::new();
+
+/// Longest a single socket read blocks before the overall deadline is checked.
+const READ_POLL: Duration = Duration::from_secs(1);
+
+/// How long to wait for the daemon's response to `msg`.
+///
+/// `--timeout` applies to every message. Otherwise requests that re-embed,
+/// summarize, or scan whole indexes get minutes and lookups five seconds.
+fn response_timeout(msg: &IpcMessage) -> Duration {
+    if let Some(timeout) = TIMEOUT_OVERRIDE.get() {
+        return *timeout;
+    }
+    let secs = match msg {
+        IpcMessage::Reindex { .. } => 600,
+        IpcMessage::IndexConversations
+        | IpcMessage::Compact
+        | IpcMessage::SummarizeExchanges { .. }
+        | IpcMessage::Maintenance { .. }
+        | IpcMessage::DedupReport { .. } => 300,
+        IpcMessage::CorrelateEvidence { .. }
+        | IpcMessage::ReloadModel { .. }
+        | IpcMessage::ImportBatch { .. }
+        | IpcMessage::CaptureBatch(_)
+        | IpcMessage::ExportVectors { .. }
+        | IpcMessage::Forget { .. }
+        | IpcMessage::Integrity
+        | IpcMessage::Reconcile => 120,
+        IpcMessage::Search { .. }
+        | IpcMessage::BlameByFingerprint { .. }
+        | IpcMessage::BlameRange { .. }
+        | IpcMessage::BlameFile { .. }
+        | IpcMessage::Stats { .. }
+        | IpcMessage::Sessions { .. } => 30,
+        _ => 5,
+    };
+    Duration::from_secs(secs)
+}
+
+/// Whether `msg` is sent with length-delimited framing (large payloads).
+fn is_framed(msg: &IpcMessage) -> bool {
+    matches!(
+        msg,
+        IpcMessage::ImportBatch { .. }
+            | IpcMessage::CaptureBatch(_)
+            | IpcMessage::CorrelateEvidence { .. }
+    )
+}
+
 fn send_message(msg: &IpcMessage) -> Result<IpcResponse> {
-    let path = socket_path();
+    send_message_with_timeout(msg, response_timeout(msg))
+}
+
+/// Send a message over TCP (`DIACHRON_ADDR`) or the local transport and
+/// wait up to `timeout` for the complete response.
+fn send_message_with_timeout(msg: &IpcMessage, timeout: Duration) -> Result<IpcResponse> {
+    if is_framed(msg) {
+        // A frame cannot be resumed after a timed-out read, so one read
+        // may take the whole budget
+        return exchange_framed(connect(timeout)?, msg, timeout);
+    }
+    exchange(connect(timeout.min(READ_POLL))?, msg, timeout)
+}
+
+/// Connect over TCP (`DIACHRON_ADDR`), the Unix socket, or the named pipe.
+fn connect(read_timeout: Duration) -> Result<Box<dyn IpcStream>> {
+    let transport = default_transport();
+    let stream = transport
+        .connect()
+        .with_context(|| format!("Failed to connect to daemon at {}", transport.describe()))?;
+    stream.set_timeouts(read_timeout, Duration::from_secs(5))?;
+    Ok(stream)
+}
+
+/// Send a streaming search and call `on_result` for each hit as it arrives.
+///
+/// Returns the number of results received.
+fn stream_search(
+    msg: &IpcMessage,
+    mut on_result: impl FnMut(diachron_core::SearchResult) -> Result<()>,
+) -> Result<usize> {
+    let mut stream = connect(Duration::from_secs(5))?;
+    let json = serde_json::to_string(msg)? + "\n";
+    stream.write_all(json.as_bytes())?;
+
+    let mut count = 0;
+    for line in BufReader::new(stream).lines() {
+        match serde_json::from_str::<IpcResponse>(&line?)? {
+            IpcResponse::SearchResultChunk(result) => {
+                on_result(result)?;
+                count += 1;
+            }
+            IpcResponse::SearchResultsEnd => return Ok(count),
+            IpcResponse::Error(e) => anyhow::bail!(e),
+            _ => anyhow::bail!("Unexpected response from daemon"),
+        }
+    }
+
+    anyhow::bail!("Connection closed before end of results")
+}
 
-    let mut stream = UnixStream::connect(&path)
-        .with_context(|| format!("Failed to connect to daemon at {:?}", path))?;
+/// Poll `Health` until the daemon reports ready or `timeout` passes.
+///
+/// The daemon loads its model before it starts listening, so a missing
+/// model is final and ends the wait early; an index rebuild is waited out.
+///
+/// Returns the last response received (a `Health`, or `Pong` from daemons
+/// that predate it), or `None` if the daemon never answered.
+fn wait_for_ready(timeout: Duration) -> Option<IpcResponse> {
+    let deadline = std::time::Instant::now() + timeout;
+    let mut last = None;
+    loop {
+        match send_message(&IpcMessage::Health) {
+            Ok(response @ IpcResponse::Health { ready: true, .. })
+            | Ok(
+                response @ IpcResponse::Health {
+                    model_loaded: false,
+                    ..
+                },
+            ) => return Some(response),
+            Ok(IpcResponse::Error(_)) => {
+                return send_message(&IpcMessage::Ping).ok().or(last);
+            }
+            Ok(response) => last = Some(response),
+            Err(_) => {}
+        }
+        if std::time::Instant::now() >= deadline {
+            return last;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
 
-    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
-    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+/// Write one message and read one length-delimited response frame.
+fn exchange_framed<S: Read + Write>(
+    mut stream: S,
+    msg: &IpcMessage,
+    timeout: Duration,
+) -> Result<IpcResponse> {
+    write_frame(&mut stream, &serde_json::to_vec(msg)?)?;
+    let payload = read_frame(&mut stream).map_err(|e| timeout_error(e, timeout))?;
+    Ok(serde_json::from_slice(&payload)?)
+}
 
+/// Write one message and read one JSON-line response.
+///
+/// The line may arrive in pieces on a slow link; reading continues until
+/// the newline or until `timeout` has passed since the request was sent.
+fn exchange<S: Read + Write>(
+    mut stream: S,
+    msg: &IpcMessage,
+    timeout: Duration,
+) -> Result<IpcResponse> {
     let json = serde_json::to_string(msg)? + "\n";
     stream.write_all(json.as_bytes())?;
 
+    let deadline = std::time::Instant::now() + timeout;
     let mut reader = BufReader::new(stream);
-    let mut response = String::new();
-    reader.read_line(&mut response)?;
+    let mut response = Vec::new();
+    loop {
+        match reader.read_until(b'\n', &mut response) {
+            Ok(_) if response.ends_with(b"\n") => break,
+            Ok(_) if response.is_empty() => {
+                anyhow::bail!("Daemon closed the connection without responding")
+            }
+            Ok(_) => anyhow::bail!(
+                "Daemon closed the connection mid-response after {} bytes",
+                response.len()
+            ),
+            // Bytes read before the timeout stay in `response`
+            Err(e) if is_timeout(&e) && std::time::Instant::now() < deadline => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(timeout_error(e, timeout)),
+        }
+    }
 
-    let response: IpcResponse = serde_json::from_str(&response)?;
+    let response: IpcResponse = serde_json::from_slice(&response)?;
     Ok(response)
 }
 
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// Explain a read timeout; other I/O errors pass through.
+fn timeout_error(e: std::io::Error, timeout: Duration) -> anyhow::Error {
+    if !is_timeout(&e) {
+        return e.into();
+    }
+    anyhow::anyhow!(
+        "No response from the daemon within {}s; it may still be working. Retry with a longer --timeout <secs>",
+        timeout.as_secs()
+    )
+}
+
+/// Print the `doctor` checks and, with `fix`, repair the problems found.
+///
+/// Destructive repairs ask first unless `yes` is set. Each repair is
+/// logged to `~/.diachron/logs/doctor.log`.
+fn run_doctor_checks(fix: bool, yes: bool) {
+    #[cfg(unix)]
+    let socket_path = tcp_addr().is_none().then(socket_path);
+    #[cfg(not(unix))]
+    let socket_path = None;
+    let env = doctor::DoctorEnv {
+        diachron_home: dirs::home_dir()
+            .map(|h| h.join(".diachron"))
+            .unwrap_or_else(|| PathBuf::from("/tmp/.diachron")),
+        socket_path,
+        capabilities: handshake::daemon_capabilities(),
+        send: &send_message,
+    };
+
+    println!("\nChecks:");
+    let mut problems = 0;
+    for check in doctor::checks() {
+        match check.diagnose(&env) {
+            doctor::Diagnosis::Ok(detail) => println!("  ✓ {}: {}", check.name(), detail),
+            doctor::Diagnosis::Skipped(reason) => {
+                println!("  - {}: skipped ({})", check.name(), reason)
+            }
+            doctor::Diagnosis::Problem(detail) => {
+                problems += 1;
+                println!("  ✗ {}: {}", check.name(), detail);
+                if !fix {
+                    continue;
+                }
+                if check.destructive()
+                    && !yes
+                    && !doctor::confirm(&format!("    Fix '{}'?", check.name()))
+                {
+                    println!("    Skipped");
+                    continue;
+                }
+                let outcome = match check.fix(&env) {
+                    Ok(done) => {
+                        println!("    ✓ Fixed: {}", done);
+                        format!("{}: {}", check.name(), done)
+                    }
+                    Err(e) => {
+                        println!("    ✗ Fix failed: {:#}", e);
+                        format!("{}: fix failed: {:#}", check.name(), e)
+                    }
+                };
+                if let Err(e) = env.log(&outcome) {
+                    eprintln!("warning: could not write the doctor log: {}", e);
+                }
+            }
+        }
+    }
+    if problems > 0 && !fix {
+        println!("\n  Run 'diachron doctor --fix' to repair.");
+    }
+}
+
+/// Directory `daemon start` sends the daemon's stdout and stderr to.
+fn daemon_logs_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".diachron"))
+        .unwrap_or_else(|| PathBuf::from("/tmp/.diachron"))
+        .join("logs")
+}
+
+/// Print the last `lines` lines of a log file, then optionally follow it.
+///
+/// Following polls for appended bytes. `daemon start` truncates the logs,
+/// so a file that shrinks is read again from the start.
+fn print_log_tail(path: &std::path::Path, lines: usize, follow: bool) -> Result<()> {
+    let content =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let text = String::from_utf8_lossy(&content);
+    let all: Vec<&str> = text.lines().collect();
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for line in &all[all.len().saturating_sub(lines)..] {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    if !follow {
+        return Ok(());
+    }
+
+    let mut pos = content.len() as u64;
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let len = match std::fs::metadata(path) {
+            Ok(meta) => meta.len(),
+            // Removed between restarts; wait for it to come back
+            Err(_) => continue,
+        };
+        if len < pos {
+            pos = 0;
+        }
+        if len == pos {
+            continue;
+        }
+        let mut file = std::fs::File::open(path)?;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)?;
+        pos += appended.len() as u64;
+        out.write_all(&appended)?;
+        out.flush()?;
+    }
+}
+
 // ============================================================================
 // Auto-start Management (launchd for macOS, systemd for Linux)
 // ============================================================================
@@ -611,18 +1279,40 @@ fn check_systemd_status() -> Result<()> {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(secs) = cli.timeout {
+        let _ = TIMEOUT_OVERRIDE.set(Duration::from_secs(secs));
+    }
 
     match cli.command {
         Commands::Timeline {
             since,
             file,
+            glob,
+            file_regex,
+            project,
+            operation,
+            category,
             limit,
+            before,
             format,
             watch,
+            group_by,
+            stats,
         } => {
+            let (file, file_match) = match file_regex {
+                Some(regex) => (Some(regex), FileMatch::Regex),
+                None if glob => (file, FileMatch::Glob),
+                None => (file, FileMatch::Substring),
+            };
+
             if watch {
                 // Watch mode: poll for new events
-                println!("📊 Watching for events... (Ctrl+C to stop)\n");
+                // (keep stdout pure ndjson when streaming jsonl)
+                if format == "jsonl" {
+                    eprintln!("📊 Watching for events... (Ctrl+C to stop)");
+                } else {
+                    println!("📊 Watching for events... (Ctrl+C to stop)\n");
+                }
 
                 let mut last_seen_id: i64 = 0;
 
@@ -631,8 +1321,13 @@ fn main() -> Result<()> {
                     since: since.clone(),
                     file_filter: file.clone(),
                     limit: 1,
+                    file_match,
+                    project: project.clone(),
+                    operation: operation.clone(),
+                    category: category.clone(),
+                    before_id: None,
                 };
-                if let Ok(IpcResponse::Events(events)) = send_message(&msg) {
+                if let Ok(IpcResponse::EventsPage { events, .. }) = send_message(&msg) {
                     if let Some(event) = events.first() {
                         last_seen_id = event.id;
                     }
@@ -647,10 +1342,15 @@ fn main() -> Result<()> {
                         since: Some("5m".to_string()), // Look back 5 minutes
                         file_filter: file.clone(),
                         limit: 50,
+                        file_match,
+                        project: project.clone(),
+                        operation: operation.clone(),
+                        category: category.clone(),
+                        before_id: None,
                     };
 
                     match send_message(&msg) {
-                        Ok(IpcResponse::Events(events)) => {
+                        Ok(IpcResponse::EventsPage { events, .. }) => {
                             // Filter to only new events (id > last_seen_id)
                             let new_events: Vec<_> = events
                                 .iter()
@@ -668,16 +1368,12 @@ fn main() -> Result<()> {
                                     "json" => {
                                         println!("{}", serde_json::to_string(event).unwrap());
                                     }
+                                    "jsonl" => {
+                                        print_jsonl(std::slice::from_ref(event))?;
+                                    }
                                     _ => {
                                         // Colored output for watch mode
-                                        let op_icon = match event.operation.as_deref() {
-                                            Some("create") => "✨",
-                                            Some("modify") => "📝",
-                                            Some("delete") => "🗑️",
-                                            Some("commit") => "📦",
-                                            Some("execute") => "⚡",
-                                            _ => "•",
-                                        };
+                                        let op_icon = operation_icon(event);
 
                                         let file_display = event
                                             .file_path
@@ -709,8 +1405,10 @@ fn main() -> Result<()> {
                                             session_short
                                         );
 
-                                        // Show diff summary if available
-                                        if let Some(ref diff) = event.diff_summary {
+                                        // Show error excerpt for failures, else the diff summary
+                                        if let Some(excerpt) = event.error_excerpt() {
+                                            println!("    └─ {}", excerpt);
+                                        } else if let Some(ref diff) = event.diff_summary {
                                             if !diff.is_empty() {
                                                 println!("    └─ {}", diff);
                                             }
@@ -735,11 +1433,24 @@ fn main() -> Result<()> {
                     since,
                     file_filter: file,
                     limit,
+                    file_match,
+                    project,
+                    operation,
+                    category,
+                    before_id: before,
                 };
 
                 match send_message(&msg) {
-                    Ok(IpcResponse::Events(events)) => {
-                        if events.is_empty() {
+                    Ok(IpcResponse::EventsPage {
+                        events,
+                        next_cursor,
+                    }) => {
+                        let stats =
+                            stats.then(|| timeline_stats::TimelineStats::from_events(&events));
+                        if let (Some(stats), "json") = (&stats, format.as_str()) {
+                            let output = serde_json::json!({ "events": events, "stats": stats });
+                            println!("{}", serde_json::to_string_pretty(&output)?);
+                        } else if events.is_empty() {
                             if format == "text" {
                                 println!("No events found");
                             } else if format == "json" {
@@ -751,6 +1462,9 @@ fn main() -> Result<()> {
                                 "json" => {
                                     println!("{}", serde_json::to_string_pretty(&events).unwrap());
                                 }
+                                "jsonl" => {
+                                    print_jsonl(&events)?;
+                                }
                                 "csv" => {
                                     println!("timestamp,tool_name,file_path,operation,session_id");
                                     for event in events {
@@ -759,46 +1473,124 @@ fn main() -> Result<()> {
                                             event.timestamp,
                                             event.tool_name,
                                             event.file_path.as_deref().unwrap_or(""),
-                                            event.operation.as_deref().unwrap_or(""),
+                                            event.operation.map_or("", |op| op.as_str()),
                                             event.session_id.as_deref().unwrap_or("")
                                         );
                                     }
                                 }
+                                "markdown" | "md" if group_by.is_some() => {
+                                    let groups = group_events(events, group_by.as_deref().unwrap());
+                                    for (i, (header, group)) in groups.iter().enumerate() {
+                                        if i > 0 {
+                                            println!();
+                                        }
+                                        println!("## {} ({} events)\n", header, group.len());
+                                        println!("| Timestamp | Tool | File | Operation |");
+                                        println!("|-----------|------|------|-----------|");
+                                        for event in group {
+                                            println!(
+                                                "| {} | {}{} | {} | {} |",
+                                                event
+                                                    .timestamp_display
+                                                    .as_deref()
+                                                    .unwrap_or(&event.timestamp),
+                                                failure_marker(event),
+                                                event.tool_name,
+                                                event.file_path.as_deref().unwrap_or("-"),
+                                                event.operation.map_or("-", |op| op.as_str())
+                                            );
+                                        }
+                                    }
+                                }
                                 "markdown" | "md" => {
                                     println!("| Timestamp | Tool | File | Operation |");
                                     println!("|-----------|------|------|-----------|");
                                     for event in events {
                                         println!(
-                                            "| {} | {} | {} | {} |",
+                                            "| {} | {}{} | {} | {} |",
                                             event
                                                 .timestamp_display
                                                 .as_deref()
                                                 .unwrap_or(&event.timestamp),
+                                            failure_marker(&event),
                                             event.tool_name,
                                             event.file_path.as_deref().unwrap_or("-"),
-                                            event.operation.as_deref().unwrap_or("-")
+                                            event.operation.map_or("-", |op| op.as_str())
                                         );
                                     }
                                 }
+                                _ if group_by.is_some() => {
+                                    let groups = group_events(events, group_by.as_deref().unwrap());
+                                    for (i, (header, group)) in groups.iter().enumerate() {
+                                        if i > 0 {
+                                            println!();
+                                        }
+                                        println!("## {} ({} events)", header, group.len());
+                                        for event in group {
+                                            println!(
+                                                "  {} {}{} {}",
+                                                event
+                                                    .timestamp_display
+                                                    .as_deref()
+                                                    .unwrap_or(&event.timestamp),
+                                                failure_marker(event),
+                                                event.tool_name,
+                                                event.file_path.as_deref().unwrap_or("-")
+                                            );
+                                        }
+                                    }
+                                }
                                 _ => {
                                     // Default: text format
                                     for event in events {
                                         println!(
-                                            "{} {} {}",
+                                            "{} {}{} {}",
                                             event
                                                 .timestamp_display
                                                 .as_deref()
                                                 .unwrap_or(&event.timestamp),
+                                            failure_marker(&event),
                                             event.tool_name,
                                             event.file_path.as_deref().unwrap_or("-")
                                         );
+                                        if let Some(excerpt) = event.error_excerpt() {
+                                            println!("    └─ {}", excerpt);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        if let Some(stats) = &stats {
+                            let mut lines = stats.summary_lines().into_iter();
+                            match format.as_str() {
+                                "json" | "jsonl" | "csv" => {}
+                                "markdown" | "md" => {
+                                    println!();
+                                    for line in lines {
+                                        println!("- {}", line);
                                     }
                                 }
+                                _ => {
+                                    println!("\nStats: {}", lines.next().unwrap_or_default());
+                                    for line in lines {
+                                        println!("       {}", line);
+                                    }
+                                }
+                            }
+                        }
+
+                        // Keep machine-readable output clean
+                        if let Some(cursor) = next_cursor {
+                            if format == "text" {
+                                println!("\nMore events: --before {}", cursor);
+                            } else {
+                                eprintln!("More events: --before {}", cursor);
                             }
                         }
                     }
                     Ok(IpcResponse::Error(e)) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                         std::process::exit(1);
                     }
                     Ok(_) => {
@@ -814,7 +1606,9 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Capture { json } => {
+        Commands::Capture { json: None } => capture_batch()?,
+
+        Commands::Capture { json: Some(json) } => {
             let event: diachron_core::CaptureEvent =
                 serde_json::from_str(&json).context("Invalid event JSON")?;
 
@@ -841,7 +1635,13 @@ fn main() -> Result<()> {
                     limit,
                     source_filter: Some(diachron_core::SearchSource::Exchange),
                     since: None,
+                    until: None,
                     project: None,
+                    tool: None,
+                    stream: false,
+                    after: None,
+                    prefer: None,
+                    explain: false,
                 };
 
                 match send_message(&msg) {
@@ -858,7 +1658,7 @@ fn main() -> Result<()> {
                         }
                     }
                     Ok(IpcResponse::Error(e)) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                         std::process::exit(1);
                     }
                     Ok(_) => {}
@@ -875,8 +1675,25 @@ fn main() -> Result<()> {
                     Ok(IpcResponse::Ok) => {
                         println!("Indexing started");
                     }
+                    Ok(IpcResponse::IndexStats {
+                        exchanges_indexed,
+                        archives_processed,
+                        errors,
+                        deduplicated,
+                    }) => {
+                        println!(
+                            "Indexed {} exchanges from {} archives",
+                            exchanges_indexed, archives_processed
+                        );
+                        if deduplicated > 0 {
+                            println!("  Near-duplicates skipped: {}", deduplicated);
+                        }
+                        if errors > 0 {
+                            println!("  Errors: {}", errors);
+                        }
+                    }
                     Ok(IpcResponse::Error(e)) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                         std::process::exit(1);
                     }
                     Ok(_) => {}
@@ -888,7 +1705,7 @@ fn main() -> Result<()> {
             }
 
             MemoryCommands::Status => {
-                let msg = IpcMessage::DoctorInfo;
+                let msg = handshake::daemon_capabilities().diagnostics_request();
                 match send_message(&msg) {
                     Ok(IpcResponse::Doctor(info)) => {
                         println!("Memory Status");
@@ -954,7 +1771,7 @@ fn main() -> Result<()> {
                         println!("\nUpgrade daemon for full stats: diachron daemon stop && diachron daemon start");
                     }
                     Ok(IpcResponse::Error(e)) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                         std::process::exit(1);
                     }
                     Ok(_) => {
@@ -969,38 +1786,224 @@ fn main() -> Result<()> {
                 }
             }
 
-            MemoryCommands::Summarize { limit } => {
-                let msg = IpcMessage::SummarizeExchanges { limit };
-                // Use longer timeout for summarization (can take a while)
-                let path = socket_path();
-                let mut stream = std::os::unix::net::UnixStream::connect(&path)
-                    .context("Failed to connect to daemon")?;
-                stream.set_read_timeout(Some(Duration::from_secs(300)))?; // 5 min timeout
-                stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+            MemoryCommands::Reindex { force } => {
+                let msg = IpcMessage::Reindex { force };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::ReindexStats {
+                        rebuilt: false,
+                        dim,
+                        ..
+                    } => {
+                        println!("Vector indexes already match the {}-dim model.", dim);
+                        println!("Use --force to rebuild anyway.");
+                    }
+                    IpcResponse::ReindexStats {
+                        dim,
+                        events_indexed,
+                        exchanges_indexed,
+                        ..
+                    } => {
+                        println!("Reindex complete ({} dimensions):", dim);
+                        println!("  Events: {}", events_indexed);
+                        println!("  Exchanges: {}", exchanges_indexed);
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            MemoryCommands::Compact => {
+                let response =
+                    send_message(&IpcMessage::Compact).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::CompactStats { events, exchanges } => {
+                        println!("Compaction complete:");
+                        for (name, stats) in [("Events", events), ("Exchanges", exchanges)] {
+                            println!(
+                                "  {}: {} -> {} vectors, {:.1} -> {:.1} MB",
+                                name,
+                                stats.vectors_before,
+                                stats.vectors_after,
+                                stats.bytes_before as f64 / 1024.0 / 1024.0,
+                                stats.bytes_after as f64 / 1024.0 / 1024.0
+                            );
+                            if stats.corrupt > 0 {
+                                println!(
+                                    "    ⚠️  {} corrupt stored embeddings skipped",
+                                    stats.corrupt
+                                );
+                            }
+                            if stats.wrong_dim > 0 {
+                                println!(
+                                    "    ⚠️  {} embeddings of another dimension skipped (run 'diachron memory reindex --force')",
+                                    stats.wrong_dim
+                                );
+                            }
+                        }
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            MemoryCommands::Forget {
+                project,
+                session,
+                before,
+                query,
+                dry_run,
+            } => {
+                let msg = IpcMessage::Forget {
+                    project,
+                    session,
+                    before,
+                    query,
+                    dry_run,
+                };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::ForgetStats {
+                        exchanges,
+                        vectors,
+                        projects,
+                        dry_run,
+                    } => {
+                        if dry_run {
+                            println!("Would delete {} exchanges ({} vectors)", exchanges, vectors);
+                        } else {
+                            println!("Deleted {} exchanges ({} vectors)", exchanges, vectors);
+                        }
+                        for project in projects {
+                            println!("  {}", project);
+                        }
+                        if dry_run && exchanges > 0 {
+                            println!("Run again without --dry-run to delete them.");
+                        }
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
 
-                let json = serde_json::to_string(&msg)? + "\n";
-                use std::io::Write;
-                stream.write_all(json.as_bytes())?;
+            MemoryCommands::DedupReport {
+                threshold,
+                sample,
+                limit,
+            } => {
+                let msg = IpcMessage::DedupReport { threshold, sample };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::DedupReport {
+                        indexed,
+                        probed,
+                        pairs,
+                        clusters,
+                        reclaimable,
+                    } => {
+                        println!("🔁 Near-duplicate exchanges (similarity ≥ {})\n", threshold);
+                        println!("   Probed:      {} of {} vectors", probed, indexed);
+                        println!("   Pairs:       {}", pairs);
+                        println!("   Clusters:    {}", clusters.len());
+                        println!("   Reclaimable: {} vectors", reclaimable);
+
+                        let shown = if limit == 0 { clusters.len() } else { limit };
+                        if !clusters.is_empty() {
+                            println!();
+                        }
+                        for cluster in clusters.iter().take(shown) {
+                            println!("   [{}] {}", cluster.len(), cluster.join(", "));
+                        }
+                        if clusters.len() > shown {
+                            println!("   ...and {} more clusters", clusters.len() - shown);
+                        }
+                        if probed < indexed {
+                            println!(
+                                "\nSampled run: duplicates of unprobed vectors are only found from their probed neighbors."
+                            );
+                        }
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
 
-                let mut reader = std::io::BufReader::new(stream);
-                let mut response = String::new();
-                use std::io::BufRead;
-                reader.read_line(&mut response)?;
+            MemoryCommands::ExportVectors { format, out } => {
+                let format = match format.as_str() {
+                    "arrow" => VectorExportFormat::Arrow,
+                    _ => VectorExportFormat::Npy,
+                };
+                // Resolved by the daemon inside its exports directory
+                let msg = IpcMessage::ExportVectors { dir: out, format };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::VectorsExported {
+                        dim,
+                        events,
+                        exchanges,
+                        files,
+                    } => {
+                        println!("📦 Exported {}-dim vectors\n", dim);
+                        println!("   Events:    {}", events);
+                        println!("   Exchanges: {}", exchanges);
+                        println!();
+                        for file in files {
+                            println!("   {}", file.display());
+                        }
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
 
-                let response: IpcResponse = serde_json::from_str(&response)?;
+            MemoryCommands::Summarize { limit } => {
+                let msg = IpcMessage::SummarizeExchanges { limit };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
                 match response {
                     IpcResponse::SummarizeStats {
                         summarized,
                         skipped,
-                        errors,
+                        retried,
+                        failed,
                     } => {
                         println!("Summarization complete:");
                         println!("  Summarized: {}", summarized);
                         println!("  Skipped: {}", skipped);
-                        println!("  Errors: {}", errors);
+                        println!("  Retried: {}", retried);
+                        println!("  Failed: {}", failed);
                     }
                     IpcResponse::Error(e) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                         std::process::exit(1);
                     }
                     _ => {
@@ -1012,22 +2015,26 @@ fn main() -> Result<()> {
         },
 
         Commands::Daemon { command } => match command {
-            DaemonCommands::Start => {
+            DaemonCommands::Start { wait } => {
                 // Check if already running
-                let socket = socket_path();
-                if socket.exists() {
-                    if let Ok(IpcResponse::Pong { .. }) = send_message(&IpcMessage::Ping) {
-                        println!("Daemon is already running");
-                        return Ok(());
+                if let Ok(IpcResponse::Pong { .. }) = send_message(&IpcMessage::Ping) {
+                    println!("Daemon is already running");
+                    return Ok(());
+                }
+
+                // Stale socket file - remove it
+                #[cfg(unix)]
+                {
+                    let socket = socket_path();
+                    if socket.exists() {
+                        let _ = std::fs::remove_file(&socket);
                     }
-                    // Stale socket file - remove it
-                    let _ = std::fs::remove_file(&socket);
                 }
 
                 // Find daemon binary (same directory as CLI)
                 let daemon_path = std::env::current_exe()?
                     .parent()
-                    .map(|p| p.join("diachrond"))
+                    .map(|p| p.join(format!("diachrond{}", std::env::consts::EXE_SUFFIX)))
                     .context("Could not determine executable directory")?;
 
                 if !daemon_path.exists() {
@@ -1040,7 +2047,7 @@ fn main() -> Result<()> {
                 let diachron_home = dirs::home_dir()
                     .map(|h| h.join(".diachron"))
                     .unwrap_or_else(|| PathBuf::from("/tmp/.diachron"));
-                let logs_dir = diachron_home.join("logs");
+                let logs_dir = daemon_logs_dir();
                 std::fs::create_dir_all(&logs_dir).ok();
 
                 // Start daemon process
@@ -1064,13 +2071,30 @@ fn main() -> Result<()> {
                 println!("Daemon started with PID {}", child.id());
                 println!("Logs: {}", logs_dir.display());
 
-                // Wait a moment and verify it's running
-                std::thread::sleep(Duration::from_millis(500));
-                if let Ok(IpcResponse::Pong { .. }) = send_message(&IpcMessage::Ping) {
-                    println!("Daemon is running and responding");
-                } else {
-                    eprintln!("Warning: Daemon started but not responding yet");
-                    eprintln!("Check logs: {}", logs_dir.join("daemon.err").display());
+                // Poll until the daemon reports ready (model loaded, indexes usable)
+                match wait_for_ready(Duration::from_secs(wait)) {
+                    Some(IpcResponse::Health {
+                        ready: true,
+                        warnings,
+                        ..
+                    }) => {
+                        println!("Daemon is ready");
+                        for warning in warnings {
+                            println!("  ⚠️  {}", warning);
+                        }
+                    }
+                    Some(IpcResponse::Health { warnings, .. }) => {
+                        eprintln!("Warning: Daemon is running but not ready");
+                        for warning in warnings {
+                            eprintln!("  - {}", warning);
+                        }
+                    }
+                    Some(_) => println!("Daemon is running and responding"),
+                    None if wait == 0 => {}
+                    None => {
+                        eprintln!("Warning: Daemon started but not responding yet");
+                        eprintln!("Check logs: {}", logs_dir.join("daemon.err").display());
+                    }
                 }
             }
 
@@ -1081,7 +2105,7 @@ fn main() -> Result<()> {
                         println!("Daemon stopped");
                     }
                     Ok(IpcResponse::Error(e)) => {
-                        eprintln!("Error: {}", e);
+                        print_daemon_error(&e);
                     }
                     Ok(_) => {}
                     Err(_) => {
@@ -1100,6 +2124,15 @@ fn main() -> Result<()> {
                         println!("Daemon: Running");
                         println!("Uptime: {}s", uptime_secs);
                         println!("Events captured: {}", events_count);
+                        if let Ok(IpcResponse::Health {
+                            ready, warnings, ..
+                        }) = send_message(&IpcMessage::Health)
+                        {
+                            println!("Ready: {}", if ready { "yes" } else { "no" });
+                            for warning in warnings {
+                                println!("  ⚠️  {}", warning);
+                            }
+                        }
                     }
                     Ok(IpcResponse::Error(e)) => {
                         eprintln!("Daemon error: {}", e);
@@ -1111,6 +2144,64 @@ fn main() -> Result<()> {
                 }
             }
 
+            DaemonCommands::ReloadModel { model_dir } => {
+                // The daemon resolves paths from its own working directory
+                let model_dir = model_dir
+                    .map(|dir| {
+                        std::fs::canonicalize(&dir)
+                            .with_context(|| format!("Model directory {} not found", dir.display()))
+                    })
+                    .transpose()?;
+                let msg = IpcMessage::ReloadModel { model_dir };
+                let response = send_message(&msg).context("Failed to connect to daemon")?;
+                match response {
+                    IpcResponse::ModelReloaded {
+                        swapped: true,
+                        dim,
+                        pool_size,
+                        ..
+                    } => {
+                        println!(
+                            "✅ Embedding model reloaded ({}-dim, pool of {})",
+                            dim, pool_size
+                        );
+                        println!("   Vectors from a different model are not comparable; run 'diachron memory reindex --force' if the model changed.");
+                    }
+                    IpcResponse::ModelReloaded {
+                        swapped: false,
+                        dim,
+                        index_dim,
+                        ..
+                    } => {
+                        eprintln!(
+                            "❌ The new model produces {}-dim embeddings but the indexes hold {}-dim vectors.",
+                            dim, index_dim
+                        );
+                        eprintln!("   Kept the current model. Restart the daemon with the new model and run 'diachron memory reindex' to switch.");
+                        std::process::exit(1);
+                    }
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
+                    }
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            DaemonCommands::Logs { follow, err, lines } => {
+                let name = if err { "daemon.err" } else { "daemon.log" };
+                let path = daemon_logs_dir().join(name);
+                if !path.exists() {
+                    println!("No daemon log at {} yet", path.display());
+                    println!("It is created when the daemon starts: diachron daemon start");
+                    return Ok(());
+                }
+                print_log_tail(&path, lines, follow)?;
+            }
+
             DaemonCommands::AutostartEnable => {
                 enable_autostart()?;
             }
@@ -1124,98 +2215,450 @@ fn main() -> Result<()> {
             }
         },
 
-        Commands::Dashboard { command } => match command {
-            DashboardCommands::Start { port, no_browser } => {
-                // Check if daemon is running first
-                if let Err(_) = send_message(&IpcMessage::Ping) {
-                    eprintln!("⚠️  Daemon is not running. Starting daemon first...");
-                    // Try to start daemon
-                    let daemon_path = std::env::current_exe()?
-                        .parent()
-                        .map(|p| p.join("diachrond"))
-                        .context("Could not determine executable directory")?;
-
-                    if daemon_path.exists() {
-                        let diachron_home = dirs::home_dir()
-                            .context("Could not find home directory")?
-                            .join(".diachron");
-                        std::fs::create_dir_all(&diachron_home)?;
-                        let logs_dir = diachron_home.join("logs");
-                        std::fs::create_dir_all(&logs_dir)?;
+        Commands::Stats { since, format } => {
+            let msg = IpcMessage::Timeline {
+                since: Some(since.clone()),
+                file_filter: None,
+                limit: STATS_EVENT_LIMIT,
+                file_match: FileMatch::Substring,
+                project: None,
+                operation: None,
+                category: None,
+                before_id: None,
+            };
 
-                        std::process::Command::new(&daemon_path)
-                            .stdout(std::fs::File::create(logs_dir.join("daemon.out"))?)
-                            .stderr(std::fs::File::create(logs_dir.join("daemon.err"))?)
-                            .spawn()
-                            .context("Failed to start daemon")?;
+            match send_message(&msg) {
+                Ok(IpcResponse::EventsPage { events, .. }) => {
+                    let rows = source_stats(&events);
+                    let total = events.len();
 
-                        std::thread::sleep(Duration::from_millis(500));
+                    if format == "json" {
+                        let json: Vec<_> = rows
+                            .iter()
+                            .map(|row| {
+                                serde_json::json!({
+                                    "tool_name": row.tool_name,
+                                    "command_category": row.command_category.map(|c| c.as_str()),
+                                    "events": row.events,
+                                    "files_touched": row.files.len(),
+                                    "net_lines": row.net_lines,
+                                    "percent": percent(row.events, total),
+                                })
+                            })
+                            .collect();
+                        println!("{}", serde_json::to_string_pretty(&json)?);
+                    } else if rows.is_empty() {
+                        println!("No events since {}", since);
                     } else {
-                        eprintln!("❌ Daemon binary not found. Run 'diachron daemon start' first.");
-                        std::process::exit(1);
+                        println!(
+                            "📊 Contribution by source (since {}, {} events)\n",
+                            since, total
+                        );
+                        println!(
+                            "{:<12} {:<10} {:>7} {:>7} {:>10} {:>7}",
+                            "Tool", "Category", "Events", "Files", "Net lines", "Share"
+                        );
+                        for row in &rows {
+                            println!(
+                                "{:<12} {:<10} {:>7} {:>7} {:>10} {:>6.1}%",
+                                row.tool_name,
+                                row.command_category.map_or("-", |c| c.as_str()),
+                                row.events,
+                                row.files.len(),
+                                format!("{:+}", row.net_lines),
+                                percent(row.events, total)
+                            );
+                        }
+                        if total >= STATS_EVENT_LIMIT {
+                            println!(
+                                "\n⚠️  Only the most recent {} events were counted",
+                                STATS_EVENT_LIMIT
+                            );
+                        }
                     }
                 }
-
-                // Find dashboard directory
-                let dashboard_dir = dirs::home_dir()
-                    .context("Could not find home directory")?
-                    .join(".claude")
-                    .join("skills")
-                    .join("diachron")
-                    .join("dashboard");
-
-                if !dashboard_dir.exists() {
-                    eprintln!("❌ Dashboard not found at {:?}", dashboard_dir);
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
                     std::process::exit(1);
                 }
+            }
+        }
 
-                // Check if already running by trying to connect
-                let check_url = format!("http://localhost:{}/api/health", port);
-                if let Ok(response) = reqwest::blocking::get(&check_url) {
-                    if response.status().is_success() {
-                        println!("✅ Dashboard is already running at http://localhost:{}", port);
-                        if !no_browser {
-                            let _ = open::that(format!("http://localhost:{}", port));
+        Commands::Show { sha, format } => {
+            let msg = IpcMessage::EventsForCommit { sha: sha.clone() };
+
+            match send_message(&msg) {
+                Ok(IpcResponse::Events(events)) => {
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&events)?);
+                    } else if events.is_empty() {
+                        println!("No events recorded for commit {}", sha);
+                    } else {
+                        let full_sha = events
+                            .iter()
+                            .find_map(|e| e.git_commit_sha.as_deref())
+                            .unwrap_or(&sha);
+                        println!("📦 Commit {} ({} events)\n", full_sha, events.len());
+                        for event in &events {
+                            println!(
+                                "{} {}{} {}",
+                                event
+                                    .timestamp_display
+                                    .as_deref()
+                                    .unwrap_or(&event.timestamp),
+                                failure_marker(event),
+                                event.tool_name,
+                                event.file_path.as_deref().unwrap_or("-")
+                            );
+                            if let Some(excerpt) = event.error_excerpt() {
+                                println!("    └─ {}", excerpt);
+                            } else if let Some(diff) = event.diff_summary.as_deref() {
+                                if !diff.is_empty() {
+                                    println!("    └─ {}", diff);
+                                }
+                            }
                         }
-                        return Ok(());
                     }
                 }
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
 
-                println!("🚀 Starting Diachron dashboard...");
-
-                // Build if dist/ doesn't exist
-                let dist_dir = dashboard_dir.join("dist");
-                let proxy_dist = dashboard_dir.join("dist").join("proxy");
-
-                if !dist_dir.exists() || !proxy_dist.exists() {
-                    println!("📦 Building dashboard (first run)...");
-                    let build_status = std::process::Command::new("npm")
-                        .current_dir(&dashboard_dir)
-                        .arg("run")
-                        .arg("build")
-                        .status()
-                        .context("Failed to run npm build")?;
+        Commands::Session { id, format } => {
+            let msg = IpcMessage::SessionSummary { session_id: id };
 
-                    if !build_status.success() {
-                        eprintln!("❌ Build failed. Run 'cd {} && npm install && npm run build' manually", dashboard_dir.display());
-                        std::process::exit(1);
+            match send_message(&msg) {
+                Ok(IpcResponse::SessionSummary(summary)) => {
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&summary)?);
+                    } else {
+                        print_session_summary(&summary);
                     }
                 }
-
-                // Start the proxy server
-                let diachron_home = dirs::home_dir()
-                    .context("Could not find home directory")?
-                    .join(".diachron");
-                let pid_file = diachron_home.join("dashboard.pid");
-                let log_file = diachron_home.join("logs").join("dashboard.log");
-                std::fs::create_dir_all(diachron_home.join("logs"))?;
-
-                let child = std::process::Command::new("node")
-                    .current_dir(&dashboard_dir)
-                    .arg("dist/proxy/server.js")
-                    .env("PORT", port.to_string())
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::Sessions {
+            since,
+            limit,
+            format,
+        } => {
+            let msg = IpcMessage::Sessions {
+                since: Some(since.clone()),
+                limit,
+            };
+
+            match send_message(&msg) {
+                Ok(IpcResponse::Sessions(sessions)) => {
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&sessions)?);
+                    } else if sessions.is_empty() {
+                        println!("No sessions since {}", since);
+                    } else {
+                        println!("🗂️  Sessions since {} ({})\n", since, sessions.len());
+                        for session in &sessions {
+                            println!(
+                                "{}  {}  {:>4} events  {:>3} files  {}",
+                                safe_truncate(&session.session_id, 8),
+                                session.started_at,
+                                session.event_count,
+                                session.files_touched.len(),
+                                session
+                                    .intent
+                                    .as_deref()
+                                    .map(|i| safe_truncate(i, 60))
+                                    .unwrap_or("-")
+                            );
+                        }
+                    }
+                }
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::Mcp => mcp::run()?,
+
+        Commands::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                "diachron",
+                &mut std::io::stdout(),
+            );
+        }
+
+        Commands::Watch { file } => watch::run(file)?,
+
+        Commands::Diff { id, format } => {
+            let msg = IpcMessage::GetEvent { id };
+
+            match send_message(&msg) {
+                Ok(IpcResponse::Event(detail)) => {
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&detail)?);
+                    } else {
+                        print_event_detail(&detail);
+                    }
+                }
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::RevertPreview { id } => {
+            let msg = IpcMessage::GetEvent { id };
+
+            match send_message(&msg) {
+                Ok(IpcResponse::Event(detail)) => print_revert_preview(&detail),
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::Lineage { id, format } => {
+            let msg = IpcMessage::Lineage { id };
+
+            match send_message(&msg) {
+                Ok(IpcResponse::Events(events)) => {
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&events)?);
+                    } else {
+                        let file = events
+                            .last()
+                            .and_then(|e| e.file_path.as_deref())
+                            .unwrap_or("-");
+                        println!(
+                            "🧬 Lineage of event {} ({}, {} edits)\n",
+                            id,
+                            file,
+                            events.len()
+                        );
+                        for (step, event) in events.iter().enumerate() {
+                            println!(
+                                "{:>3}. [{}] #{} {}{} {}",
+                                step + 1,
+                                event
+                                    .timestamp_display
+                                    .as_deref()
+                                    .unwrap_or(&event.timestamp),
+                                event.id,
+                                failure_marker(event),
+                                event.tool_name,
+                                event.operation.map_or("-", |op| op.as_str())
+                            );
+                            if let Some(diff) =
+                                event.diff_summary.as_deref().filter(|d| !d.is_empty())
+                            {
+                                println!("       └─ {}", diff);
+                            }
+                        }
+                        if events.len() == 1 {
+                            println!(
+                                "\nNo earlier edits to this file were captured in the same session."
+                            );
+                        }
+                    }
+                }
+                Ok(IpcResponse::Error(e)) => {
+                    print_daemon_error(&e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("Unexpected response");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Failed to communicate with daemon: {}", e);
+                    eprintln!("Is the daemon running? Try: diachron daemon start");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::Dashboard { command } => match command {
+            DashboardCommands::Start { port, no_browser } => {
+                // Check if daemon is running first
+                if let Err(_) = send_message(&IpcMessage::Ping) {
+                    eprintln!("⚠️  Daemon is not running. Starting daemon first...");
+                    // Try to start daemon
+                    let daemon_path = std::env::current_exe()?
+                        .parent()
+                        .map(|p| p.join(format!("diachrond{}", std::env::consts::EXE_SUFFIX)))
+                        .context("Could not determine executable directory")?;
+
+                    if daemon_path.exists() {
+                        let diachron_home = dirs::home_dir()
+                            .context("Could not find home directory")?
+                            .join(".diachron");
+                        std::fs::create_dir_all(&diachron_home)?;
+                        let logs_dir = diachron_home.join("logs");
+                        std::fs::create_dir_all(&logs_dir)?;
+
+                        std::process::Command::new(&daemon_path)
+                            .stdout(std::fs::File::create(logs_dir.join("daemon.out"))?)
+                            .stderr(std::fs::File::create(logs_dir.join("daemon.err"))?)
+                            .spawn()
+                            .context("Failed to start daemon")?;
+
+                        std::thread::sleep(Duration::from_millis(500));
+                    } else {
+                        eprintln!("❌ Daemon binary not found. Run 'diachron daemon start' first.");
+                        std::process::exit(1);
+                    }
+                }
+
+                // Find dashboard directory
+                let dashboard_dir = dirs::home_dir()
+                    .context("Could not find home directory")?
+                    .join(".claude")
+                    .join("skills")
+                    .join("diachron")
+                    .join("dashboard");
+
+                if !dashboard_dir.exists() {
+                    eprintln!("❌ Dashboard not found at {:?}", dashboard_dir);
+                    std::process::exit(1);
+                }
+
+                let diachron_home = dirs::home_dir()
+                    .context("Could not find home directory")?
+                    .join(".diachron");
+                let token_file = dashboard::token_path(&diachron_home);
+                // Opened in the browser; carries the token when auth is on
+                let page_url = |token: Option<&str>| match token {
+                    Some(token) => format!("http://localhost:{}/?token={}", port, token),
+                    None => format!("http://localhost:{}", port),
+                };
+
+                // Check if already running by trying to connect
+                let running_token = dashboard::read_token(&token_file);
+                if dashboard::is_healthy(port, running_token.as_deref()) {
+                    println!(
+                        "✅ Dashboard is already running at http://localhost:{}",
+                        port
+                    );
+                    if !no_browser {
+                        let _ = open::that(page_url(running_token.as_deref()));
+                    }
+                    return Ok(());
+                }
+
+                println!("🚀 Starting Diachron dashboard...");
+
+                // Build if dist/ doesn't exist
+                let dist_dir = dashboard_dir.join("dist");
+                let proxy_dist = dashboard_dir.join("dist").join("proxy");
+
+                if !dist_dir.exists() || !proxy_dist.exists() {
+                    println!("📦 Building dashboard (first run)...");
+                    let build_status = std::process::Command::new("npm")
+                        .current_dir(&dashboard_dir)
+                        .arg("run")
+                        .arg("build")
+                        .status()
+                        .context("Failed to run npm build")?;
+
+                    if !build_status.success() {
+                        eprintln!("❌ Build failed. Run 'cd {} && npm install && npm run build' manually", dashboard_dir.display());
+                        std::process::exit(1);
+                    }
+                }
+
+                // Start the proxy server
+                let pid_file = diachron_home.join("dashboard.pid");
+                let log_file = diachron_home.join("logs").join("dashboard.log");
+                std::fs::create_dir_all(diachron_home.join("logs"))?;
+
+                // A fresh token per start; without auth, drop any stale one
+                let token = if dashboard::DashboardConfig::load().require_auth {
+                    let token = dashboard::generate_token();
+                    dashboard::write_token(&token_file, &token)
+                        .context("Failed to write dashboard token")?;
+                    Some(token)
+                } else {
+                    let _ = std::fs::remove_file(&token_file);
+                    None
+                };
+
+                let mut command = std::process::Command::new("node");
+                command
+                    .current_dir(&dashboard_dir)
+                    .arg("dist/proxy/server.js")
+                    .env("PORT", port.to_string())
                     .stdout(std::fs::File::create(&log_file)?)
-                    .stderr(std::fs::File::create(&log_file)?)
+                    .stderr(std::fs::File::create(&log_file)?);
+                if let Some(token) = &token {
+                    command.env(dashboard::TOKEN_ENV_VAR, token);
+                }
+                let mut child = command
                     .spawn()
                     .context("Failed to start dashboard server")?;
 
@@ -1225,17 +2668,35 @@ fn main() -> Result<()> {
                 let mut ready = false;
                 for _ in 0..20 {
                     std::thread::sleep(Duration::from_millis(500));
-                    if let Ok(response) = reqwest::blocking::get(&check_url) {
-                        if response.status().is_success() {
-                            ready = true;
-                            break;
-                        }
+                    if dashboard::is_healthy(port, token.as_deref()) {
+                        ready = true;
+                        break;
                     }
                 }
 
+                // A proxy built before token auth would ignore the token
+                // and serve everyone
+                if ready && token.is_some() && dashboard::is_healthy(port, None) {
+                    let _ = child.kill();
+                    let _ = std::fs::remove_file(&pid_file);
+                    let _ = std::fs::remove_file(&token_file);
+                    eprintln!("❌ The dashboard build does not enforce dashboard.require_auth; stopped it");
+                    eprintln!(
+                        "   Rebuild with 'cd {} && npm run build'",
+                        dashboard_dir.display()
+                    );
+                    std::process::exit(1);
+                }
+
                 // Verify it's running
                 if ready {
                     println!("   Proxy: http://localhost:{}", port);
+                    if token.is_some() {
+                        println!(
+                            "   Auth: token required (saved to {})",
+                            token_file.display()
+                        );
+                    }
 
                     // Get daemon stats
                     if let Ok(IpcResponse::Pong { uptime_secs, events_count }) = send_message(&IpcMessage::Ping) {
@@ -1244,10 +2705,10 @@ fn main() -> Result<()> {
 
                     if !no_browser {
                         println!("   Opening browser...");
-                        let _ = open::that(format!("http://localhost:{}", port));
+                        let _ = open::that(page_url(token.as_deref()));
                     }
 
-                    println!("\n✅ Dashboard running at http://localhost:{}", port);
+                    println!("\n✅ Dashboard running at {}", page_url(token.as_deref()));
                     return Ok(());
                 }
 
@@ -1279,6 +2740,7 @@ fn main() -> Result<()> {
                         }
                     }
                     let _ = std::fs::remove_file(&pid_file);
+                    let _ = std::fs::remove_file(dashboard::token_path(&diachron_home));
                     println!("✅ Dashboard stopped");
                 } else {
                     println!("Dashboard is not running");
@@ -1290,24 +2752,23 @@ fn main() -> Result<()> {
                     .context("Could not find home directory")?
                     .join(".diachron");
                 let pid_file = diachron_home.join("dashboard.pid");
+                let token_file = dashboard::token_path(&diachron_home);
+                let token = dashboard::read_token(&token_file);
 
                 // Check if process is running
-                let mut dashboard_running = false;
-                if pid_file.exists() {
-                    // Try to connect
-                    if let Ok(response) = reqwest::blocking::get("http://localhost:3947/api/health") {
-                        if response.status().is_success() {
-                            dashboard_running = true;
-                        }
-                    }
-                }
+                let dashboard_running =
+                    pid_file.exists() && dashboard::is_healthy(3947, token.as_deref());
 
                 if dashboard_running {
                     println!("Dashboard: Running (http://localhost:3947)");
+                    if token.is_some() {
+                        println!("Auth: token required ({})", token_file.display());
+                    }
                 } else {
                     println!("Dashboard: Not running");
                     if pid_file.exists() {
                         let _ = std::fs::remove_file(&pid_file);
+                        let _ = std::fs::remove_file(&token_file);
                     }
                 }
 
@@ -1333,9 +2794,14 @@ fn main() -> Result<()> {
         Commands::Search {
             query,
             limit,
+            after,
             r#type,
             since,
+            until,
             project,
+            tool,
+            prefer,
+            explain,
             format,
             context_mode,
         } => {
@@ -1344,15 +2810,61 @@ fn main() -> Result<()> {
                 "exchange" => Some(diachron_core::SearchSource::Exchange),
                 _ => None,
             });
+            let prefer = prefer.and_then(|p| match p.as_str() {
+                "semantic" => Some(SearchPreference::Semantic),
+                "keyword" => Some(SearchPreference::Keyword),
+                "balanced" => Some(SearchPreference::Balanced),
+                _ => None,
+            });
+
+            // Text and jsonl render progressively; other formats need the full
+            // set, as do daemons that cannot stream
+            let stream = !context_mode
+                && matches!(format.as_str(), "text" | "jsonl")
+                && handshake::daemon_capabilities().supports(CAP_STREAMING_SEARCH);
 
             let msg = IpcMessage::Search {
                 query,
                 limit,
                 source_filter,
                 since,
+                until,
                 project,
+                tool,
+                stream,
+                after,
+                prefer,
+                explain,
+            };
+            let next_page_hint = |count: usize, last: Option<SearchCursor>| {
+                if let (true, "text", Some(cursor)) = (count == limit, format.as_str(), last) {
+                    println!("\nMore results: --after {}", cursor);
+                }
             };
 
+            if stream {
+                let mut last = None;
+                let streamed = stream_search(&msg, |result| {
+                    last = Some(SearchCursor::after(&result));
+                    if format == "jsonl" {
+                        print_jsonl(std::slice::from_ref(&result))
+                    } else {
+                        print_search_result(&result);
+                        std::io::stdout().flush()?;
+                        Ok(())
+                    }
+                });
+                match streamed {
+                    Ok(0) if format == "text" => println!("No results found"),
+                    Ok(count) => next_page_hint(count, last),
+                    Err(e) => {
+                        eprintln!("Failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+
             match send_message(&msg) {
                 Ok(IpcResponse::SearchResults(results)) => {
                     if results.is_empty() {
@@ -1365,12 +2877,19 @@ fn main() -> Result<()> {
                         }
                     } else if context_mode {
                         // Context injection mode: format for session start
-                        format_context_output(&results);
+                        if let Some(context) =
+                            context::format_context_output(&results, &ContextConfig::load())
+                        {
+                            print!("{}", context);
+                        }
                     } else {
                         match format.as_str() {
                             "json" => {
                                 println!("{}", serde_json::to_string_pretty(&results).unwrap());
                             }
+                            "jsonl" => {
+                                print_jsonl(&results)?;
+                            }
                             "csv" => {
                                 println!("score,source,timestamp,project,snippet");
                                 for result in results {
@@ -1413,27 +2932,19 @@ fn main() -> Result<()> {
                             }
                             _ => {
                                 // Default: text format
-                                for result in results {
-                                    let source_str = match result.source {
-                                        diachron_core::SearchSource::Event => "Event",
-                                        diachron_core::SearchSource::Exchange => "Exchange",
-                                    };
-                                    let proj_str = result.project.as_deref().unwrap_or("-");
-                                    println!(
-                                        "[{:.2}] {} {} ({}) - {}",
-                                        result.score,
-                                        source_str,
-                                        result.timestamp,
-                                        proj_str,
-                                        result.snippet
-                                    );
+                                for result in &results {
+                                    print_search_result(result);
                                 }
+                                next_page_hint(
+                                    results.len(),
+                                    results.last().map(SearchCursor::after),
+                                );
                             }
                         }
                     }
                 }
                 Ok(IpcResponse::Error(e)) => {
-                    eprintln!("Error: {}", e);
+                    print_daemon_error(&e);
                     std::process::exit(1);
                 }
                 Ok(_) => {}
@@ -1444,30 +2955,51 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Doctor => {
+        Commands::Doctor { fix, yes } => {
             println!("Diachron Diagnostics");
             println!("====================\n");
 
             // Check socket
-            let path = socket_path();
-            println!("Socket: {:?}", path);
-            if path.exists() {
-                println!("  Status: ✓ exists");
-            } else {
-                println!("  Status: ✗ not found");
+            #[cfg(unix)]
+            {
+                let path = socket_path();
+                println!("Socket: {:?}", path);
+                if path.exists() {
+                    println!("  Status: ✓ exists");
+                } else {
+                    println!("  Status: ✗ not found");
+                }
+            }
+            #[cfg(windows)]
+            println!("Pipe: {}", diachron_core::pipe_name());
+            if let Some(addr) = tcp_addr() {
+                println!("TCP: {} (via {})", addr, ADDR_ENV_VAR);
             }
 
             // Get comprehensive diagnostics from daemon
             println!("\nDaemon:");
-            let msg = IpcMessage::DoctorInfo;
+            let capabilities = handshake::daemon_capabilities();
+            let msg = capabilities.diagnostics_request();
             match send_message(&msg) {
                 Ok(IpcResponse::Doctor(info)) => {
                     println!("  Status: ✓ running");
-                    println!("  Uptime: {}s", info.uptime_secs);
-                    println!("  Memory: {:.1} MB (RSS)", info.memory_rss_bytes as f64 / 1024.0 / 1024.0);
-
-                    println!("\nDatabase:");
-                    println!("  Events: {}", info.events_count);
+                    if let Some(version) = &capabilities.daemon_version {
+                        println!(
+                            "  Version: {} (protocol {})",
+                            version, capabilities.protocol_version
+                        );
+                        if capabilities.protocol_version != PROTOCOL_VERSION {
+                            println!(
+                                "  ⚠️  CLI speaks protocol {}; restart the daemon after upgrading",
+                                PROTOCOL_VERSION
+                            );
+                        }
+                    }
+                    println!("  Uptime: {}s", info.uptime_secs);
+                    println!("  Memory: {:.1} MB (RSS)", info.memory_rss_bytes as f64 / 1024.0 / 1024.0);
+
+                    println!("\nDatabase:");
+                    println!("  Events: {}", info.events_count);
                     println!("  Exchanges: {}", info.exchanges_count);
                     println!("  Size: {:.1} MB", info.database_size_bytes as f64 / 1024.0 / 1024.0);
 
@@ -1480,6 +3012,14 @@ fn main() -> Result<()> {
                         info.exchanges_index_count,
                         info.exchanges_index_size_bytes as f64 / 1024.0 / 1024.0
                     );
+                    for (name, hnsw) in [
+                        ("Events", &info.events_index_hnsw),
+                        ("Exchanges", &info.exchanges_index_hnsw),
+                    ] {
+                        println!("  {} HNSW: M={}, ef_construction={}, ef_search={}",
+                            name, hnsw.connectivity, hnsw.expansion_add, hnsw.expansion_search
+                        );
+                    }
 
                     println!("\nEmbedding Model:");
                     if info.model_loaded {
@@ -1487,11 +3027,53 @@ fn main() -> Result<()> {
                     } else {
                         println!("  Status: ✗ not loaded");
                     }
+                    if info.embedding_pool_size > 0 {
+                        println!(
+                            "  Engines: {} (parallel inference)",
+                            info.embedding_pool_size
+                        );
+                    }
                     if info.model_size_bytes > 0 {
                         println!("  Size: {:.1} MB", info.model_size_bytes as f64 / 1024.0 / 1024.0);
                     } else {
                         println!("  Size: not found (run search to trigger download)");
                     }
+                    let lookups = info.embedding_cache_hits + info.embedding_cache_misses;
+                    if lookups > 0 {
+                        println!("  Cache: {} hits / {} misses ({:.0}% hit rate)",
+                            info.embedding_cache_hits,
+                            info.embedding_cache_misses,
+                            info.embedding_cache_hits as f64 / lookups as f64 * 100.0
+                        );
+                    }
+
+                    if let Ok(IpcResponse::Health {
+                        ready,
+                        model_loaded,
+                        model_warm,
+                        events_index_ok,
+                        exchanges_index_ok,
+                        warnings,
+                    }) = send_message(&IpcMessage::Health)
+                    {
+                        println!("\nReadiness:");
+                        if ready {
+                            println!("  Status: ✓ ready");
+                        } else {
+                            println!("  Status: ✗ not ready");
+                        }
+                        if model_loaded {
+                            println!("  Engines: {}", if model_warm { "warm" } else { "cold" });
+                        }
+                        println!(
+                            "  Indexes: events {}, exchanges {}",
+                            if events_index_ok { "✓" } else { "✗" },
+                            if exchanges_index_ok { "✓" } else { "✗" }
+                        );
+                        for warning in warnings {
+                            println!("  ⚠️  {}", warning);
+                        }
+                    }
                 }
                 Ok(IpcResponse::Pong { uptime_secs, events_count }) => {
                     // Fallback if daemon doesn't support DoctorInfo yet
@@ -1530,6 +3112,8 @@ fn main() -> Result<()> {
                 }
             }
 
+            run_doctor_checks(fix, yes);
+
             println!("\n--- End Diagnostics ---");
         }
 
@@ -1676,77 +3260,283 @@ enabled = true
             }
         }
 
-        Commands::Verify => {
-            println!("Diachron Hash-Chain Verification");
-            println!("=================================\n");
+        Commands::Verify {
+            since_checkpoint,
+            format,
+        } => {
+            let json = format == "json";
+            if !json {
+                println!("Diachron Hash-Chain Verification");
+                println!("=================================\n");
+            }
 
             // Open database directly for read-only verification
-            let db_path = dirs::home_dir()
-                .map(|h| h.join(".diachron/diachron.db"))
-                .context("Could not determine home directory")?;
+            let conn = open_db_readonly()?;
+
+            let checkpoints = diachron_core::load_checkpoints(&conn).unwrap_or_default();
+            // A checkpoint that doesn't record its last event can't anchor
+            // an incremental walk
+            let latest = checkpoints.last().filter(|_| since_checkpoint);
+            let anchor = latest.filter(|c| c.last_event_id.is_some());
+            let verification = match anchor {
+                Some(checkpoint) => {
+                    if !json {
+                        println!(
+                            "Incremental: trusting checkpoint #{} ({} events)\n",
+                            checkpoint.id, checkpoint.event_count
+                        );
+                    }
+                    diachron_core::verify_chain_since(&conn, checkpoint)
+                }
+                None => {
+                    if !json {
+                        match latest {
+                            Some(checkpoint) => println!(
+                                "Checkpoint #{} doesn't record its last event; verifying full chain\n",
+                                checkpoint.id
+                            ),
+                            None if since_checkpoint => {
+                                println!("No checkpoint found; verifying full chain\n")
+                            }
+                            None => {}
+                        }
+                    }
+                    verify_chain(&conn)
+                }
+            };
 
-            if !db_path.exists() {
-                eprintln!("Database not found: {:?}", db_path);
-                eprintln!("Hint: Run 'diachron daemon start' to initialize");
-                std::process::exit(1);
-            }
+            match verification {
+                Ok(result) => {
+                    // Checkpoint signatures (optional; unsigned checkpoints still verify)
+                    let key_path = diachron_core::default_signing_key_path();
+                    let public_key = diachron_core::load_verifying_key(
+                        &diachron_core::verifying_key_path(&key_path),
+                    )
+                    .ok()
+                    .flatten()
+                    .or_else(|| {
+                        diachron_core::load_signing_key(&key_path)
+                            .ok()
+                            .flatten()
+                            .map(|k| k.verifying_key())
+                    });
+                    let mut invalid_signatures = 0;
+                    let mut latest_status = "absent";
+                    for checkpoint in &checkpoints {
+                        latest_status = match (&checkpoint.signature, &public_key) {
+                            (None, _) => "absent",
+                            (Some(_), None) => "unverified (no public key)",
+                            (Some(_), Some(key)) => {
+                                if diachron_core::verify_checkpoint_signature(checkpoint, key) {
+                                    "valid"
+                                } else {
+                                    invalid_signatures += 1;
+                                    "invalid"
+                                }
+                            }
+                        };
+                    }
 
-            let conn = rusqlite::Connection::open_with_flags(
-                &db_path,
-                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
-            )
-            .context("Failed to open database")?;
+                    if json {
+                        let mut report = serde_json::to_value(&result)?;
+                        report["since_checkpoint"] = serde_json::json!(anchor.map(|c| c.id));
+                        report["signature"] =
+                            serde_json::json!((!checkpoints.is_empty()).then_some(latest_status));
+                        report["invalid_signatures"] = serde_json::json!(invalid_signatures);
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        print_chain_verification(&result);
+                        if !checkpoints.is_empty() {
+                            println!("   Signature: {}", latest_status);
+                            if invalid_signatures > 0 {
+                                println!(
+                                    "\n⚠️ {} checkpoint signature(s) invalid",
+                                    invalid_signatures
+                                );
+                            }
+                        }
+                    }
 
-            match verify_chain(&conn) {
-                Ok(result) => {
-                    if result.valid {
-                        println!("✅ Chain integrity verified");
+                    if !result.valid || invalid_signatures > 0 {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    if json {
+                        println!("{}", serde_json::json!({ "error": e.to_string() }));
                     } else {
-                        println!("❌ Chain integrity FAILED");
+                        eprintln!("Verification failed: {}", e);
                     }
+                    std::process::exit(1);
+                }
+            }
+        }
 
-                    println!("   Events checked: {}", result.events_checked);
-                    println!("   Checkpoints: {}", result.checkpoints_checked);
+        Commands::Keygen { force } => {
+            let key_path = diachron_core::default_signing_key_path();
+            if key_path.exists() && !force {
+                eprintln!("Signing key already exists: {:?}", key_path);
+                eprintln!("Use --force to replace it (old checkpoints will no longer verify)");
+                std::process::exit(1);
+            }
+
+            let key = diachron_core::generate_signing_key();
+            diachron_core::save_signing_key(&key_path, &key)
+                .context("Failed to write signing key")?;
+
+            println!("🔑 Signing key written to {:?}", key_path);
+            println!(
+                "   Public key: {:?}",
+                diachron_core::verifying_key_path(&key_path)
+            );
+            println!("   New checkpoints will be signed; share the public key with verifiers");
+        }
+
+        Commands::Export { output, since } => {
+            let conn = open_db_readonly()?;
+            let since = since.as_deref().map(|s| parse_time_range(s).0);
+
+            let file = std::fs::File::create(&output)
+                .with_context(|| format!("Failed to create {:?}", output))?;
+            let counts =
+                diachron_core::write_export(&conn, since.as_deref(), std::io::BufWriter::new(file))
+                    .context("Export failed")?;
 
-                    if let Some(ref first) = result.first_event {
-                        println!("   First event: {}", first);
+            println!(
+                "📦 Exported {} events and {} exchanges to {:?}",
+                counts.events, counts.exchanges, output
+            );
+        }
+
+        Commands::Import { input, verify } => {
+            let file = std::fs::File::open(&input)
+                .with_context(|| format!("Failed to open {:?}", input))?;
+            let doc: diachron_core::ExportDocument =
+                serde_json::from_reader(std::io::BufReader::new(file))
+                    .context("Not a diachron export file")?;
+            if doc.version > diachron_core::EXPORT_FORMAT_VERSION {
+                anyhow::bail!(
+                    "Export format v{} is newer than this diachron supports (v{})",
+                    doc.version,
+                    diachron_core::EXPORT_FORMAT_VERSION
+                );
+            }
+
+            let mut events = doc.events;
+            events.sort_by_key(|e| e.id);
+
+            let mut totals = diachron_core::ImportCounts::default();
+            let batches = events
+                .chunks(IMPORT_BATCH_SIZE)
+                .map(|chunk| (chunk.to_vec(), Vec::new()))
+                .chain(
+                    doc.exchanges
+                        .chunks(IMPORT_BATCH_SIZE)
+                        .map(|chunk| (Vec::new(), chunk.to_vec())),
+                );
+            for (events, exchanges) in batches {
+                let msg = IpcMessage::ImportBatch { events, exchanges };
+                match send_message(&msg).context("Failed to connect to daemon")? {
+                    IpcResponse::ImportStats {
+                        events_imported,
+                        events_skipped,
+                        exchanges_imported,
+                        exchanges_skipped,
+                    } => {
+                        totals.events_imported += events_imported;
+                        totals.events_skipped += events_skipped;
+                        totals.exchanges_imported += exchanges_imported;
+                        totals.exchanges_skipped += exchanges_skipped;
                     }
-                    if let Some(ref last) = result.last_event {
-                        println!("   Last event: {}", last);
+                    IpcResponse::Error(e) => {
+                        print_daemon_error(&e);
+                        std::process::exit(1);
                     }
-                    if let Some(ref root) = result.chain_root {
-                        println!("   Chain root: {}...", &root[..8.min(root.len())]);
+                    _ => {
+                        eprintln!("Unexpected response");
+                        std::process::exit(1);
                     }
+                }
+            }
 
-                    if let Some(ref bp) = result.break_point {
-                        println!("\n⚠️ Break detected at event #{}", bp.event_id);
-                        println!("   Timestamp: {}", bp.timestamp);
-                        println!("   Expected hash: {}...", &bp.expected_hash[..16]);
-                        println!("   Actual hash: {}...", &bp.actual_hash[..16]);
-                        println!("\n   Recommendation: Restore from backup or contact support");
-                    }
+            println!("📥 Import complete:");
+            println!(
+                "   Events: {} imported, {} already present",
+                totals.events_imported, totals.events_skipped
+            );
+            println!(
+                "   Exchanges: {} imported, {} already present",
+                totals.exchanges_imported, totals.exchanges_skipped
+            );
+            if totals.events_imported + totals.exchanges_imported > 0 {
+                println!(
+                    "   Run 'diachron memory reindex --force' to make them searchable by meaning"
+                );
+            }
 
-                    if !result.valid {
-                        std::process::exit(1);
+            if verify {
+                let conn = open_db_readonly()?;
+                let result = verify_chain(&conn).context("Verification failed")?;
+                if result.valid {
+                    println!(
+                        "✅ Chain integrity verified ({} events)",
+                        result.events_checked
+                    );
+                } else {
+                    println!("❌ Chain integrity FAILED");
+                    if let Some(bp) = result.break_point {
+                        println!("   Break at event {} ({})", bp.event_id, bp.timestamp);
                     }
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Commands::Maintenance { check: true, .. } => {
+            println!("🔎 Checking database integrity...\n");
+
+            match send_message(&IpcMessage::Integrity) {
+                Ok(IpcResponse::IntegrityReport(report)) => print_integrity_report(&report),
+                Ok(IpcResponse::Error(e)) => {
+                    eprintln!("❌ Integrity check failed: {}", e);
+                    std::process::exit(1);
+                }
+                Ok(_) => {
+                    eprintln!("❌ Unexpected response from daemon");
+                    std::process::exit(1);
                 }
                 Err(e) => {
-                    eprintln!("Verification failed: {}", e);
+                    eprintln!("❌ Failed to connect to daemon: {}", e);
+                    eprintln!("   Hint: Start the daemon with 'diachron daemon start'");
                     std::process::exit(1);
                 }
             }
         }
 
-        Commands::Maintenance { retention_days } => {
+        Commands::Maintenance {
+            retention_days,
+            events_retention_days,
+            exchanges_retention_days,
+            project_retention,
+            ..
+        } => {
             println!("🔧 Running database maintenance...\n");
 
-            let msg = IpcMessage::Maintenance { retention_days };
+            let msg = IpcMessage::Maintenance {
+                retention_days,
+                events_retention_days,
+                exchanges_retention_days,
+                project_retention_days: project_retention.into_iter().collect(),
+            };
             match send_message(&msg) {
                 Ok(IpcResponse::MaintenanceStats {
                     size_before,
                     size_after,
                     events_pruned,
                     exchanges_pruned,
+                    events_retention_days,
+                    exchanges_retention_days,
+                    project_overrides,
                     duration_ms,
                 }) => {
                     let reduction_pct = if size_before > 0 {
@@ -1763,17 +3553,38 @@ enabled = true
                     );
                     println!("  ├─ ANALYZE: Updated query planner stats");
 
-                    if retention_days > 0 {
+                    if events_retention_days == 0
+                        && exchanges_retention_days == 0
+                        && project_overrides == 0
+                    {
+                        println!("  └─ Pruning: disabled (use --retention-days to enable)");
+                    } else {
+                        let retention = |days: u32| match days {
+                            0 => "kept forever".to_string(),
+                            days => format!("retention: {} days", days),
+                        };
                         println!(
-                            "  ├─ Old events: {} pruned (retention: {} days)",
-                            events_pruned, retention_days
+                            "  ├─ Old events: {} pruned ({})",
+                            events_pruned,
+                            retention(events_retention_days)
                         );
+                        let branch = if project_overrides > 0 {
+                            "├─"
+                        } else {
+                            "└─"
+                        };
                         println!(
-                            "  └─ Old exchanges: {} pruned (retention: {} days)",
-                            exchanges_pruned, retention_days
+                            "  {} Old exchanges: {} pruned ({})",
+                            branch,
+                            exchanges_pruned,
+                            retention(exchanges_retention_days)
                         );
-                    } else {
-                        println!("  └─ Pruning: disabled (use --retention-days to enable)");
+                        if project_overrides > 0 {
+                            println!(
+                                "  └─ Project overrides: {} project(s) with their own retention",
+                                project_overrides
+                            );
+                        }
                     }
 
                     println!("\n✅ Maintenance complete (took {:.1}s)", duration_ms as f64 / 1000.0);
@@ -1797,11 +3608,32 @@ enabled = true
         Commands::ExportEvidence {
             output,
             pr,
+            host,
             branch,
             since,
+            format,
+            output_template,
+            time_window,
+            require_file_match,
+            intent,
         } => {
+            if !matches!(format.as_str(), "json" | "sarif" | "html" | "markdown") {
+                eprintln!(
+                    "Unknown format '{}'. Use: json, sarif, html, markdown",
+                    format
+                );
+                std::process::exit(1);
+            }
+            if output_template.is_some() && format != "markdown" {
+                eprintln!("--output-template only applies to --format markdown");
+                std::process::exit(1);
+            }
+            let template = read_output_template(output_template.as_deref())?;
+
             println!("Exporting evidence pack...\n");
 
+            let vcs = vcs_host::select_host(host.as_deref())?;
+
             // Get current branch if not specified
             let branch_name = branch.unwrap_or_else(|| {
                 std::process::Command::new("git")
@@ -1813,24 +3645,18 @@ enabled = true
                     .unwrap_or_else(|| "unknown".to_string())
             });
 
-            // Get PR number from branch if not specified
-            let pr_id = pr.unwrap_or_else(|| {
-                // Try to get PR number from gh CLI
-                std::process::Command::new("gh")
-                    .args(["pr", "view", "--json", "number", "-q", ".number"])
-                    .output()
-                    .ok()
-                    .and_then(|o| String::from_utf8(o.stdout).ok())
-                    .and_then(|s| s.trim().parse().ok())
-                    .unwrap_or(0)
-            });
+            // Get PR/MR number from the host CLI if not specified
+            let pr_id = pr.or_else(|| vcs.current_change_number()).unwrap_or(0);
 
             if pr_id == 0 {
-                eprintln!("Could not determine PR number. Use --pr flag.");
+                eprintln!(
+                    "Could not determine {} PR/MR number. Use --pr flag.",
+                    vcs.name()
+                );
                 std::process::exit(1);
             }
 
-            println!("PR: #{}", pr_id);
+            println!("{}: {}", vcs.name(), vcs.change_ref(pr_id));
             println!("Branch: {}", branch_name);
             println!("Since: {}", since);
 
@@ -1868,6 +3694,21 @@ enabled = true
 
             println!("Time range: {} to {}", start_time, end_time);
 
+            // Changed files per commit, used to break ties between nearby commits
+            let commit_files: std::collections::HashMap<String, Vec<String>> = commits
+                .iter()
+                .map(|sha| {
+                    let files = std::process::Command::new("git")
+                        .args(["diff-tree", "--no-commit-id", "--name-only", "-r", sha])
+                        .output()
+                        .ok()
+                        .and_then(|o| String::from_utf8(o.stdout).ok())
+                        .map(|s| s.lines().map(|l| l.to_string()).collect())
+                        .unwrap_or_default();
+                    (sha.clone(), files)
+                })
+                .collect();
+
             // Send correlation request to daemon
             let msg = IpcMessage::CorrelateEvidence {
                 pr_id,
@@ -1875,14 +3716,31 @@ enabled = true
                 branch: branch_name.clone(),
                 start_time,
                 end_time,
-                intent: None, // TODO: Extract from recent conversation
+                intent,
+                time_window_secs: time_window,
+                require_file_match,
+                commit_files,
             };
 
             match send_message(&msg) {
                 Ok(IpcResponse::EvidenceResult(result)) => {
                     // Write evidence pack to file
-                    let json = serde_json::to_string_pretty(&result)
-                        .context("Failed to serialize evidence pack")?;
+                    let json = match format.as_str() {
+                        "sarif" => {
+                            let pack = diachron_core::EvidencePack::from(result.clone());
+                            serde_json::to_string_pretty(&diachron_core::export_sarif(&pack))
+                        }
+                        "html" => Ok(diachron_core::render_html(
+                            &diachron_core::EvidencePack::from(result.clone()),
+                        )),
+                        "markdown" => Ok(diachron_core::render_markdown_narrative(
+                            &diachron_core::EvidencePack::from(result.clone()),
+                            &vcs.change_ref(pr_id),
+                            template.as_deref(),
+                        )?),
+                        _ => serde_json::to_string_pretty(&result),
+                    }
+                    .context("Failed to serialize evidence pack")?;
 
                     std::fs::write(&output, &json)
                         .context("Failed to write evidence pack")?;
@@ -1921,252 +3779,112 @@ enabled = true
             }
         }
 
-        Commands::PrComment { pr, evidence } => {
+        Commands::PrComment {
+            pr,
+            evidence,
+            host,
+            output_template,
+        } => {
             println!("Posting PR narrative comment...\n");
 
+            let vcs = vcs_host::select_host(host.as_deref())?;
+            let template = read_output_template(output_template.as_deref())?;
+
             // Read evidence pack
             let evidence_content = std::fs::read_to_string(&evidence)
                 .context("Failed to read evidence file")?;
 
-            let pack: serde_json::Value = serde_json::from_str(&evidence_content)
-                .context("Failed to parse evidence JSON")?;
+            let result: diachron_core::EvidencePackResult =
+                serde_json::from_str(&evidence_content).context("Failed to parse evidence JSON")?;
+            let pack = diachron_core::EvidencePack::from(result);
 
             // Build markdown narrative
-            let mut md = String::new();
-
-            // Header
-            md.push_str(&format!(
-                "## PR #{}: AI Provenance Evidence\n\n",
-                pack["pr_id"].as_u64().unwrap_or(pr)
-            ));
-
-            // Intent section (if available)
-            if let Some(intent) = pack["intent"].as_str() {
-                if !intent.is_empty() {
-                    md.push_str("### Intent\n");
-                    md.push_str(&format!("> {}\n\n", intent));
-                }
-            }
-
-            // Summary section
-            md.push_str("### What Changed\n");
-            md.push_str(&format!(
-                "- **Files modified**: {}\n",
-                pack["summary"]["files_changed"].as_u64().unwrap_or(0)
-            ));
-            md.push_str(&format!(
-                "- **Lines**: +{} / -{}\n",
-                pack["summary"]["lines_added"].as_u64().unwrap_or(0),
-                pack["summary"]["lines_removed"].as_u64().unwrap_or(0)
-            ));
-            md.push_str(&format!(
-                "- **Tool operations**: {}\n",
-                pack["summary"]["tool_operations"].as_u64().unwrap_or(0)
-            ));
-            md.push_str(&format!(
-                "- **Sessions**: {}\n\n",
-                pack["summary"]["sessions"].as_u64().unwrap_or(0)
-            ));
-
-            // Evidence trail section
-            md.push_str("### Evidence Trail\n");
-            let coverage = pack["coverage_pct"].as_f64().unwrap_or(0.0);
-            let unmatched = pack["unmatched_count"].as_u64().unwrap_or(0);
-            md.push_str(&format!("- **Coverage**: {:.1}% of events matched to commits", coverage));
-            if unmatched > 0 {
-                md.push_str(&format!(" ({} unmatched)", unmatched));
-            }
-            md.push_str("\n");
-
-            // List commits with their events
-            if let Some(commits) = pack["commits"].as_array() {
-                for commit in commits {
-                    let sha = commit["sha"].as_str().unwrap_or("");
-                    let sha_short = &sha[..7.min(sha.len())];
-                    let confidence = commit["confidence"].as_str().unwrap_or("LOW");
-
-                    md.push_str(&format!("\n**Commit `{}`**", sha_short));
-                    if let Some(msg) = commit["message"].as_str() {
-                        let first_line = msg.lines().next().unwrap_or(msg);
-                        md.push_str(&format!(": {}", first_line));
-                    }
-                    md.push_str(&format!(" ({})\n", confidence));
-
-                    if let Some(events) = commit["events"].as_array() {
-                        for event in events.iter().take(5) {
-                            let tool = event["tool_name"].as_str().unwrap_or("-");
-                            let file = event["file_path"].as_str().unwrap_or("-");
-                            let op = event["operation"].as_str().unwrap_or("-");
-                            md.push_str(&format!("  - `{}` {} → {}\n", tool, op, file));
-                        }
-                        if events.len() > 5 {
-                            md.push_str(&format!("  - *...and {} more*\n", events.len() - 5));
-                        }
-                    }
+            let md = diachron_core::render_markdown_narrative(
+                &pack,
+                &vcs.change_ref(pack.pr_id),
+                template.as_deref(),
+            )?;
+
+            // Post via the host's CLI (gh or glab)
+            match vcs.post_comment(pr, &md) {
+                Ok(()) => {
+                    println!("✅ {} comment posted successfully", vcs.change_ref(pr));
+                    println!("\nPosted content:\n{}", md);
+                }
+                Err(e) => {
+                    eprintln!("Failed to post {} comment ({})", vcs.change_ref(pr), e);
+                    std::process::exit(1);
                 }
-            }
-            md.push_str("\n");
-
-            // Verification section
-            md.push_str("### Verification\n");
-            md.push_str(&format!(
-                "- [{}] Hash chain integrity\n",
-                if pack["verification"]["chain_verified"].as_bool().unwrap_or(false) { "x" } else { " " }
-            ));
-            md.push_str(&format!(
-                "- [{}] Tests executed after changes\n",
-                if pack["verification"]["tests_executed"].as_bool().unwrap_or(false) { "x" } else { " " }
-            ));
-            md.push_str(&format!(
-                "- [{}] Build succeeded\n",
-                if pack["verification"]["build_succeeded"].as_bool().unwrap_or(false) { "x" } else { " " }
-            ));
-            md.push_str(&format!(
-                "- [{}] Human review\n\n",
-                if pack["verification"]["human_reviewed"].as_bool().unwrap_or(false) { "x" } else { " " }
-            ));
-
-            // Footer
-            md.push_str(&format!(
-                "---\n*Generated by [Diachron](https://github.com/wolfiesch/diachron) v{} at {}*\n",
-                pack["diachron_version"].as_str().unwrap_or(env!("CARGO_PKG_VERSION")),
-                pack["generated_at"].as_str().unwrap_or("unknown")
-            ));
-
-            // Post via gh CLI
-            let status = std::process::Command::new("gh")
-                .args(["pr", "comment", &pr.to_string(), "-b", &md])
-                .status()
-                .context("Failed to run gh CLI")?;
-
-            if status.success() {
-                println!("✅ PR comment posted successfully");
-                println!("\nPosted content:\n{}", md);
-            } else {
-                eprintln!("Failed to post PR comment (gh exit code: {:?})", status.code());
-                std::process::exit(1);
             }
         }
 
-        Commands::Blame { target, format, mode } => {
-            // Parse file:line
-            let parts: Vec<&str> = target.rsplitn(2, ':').collect();
-            if parts.len() != 2 {
-                eprintln!("Invalid target format. Use: file:line (e.g., src/auth.rs:42)");
+        Commands::Blame {
+            target,
+            format,
+            mode,
+            context,
+        } => {
+            // Parse file:line or file:start-end
+            let Some((file, start, end)) = parse_blame_target(&target) else {
+                eprintln!(
+                    "Invalid target format. Use: file:line or file:start-end (e.g., src/auth.rs:42)"
+                );
                 std::process::exit(1);
-            }
-
-            let line: u32 = parts[0].parse().context("Invalid line number")?;
-            let file = parts[1];
-
-            // Read file content to get the line and context
-            let file_path = std::path::Path::new(file);
-            let (content, context) = if file_path.exists() {
-                let file_content = std::fs::read_to_string(file_path)
-                    .unwrap_or_default();
-                let lines: Vec<&str> = file_content.lines().collect();
-
-                // Get the target line (1-indexed)
-                let line_idx = (line as usize).saturating_sub(1);
-                let target_line = lines.get(line_idx).unwrap_or(&"").to_string();
-
-                // Get context (±5 lines)
-                let start = line_idx.saturating_sub(5);
-                let end = (line_idx + 6).min(lines.len());
-                let context_lines: String = lines[start..end].join("\n");
-
-                (target_line, context_lines)
+            };
+            let label = if start == end {
+                start.to_string()
             } else {
-                // File doesn't exist locally, use empty placeholders
-                (String::new(), String::new())
+                format!("{}-{}", start, end)
             };
 
+            let (lines, lines_start) = read_blame_lines(file, start, end);
+
             // Use fingerprint-based blame via daemon
-            let msg = IpcMessage::BlameByFingerprint {
+            let msg = IpcMessage::BlameRange {
                 file_path: file.to_string(),
-                line_number: line,
-                content,
-                context,
+                start,
+                end,
+                lines,
+                lines_start,
                 mode: mode.clone(),
+                session_context: context,
             };
 
             match send_message(&msg) {
-                Ok(IpcResponse::BlameResult(blame_match)) => {
-                    let event = &blame_match.event;
-
-                    if format == "json" {
-                        let result = serde_json::json!({
-                            "file": file,
-                            "line": line,
-                            "event_id": event.id,
-                            "timestamp": event.timestamp,
-                            "tool_name": event.tool_name,
-                            "operation": event.operation,
-                            "session_id": event.session_id,
-                            "diff_summary": event.diff_summary,
-                            "confidence": blame_match.confidence.to_uppercase(),
-                            "match_type": blame_match.match_type,
-                            "similarity": blame_match.similarity,
-                            "intent": blame_match.intent
-                        });
-                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                Ok(IpcResponse::BlameRangeResult(runs)) => {
+                    let single = match runs.as_slice() {
+                        [run] if start == end => run.blame.as_ref(),
+                        _ => None,
+                    };
+                    if let Some(blame_match) = single {
+                        print_blame_match(file, start, blame_match, &format);
                     } else {
-                        println!("Diachron Blame");
-                        println!("==============\n");
-                        println!("File: {}:{}", file, line);
-
-                        let confidence_emoji = match blame_match.confidence.as_str() {
-                            "high" => "🎯",
-                            "medium" => "📊",
-                            "low" => "⚠️",
-                            _ => "❓",
-                        };
-
-                        println!(
-                            "\n{} Confidence: {} ({})",
-                            confidence_emoji,
-                            blame_match.confidence.to_uppercase(),
-                            blame_match.match_type
-                        );
-                        println!(
-                            "📍 Source: Claude Code (Session {})",
-                            event.session_id.as_deref().unwrap_or("unknown")
-                        );
-                        println!(
-                            "⏰ When: {}",
-                            event.timestamp_display.as_deref().unwrap_or(&event.timestamp)
-                        );
-                        println!(
-                            "🔧 Tool: {} ({})",
-                            event.tool_name,
-                            event.operation.as_deref().unwrap_or("-")
-                        );
-                        if let Some(ref diff) = event.diff_summary {
-                            println!("📝 Changes: {}", diff);
-                        }
-                        if let Some(ref intent) = blame_match.intent {
-                            println!("💬 Intent: \"{}\"", intent);
-                        }
+                        print_blame_runs(file, &label, &runs, &format);
                     }
                 }
                 Ok(IpcResponse::BlameNotFound { reason }) => {
                     if format == "json" {
-                        let result = serde_json::json!({
+                        let mut result = serde_json::json!({
                             "file": file,
-                            "line": line,
                             "error": "not_found",
                             "reason": reason
                         });
+                        if start == end {
+                            result["line"] = serde_json::json!(start);
+                        } else {
+                            result["start_line"] = serde_json::json!(start);
+                            result["end_line"] = serde_json::json!(end);
+                        }
                         println!("{}", serde_json::to_string_pretty(&result).unwrap());
                     } else {
                         println!("Diachron Blame");
                         println!("==============\n");
-                        println!("File: {}:{}", file, line);
+                        println!("File: {}:{}", file, label);
                         println!("\n⚠️ {}", reason);
                     }
                 }
                 Ok(IpcResponse::Error(e)) => {
-                    eprintln!("Error: {}", e);
+                    print_daemon_error(&e);
                     std::process::exit(1);
                 }
                 Ok(_) => {
@@ -2185,283 +3903,845 @@ enabled = true
     Ok(())
 }
 
-/// Parse a string value into appropriate TOML type
-fn parse_toml_value(s: &str) -> toml::Value {
-    // Try boolean
-    if s == "true" {
-        return toml::Value::Boolean(true);
+/// Read a blame range plus ±5 lines of context (a single line is a 1-line range).
+///
+/// Returns the lines and the 1-based line number of the first one; the
+/// lines are empty if the file doesn't exist locally.
+fn read_blame_lines(file: &str, start: u32, end: u32) -> (Vec<String>, u32) {
+    let lines_start = start.saturating_sub(5).max(1);
+    let lines = std::fs::read_to_string(file)
+        .map(|content| {
+            content
+                .lines()
+                .skip(lines_start as usize - 1)
+                .take((end - lines_start) as usize + 6)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    (lines, lines_start)
+}
+
+/// Open the global database read-only, exiting if it has not been created.
+///
+/// Waits up to [`diachron_core::BUSY_TIMEOUT`] for the daemon's writes.
+fn open_db_readonly() -> Result<rusqlite::Connection> {
+    let db_path = dirs::home_dir()
+        .map(|h| h.join(".diachron/diachron.db"))
+        .context("Could not determine home directory")?;
+
+    if !db_path.exists() {
+        eprintln!("Database not found: {:?}", db_path);
+        eprintln!("Hint: Run 'diachron daemon start' to initialize");
+        std::process::exit(1);
     }
-    if s == "false" {
-        return toml::Value::Boolean(false);
+
+    let conn =
+        rusqlite::Connection::open_with_flags(&db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open database")?;
+    conn.busy_timeout(diachron_core::BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+/// Parse a blame target of the form `file:line` or `file:start-end`.
+fn parse_blame_target(target: &str) -> Option<(&str, u32, u32)> {
+    let (file, range) = target.rsplit_once(':')?;
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let line = range.parse().ok()?;
+            (line, line)
+        }
+    };
+    if file.is_empty() || start == 0 || end < start {
+        return None;
     }
-    // Try integer
-    if let Ok(n) = s.parse::<i64>() {
-        return toml::Value::Integer(n);
+    Some((file, start, end))
+}
+
+/// Icon for an event's operation, or a cross for a failed tool call.
+fn operation_icon(event: &diachron_core::StoredEvent) -> &'static str {
+    if event.outcome() == Some(Outcome::Failure) {
+        return "❌";
     }
-    // Try float
-    if let Ok(f) = s.parse::<f64>() {
-        return toml::Value::Float(f);
+    match event.operation {
+        Some(Operation::Create) => "✨",
+        Some(Operation::Modify) => "📝",
+        Some(Operation::Delete) => "🗑️",
+        Some(Operation::Commit) => "📦",
+        Some(Operation::Execute) => "⚡",
+        _ => "•",
     }
-    // Default to string
-    toml::Value::String(s.to_string())
 }
 
-/// Parse a time filter string into (start_time, end_time) ISO timestamps.
-///
-/// Supports formats:
-/// - "1h", "2d", "7d" - relative from now
-/// - "2024-01-01" - absolute date (assumes midnight)
-/// - ISO timestamp
-fn parse_time_range(since: &str) -> (String, String) {
-    use chrono::{Duration, NaiveDate, Utc};
+/// "❌ " prefix for events whose tool call failed, empty otherwise
+fn failure_marker(event: &diachron_core::StoredEvent) -> &'static str {
+    if event.outcome() == Some(Outcome::Failure) {
+        "❌ "
+    } else {
+        ""
+    }
+}
+
+/// Print a chain verification result: counts, range, and any break.
+fn print_chain_verification(result: &diachron_core::ChainVerificationResult) {
+    if result.valid {
+        println!("✅ Chain integrity verified");
+    } else {
+        println!("❌ Chain integrity FAILED");
+    }
 
-    let now = Utc::now();
-    let end_time = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+    println!("   Events checked: {}", result.events_checked);
+    println!("   Checkpoints: {}", result.checkpoints_checked);
 
-    // Try relative time (e.g., "1h", "7d")
-    if let Some(stripped) = since.strip_suffix('h') {
-        if let Ok(hours) = stripped.parse::<i64>() {
-            let start = now - Duration::hours(hours);
-            return (start.format("%Y-%m-%dT%H:%M:%S").to_string(), end_time);
+    if let Some(ref first) = result.first_event {
+        println!("   First event: {}", first);
+    }
+    if let Some(ref last) = result.last_event {
+        println!("   Last event: {}", last);
+    }
+    if let Some(ref root) = result.chain_root {
+        println!("   Chain root: {}...", &root[..8.min(root.len())]);
+    }
+
+    if let Some(ref bp) = result.break_point {
+        println!("\n⚠️ Break detected at event #{}", bp.event_id);
+        println!("   Timestamp: {}", bp.timestamp);
+        println!("   Expected hash: {}...", &bp.expected_hash[..16]);
+        println!("   Actual hash: {}...", &bp.actual_hash[..16]);
+        if !bp.diverging_fields.is_empty() {
+            println!("   Tampered field: {}", bp.diverging_fields.join(", "));
         }
+        println!("\n   Recommendation: Restore from backup or contact support");
+    }
+
+    if let Some(checkpoint_id) = result.merkle_mismatch {
+        println!("\n⚠️ Merkle root mismatch at checkpoint #{}", checkpoint_id);
+        println!("   Events covered by this checkpoint were altered or removed");
     }
+}
 
-    if let Some(stripped) = since.strip_suffix('d') {
-        if let Ok(days) = stripped.parse::<i64>() {
-            let start = now - Duration::days(days);
-            return (start.format("%Y-%m-%dT%H:%M:%S").to_string(), end_time);
+/// Print a session's time range, activity counts, intent, and files.
+fn print_session_summary(summary: &diachron_core::SessionSummary) {
+    println!("🗂️  Session {}", summary.session_id);
+    println!("   Time:     {} → {}", summary.started_at, summary.ended_at);
+    println!("   Events:   {}", summary.event_count);
+
+    let mut tools: Vec<_> = summary.tools.iter().collect();
+    tools.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let tools: Vec<String> = tools
+        .iter()
+        .map(|(tool, count)| format!("{} ×{}", tool, count))
+        .collect();
+    println!("   Tools:    {}", tools.join(", "));
+
+    if let Some(intent) = summary.intent.as_deref() {
+        println!("   Intent:   {}", intent);
+    }
+    if !summary.commits.is_empty() {
+        println!("   Commits:  {}", summary.commits.join(", "));
+    }
+    if !summary.files_touched.is_empty() {
+        println!("\n📁 Files ({})", summary.files_touched.len());
+        for file in &summary.files_touched {
+            println!("   {}", file);
         }
     }
+}
 
-    // Try date (e.g., "2024-01-01")
-    if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
-        let start = date.and_hms_opt(0, 0, 0).unwrap();
-        return (start.format("%Y-%m-%dT%H:%M:%S").to_string(), end_time);
+/// Print an integrity report with what to do about each problem.
+fn print_integrity_report(report: &diachron_core::IntegrityReport) {
+    for (name, table) in [("Events", &report.events), ("Exchanges", &report.exchanges)] {
+        println!("{} ({} rows)", name, table.rows);
+        println!("  ├─ Without embedding: {}", table.without_embedding);
+        println!(
+            "  ├─ Embedded but not indexed: {}",
+            table.missing_from_index
+        );
+        println!(
+            "  ├─ Index entries without a row: {}",
+            table.orphaned_vectors
+        );
+        println!(
+            "  └─ Full-text index: {} documents{}",
+            table.fts_rows,
+            if table.fts_stale() { " (stale)" } else { "" }
+        );
     }
 
-    // Try full ISO timestamp
-    if since.contains('T') {
-        return (since.to_string(), end_time);
+    println!("Hash chain");
+    println!(
+        "  ├─ Events without chain fields: {}",
+        report.events_without_hash
+    );
+    match &report.chain.break_point {
+        Some(bp) => println!("  └─ ❌ Broken at event {} ({})", bp.event_id, bp.timestamp),
+        None if !report.chain.valid => println!("  └─ ❌ Invalid (run 'diachron verify')"),
+        None => println!("  └─ ✅ Valid ({} events)", report.chain.events_checked),
     }
 
-    // Default: last 7 days
-    let start = now - Duration::days(7);
-    (start.format("%Y-%m-%dT%H:%M:%S").to_string(), end_time)
+    println!();
+    if report.is_clean() {
+        println!("✅ No problems found");
+        return;
+    }
+    if report.needs_reindex() {
+        println!("⚠️  Vector indexes are out of date: run 'diachron memory reindex --force'");
+    }
+    if report.events.fts_stale() || report.exchanges.fts_stale() {
+        println!("⚠️  Full-text search may miss rows or return deleted ones");
+    }
+    if !report.chain.valid {
+        println!("⚠️  The event log may have been modified: run 'diachron verify' for details");
+    }
 }
 
-/// Format search results for context injection at session start.
+/// Print an event's metadata, fingerprint, and full captured content.
 ///
-/// Produces token-conscious output:
-/// - Max 1500 tokens (~6000 chars)
-/// - Summarized snippets (first 200 chars each)
-/// - Formatted as markdown for Claude to parse
+/// Content is syntax-highlighted when stdout is a terminal and `NO_COLOR`
+/// is unset.
+fn print_event_detail(detail: &diachron_core::EventDetail) {
+    use std::io::IsTerminal;
+
+    let event = &detail.event;
+    println!(
+        "📄 Event {} — {}{} {}",
+        event.id,
+        failure_marker(event),
+        event.tool_name,
+        event.operation.map_or("unknown", |op| op.as_str())
+    );
+    println!(
+        "   Time:     {}",
+        event
+            .timestamp_display
+            .as_deref()
+            .unwrap_or(&event.timestamp)
+    );
+    if let Some(path) = event.file_path.as_deref() {
+        println!("   File:     {}", path);
+    }
+    if let Some(diff) = event.diff_summary.as_deref().filter(|d| !d.is_empty()) {
+        println!("   Summary:  {}", diff);
+    }
+    if let Some(sha) = event.git_commit_sha.as_deref() {
+        println!("   Commit:   {}", sha);
+    }
+    if let Some(session) = event.session_id.as_deref() {
+        println!("   Session:  {}", session);
+    }
+    if let Some(excerpt) = event.error_excerpt() {
+        println!("   Error:    {}", excerpt);
+    }
+    if let Some(hash) = detail.content_hash.as_deref() {
+        println!("   Content:  {}", hash);
+    }
+    if let Some(hash) = detail.context_hash.as_deref() {
+        println!("   Context:  {}", hash);
+    }
+    if let Some(dim) = detail.semantic_sig_dim {
+        println!("   Semantic: {} dimensions", dim);
+    }
+    println!();
+
+    let Some(content) = event.raw_input.as_deref().filter(|c| !c.is_empty()) else {
+        println!("(no content captured)");
+        return;
+    };
+
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let lang = if color {
+        highlight::language_for(event.file_path.as_deref())
+    } else {
+        None
+    };
+    let width = content.lines().count().max(1).to_string().len();
+    for (i, line) in content.lines().enumerate() {
+        let line = match lang {
+            Some(lang) => highlight::highlight_line(line, lang),
+            None => line.to_string(),
+        };
+        println!("{:>width$} │ {}", i + 1, line, width = width);
+    }
+}
+
+/// Print a unified diff that would undo an edit.
 ///
-/// T4 Quality Fixes (01/10/2026):
-/// - T4-1: Strip HTML tags (<b>, </b>)
-/// - T4-2: Clean line prefixes (N→)
-/// - T4-3: Filter tool wrappers ([Result:, Shell cwd)
-/// - T4-4: Deduplicate results
-/// - T4-5: Quality threshold (score > 5.0)
-fn format_context_output(results: &[diachron_core::SearchResult]) {
-    use std::collections::HashSet;
-
-    const MAX_CHARS: usize = 6000; // ~1500 tokens
-    const SNIPPET_MAX: usize = 200;
-    const MIN_SCORE: f32 = 5.0; // T4-5: Quality threshold
-
-    let mut output = String::new();
-    let mut char_count = 0;
-    let mut included_count = 0;
-    let mut seen_snippets: HashSet<String> = HashSet::new(); // T4-4: Deduplication
-
-    // Header
-    let header = "## Prior Context from This Project\n\n";
-    output.push_str(header);
-    char_count += header.len();
-
-    for result in results {
-        // T4-5: Skip low-quality results
-        if result.score < MIN_SCORE {
-            continue;
+/// When the edit's new text occurs exactly once in the file as it is now,
+/// the diff is against the whole file so its line numbers apply directly.
+/// Otherwise only the edit's own hunk is shown.
+fn print_revert_preview(detail: &diachron_core::EventDetail) {
+    use std::io::IsTerminal;
+
+    let event = &detail.event;
+    let (Some(old), Some(new)) = (detail.old_content.as_deref(), detail.new_content.as_deref())
+    else {
+        eprintln!("Event {} has no stored edit content.", event.id);
+        eprintln!(
+            "Enable it with: diachron config set capture.store_edit_content true (applies to later edits)"
+        );
+        std::process::exit(1);
+    };
+    let path = event.file_path.as_deref().unwrap_or("unknown");
+
+    let current = std::fs::read_to_string(path).ok();
+    let (before, after) = match current {
+        Some(current) if !new.is_empty() && current.matches(new).count() == 1 => {
+            let reverted = current.replacen(new, old, 1);
+            (current, reverted)
         }
+        _ => {
+            eprintln!(
+                "note: the edit's text is not in {} as it is now; showing the edit's hunk alone",
+                path
+            );
+            (new.to_string(), old.to_string())
+        }
+    };
+
+    let label = path.trim_start_matches('/');
+    let diff = similar::TextDiff::from_lines(&before, &after);
+    let patch = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", label), &format!("b/{}", label))
+        .to_string();
+    if patch.is_empty() {
+        println!("(reverting event {} changes nothing)", event.id);
+        return;
+    }
 
-        // T4-3: Skip results that are primarily tool output noise
-        if is_tool_noise(&result.snippet) {
-            continue;
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    for line in patch.lines() {
+        let paint = match line.as_bytes().first() {
+            _ if !color => None,
+            _ if line.starts_with("+++") || line.starts_with("---") => Some("\x1b[1m"),
+            Some(b'+') => Some("\x1b[32m"),
+            Some(b'-') => Some("\x1b[31m"),
+            Some(b'@') => Some("\x1b[36m"),
+            _ => None,
+        };
+        match paint {
+            Some(code) => println!("{}{}\x1b[0m", code, line),
+            None => println!("{}", line),
         }
+    }
+}
 
-        // Clean the snippet (T4-1, T4-2, T4-3)
-        let cleaned = clean_snippet(&result.snippet);
+fn confidence_emoji(confidence: BlameConfidence) -> &'static str {
+    match confidence {
+        BlameConfidence::High => "🎯",
+        BlameConfidence::Medium => "📊",
+        BlameConfidence::Low => "⚠️",
+        BlameConfidence::Inferred => "❓",
+    }
+}
 
-        // Skip if cleaned snippet is too short (likely all noise)
-        if cleaned.len() < 20 {
-            continue;
+/// Print a single-line blame result.
+fn print_blame_match(file: &str, line: u32, blame_match: &diachron_core::BlameMatch, format: &str) {
+    let event = &blame_match.event;
+
+    if format == "json" {
+        let result = serde_json::json!({
+            "file": file,
+            "line": line,
+            "event_id": event.id,
+            "timestamp": event.timestamp,
+            "tool_name": event.tool_name,
+            "operation": event.operation,
+            "session_id": event.session_id,
+            "diff_summary": event.diff_summary,
+            "confidence": blame_match.confidence.as_str().to_uppercase(),
+            "score": blame_match.score,
+            "reasons": blame_match.reasons,
+            "match_type": blame_match.match_type,
+            "similarity": blame_match.similarity,
+            "intent": blame_match.intent,
+            "context_events": blame_match.context_events
+        });
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else {
+        println!("Diachron Blame");
+        println!("==============\n");
+        println!("File: {}:{}", file, line);
+
+        println!(
+            "\n{} Confidence: {} ({})",
+            confidence_emoji(blame_match.confidence),
+            blame_match.confidence.as_str().to_uppercase(),
+            blame_match.match_type
+        );
+        if !blame_match.reasons.is_empty() {
+            println!(
+                "   Score {:.2}: {}",
+                blame_match.score,
+                blame_match.reasons.join(", ")
+            );
         }
-
-        // T4-4: Skip duplicates (check first 50 chars for similarity)
-        let dedup_key = safe_truncate(&cleaned, 50).to_lowercase();
-        if seen_snippets.contains(&dedup_key) {
-            continue;
+        println!(
+            "📍 Source: Claude Code (Session {})",
+            event.session_id.as_deref().unwrap_or("unknown")
+        );
+        println!(
+            "⏰ When: {}",
+            event
+                .timestamp_display
+                .as_deref()
+                .unwrap_or(&event.timestamp)
+        );
+        println!(
+            "🔧 Tool: {} ({})",
+            event.tool_name,
+            event.operation.map_or("-", |op| op.as_str())
+        );
+        if let Some(ref diff) = event.diff_summary {
+            println!("📝 Changes: {}", diff);
         }
-        seen_snippets.insert(dedup_key);
+        if let Some(ref intent) = blame_match.intent {
+            println!("💬 Intent: \"{}\"", intent);
+        }
+        if !blame_match.context_events.is_empty() {
+            println!("\n🧭 Surrounding activity:");
+            print_context_events(blame_match, "   ");
+        }
+    }
+}
 
-        // Format each result as a compact entry
-        let date = if result.timestamp.len() >= 10 {
-            &result.timestamp[..10] // YYYY-MM-DD
-        } else {
-            &result.timestamp
-        };
+/// Print a match's session neighbors with the match itself marked, one
+/// line each.
+fn print_context_events(blame: &diachron_core::BlameMatch, indent: &str) {
+    let matched = &blame.event;
+    let before = blame.context_events.iter().filter(|e| e.id < matched.id);
+    let after = blame.context_events.iter().filter(|e| e.id > matched.id);
+    for event in before.chain(std::iter::once(matched)).chain(after) {
+        let marker = if event.id == matched.id { "→" } else { " " };
+        let time = event
+            .timestamp
+            .get(11..19)
+            .unwrap_or(event.timestamp.as_str());
+        let target = event
+            .file_path
+            .as_deref()
+            .or(event.diff_summary.as_deref())
+            .unwrap_or("");
+        println!(
+            "{}{} #{} {} {} ({}) {}",
+            indent,
+            marker,
+            event.id,
+            time,
+            event.tool_name,
+            event.operation.map_or("-", |op| op.as_str()),
+            target
+        );
+    }
+}
 
-        let source_str = match result.source {
-            diachron_core::SearchSource::Event => "Code change",
-            diachron_core::SearchSource::Exchange => "Discussion",
-        };
+/// JSON form of range blame results, one object per run.
+fn blame_runs_json(file: &str, runs: &[diachron_core::BlameRun]) -> serde_json::Value {
+    runs.iter()
+        .map(|run| {
+            let blame = run.blame.as_ref();
+            serde_json::json!({
+                "file": file,
+                "start_line": run.start_line,
+                "end_line": run.end_line,
+                "event_id": blame.map(|b| b.event.id),
+                "timestamp": blame.map(|b| &b.event.timestamp),
+                "tool_name": blame.map(|b| &b.event.tool_name),
+                "session_id": blame.and_then(|b| b.event.session_id.as_ref()),
+                "confidence": blame.map(|b| b.confidence.as_str().to_uppercase()),
+                "score": blame.map(|b| b.score),
+                "reasons": blame.map(|b| &b.reasons),
+                "match_type": blame.map(|b| &b.match_type),
+                "similarity": blame.map(|b| b.similarity),
+                "intent": blame.and_then(|b| b.intent.as_ref()),
+                "context_events": blame.map(|b| &b.context_events)
+            })
+        })
+        .collect()
+}
 
-        // Truncate snippet safely (UTF-8 aware)
-        let snippet_final = safe_truncate(&cleaned, SNIPPET_MAX);
+/// Print range blame results, one entry per contiguous run of lines.
+fn print_blame_runs(file: &str, label: &str, runs: &[diachron_core::BlameRun], format: &str) {
+    if format == "json" {
+        let result = blame_runs_json(file, runs);
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
 
-        let entry = format!(
-            "### {} - {}\n{}\n\n",
-            date, source_str, snippet_final
-        );
+    println!("Diachron Blame");
+    println!("==============\n");
+    println!("File: {}:{}\n", file, label);
 
-        // Check token budget
-        if char_count + entry.len() > MAX_CHARS {
-            break;
+    for run in runs {
+        let lines = if run.start_line == run.end_line {
+            format!("line {}", run.start_line)
+        } else {
+            format!("lines {}–{}", run.start_line, run.end_line)
+        };
+        match run.blame {
+            Some(ref blame) => {
+                let event = &blame.event;
+                println!(
+                    "{} {}: event #{} ({}, {})",
+                    confidence_emoji(blame.confidence),
+                    lines,
+                    event.id,
+                    blame.confidence.as_str().to_uppercase(),
+                    blame.match_type
+                );
+                println!(
+                    "   {} {} ({})",
+                    event
+                        .timestamp_display
+                        .as_deref()
+                        .unwrap_or(&event.timestamp),
+                    event.tool_name,
+                    event.operation.map_or("-", |op| op.as_str())
+                );
+                if let Some(ref intent) = blame.intent {
+                    println!("   💬 \"{}\"", intent);
+                }
+                if !blame.context_events.is_empty() {
+                    print_context_events(blame, "     ");
+                }
+            }
+            None => println!("❓ {}: no matching event", lines),
         }
+    }
+}
 
-        output.push_str(&entry);
-        char_count += entry.len();
-        included_count += 1;
+/// Print one search result in the default text format.
+fn print_search_result(result: &diachron_core::SearchResult) {
+    let source_str = match result.source {
+        diachron_core::SearchSource::Event => "Event",
+        diachron_core::SearchSource::Exchange => "Exchange",
+    };
+    let proj_str = result.project.as_deref().unwrap_or("-");
+    println!(
+        "[{:.2}] {} {} ({}) - {}",
+        result.score, source_str, result.timestamp, proj_str, result.snippet
+    );
+    if let Some(explain) = &result.explain {
+        let score = |score: Option<f32>| score.map_or("-".to_string(), |s| format!("{:.3}", s));
+        println!(
+            "       vector: {}  fts: {}  final: {:.4}",
+            score(explain.vector_sim),
+            score(explain.fts_score),
+            explain.final_score
+        );
     }
+}
 
-    // Only output if we have meaningful content
-    if included_count == 0 {
-        return; // Silent - no quality context found
+/// Print an error returned by the daemon, with a next step where there is one.
+fn print_daemon_error(e: &DaemonError) {
+    eprintln!("Error: {}", e);
+    if let DaemonError::ModelNotLoaded(_) = e {
+        eprintln!(
+            "Hint: the embedding model is not loaded. Restart the daemon \
+             ('diachron daemon stop && diachron daemon start') to download it, \
+             or set [embedding] model_dir in ~/.diachron/config.toml."
+        );
     }
+}
 
-    // Footer with stats (helps user understand what was injected)
-    let word_count = output.split_whitespace().count();
-    let approx_tokens = word_count * 4 / 3; // Rough approximation
-    let footer = format!(
-        "_({} items, ~{} tokens)_\n",
-        included_count, approx_tokens
-    );
+/// Print items as JSON Lines: one compact object per line, flushed as it goes.
+///
+/// Prints nothing for an empty slice.
+fn print_jsonl<T: serde::Serialize>(items: &[T]) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for item in items {
+        serde_json::to_writer(&mut out, item)?;
+        out.write_all(b"\n")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Group timeline events for `--group-by`, preserving first-seen order.
+///
+/// Keys: `session` (full session ID, shown shortened), `file` (full path),
+/// `day` (date portion of the timestamp). Only non-empty groups are
+/// returned, each with its display header.
+fn group_events(
+    events: Vec<diachron_core::StoredEvent>,
+    by: &str,
+) -> Vec<(String, Vec<diachron_core::StoredEvent>)> {
+    let mut groups: Vec<(Option<String>, Vec<diachron_core::StoredEvent>)> = Vec::new();
+
+    for event in events {
+        let key = match by {
+            "session" => event.session_id.clone(),
+            "file" => event.file_path.clone(),
+            _ => Some(
+                event
+                    .timestamp
+                    .get(..10)
+                    .unwrap_or(&event.timestamp)
+                    .to_string(),
+            ),
+        };
 
-    if char_count + footer.len() <= MAX_CHARS + 100 {
-        output.push_str(&footer);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(event),
+            None => groups.push((key, vec![event])),
+        }
     }
 
-    print!("{}", output);
+    // Sessions sharing a prefix stay separate groups; only the header is short
+    groups
+        .into_iter()
+        .map(|(key, group)| {
+            let header = match by {
+                "session" => format!(
+                    "Session {}",
+                    key.as_deref().map_or("unknown", |s| safe_truncate(s, 6))
+                ),
+                "file" => format!("File {}", key.as_deref().unwrap_or("(none)")),
+                _ => format!("Day {}", key.unwrap_or_default()),
+            };
+            (header, group)
+        })
+        .collect()
 }
 
-/// Clean a snippet by removing artifacts and noise.
-/// T4-1: Strip HTML tags
-/// T4-2: Clean line prefixes
-/// T4-3: Filter tool wrappers
-fn clean_snippet(s: &str) -> String {
-    let mut result = s.to_string();
+/// Maximum events fetched for `diachron stats`
+const STATS_EVENT_LIMIT: usize = 100_000;
 
-    // T4-1: Strip HTML tags from FTS highlighting
-    result = result.replace("<b>", "");
-    result = result.replace("</b>", "");
-    result = result.replace("<em>", "");
-    result = result.replace("</em>", "");
+/// Events or exchanges sent per `ImportBatch` message
+const IMPORT_BATCH_SIZE: usize = 500;
 
-    // T4-2: Remove line number prefixes (e.g., "1→", "42→")
-    // Pattern: digits followed by → at start of line or after whitespace
-    let re_line_nums = regex::Regex::new(r"(\s|^)\d+→").unwrap_or_else(|_| {
-        // Fallback: simple replacement
-        regex::Regex::new(r"\d+→").unwrap()
-    });
-    result = re_line_nums.replace_all(&result, " ").to_string();
+/// Events sent per `CaptureBatch` message
+const CAPTURE_BATCH_SIZE: usize = 500;
 
-    // T4-3: Remove tool output wrappers
-    // Remove [Result: prefix
-    if result.starts_with("[Result:") {
-        if let Some(pos) = result.find(']') {
-            result = result[pos + 1..].to_string();
+/// Capture events read from stdin over a single daemon connection.
+///
+/// Accepts a JSON array of events or newline-delimited events. Like a
+/// single capture, problems are reported on stderr without failing, so a
+/// migration script or hook is never interrupted: unparseable lines are
+/// skipped, and if the daemon goes away the remaining events are dropped.
+fn capture_batch() -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read events from stdin")?;
+
+    let (events, mut failed) = parse_capture_input(&input);
+    if events.is_empty() {
+        if failed > 0 {
+            eprintln!("Warning: no valid events on stdin ({} unparseable)", failed);
         }
+        return Ok(());
     }
-    result = result.replace("[Result:", "");
-    result = result.replace("...]", "");
-
-    // T4-3: Remove shell noise
-    let shell_patterns = [
-        "Shell cwd was reset to",
-        "Shell cwd: ",
-        "<system-reminder>",
-        "</system-reminder>",
-    ];
-    for pattern in &shell_patterns {
-        if let Some(pos) = result.find(pattern) {
-            // Remove from pattern to end of line
-            if let Some(newline) = result[pos..].find('\n') {
-                result = format!("{}{}", &result[..pos], &result[pos + newline..]);
-            } else {
-                result = result[..pos].to_string();
+
+    let (mut saved, mut skipped) = (0u64, 0u64);
+    let timeout = response_timeout(&IpcMessage::CaptureBatch(Vec::new()));
+    let mut stream = match connect(timeout) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            return Ok(());
+        }
+    };
+    let mut chunks = events.chunks(CAPTURE_BATCH_SIZE);
+    for chunk in chunks.by_ref() {
+        let msg = IpcMessage::CaptureBatch(chunk.to_vec());
+        match exchange_framed(&mut stream, &msg, timeout) {
+            Ok(IpcResponse::CaptureStats {
+                saved: s,
+                skipped: k,
+                failed: f,
+            }) => {
+                saved += s;
+                skipped += k;
+                failed += f;
+            }
+            Ok(IpcResponse::Error(e)) => {
+                eprintln!("Capture error: {}", e);
+                failed += chunk.len() as u64;
+            }
+            Ok(_) => failed += chunk.len() as u64,
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                failed += chunk.len() as u64;
+                break;
             }
         }
     }
+    failed += chunks.map(|c| c.len() as u64).sum::<u64>();
 
-    // Normalize whitespace
-    result = result.replace('\n', " ");
-    result = result.split_whitespace().collect::<Vec<_>>().join(" ");
-
-    result.trim().to_string()
+    eprintln!(
+        "Captured {} events ({} unchanged edits skipped, {} failed)",
+        saved, skipped, failed
+    );
+    Ok(())
 }
 
-/// Check if a snippet is primarily tool output noise.
-fn is_tool_noise(s: &str) -> bool {
-    // Check for tool result wrappers at the start
-    if s.starts_with("[Result:") || s.starts_with("[Tool:") {
-        return true;
+/// Parse a JSON array of capture events, or one event per line.
+///
+/// # Returns
+/// The parsed events and the number of lines that did not parse.
+fn parse_capture_input(input: &str) -> (Vec<diachron_core::CaptureEvent>, u64) {
+    let trimmed = input.trim_start();
+    if trimmed.starts_with('[') {
+        return match serde_json::from_str(trimmed) {
+            Ok(events) => (events, 0),
+            Err(e) => {
+                eprintln!("Warning: invalid event array: {}", e);
+                (Vec::new(), 1)
+            }
+        };
     }
 
-    // Check for internal/system messages
-    let noise_starts = [
-        "Warmup",
-        "Stop hook feedback",
-        "Analyze this conversation",
-        "You MUST call",
-        "This session is being continued",
-        "<function_calls>",
-        "```json",
-        "I'm Claude Code",
-        "I'm ready to help",
-    ];
-    for pattern in &noise_starts {
-        if s.starts_with(pattern) {
-            return true;
+    let mut events = Vec::new();
+    let mut failed = 0;
+    for (n, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(event) => events.push(event),
+            Err(e) => {
+                eprintln!("Warning: skipping line {}: {}", n + 1, e);
+                failed += 1;
+            }
         }
     }
+    (events, failed)
+}
+
+/// Per-source totals for `diachron stats`
+struct SourceStats {
+    tool_name: String,
+    command_category: Option<CommandCategory>,
+    events: usize,
+    files: std::collections::HashSet<String>,
+    net_lines: i64,
+}
 
-    // Check for error indicators
-    let noise_contains = [
-        "Shell cwd was reset",
-        "401 {\"type\":\"error\"",
-        "authentication_error",
-        "Failed to find element",
-        "Permission denied",
-        "No such file",
-        "command not found",
-        "<system-reminder>",
-        "hookSpecificOutput",
-    ];
-    for indicator in &noise_contains {
-        if s.contains(indicator) {
-            return true;
+/// Aggregate events by tool name and command category, busiest first.
+///
+/// Splitting on the category keeps e.g. Bash `git` commits apart from Bash
+/// test runs, while Claude's `Write`/`Edit` and `Codex` stay separate by tool.
+fn source_stats(events: &[diachron_core::StoredEvent]) -> Vec<SourceStats> {
+    let mut rows: Vec<SourceStats> = Vec::new();
+
+    for event in events {
+        let category = event
+            .command_category
+            .filter(|c| *c != CommandCategory::Unknown);
+        let idx = match rows
+            .iter()
+            .position(|r| r.tool_name == event.tool_name && r.command_category == category)
+        {
+            Some(idx) => idx,
+            None => {
+                rows.push(SourceStats {
+                    tool_name: event.tool_name.clone(),
+                    command_category: category,
+                    events: 0,
+                    files: std::collections::HashSet::new(),
+                    net_lines: 0,
+                });
+                rows.len() - 1
+            }
+        };
+
+        let row = &mut rows[idx];
+        row.events += 1;
+        if let Some(path) = &event.file_path {
+            row.files.insert(path.clone());
         }
+        row.net_lines += event
+            .diff_summary
+            .as_deref()
+            .and_then(net_lines)
+            .unwrap_or(0);
+    }
+
+    rows.sort_by_key(|r| std::cmp::Reverse(r.events));
+    rows
+}
+
+/// Net line change from a diff summary like "+12 lines", "-3 lines" or
+/// "+12 -3 lines". Other summaries (commit messages, "new file") yield None.
+fn net_lines(summary: &str) -> Option<i64> {
+    let counts = summary.strip_suffix(" lines")?;
+    counts
+        .split_whitespace()
+        .map(|part| match part.as_bytes().first() {
+            Some(b'+') | Some(b'-') => part.parse::<i64>().ok(),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Share of `count` in `total`, as a percentage
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+/// Parse a `--project-retention` value of the form `PROJECT=DAYS`
+fn parse_project_retention(s: &str) -> Result<(String, u32), String> {
+    let (project, days) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected PROJECT=DAYS, got '{}'", s))?;
+    if project.is_empty() {
+        return Err("project name is empty".to_string());
     }
+    let days = days
+        .parse()
+        .map_err(|_| format!("invalid number of days: '{}'", days))?;
+    Ok((project.to_string(), days))
+}
 
-    // Skip if mostly line numbers (file content dump)
-    let arrow_count = s.matches('→').count();
-    let char_count = s.len();
-    if arrow_count > 3 && (arrow_count * 15) > char_count {
-        return true;
+/// Parse a string value into appropriate TOML type
+fn parse_toml_value(s: &str) -> toml::Value {
+    // Try boolean
+    if s == "true" {
+        return toml::Value::Boolean(true);
+    }
+    if s == "false" {
+        return toml::Value::Boolean(false);
     }
+    // Try integer
+    if let Ok(n) = s.parse::<i64>() {
+        return toml::Value::Integer(n);
+    }
+    // Try float
+    if let Ok(f) = s.parse::<f64>() {
+        return toml::Value::Float(f);
+    }
+    // Default to string
+    toml::Value::String(s.to_string())
+}
+
+/// Read a `--output-template` file, if one was given.
+fn read_output_template(path: Option<&std::path::Path>) -> Result<Option<String>> {
+    path.map(|path| {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template {}", path.display()))
+    })
+    .transpose()
+}
+
+/// Parse a time filter string into (start_time, end_time) timestamps.
+///
+/// Accepts every format of [`diachron_core::time::parse_since`]; anything
+/// else falls back to the last 7 days. Both ends are local time, matching
+/// stored event timestamps.
+fn parse_time_range(since: &str) -> (String, String) {
+    use diachron_core::time::{format_local, parse_since};
 
-    false
+    let now = chrono::Utc::now();
+    let start = parse_since(since).unwrap_or_else(|| now - chrono::Duration::days(7));
+    (format_local(start), format_local(now))
 }
 
 /// Safely truncate a string at a UTF-8 character boundary.
@@ -2478,3 +4758,213 @@ fn safe_truncate(s: &str, max_len: usize) -> &str {
 
     &s[..end]
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// Stream replaying scripted reads, each at most one chunk of bytes.
+    struct Scripted {
+        reads: VecDeque<std::io::Result<Vec<u8>>>,
+        written: Vec<u8>,
+    }
+
+    impl Scripted {
+        fn new(reads: Vec<std::io::Result<Vec<u8>>>) -> Self {
+            Self {
+                reads: reads.into(),
+                written: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for Scripted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.reads.pop_front() {
+                Some(Ok(mut chunk)) => {
+                    if chunk.len() > buf.len() {
+                        self.reads.push_front(Ok(chunk.split_off(buf.len())));
+                    }
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                Some(Err(e)) => Err(e),
+                None => Ok(0),
+            }
+        }
+    }
+
+    impl Write for Scripted {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn stored_event(id: i64, timestamp: &str, session: &str) -> diachron_core::StoredEvent {
+        diachron_core::StoredEvent {
+            id,
+            timestamp: timestamp.to_string(),
+            timestamp_display: None,
+            session_id: Some(session.to_string()),
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: None,
+            diff_summary: None,
+            raw_input: None,
+            ai_summary: None,
+            git_commit_sha: None,
+            metadata: None,
+            command_category: None,
+        }
+    }
+
+    fn group_ids(groups: &[(String, Vec<diachron_core::StoredEvent>)]) -> Vec<(&str, Vec<i64>)> {
+        groups
+            .iter()
+            .map(|(header, group)| (header.as_str(), group.iter().map(|e| e.id).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_group_by_session_keeps_shared_prefixes_apart() {
+        let events = vec![
+            stored_event(1, "2026-03-10T09:00:00", "abcdef-111"),
+            stored_event(2, "2026-03-10T09:05:00", "abcdef-222"),
+            stored_event(3, "2026-03-10T09:10:00", "abcdef-111"),
+        ];
+        let groups = group_events(events, "session");
+        assert_eq!(
+            group_ids(&groups),
+            vec![("Session abcdef", vec![1, 3]), ("Session abcdef", vec![2])]
+        );
+    }
+
+    #[test]
+    fn test_group_by_day_buckets_by_date() {
+        // Newest first, as the timeline returns them
+        let events = vec![
+            stored_event(4, "2026-03-11T08:00:00", "s1"),
+            stored_event(3, "2026-03-10T23:59:59", "s1"),
+            stored_event(2, "2026-03-11T07:00:00", "s2"),
+            stored_event(1, "2026-03-09T12:00:00", "s2"),
+        ];
+        let groups = group_events(events, "day");
+        assert_eq!(
+            group_ids(&groups),
+            vec![
+                ("Day 2026-03-11", vec![4, 2]),
+                ("Day 2026-03-10", vec![3]),
+                ("Day 2026-03-09", vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_session_keeps_first_seen_order() {
+        let mut unknown = stored_event(5, "2026-03-11T09:00:00", "s1");
+        unknown.session_id = None;
+        let events = vec![
+            stored_event(4, "2026-03-11T08:00:00", "s2"),
+            unknown,
+            stored_event(2, "2026-03-11T07:00:00", "s1"),
+        ];
+        let groups = group_events(events, "session");
+        assert_eq!(
+            group_ids(&groups),
+            vec![
+                ("Session s2", vec![4]),
+                ("Session unknown", vec![5]),
+                ("Session s1", vec![2]),
+            ]
+        );
+    }
+
+    fn would_block() -> std::io::Result<Vec<u8>> {
+        Err(std::io::ErrorKind::WouldBlock.into())
+    }
+
+    fn ok_line() -> Vec<u8> {
+        (serde_json::to_string(&IpcResponse::Ok).unwrap() + "\n").into_bytes()
+    }
+
+    #[test]
+    fn test_exchange_resumes_after_timed_out_partial_reads() {
+        let line = ok_line();
+        let (head, tail) = line.split_at(4);
+        let mut stream = Scripted::new(vec![
+            Ok(head.to_vec()),
+            would_block(),
+            would_block(),
+            Ok(tail.to_vec()),
+        ]);
+
+        let response = exchange(&mut stream, &IpcMessage::Ping, Duration::from_secs(5)).unwrap();
+        assert!(matches!(response, IpcResponse::Ok));
+        assert_eq!(
+            stream.written,
+            (serde_json::to_string(&IpcMessage::Ping).unwrap() + "\n").into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_exchange_reports_short_and_late_responses() {
+        let line = ok_line();
+        let mut stream = Scripted::new(vec![Ok(line[..5].to_vec())]);
+        let err = exchange(&mut stream, &IpcMessage::Ping, Duration::from_secs(5)).unwrap_err();
+        assert!(
+            err.to_string().contains("mid-response after 5 bytes"),
+            "{}",
+            err
+        );
+
+        let mut stream = Scripted::new(vec![]);
+        let err = exchange(&mut stream, &IpcMessage::Ping, Duration::from_secs(5)).unwrap_err();
+        assert!(err.to_string().contains("without responding"), "{}", err);
+
+        // Past the deadline a timed-out read ends the wait
+        let mut stream = Scripted::new(vec![Ok(line[..5].to_vec()), would_block()]);
+        let err = exchange(&mut stream, &IpcMessage::Ping, Duration::ZERO).unwrap_err();
+        assert!(
+            err.to_string().contains("No response from the daemon"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_exchange_framed_reads_split_and_short_frames() {
+        let mut frame = Vec::new();
+        write_frame(&mut frame, &serde_json::to_vec(&IpcResponse::Ok).unwrap()).unwrap();
+
+        // The length prefix and body arrive in pieces
+        let chunks = vec![
+            Ok(frame[..2].to_vec()),
+            Ok(frame[2..7].to_vec()),
+            Ok(frame[7..].to_vec()),
+        ];
+        let response = exchange_framed(
+            Scripted::new(chunks),
+            &IpcMessage::Ping,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(matches!(response, IpcResponse::Ok));
+
+        let short = vec![Ok(frame[..frame.len() - 1].to_vec())];
+        let err = exchange_framed(
+            Scripted::new(short),
+            &IpcMessage::Ping,
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        let io = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
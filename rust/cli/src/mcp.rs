@@ -0,0 +1,502 @@
+//! Model Context Protocol server (`diachron mcp`)
+//!
+//! Editors that speak MCP launch `diachron mcp` and exchange newline-delimited
+//! JSON-RPC 2.0 messages over stdin/stdout. Each tool is a thin adapter: it
+//! sends the matching `IpcMessage` to the running daemon and returns the same
+//! text the CLI would print. Diagnostics go to stderr so stdout stays a clean
+//! protocol stream.
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::Result;
+use diachron_core::{IpcMessage, IpcResponse, SearchCursor, UnknownVariant};
+use serde_json::{json, Value};
+
+use crate::context::ContextConfig;
+
+/// Sends a request to the daemon and waits for its answer.
+type SendFn<'a> = dyn Fn(&IpcMessage) -> Result<IpcResponse> + 'a;
+
+/// MCP revision this server implements.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Server-defined: the daemon could not be reached
+const DAEMON_UNAVAILABLE: i64 = -32001;
+
+/// JSON-RPC error returned in place of a result.
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+
+    fn daemon_unavailable(err: &anyhow::Error) -> Self {
+        Self {
+            code: DAEMON_UNAVAILABLE,
+            message: "Diachron daemon is not running".to_string(),
+            data: Some(json!({
+                "reason": "daemon_unavailable",
+                "detail": err.to_string(),
+                "hint": "Start it with: diachron daemon start"
+            })),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code, "message": self.message });
+        if let Some(ref data) = self.data {
+            error["data"] = data.clone();
+        }
+        error
+    }
+}
+
+/// Serve MCP requests from stdin until it closes.
+pub fn run() -> Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => handle_request(&request, &crate::send_message),
+            Err(e) => Some(response(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, format!("Invalid JSON: {}", e))),
+            )),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Dispatch one JSON-RPC message, reaching the daemon through `send`.
+///
+/// Returns `None` for notifications, which get no reply.
+fn handle_request(request: &Value, send: &SendFn) -> Option<Value> {
+    let id = request.get("id")?.clone();
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").unwrap_or(&Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": "diachron",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(params, send),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method: {}", method),
+        )),
+    };
+    Some(response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error.to_json() }),
+    }
+}
+
+/// Tool descriptors advertised by `tools/list`.
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "diachron.search",
+            "description": "Search prior code changes and conversations in this project. Returns a cleaned markdown digest of the most relevant hits.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "What to look for" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 10)" },
+                    "after": { "type": "string", "description": "Cursor from the previous page; returns the results ranked after it" },
+                    "since": { "type": "string", "description": "Only results since this time (e.g. \"1h\", \"7d\", \"2024-01-01\")" },
+                    "project": { "type": "string", "description": "Project name filter" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "diachron.timeline",
+            "description": "List recent captured events (file edits, commands, commits), newest first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "since": { "type": "string", "description": "Only events since this time (e.g. \"1h\", \"7d\")" },
+                    "file": { "type": "string", "description": "File path filter" },
+                    "file_match": { "type": "string", "enum": ["substring", "glob", "regex"], "description": "How the file filter is matched (default substring)" },
+                    "limit": { "type": "integer", "description": "Maximum events (default 20)" },
+                    "project": { "type": "string", "description": "Project name filter" },
+                    "operation": { "type": "string", "description": "Operation filter (e.g. \"modify\", \"execute\")" },
+                    "category": { "type": "string", "description": "Bash command category filter (e.g. \"test\", \"build\")" },
+                    "before": { "type": "integer", "description": "Cursor from the previous page; returns the events after it" }
+                }
+            }
+        },
+        {
+            "name": "diachron.blame",
+            "description": "Find the captured event that wrote a line or range of a file, with confidence and intent.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file": { "type": "string", "description": "File path" },
+                    "line": { "type": "integer", "description": "First line (1-based)" },
+                    "end_line": { "type": "integer", "description": "Last line of a range (defaults to line)" },
+                    "mode": { "type": "string", "enum": ["strict", "best-effort", "inferred"], "description": "Blame mode (default strict)" }
+                },
+                "required": ["file", "line"]
+            }
+        }
+    ])
+}
+
+/// Handle `tools/call`.
+fn call_tool(params: &Value, send: &SendFn) -> Result<Value, RpcError> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params("Missing tool name"))?;
+    let empty = json!({});
+    let args = params.get("arguments").unwrap_or(&empty);
+
+    match name {
+        "diachron.search" => search(args, send),
+        "diachron.timeline" => timeline(args, send),
+        "diachron.blame" => blame(args, send),
+        _ => Err(RpcError::invalid_params(format!("Unknown tool: {}", name))),
+    }
+}
+
+fn search(args: &Value, send: &SendFn) -> Result<Value, RpcError> {
+    let query =
+        str_arg(args, "query").ok_or_else(|| RpcError::invalid_params("`query` is required"))?;
+    let limit = usize_arg(args, "limit").unwrap_or(10);
+    let msg = IpcMessage::Search {
+        query,
+        limit,
+        source_filter: None,
+        since: str_arg(args, "since"),
+        until: None,
+        project: str_arg(args, "project"),
+        tool: None,
+        stream: false,
+        after: str_arg(args, "after")
+            .map(|cursor| cursor.parse())
+            .transpose()
+            .map_err(|e: UnknownVariant| RpcError::invalid_params(e.to_string()))?,
+        prefer: None,
+        explain: false,
+    };
+
+    let result = match ask(send, &msg)? {
+        IpcResponse::SearchResults(results) => {
+            let mut text = crate::context::format_context_output(&results, &ContextConfig::load())
+                .unwrap_or_else(|| "No relevant prior context found.".to_string());
+            if let (true, Some(last)) = (results.len() == limit, results.last()) {
+                text.push_str(&format!(
+                    "\n\nMore results: after = \"{}\"",
+                    SearchCursor::after(last)
+                ));
+            }
+            Ok(tool_text(text))
+        }
+        other => Err(unexpected(other)),
+    };
+    result.or_else(tool_error)
+}
+
+fn timeline(args: &Value, send: &SendFn) -> Result<Value, RpcError> {
+    let msg = IpcMessage::Timeline {
+        since: str_arg(args, "since"),
+        file_filter: str_arg(args, "file"),
+        limit: usize_arg(args, "limit").unwrap_or(20),
+        file_match: args
+            .get("file_match")
+            .and_then(|m| serde_json::from_value(m.clone()).ok())
+            .unwrap_or_default(),
+        project: str_arg(args, "project"),
+        operation: str_arg(args, "operation"),
+        category: str_arg(args, "category"),
+        before_id: args.get("before").and_then(Value::as_i64),
+    };
+
+    let result = match ask(send, &msg)? {
+        IpcResponse::EventsPage { events, .. } if events.is_empty() => {
+            Ok(tool_text("No events found.".to_string()))
+        }
+        IpcResponse::EventsPage {
+            events,
+            next_cursor,
+        } => {
+            let mut text = String::new();
+            for event in &events {
+                text.push_str(&format!(
+                    "{} {}{} {} {}\n",
+                    event
+                        .timestamp_display
+                        .as_deref()
+                        .unwrap_or(&event.timestamp),
+                    crate::failure_marker(event),
+                    event.tool_name,
+                    event.operation.map_or("-", |op| op.as_str()),
+                    event.file_path.as_deref().unwrap_or("-")
+                ));
+                if let Some(excerpt) = event.error_excerpt() {
+                    text.push_str(&format!("    └─ {}\n", excerpt));
+                } else if let Some(diff) = event.diff_summary.as_deref().filter(|d| !d.is_empty()) {
+                    text.push_str(&format!("    └─ {}\n", diff));
+                }
+            }
+            if let Some(cursor) = next_cursor {
+                text.push_str(&format!("(more events: before={})\n", cursor));
+            }
+            Ok(tool_text(text))
+        }
+        other => Err(unexpected(other)),
+    };
+    result.or_else(tool_error)
+}
+
+fn blame(args: &Value, send: &SendFn) -> Result<Value, RpcError> {
+    let file =
+        str_arg(args, "file").ok_or_else(|| RpcError::invalid_params("`file` is required"))?;
+    let start = usize_arg(args, "line")
+        .and_then(|line| u32::try_from(line).ok())
+        .filter(|&line| line > 0)
+        .ok_or_else(|| RpcError::invalid_params("`line` must be a positive integer"))?;
+    let end = usize_arg(args, "end_line")
+        .and_then(|line| u32::try_from(line).ok())
+        .unwrap_or(start);
+    if end < start {
+        return Err(RpcError::invalid_params("`end_line` is before `line`"));
+    }
+
+    let (lines, lines_start) = crate::read_blame_lines(&file, start, end);
+    let msg = IpcMessage::BlameRange {
+        file_path: file.clone(),
+        start,
+        end,
+        lines,
+        lines_start,
+        mode: str_arg(args, "mode").unwrap_or_else(|| "strict".to_string()),
+        session_context: 0,
+    };
+
+    let result = match ask(send, &msg)? {
+        IpcResponse::BlameRangeResult(runs) => {
+            let result = crate::blame_runs_json(&file, &runs);
+            Ok(tool_text(
+                serde_json::to_string_pretty(&result).unwrap_or_default(),
+            ))
+        }
+        IpcResponse::BlameNotFound { reason } => Ok(tool_text(reason)),
+        other => Err(unexpected(other)),
+    };
+    result.or_else(tool_error)
+}
+
+/// Send a message to the daemon, mapping connection failures to a
+/// structured error.
+fn ask(send: &SendFn, msg: &IpcMessage) -> Result<IpcResponse, RpcError> {
+    send(msg).map_err(|e| RpcError::daemon_unavailable(&e))
+}
+
+/// Error from the daemon or an unexpected response type.
+fn unexpected(response: IpcResponse) -> String {
+    match response {
+        IpcResponse::Error(e) => e.to_string(),
+        _ => "Unexpected response from daemon".to_string(),
+    }
+}
+
+/// Report a daemon-side failure as a tool result with `isError` set, so the
+/// client shows it to the model instead of treating it as a protocol error.
+fn tool_error(message: String) -> Result<Value, RpcError> {
+    Ok(json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true
+    }))
+}
+
+fn tool_text(text: String) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": false
+    })
+}
+
+fn str_arg(args: &Value, key: &str) -> Option<String> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn usize_arg(args: &Value, key: &str) -> Option<usize> {
+    args.get(key)
+        .and_then(Value::as_u64)
+        .and_then(|n| usize::try_from(n).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use diachron_core::{DaemonError, SearchResult, SearchSource};
+
+    use super::*;
+
+    fn call(tool: &str, arguments: Value, send: &SendFn) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": tool, "arguments": arguments }
+        });
+        handle_request(&request, send).unwrap()
+    }
+
+    fn unreachable_daemon(_: &IpcMessage) -> Result<IpcResponse> {
+        anyhow::bail!("connection refused")
+    }
+
+    fn text(response: &Value) -> &str {
+        response["result"]["content"][0]["text"].as_str().unwrap()
+    }
+
+    #[test]
+    fn test_protocol_methods() {
+        let request = |method: &str| json!({ "jsonrpc": "2.0", "id": 1, "method": method });
+
+        let init = handle_request(&request("initialize"), &unreachable_daemon).unwrap();
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+
+        let list = handle_request(&request("tools/list"), &unreachable_daemon).unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["diachron.search", "diachron.timeline", "diachron.blame"]
+        );
+
+        let unknown = handle_request(&request("resources/list"), &unreachable_daemon).unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+
+        // Notifications carry no id and get no reply
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(handle_request(&notification, &unreachable_daemon).is_none());
+    }
+
+    #[test]
+    fn test_search_sends_query_and_formats_results() {
+        let sent = RefCell::new(Vec::new());
+        let send = |msg: &IpcMessage| {
+            sent.borrow_mut().push(msg.clone());
+            Ok(IpcResponse::SearchResults(vec![SearchResult {
+                id: "exchange:1".to_string(),
+                score: 0.05,
+                source: SearchSource::Exchange,
+                snippet: "Moved session refresh into the auth middleware".to_string(),
+                timestamp: "2026-01-10T12:00:00Z".to_string(),
+                project: None,
+                explain: None,
+            }]))
+        };
+
+        let response = call(
+            "diachron.search",
+            json!({ "query": "auth refresh", "limit": 1 }),
+            &send,
+        );
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["isError"], false);
+        assert!(text(&response).contains("auth middleware"));
+        // A full page points at the next one
+        assert!(text(&response).contains("More results: after = "));
+
+        match sent.borrow().as_slice() {
+            [IpcMessage::Search {
+                query,
+                limit,
+                stream,
+                ..
+            }] => {
+                assert_eq!(query, "auth refresh");
+                assert_eq!(*limit, 1);
+                assert!(!stream);
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        };
+    }
+
+    #[test]
+    fn test_daemon_errors_become_tool_errors() {
+        let send = |_: &IpcMessage| {
+            Ok(IpcResponse::Error(DaemonError::InvalidRequest(
+                "bad window".to_string(),
+            )))
+        };
+        let response = call("diachron.timeline", json!({ "since": "soon" }), &send);
+        assert_eq!(response["result"]["isError"], true);
+        assert!(text(&response).contains("bad window"));
+    }
+
+    #[test]
+    fn test_call_errors() {
+        let response = call(
+            "diachron.search",
+            json!({ "query": "x" }),
+            &unreachable_daemon,
+        );
+        assert_eq!(response["error"]["code"], DAEMON_UNAVAILABLE);
+        assert_eq!(response["error"]["data"]["reason"], "daemon_unavailable");
+
+        // Bad arguments are rejected before the daemon is asked
+        let response = call(
+            "diachron.blame",
+            json!({ "file": "src/a.rs" }),
+            &unreachable_daemon,
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call("diachron.search", json!({}), &unreachable_daemon);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call("diachron.forget", json!({}), &unreachable_daemon);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+}
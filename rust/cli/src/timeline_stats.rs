@@ -0,0 +1,223 @@
+//! Totals printed by `diachron timeline --stats`
+//!
+//! Aggregated on the client from the page of events the daemon returned,
+//! so they cover what was listed (after `--limit` and the filters), not the
+//! whole database.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use diachron_core::StoredEvent;
+use serde::Serialize;
+
+/// Aggregate counts over a list of events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineStats {
+    /// Events listed
+    pub events: usize,
+    /// Distinct file paths among them
+    pub files: usize,
+    /// Distinct sessions among them
+    pub sessions: usize,
+    /// Timestamp of the oldest event
+    pub first: Option<String>,
+    /// Timestamp of the newest event
+    pub last: Option<String>,
+    /// Seconds from the oldest to the newest event, when both parse
+    pub span_secs: Option<i64>,
+    /// Events per tool, most used first
+    pub tools: Vec<ToolCount>,
+}
+
+/// Events recorded for one tool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCount {
+    pub tool: String,
+    pub events: usize,
+    /// Share of all listed events, 0-100
+    pub percent: f64,
+}
+
+impl TimelineStats {
+    /// Aggregate `events`, in any order.
+    pub fn from_events(events: &[StoredEvent]) -> Self {
+        let files: HashSet<&str> = events
+            .iter()
+            .filter_map(|e| e.file_path.as_deref())
+            .collect();
+        let sessions: HashSet<&str> = events
+            .iter()
+            .filter_map(|e| e.session_id.as_deref())
+            .collect();
+
+        // Stored timestamps are ISO 8601, so they sort as strings
+        let first = events.iter().map(|e| e.timestamp.as_str()).min();
+        let last = events.iter().map(|e| e.timestamp.as_str()).max();
+        let span_secs = match (
+            first.and_then(parse_timestamp),
+            last.and_then(parse_timestamp),
+        ) {
+            (Some(first), Some(last)) => Some((last - first).num_seconds()),
+            _ => None,
+        };
+
+        let mut per_tool: HashMap<&str, usize> = HashMap::new();
+        for event in events {
+            *per_tool.entry(event.tool_name.as_str()).or_default() += 1;
+        }
+        let mut tools: Vec<ToolCount> = per_tool
+            .into_iter()
+            .map(|(tool, count)| ToolCount {
+                tool: tool.to_string(),
+                events: count,
+                // Only tools with events are listed, so `events` is not 0
+                percent: count as f64 / events.len() as f64 * 100.0,
+            })
+            .collect();
+        tools.sort_by(|a, b| b.events.cmp(&a.events).then_with(|| a.tool.cmp(&b.tool)));
+
+        Self {
+            events: events.len(),
+            files: files.len(),
+            sessions: sessions.len(),
+            first: first.map(str::to_string),
+            last: last.map(str::to_string),
+            span_secs,
+            tools,
+        }
+    }
+
+    /// Summary as display lines: totals and date span, then tools.
+    ///
+    /// # Returns
+    /// `["no events"]` when nothing was listed.
+    pub fn summary_lines(&self) -> Vec<String> {
+        if self.events == 0 {
+            return vec!["no events".to_string()];
+        }
+        let mut totals = format!(
+            "{} {}, {} {}, {} {}",
+            self.events,
+            plural(self.events, "event", "events"),
+            self.files,
+            plural(self.files, "file", "files"),
+            self.sessions,
+            plural(self.sessions, "session", "sessions"),
+        );
+        if let (Some(first), Some(last)) = (&self.first, &self.last) {
+            totals.push_str(&format!(
+                ", {} to {}",
+                short_timestamp(first),
+                short_timestamp(last)
+            ));
+            if let Some(secs) = self.span_secs {
+                totals.push_str(&format!(" ({})", format_span(secs)));
+            }
+        }
+
+        let tools = self
+            .tools
+            .iter()
+            .map(|t| format!("{} {} ({:.0}%)", t.tool, t.events, t.percent))
+            .collect::<Vec<_>>()
+            .join(", ");
+        vec![totals, format!("Tools: {}", tools)]
+    }
+}
+
+fn plural<'a>(n: usize, one: &'a str, many: &'a str) -> &'a str {
+    if n == 1 {
+        one
+    } else {
+        many
+    }
+}
+
+/// Parse a stored timestamp (`2024-01-01T10:00:00.000`, or RFC 3339 for
+/// imported events).
+fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .or_else(|| {
+            chrono::DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|t| t.naive_utc())
+        })
+}
+
+/// `2024-01-01T10:00:00.000` as `2024-01-01 10:00`.
+fn short_timestamp(timestamp: &str) -> String {
+    timestamp
+        .get(..16)
+        .unwrap_or(timestamp)
+        .replacen('T', " ", 1)
+}
+
+/// Span in its two largest units: `45s`, `12m`, `3h 5m`, `2d 4h`.
+fn format_span(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, hours, mins) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m", mins)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: &str, tool: &str, file: Option<&str>, session: &str) -> StoredEvent {
+        StoredEvent {
+            id: 0,
+            timestamp: timestamp.to_string(),
+            timestamp_display: None,
+            session_id: Some(session.to_string()),
+            tool_name: tool.to_string(),
+            file_path: file.map(str::to_string),
+            operation: None,
+            diff_summary: None,
+            raw_input: None,
+            ai_summary: None,
+            git_commit_sha: None,
+            metadata: None,
+            command_category: None,
+        }
+    }
+
+    #[test]
+    fn test_stats_totals_span_and_tools() {
+        let events = [
+            event("2024-01-02T11:30:00.000", "Edit", Some("src/a.rs"), "s2"),
+            event("2024-01-02T09:00:00.000", "Bash", None, "s2"),
+            event("2024-01-01T10:00:00.000", "Edit", Some("src/a.rs"), "s1"),
+            event("2024-01-01T10:05:00.000", "Write", Some("src/b.rs"), "s1"),
+        ];
+        let stats = TimelineStats::from_events(&events);
+        assert_eq!((stats.events, stats.files, stats.sessions), (4, 2, 2));
+        assert_eq!(stats.span_secs, Some(91_800));
+        assert_eq!(
+            stats.summary_lines(),
+            [
+                "4 events, 2 files, 2 sessions, 2024-01-01 10:00 to 2024-01-02 11:30 (1d 1h)",
+                "Tools: Edit 2 (50%), Bash 1 (25%), Write 1 (25%)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_without_events() {
+        let stats = TimelineStats::from_events(&[]);
+        assert_eq!(stats.events, 0);
+        assert!(stats.tools.is_empty());
+        assert_eq!(stats.span_secs, None);
+        assert_eq!(stats.summary_lines(), ["no events"]);
+        // Serializes without NaN
+        assert!(serde_json::to_string(&stats).is_ok());
+    }
+}
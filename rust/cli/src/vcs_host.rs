@@ -0,0 +1,114 @@
+//! Code-review host integration (GitHub PRs, GitLab MRs)
+//!
+//! Evidence correlation only needs commits and time windows, so it stays
+//! host-agnostic. The host-specific bits — finding the open PR/MR for the
+//! current branch and posting the narrative comment — live behind
+//! [`VcsHost`], backed by the `gh` and `glab` CLIs.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// A code-review host reachable through its CLI.
+pub trait VcsHost {
+    /// Human-readable host name (e.g., "GitHub")
+    fn name(&self) -> &'static str;
+
+    /// Reference to a change request as the host writes it (e.g., "PR #12", "MR !12")
+    fn change_ref(&self, number: u64) -> String;
+
+    /// Number of the open PR/MR for the current branch, if any
+    fn current_change_number(&self) -> Option<u64>;
+
+    /// Post a markdown comment on a PR/MR
+    fn post_comment(&self, number: u64, body: &str) -> Result<()>;
+}
+
+/// GitHub pull requests via the `gh` CLI.
+pub struct GitHubHost;
+
+impl VcsHost for GitHubHost {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn change_ref(&self, number: u64) -> String {
+        format!("PR #{}", number)
+    }
+
+    fn current_change_number(&self) -> Option<u64> {
+        Command::new("gh")
+            .args(["pr", "view", "--json", "number", "-q", ".number"])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .and_then(|s| s.trim().parse().ok())
+    }
+
+    fn post_comment(&self, number: u64, body: &str) -> Result<()> {
+        let status = Command::new("gh")
+            .args(["pr", "comment", &number.to_string(), "-b", body])
+            .status()
+            .context("Failed to run gh CLI")?;
+        if !status.success() {
+            bail!("gh exit code: {:?}", status.code());
+        }
+        Ok(())
+    }
+}
+
+/// GitLab merge requests via the `glab` CLI.
+pub struct GitLabHost;
+
+impl VcsHost for GitLabHost {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    fn change_ref(&self, number: u64) -> String {
+        format!("MR !{}", number)
+    }
+
+    fn current_change_number(&self) -> Option<u64> {
+        let output = Command::new("glab")
+            .args(["mr", "view", "--output", "json"])
+            .output()
+            .ok()?;
+        let mr: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        mr["iid"].as_u64()
+    }
+
+    fn post_comment(&self, number: u64, body: &str) -> Result<()> {
+        let status = Command::new("glab")
+            .args(["mr", "note", &number.to_string(), "-m", body])
+            .status()
+            .context("Failed to run glab CLI")?;
+        if !status.success() {
+            bail!("glab exit code: {:?}", status.code());
+        }
+        Ok(())
+    }
+}
+
+/// Select a host by name ("github" or "gitlab"), or detect it from the
+/// `origin` remote URL when no name is given. Defaults to GitHub.
+pub fn select_host(name: Option<&str>) -> Result<Box<dyn VcsHost>> {
+    match name.map(str::to_lowercase).as_deref() {
+        Some("github") => Ok(Box::new(GitHubHost)),
+        Some("gitlab") => Ok(Box::new(GitLabHost)),
+        Some(other) => bail!("Unknown host '{}'. Use: github, gitlab", other),
+        None => {
+            let remote = Command::new("git")
+                .args(["remote", "get-url", "origin"])
+                .output()
+                .ok()
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+                .unwrap_or_default();
+            if remote.contains("gitlab") {
+                Ok(Box::new(GitLabHost))
+            } else {
+                Ok(Box::new(GitHubHost))
+            }
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Live event monitor (`diachron watch`)
+//!
+//! Subscribes to the daemon's event feed and renders a scrolling timeline
+//! next to live counts per tool and per session. A background thread owns
+//! the connection: it subscribes, backfills recent events from the timeline,
+//! forwards pushed events, and reconnects when the daemon restarts. Each
+//! backfill covers the gap since the last event seen, so captures made while
+//! the daemon was down still show up.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::Result;
+use diachron_core::{FileMatch, IpcMessage, IpcResponse, Outcome, StoredEvent, CAP_SUBSCRIBE};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+/// Events kept for scrolling back.
+const SCROLLBACK: usize = 2000;
+
+/// Recent events loaded on connect, and the most a reconnect can backfill.
+const BACKFILL: usize = 200;
+
+/// Read timeout on the subscription. The daemon sends a heartbeat every
+/// 15 seconds, so silence this long means the connection is dead.
+const FEED_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Wait between reconnect attempts.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// How often the UI checks for input and new events.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Message from the feed thread to the UI.
+enum Update {
+    Connected,
+    Event(Box<StoredEvent>),
+    Disconnected(String),
+}
+
+/// Run the monitor until the user quits.
+///
+/// # Arguments
+/// - `file`: Only show events whose file path contains this text.
+pub fn run(file: Option<String>) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        anyhow::bail!(
+            "diachron watch needs a terminal; use 'diachron timeline --watch' to stream to a pipe"
+        );
+    }
+
+    let capabilities = crate::handshake::daemon_capabilities();
+    if capabilities.daemon_version.is_some() && !capabilities.supports(CAP_SUBSCRIBE) {
+        anyhow::bail!("The daemon has no event feed; 'diachron timeline --watch' polls instead");
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || feed(&tx, file.as_deref()));
+
+    let mut terminal = ratatui::init();
+    let result = App::default().run(&mut terminal, &rx);
+    ratatui::restore();
+    result
+}
+
+/// Keep a subscription open, reconnecting until the UI goes away.
+fn feed(tx: &mpsc::Sender<Update>, file: Option<&str>) {
+    let mut last_id = 0;
+    loop {
+        let reason = match subscribe(tx, file, &mut last_id) {
+            Ok(()) => "Daemon closed the connection".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if tx.send(Update::Disconnected(reason)).is_err() {
+            return;
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Subscribe, backfill, and forward events until the connection drops.
+///
+/// Subscribing before the backfill means nothing saved in between is
+/// missed; events seen in both are dropped by ID.
+fn subscribe(tx: &mpsc::Sender<Update>, file: Option<&str>, last_id: &mut i64) -> Result<()> {
+    let mut stream = crate::connect(FEED_TIMEOUT)?;
+    let json = serde_json::to_string(&IpcMessage::Subscribe)? + "\n";
+    stream.write_all(json.as_bytes())?;
+    let mut lines = BufReader::new(stream).lines();
+
+    match next_response(&mut lines)? {
+        IpcResponse::Ok => {}
+        IpcResponse::Error(e) => anyhow::bail!(
+            "{} (older daemon? 'diachron timeline --watch' polls instead)",
+            e
+        ),
+        _ => anyhow::bail!("Unexpected response from daemon"),
+    }
+    let _ = tx.send(Update::Connected);
+
+    let backfill = IpcMessage::Timeline {
+        since: None,
+        file_filter: file.map(str::to_string),
+        limit: BACKFILL,
+        file_match: FileMatch::Substring,
+        project: None,
+        operation: None,
+        category: None,
+        before_id: None,
+    };
+    if let Ok(IpcResponse::EventsPage { events, .. }) = crate::send_message(&backfill) {
+        // Timeline is newest first
+        for event in events.into_iter().rev() {
+            forward(tx, event, file, last_id)?;
+        }
+    }
+
+    loop {
+        match next_response(&mut lines)? {
+            IpcResponse::EventPushed(event) => forward(tx, event, file, last_id)?,
+            // Heartbeat
+            IpcResponse::Ok => {}
+            IpcResponse::Error(e) => anyhow::bail!(e),
+            _ => anyhow::bail!("Unexpected response from daemon"),
+        }
+    }
+}
+
+fn next_response(lines: &mut impl Iterator<Item = std::io::Result<String>>) -> Result<IpcResponse> {
+    let line = lines
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Daemon closed the connection"))??;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Send an event to the UI unless it was already sent or is filtered out.
+fn forward(
+    tx: &mpsc::Sender<Update>,
+    event: StoredEvent,
+    file: Option<&str>,
+    last_id: &mut i64,
+) -> Result<()> {
+    if event.id <= *last_id {
+        return Ok(());
+    }
+    *last_id = event.id;
+
+    let matches = file.is_none_or(|f| event.file_path.as_deref().is_some_and(|p| p.contains(f)));
+    if matches {
+        tx.send(Update::Event(Box::new(event)))
+            .map_err(|_| anyhow::anyhow!("Monitor closed"))?;
+    }
+    Ok(())
+}
+
+/// Connection state shown in the header.
+#[derive(Default)]
+enum Status {
+    #[default]
+    Connecting,
+    Live,
+    Disconnected(String),
+}
+
+#[derive(Default)]
+struct App {
+    events: VecDeque<StoredEvent>,
+    tools: HashMap<String, u64>,
+    sessions: HashMap<String, u64>,
+    total: u64,
+    status: Status,
+    /// Events scrolled up from the newest; 0 follows new events
+    scroll: usize,
+}
+
+impl App {
+    fn run(mut self, terminal: &mut DefaultTerminal, rx: &mpsc::Receiver<Update>) -> Result<()> {
+        loop {
+            for update in rx.try_iter() {
+                self.apply(update);
+            }
+            terminal.draw(|frame| self.render(frame))?;
+
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let max_scroll = self.events.len().saturating_sub(1);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.scroll = (self.scroll + 1).min(max_scroll),
+                KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::PageUp => self.scroll = (self.scroll + 10).min(max_scroll),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::Home | KeyCode::Char('g') => self.scroll = max_scroll,
+                KeyCode::End | KeyCode::Char('G') => self.scroll = 0,
+                _ => {}
+            }
+        }
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Connected => self.status = Status::Live,
+            Update::Disconnected(reason) => self.status = Status::Disconnected(reason),
+            Update::Event(event) => {
+                *self.tools.entry(event.tool_name.clone()).or_default() += 1;
+                *self.sessions.entry(session_label(&event)).or_default() += 1;
+                self.total += 1;
+
+                // Keep the view still while scrolled back
+                if self.scroll > 0 {
+                    self.scroll += 1;
+                }
+                if self.events.len() == SCROLLBACK {
+                    self.events.pop_front();
+                }
+                self.events.push_back(*event);
+                self.scroll = self.scroll.min(self.events.len().saturating_sub(1));
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [timeline, side] =
+            Layout::horizontal([Constraint::Min(40), Constraint::Length(32)]).areas(body);
+        let [tools, sessions] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(side);
+
+        let status = match &self.status {
+            Status::Connecting => Span::styled("connecting…", Style::new().fg(Color::Yellow)),
+            Status::Live => Span::styled("● live", Style::new().fg(Color::Green)),
+            Status::Disconnected(reason) => Span::styled(
+                format!("○ reconnecting: {}", reason),
+                Style::new().fg(Color::Red),
+            ),
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "diachron watch  ",
+                    Style::new().add_modifier(Modifier::BOLD),
+                ),
+                status,
+                Span::raw(format!("  {} events", self.total)),
+            ])),
+            header,
+        );
+
+        self.render_timeline(frame, timeline);
+        render_counts(frame, tools, " Tools ", &self.tools);
+        render_counts(frame, sessions, " Sessions ", &self.sessions);
+
+        let hint = if self.scroll > 0 {
+            "↑/↓ PgUp/PgDn scroll · End follow · q quit"
+        } else {
+            "↑/↓ PgUp/PgDn scroll · q quit"
+        };
+        frame.render_widget(
+            Paragraph::new(hint).style(Style::new().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn render_timeline(&self, frame: &mut Frame, area: Rect) {
+        let rows = area.height.saturating_sub(2) as usize;
+        let end = self.events.len() - self.scroll.min(self.events.len());
+        let start = end.saturating_sub(rows);
+        let lines: Vec<Line> = self.events.range(start..end).map(event_line).collect();
+
+        let title = if self.scroll > 0 {
+            format!(" Timeline (scrolled back {}) ", self.scroll)
+        } else {
+            " Timeline ".to_string()
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(title)),
+            area,
+        );
+    }
+}
+
+fn event_line(event: &StoredEvent) -> Line<'_> {
+    let failed = event.outcome() == Some(Outcome::Failure);
+    // HH:MM:SS; the date is rarely useful in a live view
+    let time = event.timestamp.get(11..19).unwrap_or(&event.timestamp);
+    let detail = event
+        .error_excerpt()
+        .or_else(|| event.diff_summary.clone().filter(|d| !d.is_empty()))
+        .unwrap_or_default();
+
+    let style = if failed {
+        Style::new().fg(Color::Red)
+    } else {
+        Style::new()
+    };
+    Line::from(vec![
+        Span::styled(format!("{} ", time), Style::new().fg(Color::DarkGray)),
+        Span::raw(format!("{} ", crate::operation_icon(event))),
+        Span::styled(
+            format!("{:<8} ", event.tool_name),
+            style.add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(event.file_path.as_deref().unwrap_or("-"), style),
+        Span::styled(format!("  {}", detail), Style::new().fg(Color::DarkGray)),
+    ])
+}
+
+/// Render a count table, largest first.
+fn render_counts(frame: &mut Frame, area: Rect, title: &str, counts: &HashMap<String, u64>) {
+    let mut sorted: Vec<(&String, &u64)> = counts.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let width = area.width.saturating_sub(10) as usize;
+    let lines: Vec<Line> = sorted
+        .into_iter()
+        .take(area.height.saturating_sub(2) as usize)
+        .map(|(name, count)| {
+            Line::from(format!(
+                "{:<width$} {:>6}",
+                name.chars().take(width).collect::<String>(),
+                count,
+                width = width
+            ))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(title)),
+        area,
+    );
+}
+
+fn session_label(event: &StoredEvent) -> String {
+    event
+        .session_id
+        .as_deref()
+        .map(|s| s.chars().take(8).collect())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use diachron_core::Operation;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+
+    fn event(id: i64, path: &str, metadata: Option<&str>) -> StoredEvent {
+        StoredEvent {
+            id,
+            timestamp: "2026-01-10T12:34:56".to_string(),
+            timestamp_display: None,
+            session_id: Some("abcdef1234".to_string()),
+            tool_name: "Edit".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Some(Operation::Modify),
+            diff_summary: Some("+1 -1".to_string()),
+            raw_input: None,
+            ai_summary: None,
+            git_commit_sha: None,
+            metadata: metadata.map(str::to_string),
+            command_category: None,
+        }
+    }
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_forward_skips_seen_and_filtered_events() {
+        let (tx, rx) = mpsc::channel();
+        let mut last_id = 0;
+        for (id, path) in [
+            (1, "src/a.rs"),
+            (2, "docs/b.md"),
+            (1, "src/a.rs"),
+            (3, "src/c.rs"),
+        ] {
+            forward(&tx, event(id, path, None), Some("src/"), &mut last_id).unwrap();
+        }
+
+        let ids: Vec<i64> = rx
+            .try_iter()
+            .map(|update| match update {
+                Update::Event(event) => event.id,
+                _ => panic!("expected an event"),
+            })
+            .collect();
+        assert_eq!(ids, [1, 3]);
+        // Filtered events still move the backfill point past them
+        assert_eq!(last_id, 3);
+    }
+
+    #[test]
+    fn test_apply_counts_and_keeps_scrolled_view_still() {
+        let mut app = App::default();
+        app.apply(Update::Connected);
+        for id in 1..=3 {
+            app.apply(Update::Event(Box::new(event(id, "src/a.rs", None))));
+        }
+        assert_eq!(app.total, 3);
+        assert_eq!(app.tools["Edit"], 3);
+        assert_eq!(app.sessions["abcdef12"], 3);
+
+        app.scroll = 1;
+        app.apply(Update::Event(Box::new(event(4, "src/a.rs", None))));
+        assert_eq!(app.scroll, 2);
+    }
+
+    #[test]
+    fn test_render_shows_status_counts_and_failures() {
+        let mut app = App::default();
+        app.apply(Update::Event(Box::new(event(1, "src/ok.rs", None))));
+        app.apply(Update::Event(Box::new(event(
+            2,
+            "src/broken.rs",
+            Some(r#"{"outcome":"failure","error_excerpt":"old_string not found"}"#),
+        ))));
+        app.apply(Update::Disconnected("daemon restarted".to_string()));
+
+        let screen = screen(&app);
+        assert!(screen.contains("reconnecting: daemon restarted"));
+        assert!(screen.contains("2 events"));
+        assert!(screen.contains("12:34:56"));
+        assert!(screen.contains("src/broken.rs  old_string not found"));
+        assert!(screen.contains("❌"));
+        assert!(screen.contains("abcdef12"));
+    }
+}
@@ -17,11 +17,11 @@
 
 use anyhow::{Context, Result};
 use clap::Parser;
+use diachron_core::{default_transport, DiffStats, Transport};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
-use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::process::Command;
 use tracing::{debug, info, warn};
@@ -173,21 +173,7 @@ fn parse_patch_content(patch: &str) -> Vec<FileOperation> {
     let delete_re = Regex::new(r"\*\*\* Delete File:\s*(.+?)(?:\n|$)").unwrap();
 
     // Count lines for diff summary
-    let lines_added = patch.lines().filter(|l| l.starts_with('+') && !l.starts_with("++")).count();
-    let lines_removed = patch.lines().filter(|l| l.starts_with('-') && !l.starts_with("--")).count();
-
-    let diff_summary = if lines_added > 0 || lines_removed > 0 {
-        let mut parts = Vec::new();
-        if lines_added > 0 {
-            parts.push(format!("+{}", lines_added));
-        }
-        if lines_removed > 0 {
-            parts.push(format!("-{}", lines_removed));
-        }
-        Some(format!("{} lines", parts.join(" ")))
-    } else {
-        None
-    };
+    let diff_summary = DiffStats::from_patch(patch).summary();
 
     for cap in add_re.captures_iter(patch) {
         operations.push(FileOperation {
@@ -304,13 +290,9 @@ fn send_to_daemon(
     cli_version: Option<&str>,
     cwd: Option<&str>,
 ) -> Result<usize> {
-    let socket_path = dirs::home_dir()
-        .context("No home directory")?
-        .join(".diachron")
-        .join("diachron.sock");
-
-    if !socket_path.exists() {
-        warn!("Diachron daemon not running ({})", socket_path.display());
+    let transport = default_transport();
+    if !transport.is_available() {
+        warn!("Diachron daemon not running ({})", transport.describe());
         return Ok(0);
     }
 
@@ -346,7 +328,7 @@ fn send_to_daemon(
             },
         };
 
-        match send_message(&socket_path, &message) {
+        match send_message(transport.as_ref(), &message) {
             Ok(_) => success_count += 1,
             Err(e) => warn!("Failed to send event: {}", e),
         }
@@ -356,15 +338,15 @@ fn send_to_daemon(
 }
 
 /// Send a single message to the daemon
-fn send_message(socket_path: &PathBuf, message: &CaptureMessage) -> Result<()> {
-    let mut stream = UnixStream::connect(socket_path)?;
+fn send_message(transport: &dyn Transport, message: &CaptureMessage) -> Result<()> {
+    let mut stream = transport.connect()?;
 
     let json = serde_json::to_string(message)? + "\n";
     stream.write_all(json.as_bytes())?;
 
     // Read response
     let mut response = String::new();
-    let mut reader = BufReader::new(&stream);
+    let mut reader = BufReader::new(stream);
     reader.read_line(&mut response)?;
 
     let resp: serde_json::Value = serde_json::from_str(&response)?;
@@ -0,0 +1,41 @@
+//! Scalar vs chunked dot products on 384-dimension embeddings.
+//!
+//! Run with `cargo bench -p diachron-core --bench cosine`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use diachron_core::simd::{dot, dot_scalar};
+use diachron_core::{cosine_similarity, EMBEDDING_DIM};
+
+fn vector(seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    (0..EMBEDDING_DIM)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+        })
+        .collect()
+}
+
+fn similarity(c: &mut Criterion) {
+    let a = vector(42);
+    let b = vector(43);
+
+    let mut group = c.benchmark_group("384-dim pair");
+    group.bench_function("dot_scalar", |bench| {
+        bench.iter(|| dot_scalar(black_box(&a), black_box(&b)))
+    });
+    group.bench_function("dot", |bench| {
+        bench.iter(|| dot(black_box(&a), black_box(&b)))
+    });
+    group.bench_function("cosine_similarity", |bench| {
+        bench.iter(|| cosine_similarity(black_box(&a), black_box(&b)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, similarity);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Line counts for patches captured by agent wrappers
+//!
+//! Codex patches (`*** Update File:` envelopes) and unified diffs (Aider's
+//! ```` ```diff ```` fences) both mark added and removed lines with `+`/`-`,
+//! so the wrappers share one counter and one summary format.
+
+/// Added/removed line counts for a patch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// Lines starting with `+` (excluding `+++` file headers)
+    pub added: usize,
+    /// Lines starting with `-` (excluding `---` file headers)
+    pub removed: usize,
+}
+
+impl DiffStats {
+    /// Count added and removed lines in a patch.
+    ///
+    /// # Arguments
+    /// * `patch` - Patch text in Codex or unified diff format
+    ///
+    /// # Returns
+    /// Line counts, ignoring `+++`/`---` file headers
+    pub fn from_patch(patch: &str) -> Self {
+        patch.lines().fold(Self::default(), |mut stats, line| {
+            if line.starts_with('+') && !line.starts_with("+++") {
+                stats.added += 1;
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                stats.removed += 1;
+            }
+            stats
+        })
+    }
+
+    /// Merge counts from another patch.
+    pub fn add(&mut self, other: DiffStats) {
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+
+    /// Whether no lines were added or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+
+    /// Human-readable summary (e.g., "+12 -3 lines").
+    ///
+    /// # Returns
+    /// `None` if no lines changed
+    pub fn summary(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut parts = Vec::new();
+        if self.added > 0 {
+            parts.push(format!("+{}", self.added));
+        }
+        if self.removed > 0 {
+            parts.push(format!("-{}", self.removed));
+        }
+        Some(format!("{} lines", parts.join(" ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_headers_not_counted() {
+        let patch =
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n+extra\n context";
+        let stats = DiffStats::from_patch(patch);
+        assert_eq!(
+            stats,
+            DiffStats {
+                added: 2,
+                removed: 1
+            }
+        );
+        assert_eq!(stats.summary().as_deref(), Some("+2 -1 lines"));
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        assert_eq!(DiffStats::from_patch(" context only").summary(), None);
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for Diachron
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Core error type for Diachron operations.
@@ -29,3 +30,169 @@ pub enum Error {
 
 /// Result alias for core operations.
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// A stored string that names no variant of a typed enum.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown {kind} '{value}'")]
+pub struct UnknownVariant {
+    /// Enum being parsed (`operation`, `command category`)
+    pub kind: &'static str,
+    /// The string as given
+    pub value: String,
+}
+
+/// Error returned by the daemon in place of a result.
+///
+/// Sent as `{ "code", "message", "retryable" }` so clients can branch on
+/// `code` instead of matching message text. `Display` shows the message,
+/// worded as the daemon has always reported it.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "ErrorPayload", from = "ErrorPayload")]
+pub enum DaemonError {
+    /// The embedding model is not loaded, so the request cannot be served
+    #[error("{0}")]
+    ModelNotLoaded(String),
+
+    /// A database query or write failed
+    #[error("{0}")]
+    DatabaseError(String),
+
+    /// The request was malformed or its arguments are invalid
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// Another operation holds a resource the request needs; try again later
+    #[error("{0}")]
+    Busy(String),
+
+    /// The requested item does not exist
+    #[error("{0}")]
+    NotFound(String),
+
+    /// Any other failure
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl DaemonError {
+    /// Stable machine-readable code (`model_not_loaded`, `busy`, ...).
+    pub fn code(&self) -> &'static str {
+        match self {
+            DaemonError::ModelNotLoaded(_) => "model_not_loaded",
+            DaemonError::DatabaseError(_) => "database_error",
+            DaemonError::InvalidRequest(_) => "invalid_request",
+            DaemonError::Busy(_) => "busy",
+            DaemonError::NotFound(_) => "not_found",
+            DaemonError::Internal(_) => "internal",
+        }
+    }
+
+    /// Human-readable message.
+    pub fn message(&self) -> &str {
+        match self {
+            DaemonError::ModelNotLoaded(m)
+            | DaemonError::DatabaseError(m)
+            | DaemonError::InvalidRequest(m)
+            | DaemonError::Busy(m)
+            | DaemonError::NotFound(m)
+            | DaemonError::Internal(m) => m,
+        }
+    }
+
+    /// Whether sending the same request again later may succeed.
+    pub fn retryable(&self) -> bool {
+        matches!(self, DaemonError::Busy(_))
+    }
+
+    /// Prefix the message (`"{context}: {message}"`), keeping the code.
+    ///
+    /// # Arguments
+    /// - `context`: What was being attempted, e.g. `"Reindex failed"`.
+    pub fn context(self, context: &str) -> Self {
+        let message = format!("{}: {}", context, self.message());
+        self.with_message(message)
+    }
+
+    fn with_message(&self, message: String) -> Self {
+        match self {
+            DaemonError::ModelNotLoaded(_) => DaemonError::ModelNotLoaded(message),
+            DaemonError::DatabaseError(_) => DaemonError::DatabaseError(message),
+            DaemonError::InvalidRequest(_) => DaemonError::InvalidRequest(message),
+            DaemonError::Busy(_) => DaemonError::Busy(message),
+            DaemonError::NotFound(_) => DaemonError::NotFound(message),
+            DaemonError::Internal(_) => DaemonError::Internal(message),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DaemonError {
+    fn from(e: rusqlite::Error) -> Self {
+        DaemonError::DatabaseError(format!("Database error: {}", e))
+    }
+}
+
+impl From<Error> for DaemonError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Database(_) => DaemonError::DatabaseError(e.to_string()),
+            _ => DaemonError::Internal(e.to_string()),
+        }
+    }
+}
+
+/// Keeps the code of a `DaemonError` anywhere in the chain (for example one
+/// returned with `?` and wrapped in context); anything else is `Internal`.
+/// The message is the full error text.
+impl From<anyhow::Error> for DaemonError {
+    fn from(e: anyhow::Error) -> Self {
+        let message = e.to_string();
+        match e
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<DaemonError>())
+        {
+            Some(typed) => typed.with_message(message),
+            None => DaemonError::Internal(message),
+        }
+    }
+}
+
+/// Wire form of [`DaemonError`]. Daemons before structured errors sent a
+/// bare message string, which reads as `internal`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ErrorPayload {
+    Structured {
+        code: String,
+        message: String,
+        #[serde(default)]
+        retryable: bool,
+    },
+    Legacy(String),
+}
+
+impl From<DaemonError> for ErrorPayload {
+    fn from(e: DaemonError) -> Self {
+        ErrorPayload::Structured {
+            code: e.code().to_string(),
+            retryable: e.retryable(),
+            message: e.message().to_string(),
+        }
+    }
+}
+
+impl From<ErrorPayload> for DaemonError {
+    fn from(payload: ErrorPayload) -> Self {
+        let (code, message) = match payload {
+            ErrorPayload::Structured { code, message, .. } => (code, message),
+            ErrorPayload::Legacy(message) => (String::new(), message),
+        };
+        match code.as_str() {
+            "model_not_loaded" => DaemonError::ModelNotLoaded(message),
+            "database_error" => DaemonError::DatabaseError(message),
+            "invalid_request" => DaemonError::InvalidRequest(message),
+            "busy" => DaemonError::Busy(message),
+            "not_found" => DaemonError::NotFound(message),
+            _ => DaemonError::Internal(message),
+        }
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! This module generates structured evidence packs that can be:
 //! - Exported as JSON for GitHub Actions
+//! - Exported as SARIF for code-scanning annotations
 //! - Rendered as Markdown for PR comments
+//! - Rendered as self-contained HTML for archiving
 //! - Stored for audit trails
 //!
 //! # Evidence Pack Structure
@@ -22,11 +24,19 @@
 use serde::{Deserialize, Serialize};
 
 use crate::hash_chain::ChainVerificationResult;
-use crate::pr_correlation::{CommitEvidence, PREvidence, PRSummary};
+use crate::pr_correlation::{CommitEvidence, MatchConfidence, PREvidence, PRSummary};
+use crate::template::{render_template, TemplateError};
+use crate::types::{EvidencePackResult, StoredEvent};
 
 /// Diachron version for evidence packs.
 pub const DIACHRON_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// SARIF rule for changes that couldn't be linked to any commit.
+pub const SARIF_RULE_UNMATCHED: &str = "diachron/unmatched-change";
+
+/// SARIF rule for changes linked to a commit only by time window.
+pub const SARIF_RULE_LOW_CONFIDENCE: &str = "diachron/low-confidence-commit";
+
 /// Complete evidence pack for a pull request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvidencePack {
@@ -48,6 +58,9 @@ pub struct EvidencePack {
     pub coverage_pct: f32,
     /// Unmatched event count
     pub unmatched_count: usize,
+    /// Events that couldn't be matched to any commit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched_events: Vec<StoredEvent>,
 }
 
 /// Verification status of the evidence.
@@ -131,117 +144,300 @@ pub fn generate_evidence_pack(
         intent,
         coverage_pct: pr_evidence.coverage_pct,
         unmatched_count: pr_evidence.unmatched_events.len(),
+        unmatched_events: pr_evidence.unmatched_events,
+    }
+}
+
+impl From<EvidencePackResult> for EvidencePack {
+    fn from(result: EvidencePackResult) -> Self {
+        Self {
+            pr_id: result.pr_id,
+            generated_at: result.generated_at,
+            diachron_version: result.diachron_version,
+            summary: PRSummary {
+                files_changed: result.summary.files_changed,
+                lines_added: result.summary.lines_added,
+                lines_removed: result.summary.lines_removed,
+                tool_operations: result.summary.tool_operations,
+                sessions: result.summary.sessions,
+            },
+            commits: result
+                .commits
+                .into_iter()
+                .map(|c| CommitEvidence {
+                    sha: c.sha,
+                    message: c.message,
+                    events: c.events,
+                    confidence: MatchConfidence::parse(&c.confidence)
+                        .unwrap_or(MatchConfidence::Low),
+                })
+                .collect(),
+            verification: VerificationStatus {
+                chain_verified: result.verification.chain_verified,
+                tests_executed: result.verification.tests_executed,
+                build_succeeded: result.verification.build_succeeded,
+                human_reviewed: result.verification.human_reviewed,
+            },
+            intent: result.intent,
+            coverage_pct: result.coverage_pct,
+            unmatched_count: result.unmatched_count,
+            unmatched_events: result.unmatched_events,
+        }
     }
 }
 
+/// Events listed per commit in the Markdown narrative; the rest are counted.
+pub const NARRATIVE_EVENTS_PER_COMMIT: usize = 5;
+
+/// Default Markdown narrative layout, used when no template is given.
+///
+/// Copy it as a starting point for `--output-template`.
+pub const DEFAULT_NARRATIVE_TEMPLATE: &str = include_str!("../templates/pr-comment.md");
+
+/// Build the values a narrative template can reference.
+///
+/// Mirrors the pack's JSON shape, plus display fields: `change_ref`,
+/// `coverage` (one decimal), and per commit `sha_short`, `title` (first
+/// message line), `more_events` and at most
+/// [`NARRATIVE_EVENTS_PER_COMMIT`] `events` with `-` for missing files
+/// and operations.
+fn narrative_context(pack: &EvidencePack, change_ref: &str) -> serde_json::Value {
+    let commits: Vec<serde_json::Value> = pack
+        .commits
+        .iter()
+        .map(|commit| {
+            let events: Vec<serde_json::Value> = commit
+                .events
+                .iter()
+                .take(NARRATIVE_EVENTS_PER_COMMIT)
+                .map(|event| {
+                    serde_json::json!({
+                        "tool_name": event.tool_name,
+                        "file_path": event.file_path.as_deref().unwrap_or("-"),
+                        "operation": event.operation.map_or("-", |op| op.as_str()),
+                        "timestamp": event.timestamp,
+                        "session_id": event.session_id,
+                        "diff_summary": event.diff_summary,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "sha": commit.sha,
+                "sha_short": &commit.sha[..7.min(commit.sha.len())],
+                "message": commit.message,
+                "title": commit.message.as_deref().and_then(|msg| msg.lines().next()),
+                "confidence": commit.confidence.as_str(),
+                "events": events,
+                "event_count": commit.events.len(),
+                "more_events": commit.events.len().saturating_sub(NARRATIVE_EVENTS_PER_COMMIT),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "pr_id": pack.pr_id,
+        "change_ref": change_ref,
+        "generated_at": pack.generated_at,
+        "diachron_version": pack.diachron_version,
+        "intent": pack.intent.as_deref().filter(|intent| !intent.is_empty()),
+        "summary": pack.summary,
+        "commits": commits,
+        "verification": pack.verification,
+        "coverage_pct": pack.coverage_pct,
+        "coverage": format!("{:.1}", pack.coverage_pct),
+        "unmatched_count": pack.unmatched_count,
+    })
+}
+
 /// Render an evidence pack as Markdown for PR comments.
 ///
 /// # Arguments
 ///
 /// * `pack` - The evidence pack to render
+/// * `change_ref` - How the header names the change (`PR #142`, `MR !142`)
+/// * `template` - Mustache-style layout (see [`crate::template`]);
+///   `None` uses [`DEFAULT_NARRATIVE_TEMPLATE`]
+///
+/// # Returns
+///
+/// Markdown string suitable for a GitHub or GitLab comment
+///
+/// # Errors
+///
+/// Returns `TemplateError` if the template is malformed
+pub fn render_markdown_narrative(
+    pack: &EvidencePack,
+    change_ref: &str,
+    template: Option<&str>,
+) -> Result<String, TemplateError> {
+    render_template(
+        template.unwrap_or(DEFAULT_NARRATIVE_TEMPLATE),
+        &narrative_context(pack, change_ref),
+    )
+}
+
+/// Inline stylesheet for `render_html` (no external assets).
+const HTML_STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; color: #1f2328; }
+h1 { font-size: 1.6em; border-bottom: 1px solid #d0d7de; padding-bottom: .3em; }
+blockquote { margin: 0; padding: 0 1em; color: #59636e; border-left: .25em solid #d0d7de; }
+.gauge { background: #eaeef2; border-radius: 6px; height: 14px; overflow: hidden; }
+.gauge > div { background: #1f883d; height: 100%; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: .5em 0; padding: .5em 1em; }
+summary { cursor: pointer; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; background: #f6f8fa; padding: .1em .3em; border-radius: 4px; }
+.conf { font-size: .8em; font-weight: 600; padding: .1em .5em; border-radius: 1em; background: #eaeef2; }
+.conf-HIGH { background: #dafbe1; } .conf-MEDIUM { background: #fff8c5; } .conf-LOW { background: #ffebe9; }
+.empty { color: #59636e; font-style: italic; }
+ul.checklist { list-style: none; padding-left: 0; }
+footer { margin-top: 2em; color: #59636e; font-size: .85em; }
+";
+
+/// Escape text for safe inclusion in HTML content and attribute values.
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render an evidence pack as a single self-contained HTML page.
+///
+/// The page has inline CSS and no external assets: a coverage gauge, a
+/// collapsible `<details>` tree of events per commit, and the verification
+/// checklist. All event and intent text is HTML-escaped.
+///
+/// # Arguments
+///
+/// * `pack` - The evidence pack to render
 ///
 /// # Returns
 ///
-/// Markdown string suitable for GitHub PR comment
-pub fn render_markdown_narrative(pack: &EvidencePack) -> String {
-    let mut md = String::new();
+/// Complete HTML document
+pub fn render_html(pack: &EvidencePack) -> String {
+    let mut html = String::new();
+    let title = format!("PR #{}: AI Provenance Evidence", pack.pr_id);
 
-    // Header
-    md.push_str(&format!("## PR #{}: AI Provenance Evidence\n\n", pack.pr_id));
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", html_escape(&title)));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", HTML_STYLE));
+    html.push_str(&format!("<h1>{}</h1>\n", html_escape(&title)));
 
     // Intent section (if available)
     if let Some(ref intent) = pack.intent {
-        md.push_str("### Intent\n");
-        md.push_str(&format!("> {}\n\n", intent));
+        html.push_str("<h2>Intent</h2>\n");
+        html.push_str(&format!(
+            "<blockquote>{}</blockquote>\n",
+            html_escape(intent)
+        ));
     }
 
     // Summary section
-    md.push_str("### What Changed\n");
-    md.push_str(&format!(
-        "- **Files modified**: {}\n",
+    html.push_str("<h2>What Changed</h2>\n<ul>\n");
+    html.push_str(&format!(
+        "<li><strong>Files modified</strong>: {}</li>\n",
         pack.summary.files_changed
     ));
-    md.push_str(&format!(
-        "- **Lines**: +{} / -{}\n",
+    html.push_str(&format!(
+        "<li><strong>Lines</strong>: +{} / -{}</li>\n",
         pack.summary.lines_added, pack.summary.lines_removed
     ));
-    md.push_str(&format!(
-        "- **Tool operations**: {}\n",
+    html.push_str(&format!(
+        "<li><strong>Tool operations</strong>: {}</li>\n",
         pack.summary.tool_operations
     ));
-    md.push_str(&format!("- **Sessions**: {}\n\n", pack.summary.sessions));
+    html.push_str(&format!(
+        "<li><strong>Sessions</strong>: {}</li>\n</ul>\n",
+        pack.summary.sessions
+    ));
 
-    // Evidence trail section
-    md.push_str("### Evidence Trail\n");
-    md.push_str(&format!(
-        "- **Coverage**: {:.1}% of events matched to commits",
-        pack.coverage_pct
+    // Coverage gauge
+    let coverage = pack.coverage_pct.clamp(0.0, 100.0);
+    html.push_str("<h2>Evidence Trail</h2>\n");
+    html.push_str(&format!(
+        "<p><strong>Coverage</strong>: {:.1}% of events matched to commits",
+        coverage
     ));
     if pack.unmatched_count > 0 {
-        md.push_str(&format!(" ({} unmatched)", pack.unmatched_count));
+        html.push_str(&format!(" ({} unmatched)", pack.unmatched_count));
     }
-    md.push_str("\n");
+    html.push_str("</p>\n");
+    html.push_str(&format!(
+        "<div class=\"gauge\" role=\"meter\" aria-valuemin=\"0\" aria-valuemax=\"100\" aria-valuenow=\"{:.1}\"><div style=\"width: {:.1}%\"></div></div>\n",
+        coverage, coverage
+    ));
 
+    // Per-commit event tree
+    if pack.commits.is_empty() {
+        html.push_str("<p class=\"empty\">No correlated changes.</p>\n");
+    }
     for commit in &pack.commits {
         let sha_short = &commit.sha[..7.min(commit.sha.len())];
-        md.push_str(&format!("\n**Commit `{}`**", sha_short));
+        let confidence = commit.confidence.as_str();
+        html.push_str("<details>\n<summary>");
+        html.push_str(&format!("<code>{}</code>", html_escape(sha_short)));
         if let Some(ref msg) = commit.message {
             let first_line = msg.lines().next().unwrap_or(msg);
-            md.push_str(&format!(": {}", first_line));
+            html.push_str(&format!(" {}", html_escape(first_line)));
         }
-        md.push_str(&format!(" ({})\n", commit.confidence.as_str()));
-
+        html.push_str(&format!(
+            " <span class=\"conf conf-{}\">{}</span> ({} events)</summary>\n<ul>\n",
+            confidence,
+            confidence,
+            commit.events.len()
+        ));
         for event in &commit.events {
-            let tool = &event.tool_name;
-            let file = event.file_path.as_deref().unwrap_or("-");
-            let op = event.operation.as_deref().unwrap_or("-");
-            md.push_str(&format!("  - `{}` {} → {}\n", tool, op, file));
+            html.push_str(&format!(
+                "<li><code>{}</code> {} → {}",
+                html_escape(&event.tool_name),
+                html_escape(event.operation.map_or("-", |op| op.as_str())),
+                html_escape(event.file_path.as_deref().unwrap_or("-"))
+            ));
+            if let Some(ref diff) = event.diff_summary {
+                html.push_str(&format!(" <em>{}</em>", html_escape(diff)));
+            }
+            html.push_str("</li>\n");
         }
+        html.push_str("</ul>\n</details>\n");
     }
-    md.push_str("\n");
-
-    // Verification section
-    md.push_str("### Verification\n");
-    md.push_str(&format!(
-        "- [{}] Hash chain integrity\n",
-        if pack.verification.chain_verified {
-            "x"
-        } else {
-            " "
-        }
+
+    // Verification checklist
+    let check = |done: bool| if done { "☑" } else { "☐" };
+    html.push_str("<h2>Verification</h2>\n<ul class=\"checklist\">\n");
+    html.push_str(&format!(
+        "<li>{} Hash chain integrity</li>\n",
+        check(pack.verification.chain_verified)
     ));
-    md.push_str(&format!(
-        "- [{}] Tests executed after changes\n",
-        if pack.verification.tests_executed {
-            "x"
-        } else {
-            " "
-        }
+    html.push_str(&format!(
+        "<li>{} Tests executed after changes</li>\n",
+        check(pack.verification.tests_executed)
     ));
-    md.push_str(&format!(
-        "- [{}] Build succeeded\n",
-        if pack.verification.build_succeeded {
-            "x"
-        } else {
-            " "
-        }
+    html.push_str(&format!(
+        "<li>{} Build succeeded</li>\n",
+        check(pack.verification.build_succeeded)
     ));
-    md.push_str(&format!(
-        "- [{}] Human review\n\n",
-        if pack.verification.human_reviewed {
-            "x"
-        } else {
-            " "
-        }
+    html.push_str(&format!(
+        "<li>{} Human review</li>\n</ul>\n",
+        check(pack.verification.human_reviewed)
     ));
 
     // Footer
-    md.push_str(&format!(
-        "---\n*Generated by [Diachron](https://github.com/wolfiesch/diachron) v{} at {}*\n",
-        pack.diachron_version, pack.generated_at
+    html.push_str(&format!(
+        "<footer>Generated by <a href=\"https://github.com/wolfiesch/diachron\">Diachron</a> v{} at {}</footer>\n",
+        html_escape(&pack.diachron_version),
+        html_escape(&pack.generated_at)
     ));
+    html.push_str("</body>\n</html>\n");
 
-    md
+    html
 }
 
 /// Export evidence pack as JSON string.
@@ -257,11 +453,121 @@ pub fn export_json(pack: &EvidencePack) -> Result<String, serde_json::Error> {
     serde_json::to_string_pretty(pack)
 }
 
+/// Export evidence pack as a SARIF 2.1.0 log for code-scanning annotations.
+///
+/// Each unmatched event becomes a `diachron/unmatched-change` warning and each
+/// event in a LOW-confidence commit a `diachron/low-confidence-commit` note,
+/// located at the event's file. Events without a file path are skipped.
+///
+/// # Arguments
+///
+/// * `pack` - The evidence pack to export
+///
+/// # Returns
+///
+/// SARIF log as a JSON value
+pub fn export_sarif(pack: &EvidencePack) -> serde_json::Value {
+    use serde_json::json;
+
+    let rules = json!([
+        {
+            "id": SARIF_RULE_UNMATCHED,
+            "name": "UnmatchedChange",
+            "shortDescription": { "text": "AI change not linked to any commit" },
+            "fullDescription": {
+                "text": "Diachron recorded this change but could not correlate it with a commit in the PR."
+            },
+            "defaultConfiguration": { "level": "warning" }
+        },
+        {
+            "id": SARIF_RULE_LOW_CONFIDENCE,
+            "name": "LowConfidenceCommit",
+            "shortDescription": { "text": "AI change linked to a commit by time window only" },
+            "fullDescription": {
+                "text": "Diachron linked this change to a commit without a session or commit SHA match."
+            },
+            "defaultConfiguration": { "level": "note" }
+        }
+    ]);
+
+    let result = |rule_index: usize, event: &StoredEvent, text: String| {
+        let rule_id = [SARIF_RULE_UNMATCHED, SARIF_RULE_LOW_CONFIDENCE][rule_index];
+        let level = ["warning", "note"][rule_index];
+        event.file_path.as_ref().map(|path| {
+            json!({
+                "ruleId": rule_id,
+                "ruleIndex": rule_index,
+                "level": level,
+                "message": { "text": text },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": { "uri": path } }
+                }],
+                "properties": {
+                    "event_id": event.id,
+                    "tool_name": event.tool_name,
+                    "session_id": event.session_id,
+                    "timestamp": event.timestamp
+                }
+            })
+        })
+    };
+
+    let mut results: Vec<serde_json::Value> = pack
+        .unmatched_events
+        .iter()
+        .filter_map(|event| {
+            let text = format!(
+                "{} ({}) was not linked to any commit in PR #{}",
+                event.tool_name,
+                event.operation.map_or("-", |op| op.as_str()),
+                pack.pr_id
+            );
+            result(0, event, text)
+        })
+        .collect();
+
+    for commit in &pack.commits {
+        if commit.confidence != MatchConfidence::Low {
+            continue;
+        }
+        let sha_short = &commit.sha[..7.min(commit.sha.len())];
+        results.extend(commit.events.iter().filter_map(|event| {
+            let text = format!(
+                "{} ({}) linked to commit {} by time window only (LOW confidence)",
+                event.tool_name,
+                event.operation.map_or("-", |op| op.as_str()),
+                sha_short
+            );
+            result(1, event, text)
+        }));
+    }
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "diachron",
+                    "version": pack.diachron_version,
+                    "informationUri": "https://github.com/wolfiesch/diachron",
+                    "rules": rules
+                }
+            },
+            "results": results,
+            "properties": {
+                "pr_id": pack.pr_id,
+                "coverage_pct": pack.coverage_pct,
+                "unmatched_count": pack.unmatched_count
+            }
+        }]
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pr_correlation::MatchConfidence;
-    use crate::types::StoredEvent;
+    use crate::types::Operation;
 
     fn mock_event(tool: &str, file: &str, op: &str) -> StoredEvent {
         StoredEvent {
@@ -271,12 +577,13 @@ mod tests {
             session_id: Some("session-1".to_string()),
             tool_name: tool.to_string(),
             file_path: Some(file.to_string()),
-            operation: Some(op.to_string()),
+            operation: Some(Operation::from_stored(op)),
             diff_summary: Some("+10 lines".to_string()),
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: None,
+            command_category: None,
         }
     }
 
@@ -311,9 +618,10 @@ mod tests {
             intent: Some("Fix the 401 errors on page refresh".to_string()),
             coverage_pct: 100.0,
             unmatched_count: 0,
+            unmatched_events: vec![],
         };
 
-        let md = render_markdown_narrative(&pack);
+        let md = render_markdown_narrative(&pack, "PR #142", None).unwrap();
 
         assert!(md.contains("## PR #142"));
         assert!(md.contains("Fix the 401 errors"));
@@ -322,6 +630,57 @@ mod tests {
         assert!(md.contains("[ ] Human review"));
     }
 
+    #[test]
+    fn test_render_markdown_narrative_with_template() {
+        let pack = EvidencePack {
+            pr_id: 7,
+            generated_at: "2026-01-11T00:00:00Z".to_string(),
+            diachron_version: "0.3.0".to_string(),
+            summary: PRSummary {
+                files_changed: 3,
+                lines_added: 12,
+                lines_removed: 4,
+                tool_operations: 6,
+                sessions: 1,
+            },
+            commits: vec![CommitEvidence {
+                sha: "abc1234567890".to_string(),
+                message: Some("Add retries\n\nLonger body".to_string()),
+                events: (0..6)
+                    .map(|i| mock_event("Edit", &format!("src/f{}.rs", i), "modify"))
+                    .collect(),
+                confidence: MatchConfidence::Medium,
+            }],
+            verification: VerificationStatus::default(),
+            intent: None,
+            coverage_pct: 87.54,
+            unmatched_count: 0,
+            unmatched_events: vec![],
+        };
+        let template = "{{change_ref}}: {{summary.files_changed}} files, {{coverage}}%\n\
+            {{^intent}}\n\
+            No intent recorded.\n\
+            {{/intent}}\n\
+            {{#commits}}\n\
+            * {{sha_short}} {{title}} [{{confidence}}] {{event_count}} events, {{more_events}} hidden\n\
+            {{/commits}}\n";
+
+        let md = render_markdown_narrative(&pack, "MR !7", Some(template)).unwrap();
+        assert_eq!(
+            md,
+            "MR !7: 3 files, 87.5%\n\
+             No intent recorded.\n\
+             * abc1234 Add retries [MEDIUM] 6 events, 1 hidden\n"
+        );
+
+        // The default layout lists five events and counts the rest
+        let md = render_markdown_narrative(&pack, "PR #7", None).unwrap();
+        assert!(md.contains("src/f4.rs"));
+        assert!(!md.contains("src/f5.rs"));
+        assert!(md.contains("  - *...and 1 more*\n"));
+        assert!(!md.contains("### Intent"));
+    }
+
     #[test]
     fn test_export_json() {
         let pack = EvidencePack {
@@ -340,10 +699,117 @@ mod tests {
             intent: None,
             coverage_pct: 100.0,
             unmatched_count: 0,
+            unmatched_events: vec![],
         };
 
         let json = export_json(&pack).unwrap();
         assert!(json.contains("\"pr_id\": 42"));
         assert!(json.contains("\"diachron_version\""));
     }
+
+    #[test]
+    fn test_export_sarif() {
+        // Events without a file path can't be located and are skipped
+        let mut command = mock_event("Bash", "", "execute");
+        command.file_path = None;
+        let pack = EvidencePack {
+            pr_id: 42,
+            generated_at: "2026-01-11T00:00:00Z".to_string(),
+            diachron_version: "0.3.0".to_string(),
+            summary: PRSummary {
+                files_changed: 2,
+                lines_added: 10,
+                lines_removed: 0,
+                tool_operations: 3,
+                sessions: 1,
+            },
+            commits: vec![
+                CommitEvidence {
+                    sha: "abc1234567890".to_string(),
+                    message: None,
+                    events: vec![mock_event("Write", "src/auth.rs", "create")],
+                    confidence: MatchConfidence::High,
+                },
+                CommitEvidence {
+                    sha: "def4567890123".to_string(),
+                    message: None,
+                    events: vec![mock_event("Write", "src/db.rs", "create")],
+                    confidence: MatchConfidence::Low,
+                },
+            ],
+            verification: VerificationStatus::default(),
+            intent: None,
+            coverage_pct: 66.7,
+            unmatched_count: 1,
+            unmatched_events: vec![mock_event("Edit", "src/lib.rs", "modify"), command],
+        };
+
+        let sarif = export_sarif(&pack);
+        assert_eq!(sarif["version"], "2.1.0");
+        assert!(sarif["$schema"].is_string());
+
+        // Required by the SARIF 2.1.0 schema: runs[].tool.driver.name
+        let driver = &sarif["runs"][0]["tool"]["driver"];
+        assert_eq!(driver["name"], "diachron");
+        let rules = driver["rules"].as_array().unwrap();
+        assert!(rules.iter().all(|r| r["id"].is_string()));
+
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], SARIF_RULE_UNMATCHED);
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/lib.rs"
+        );
+        assert_eq!(results[1]["ruleId"], SARIF_RULE_LOW_CONFIDENCE);
+        for result in results {
+            let index = result["ruleIndex"].as_u64().unwrap() as usize;
+            assert_eq!(rules[index]["id"], result["ruleId"]);
+            assert!(result["message"]["text"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_handles_empty() {
+        let mut event = mock_event("Write", "src/<script>.rs", "create");
+        event.diff_summary = Some("a & b".to_string());
+        let mut pack = EvidencePack {
+            pr_id: 7,
+            generated_at: "2026-01-11T00:00:00Z".to_string(),
+            diachron_version: "0.3.0".to_string(),
+            summary: PRSummary {
+                files_changed: 1,
+                lines_added: 1,
+                lines_removed: 0,
+                tool_operations: 1,
+                sessions: 1,
+            },
+            commits: vec![CommitEvidence {
+                sha: "abc1234567890".to_string(),
+                message: None,
+                events: vec![event],
+                confidence: MatchConfidence::Medium,
+            }],
+            verification: VerificationStatus::default(),
+            intent: Some("\"quoted\" <b>intent</b>".to_string()),
+            coverage_pct: 50.0,
+            unmatched_count: 1,
+            unmatched_events: vec![],
+        };
+
+        let html = render_html(&pack);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("src/&lt;script&gt;.rs"));
+        assert!(html.contains("a &amp; b"));
+        assert!(html.contains("&quot;quoted&quot; &lt;b&gt;intent&lt;/b&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<details>"));
+        assert!(html.contains("width: 50.0%"));
+        assert!(!html.contains("No correlated changes"));
+
+        pack.commits.clear();
+        let html = render_html(&pack);
+        assert!(html.contains("No correlated changes"));
+        assert!(!html.contains("<details>"));
+    }
 }
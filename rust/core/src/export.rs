@@ -0,0 +1,576 @@
+//! Portable JSON export/import of the provenance history
+//!
+//! An export document holds every event (with its hash-chain fields) and
+//! every exchange, so a history can be backed up outside SQLite or moved to
+//! another machine.
+//!
+//! # Import semantics
+//!
+//! Imported events are appended to the local chain: each gets the next local
+//! ID and is re-linked to the current chain head. Importing a dump into an
+//! empty database reproduces the original hashes exactly. When the chain
+//! diverges (the database already had events), the original hash is kept in
+//! the event metadata as `imported_hash` so the event stays traceable.
+//!
+//! Import is idempotent: events whose original hash is already present
+//! (as `event_hash` or `imported_hash`) and exchanges whose ID already
+//! exists are skipped. Events exported before the hash chain existed carry
+//! no hash; they are recorded under a digest of their exported content
+//! instead, so importing them twice is skipped too.
+
+use std::io::Write;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::hash_chain::{
+    compute_event_hash, compute_field_hashes, get_last_event_hash, EventHashInput, GENESIS_HASH,
+};
+use crate::schema::project_from_metadata;
+use crate::types::Exchange;
+
+/// Version of the export document layout.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// An event as written to an export document.
+///
+/// Hashes are hex-encoded. Embeddings are omitted since they depend on the
+/// embedding model; run `diachron memory reindex` after importing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedEvent {
+    pub id: i64,
+    pub timestamp: String,
+    pub timestamp_display: Option<String>,
+    pub session_id: Option<String>,
+    pub tool_name: String,
+    pub file_path: Option<String>,
+    pub operation: Option<String>,
+    pub diff_summary: Option<String>,
+    pub raw_input: Option<String>,
+    pub ai_summary: Option<String>,
+    pub git_commit_sha: Option<String>,
+    pub metadata: Option<String>,
+    pub prev_hash: Option<String>,
+    pub event_hash: Option<String>,
+    pub content_hash: Option<String>,
+    #[serde(default)]
+    pub context_hash: Option<String>,
+    /// Semantic signature of the written content (fingerprint blame)
+    #[serde(default)]
+    pub semantic_sig: Option<String>,
+    #[serde(default)]
+    pub old_content: Option<String>,
+    #[serde(default)]
+    pub new_content: Option<String>,
+}
+
+/// A complete export document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportDocument {
+    pub version: u32,
+    pub exported_at: String,
+    pub events: Vec<ExportedEvent>,
+    pub exchanges: Vec<Exchange>,
+}
+
+/// Number of rows written by an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportCounts {
+    pub events: u64,
+    pub exchanges: u64,
+}
+
+/// Rows added and skipped by an import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportCounts {
+    pub events_imported: u64,
+    pub events_skipped: u64,
+    pub exchanges_imported: u64,
+    pub exchanges_skipped: u64,
+}
+
+/// Stream all events and exchanges as an export document.
+///
+/// Rows are serialized one at a time, so memory use does not grow with the
+/// size of the history.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection (read-only is sufficient)
+/// * `since` - Only export rows with a timestamp at or after this ISO timestamp
+/// * `out` - Destination for the JSON document
+///
+/// # Returns
+///
+/// Counts of exported events and exchanges
+pub fn write_export<W: Write>(
+    conn: &Connection,
+    since: Option<&str>,
+    mut out: W,
+) -> Result<ExportCounts> {
+    let since = since.unwrap_or("");
+    let mut counts = ExportCounts::default();
+
+    write!(
+        out,
+        "{{\"version\":{},\"exported_at\":{},\"events\":[",
+        EXPORT_FORMAT_VERSION,
+        serde_json::to_string(&chrono::Utc::now().to_rfc3339())?
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
+                operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                prev_hash, event_hash, content_hash, context_hash, semantic_sig,
+                old_content, new_content
+         FROM events
+         WHERE timestamp >= ?1
+         ORDER BY id ASC",
+    )?;
+    let mut rows = stmt.query([since])?;
+    while let Some(row) = rows.next()? {
+        let event = ExportedEvent {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            timestamp_display: row.get(2)?,
+            session_id: row.get(3)?,
+            tool_name: row.get(4)?,
+            file_path: row.get(5)?,
+            operation: row.get(6)?,
+            diff_summary: row.get(7)?,
+            raw_input: row.get(8)?,
+            ai_summary: row.get(9)?,
+            git_commit_sha: row.get(10)?,
+            metadata: row.get(11)?,
+            prev_hash: row.get::<_, Option<Vec<u8>>>(12)?.map(hex::encode),
+            event_hash: row.get::<_, Option<Vec<u8>>>(13)?.map(hex::encode),
+            content_hash: row.get::<_, Option<Vec<u8>>>(14)?.map(hex::encode),
+            context_hash: row.get::<_, Option<Vec<u8>>>(15)?.map(hex::encode),
+            semantic_sig: row.get::<_, Option<Vec<u8>>>(16)?.map(hex::encode),
+            old_content: row.get(17)?,
+            new_content: row.get(18)?,
+        };
+        if counts.events > 0 {
+            out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut out, &event)?;
+        counts.events += 1;
+    }
+
+    out.write_all(b"],\"exchanges\":[")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, project, session_id, user_message, assistant_message,
+                tool_calls, archive_path, line_start, line_end, summary, git_branch, cwd
+         FROM exchanges
+         WHERE timestamp >= ?1
+         ORDER BY timestamp ASC",
+    )?;
+    let mut rows = stmt.query([since])?;
+    while let Some(row) = rows.next()? {
+        let exchange = Exchange {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            project: row.get(2)?,
+            session_id: row.get(3)?,
+            user_message: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+            assistant_message: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            tool_calls: row.get(6)?,
+            archive_path: row.get(7)?,
+            line_start: row.get(8)?,
+            line_end: row.get(9)?,
+            embedding: None,
+            summary: row.get(10)?,
+            git_branch: row.get(11)?,
+            cwd: row.get(12)?,
+        };
+        if counts.exchanges > 0 {
+            out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut out, &exchange)?;
+        counts.exchanges += 1;
+    }
+
+    out.write_all(b"]}")?;
+    out.flush()?;
+    Ok(counts)
+}
+
+/// Import a batch of events and exchanges in a single transaction.
+///
+/// Events are appended in the order given, which should be the export
+/// order (ascending original ID) to preserve the chain sequence.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection
+/// * `events` - Events from an export document
+/// * `exchanges` - Exchanges from an export document
+///
+/// # Returns
+///
+/// Counts of imported and already-present rows
+pub fn import_batch(
+    conn: &Connection,
+    events: &[ExportedEvent],
+    exchanges: &[Exchange],
+) -> Result<ImportCounts> {
+    let tx = conn.unchecked_transaction()?;
+    let mut counts = ImportCounts::default();
+
+    for event in events {
+        if import_event(&tx, event)? {
+            counts.events_imported += 1;
+        } else {
+            counts.events_skipped += 1;
+        }
+    }
+    for exchange in exchanges {
+        if import_exchange(&tx, exchange)? {
+            counts.exchanges_imported += 1;
+        } else {
+            counts.exchanges_skipped += 1;
+        }
+    }
+
+    tx.commit()?;
+    Ok(counts)
+}
+
+/// Find an event by its hash (`?1`) or the original hash it was imported
+/// under (`?2`). Two lookups rather than an OR, so each uses its index.
+const FIND_IMPORTED_SQL: &str = "SELECT id FROM events WHERE event_hash = ?1
+     UNION ALL
+     SELECT id FROM events WHERE json_extract(metadata, '$.imported_hash') = ?2
+     LIMIT 1";
+
+/// Append an exported event to the local hash chain.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection
+/// * `event` - Event from an export document
+///
+/// # Returns
+///
+/// `true` if the event was inserted, `false` if it was already present
+pub fn import_event(conn: &Connection, event: &ExportedEvent) -> Result<bool> {
+    let mut input = EventHashInput {
+        id: event.id,
+        timestamp: event.timestamp.clone(),
+        tool_name: event.tool_name.clone(),
+        file_path: event.file_path.clone(),
+        operation: event.operation.clone().unwrap_or_default(),
+        diff_summary: event.diff_summary.clone(),
+        raw_input: event.raw_input.clone(),
+        session_id: event.session_id.clone(),
+        git_commit_sha: event.git_commit_sha.clone(),
+        metadata: event.metadata.clone(),
+    };
+    let original_hash = event
+        .event_hash
+        .as_deref()
+        .and_then(|h| hex::decode(h).ok())
+        .filter(|h| h.len() == 32);
+    let original = match original_hash {
+        Some(ref hash) => hex::encode(hash),
+        // Unchained event: stands in for the hash it never had
+        None => hex::encode(compute_event_hash(&input, &GENESIS_HASH)),
+    };
+
+    let exists: Option<i64> = conn
+        .query_row(FIND_IMPORTED_SQL, params![original_hash, original], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if exists.is_some() {
+        return Ok(false);
+    }
+
+    let prev_hash = get_last_event_hash(conn)?;
+    input.id = conn.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM events", [], |row| {
+        row.get(0)
+    })?;
+    let mut event_hash = compute_event_hash(&input, &prev_hash);
+
+    // The chain diverged from the exporting database; remember where it came from
+    if original_hash.is_none_or(|hash| event_hash.as_slice() != hash.as_slice()) {
+        input.metadata = Some(with_imported_hash(event.metadata.as_deref(), &original));
+        event_hash = compute_event_hash(&input, &prev_hash);
+    }
+
+    let field_hashes = serde_json::to_string(&compute_field_hashes(&input))?;
+    let project = input.metadata.as_deref().and_then(project_from_metadata);
+    let decode = |hash: &Option<String>| hash.as_deref().and_then(|h| hex::decode(h).ok());
+
+    conn.execute(
+        "INSERT INTO events (
+            id, timestamp, timestamp_display, session_id, tool_name, file_path,
+            operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+            prev_hash, event_hash, field_hashes, content_hash, context_hash, semantic_sig,
+            old_content, new_content, project
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                  ?18, ?19, ?20, ?21)",
+        params![
+            input.id,
+            input.timestamp,
+            event.timestamp_display,
+            input.session_id,
+            input.tool_name,
+            input.file_path,
+            event.operation,
+            input.diff_summary,
+            input.raw_input,
+            event.ai_summary,
+            input.git_commit_sha,
+            input.metadata,
+            prev_hash.as_slice(),
+            event_hash.as_slice(),
+            field_hashes,
+            decode(&event.content_hash),
+            decode(&event.context_hash),
+            decode(&event.semantic_sig),
+            event.old_content,
+            event.new_content,
+            project,
+        ],
+    )?;
+
+    Ok(true)
+}
+
+/// Insert an exported exchange unless one with the same ID exists.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection
+/// * `exchange` - Exchange from an export document
+///
+/// # Returns
+///
+/// `true` if the exchange was inserted, `false` if it was already present
+pub fn import_exchange(conn: &Connection, exchange: &Exchange) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO exchanges (
+            id, timestamp, project, session_id, user_message,
+            assistant_message, tool_calls, archive_path, line_start,
+            line_end, summary, git_branch, cwd
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            exchange.id,
+            exchange.timestamp,
+            exchange.project,
+            exchange.session_id,
+            exchange.user_message,
+            exchange.assistant_message,
+            exchange.tool_calls,
+            exchange.archive_path,
+            exchange.line_start,
+            exchange.line_end,
+            exchange.summary,
+            exchange.git_branch,
+            exchange.cwd,
+        ],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Add `imported_hash` to an event's metadata JSON.
+fn with_imported_hash(metadata: Option<&str>, original_hash: &str) -> String {
+    let mut value = metadata
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    value["imported_hash"] = serde_json::json!(original_hash);
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_chain::verify_chain;
+    use crate::schema::init_schema;
+
+    fn sample_event(id: i64, diff: &str) -> ExportedEvent {
+        ExportedEvent {
+            id,
+            timestamp: format!("2026-01-0{}T10:00:00.000", id),
+            timestamp_display: None,
+            session_id: Some("s1".to_string()),
+            tool_name: "Write".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Some("modify".to_string()),
+            diff_summary: Some(diff.to_string()),
+            raw_input: None,
+            ai_summary: None,
+            git_commit_sha: None,
+            metadata: Some(r#"{"cwd":"/home/me/app"}"#.to_string()),
+            prev_hash: None,
+            event_hash: None,
+            content_hash: None,
+            context_hash: None,
+            semantic_sig: None,
+            old_content: None,
+            new_content: None,
+        }
+    }
+
+    fn event_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn export_document(conn: &Connection) -> ExportDocument {
+        let mut buf = Vec::new();
+        write_export(conn, None, &mut buf).unwrap();
+        serde_json::from_slice(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip_preserves_hashes() {
+        let source = Connection::open_in_memory().unwrap();
+        init_schema(&source).unwrap();
+        for (id, diff) in [(1, "+1 lines"), (2, "+2 lines")] {
+            import_event(&source, &sample_event(id, diff)).unwrap();
+        }
+        let doc = export_document(&source);
+        assert_eq!(doc.version, EXPORT_FORMAT_VERSION);
+        assert_eq!(doc.events.len(), 2);
+
+        let target = Connection::open_in_memory().unwrap();
+        init_schema(&target).unwrap();
+        for event in &doc.events {
+            assert!(import_event(&target, event).unwrap());
+        }
+        assert_eq!(
+            export_document(&target).events[1].event_hash,
+            doc.events[1].event_hash
+        );
+        assert!(verify_chain(&target).unwrap().valid);
+
+        // Re-importing the same dump adds nothing
+        for event in &doc.events {
+            assert!(!import_event(&target, event).unwrap());
+        }
+        assert_eq!(event_count(&target), 2);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_fingerprint_columns() {
+        let source = Connection::open_in_memory().unwrap();
+        init_schema(&source).unwrap();
+        let event = ExportedEvent {
+            content_hash: Some(hex::encode([1u8; 32])),
+            context_hash: Some(hex::encode([2u8; 32])),
+            semantic_sig: Some(hex::encode([3u8; 16])),
+            old_content: Some("fn old() {}".to_string()),
+            new_content: Some("fn new() {}".to_string()),
+            ..sample_event(1, "+1 lines")
+        };
+        import_event(&source, &event).unwrap();
+        let exported = export_document(&source).events.pop().unwrap();
+        assert_eq!(exported.context_hash, event.context_hash);
+        assert_eq!(exported.semantic_sig, event.semantic_sig);
+
+        let target = Connection::open_in_memory().unwrap();
+        init_schema(&target).unwrap();
+        assert!(import_event(&target, &exported).unwrap());
+        let imported = export_document(&target).events.pop().unwrap();
+        assert_eq!(imported.event_hash, exported.event_hash);
+        assert_eq!(imported.content_hash, event.content_hash);
+        assert_eq!(imported.context_hash, event.context_hash);
+        assert_eq!(imported.semantic_sig, event.semantic_sig);
+        assert_eq!(imported.old_content, event.old_content);
+        assert_eq!(imported.new_content, event.new_content);
+    }
+
+    #[test]
+    fn test_unchained_events_import_once() {
+        // Exported before the hash chain: no hashes at all
+        let legacy = [sample_event(1, "+1 lines"), sample_event(2, "+2 lines")];
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        for event in &legacy {
+            assert!(import_event(&conn, event).unwrap());
+        }
+        for event in &legacy {
+            assert!(!import_event(&conn, event).unwrap());
+        }
+        assert_eq!(event_count(&conn), 2);
+        assert!(verify_chain(&conn).unwrap().valid);
+    }
+
+    #[test]
+    fn test_import_lookup_uses_indexes() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let mut stmt = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", FIND_IMPORTED_SQL))
+            .unwrap();
+        let plan: Vec<String> = stmt
+            .query_map(params![None::<Vec<u8>>, "00"], |row| row.get(3))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert!(
+            plan.iter().all(|step| !step.starts_with("SCAN")),
+            "{:?}",
+            plan
+        );
+        assert!(plan
+            .iter()
+            .any(|step| step.contains("idx_events_imported_hash")));
+    }
+
+    #[test]
+    fn test_import_relinks_onto_existing_chain() {
+        let source = Connection::open_in_memory().unwrap();
+        init_schema(&source).unwrap();
+        import_event(&source, &sample_event(1, "+1 lines")).unwrap();
+        let doc = export_document(&source);
+
+        let target = Connection::open_in_memory().unwrap();
+        init_schema(&target).unwrap();
+        import_event(&target, &sample_event(2, "local")).unwrap();
+        assert!(import_event(&target, &doc.events[0]).unwrap());
+        assert!(!import_event(&target, &doc.events[0]).unwrap());
+        assert!(verify_chain(&target).unwrap().valid);
+
+        let imported = export_document(&target).events.pop().unwrap();
+        assert_ne!(imported.event_hash, doc.events[0].event_hash);
+        let metadata: serde_json::Value =
+            serde_json::from_str(imported.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            metadata["imported_hash"].as_str(),
+            doc.events[0].event_hash.as_deref()
+        );
+        assert_eq!(metadata["cwd"], "/home/me/app");
+    }
+
+    #[test]
+    fn test_exchange_import_is_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let exchange = Exchange {
+            id: "ex-1".to_string(),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            project: None,
+            session_id: None,
+            user_message: "hi".to_string(),
+            assistant_message: "hello".to_string(),
+            tool_calls: None,
+            archive_path: None,
+            line_start: None,
+            line_end: None,
+            embedding: None,
+            summary: None,
+            git_branch: None,
+            cwd: None,
+        };
+        assert!(import_exchange(&conn, &exchange).unwrap());
+        assert!(!import_exchange(&conn, &exchange).unwrap());
+        assert_eq!(export_document(&conn).exchanges.len(), 1);
+    }
+}
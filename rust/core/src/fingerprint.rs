@@ -5,7 +5,8 @@
 //!
 //! # Fingerprint Components
 //!
-//! 1. **Content Hash**: SHA256 of normalized content (whitespace-normalized)
+//! 1. **Content Hash**: SHA256 of normalized content (trailing whitespace
+//!    stripped; see [`FingerprintOptions`] for reformat-proof hashing)
 //! 2. **Context Hash**: SHA256 of surrounding context (±5 lines)
 //! 3. **Semantic Signature**: Embedding vector for semantic similarity matching
 //!
@@ -18,6 +19,8 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use crate::simd;
+
 /// Default context size (lines before and after the change)
 pub const DEFAULT_CONTEXT_LINES: usize = 5;
 
@@ -70,16 +73,163 @@ pub enum MatchType {
     SemanticSimilarity,
 }
 
+/// Language of hashed content, for finding its line comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    Rust,
+    Go,
+    /// C and C++
+    C,
+    /// Java, Kotlin and Scala
+    Java,
+    CSharp,
+    Swift,
+    /// JavaScript and TypeScript
+    JavaScript,
+    Python,
+    Ruby,
+    Shell,
+    Sql,
+}
+
+impl Lang {
+    /// Pick a language from a file path's extension.
+    ///
+    /// # Arguments
+    /// - `path`: File path
+    ///
+    /// # Returns
+    /// The matching language, or `None` for unknown extensions
+    pub fn from_path(path: &str) -> Option<Self> {
+        let file_name = path.rsplit(['/', '\\']).next()?;
+        let ext = file_name.rsplit_once('.')?.1.to_ascii_lowercase();
+        let lang = match ext.as_str() {
+            "rs" => Lang::Rust,
+            "go" => Lang::Go,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Lang::C,
+            "java" | "kt" | "kts" | "scala" => Lang::Java,
+            "cs" => Lang::CSharp,
+            "swift" => Lang::Swift,
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Lang::JavaScript,
+            "py" | "pyi" => Lang::Python,
+            "rb" => Lang::Ruby,
+            "sh" | "bash" | "zsh" => Lang::Shell,
+            "sql" => Lang::Sql,
+            _ => return None,
+        };
+        Some(lang)
+    }
+
+    /// Marker that starts a line comment.
+    fn line_comment(self) -> &'static str {
+        match self {
+            Lang::Python | Lang::Ruby | Lang::Shell => "#",
+            Lang::Sql => "--",
+            _ => "//",
+        }
+    }
+
+    /// Characters that open and close a string (or character) literal.
+    ///
+    /// Rust's `'` is left out: it also starts lifetimes, which never
+    /// close. Its character literals are skipped separately.
+    fn quotes(self) -> &'static [char] {
+        match self {
+            Lang::Rust | Lang::Swift => &['"'],
+            Lang::Go | Lang::JavaScript | Lang::Shell => &['"', '\'', '`'],
+            _ => &['"', '\''],
+        }
+    }
+}
+
+/// How content is normalized before hashing.
+///
+/// The default only strips trailing whitespace, which is what every stored
+/// fingerprint was computed with. Content must be hashed with the same
+/// options on both sides for a content-hash match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FingerprintOptions {
+    /// Ignore indentation, runs of spaces, and blank lines, so the hash
+    /// survives a reformat (`rustfmt`, `prettier`) that keeps line breaks
+    pub normalize_whitespace: bool,
+    /// Drop line comments; needs `language` to know the comment syntax
+    pub strip_comments: bool,
+    /// Language of the content, usually from [`Lang::from_path`]
+    pub language: Option<Lang>,
+}
+
 /// Normalize content for consistent hashing.
 ///
-/// Removes trailing whitespace, normalizes line endings,
-/// and optionally removes comments and blank lines.
-fn normalize_content(content: &str) -> String {
-    content
-        .lines()
-        .map(|line| line.trim_end())
-        .collect::<Vec<_>>()
-        .join("\n")
+/// Always removes trailing whitespace and normalizes line endings; the
+/// options add comment stripping (lines holding only a comment are
+/// dropped) and whitespace collapsing.
+fn normalize_content(content: &str, options: &FingerprintOptions) -> String {
+    let comments = options.language.filter(|_| options.strip_comments);
+    let lines = content.lines().filter_map(|line| match comments {
+        Some(lang) => strip_line_comment(line, lang),
+        None => Some(line),
+    });
+    if options.normalize_whitespace {
+        lines
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        lines.map(str::trim_end).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Cut the line comment off a line, outside string literals.
+///
+/// Strings are assumed not to span lines.
+///
+/// # Returns
+/// The code before the comment, or `None` if the line held nothing else
+fn strip_line_comment(line: &str, lang: Lang) -> Option<&str> {
+    let marker = lang.line_comment();
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if lang.quotes().contains(&c) {
+            let mut escaped = false;
+            for (_, ch) in chars.by_ref() {
+                if !escaped && ch == c {
+                    break;
+                }
+                escaped = !escaped && ch == '\\';
+            }
+        } else if lang == Lang::Rust && c == '\'' {
+            // A character literal ('x', '\n'); anything else is a lifetime
+            let rest = &line[i + 1..];
+            let literal_len = match rest.strip_prefix('\\') {
+                Some(escape) => match escape.chars().next() {
+                    // `'\` at the end of the line is not a literal
+                    None => None,
+                    Some(ch) => escape[ch.len_utf8()..]
+                        .find('\'')
+                        .map(|end| end + ch.len_utf8() + 2),
+                },
+                None => rest
+                    .chars()
+                    .next()
+                    .filter(|ch| rest[ch.len_utf8()..].starts_with('\''))
+                    .map(|ch| ch.len_utf8() + 1),
+            };
+            if let Some(len) = literal_len {
+                while chars.peek().is_some_and(|&(j, _)| j <= i + len) {
+                    chars.next();
+                }
+            }
+        } else if line[i..].starts_with(marker)
+            // `$#` and `${#x}` are not comments in shell
+            && (lang != Lang::Shell || i == 0 || line[..i].ends_with(char::is_whitespace))
+        {
+            let code = &line[..i];
+            return (!code.trim().is_empty()).then_some(code);
+        }
+    }
+    Some(line)
 }
 
 /// Compute SHA256 hash of a string.
@@ -96,6 +246,7 @@ fn sha256_hash(data: &str) -> [u8; 32] {
 /// * `content` - The changed content (e.g., added/modified lines)
 /// * `context` - Optional surrounding context (lines before and after)
 /// * `embedding` - Optional pre-computed embedding vector
+/// * `options` - Normalization applied to content and context before hashing
 ///
 /// # Returns
 ///
@@ -104,15 +255,16 @@ pub fn compute_fingerprint(
     content: &str,
     context: Option<&str>,
     embedding: Option<Vec<f32>>,
+    options: &FingerprintOptions,
 ) -> HunkFingerprint {
     // Normalize and hash content
-    let normalized = normalize_content(content);
+    let normalized = normalize_content(content, options);
     let content_hash = sha256_hash(&normalized);
 
     // Compute context hash
     let context_hash = match context {
         Some(ctx) => {
-            let normalized_ctx = normalize_content(ctx);
+            let normalized_ctx = normalize_content(ctx, options);
             sha256_hash(&normalized_ctx)
         }
         None => [0u8; 32], // No context available
@@ -160,9 +312,9 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
 
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let dot = simd::dot(a, b);
+    let norm_a = simd::dot(a, a).sqrt();
+    let norm_b = simd::dot(b, b).sqrt();
 
     if norm_a == 0.0 || norm_b == 0.0 {
         return 0.0;
@@ -217,9 +369,10 @@ pub fn match_fingerprint(
             {
                 best_match = Some(this_match);
             }
+            continue;
         }
 
-        // 3. Try semantic similarity (low confidence but survives refactors)
+        // 3. Hashes missed: try semantic similarity (low confidence but survives refactors)
         if let (Some(ref curr_emb), Some(ref cand_emb)) =
             (&current.semantic_sig, &candidate.semantic_sig)
         {
@@ -267,15 +420,15 @@ mod tests {
         // Note: normalize_content strips trailing whitespace per-line and joins with \n
         // It does NOT preserve trailing newlines from the original content
         let content = "  hello world  \n  foo bar  \n";
-        let normalized = normalize_content(content);
+        let normalized = normalize_content(content, &FingerprintOptions::default());
         assert_eq!(normalized, "  hello world\n  foo bar");
     }
 
     #[test]
     fn test_compute_fingerprint_deterministic() {
         let content = "function add(a, b) {\n  return a + b;\n}";
-        let fp1 = compute_fingerprint(content, None, None);
-        let fp2 = compute_fingerprint(content, None, None);
+        let fp1 = compute_fingerprint(content, None, None, &FingerprintOptions::default());
+        let fp2 = compute_fingerprint(content, None, None, &FingerprintOptions::default());
 
         assert_eq!(fp1.content_hash, fp2.content_hash);
     }
@@ -285,12 +438,80 @@ mod tests {
         let content1 = "function add(a, b) { return a + b; }";
         let content2 = "function subtract(a, b) { return a - b; }";
 
-        let fp1 = compute_fingerprint(content1, None, None);
-        let fp2 = compute_fingerprint(content2, None, None);
+        let fp1 = compute_fingerprint(content1, None, None, &FingerprintOptions::default());
+        let fp2 = compute_fingerprint(content2, None, None, &FingerprintOptions::default());
 
         assert_ne!(fp1.content_hash, fp2.content_hash);
     }
 
+    #[test]
+    fn test_normalized_hash_survives_reindent() {
+        let before = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+        let after = "fn add(a: i32,  b: i32) -> i32 {\n\n        a + b   \n}";
+        let default = FingerprintOptions::default();
+        assert_ne!(
+            compute_fingerprint(before, None, None, &default).content_hash,
+            compute_fingerprint(after, None, None, &default).content_hash
+        );
+
+        let options = FingerprintOptions {
+            normalize_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_fingerprint(before, Some(before), None, &options).content_hash,
+            compute_fingerprint(after, Some(after), None, &options).content_hash
+        );
+        assert_eq!(
+            compute_fingerprint(before, Some(before), None, &options).context_hash,
+            compute_fingerprint(after, Some(after), None, &options).context_hash
+        );
+    }
+
+    #[test]
+    fn test_strip_comments_by_language() {
+        assert_eq!(Lang::from_path("src/Main.RS"), Some(Lang::Rust));
+        assert_eq!(Lang::from_path("web/app.tsx"), Some(Lang::JavaScript));
+        assert_eq!(Lang::from_path("Makefile"), None);
+        assert_eq!(Lang::from_path("v1.2/README"), None);
+
+        let options = |language| FingerprintOptions {
+            normalize_whitespace: false,
+            strip_comments: true,
+            language: Some(language),
+        };
+        let rust = [
+            "/// Docs",
+            "fn f<'a>(s: &'a str) -> char { // trailing",
+            "    let url = \"http://x\"; '/' // end",
+            "}",
+        ]
+        .join("\n");
+        assert_eq!(
+            normalize_content(&rust, &options(Lang::Rust)),
+            "fn f<'a>(s: &'a str) -> char {\n    let url = \"http://x\"; '/'\n}"
+        );
+        // A stray `'\` ends the line without hiding the code before it
+        assert_eq!(
+            normalize_content("let c = '\\n'; x // y\nlet q = '\\", &options(Lang::Rust)),
+            "let c = '\\n'; x\nlet q = '\\"
+        );
+        assert_eq!(
+            normalize_content("x = '#'  # note\n# only a comment", &options(Lang::Python)),
+            "x = '#'"
+        );
+        assert_eq!(
+            normalize_content("echo $# ${#a} # count", &options(Lang::Shell)),
+            "echo $# ${#a}"
+        );
+        // Without a language the comment syntax is unknown
+        let unknown = FingerprintOptions {
+            strip_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize_content("a // b", &unknown), "a // b");
+    }
+
     #[test]
     fn test_extract_context() {
         let file_content = "line 0\nline 1\nline 2\nline 3\nline 4\nline 5\nline 6";
@@ -323,7 +544,7 @@ mod tests {
     #[test]
     fn test_match_fingerprint_exact() {
         let content = "hello world";
-        let fp = compute_fingerprint(content, None, None);
+        let fp = compute_fingerprint(content, None, None, &FingerprintOptions::default());
 
         let candidates = vec![(42, fp.clone())];
 
@@ -7,8 +7,9 @@
 //! # Security Model
 //!
 //! This is tamper-*detection*, not tamper-*prevention*. A determined attacker with
-//! database access could recompute the entire chain. For audit-grade guarantees,
-//! future versions will add device key signing and optional third-party attestation.
+//! database access could recompute the entire chain. Checkpoints can optionally be
+//! signed with an ed25519 device key (`~/.diachron/signing.key`), which an attacker
+//! without the key cannot forge.
 //!
 //! # Usage
 //!
@@ -26,13 +27,22 @@
 //! let hash = compute_event_hash(&input, &genesis_hash);
 //! ```
 
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signer, Verifier};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
 /// Genesis hash (all zeros) for the first event in a chain.
 pub const GENESIS_HASH: [u8; 32] = [0u8; 32];
 
+/// Domain separator prepended to signed checkpoint payloads.
+const CHECKPOINT_SIGNING_DOMAIN: &[u8] = b"diachron-checkpoint-v1";
+
 /// Input structure for computing event hashes.
 ///
 /// This includes all fields that should be part of the canonical
@@ -68,6 +78,10 @@ pub struct ChainVerificationResult {
     pub chain_root: Option<String>,
     /// Details of where the chain broke (if invalid)
     pub break_point: Option<ChainBreak>,
+    /// ID of the first checkpoint whose stored Merkle root doesn't match
+    /// the root recomputed from the events it covers
+    #[serde(default)]
+    pub merkle_mismatch: Option<i64>,
 }
 
 /// Details of a chain break point.
@@ -81,6 +95,10 @@ pub struct ChainBreak {
     pub expected_hash: String,
     /// Hash that was actually stored
     pub actual_hash: String,
+    /// Fields whose current value no longer matches the digest recorded at
+    /// capture time (empty if the event predates per-field digests)
+    #[serde(default)]
+    pub diverging_fields: Vec<String>,
 }
 
 /// Checkpoint record for daily chain snapshots.
@@ -90,10 +108,28 @@ pub struct ChainCheckpoint {
     pub date: String,
     pub event_count: u64,
     pub final_hash: [u8; 32],
+    /// Merkle root over all event hashes up to this checkpoint
+    #[serde(default)]
+    pub merkle_root: [u8; 32],
+    /// ID of the last event covered by this checkpoint (0 if none)
+    ///
+    /// `None` for checkpoints recorded before the ID was stored whose final
+    /// event could not be matched during migration.
+    #[serde(default)]
+    pub last_event_id: Option<i64>,
     pub signature: Option<Vec<u8>>,
     pub created_at: String,
 }
 
+/// One step of a Merkle inclusion proof: the sibling hash at that level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MerkleProofStep {
+    /// Sibling sits to the left: parent = H(sibling || node)
+    Left([u8; 32]),
+    /// Sibling sits to the right: parent = H(node || sibling)
+    Right([u8; 32]),
+}
+
 /// Compute the SHA256 hash of an event including the previous hash.
 ///
 /// # Algorithm
@@ -121,10 +157,156 @@ pub fn compute_event_hash(event: &EventHashInput, prev_hash: &[u8; 32]) -> [u8;
     hasher.finalize().into()
 }
 
+/// Compute per-field SHA256 digests of an event's canonical input.
+///
+/// Stored next to the event hash at capture time so that, if the event hash
+/// later fails to verify, the altered field(s) can be named without keeping
+/// a second copy of the event data.
+///
+/// # Arguments
+///
+/// * `input` - Event data as hashed into the chain
+///
+/// # Returns
+///
+/// Map of field name to hex digest of its canonical JSON value
+pub fn compute_field_hashes(input: &EventHashInput) -> BTreeMap<String, String> {
+    let value = serde_json::to_value(input).unwrap_or_default();
+    value
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|(name, v)| {
+                    let digest: [u8; 32] = Sha256::digest(v.to_string().as_bytes()).into();
+                    (name.clone(), hex::encode(digest))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Name the fields whose current values differ from recorded digests.
+///
+/// # Arguments
+///
+/// * `recorded` - Digests from `compute_field_hashes` at capture time
+/// * `input` - Event data as currently stored
+///
+/// # Returns
+///
+/// Field names in canonical (alphabetical) order
+pub fn diverging_fields(
+    recorded: &BTreeMap<String, String>,
+    input: &EventHashInput,
+) -> Vec<String> {
+    compute_field_hashes(input)
+        .into_iter()
+        .filter(|(name, digest)| recorded.get(name) != Some(digest))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Hash two child nodes into their parent.
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Compute the Merkle root over a list of event hashes.
+///
+/// Leaves are the event hashes in chain order. Each level pairs adjacent
+/// nodes as `SHA256(left || right)`; an odd node out is paired with itself.
+/// A single leaf is its own root.
+///
+/// # Arguments
+///
+/// * `hashes` - Event hashes in chain (ID) order
+///
+/// # Returns
+///
+/// The 32-byte root, or GENESIS_HASH for an empty list
+pub fn compute_merkle_root(hashes: &[[u8; 32]]) -> [u8; 32] {
+    if hashes.is_empty() {
+        return GENESIS_HASH;
+    }
+
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    level[0]
+}
+
+/// Build an inclusion proof for the leaf at `index`.
+///
+/// # Arguments
+///
+/// * `hashes` - Event hashes in chain (ID) order
+/// * `index` - Position of the leaf to prove
+///
+/// # Returns
+///
+/// Sibling hashes from leaf to root, or None if `index` is out of range
+pub fn compute_merkle_proof(hashes: &[[u8; 32]], index: usize) -> Option<Vec<MerkleProofStep>> {
+    if index >= hashes.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level = hashes.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        let step = if index.is_multiple_of(2) {
+            MerkleProofStep::Right(*level.get(index + 1).unwrap_or(&level[index]))
+        } else {
+            MerkleProofStep::Left(level[index - 1])
+        };
+        proof.push(step);
+
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Verify that an event hash is included under a Merkle root.
+///
+/// # Arguments
+///
+/// * `event_hash` - Leaf hash being proven
+/// * `proof` - Sibling path from `compute_merkle_proof`
+/// * `root` - Expected Merkle root (e.g. from a checkpoint)
+///
+/// # Returns
+///
+/// True if folding the proof over the leaf yields `root`
+pub fn verify_merkle_inclusion(
+    event_hash: &[u8; 32],
+    proof: &[MerkleProofStep],
+    root: &[u8; 32],
+) -> bool {
+    let computed = proof.iter().fold(*event_hash, |node, step| match step {
+        MerkleProofStep::Left(sibling) => merkle_parent(sibling, &node),
+        MerkleProofStep::Right(sibling) => merkle_parent(&node, sibling),
+    });
+    computed == *root
+}
+
 /// Verify the integrity of the event hash chain.
 ///
 /// Iterates through all events with hashes, recomputing each hash
-/// and comparing against stored values.
+/// and comparing against stored values. Checkpoints that carry a Merkle
+/// root are then checked against the root recomputed from the events
+/// they cover.
 ///
 /// # Arguments
 ///
@@ -134,7 +316,81 @@ pub fn compute_event_hash(event: &EventHashInput, prev_hash: &[u8; 32]) -> [u8;
 ///
 /// Verification result with details of any breaks found
 pub fn verify_chain(conn: &Connection) -> Result<ChainVerificationResult, rusqlite::Error> {
-    let mut result = ChainVerificationResult {
+    let mut result = empty_verification_result();
+    let event_hashes = walk_chain(conn, 0, GENESIS_HASH, &mut result)?;
+
+    // Count checkpoints
+    let checkpoint_count: i64 =
+        conn.query_row("SELECT COUNT(*) FROM chain_checkpoints", [], |row| {
+            row.get(0)
+        })?;
+    result.checkpoints_checked = checkpoint_count as u64;
+
+    // Recompute Merkle roots (only meaningful if the linear chain held)
+    if result.valid {
+        let mut stmt = conn.prepare(
+            "SELECT id, event_count, merkle_root FROM chain_checkpoints
+             WHERE merkle_root IS NOT NULL
+             ORDER BY id ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let event_count: i64 = row.get(1)?;
+            let stored_root: Vec<u8> = row.get(2)?;
+
+            let covered = event_hashes.get(..event_count as usize);
+            let matches = covered
+                .map(|hashes| compute_merkle_root(hashes).as_slice() == stored_root.as_slice())
+                .unwrap_or(false);
+            if !matches {
+                result.valid = false;
+                result.merkle_mismatch = Some(id);
+                break;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Verify only the events recorded after a checkpoint.
+///
+/// The checkpoint is trusted: the first new event must link to its
+/// `final_hash`, but events it covers are not re-hashed and its Merkle
+/// root is not recomputed. Tampering before the checkpoint therefore goes
+/// unnoticed here by design; run `verify_chain` for a full audit.
+///
+/// A checkpoint without a `last_event_id` cannot say where to resume, so
+/// it is treated as no checkpoint and the full chain is verified.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection
+/// * `checkpoint` - Previously created checkpoint to resume from
+///
+/// # Returns
+///
+/// Verification result covering events after the checkpoint
+pub fn verify_chain_since(
+    conn: &Connection,
+    checkpoint: &ChainCheckpoint,
+) -> Result<ChainVerificationResult, rusqlite::Error> {
+    let Some(last_event_id) = checkpoint.last_event_id else {
+        return verify_chain(conn);
+    };
+
+    let mut result = empty_verification_result();
+    walk_chain(conn, last_event_id, checkpoint.final_hash, &mut result)?;
+    result.chain_root = Some(format_hash(&checkpoint.final_hash));
+    // Only the anchoring checkpoint is relied upon
+    result.checkpoints_checked = 1;
+
+    Ok(result)
+}
+
+fn empty_verification_result() -> ChainVerificationResult {
+    ChainVerificationResult {
         valid: true,
         events_checked: 0,
         checkpoints_checked: 0,
@@ -142,20 +398,34 @@ pub fn verify_chain(conn: &Connection) -> Result<ChainVerificationResult, rusqli
         last_event: None,
         chain_root: None,
         break_point: None,
-    };
+        merkle_mismatch: None,
+    }
+}
 
+/// Walk events with `id > after_id`, starting the chain at `start_hash`.
+///
+/// Records counts, timestamps and the first break into `result`, and
+/// returns the stored hashes of every event that verified.
+fn walk_chain(
+    conn: &Connection,
+    after_id: i64,
+    start_hash: [u8; 32],
+    result: &mut ChainVerificationResult,
+) -> Result<Vec<[u8; 32]>, rusqlite::Error> {
     // Query events with hashes, ordered by ID (insertion order)
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, tool_name, file_path, operation, diff_summary,
-                raw_input, session_id, git_commit_sha, metadata, prev_hash, event_hash
+                raw_input, session_id, git_commit_sha, metadata, prev_hash, event_hash,
+                field_hashes
          FROM events
-         WHERE event_hash IS NOT NULL
+         WHERE event_hash IS NOT NULL AND id > ?1
          ORDER BY id ASC",
     )?;
 
-    let mut rows = stmt.query([])?;
-    let mut expected_prev_hash = GENESIS_HASH;
+    let mut rows = stmt.query([after_id])?;
+    let mut expected_prev_hash = start_hash;
     let mut is_first = true;
+    let mut event_hashes: Vec<[u8; 32]> = Vec::new();
 
     while let Some(row) = rows.next()? {
         let id: i64 = row.get(0)?;
@@ -170,11 +440,12 @@ pub fn verify_chain(conn: &Connection) -> Result<ChainVerificationResult, rusqli
         let metadata: Option<String> = row.get(9)?;
         let stored_prev_hash: Option<Vec<u8>> = row.get(10)?;
         let stored_event_hash: Option<Vec<u8>> = row.get(11)?;
+        let field_hashes: Option<String> = row.get(12)?;
 
         // Set first/last timestamps
         if is_first {
             result.first_event = Some(timestamp.clone());
-            result.chain_root = Some(hex::encode(&expected_prev_hash));
+            result.chain_root = Some(format_hash(&expected_prev_hash));
             is_first = false;
         }
         result.last_event = Some(timestamp.clone());
@@ -203,8 +474,9 @@ pub fn verify_chain(conn: &Connection) -> Result<ChainVerificationResult, rusqli
                     result.break_point = Some(ChainBreak {
                         event_id: id,
                         timestamp,
-                        expected_hash: hex::encode(&expected_prev_hash),
-                        actual_hash: hex::encode(&stored_prev),
+                        expected_hash: format_hash(&expected_prev_hash),
+                        actual_hash: format_hash(&stored_prev),
+                        diverging_fields: vec!["prev_hash".to_string()],
                     });
                     break;
                 }
@@ -218,28 +490,27 @@ pub fn verify_chain(conn: &Connection) -> Result<ChainVerificationResult, rusqli
             if hash_bytes.len() == 32 {
                 let stored_hash: [u8; 32] = hash_bytes.as_slice().try_into().unwrap_or([0u8; 32]);
                 if stored_hash != computed_hash {
+                    let recorded: Option<BTreeMap<String, String>> =
+                        field_hashes.and_then(|json| serde_json::from_str(&json).ok());
                     result.valid = false;
                     result.break_point = Some(ChainBreak {
                         event_id: id,
                         timestamp,
-                        expected_hash: hex::encode(&computed_hash),
-                        actual_hash: hex::encode(&stored_hash),
+                        expected_hash: format_hash(&computed_hash),
+                        actual_hash: format_hash(&stored_hash),
+                        diverging_fields: recorded
+                            .map(|r| diverging_fields(&r, &input))
+                            .unwrap_or_default(),
                     });
                     break;
                 }
                 expected_prev_hash = stored_hash;
+                event_hashes.push(stored_hash);
             }
         }
     }
 
-    // Count checkpoints
-    let checkpoint_count: i64 =
-        conn.query_row("SELECT COUNT(*) FROM chain_checkpoints", [], |row| {
-            row.get(0)
-        })?;
-    result.checkpoints_checked = checkpoint_count as u64;
-
-    Ok(result)
+    Ok(event_hashes)
 }
 
 /// Get the hash of the last event in the chain.
@@ -279,35 +550,277 @@ pub fn get_last_event_hash(conn: &Connection) -> Result<[u8; 32], rusqlite::Erro
 ///
 /// * `conn` - Database connection
 /// * `date` - Date string for the checkpoint (YYYY-MM-DD)
+/// * `signing_key` - Optional device key; when set, the checkpoint is signed
 ///
 /// # Returns
 ///
 /// The created checkpoint record
-pub fn create_checkpoint(conn: &Connection, date: &str) -> Result<ChainCheckpoint, rusqlite::Error> {
+pub fn create_checkpoint(
+    conn: &Connection,
+    date: &str,
+    signing_key: Option<&SigningKey>,
+) -> Result<ChainCheckpoint, rusqlite::Error> {
     let event_count: i64 =
         conn.query_row("SELECT COUNT(*) FROM events WHERE event_hash IS NOT NULL", [], |row| {
             row.get(0)
         })?;
 
+    let last_event_id: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) FROM events WHERE event_hash IS NOT NULL",
+        [],
+        |row| row.get(0),
+    )?;
     let final_hash = get_last_event_hash(conn)?;
+    let merkle_root = compute_merkle_root(&load_event_hashes(conn)?);
     let created_at = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
 
-    conn.execute(
-        "INSERT INTO chain_checkpoints (date, event_count, final_hash, created_at)
-         VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![date, event_count, final_hash.as_slice(), created_at],
-    )?;
-
-    let id = conn.last_insert_rowid();
-
-    Ok(ChainCheckpoint {
-        id,
+    let mut checkpoint = ChainCheckpoint {
+        id: 0,
         date: date.to_string(),
         event_count: event_count as u64,
         final_hash,
+        merkle_root,
+        last_event_id: Some(last_event_id),
         signature: None,
         created_at,
-    })
+    };
+    if let Some(key) = signing_key {
+        let payload = checkpoint_signing_payload(&checkpoint);
+        checkpoint.signature = Some(key.sign(&payload).to_bytes().to_vec());
+    }
+
+    conn.execute(
+        "INSERT INTO chain_checkpoints
+             (date, event_count, final_hash, merkle_root, last_event_id, signature, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            checkpoint.date,
+            event_count,
+            checkpoint.final_hash.as_slice(),
+            checkpoint.merkle_root.as_slice(),
+            checkpoint.last_event_id,
+            checkpoint.signature,
+            checkpoint.created_at
+        ],
+    )?;
+
+    checkpoint.id = conn.last_insert_rowid();
+    Ok(checkpoint)
+}
+
+/// Load all checkpoints, oldest first.
+///
+/// Checkpoints created before Merkle roots existed report GENESIS_HASH
+/// as their root.
+///
+/// # Arguments
+///
+/// * `conn` - Database connection
+///
+/// # Returns
+///
+/// Checkpoint records ordered by ID
+pub fn load_checkpoints(conn: &Connection) -> Result<Vec<ChainCheckpoint>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, date, event_count, final_hash, merkle_root, signature, created_at,
+                last_event_id
+         FROM chain_checkpoints
+         ORDER BY id ASC",
+    )?;
+    let to_hash = |bytes: Option<Vec<u8>>| {
+        bytes
+            .and_then(|b| <[u8; 32]>::try_from(b.as_slice()).ok())
+            .unwrap_or(GENESIS_HASH)
+    };
+    let checkpoints = stmt
+        .query_map([], |row| {
+            Ok(ChainCheckpoint {
+                id: row.get(0)?,
+                date: row.get(1)?,
+                event_count: row.get::<_, i64>(2)? as u64,
+                final_hash: to_hash(row.get(3)?),
+                merkle_root: to_hash(row.get(4)?),
+                last_event_id: row.get(7)?,
+                signature: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(checkpoints)
+}
+
+/// Build the byte string a checkpoint signature covers.
+///
+/// Covers the Merkle root, final chain hash, event count, and creation
+/// timestamp, behind a domain separator.
+fn checkpoint_signing_payload(checkpoint: &ChainCheckpoint) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(CHECKPOINT_SIGNING_DOMAIN);
+    payload.extend_from_slice(&checkpoint.merkle_root);
+    payload.extend_from_slice(&checkpoint.final_hash);
+    payload.extend_from_slice(&checkpoint.event_count.to_be_bytes());
+    payload.extend_from_slice(checkpoint.created_at.as_bytes());
+    payload
+}
+
+/// Verify a checkpoint's ed25519 signature.
+///
+/// # Arguments
+///
+/// * `checkpoint` - Checkpoint to check
+/// * `public_key` - Device public key
+///
+/// # Returns
+///
+/// True only if a signature is present and valid for `public_key`
+pub fn verify_checkpoint_signature(
+    checkpoint: &ChainCheckpoint,
+    public_key: &VerifyingKey,
+) -> bool {
+    let Some(bytes) = checkpoint.signature.as_deref() else {
+        return false;
+    };
+    let Ok(signature) = ed25519_dalek::Signature::from_slice(bytes) else {
+        return false;
+    };
+    public_key
+        .verify(&checkpoint_signing_payload(checkpoint), &signature)
+        .is_ok()
+}
+
+/// Default location of the checkpoint signing key.
+///
+/// # Returns
+///
+/// `~/.diachron/signing.key` (or `/tmp/.diachron/signing.key`)
+pub fn default_signing_key_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".diachron")
+        .join("signing.key")
+}
+
+/// Generate a new random signing key.
+pub fn generate_signing_key() -> SigningKey {
+    SigningKey::generate(&mut rand_core::OsRng)
+}
+
+/// Path of the public key written alongside a signing key (`*.pub`).
+pub fn verifying_key_path(signing_key_path: &Path) -> PathBuf {
+    signing_key_path.with_extension("pub")
+}
+
+/// Write a signing key as hex (owner-only permissions on Unix), plus its
+/// public key at `verifying_key_path(path)` for sharing with verifiers.
+///
+/// The key file is created owner-only, and an existing one is narrowed
+/// before it is rewritten, so the secret is never readable by others.
+///
+/// # Arguments
+///
+/// * `path` - Destination file
+/// * `key` - Key to persist
+///
+/// # Errors
+///
+/// Returns an I/O error if the file cannot be written.
+pub fn save_signing_key(path: &Path, key: &SigningKey) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        // `mode` only applies when the file is created
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::io::Write::write_all(&mut file, hex::encode(key.to_bytes()).as_bytes())?;
+    std::fs::write(
+        verifying_key_path(path),
+        hex::encode(key.verifying_key().to_bytes()),
+    )?;
+    Ok(())
+}
+
+/// Load a hex-encoded signing key.
+///
+/// # Arguments
+///
+/// * `path` - Key file written by `save_signing_key`
+///
+/// # Returns
+///
+/// The key, or None if the file doesn't exist
+///
+/// # Errors
+///
+/// Returns an I/O error if the file is unreadable or malformed.
+pub fn load_signing_key(path: &Path) -> std::io::Result<Option<SigningKey>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    let bytes = hex::decode(contents.trim())
+        .ok()
+        .and_then(|b| <[u8; 32]>::try_from(b.as_slice()).ok())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "signing key must be 32 hex-encoded bytes",
+            )
+        })?;
+    Ok(Some(SigningKey::from_bytes(&bytes)))
+}
+
+/// Load a hex-encoded public key written by `save_signing_key`.
+///
+/// # Arguments
+///
+/// * `path` - Public key file
+///
+/// # Returns
+///
+/// The key, or None if the file doesn't exist
+///
+/// # Errors
+///
+/// Returns an I/O error if the file is unreadable or not a valid key.
+pub fn load_verifying_key(path: &Path) -> std::io::Result<Option<VerifyingKey>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    hex::decode(contents.trim())
+        .ok()
+        .and_then(|b| <[u8; 32]>::try_from(b.as_slice()).ok())
+        .and_then(|b| VerifyingKey::from_bytes(&b).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "public key must be 32 hex-encoded bytes",
+            )
+        })
+}
+
+/// Load all stored event hashes in chain (ID) order.
+fn load_event_hashes(conn: &Connection) -> Result<Vec<[u8; 32]>, rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT event_hash FROM events WHERE event_hash IS NOT NULL ORDER BY id ASC")?;
+    let hashes = stmt
+        .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+        .filter_map(|r| r.ok())
+        .filter_map(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .collect();
+    Ok(hashes)
 }
 
 /// Format hash bytes as hex string for display.
@@ -412,6 +925,238 @@ mod tests {
         );
     }
 
+    fn leaf(n: u8) -> [u8; 32] {
+        [n; 32]
+    }
+
+    #[test]
+    fn test_merkle_root_single_leaf() {
+        let leaves = [leaf(1)];
+        assert_eq!(compute_merkle_root(&leaves), leaf(1));
+
+        let proof = compute_merkle_proof(&leaves, 0).unwrap();
+        assert!(proof.is_empty());
+        assert!(verify_merkle_inclusion(&leaf(1), &proof, &leaf(1)));
+    }
+
+    #[test]
+    fn test_merkle_root_even_leaves() {
+        let leaves: Vec<[u8; 32]> = (1..=4).map(leaf).collect();
+        let expected = merkle_parent(
+            &merkle_parent(&leaf(1), &leaf(2)),
+            &merkle_parent(&leaf(3), &leaf(4)),
+        );
+        let root = compute_merkle_root(&leaves);
+        assert_eq!(root, expected);
+
+        for (i, hash) in leaves.iter().enumerate() {
+            let proof = compute_merkle_proof(&leaves, i).unwrap();
+            assert!(verify_merkle_inclusion(hash, &proof, &root), "leaf {}", i);
+        }
+    }
+
+    #[test]
+    fn test_merkle_root_odd_leaves() {
+        let leaves: Vec<[u8; 32]> = (1..=3).map(leaf).collect();
+        let expected = merkle_parent(
+            &merkle_parent(&leaf(1), &leaf(2)),
+            &merkle_parent(&leaf(3), &leaf(3)),
+        );
+        let root = compute_merkle_root(&leaves);
+        assert_eq!(root, expected);
+
+        for (i, hash) in leaves.iter().enumerate() {
+            let proof = compute_merkle_proof(&leaves, i).unwrap();
+            assert!(verify_merkle_inclusion(hash, &proof, &root), "leaf {}", i);
+        }
+
+        // Wrong leaf or wrong root must not verify
+        let proof = compute_merkle_proof(&leaves, 0).unwrap();
+        assert!(!verify_merkle_inclusion(&leaf(9), &proof, &root));
+        assert!(!verify_merkle_inclusion(&leaf(1), &proof, &leaf(0)));
+        assert!(compute_merkle_proof(&leaves, 3).is_none());
+    }
+
+    /// In-memory database holding a valid chain of `count` events.
+    fn chained_db(count: i64) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::schema::init_schema(&conn).unwrap();
+        append_chained_events(&conn, 1..=count);
+        conn
+    }
+
+    fn append_chained_events(conn: &Connection, ids: std::ops::RangeInclusive<i64>) {
+        let mut prev = get_last_event_hash(conn).unwrap();
+        for id in ids {
+            let input = EventHashInput {
+                id,
+                timestamp: format!("2026-01-11T00:0{}:00", id),
+                tool_name: "Write".to_string(),
+                file_path: Some("test.txt".to_string()),
+                operation: "modify".to_string(),
+                diff_summary: None,
+                raw_input: None,
+                session_id: None,
+                git_commit_sha: None,
+                metadata: None,
+            };
+            let hash = compute_event_hash(&input, &prev);
+            let field_hashes = serde_json::to_string(&compute_field_hashes(&input)).unwrap();
+            conn.execute(
+                "INSERT INTO events (id, timestamp, tool_name, file_path, operation, diff_summary,
+                                     prev_hash, event_hash, field_hashes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![
+                    id,
+                    input.timestamp,
+                    input.tool_name,
+                    input.file_path,
+                    input.operation,
+                    input.diff_summary,
+                    prev.as_slice(),
+                    hash.as_slice(),
+                    field_hashes
+                ],
+            )
+            .unwrap();
+            prev = hash;
+        }
+    }
+
+    #[test]
+    fn test_verify_chain_checks_checkpoint_merkle_root() {
+        let conn = chained_db(3);
+
+        let checkpoint = create_checkpoint(&conn, "2026-01-11", None).unwrap();
+        assert_ne!(checkpoint.merkle_root, GENESIS_HASH);
+        assert!(verify_chain(&conn).unwrap().valid);
+
+        // Corrupt the stored root: the linear chain still holds, the root doesn't
+        conn.execute(
+            "UPDATE chain_checkpoints SET merkle_root = ?1 WHERE id = ?2",
+            rusqlite::params![[7u8; 32].as_slice(), checkpoint.id],
+        )
+        .unwrap();
+        let result = verify_chain(&conn).unwrap();
+        assert!(!result.valid);
+        assert!(result.break_point.is_none());
+        assert_eq!(result.merkle_mismatch, Some(checkpoint.id));
+    }
+
+    #[test]
+    fn test_verify_chain_reports_tampered_field() {
+        let conn = chained_db(3);
+        assert!(verify_chain(&conn).unwrap().valid);
+
+        conn.execute("UPDATE events SET file_path = 'other.txt' WHERE id = 2", [])
+            .unwrap();
+
+        let result = verify_chain(&conn).unwrap();
+        assert!(!result.valid);
+        let bp = result.break_point.unwrap();
+        assert_eq!(bp.event_id, 2);
+        assert_eq!(bp.diverging_fields, vec!["file_path".to_string()]);
+    }
+
+    #[test]
+    fn test_verify_chain_since_checkpoint() {
+        let conn = chained_db(3);
+        let checkpoint = create_checkpoint(&conn, "2026-01-11", None).unwrap();
+        assert_eq!(checkpoint.last_event_id, Some(3));
+        append_chained_events(&conn, 4..=5);
+
+        let result = verify_chain_since(&conn, &checkpoint).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.events_checked, 2);
+        assert_eq!(result.chain_root, Some(hex::encode(checkpoint.final_hash)));
+
+        // Tampering before the checkpoint is trusted away by design;
+        // only the full walk catches it.
+        conn.execute("UPDATE events SET file_path = 'other.txt' WHERE id = 2", [])
+            .unwrap();
+        assert!(verify_chain_since(&conn, &checkpoint).unwrap().valid);
+        assert!(!verify_chain(&conn).unwrap().valid);
+
+        // Tampering after the checkpoint is caught incrementally
+        conn.execute("UPDATE events SET file_path = 'other.txt' WHERE id = 5", [])
+            .unwrap();
+        let result = verify_chain_since(&conn, &checkpoint).unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.break_point.unwrap().event_id, 5);
+    }
+
+    #[test]
+    fn test_verify_chain_since_checkpoint_without_last_event_id() {
+        let conn = chained_db(3);
+        create_checkpoint(&conn, "2026-01-11", None).unwrap();
+        append_chained_events(&conn, 4..=5);
+        // A pre-V7 checkpoint whose final event the migration couldn't find
+        conn.execute("UPDATE chain_checkpoints SET last_event_id = NULL", [])
+            .unwrap();
+
+        let checkpoint = load_checkpoints(&conn).unwrap().pop().unwrap();
+        assert_eq!(checkpoint.last_event_id, None);
+
+        // Resuming from event 0 with the checkpoint's hash would report a
+        // break at event 1; the whole chain is verified instead
+        let result = verify_chain_since(&conn, &checkpoint).unwrap();
+        assert!(result.valid, "{:?}", result.break_point);
+        assert_eq!(result.events_checked, 5);
+    }
+
+    #[test]
+    fn test_checkpoint_signature_round_trip() {
+        let conn = chained_db(2);
+        let key = generate_signing_key();
+        let public = key.verifying_key();
+
+        let signed = create_checkpoint(&conn, "2026-01-11", Some(&key)).unwrap();
+        assert!(verify_checkpoint_signature(&signed, &public));
+
+        // Stored and reloaded checkpoint still verifies
+        let loaded = load_checkpoints(&conn).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert!(verify_checkpoint_signature(&loaded[0], &public));
+
+        // Wrong key, altered fields, and unsigned checkpoints don't
+        let other = generate_signing_key().verifying_key();
+        assert!(!verify_checkpoint_signature(&signed, &other));
+        let mut tampered = signed.clone();
+        tampered.event_count += 1;
+        assert!(!verify_checkpoint_signature(&tampered, &public));
+        let unsigned = create_checkpoint(&conn, "2026-01-12", None).unwrap();
+        assert!(!verify_checkpoint_signature(&unsigned, &public));
+        assert!(verify_chain(&conn).unwrap().valid);
+    }
+
+    #[test]
+    fn test_signing_key_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("diachron-key-{}", std::process::id()))
+            .join("signing.key");
+        assert!(load_signing_key(&path).unwrap().is_none());
+
+        let key = generate_signing_key();
+        save_signing_key(&path, &key).unwrap();
+        let loaded = load_signing_key(&path).unwrap().unwrap();
+        assert_eq!(loaded.to_bytes(), key.to_bytes());
+        let public_path = verifying_key_path(&path);
+        let public = load_verifying_key(&public_path).unwrap().unwrap();
+        assert_eq!(public, key.verifying_key());
+
+        // A replaced key file ends up owner-only even if it was not before
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            save_signing_key(&path, &generate_signing_key()).unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
     #[test]
     fn test_format_hash() {
         let hash = [0xab; 32];
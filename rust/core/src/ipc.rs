@@ -1,15 +1,77 @@
 //! IPC client for communicating with the Diachron daemon
 //!
 //! This module provides a synchronous client for sending messages to the daemon
-//! over a Unix socket. It's designed to be used by the hook (which needs sync I/O)
-//! and can also be used by CLI commands.
+//! over a local transport (a Unix socket, or a named pipe on Windows) or TCP.
+//! It's designed to be used by the hook (which needs sync I/O) and can also be
+//! used by CLI commands.
 
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use crate::{CaptureEvent, IpcMessage, IpcResponse};
+use crate::{CaptureEvent, DaemonError, IpcMessage, IpcResponse};
+
+/// Environment variable overriding the local endpoint: the Unix socket path,
+/// or the pipe name on Windows.
+pub const SOCKET_ENV_VAR: &str = "DIACHRON_SOCKET";
+
+/// Environment variable selecting a TCP daemon address (e.g. `127.0.0.1:7700`).
+/// Takes precedence over [`SOCKET_ENV_VAR`].
+pub const ADDR_ENV_VAR: &str = "DIACHRON_ADDR";
+
+/// Default named pipe the daemon listens on (Windows).
+pub const PIPE_NAME: &str = r"\\.\pipe\diachron";
+
+/// Upper bound on a single length-delimited frame (256 MiB).
+pub const MAX_FRAME_LEN: usize = 256 * 1024 * 1024;
+
+/// IPC protocol version, bumped on breaking changes to the wire format of
+/// `IpcMessage` or `IpcResponse`. Additions behind `#[serde(default)]` and
+/// new variants are announced as capabilities instead.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Capability: `DoctorInfo` answers with a `Doctor` report.
+pub const CAP_DOCTOR_INFO: &str = "doctor_info";
+
+/// Capability: `Health` answers with a readiness report.
+pub const CAP_HEALTH: &str = "health";
+
+/// Capability: `Search` with `stream: true` answers with result chunks.
+pub const CAP_STREAMING_SEARCH: &str = "streaming_search";
+
+/// Capability: `Subscribe` pushes captured events.
+pub const CAP_SUBSCRIBE: &str = "subscribe";
+
+/// Capability: `Stats` answers with time-bucketed aggregates.
+pub const CAP_STATS: &str = "stats";
+
+/// Capability: `BlameRange` blames several lines at once.
+pub const CAP_BLAME_RANGE: &str = "blame_range";
+
+/// Capability: `BlameFile` blames a whole file at once.
+pub const CAP_BLAME_FILE: &str = "blame_file";
+
+/// Capability: `ExportVectors` writes the vector indexes to files.
+pub const CAP_EXPORT_VECTORS: &str = "export_vectors";
+
+/// Capability: `Reconcile` repairs vector index drift on demand.
+pub const CAP_RECONCILE: &str = "reconcile";
+
+/// Capabilities of this build, as announced by the daemon in `Hello`.
+pub const CAPABILITIES: &[&str] = &[
+    CAP_DOCTOR_INFO,
+    CAP_HEALTH,
+    CAP_STREAMING_SEARCH,
+    CAP_SUBSCRIBE,
+    CAP_STATS,
+    CAP_BLAME_RANGE,
+    CAP_EXPORT_VECTORS,
+    CAP_BLAME_FILE,
+    CAP_RECONCILE,
+];
 
 /// Return the default Unix socket path.
 ///
@@ -22,6 +84,180 @@ pub fn socket_path() -> PathBuf {
         .join("diachron.sock")
 }
 
+/// Return the named pipe the daemon listens on.
+///
+/// # Returns
+/// `DIACHRON_SOCKET` if set, otherwise [`PIPE_NAME`].
+pub fn pipe_name() -> String {
+    std::env::var(SOCKET_ENV_VAR)
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| PIPE_NAME.to_string())
+}
+
+/// A connected, bidirectional byte stream to the daemon.
+pub trait IpcStream: Read + Write {
+    /// Apply read and write timeouts where the transport supports them.
+    ///
+    /// # Arguments
+    /// - `read`: Timeout for reads (waiting on the daemon's response).
+    /// - `write`: Timeout for writes.
+    ///
+    /// # Errors
+    /// Returns the underlying I/O error if the timeouts cannot be set.
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()>;
+}
+
+impl IpcStream for TcpStream {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(read))?;
+        self.set_write_timeout(Some(write))
+    }
+}
+
+#[cfg(unix)]
+impl IpcStream for UnixStream {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(read))?;
+        self.set_write_timeout(Some(write))
+    }
+}
+
+/// Client end of a named pipe, opened as a file.
+#[cfg(windows)]
+impl IpcStream for std::fs::File {
+    fn set_timeouts(&self, _read: Duration, _write: Duration) -> std::io::Result<()> {
+        // Synchronous pipe handles have no per-operation timeouts
+        Ok(())
+    }
+}
+
+/// A way of reaching the daemon.
+///
+/// All transports carry the same protocol (newline-delimited JSON or
+/// length-delimited frames).
+pub trait Transport: Send + Sync {
+    /// Open a new connection to the daemon.
+    ///
+    /// # Errors
+    /// Returns the underlying I/O error; `NotFound` and `ConnectionRefused`
+    /// mean the daemon is not running.
+    fn connect(&self) -> std::io::Result<Box<dyn IpcStream>>;
+
+    /// Whether the daemon appears to be running, without connecting.
+    ///
+    /// Transports that cannot tell report true and let `connect` fail.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Human-readable endpoint for messages (e.g. `tcp://127.0.0.1:7700`).
+    fn describe(&self) -> String;
+}
+
+/// Unix domain socket transport.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct UnixSocketTransport {
+    pub path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Transport for UnixSocketTransport {
+    fn connect(&self) -> std::io::Result<Box<dyn IpcStream>> {
+        Ok(Box::new(UnixStream::connect(&self.path)?))
+    }
+
+    fn is_available(&self) -> bool {
+        self.path.exists()
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// Windows named pipe transport (e.g. `\\.\pipe\diachron`).
+#[cfg(windows)]
+#[derive(Debug, Clone)]
+pub struct NamedPipeTransport {
+    pub name: String,
+}
+
+#[cfg(windows)]
+impl Transport for NamedPipeTransport {
+    fn connect(&self) -> std::io::Result<Box<dyn IpcStream>> {
+        // All pipe instances are briefly busy while the daemon sets up the
+        // next one after an accept
+        const ERROR_PIPE_BUSY: i32 = 231;
+        const BUSY_RETRIES: u32 = 20;
+
+        let mut attempt = 0;
+        loop {
+            match std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&self.name)
+            {
+                Ok(file) => return Ok(Box::new(file)),
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempt < BUSY_RETRIES => {
+                    attempt += 1;
+                    std::thread::sleep(Duration::from_millis(25));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// TCP transport (opt-in via `DIACHRON_ADDR`).
+#[derive(Debug, Clone)]
+pub struct TcpTransport {
+    pub addr: String,
+}
+
+impl Transport for TcpTransport {
+    fn connect(&self) -> std::io::Result<Box<dyn IpcStream>> {
+        Ok(Box::new(TcpStream::connect(self.addr.as_str())?))
+    }
+
+    fn describe(&self) -> String {
+        format!("tcp://{}", self.addr)
+    }
+}
+
+/// Resolve the transport from the environment.
+///
+/// `DIACHRON_ADDR` selects TCP; otherwise the platform's local transport is
+/// used, at `DIACHRON_SOCKET` if set.
+///
+/// # Returns
+/// The transport clients should connect through.
+pub fn default_transport() -> Box<dyn Transport> {
+    if let Some(addr) = std::env::var(ADDR_ENV_VAR).ok().filter(|a| !a.is_empty()) {
+        return Box::new(TcpTransport { addr });
+    }
+    local_transport()
+}
+
+#[cfg(unix)]
+fn local_transport() -> Box<dyn Transport> {
+    let path = std::env::var_os(SOCKET_ENV_VAR)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(socket_path);
+    Box::new(UnixSocketTransport { path })
+}
+
+#[cfg(windows)]
+fn local_transport() -> Box<dyn Transport> {
+    Box::new(NamedPipeTransport { name: pipe_name() })
+}
+
 /// Error type for IPC operations.
 #[derive(Debug)]
 pub enum IpcError {
@@ -36,7 +272,7 @@ pub enum IpcError {
     /// Invalid response format
     InvalidResponse(String),
     /// Daemon returned an error
-    DaemonError(String),
+    DaemonError(DaemonError),
 }
 
 impl std::fmt::Display for IpcError {
@@ -47,7 +283,7 @@ impl std::fmt::Display for IpcError {
             IpcError::SendFailed(e) => write!(f, "Send failed: {}", e),
             IpcError::ReceiveFailed(e) => write!(f, "Receive failed: {}", e),
             IpcError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
-            IpcError::DaemonError(msg) => write!(f, "Daemon error: {}", msg),
+            IpcError::DaemonError(err) => write!(f, "Daemon error: {}", err),
         }
     }
 }
@@ -56,8 +292,9 @@ impl std::error::Error for IpcError {}
 
 /// IPC client for communicating with the daemon.
 pub struct IpcClient {
-    socket_path: PathBuf,
+    transport: Box<dyn Transport>,
     timeout: Duration,
+    framed: bool,
 }
 
 impl Default for IpcClient {
@@ -69,12 +306,27 @@ impl Default for IpcClient {
 impl IpcClient {
     /// Create a new IPC client with default settings.
     ///
+    /// Honors `DIACHRON_ADDR` (TCP) and `DIACHRON_SOCKET` (local endpoint)
+    /// overrides; otherwise uses the default socket path or pipe name.
+    ///
     /// # Returns
-    /// Client configured with the default socket path and timeout.
+    /// Client configured with the resolved endpoint and default timeout.
     pub fn new() -> Self {
+        Self::with_transport(default_transport())
+    }
+
+    /// Create a client that connects through a specific transport.
+    ///
+    /// # Arguments
+    /// - `transport`: Transport used for every request.
+    ///
+    /// # Returns
+    /// Client configured with the transport and default timeout.
+    pub fn with_transport(transport: Box<dyn Transport>) -> Self {
         Self {
-            socket_path: socket_path(),
+            transport,
             timeout: Duration::from_secs(5),
+            framed: false,
         }
     }
 
@@ -85,11 +337,23 @@ impl IpcClient {
     ///
     /// # Returns
     /// Client configured with the provided socket path.
+    #[cfg(unix)]
     pub fn with_socket_path(socket_path: PathBuf) -> Self {
-        Self {
-            socket_path,
-            timeout: Duration::from_secs(5),
-        }
+        Self::with_transport(Box::new(UnixSocketTransport { path: socket_path }))
+    }
+
+    /// Create a client that talks to the daemon over TCP.
+    ///
+    /// The protocol is identical to the local transport
+    /// (newline-delimited JSON).
+    ///
+    /// # Arguments
+    /// - `addr`: Daemon address, e.g. `127.0.0.1:7700`.
+    ///
+    /// # Returns
+    /// Client configured with the provided TCP address.
+    pub fn connect_tcp(addr: impl Into<String>) -> Self {
+        Self::with_transport(Box::new(TcpTransport { addr: addr.into() }))
     }
 
     /// Set the connection timeout.
@@ -104,12 +368,33 @@ impl IpcClient {
         self
     }
 
+    /// Use length-delimited framing instead of newline-delimited JSON.
+    ///
+    /// Each message is prefixed with a 4-byte big-endian length. The daemon
+    /// detects the mode from the first byte of the connection, so this is
+    /// safe to enable per client. Prefer it for large payloads such as
+    /// evidence packs.
+    ///
+    /// # Arguments
+    /// - `framed`: Whether to use length-delimited framing.
+    ///
+    /// # Returns
+    /// Client configured with the requested framing.
+    pub fn framed(mut self, framed: bool) -> Self {
+        self.framed = framed;
+        self
+    }
+
     /// Check if the daemon appears to be running (socket exists).
     ///
+    /// For TCP and named pipe endpoints this cannot be known without
+    /// connecting, so it always returns true and `send` reports
+    /// `DaemonNotRunning` instead.
+    ///
     /// # Returns
-    /// True if the socket path exists, otherwise false.
+    /// True if the endpoint may be reachable, otherwise false.
     pub fn daemon_available(&self) -> bool {
-        self.socket_path.exists()
+        self.transport.is_available()
     }
 
     /// Send a message to the daemon and wait for a response.
@@ -124,48 +409,51 @@ impl IpcClient {
     /// Returns `IpcError` if the daemon is unavailable, I/O fails, or the
     /// response is invalid.
     pub fn send(&self, message: &IpcMessage) -> Result<IpcResponse, IpcError> {
-        // Check if socket exists first (fast path)
-        if !self.socket_path.exists() {
+        // Fast path: a missing socket file means no daemon
+        if !self.transport.is_available() {
             return Err(IpcError::DaemonNotRunning);
         }
 
-        // Connect to daemon
-        let mut stream = UnixStream::connect(&self.socket_path).map_err(|e| {
-            if e.kind() == std::io::ErrorKind::ConnectionRefused
-                || e.kind() == std::io::ErrorKind::NotFound
-            {
-                IpcError::DaemonNotRunning
-            } else {
-                IpcError::ConnectionFailed(e)
-            }
-        })?;
-
-        // Set timeouts
-        stream.set_read_timeout(Some(self.timeout)).ok();
-        stream.set_write_timeout(Some(self.timeout)).ok();
+        let stream = self.transport.connect().map_err(connect_error)?;
+        stream.set_timeouts(self.timeout, self.timeout).ok();
+        self.exchange(stream, message)
+    }
 
-        // Send message as JSON line
+    /// Write one message and read one response over a connected stream.
+    fn exchange<S: Read + Write>(
+        &self,
+        mut stream: S,
+        message: &IpcMessage,
+    ) -> Result<IpcResponse, IpcError> {
         let json = serde_json::to_string(message).map_err(|e| {
             IpcError::SendFailed(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
         })?;
 
-        writeln!(stream, "{}", json).map_err(IpcError::SendFailed)?;
-        stream.flush().map_err(IpcError::SendFailed)?;
+        let response: IpcResponse = if self.framed {
+            // Send and receive length-delimited frames
+            write_frame(&mut stream, json.as_bytes()).map_err(IpcError::SendFailed)?;
+            let payload = read_frame(&mut stream).map_err(IpcError::ReceiveFailed)?;
+            serde_json::from_slice(&payload)
+                .map_err(|e| IpcError::InvalidResponse(e.to_string()))?
+        } else {
+            // Send message as JSON line
+            writeln!(stream, "{}", json).map_err(IpcError::SendFailed)?;
+            stream.flush().map_err(IpcError::SendFailed)?;
 
-        // Read response
-        let mut reader = BufReader::new(stream);
-        let mut response_line = String::new();
-        reader
-            .read_line(&mut response_line)
-            .map_err(IpcError::ReceiveFailed)?;
+            // Read response
+            let mut reader = BufReader::new(stream);
+            let mut response_line = String::new();
+            reader
+                .read_line(&mut response_line)
+                .map_err(IpcError::ReceiveFailed)?;
 
-        // Parse response
-        let response: IpcResponse = serde_json::from_str(&response_line)
-            .map_err(|e| IpcError::InvalidResponse(e.to_string()))?;
+            serde_json::from_str(&response_line)
+                .map_err(|e| IpcError::InvalidResponse(e.to_string()))?
+        };
 
         // Check for daemon errors
-        if let IpcResponse::Error(msg) = &response {
-            return Err(IpcError::DaemonError(msg.clone()));
+        if let IpcResponse::Error(err) = &response {
+            return Err(IpcError::DaemonError(err.clone()));
         }
 
         Ok(response)
@@ -185,7 +473,7 @@ impl IpcClient {
         let response = self.send(&IpcMessage::Capture(event))?;
         match response {
             IpcResponse::Ok => Ok(()),
-            IpcResponse::Error(msg) => Err(IpcError::DaemonError(msg)),
+            IpcResponse::Error(err) => Err(IpcError::DaemonError(err)),
             _ => Err(IpcError::InvalidResponse("Unexpected response type".into())),
         }
     }
@@ -204,12 +492,78 @@ impl IpcClient {
                 uptime_secs,
                 events_count,
             } => Ok((uptime_secs, events_count)),
-            IpcResponse::Error(msg) => Err(IpcError::DaemonError(msg)),
+            IpcResponse::Error(err) => Err(IpcError::DaemonError(err)),
             _ => Err(IpcError::InvalidResponse("Unexpected response type".into())),
         }
     }
 }
 
+/// Write one length-delimited frame: 4-byte big-endian length, then payload.
+///
+/// # Arguments
+/// - `writer`: Destination stream.
+/// - `payload`: Frame body (serialized JSON).
+///
+/// # Errors
+/// Returns `InvalidInput` if the payload exceeds [`MAX_FRAME_LEN`], or any
+/// underlying I/O error.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> std::io::Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("frame of {} bytes exceeds limit", payload.len()),
+        ));
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Read one length-delimited frame written by [`write_frame`].
+///
+/// # Arguments
+/// - `reader`: Source stream.
+///
+/// # Returns
+/// The frame body.
+///
+/// # Errors
+/// Returns `InvalidData` if the declared length exceeds [`MAX_FRAME_LEN`],
+/// or any underlying I/O error (including `UnexpectedEof`).
+pub fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf)?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds limit", len),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Whether a connection's first byte indicates length-delimited framing.
+///
+/// Line-mode messages are JSON objects and always start with `{`; a frame
+/// starts with the high byte of its length, which is never `{` for frames
+/// within [`MAX_FRAME_LEN`].
+pub fn is_framed_start(first_byte: u8) -> bool {
+    first_byte != b'{'
+}
+
+/// Map a connect error, treating refused/missing endpoints as "not running".
+fn connect_error(e: std::io::Error) -> IpcError {
+    if e.kind() == std::io::ErrorKind::ConnectionRefused || e.kind() == std::io::ErrorKind::NotFound
+    {
+        IpcError::DaemonNotRunning
+    } else {
+        IpcError::ConnectionFailed(e)
+    }
+}
+
 /// Send a capture event to the daemon using a default client.
 ///
 /// # Arguments
@@ -245,6 +599,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(unix)]
     fn test_daemon_not_running() {
         let client = IpcClient::with_socket_path(PathBuf::from("/nonexistent/path.sock"));
         assert!(!client.daemon_available());
@@ -252,4 +607,96 @@ mod tests {
         let result = client.ping();
         assert!(matches!(result, Err(IpcError::DaemonNotRunning)));
     }
+
+    #[test]
+    fn test_framed_codec_round_trips_2mb_payload() {
+        let snippet = "x".repeat(2 * 1024 * 1024);
+        let response = IpcResponse::Error(DaemonError::Internal(snippet.clone()));
+        let json = serde_json::to_vec(&response).unwrap();
+
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &json).unwrap();
+        assert_eq!(buf.len(), json.len() + 4);
+        assert!(is_framed_start(buf[0]));
+
+        let payload = read_frame(&mut buf.as_slice()).unwrap();
+        match serde_json::from_slice::<IpcResponse>(&payload).unwrap() {
+            IpcResponse::Error(err) => assert_eq!(err.message(), snippet),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_error_response_carries_code_and_accepts_legacy_string() {
+        let response = IpcResponse::Error(DaemonError::Busy("Reindex running".to_string()));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "Error",
+                "payload": { "code": "busy", "message": "Reindex running", "retryable": true }
+            })
+        );
+        match serde_json::from_value::<IpcResponse>(json).unwrap() {
+            IpcResponse::Error(err) => assert!(matches!(err, DaemonError::Busy(_))),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let legacy = r#"{"type":"Error","payload":"Database error: locked"}"#;
+        match serde_json::from_str::<IpcResponse>(legacy).unwrap() {
+            IpcResponse::Error(err) => {
+                assert_eq!(err.code(), "internal");
+                assert_eq!(err.to_string(), "Database error: locked");
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_frame_rejects_oversized_length() {
+        let mut buf = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes().to_vec();
+        buf.extend_from_slice(b"{}");
+        let err = read_frame(&mut buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(!is_framed_start(b'{'));
+    }
+
+    #[test]
+    fn test_tcp_round_trip() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(matches!(
+                serde_json::from_str::<IpcMessage>(&line).unwrap(),
+                IpcMessage::Ping
+            ));
+            let response = IpcResponse::Pong {
+                uptime_secs: 7,
+                events_count: 3,
+            };
+            let mut stream = stream;
+            writeln!(stream, "{}", serde_json::to_string(&response).unwrap()).unwrap();
+        });
+
+        let client = IpcClient::connect_tcp(addr.to_string());
+        assert!(client.daemon_available());
+        assert_eq!(client.ping().unwrap(), (7, 3));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_tcp_daemon_not_running() {
+        // Bind then drop to get a port with nothing listening
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = IpcClient::connect_tcp(addr.to_string());
+        assert!(matches!(client.ping(), Err(IpcError::DaemonNotRunning)));
+    }
 }
@@ -7,43 +7,76 @@
 //! - IPC client for daemon communication
 //! - Vector index for semantic search
 
+pub mod diff_stats;
 pub mod error;
 pub mod evidence_pack;
+pub mod export;
 pub mod fingerprint;
 pub mod hash_chain;
 pub mod ipc;
 pub mod pr_correlation;
 pub mod schema;
+pub mod simd;
+pub mod template;
+pub mod time;
 pub mod types;
 pub mod vector;
 
-pub use error::Error;
+pub use diff_stats::DiffStats;
+pub use error::{DaemonError, Error, UnknownVariant};
 pub use evidence_pack::{
-    export_json, generate_evidence_pack, render_markdown_narrative, EvidencePack,
-    VerificationStatus, DIACHRON_VERSION,
+    export_json, export_sarif, generate_evidence_pack, render_html, render_markdown_narrative,
+    EvidencePack, VerificationStatus, DEFAULT_NARRATIVE_TEMPLATE, DIACHRON_VERSION,
+    NARRATIVE_EVENTS_PER_COMMIT, SARIF_RULE_LOW_CONFIDENCE, SARIF_RULE_UNMATCHED,
+};
+pub use export::{
+    import_batch, import_event, import_exchange, write_export, ExportCounts, ExportDocument,
+    ExportedEvent, ImportCounts, EXPORT_FORMAT_VERSION,
 };
 pub use fingerprint::{
     compute_fingerprint, cosine_similarity, extract_context, format_fingerprint, match_fingerprint,
-    FingerprintMatch, HunkFingerprint, MatchConfidence, MatchType, DEFAULT_CONTEXT_LINES,
-    DEFAULT_SIMILARITY_THRESHOLD,
+    FingerprintMatch, FingerprintOptions, HunkFingerprint, Lang, MatchConfidence, MatchType,
+    DEFAULT_CONTEXT_LINES, DEFAULT_SIMILARITY_THRESHOLD,
 };
 pub use hash_chain::{
-    compute_event_hash, create_checkpoint, format_hash, format_hash_short, get_last_event_hash,
-    verify_chain, ChainBreak, ChainCheckpoint, ChainVerificationResult, EventHashInput,
-    GENESIS_HASH,
+    compute_event_hash, compute_field_hashes, compute_merkle_proof, compute_merkle_root,
+    create_checkpoint, default_signing_key_path, diverging_fields, format_hash, format_hash_short,
+    generate_signing_key, get_last_event_hash, load_checkpoints, load_signing_key,
+    load_verifying_key, save_signing_key, verify_chain, verify_chain_since,
+    verify_checkpoint_signature, verify_merkle_inclusion, verifying_key_path, ChainBreak,
+    ChainCheckpoint, ChainVerificationResult, EventHashInput, MerkleProofStep, SigningKey,
+    VerifyingKey, GENESIS_HASH,
+};
+#[cfg(windows)]
+pub use ipc::NamedPipeTransport;
+#[cfg(unix)]
+pub use ipc::UnixSocketTransport;
+pub use ipc::{
+    default_transport, is_daemon_running, is_framed_start, pipe_name, read_frame, send_to_daemon,
+    write_frame, IpcClient, IpcError, IpcStream, TcpTransport, Transport, ADDR_ENV_VAR,
+    CAPABILITIES, CAP_BLAME_FILE, CAP_BLAME_RANGE, CAP_DOCTOR_INFO, CAP_EXPORT_VECTORS, CAP_HEALTH,
+    CAP_RECONCILE, CAP_STATS, CAP_STREAMING_SEARCH, CAP_SUBSCRIBE, MAX_FRAME_LEN, PIPE_NAME,
+    PROTOCOL_VERSION, SOCKET_ENV_VAR,
 };
-pub use ipc::{is_daemon_running, send_to_daemon, IpcClient, IpcError};
 pub use pr_correlation::{
-    correlate_events_to_pr, CommitEvidence, MatchConfidence as PRMatchConfidence, PREvidence,
-    PRSummary, DEFAULT_TIME_WINDOW_SECS,
+    correlate_events_to_pr, CommitEvidence, CorrelationConfig,
+    MatchConfidence as PRMatchConfidence, PREvidence, PRSummary, DEFAULT_TIME_WINDOW_SECS,
 };
-pub use schema::{fts_search_events, fts_search_exchanges, init_schema, FtsSearchResult};
+pub use schema::{
+    fts_search_events, fts_search_exchanges, init_schema, lookup_event_hits, lookup_exchange_hits,
+    migrate, project_from_metadata, project_name_from_cwd, ExchangeFtsWeights, FtsOptions,
+    FtsSearchResult, SearchFilter, SearchHitInfo, BUSY_TIMEOUT,
+};
+pub use template::{render_template, TemplateError};
 pub use types::*;
-pub use vector::{VectorError, VectorIndex, VectorSearchResult, EMBEDDING_DIM};
+pub use vector::{
+    HnswConfig, Metric, Quantization, VectorEntry, VectorError, VectorIndex, VectorSearchResult,
+    EMBEDDING_DIM,
+};
 
 /// Re-export commonly used items
 pub mod prelude {
-    pub use crate::error::Error;
+    pub use crate::error::{DaemonError, Error};
     pub use crate::ipc::{is_daemon_running, send_to_daemon, IpcClient, IpcError};
     pub use crate::types::*;
     pub use crate::vector::{VectorError, VectorIndex, VectorSearchResult};
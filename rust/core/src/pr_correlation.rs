@@ -9,6 +9,10 @@
 //! 2. **Session match**: Events in same session as a commit event (MEDIUM confidence)
 //! 3. **Time match**: Events within 5min before commit, same branch (LOW confidence)
 //!
+//! When an event falls within the time window of several commits, the commit
+//! whose changed files overlap the event's `file_path` wins (see
+//! [`CorrelationConfig`]); remaining ties go to the nearest commit in time.
+//!
 //! # Usage
 //!
 //! ```rust,ignore
@@ -16,14 +20,19 @@
 //!
 //! let evidence = correlate_events_to_pr(
 //!     &conn,
-//!     &["abc123", "def456"],  // Commit SHAs from PR
-//!     "feat/auth",            // Branch name
-//!     (start_time, end_time), // Time window
+//!     142,                           // PR number
+//!     &["abc123", "def456"],         // Commit SHAs from PR
+//!     "feat/auth",                   // Branch name
+//!     start_time,                    // Time window start
+//!     end_time,                      // Time window end
+//!     &CorrelationConfig::default(),
 //! )?;
 //!
 //! println!("Coverage: {:.1}%", evidence.coverage_pct);
 //! ```
 
+use std::collections::{HashMap, HashSet};
+
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
@@ -32,6 +41,34 @@ use crate::types::StoredEvent;
 /// Time window for event-commit matching (in seconds)
 pub const DEFAULT_TIME_WINDOW_SECS: i64 = 300; // 5 minutes
 
+/// Tuning for time-window (LOW confidence) event-commit matching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    /// How long before a commit an event may occur and still match it
+    pub time_window_secs: i64,
+    /// Score bonus for a commit whose changed files include the event's file.
+    /// Time proximity contributes at most 1.0, so a weight >= 1.0 makes file
+    /// overlap decisive when an event is in the window of several commits.
+    pub file_overlap_weight: f32,
+    /// Only time-match events whose file the commit changed
+    pub require_file_match: bool,
+    /// Files changed by each commit (SHA -> repo-relative paths). Commits
+    /// without an entry can still time-match unless `require_file_match`.
+    #[serde(default)]
+    pub commit_files: HashMap<String, Vec<String>>,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            time_window_secs: DEFAULT_TIME_WINDOW_SECS,
+            file_overlap_weight: 1.0,
+            require_file_match: false,
+            commit_files: HashMap::new(),
+        }
+    }
+}
+
 /// Evidence gathered for a pull request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PREvidence {
@@ -82,6 +119,16 @@ impl MatchConfidence {
             MatchConfidence::Low => "LOW",
         }
     }
+
+    /// Parse a display string (case-insensitive) back into a confidence level.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "HIGH" => Some(MatchConfidence::High),
+            "MEDIUM" => Some(MatchConfidence::Medium),
+            "LOW" => Some(MatchConfidence::Low),
+            _ => None,
+        }
+    }
 }
 
 /// Correlate events to pull request commits.
@@ -94,6 +141,7 @@ impl MatchConfidence {
 /// * `branch` - Branch name for the PR
 /// * `start_time` - Start of time window (ISO timestamp)
 /// * `end_time` - End of time window (ISO timestamp)
+/// * `config` - Time-window matching and tie-breaking settings
 ///
 /// # Returns
 ///
@@ -105,20 +153,21 @@ pub fn correlate_events_to_pr(
     branch: &str,
     start_time: &str,
     end_time: &str,
+    config: &CorrelationConfig,
 ) -> Result<PREvidence, rusqlite::Error> {
-    let mut commit_evidence: Vec<CommitEvidence> = Vec::new();
     let mut unmatched_events: Vec<StoredEvent> = Vec::new();
-    let mut matched_event_ids: std::collections::HashSet<i64> = std::collections::HashSet::new();
+    let mut matched_event_ids: HashSet<i64> = HashSet::new();
 
     // 1. Query all events in the time window
     let all_events = query_events_in_window(conn, start_time, end_time)?;
     let total_events = all_events.len() as u64;
 
-    // 2. For each commit, find matching events
-    for commit_sha in pr_commits {
-        let mut commit_events: Vec<StoredEvent> = Vec::new();
-        let mut confidence = MatchConfidence::Low;
+    // Per-commit (events, confidence), in PR commit order
+    let mut matches: Vec<(Vec<StoredEvent>, MatchConfidence)> =
+        vec![(Vec::new(), MatchConfidence::Low); pr_commits.len()];
 
+    // 2. For each commit, find direct and session matches
+    for (commit_sha, (commit_events, confidence)) in pr_commits.iter().zip(matches.iter_mut()) {
         // 2a. HIGH confidence: Direct git_commit_sha match
         let direct_matches: Vec<StoredEvent> = all_events
             .iter()
@@ -132,7 +181,7 @@ pub fn correlate_events_to_pr(
             .collect();
 
         if !direct_matches.is_empty() {
-            confidence = MatchConfidence::High;
+            *confidence = MatchConfidence::High;
             for event in &direct_matches {
                 if matched_event_ids.insert(event.id) {
                     commit_events.push(event.clone());
@@ -159,45 +208,74 @@ pub fn correlate_events_to_pr(
                 for event in session_matches {
                     if matched_event_ids.insert(event.id) {
                         commit_events.push(event);
-                        if confidence == MatchConfidence::Low {
-                            confidence = MatchConfidence::Medium;
+                        if *confidence == MatchConfidence::Low {
+                            *confidence = MatchConfidence::Medium;
                         }
                     }
                 }
             }
         }
+    }
 
-        // 2c. LOW confidence: Time-based matching
-        // Find commit timestamp and match events within window
-        let commit_timestamp = get_commit_timestamp(conn, commit_sha);
-        if let Some(commit_ts) = commit_timestamp {
-            let time_matches: Vec<StoredEvent> = all_events
-                .iter()
-                .filter(|e| {
-                    !matched_event_ids.contains(&e.id)
-                        && is_within_time_window(&e.timestamp, &commit_ts, DEFAULT_TIME_WINDOW_SECS)
-                        && matches_branch(e, branch)
-                })
-                .cloned()
-                .collect();
-
-            for event in time_matches {
-                if matched_event_ids.insert(event.id) {
-                    commit_events.push(event);
-                }
-            }
+    // 2c. LOW confidence: Time-based matching
+    // Each remaining event goes to the best-scoring commit whose window holds it
+    let commit_timestamps: Vec<Option<String>> = pr_commits
+        .iter()
+        .map(|sha| get_commit_timestamp(conn, sha))
+        .collect();
+    for event in &all_events {
+        if matched_event_ids.contains(&event.id) || !matches_branch(event, branch) {
+            continue;
         }
 
-        if !commit_events.is_empty() {
-            commit_evidence.push(CommitEvidence {
-                sha: commit_sha.clone(),
-                message: get_commit_message(conn, commit_sha),
-                events: commit_events,
-                confidence,
+        let best = pr_commits
+            .iter()
+            .zip(&commit_timestamps)
+            .enumerate()
+            .filter_map(|(idx, (sha, commit_ts))| {
+                let commit_ts = commit_ts.as_deref()?;
+                if !is_within_time_window(&event.timestamp, commit_ts, config.time_window_secs) {
+                    return None;
+                }
+                let overlaps = config
+                    .commit_files
+                    .get(sha)
+                    .is_some_and(|files| file_overlaps(event, files));
+                if config.require_file_match && !overlaps {
+                    return None;
+                }
+                let score = time_proximity(&event.timestamp, commit_ts, config.time_window_secs)
+                    + if overlaps {
+                        config.file_overlap_weight
+                    } else {
+                        0.0
+                    };
+                Some((idx, score))
+            })
+            // Highest score wins; on equal scores the earlier commit wins
+            .fold(None, |best: Option<(usize, f32)>, candidate| match best {
+                Some(b) if b.1 >= candidate.1 => Some(b),
+                _ => Some(candidate),
             });
+
+        if let Some((idx, _)) = best {
+            matched_event_ids.insert(event.id);
+            matches[idx].0.push(event.clone());
         }
     }
 
+    let commit_evidence: Vec<CommitEvidence> = pr_commits
+        .iter()
+        .zip(matches)
+        .filter(|(_, (events, _))| !events.is_empty())
+        .map(|(commit_sha, (events, confidence))| CommitEvidence {
+            sha: commit_sha.clone(),
+            message: get_commit_message(conn, commit_sha),
+            events,
+            confidence,
+        })
+        .collect();
+
     // 3. Collect unmatched events
     for event in all_events {
         if !matched_event_ids.contains(&event.id) {
@@ -231,7 +309,8 @@ fn query_events_in_window(
 ) -> Result<Vec<StoredEvent>, rusqlite::Error> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
-                operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata
+                operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                json_extract(metadata, '$.command_category')
          FROM events
          WHERE timestamp >= ?1 AND timestamp <= ?2
          ORDER BY timestamp ASC",
@@ -252,6 +331,7 @@ fn query_events_in_window(
                 ai_summary: row.get(9)?,
                 git_commit_sha: row.get(10)?,
                 metadata: row.get(11)?,
+                command_category: row.get(12)?,
             })
         })?
         .filter_map(|r| r.ok())
@@ -288,25 +368,25 @@ fn get_commit_message(conn: &Connection, commit_sha: &str) -> Option<String> {
     })
 }
 
-/// Check if event timestamp is within window of commit timestamp.
-fn is_within_time_window(event_ts: &str, commit_ts: &str, window_secs: i64) -> bool {
+/// Parse an event/commit timestamp into Unix seconds.
+fn parse_timestamp(ts: &str) -> Option<i64> {
     use chrono::NaiveDateTime;
 
-    // Try parsing as NaiveDateTime (without timezone) first, then fallback to RFC3339
-    let parse_timestamp = |ts: &str| -> Option<i64> {
-        // Try various formats without timezone
-        NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.3f")
-            .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S"))
-            .map(|dt| dt.and_utc().timestamp())
-            .ok()
-            .or_else(|| {
-                // Try RFC3339 with timezone
-                chrono::DateTime::parse_from_rfc3339(ts)
-                    .map(|dt| dt.timestamp())
-                    .ok()
-            })
-    };
+    // Try various formats without timezone first, then fallback to RFC3339
+    NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.3f")
+        .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S"))
+        .map(|dt| dt.and_utc().timestamp())
+        .ok()
+        .or_else(|| {
+            // Try RFC3339 with timezone
+            chrono::DateTime::parse_from_rfc3339(ts)
+                .map(|dt| dt.timestamp())
+                .ok()
+        })
+}
 
+/// Check if event timestamp is within window of commit timestamp.
+fn is_within_time_window(event_ts: &str, commit_ts: &str, window_secs: i64) -> bool {
     let event_secs = parse_timestamp(event_ts);
     let commit_secs = parse_timestamp(commit_ts);
 
@@ -319,6 +399,32 @@ fn is_within_time_window(event_ts: &str, commit_ts: &str, window_secs: i64) -> b
     }
 }
 
+/// How close an event is to a commit: 1.0 at the commit, 0.0 at the window edge.
+fn time_proximity(event_ts: &str, commit_ts: &str, window_secs: i64) -> f32 {
+    match (parse_timestamp(event_ts), parse_timestamp(commit_ts)) {
+        (Some(e), Some(c)) if window_secs > 0 => {
+            1.0 - ((c - e).abs() as f32 / window_secs as f32).min(1.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Check if an event's file is among a commit's changed files.
+///
+/// Event paths are often absolute while git reports repo-relative paths, so
+/// a path-component suffix match counts as overlap.
+fn file_overlaps(event: &StoredEvent, commit_files: &[String]) -> bool {
+    let Some(ref path) = event.file_path else {
+        return false;
+    };
+    commit_files.iter().any(|file| {
+        path == file
+            || path
+                .strip_suffix(file.as_str())
+                .is_some_and(|prefix| prefix.ends_with('/'))
+    })
+}
+
 /// Check if event metadata contains matching branch.
 fn matches_branch(event: &StoredEvent, branch: &str) -> bool {
     event.metadata.as_ref().map_or(true, |m| {
@@ -452,6 +558,7 @@ mod tests {
             ai_summary: None,
             git_commit_sha: None,
             metadata: Some(r#"{"git_branch": "feat/auth"}"#.to_string()),
+            command_category: None,
         };
 
         assert!(matches_branch(&event_with_branch, "feat/auth"));
@@ -460,9 +567,103 @@ mod tests {
         // Event without metadata should match any branch (permissive)
         let event_no_meta = StoredEvent {
             metadata: None,
+            command_category: None,
             ..event_with_branch.clone()
         };
 
         assert!(matches_branch(&event_no_meta, "any/branch"));
     }
+
+    fn insert_event(
+        conn: &Connection,
+        timestamp: &str,
+        tool_name: &str,
+        file_path: Option<&str>,
+        git_commit_sha: Option<&str>,
+    ) {
+        conn.execute(
+            "INSERT INTO events (timestamp, tool_name, file_path, operation, git_commit_sha)
+             VALUES (?1, ?2, ?3, 'modify', ?4)",
+            rusqlite::params![timestamp, tool_name, file_path, git_commit_sha],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_time_match_tie_broken_by_file_overlap() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::schema::init_schema(&conn).unwrap();
+
+        // Two commits 30s apart; both edits fall within both windows
+        insert_event(
+            &conn,
+            "2026-01-11T00:04:00.000",
+            "Edit",
+            Some("/repo/src/a.rs"),
+            None,
+        );
+        insert_event(
+            &conn,
+            "2026-01-11T00:04:10.000",
+            "Edit",
+            Some("/repo/src/b.rs"),
+            None,
+        );
+        insert_event(&conn, "2026-01-11T00:05:00.000", "Bash", None, Some("aaa"));
+        insert_event(&conn, "2026-01-11T00:05:30.000", "Bash", None, Some("bbb"));
+
+        let commits = vec!["aaa".to_string(), "bbb".to_string()];
+        let correlate = |config: &CorrelationConfig| {
+            correlate_events_to_pr(
+                &conn,
+                1,
+                &commits,
+                "main",
+                "2026-01-11T00:00:00",
+                "2026-01-11T01:00:00",
+                config,
+            )
+            .unwrap()
+        };
+        let files_of = |evidence: &PREvidence, sha: &str| -> Vec<String> {
+            evidence
+                .commits
+                .iter()
+                .find(|c| c.sha == sha)
+                .map(|c| {
+                    c.events
+                        .iter()
+                        .filter_map(|e| e.file_path.clone())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        // Without file info, time proximity alone sends both edits to the first commit
+        let evidence = correlate(&CorrelationConfig::default());
+        assert_eq!(
+            files_of(&evidence, "aaa"),
+            vec!["/repo/src/a.rs", "/repo/src/b.rs"]
+        );
+
+        // With changed files, each edit goes to the commit that touched its file
+        let mut config = CorrelationConfig::default();
+        config
+            .commit_files
+            .insert("aaa".to_string(), vec!["src/a.rs".to_string()]);
+        config
+            .commit_files
+            .insert("bbb".to_string(), vec!["src/b.rs".to_string()]);
+        let evidence = correlate(&config);
+        assert_eq!(files_of(&evidence, "aaa"), vec!["/repo/src/a.rs"]);
+        assert_eq!(files_of(&evidence, "bbb"), vec!["/repo/src/b.rs"]);
+        assert_eq!(evidence.coverage_pct, 100.0);
+
+        // Requiring a file match leaves edits to unlisted files unmatched
+        config.commit_files.remove("bbb");
+        config.require_file_match = true;
+        let evidence = correlate(&config);
+        assert_eq!(files_of(&evidence, "aaa"), vec!["/repo/src/a.rs"]);
+        assert_eq!(evidence.unmatched_events.len(), 1);
+    }
 }
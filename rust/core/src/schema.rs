@@ -5,12 +5,48 @@
 //! - exchanges: Conversation memory (migrated from episodic-memory)
 //! - FTS5 indexes for full-text search
 
-use rusqlite::Connection;
+use std::collections::HashMap;
+use std::time::Duration;
 
-use crate::error::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+
+use crate::error::{Error, Result};
+use crate::time;
 
 /// Current schema version.
-pub const SCHEMA_VERSION: i32 = 4;
+pub const SCHEMA_VERSION: u32 = 14;
+
+/// How long a connection waits on another connection's lock before failing
+/// with "database is locked".
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A single schema upgrade step.
+///
+/// Steps must be idempotent: a database created by an older build (or by the
+/// hook's fallback writer) may already contain some of the objects a step
+/// creates, so columns are added through [`add_column_if_missing`] and
+/// tables/indexes use `IF NOT EXISTS`.
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered migration steps. Entry `i` upgrades the database to version `i + 1`.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1,
+    migrate_v2,
+    migrate_v3,
+    migrate_v4,
+    migrate_v5,
+    migrate_v6,
+    migrate_v7,
+    migrate_v8,
+    migrate_v9,
+    migrate_v10,
+    migrate_v11,
+    migrate_v12,
+    migrate_v13,
+    migrate_v14,
+];
 
 /// Initialize or migrate the database schema.
 ///
@@ -20,32 +56,51 @@ pub const SCHEMA_VERSION: i32 = 4;
 /// # Errors
 /// Returns `Error` if schema queries or migrations fail.
 pub fn init_schema(conn: &Connection) -> Result<()> {
-    let version = get_schema_version(conn)?;
+    migrate(conn)?;
+    Ok(())
+}
 
-    if version < 1 {
-        migrate_v1(conn)?;
-    }
-    if version < 2 {
-        migrate_v2(conn)?;
-    }
-    if version < 3 {
-        migrate_v3(conn)?;
-    }
-    if version < 4 {
-        migrate_v4(conn)?;
+/// Bring the database up to [`SCHEMA_VERSION`].
+///
+/// Each pending step runs in its own transaction together with the version
+/// bump, so a failed step leaves the database at the previous version. Safe
+/// to call repeatedly; an up-to-date database is left untouched.
+///
+/// # Arguments
+/// - `conn`: Open SQLite connection for the database.
+///
+/// # Returns
+/// The schema version after migrating.
+///
+/// # Errors
+/// Returns `Error` if schema queries or a migration step fail.
+pub fn migrate(conn: &Connection) -> Result<u32> {
+    let mut version = get_schema_version(conn)?;
+
+    for (index, step) in MIGRATIONS.iter().enumerate() {
+        let target = index as u32 + 1;
+        if version >= target {
+            continue;
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        step(&tx)?;
+        set_schema_version(&tx, target)?;
+        tx.commit()?;
+        version = target;
     }
 
-    Ok(())
+    Ok(version)
 }
 
-fn get_schema_version(conn: &Connection) -> Result<i32> {
+fn get_schema_version(conn: &Connection) -> Result<u32> {
     // Create version table if not exists
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY)",
         [],
     )?;
 
-    let version: i32 = conn.query_row(
+    let version: u32 = conn.query_row(
         "SELECT COALESCE(MAX(version), 0) FROM schema_version",
         [],
         |row| row.get(0),
@@ -54,14 +109,34 @@ fn get_schema_version(conn: &Connection) -> Result<i32> {
     Ok(version)
 }
 
-fn set_schema_version(conn: &Connection, version: i32) -> Result<()> {
+fn set_schema_version(conn: &Connection, version: u32) -> Result<()> {
     conn.execute(
-        "INSERT INTO schema_version (version) VALUES (?1)",
+        "INSERT OR IGNORE INTO schema_version (version) VALUES (?1)",
         [version],
     )?;
     Ok(())
 }
 
+/// Add a column unless the table already has it.
+///
+/// SQLite has no `ADD COLUMN IF NOT EXISTS`, so the existing columns are read
+/// from `PRAGMA table_info` first.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, decl
+        ))?;
+    }
+    Ok(())
+}
+
 /// V1: Original events table (existing Diachron schema)
 fn migrate_v1(conn: &Connection) -> Result<()> {
     conn.execute_batch(
@@ -89,7 +164,6 @@ fn migrate_v1(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_events_tool_name ON events(tool_name);",
     )?;
 
-    set_schema_version(conn, 1)?;
     Ok(())
 }
 
@@ -137,17 +211,15 @@ fn migrate_v2(conn: &Connection) -> Result<()> {
         );",
     )?;
 
-    set_schema_version(conn, 2)?;
     Ok(())
 }
 
 /// V3: Add FTS triggers and project_path column
 fn migrate_v3(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        "-- Add project_path column to events if not exists
-        ALTER TABLE events ADD COLUMN project_path TEXT;
+    add_column_if_missing(conn, "events", "project_path", "TEXT")?;
 
-        -- Create FTS sync triggers for events
+    conn.execute_batch(
+        "-- Create FTS sync triggers for events
         CREATE TRIGGER IF NOT EXISTS events_fts_insert AFTER INSERT ON events BEGIN
             INSERT INTO events_fts(rowid, tool_name, operation, diff_summary, raw_input)
             VALUES (new.id, new.tool_name, new.operation, new.diff_summary, new.raw_input);
@@ -183,7 +255,6 @@ fn migrate_v3(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_events_project_path ON events(project_path);",
     )?;
 
-    set_schema_version(conn, 3)?;
     Ok(())
 }
 
@@ -194,15 +265,16 @@ fn migrate_v3(conn: &Connection) -> Result<()> {
 /// - Content fingerprint columns (content_hash, context_hash) for stable blame
 /// - Chain checkpoints table for daily integrity snapshots
 fn migrate_v4(conn: &Connection) -> Result<()> {
+    // Hash chain columns for tamper-evidence
+    add_column_if_missing(conn, "events", "prev_hash", "BLOB")?;
+    add_column_if_missing(conn, "events", "event_hash", "BLOB")?;
+
+    // Content fingerprint columns for stable blame
+    add_column_if_missing(conn, "events", "content_hash", "BLOB")?;
+    add_column_if_missing(conn, "events", "context_hash", "BLOB")?;
+
     conn.execute_batch(
-        "-- Hash chain columns for tamper-evidence
-        ALTER TABLE events ADD COLUMN prev_hash BLOB;
-        ALTER TABLE events ADD COLUMN event_hash BLOB;
-        CREATE INDEX IF NOT EXISTS idx_events_hash ON events(event_hash);
-
-        -- Content fingerprint columns for stable blame
-        ALTER TABLE events ADD COLUMN content_hash BLOB;
-        ALTER TABLE events ADD COLUMN context_hash BLOB;
+        "CREATE INDEX IF NOT EXISTS idx_events_hash ON events(event_hash);
         CREATE INDEX IF NOT EXISTS idx_events_content_hash ON events(content_hash);
 
         -- Chain checkpoints table for daily integrity snapshots
@@ -217,49 +289,534 @@ fn migrate_v4(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_checkpoints_date ON chain_checkpoints(date);",
     )?;
 
-    set_schema_version(conn, 4)?;
     Ok(())
 }
 
+/// V5: Add Merkle roots to chain checkpoints
+///
+/// Checkpoints created before this migration keep a NULL root and are
+/// skipped by Merkle verification.
+fn migrate_v5(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "chain_checkpoints", "merkle_root", "BLOB")?;
+
+    Ok(())
+}
+
+/// V6: Record per-field digests so chain breaks can name the altered field
+fn migrate_v6(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "events", "field_hashes", "TEXT")?;
+
+    Ok(())
+}
+
+/// V7: Record the last covered event ID so verification can resume after a checkpoint
+fn migrate_v7(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "chain_checkpoints", "last_event_id", "INTEGER")?;
+
+    conn.execute_batch(
+        "UPDATE chain_checkpoints SET last_event_id = (
+            SELECT id FROM events WHERE events.event_hash = chain_checkpoints.final_hash
+        ) WHERE last_event_id IS NULL;",
+    )?;
+
+    Ok(())
+}
+
+/// V8: Persist the semantic signature of written content for fingerprint blame
+fn migrate_v8(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "events", "semantic_sig", "BLOB")?;
+
+    Ok(())
+}
+
+/// V9: Record the project each event belongs to, backfilled from `metadata.cwd`
+///
+/// Also narrows the events FTS update trigger to the indexed columns. Rows
+/// written before V3 were never indexed, and re-indexing them on an unrelated
+/// UPDATE (like this backfill) corrupts `events_fts`.
+fn migrate_v9(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "events", "project", "TEXT")?;
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_events_project ON events(project);
+
+        DROP TRIGGER IF EXISTS events_fts_update;
+        CREATE TRIGGER events_fts_update
+        AFTER UPDATE OF tool_name, operation, diff_summary, raw_input ON events BEGIN
+            DELETE FROM events_fts WHERE rowid = old.id;
+            INSERT INTO events_fts(rowid, tool_name, operation, diff_summary, raw_input)
+            VALUES (new.id, new.tool_name, new.operation, new.diff_summary, new.raw_input);
+        END;",
+    )?;
+
+    let pending: Vec<(i64, String)> = conn
+        .prepare(
+            "SELECT id, metadata FROM events
+             WHERE project IS NULL AND metadata LIKE '%\"cwd\"%'",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut update = conn.prepare("UPDATE events SET project = ?1 WHERE id = ?2")?;
+    for (id, metadata) in pending {
+        if let Some(project) = project_from_metadata(&metadata) {
+            update.execute(params![project, id])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// V10: Mark exchanges skipped as near-duplicates of an indexed exchange
+fn migrate_v10(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "exchanges", "duplicate_of", "TEXT")?;
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_exchanges_duplicate_of ON exchanges(duplicate_of);",
+    )?;
+
+    Ok(())
+}
+
+/// V11: Index operation and command category for timeline filters
+fn migrate_v11(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_events_operation ON events(operation);
+         CREATE INDEX IF NOT EXISTS idx_events_command_category
+             ON events(json_extract(metadata, '$.command_category'));",
+    )?;
+
+    Ok(())
+}
+
+/// V12: Before/after content of edits (`capture.store_edit_content`)
+fn migrate_v12(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "events", "old_content", "TEXT")?;
+    add_column_if_missing(conn, "events", "new_content", "TEXT")?;
+
+    Ok(())
+}
+
+/// V13: Index event file paths for full-text search
+///
+/// Deletions carry little besides their path, so without it they could not
+/// be found by keyword. FTS5 cannot add a column, so `events_fts` is
+/// recreated and rebuilt from `events`.
+fn migrate_v13(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DROP TRIGGER IF EXISTS events_fts_insert;
+        DROP TRIGGER IF EXISTS events_fts_update;
+        DROP TRIGGER IF EXISTS events_fts_delete;
+        DROP TABLE IF EXISTS events_fts;
+
+        CREATE VIRTUAL TABLE events_fts USING fts5(
+            tool_name,
+            operation,
+            diff_summary,
+            raw_input,
+            file_path,
+            content=events,
+            content_rowid=id
+        );
+
+        CREATE TRIGGER events_fts_insert AFTER INSERT ON events BEGIN
+            INSERT INTO events_fts(rowid, tool_name, operation, diff_summary, raw_input, file_path)
+            VALUES (new.id, new.tool_name, new.operation, new.diff_summary, new.raw_input,
+                    new.file_path);
+        END;
+
+        CREATE TRIGGER events_fts_update
+        AFTER UPDATE OF tool_name, operation, diff_summary, raw_input, file_path ON events BEGIN
+            DELETE FROM events_fts WHERE rowid = old.id;
+            INSERT INTO events_fts(rowid, tool_name, operation, diff_summary, raw_input, file_path)
+            VALUES (new.id, new.tool_name, new.operation, new.diff_summary, new.raw_input,
+                    new.file_path);
+        END;
+
+        CREATE TRIGGER events_fts_delete AFTER DELETE ON events BEGIN
+            DELETE FROM events_fts WHERE rowid = old.id;
+        END;
+
+        INSERT INTO events_fts(events_fts) VALUES ('rebuild');",
+    )?;
+
+    Ok(())
+}
+
+/// V14: Index the original hash of imported events so import can skip them
+fn migrate_v14(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_events_imported_hash
+             ON events(json_extract(metadata, '$.imported_hash'));",
+    )?;
+
+    Ok(())
+}
+
+/// Derive a project name from a working directory.
+///
+/// Uses the same encoding Claude Code applies to its per-project archive
+/// directories (every non-alphanumeric character becomes `-`), so event
+/// projects line up with the `project` of indexed exchanges.
+///
+/// # Arguments
+/// - `cwd`: Absolute working directory.
+///
+/// # Returns
+/// Encoded project name (e.g., "/Users/me/app" -> "-Users-me-app").
+pub fn project_name_from_cwd(cwd: &str) -> String {
+    cwd.trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Derive a project name from event metadata JSON containing a `cwd` key.
+///
+/// # Arguments
+/// - `metadata`: Event metadata JSON string.
+///
+/// # Returns
+/// Project name, or `None` if the metadata has no usable `cwd`.
+pub fn project_from_metadata(metadata: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(metadata).ok()?;
+    let cwd = value.get("cwd")?.as_str()?;
+    if cwd.is_empty() {
+        return None;
+    }
+    Some(project_name_from_cwd(cwd))
+}
+
+/// Indexed columns of `events_fts`, in declaration order.
+const EVENTS_FTS_COLUMNS: &[&str] = &[
+    "tool_name",
+    "operation",
+    "diff_summary",
+    "raw_input",
+    "file_path",
+];
+
+/// Indexed columns of `exchanges_fts`, in declaration order.
+const EXCHANGES_FTS_COLUMNS: &[&str] = &["user_message", "assistant_message", "summary"];
+
+/// Snippet options for FTS queries, mapped onto SQLite's `snippet()` arguments.
+///
+/// # Fields
+/// - `snippet_tokens`: Maximum tokens in the snippet (SQLite caps this at 64).
+/// - `open_tag`: Text inserted before each matched term.
+/// - `close_tag`: Text inserted after each matched term.
+/// - `column`: Column to take the snippet from, or `None` for the table default.
+///
+/// Empty tags disable highlighting, so the snippet needs no further cleaning.
+#[derive(Debug, Clone, Copy)]
+pub struct FtsOptions<'a> {
+    pub snippet_tokens: usize,
+    pub open_tag: &'a str,
+    pub close_tag: &'a str,
+    pub column: Option<&'a str>,
+}
+
+impl FtsOptions<'static> {
+    /// Default options for event search: 32-token `diff_summary` snippets with `<b>` highlights.
+    pub fn events() -> Self {
+        Self {
+            snippet_tokens: 32,
+            open_tag: "<b>",
+            close_tag: "</b>",
+            column: None,
+        }
+    }
+
+    /// Default options for exchange search: 64-token `user_message` snippets with `<b>` highlights.
+    pub fn exchanges() -> Self {
+        Self {
+            snippet_tokens: 64,
+            open_tag: "<b>",
+            close_tag: "</b>",
+            column: None,
+        }
+    }
+}
+
+impl FtsOptions<'_> {
+    /// Same options with highlighting disabled.
+    pub fn plain(self) -> Self {
+        Self {
+            open_tag: "",
+            close_tag: "",
+            ..self
+        }
+    }
+
+    /// Resolve the snippet column to its index in `columns`.
+    fn column_index(&self, columns: &[&str], default: &str) -> Result<i64> {
+        let name = self.column.unwrap_or(default);
+        columns
+            .iter()
+            .position(|c| *c == name)
+            .map(|i| i as i64)
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Unknown FTS column '{}'. Use: {}",
+                    name,
+                    columns.join(", ")
+                ))
+            })
+    }
+}
+
+/// BM25 column weights for exchange search.
+///
+/// # Fields
+/// - `user_message`: Weight of the user's message.
+/// - `assistant_message`: Weight of the assistant's reply.
+/// - `summary`: Weight of the exchange summary.
+///
+/// The defaults favour the user's message, so a question that mentions a
+/// term outranks assistant boilerplate that merely repeats it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExchangeFtsWeights {
+    pub user_message: f64,
+    pub assistant_message: f64,
+    pub summary: f64,
+}
+
+impl Default for ExchangeFtsWeights {
+    fn default() -> Self {
+        Self {
+            user_message: 2.0,
+            assistant_message: 1.0,
+            summary: 1.0,
+        }
+    }
+}
+
+/// Row filters applied in SQL to search hits.
+///
+/// # Fields
+/// - `since`: Earliest instant to include (inclusive).
+/// - `until`: Latest instant to include (inclusive).
+/// - `tool`: Tool name to match, case-insensitive. Exchanges have no tool,
+///   so a tool filter excludes every exchange.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchFilter<'a> {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub tool: Option<&'a str>,
+}
+
+impl SearchFilter<'_> {
+    /// Whether any filter is set.
+    pub fn is_active(&self) -> bool {
+        self.since.is_some() || self.until.is_some() || self.tool.is_some()
+    }
+
+    /// The time window as event timestamps, which are local time.
+    fn event_window(&self) -> [Option<String>; 2] {
+        [self.since, self.until].map(|bound| bound.map(time::format_local))
+    }
+
+    /// The time window as exchange timestamps, which are UTC.
+    fn exchange_window(&self) -> [Option<String>; 2] {
+        [self.since, self.until].map(|bound| bound.map(time::format_utc))
+    }
+}
+
+/// Timestamp and context of a search hit, looked up by ID.
+///
+/// # Fields
+/// - `timestamp`: ISO timestamp string.
+/// - `context`: File path (events) or project name (exchanges).
+#[derive(Debug, Clone)]
+pub struct SearchHitInfo {
+    pub timestamp: String,
+    pub context: Option<String>,
+}
+
+/// Look up events by ID, keeping those that pass a filter.
+///
+/// Vector search only returns IDs; this fills in what filtering and
+/// display need.
+///
+/// # Arguments
+/// - `conn`: Open SQLite connection for the database.
+/// - `ids`: Event IDs; IDs that are not integers are ignored.
+/// - `filter`: Time window and tool filter.
+///
+/// # Returns
+/// Map from event ID to its timestamp and file path, for matching events.
+///
+/// # Errors
+/// Returns `Error` if query preparation or execution fails.
+pub fn lookup_event_hits(
+    conn: &Connection,
+    ids: &[&str],
+    filter: &SearchFilter,
+) -> Result<HashMap<String, SearchHitInfo>> {
+    let ids: Vec<i64> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let sql = format!(
+        "SELECT id, timestamp, file_path FROM events
+         WHERE id IN ({})
+           AND (?1 IS NULL OR timestamp >= ?1)
+           AND (?2 IS NULL OR timestamp <= ?2)
+           AND (?3 IS NULL OR tool_name = ?3 COLLATE NOCASE)",
+        id_placeholders(ids.len(), 4)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let [since, until] = filter.event_window();
+    let mut params = optional_text([since.as_deref(), until.as_deref(), filter.tool]);
+    params.extend(ids.into_iter().map(Value::Integer));
+
+    let hits = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, i64>(0)?.to_string(),
+                SearchHitInfo {
+                    timestamp: row.get(1)?,
+                    context: row.get(2)?,
+                },
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(hits)
+}
+
+/// Look up exchanges by ID, keeping those that pass a filter.
+///
+/// # Arguments
+/// - `conn`: Open SQLite connection for the database.
+/// - `ids`: Exchange IDs.
+/// - `filter`: Time window; a tool filter matches no exchanges.
+///
+/// # Returns
+/// Map from exchange ID to its timestamp and project, for matching exchanges.
+///
+/// # Errors
+/// Returns `Error` if query preparation or execution fails.
+pub fn lookup_exchange_hits(
+    conn: &Connection,
+    ids: &[&str],
+    filter: &SearchFilter,
+) -> Result<HashMap<String, SearchHitInfo>> {
+    if ids.is_empty() || filter.tool.is_some() {
+        return Ok(HashMap::new());
+    }
+
+    let sql = format!(
+        "SELECT id, timestamp, project FROM exchanges
+         WHERE id IN ({})
+           AND (?1 IS NULL OR timestamp >= ?1)
+           AND (?2 IS NULL OR timestamp <= ?2)",
+        id_placeholders(ids.len(), 3)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let [since, until] = filter.exchange_window();
+    let mut params = optional_text([since.as_deref(), until.as_deref()]);
+    params.extend(ids.iter().map(|id| Value::Text(id.to_string())));
+
+    let hits = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                SearchHitInfo {
+                    timestamp: row.get(1)?,
+                    context: row.get(2)?,
+                },
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(hits)
+}
+
+/// SQL values for optional text parameters, `NULL` where unset.
+fn optional_text<const N: usize>(values: [Option<&str>; N]) -> Vec<Value> {
+    values
+        .iter()
+        .map(|value| value.map_or(Value::Null, |text| Value::Text(text.to_string())))
+        .collect()
+}
+
+/// Numbered placeholders `?first, ?first+1, ...` for an `IN` list.
+fn id_placeholders(count: usize, first: usize) -> String {
+    (first..first + count)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Full-text search for events.
 ///
 /// # Arguments
 /// - `conn`: Open SQLite connection for the database.
 /// - `query`: FTS5 query string.
 /// - `limit`: Maximum number of results to return.
+/// - `options`: Snippet length, highlight tags and column.
+/// - `filter`: Time window and tool filter.
 ///
 /// # Returns
 /// Vector of search results ordered by BM25 score.
 ///
 /// # Errors
-/// Returns `Error` if query preparation or execution fails.
+/// Returns `Error` if the snippet column is unknown, or if query preparation
+/// or execution fails.
 pub fn fts_search_events(
     conn: &Connection,
     query: &str,
     limit: usize,
+    options: &FtsOptions,
+    filter: &SearchFilter,
 ) -> Result<Vec<FtsSearchResult>> {
+    let column = options.column_index(EVENTS_FTS_COLUMNS, "diff_summary")?;
+    let [since, until] = filter.event_window();
     let mut stmt = conn.prepare(
         "SELECT e.id, e.timestamp, e.file_path, e.tool_name,
-                snippet(events_fts, 2, '<b>', '</b>', '...', 32) as snippet,
+                snippet(events_fts, ?3, ?4, ?5, '...', ?6) as snippet,
                 bm25(events_fts) as score
          FROM events_fts
          JOIN events e ON events_fts.rowid = e.id
          WHERE events_fts MATCH ?1
+           AND (?7 IS NULL OR e.timestamp >= ?7)
+           AND (?8 IS NULL OR e.timestamp <= ?8)
+           AND (?9 IS NULL OR e.tool_name = ?9 COLLATE NOCASE)
          ORDER BY bm25(events_fts)
          LIMIT ?2",
     )?;
 
     let results = stmt
-        .query_map([query, &limit.to_string()], |row| {
-            Ok(FtsSearchResult {
-                id: row.get::<_, i64>(0)?.to_string(),
-                timestamp: row.get(1)?,
-                context: row.get::<_, Option<String>>(2)?,
-                source_type: "event".to_string(),
-                snippet: row.get(4)?,
-                score: row.get(5)?,
-            })
-        })?
+        .query_map(
+            params![
+                query,
+                limit as i64,
+                column,
+                options.open_tag,
+                options.close_tag,
+                options.snippet_tokens as i64,
+                since,
+                until,
+                filter.tool
+            ],
+            |row| {
+                Ok(FtsSearchResult {
+                    id: row.get::<_, i64>(0)?.to_string(),
+                    timestamp: row.get(1)?,
+                    context: row.get::<_, Option<String>>(2)?,
+                    source_type: "event".to_string(),
+                    // NULL when the match is outside the snippet column,
+                    // e.g. a deletion matched by its file path
+                    snippet: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    score: row.get(5)?,
+                })
+            },
+        )?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -272,39 +829,69 @@ pub fn fts_search_events(
 /// - `conn`: Open SQLite connection for the database.
 /// - `query`: FTS5 query string.
 /// - `limit`: Maximum number of results to return.
+/// - `options`: Snippet length, highlight tags and column.
+/// - `weights`: Per-column BM25 weights.
+/// - `filter`: Time window; a tool filter matches no exchanges.
 ///
 /// # Returns
-/// Vector of search results ordered by BM25 score.
+/// Vector of search results ordered by weighted BM25 score.
 ///
 /// # Errors
-/// Returns `Error` if query preparation or execution fails.
+/// Returns `Error` if the snippet column is unknown, or if query preparation
+/// or execution fails.
 pub fn fts_search_exchanges(
     conn: &Connection,
     query: &str,
     limit: usize,
+    options: &FtsOptions,
+    weights: &ExchangeFtsWeights,
+    filter: &SearchFilter,
 ) -> Result<Vec<FtsSearchResult>> {
+    let column = options.column_index(EXCHANGES_FTS_COLUMNS, "user_message")?;
+    if filter.tool.is_some() {
+        return Ok(Vec::new());
+    }
+    let [since, until] = filter.exchange_window();
+
     let mut stmt = conn.prepare(
         "SELECT e.id, e.timestamp, e.project,
-                snippet(exchanges_fts, 0, '<b>', '</b>', '...', 64) as snippet,
-                bm25(exchanges_fts) as score
+                snippet(exchanges_fts, ?3, ?4, ?5, '...', ?6) as snippet,
+                bm25(exchanges_fts, ?7, ?8, ?9) as score
          FROM exchanges_fts
          JOIN exchanges e ON exchanges_fts.rowid = e.rowid
          WHERE exchanges_fts MATCH ?1
-         ORDER BY bm25(exchanges_fts)
+           AND (?10 IS NULL OR e.timestamp >= ?10)
+           AND (?11 IS NULL OR e.timestamp <= ?11)
+         ORDER BY score
          LIMIT ?2",
     )?;
 
     let results = stmt
-        .query_map([query, &limit.to_string()], |row| {
-            Ok(FtsSearchResult {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                context: row.get::<_, Option<String>>(2)?,
-                source_type: "exchange".to_string(),
-                snippet: row.get(3)?,
-                score: row.get(4)?,
-            })
-        })?
+        .query_map(
+            params![
+                query,
+                limit as i64,
+                column,
+                options.open_tag,
+                options.close_tag,
+                options.snippet_tokens as i64,
+                weights.user_message,
+                weights.assistant_message,
+                weights.summary,
+                since,
+                until
+            ],
+            |row| {
+                Ok(FtsSearchResult {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    context: row.get::<_, Option<String>>(2)?,
+                    source_type: "exchange".to_string(),
+                    snippet: row.get(3)?,
+                    score: row.get(4)?,
+                })
+            },
+        )?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -333,6 +920,7 @@ pub struct FtsSearchResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_schema_init() {
@@ -352,4 +940,260 @@ mod tests {
         assert!(tables.contains(&"exchanges".to_string()));
         assert!(tables.contains(&"schema_version".to_string()));
     }
+
+    fn column_names(conn: &Connection, table: &str) -> Vec<String> {
+        conn.prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap()
+            .query_map([], |row| row.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, SCHEMA_VERSION as i64);
+    }
+
+    #[test]
+    fn test_migrate_upgrades_pre_hash_chain_database() {
+        // Shape written by older builds and the hook's fallback writer:
+        // project_path already present, no hash columns, no version table.
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE events (
+                id INTEGER PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                timestamp_display TEXT,
+                session_id TEXT,
+                tool_name TEXT NOT NULL,
+                file_path TEXT,
+                operation TEXT,
+                diff_summary TEXT,
+                raw_input TEXT,
+                ai_summary TEXT,
+                git_commit_sha TEXT,
+                parent_event_id INTEGER,
+                metadata TEXT,
+                embedding BLOB,
+                project_path TEXT
+            );
+            INSERT INTO events (timestamp, tool_name, file_path)
+            VALUES ('2026-01-01T00:00:00Z', 'Write', 'src/lib.rs');",
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+
+        let columns = column_names(&conn, "events");
+        for column in [
+            "prev_hash",
+            "event_hash",
+            "content_hash",
+            "context_hash",
+            "field_hashes",
+            "semantic_sig",
+        ] {
+            assert!(columns.contains(&column.to_string()), "missing {}", column);
+        }
+        assert!(column_names(&conn, "chain_checkpoints").contains(&"last_event_id".to_string()));
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_fts_custom_snippet_delimiters() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO events (timestamp, tool_name, file_path, operation, diff_summary)
+             VALUES ('2026-01-01T00:00:00Z', 'Edit', 'src/auth.rs', 'modify', 'refresh token rotation')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO exchanges (id, timestamp, user_message, assistant_message)
+             VALUES ('ex-1', '2026-01-01T00:00:00Z', 'how does token refresh work', 'It rotates.')",
+            [],
+        )
+        .unwrap();
+
+        let default = fts_search_events(
+            &conn,
+            "token",
+            10,
+            &FtsOptions::events(),
+            &SearchFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(default[0].snippet, "refresh <b>token</b> rotation");
+
+        let custom = FtsOptions {
+            open_tag: "[[",
+            close_tag: "]]",
+            ..FtsOptions::events()
+        };
+        let events =
+            fts_search_events(&conn, "token", 10, &custom, &SearchFilter::default()).unwrap();
+        assert_eq!(events[0].snippet, "refresh [[token]] rotation");
+
+        let plain = FtsOptions::exchanges().plain();
+        let weights = ExchangeFtsWeights::default();
+        let exchanges = fts_search_exchanges(
+            &conn,
+            "token",
+            10,
+            &plain,
+            &weights,
+            &SearchFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(exchanges[0].snippet, "how does token refresh work");
+
+        let bad_column = FtsOptions {
+            column: Some("nope"),
+            ..FtsOptions::exchanges()
+        };
+        assert!(fts_search_exchanges(
+            &conn,
+            "token",
+            10,
+            &bad_column,
+            &weights,
+            &SearchFilter::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_exchange_search_ranks_user_message_hits_first() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        // Assistant-side hit inserted first so insertion order can't explain the ranking.
+        conn.execute(
+            "INSERT INTO exchanges (id, timestamp, user_message, assistant_message)
+             VALUES ('assistant-hit', '2026-01-01T00:00:00Z',
+                     'please review the parser', 'I checked the migration for you')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO exchanges (id, timestamp, user_message, assistant_message)
+             VALUES ('user-hit', '2026-01-01T00:01:00Z',
+                     'please review the migration', 'I checked the parser for you')",
+            [],
+        )
+        .unwrap();
+
+        let results = fts_search_exchanges(
+            &conn,
+            "migration",
+            10,
+            &FtsOptions::exchanges(),
+            &ExchangeFtsWeights::default(),
+            &SearchFilter::default(),
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["user-hit", "assistant-hit"]);
+        assert!(results[0].score < results[1].score);
+    }
+
+    #[test]
+    fn test_search_filters_apply_in_sql() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO events (id, timestamp, tool_name, file_path, diff_summary)
+             VALUES (1, '2026-01-01T00:00:00', 'Write', 'old.rs', 'window_token'),
+                    (2, '2026-03-01T00:00:00', 'Edit', 'new.rs', 'window_token'),
+                    (3, '2026-06-01T00:00:00', 'Edit', 'later.rs', 'window_token');
+             INSERT INTO exchanges (id, timestamp, user_message, assistant_message)
+             VALUES ('ex-old', '2026-01-01T00:00:00Z', 'window_token', 'ok'),
+                    ('ex-new', '2026-03-01T00:00:00Z', 'window_token', 'ok');",
+        )
+        .unwrap();
+
+        let window = SearchFilter {
+            since: Some(Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap()),
+            until: Some(Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap()),
+            tool: None,
+        };
+        let events =
+            fts_search_events(&conn, "window_token", 10, &FtsOptions::events(), &window).unwrap();
+        let ids: Vec<&str> = events.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2"]);
+
+        let exchanges = fts_search_exchanges(
+            &conn,
+            "window_token",
+            10,
+            &FtsOptions::exchanges(),
+            &ExchangeFtsWeights::default(),
+            &window,
+        )
+        .unwrap();
+        assert_eq!(exchanges.len(), 1);
+        assert_eq!(exchanges[0].id, "ex-new");
+
+        let edits = SearchFilter {
+            tool: Some("edit"),
+            ..SearchFilter::default()
+        };
+        let hits = lookup_event_hits(&conn, &["1", "2", "3"], &edits).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits["3"].context.as_deref(), Some("later.rs"));
+        assert!(lookup_exchange_hits(&conn, &["ex-old", "ex-new"], &edits)
+            .unwrap()
+            .is_empty());
+
+        let hits = lookup_exchange_hits(&conn, &["ex-old", "ex-new"], &window).unwrap();
+        assert_eq!(hits["ex-new"].timestamp, "2026-03-01T00:00:00Z");
+        assert!(!hits.contains_key("ex-old"));
+    }
+
+    #[test]
+    fn test_migrate_backfills_project_from_cwd() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE events (
+                id INTEGER PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                timestamp_display TEXT,
+                session_id TEXT,
+                tool_name TEXT NOT NULL,
+                file_path TEXT,
+                operation TEXT,
+                diff_summary TEXT,
+                raw_input TEXT,
+                metadata TEXT
+            );
+            INSERT INTO events (timestamp, tool_name, metadata) VALUES
+                ('2026-01-01T00:00:00', 'Codex', '{\"cwd\":\"/Users/me/my.app\"}'),
+                ('2026-01-01T00:00:01', 'Write', '{\"git_branch\":\"main\"}');",
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+
+        let projects: Vec<Option<String>> = conn
+            .prepare("SELECT project FROM events ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(projects, vec![Some("-Users-me-my-app".to_string()), None]);
+    }
 }
@@ -0,0 +1,254 @@
+//! Mustache-style templates for rendered reports
+//!
+//! A small subset of mustache, enough for PR comments:
+//! - `{{name}}` and `{{a.b}}` insert a value, looked up from the innermost
+//!   section outwards; `{{.}}` is the current item
+//! - `{{#name}}...{{/name}}` repeats for each item of an array, or renders
+//!   once with the value in scope when it is truthy
+//! - `{{^name}}...{{/name}}` renders when the value is missing or falsy
+//!
+//! `null`, `false`, `0`, `""` and `[]` are falsy. Values are inserted as-is
+//! (no HTML escaping), and missing values render empty. A line holding
+//! nothing but a section tag is dropped from the output, so sections can
+//! sit on their own lines without leaving blank ones behind.
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// Errors from parsing a template.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{{` with no matching `}}`
+    #[error("Unclosed tag starting at byte {0}")]
+    UnclosedTag(usize),
+
+    /// A `{{#name}}` or `{{^name}}` with no matching `{{/name}}`
+    #[error("Section '{0}' is never closed")]
+    UnclosedSection(String),
+
+    /// A `{{/name}}` that doesn't close the innermost open section
+    #[error("Unexpected '{{{{/{0}}}}}'")]
+    UnexpectedClose(String),
+}
+
+/// Parsed template node.
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Value(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// Render `template` against `context`.
+///
+/// # Arguments
+/// - `template`: Template source
+/// - `context`: Root value for lookups, usually an object
+///
+/// # Returns
+/// The rendered text
+///
+/// # Errors
+/// Returns `TemplateError` if a tag or section is left open or a section
+/// is closed out of order.
+pub fn render_template(template: &str, context: &Value) -> Result<String, TemplateError> {
+    let nodes = parse(&strip_standalone_tags(template))?;
+    let mut out = String::with_capacity(template.len());
+    render_nodes(&nodes, &mut vec![context], &mut out);
+    Ok(out)
+}
+
+/// Reduce lines holding only a section tag to the bare tag.
+fn strip_standalone_tags(template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    for line in template.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let standalone = ["{{#", "{{^", "{{/"].iter().any(|p| trimmed.starts_with(p))
+            && trimmed.ends_with("}}")
+            && trimmed.matches("}}").count() == 1;
+        out.push_str(if standalone { trimmed } else { line });
+    }
+    out
+}
+
+fn parse(template: &str) -> Result<Vec<Node>, TemplateError> {
+    // Open sections, innermost last, with the nodes collected so far
+    let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let offset = template.len() - rest.len() + start;
+        let end = rest[start..]
+            .find("}}")
+            .ok_or(TemplateError::UnclosedTag(offset))?;
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+
+        if let Some(name) = tag.strip_prefix('#') {
+            stack.push((name.trim().to_string(), false, std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('^') {
+            stack.push((name.trim().to_string(), true, std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            match stack.pop() {
+                Some((open, inverted, parent)) if open == name => {
+                    let children = std::mem::replace(&mut nodes, parent);
+                    nodes.push(Node::Section {
+                        name: open,
+                        inverted,
+                        children,
+                    });
+                }
+                _ => return Err(TemplateError::UnexpectedClose(name.to_string())),
+            }
+        } else {
+            nodes.push(Node::Value(tag.to_string()));
+        }
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+
+    match stack.pop() {
+        Some((name, _, _)) => Err(TemplateError::UnclosedSection(name)),
+        None => Ok(nodes),
+    }
+}
+
+fn render_nodes(nodes: &[Node], scopes: &mut Vec<&Value>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(name) => {
+                if let Some(value) = lookup(scopes, name) {
+                    push_value(value, out);
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let value = lookup(scopes, name);
+                let truthy = value.is_some_and(is_truthy);
+                if *inverted {
+                    if !truthy {
+                        render_nodes(children, scopes, out);
+                    }
+                    continue;
+                }
+                match value {
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            scopes.push(item);
+                            render_nodes(children, scopes, out);
+                            scopes.pop();
+                        }
+                    }
+                    Some(value) if truthy => {
+                        scopes.push(value);
+                        render_nodes(children, scopes, out);
+                        scopes.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Resolve a dotted name against the scope stack, innermost first.
+///
+/// The first segment picks the innermost scope that has it; later
+/// segments must resolve from there.
+fn lookup<'a>(scopes: &[&'a Value], name: &str) -> Option<&'a Value> {
+    if name == "." {
+        return scopes.last().copied();
+    }
+    let mut segments = name.split('.');
+    let first = segments.next()?;
+    let mut value = scopes.iter().rev().find_map(|scope| scope.get(first))?;
+    for segment in segments {
+        value = value.get(segment)?;
+    }
+    Some(value)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn push_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => {}
+        Value::String(s) => out.push_str(s),
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_values_and_sections() {
+        let context = json!({
+            "title": "Fix auth",
+            "summary": { "files": 2 },
+            "empty": [],
+            "commits": [
+                { "sha": "abc", "events": ["Write", "Edit"] },
+                { "sha": "def", "events": [] }
+            ]
+        });
+        let template = "# {{title}} ({{summary.files}} files){{missing}}\n\
+            {{#commits}}\n\
+            - {{sha}} in {{title}}:{{#events}} {{.}}{{/events}}{{^events}} none{{/events}}\n\
+            {{/commits}}\n\
+            {{^empty}}\n\
+            nothing else\n\
+            {{/empty}}\n";
+
+        let out = render_template(template, &context).unwrap();
+        assert_eq!(
+            out,
+            "# Fix auth (2 files)\n\
+             - abc in Fix auth: Write Edit\n\
+             - def in Fix auth: none\n\
+             nothing else\n"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let context = json!({});
+        assert_eq!(
+            render_template("{{#a}}x", &context),
+            Err(TemplateError::UnclosedSection("a".to_string()))
+        );
+        assert_eq!(
+            render_template("{{#a}}{{/b}}", &context),
+            Err(TemplateError::UnexpectedClose("b".to_string()))
+        );
+        assert_eq!(
+            render_template("ok {{name", &context),
+            Err(TemplateError::UnclosedTag(3))
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Time filter parsing shared by the CLI and daemon
+//!
+//! Every `--since`/`since` filter goes through [`parse_since`], so timeline,
+//! search, stats, and export accept exactly the same inputs:
+//!
+//! - Relative offsets: `30m`, `2h`, `7d`, `1w`
+//! - Spelled out: `3 days ago`, `1 hour ago`, `2 weeks ago`
+//! - `today` and `yesterday` (local midnight)
+//! - ISO dates (`2024-01-15`, local midnight)
+//! - ISO datetimes, with an offset (`2024-01-15T10:30:00Z`) or without one
+//!   (`2024-01-15T10:30:00`, `2024-01-15 10:30`, taken as local time)
+
+use chrono::{
+    DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+
+/// Datetime layouts accepted without an offset.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parse a time filter into the instant it refers to.
+///
+/// # Arguments
+/// - `input`: Filter text (case-insensitive, surrounding whitespace ignored).
+///
+/// # Returns
+/// The start of the window, or `None` if the input is not recognized.
+pub fn parse_since(input: &str) -> Option<DateTime<Utc>> {
+    parse_since_at(input, Local::now())
+}
+
+/// Parse a time filter relative to a fixed "now".
+///
+/// Same as [`parse_since`], with the clock passed in so results are
+/// reproducible.
+///
+/// # Arguments
+/// - `input`: Filter text.
+/// - `now`: Current local time.
+///
+/// # Returns
+/// The start of the window, or `None` if the input is not recognized.
+pub fn parse_since_at(input: &str, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    let lower = input.trim().to_lowercase();
+    if lower.is_empty() {
+        return None;
+    }
+
+    match lower.as_str() {
+        "today" => return local_midnight(now.date_naive()),
+        "yesterday" => return local_midnight(now.date_naive() - Duration::days(1)),
+        _ => {}
+    }
+
+    if let Some(offset) = parse_offset(&lower) {
+        return now.with_timezone(&Utc).checked_sub_signed(offset);
+    }
+
+    // Date formats use upper-case `T` and `Z`
+    let upper = lower.to_uppercase();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(&upper) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Some(naive) = NAIVE_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&upper, format).ok())
+    {
+        return local_instant(naive);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&upper, "%Y-%m-%d") {
+        return local_midnight(date);
+    }
+
+    None
+}
+
+/// Format an instant the way event timestamps are stored: local time
+/// without an offset, so it compares correctly against the `timestamp`
+/// column as text.
+///
+/// # Arguments
+/// - `instant`: Instant to format.
+///
+/// # Returns
+/// Timestamp like `2024-01-15T10:30:00`.
+pub fn format_local(instant: DateTime<Utc>) -> String {
+    format_wall_clock(instant, &Local)
+}
+
+/// Format an instant as wall-clock time in a time zone, without an offset.
+///
+/// # Arguments
+/// - `instant`: Instant to format.
+/// - `tz`: Time zone whose wall clock to read.
+///
+/// # Returns
+/// Timestamp like `2024-01-15T10:30:00`.
+pub fn format_wall_clock<Tz: TimeZone>(instant: DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    instant
+        .with_timezone(tz)
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// Format an instant the way exchange timestamps are stored: UTC with a
+/// `Z` suffix, as written in conversation transcripts.
+///
+/// # Arguments
+/// - `instant`: Instant to format.
+///
+/// # Returns
+/// Timestamp like `2024-01-15T10:30:00Z`.
+pub fn format_utc(instant: DateTime<Utc>) -> String {
+    instant.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Parse `30m`, `2h`, `7d`, `1w`, or `N <unit>(s) ago`.
+fn parse_offset(input: &str) -> Option<Duration> {
+    let (amount, unit) = match input.strip_suffix(" ago") {
+        Some(spelled) => spelled.split_once(' ')?,
+        None => input.split_at(input.find(|c: char| !c.is_ascii_digit())?),
+    };
+    let amount: i64 = amount.trim().parse().ok()?;
+
+    match unit.trim() {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+        "d" | "day" | "days" => Duration::try_days(amount),
+        "w" | "week" | "weeks" => Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Utc>> {
+    local_instant(date.and_time(NaiveTime::MIN))
+}
+
+/// Resolve a local wall-clock time, taking the earlier instant when a DST
+/// change makes it ambiguous.
+fn local_instant(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match Local.from_local_datetime(&naive) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => Some(t.with_timezone(&Utc)),
+        // Skipped by a DST jump; the UTC reading is within the hour
+        LocalResult::None => Some(Utc.from_utc_datetime(&naive)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 10, 15, 30, 0).unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_relative_offsets() {
+        let now_utc = now().with_timezone(&Utc);
+        let cases = [
+            ("30m", Duration::minutes(30)),
+            ("2h", Duration::hours(2)),
+            ("7d", Duration::days(7)),
+            ("1w", Duration::weeks(1)),
+            ("0d", Duration::zero()),
+            ("  2H ", Duration::hours(2)),
+            ("3 days ago", Duration::days(3)),
+            ("1 day ago", Duration::days(1)),
+            ("45 minutes ago", Duration::minutes(45)),
+            ("1 hour ago", Duration::hours(1)),
+            ("2 weeks ago", Duration::weeks(2)),
+            ("5 Days Ago", Duration::days(5)),
+        ];
+        for (input, offset) in cases {
+            assert_eq!(
+                parse_since_at(input, now()),
+                Some(now_utc - offset),
+                "input {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_named_days() {
+        assert_eq!(
+            parse_since_at("today", now()),
+            Some(local(2026, 3, 10, 0, 0))
+        );
+        assert_eq!(
+            parse_since_at("Yesterday", now()),
+            Some(local(2026, 3, 9, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_absolute_dates_and_datetimes() {
+        let cases = [
+            ("2024-01-15", local(2024, 1, 15, 0, 0)),
+            ("2024-01-15T10:30:00", local(2024, 1, 15, 10, 30)),
+            (
+                "2024-01-15T10:30:00.250",
+                local(2024, 1, 15, 10, 30) + Duration::milliseconds(250),
+            ),
+            ("2024-01-15T10:30", local(2024, 1, 15, 10, 30)),
+            ("2024-01-15 10:30:00", local(2024, 1, 15, 10, 30)),
+            ("2024-01-15 10:30", local(2024, 1, 15, 10, 30)),
+            (
+                "2024-01-15T10:30:00Z",
+                Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap(),
+            ),
+            (
+                "2024-01-15t10:30:00z",
+                Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap(),
+            ),
+            (
+                "2024-01-15T10:30:00+02:00",
+                Utc.with_ymd_and_hms(2024, 1, 15, 8, 30, 0).unwrap(),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                parse_since_at(input, now()),
+                Some(expected),
+                "input {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_unrecognized_input() {
+        for input in [
+            "",
+            "   ",
+            "invalid",
+            "h",
+            "7",
+            "7y",
+            "-",
+            "d7",
+            "ago",
+            "x days ago",
+            "2024-13-01",
+            "2024-01-15T25:00:00",
+            "99999999999999999999d",
+            "9999999999999999w",
+            "99999999999d",
+        ] {
+            assert_eq!(parse_since_at(input, now()), None, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_format_local_round_trips() {
+        let instant = local(2024, 1, 15, 10, 30);
+        assert_eq!(format_local(instant), "2024-01-15T10:30:00");
+        assert_eq!(parse_since(&format_local(instant)), Some(instant));
+    }
+
+    #[test]
+    fn test_window_formats_follow_each_table_outside_utc() {
+        // Events store local wall-clock time, exchanges store UTC
+        let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let now = ist
+            .with_ymd_and_hms(2026, 3, 10, 15, 30, 0)
+            .unwrap()
+            .with_timezone(&Local);
+
+        let since = parse_since_at("1h", now).unwrap();
+        assert_eq!(format_wall_clock(since, &ist), "2026-03-10T14:30:00");
+        assert_eq!(format_utc(since), "2026-03-10T09:00:00Z");
+
+        // An event written at 10:00 IST is outside the last hour; the UTC
+        // string would have let it through
+        let stored_event = "2026-03-10T10:00:00";
+        assert!(stored_event < format_wall_clock(since, &ist).as_str());
+        assert!(stored_event > format_utc(since).as_str());
+    }
+}
@@ -2,10 +2,19 @@
 //!
 //! These types are shared between the hook, daemon, and CLI.
 
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
 use serde::{Deserialize, Serialize};
 
+use crate::error::{DaemonError, UnknownVariant};
+use crate::export::ExportedEvent;
+use crate::hash_chain::ChainVerificationResult;
+use crate::vector::HnswConfig;
+
 /// Operations that can be performed on files
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Operation {
     Create,
@@ -15,6 +24,9 @@ pub enum Operation {
     Copy,
     Commit,
     Execute,
+    /// Anything else, including values written by older versions
+    #[serde(other)]
+    #[default]
     Unknown,
 }
 
@@ -35,6 +47,49 @@ impl Operation {
             Operation::Unknown => "unknown",
         }
     }
+
+    /// Parse a stored operation, keeping unrecognized values as `Unknown`.
+    ///
+    /// # Arguments
+    /// - `s`: Operation string as read from the database.
+    pub fn from_stored(s: &str) -> Self {
+        s.parse().unwrap_or(Operation::Unknown)
+    }
+}
+
+impl FromStr for Operation {
+    type Err = UnknownVariant;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "create" => Ok(Operation::Create),
+            "modify" => Ok(Operation::Modify),
+            "delete" => Ok(Operation::Delete),
+            "move" => Ok(Operation::Move),
+            "copy" => Ok(Operation::Copy),
+            "commit" => Ok(Operation::Commit),
+            "execute" => Ok(Operation::Execute),
+            "unknown" => Ok(Operation::Unknown),
+            _ => Err(UnknownVariant {
+                kind: "operation",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<&str> for Operation {
+    type Error = UnknownVariant;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromSql for Operation {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(Operation::from_stored)
+    }
 }
 
 /// Semantic command categories for Bash commands
@@ -47,6 +102,8 @@ pub enum CommandCategory {
     Deploy,
     FileOps,
     Package,
+    /// Anything else, including values written by older versions
+    #[serde(other)]
     Unknown,
 }
 
@@ -66,6 +123,70 @@ impl CommandCategory {
             CommandCategory::Unknown => "unknown",
         }
     }
+
+    /// Parse a stored category, keeping unrecognized values as `Unknown`.
+    ///
+    /// # Arguments
+    /// - `s`: Category string as read from event metadata.
+    pub fn from_stored(s: &str) -> Self {
+        s.parse().unwrap_or(CommandCategory::Unknown)
+    }
+}
+
+impl FromStr for CommandCategory {
+    type Err = UnknownVariant;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "git" => Ok(CommandCategory::Git),
+            "test" => Ok(CommandCategory::Test),
+            "build" => Ok(CommandCategory::Build),
+            "deploy" => Ok(CommandCategory::Deploy),
+            // Serde spells it without the underscore
+            "file_ops" | "fileops" => Ok(CommandCategory::FileOps),
+            "package" => Ok(CommandCategory::Package),
+            "unknown" => Ok(CommandCategory::Unknown),
+            _ => Err(UnknownVariant {
+                kind: "command category",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<&str> for CommandCategory {
+    type Error = UnknownVariant;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromSql for CommandCategory {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(CommandCategory::from_stored)
+    }
+}
+
+/// Whether a tool call succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+impl Outcome {
+    /// Return the lowercase string representation used for storage.
+    ///
+    /// # Returns
+    /// String slice for this outcome.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failure => "failure",
+        }
+    }
 }
 
 /// A captured code change event.
@@ -79,7 +200,13 @@ impl CommandCategory {
 /// - `metadata`: Optional JSON metadata (branch, category, etc.).
 /// - `git_commit_sha`: Optional commit SHA.
 /// - `command_category`: Optional semantic category for bash commands.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// - `outcome`: Whether the tool call succeeded, if known.
+/// - `error_excerpt`: Short excerpt of the tool error for failed calls.
+/// - `old_content`: Text an edit replaced, when edit content is stored.
+/// - `new_content`: Text an edit inserted, when edit content is stored.
+/// - `raw_input_truncated`: `raw_input` was cut short; the hook marks the
+///   cut with a trailing `...`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CaptureEvent {
     pub tool_name: String,
     pub file_path: Option<String>,
@@ -89,6 +216,16 @@ pub struct CaptureEvent {
     pub metadata: Option<String>,
     pub git_commit_sha: Option<String>,
     pub command_category: Option<CommandCategory>,
+    #[serde(default)]
+    pub outcome: Option<Outcome>,
+    #[serde(default)]
+    pub error_excerpt: Option<String>,
+    #[serde(default)]
+    pub old_content: Option<String>,
+    #[serde(default)]
+    pub new_content: Option<String>,
+    #[serde(default)]
+    pub raw_input_truncated: bool,
 }
 
 /// A conversation exchange used for memory indexing.
@@ -135,6 +272,8 @@ pub struct Exchange {
 /// - `snippet`: Highlighted snippet for display.
 /// - `timestamp`: Timestamp for the matched item.
 /// - `project`: Optional project name for context.
+/// - `explain`: Which retrievers found the item and their scores (only
+///   when the search asked for it).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub id: String,
@@ -143,16 +282,228 @@ pub struct SearchResult {
     pub snippet: String,
     pub timestamp: String,
     pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<SearchExplanation>,
+}
+
+/// Where a page of search results ended, for requesting the next page.
+///
+/// Written as `<score>:<source>:<id>`, e.g. `0.0325:event:1234`.
+///
+/// # Fields
+/// - `score`: Score of the last result shown.
+/// - `source`: Source of the last result shown.
+/// - `id`: ID of the last result shown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    pub score: f32,
+    pub source: SearchSource,
+    pub id: String,
+}
+
+impl SearchCursor {
+    /// Cursor for the page after `result`.
+    pub fn after(result: &SearchResult) -> Self {
+        Self {
+            score: result.score,
+            source: result.source,
+            id: result.id.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self.source {
+            SearchSource::Event => "event",
+            SearchSource::Exchange => "exchange",
+        };
+        // `f32` displays the shortest text that parses back to the same value
+        write!(f, "{}:{}:{}", self.score, source, self.id)
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = UnknownVariant;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || UnknownVariant {
+            kind: "search cursor",
+            value: s.to_string(),
+        };
+        let mut parts = s.trim().splitn(3, ':');
+        let score = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let source = match parts.next() {
+            Some("event") => SearchSource::Event,
+            Some("exchange") => SearchSource::Exchange,
+            _ => return Err(invalid()),
+        };
+        let id = parts
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(invalid)?;
+        Ok(Self {
+            score,
+            source,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Retrieval provenance of a search result.
+///
+/// # Fields
+/// - `from_vector`: Found by the vector index.
+/// - `from_fts`: Found by full-text search.
+/// - `vector_sim`: Cosine similarity to the query, if found by vector.
+/// - `fts_score`: BM25 score mapped to 0..1, if found by full text.
+/// - `final_score`: Score after fusion and boosts (the result's `score`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchExplanation {
+    pub from_vector: bool,
+    pub from_fts: bool,
+    pub vector_sim: Option<f32>,
+    pub fts_score: Option<f32>,
+    pub final_score: f32,
+}
+
+impl SearchExplanation {
+    /// Provenance of a vector hit.
+    pub fn vector(similarity: f32) -> Self {
+        Self {
+            from_vector: true,
+            vector_sim: Some(similarity),
+            ..Self::default()
+        }
+    }
+
+    /// Provenance of a full-text hit.
+    pub fn fts(score: f32) -> Self {
+        Self {
+            from_fts: true,
+            fts_score: Some(score),
+            ..Self::default()
+        }
+    }
+
+    /// Combine with the provenance of the same item from another retriever.
+    pub fn merge(&mut self, other: &Self) {
+        self.from_vector |= other.from_vector;
+        self.from_fts |= other.from_fts;
+        self.vector_sim = self.vector_sim.or(other.vector_sim);
+        self.fts_score = self.fts_score.or(other.fts_score);
+    }
 }
 
 /// Source of a search result.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SearchSource {
     Event,
     Exchange,
 }
 
+/// Width of a time bucket for activity stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Hour,
+    Day,
+    /// ISO week, starting Monday
+    Week,
+}
+
+/// How a timeline file filter is matched against stored file paths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileMatch {
+    /// Case-insensitive substring anywhere in the path
+    #[default]
+    Substring,
+    /// Glob (`*` stays within a directory, `**` spans directories); relative
+    /// patterns are anchored at the working directory the event was
+    /// captured in
+    Glob,
+    /// Regular expression searched anywhere in the path
+    Regex,
+}
+
+/// File format for `ExportVectors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorExportFormat {
+    /// NumPy `.npy` matrix with the IDs in a companion `ids.txt`
+    Npy,
+    /// Arrow IPC file with an `id` column and a fixed-size-list
+    /// `embedding` column
+    Arrow,
+}
+
+/// How hybrid search weighs vector similarity against keyword matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchPreference {
+    /// Favor semantically similar results
+    Semantic,
+    /// Favor exact keyword matches
+    Keyword,
+    /// Weigh both retrievers equally
+    Balanced,
+}
+
+impl SearchPreference {
+    /// Blend weights as `(vector_weight, fts_weight)`.
+    pub fn weights(self) -> (f32, f32) {
+        match self {
+            SearchPreference::Semantic => (0.8, 0.2),
+            SearchPreference::Keyword => (0.2, 0.8),
+            SearchPreference::Balanced => (0.5, 0.5),
+        }
+    }
+}
+
+/// Activity aggregates for one time bucket.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatBucket {
+    /// Start of the bucket (local time, e.g., "2024-01-15T00:00:00")
+    pub bucket_start: String,
+    /// Events captured in the bucket
+    pub event_count: u64,
+    /// Conversation exchanges in the bucket
+    pub exchange_count: u64,
+    /// Event counts per operation (operations without events are omitted)
+    pub by_operation: std::collections::HashMap<String, u64>,
+    /// Event counts per tool (tools without events are omitted)
+    pub by_tool: std::collections::HashMap<String, u64>,
+}
+
+/// Aggregates over the events of one coding session.
+///
+/// # Fields
+/// - `session_id`: Full session ID.
+/// - `started_at`: Timestamp of the first event (local time).
+/// - `ended_at`: Timestamp of the last event (local time).
+/// - `event_count`: Events captured in the session.
+/// - `files_touched`: Distinct file paths, in order of first change.
+/// - `commits`: Distinct commit SHAs recorded, in order of first sighting.
+/// - `tools`: Event counts per tool.
+/// - `intent`: What the user asked for, from the conversation before the
+///   session's first file change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub event_count: u64,
+    pub files_touched: Vec<String>,
+    pub commits: Vec<String>,
+    pub tools: std::collections::HashMap<String, u64>,
+    pub intent: Option<String>,
+}
+
 /// IPC message between CLI and daemon.
 ///
 /// Messages are serialized to JSON and sent over the Unix socket.
@@ -162,6 +513,10 @@ pub enum IpcMessage {
     /// Capture a code change event
     Capture(CaptureEvent),
 
+    /// Capture several events at once, embedded together and written in
+    /// one transaction, in order
+    CaptureBatch(Vec<CaptureEvent>),
+
     /// Search for similar content
     Search {
         query: String,
@@ -169,8 +524,29 @@ pub enum IpcMessage {
         source_filter: Option<SearchSource>,
         /// Filter by time (e.g., "1h", "7d", "2024-01-01")
         since: Option<String>,
+        /// Upper time bound, in the same formats as `since`
+        #[serde(default)]
+        until: Option<String>,
         /// Filter by project name
         project: Option<String>,
+        /// Filter events by tool name; excludes exchanges
+        #[serde(default)]
+        tool: Option<String>,
+        /// Stream results as `SearchResultChunk` messages ending with
+        /// `SearchResultsEnd` instead of one `SearchResults` response
+        #[serde(default)]
+        stream: bool,
+        /// Return the results ranked after this one, for paging
+        #[serde(default)]
+        after: Option<SearchCursor>,
+        /// Blend vector and keyword scores with this preference's weights
+        /// instead of the configured fusion
+        #[serde(default)]
+        prefer: Option<SearchPreference>,
+        /// Attach retrieval provenance (`SearchResult::explain`) to each
+        /// result
+        #[serde(default)]
+        explain: bool,
     },
 
     /// Get timeline events
@@ -178,11 +554,68 @@ pub enum IpcMessage {
         since: Option<String>,
         file_filter: Option<String>,
         limit: usize,
+        /// How `file_filter` is matched (default: substring)
+        #[serde(default)]
+        file_match: FileMatch,
+        /// Filter by project name (substring, case-insensitive)
+        #[serde(default)]
+        project: Option<String>,
+        /// Filter by operation (`create`, `modify`, `execute`, ...)
+        #[serde(default)]
+        operation: Option<String>,
+        /// Filter by Bash command category (`git`, `test`, `build`, ...)
+        #[serde(default)]
+        category: Option<String>,
+        /// Paging cursor: the `next_cursor` of the previous page
+        #[serde(default)]
+        before_id: Option<i64>,
+    },
+
+    /// Get the events that went into a git commit
+    EventsForCommit {
+        /// Full or abbreviated commit SHA
+        sha: String,
     },
 
+    /// Get one event with its stored fingerprint
+    GetEvent { id: i64 },
+
+    /// Get the chain of earlier edits to the same file in the same session,
+    /// ending with the given event
+    Lineage { id: i64 },
+
     /// Index pending conversations
     IndexConversations,
 
+    /// Import events and exchanges from an export document
+    ImportBatch {
+        events: Vec<ExportedEvent>,
+        exchanges: Vec<Exchange>,
+    },
+
+    /// Rebuild vector indexes by re-embedding stored events and exchanges
+    Reindex {
+        /// Rebuild even if the index dimensions already match the model
+        #[serde(default)]
+        force: bool,
+    },
+
+    /// Rebuild the vector indexes from the embeddings stored in the
+    /// database, dropping the space left by removed vectors
+    Compact,
+
+    /// Re-add stored embeddings missing from the vector indexes and drop
+    /// entries whose row is gone, as the daemon does on start
+    Reconcile,
+
+    /// Load the embedding model again and swap it in once warmed up
+    ReloadModel {
+        /// Directory with `model.onnx` and `tokenizer.json` (default:
+        /// `embedding.model_dir` from the config file as it is now)
+        #[serde(default)]
+        model_dir: Option<PathBuf>,
+    },
+
     /// Get diagnostic information
     DoctorInfo,
 
@@ -192,16 +625,89 @@ pub enum IpcMessage {
         limit: usize,
     },
 
+    /// Version handshake, sent first so the client can feature-detect
+    Hello {
+        /// Version of the client (its `CARGO_PKG_VERSION`)
+        client_version: String,
+    },
+
     /// Health check
     Ping,
 
+    /// Readiness check: model loaded and vector indexes usable
+    Health,
+
+    /// Keep the connection open and push each captured event as an
+    /// `EventPushed` response. The daemon acknowledges with `Ok` and repeats
+    /// `Ok` as a heartbeat while no events arrive.
+    Subscribe,
+
     /// Shutdown daemon
     Shutdown,
 
     /// Run database maintenance (VACUUM, ANALYZE, prune old events)
+    ///
+    /// Retention for each data type is the per-type field if set, else
+    /// `retention_days` if nonzero, else the daemon's `[retention]` config.
     Maintenance {
-        /// Prune events older than this many days (0 = no pruning)
+        /// Prune events and exchanges older than this many days
+        /// (0 = no pruning)
         retention_days: u32,
+        /// Prune events older than this many days (0 = keep forever)
+        #[serde(default)]
+        events_retention_days: Option<u32>,
+        /// Prune exchanges older than this many days (0 = keep forever)
+        #[serde(default)]
+        exchanges_retention_days: Option<u32>,
+        /// Retention in days for events and exchanges of a project
+        /// (0 = keep forever), keyed by project name substring; replaces
+        /// the configured overrides when not empty
+        #[serde(default)]
+        project_retention_days: std::collections::HashMap<String, u32>,
+    },
+
+    /// Report data-quality problems (missing embeddings, vector index
+    /// drift, hash-chain breaks, stale full-text indexes) without
+    /// modifying anything
+    Integrity,
+
+    /// Delete conversation exchanges and their vectors.
+    ///
+    /// Filters combine with AND; at least one is required.
+    Forget {
+        /// Project name substring (case-insensitive)
+        #[serde(default)]
+        project: Option<String>,
+        /// Exact session ID
+        #[serde(default)]
+        session: Option<String>,
+        /// Only exchanges older than this (relative or ISO, as `since`)
+        #[serde(default)]
+        before: Option<String>,
+        /// FTS5 query over the user message, assistant message and summary
+        #[serde(default)]
+        query: Option<String>,
+        /// Report what would be deleted without deleting anything
+        #[serde(default)]
+        dry_run: bool,
+    },
+
+    /// Audit the exchange vector index for near-duplicate vectors
+    DedupReport {
+        /// Minimum cosine similarity for two exchanges to count as duplicates
+        threshold: f32,
+        /// Probe at most this many vectors (default: all)
+        #[serde(default)]
+        sample: Option<usize>,
+    },
+
+    /// Write the vector indexes to files for analysis outside diachron
+    ExportVectors {
+        /// Directory to write into (created if missing); must lie in the
+        /// daemon's `exports` directory, and a relative path is taken from it
+        dir: PathBuf,
+        /// File format
+        format: VectorExportFormat,
     },
 
     /// Blame a specific file line using fingerprint matching
@@ -216,6 +722,36 @@ pub enum IpcMessage {
         context: String,
         /// Blame mode: "strict", "best-effort", or "inferred"
         mode: String,
+        /// Same-session events to return on each side of the match
+        #[serde(default)]
+        session_context: usize,
+    },
+
+    /// Blame a range of lines, grouping contiguous lines by matched event
+    BlameRange {
+        /// File path being blamed
+        file_path: String,
+        /// First line to blame (1-indexed, inclusive)
+        start: u32,
+        /// Last line to blame (1-indexed, inclusive)
+        end: u32,
+        /// File lines from `start - 5` to `end + 5` (clamped), for content and context
+        lines: Vec<String>,
+        /// Line number of `lines[0]` (1-indexed)
+        lines_start: u32,
+        /// Blame mode: "strict", "best-effort", or "inferred"
+        mode: String,
+        /// Same-session events to return on each side of each run's match
+        #[serde(default)]
+        session_context: usize,
+    },
+
+    /// Blame a whole file at once, for editor annotations
+    BlameFile {
+        /// File path being blamed
+        file_path: String,
+        /// Current content of the file
+        content: String,
     },
 
     /// Correlate events with PR commits and generate evidence pack
@@ -232,6 +768,38 @@ pub enum IpcMessage {
         end_time: String,
         /// Optional user intent
         intent: Option<String>,
+        /// Time-match window in seconds (default: 300)
+        #[serde(default)]
+        time_window_secs: Option<i64>,
+        /// Only time-match events whose file the commit changed
+        #[serde(default)]
+        require_file_match: bool,
+        /// Files changed by each commit (SHA -> repo-relative paths)
+        #[serde(default)]
+        commit_files: std::collections::HashMap<String, Vec<String>>,
+    },
+
+    /// Time-bucketed activity aggregates for charts
+    Stats {
+        /// Bucket width
+        bucket: Bucket,
+        /// Filter by time (e.g., "7d", "2024-01-01")
+        since: Option<String>,
+    },
+
+    /// Summarize one session
+    SessionSummary {
+        /// Session ID or a unique prefix of one
+        session_id: String,
+    },
+
+    /// Summarize recently active sessions, most recent first
+    Sessions {
+        /// Only sessions with events in this window (e.g., "7d")
+        #[serde(default)]
+        since: Option<String>,
+        /// Maximum number of sessions
+        limit: usize,
     },
 }
 
@@ -240,18 +808,112 @@ pub enum IpcMessage {
 #[serde(tag = "type", content = "payload")]
 pub enum IpcResponse {
     Ok,
-    Error(String),
+    Error(DaemonError),
     SearchResults(Vec<SearchResult>),
+    /// One result of a streaming search (best first)
+    SearchResultChunk(SearchResult),
+    /// Terminates a streaming search
+    SearchResultsEnd,
     Events(Vec<StoredEvent>),
+    /// One page of timeline events, answering `Timeline`
+    EventsPage {
+        events: Vec<StoredEvent>,
+        /// Pass as `before_id` to get the next page; `None` on the last page
+        next_cursor: Option<i64>,
+    },
+    /// A single event, answering `GetEvent`
+    Event(EventDetail),
+    /// A newly captured event, pushed to `Subscribe` connections
+    EventPushed(StoredEvent),
+    /// Answer to `Hello`
+    Hello {
+        /// Version of the daemon (its `CARGO_PKG_VERSION`)
+        daemon_version: String,
+        /// The daemon's `PROTOCOL_VERSION`
+        protocol_version: u32,
+        /// Optional features the daemon supports (`CAPABILITIES`)
+        capabilities: Vec<String>,
+    },
     Pong {
         uptime_secs: u64,
         events_count: u64,
     },
+    /// Readiness report, answering `Health`
+    Health {
+        /// Searches get semantic results: the model is loaded (or the
+        /// daemon is configured to run degraded) and both indexes are usable
+        ready: bool,
+        model_loaded: bool,
+        /// Every embedding engine has run a warm-up inference, so searches
+        /// do not pay the model's cold-start cost
+        #[serde(default)]
+        model_warm: bool,
+        events_index_ok: bool,
+        exchanges_index_ok: bool,
+        /// Why the daemon is not ready, or is running degraded
+        warnings: Vec<String>,
+    },
     /// Result of indexing conversations
     IndexStats {
         exchanges_indexed: u64,
         archives_processed: u64,
         errors: u64,
+        /// Near-duplicate exchanges saved but left out of the vector index
+        #[serde(default)]
+        deduplicated: u64,
+    },
+    /// Result of `CaptureBatch`
+    CaptureStats {
+        /// Events written
+        saved: u64,
+        /// Edits not written because their new content is the same as
+        /// their old content (`capture.skip_noop_edits`)
+        skipped: u64,
+        /// Events that could not be written
+        failed: u64,
+    },
+    /// Result of importing a batch; skipped rows were already present
+    ImportStats {
+        events_imported: u64,
+        events_skipped: u64,
+        exchanges_imported: u64,
+        exchanges_skipped: u64,
+    },
+    /// Result of rebuilding vector indexes
+    ReindexStats {
+        /// Whether the indexes were rebuilt (false if already consistent)
+        rebuilt: bool,
+        /// Embedding dimension of the rebuilt indexes
+        dim: usize,
+        events_indexed: u64,
+        exchanges_indexed: u64,
+    },
+    /// Result of `Compact`
+    CompactStats {
+        events: IndexCompaction,
+        exchanges: IndexCompaction,
+    },
+    /// Result of `Reconcile`
+    ReconcileStats {
+        /// Stored embeddings re-added to an index
+        restored: u64,
+        /// Index entries dropped because their row no longer exists
+        removed: u64,
+        /// Missing vectors left for another pass (at most a capped number
+        /// are restored per pass)
+        deferred: u64,
+    },
+    /// Result of `ReloadModel`
+    ModelReloaded {
+        /// Whether the new engines replaced the old ones (false if their
+        /// dimension does not match the vector indexes)
+        swapped: bool,
+        /// Embedding dimension of the new model
+        dim: usize,
+        /// Embedding dimension of the vector indexes
+        index_dim: usize,
+        /// Engines loaded for the new model
+        pool_size: usize,
     },
     /// Diagnostic information
     Doctor(DiagnosticInfo),
@@ -259,7 +921,13 @@ pub enum IpcResponse {
     SummarizeStats {
         summarized: u64,
         skipped: u64,
-        errors: u64,
+        /// Exchanges that needed at least one retry (also counted as
+        /// summarized or failed)
+        #[serde(default)]
+        retried: u64,
+        /// Exchanges left unsummarized after retries
+        #[serde(alias = "errors")]
+        failed: u64,
     },
     /// Result of database maintenance
     MaintenanceStats {
@@ -271,17 +939,122 @@ pub enum IpcResponse {
         events_pruned: u64,
         /// Exchanges pruned (if retention enabled)
         exchanges_pruned: u64,
+        /// Event retention applied, in days (0 = kept forever)
+        #[serde(default)]
+        events_retention_days: u32,
+        /// Exchange retention applied, in days (0 = kept forever)
+        #[serde(default)]
+        exchanges_retention_days: u32,
+        /// Projects with their own retention
+        #[serde(default)]
+        project_overrides: u64,
         /// Time taken (milliseconds)
         duration_ms: u64,
     },
+    /// Result of `Integrity`
+    IntegrityReport(IntegrityReport),
+    /// Result of `Forget`
+    ForgetStats {
+        /// Exchanges deleted (or matched, for a dry run)
+        exchanges: u64,
+        /// Vectors removed from the exchanges index (or that would be)
+        vectors: u64,
+        /// Distinct projects of the matched exchanges
+        projects: Vec<String>,
+        /// Nothing was deleted
+        dry_run: bool,
+    },
+    /// Result of `DedupReport`
+    DedupReport {
+        /// Vectors in the exchanges index
+        indexed: u64,
+        /// Vectors whose neighbors were searched
+        probed: u64,
+        /// Near-duplicate pairs found
+        pairs: u64,
+        /// Exchange IDs linked by near-duplicate pairs, largest group first
+        clusters: Vec<Vec<String>>,
+        /// Vectors that could be dropped while keeping one per cluster
+        reclaimable: u64,
+    },
+    /// Result of `ExportVectors`
+    VectorsExported {
+        /// Embedding dimension (columns of each matrix)
+        dim: usize,
+        /// Vectors written from the events index
+        events: u64,
+        /// Vectors written from the exchanges index
+        exchanges: u64,
+        /// Files written
+        files: Vec<PathBuf>,
+    },
     /// Result of fingerprint-based blame
     BlameResult(BlameMatch),
+    /// Result of range blame: contiguous runs of lines in file order
+    BlameRangeResult(Vec<BlameRun>),
+    /// Result of file blame: matched runs of lines in file order (every
+    /// `blame` is set; lines no event wrote are left out)
+    BlameFileResult(Vec<BlameRun>),
     /// No blame match found
     BlameNotFound {
         reason: String,
     },
     /// Result of PR evidence correlation
     EvidenceResult(EvidencePackResult),
+    /// Activity aggregates, one entry per bucket with no gaps, oldest first
+    Stats(Vec<StatBucket>),
+    /// Summary of one session
+    SessionSummary(SessionSummary),
+    /// Summaries of recent sessions, most recently active first
+    Sessions(Vec<SessionSummary>),
+}
+
+/// How a blamed line was tied to its event, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlameConfidence {
+    /// No fingerprint matched; the most recent event for the file
+    Inferred,
+    /// Semantic similarity of the line's content
+    Low,
+    /// Hash of the surrounding lines
+    Medium,
+    /// Exact hash of the line's content
+    High,
+}
+
+impl BlameConfidence {
+    /// Return the lowercase string representation used on the wire.
+    ///
+    /// # Returns
+    /// String slice for this confidence level.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlameConfidence::Inferred => "inferred",
+            BlameConfidence::Low => "low",
+            BlameConfidence::Medium => "medium",
+            BlameConfidence::High => "high",
+        }
+    }
+
+    /// How much a match at this level is trusted, from 0 to 1.
+    ///
+    /// # Returns
+    /// Weight applied to the match similarity to get its score.
+    pub fn weight(&self) -> f32 {
+        match self {
+            BlameConfidence::Inferred => 0.3,
+            BlameConfidence::Low => 0.6,
+            BlameConfidence::Medium => 0.8,
+            BlameConfidence::High => 1.0,
+        }
+    }
+}
+
+impl std::fmt::Display for BlameConfidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Blame match result from fingerprint lookup
@@ -289,14 +1062,36 @@ pub enum IpcResponse {
 pub struct BlameMatch {
     /// The matched event
     pub event: StoredEvent,
-    /// Confidence level: "high", "medium", "low", "inferred"
-    pub confidence: String,
+    /// How the line was matched
+    pub confidence: BlameConfidence,
+    /// Overall confidence (0.0 - 1.0): the similarity weighted by the
+    /// confidence level, plus any corroborating evidence
+    #[serde(default)]
+    pub score: f32,
+    /// Evidence behind the match, e.g. "content hash exact"
+    #[serde(default)]
+    pub reasons: Vec<String>,
     /// Match type description
     pub match_type: String,
     /// Similarity score (0.0 - 1.0)
     pub similarity: f32,
     /// User intent if available from conversation
     pub intent: Option<String>,
+    /// Events just before and after `event` in its session, oldest first
+    /// (empty unless requested)
+    #[serde(default)]
+    pub context_events: Vec<StoredEvent>,
+}
+
+/// A contiguous run of lines attributed to the same event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameRun {
+    /// First line of the run (1-indexed, inclusive)
+    pub start_line: u32,
+    /// Last line of the run (1-indexed, inclusive)
+    pub end_line: u32,
+    /// Weakest match across the run's lines, or None if no event matched
+    pub blame: Option<BlameMatch>,
 }
 
 /// Evidence pack result from PR correlation
@@ -322,6 +1117,9 @@ pub struct EvidencePackResult {
     pub coverage_pct: f32,
     /// Number of unmatched events
     pub unmatched_count: usize,
+    /// Events that couldn't be matched to any commit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmatched_events: Vec<StoredEvent>,
     /// Total events considered
     pub total_events: u64,
 }
@@ -392,6 +1190,102 @@ pub struct DiagnosticInfo {
     pub model_size_bytes: u64,
     /// Daemon memory usage in bytes (RSS)
     pub memory_rss_bytes: u64,
+    /// HNSW parameters of the live events index
+    #[serde(default)]
+    pub events_index_hnsw: HnswConfig,
+    /// HNSW parameters of the live exchanges index
+    #[serde(default)]
+    pub exchanges_index_hnsw: HnswConfig,
+    /// Embedding cache hits since daemon start
+    #[serde(default)]
+    pub embedding_cache_hits: u64,
+    /// Embedding cache misses since daemon start
+    #[serde(default)]
+    pub embedding_cache_misses: u64,
+    /// Embedding engines loaded for parallel inference (0 if no model)
+    #[serde(default)]
+    pub embedding_pool_size: usize,
+}
+
+/// Data-quality counts for a table mirrored in a vector index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableIntegrity {
+    /// Rows in the table
+    pub rows: u64,
+    /// Rows with neither a stored embedding nor a vector in the index
+    /// (exchanges marked as near-duplicates are kept out on purpose and not
+    /// counted)
+    pub without_embedding: u64,
+    /// Rows with a stored embedding whose vector is not in the index
+    pub missing_from_index: u64,
+    /// Index entries whose row no longer exists
+    pub orphaned_vectors: u64,
+    /// Documents in the full-text index
+    pub fts_rows: u64,
+}
+
+impl TableIntegrity {
+    /// Whether the full-text index covers a different number of rows than
+    /// the table.
+    pub fn fts_stale(&self) -> bool {
+        self.fts_rows != self.rows
+    }
+
+    /// Whether the vector index has drifted from the stored embeddings.
+    pub fn index_drifted(&self) -> bool {
+        self.missing_from_index > 0 || self.orphaned_vectors > 0
+    }
+}
+
+/// One vector index before and after `Compact`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexCompaction {
+    /// Vectors in the index before compaction
+    pub vectors_before: u64,
+    /// Vectors in the rebuilt index
+    pub vectors_after: u64,
+    /// Size of the index files before compaction (bytes)
+    pub bytes_before: u64,
+    /// Size of the rebuilt index files (bytes)
+    pub bytes_after: u64,
+    /// Stored embeddings left out because their blob is not a whole number
+    /// of `f32`s
+    pub corrupt: u64,
+    /// Stored embeddings left out because their dimension differs from the
+    /// index (a `Reindex` re-embeds those)
+    pub wrong_dim: u64,
+}
+
+/// Data-quality report, answering `Integrity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Events table against the events index
+    pub events: TableIntegrity,
+    /// Exchanges table against the exchanges index
+    pub exchanges: TableIntegrity,
+    /// Events without hash-chain fields, written before the chain existed
+    pub events_without_hash: u64,
+    /// Full hash-chain verification
+    pub chain: ChainVerificationResult,
+}
+
+impl IntegrityReport {
+    /// Whether a vector index rebuild would change anything.
+    pub fn needs_reindex(&self) -> bool {
+        self.events.index_drifted()
+            || self.exchanges.index_drifted()
+            || self.events.without_embedding > 0
+            || self.exchanges.without_embedding > 0
+    }
+
+    /// Whether no problem was found.
+    pub fn is_clean(&self) -> bool {
+        !self.needs_reindex()
+            && !self.events.fts_stale()
+            && !self.exchanges.fts_stale()
+            && self.events_without_hash == 0
+            && self.chain.valid
+    }
 }
 
 /// Event as stored in the database (with ID and timestamps).
@@ -403,12 +1297,14 @@ pub struct DiagnosticInfo {
 /// - `session_id`: Optional session identifier.
 /// - `tool_name`: Tool name that produced the event.
 /// - `file_path`: Optional file path affected by the event.
-/// - `operation`: Optional operation string.
+/// - `operation`: Optional operation; values this version does not know
+///   read as `Operation::Unknown`.
 /// - `diff_summary`: Optional diff summary.
 /// - `raw_input`: Optional raw tool input.
 /// - `ai_summary`: Optional AI summary.
 /// - `git_commit_sha`: Optional commit SHA.
 /// - `metadata`: Optional JSON metadata string.
+/// - `command_category`: Bash command category from the metadata, if any.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredEvent {
     pub id: i64,
@@ -417,10 +1313,71 @@ pub struct StoredEvent {
     pub session_id: Option<String>,
     pub tool_name: String,
     pub file_path: Option<String>,
-    pub operation: Option<String>,
+    pub operation: Option<Operation>,
     pub diff_summary: Option<String>,
     pub raw_input: Option<String>,
     pub ai_summary: Option<String>,
     pub git_commit_sha: Option<String>,
     pub metadata: Option<String>,
+    #[serde(default)]
+    pub command_category: Option<CommandCategory>,
+}
+
+impl StoredEvent {
+    /// Outcome recorded in the event metadata, if any.
+    ///
+    /// # Returns
+    /// `Some(Outcome)` when the capture recorded one, `None` for older events.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.metadata_str("outcome").and_then(|o| match o.as_str() {
+            "success" => Some(Outcome::Success),
+            "failure" => Some(Outcome::Failure),
+            _ => None,
+        })
+    }
+
+    /// Error excerpt recorded for a failed tool call, if any.
+    pub fn error_excerpt(&self) -> Option<String> {
+        self.metadata_str("error_excerpt")
+    }
+
+    /// Working directory the event was captured in, if recorded.
+    pub fn cwd(&self) -> Option<String> {
+        self.metadata_str("cwd")
+    }
+
+    /// Whether `raw_input` was cut short at capture.
+    pub fn raw_input_truncated(&self) -> bool {
+        self.metadata
+            .as_deref()
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+            .and_then(|meta| meta.get("raw_input_truncated")?.as_bool())
+            .unwrap_or(false)
+    }
+
+    fn metadata_str(&self, key: &str) -> Option<String> {
+        let meta: serde_json::Value = serde_json::from_str(self.metadata.as_deref()?).ok()?;
+        meta.get(key)?.as_str().map(str::to_string)
+    }
+}
+
+/// An event together with the fingerprint and edit content stored for it.
+///
+/// # Fields
+/// - `event`: The stored event, including its full `raw_input`.
+/// - `content_hash`: Hex SHA-256 of the normalized content, if fingerprinted.
+/// - `context_hash`: Hex SHA-256 of the surrounding context, if fingerprinted.
+/// - `semantic_sig_dim`: Dimension of the stored semantic signature, if any.
+/// - `old_content`: Text an edit replaced, if `capture.store_edit_content` was on.
+/// - `new_content`: Text an edit inserted, if `capture.store_edit_content` was on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDetail {
+    pub event: StoredEvent,
+    pub content_hash: Option<String>,
+    pub context_hash: Option<String>,
+    pub semantic_sig_dim: Option<usize>,
+    #[serde(default)]
+    pub old_content: Option<String>,
+    #[serde(default)]
+    pub new_content: Option<String>,
 }
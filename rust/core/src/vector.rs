@@ -6,15 +6,18 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use usearch::{Index, IndexOptions, MetricKind, ScalarKind};
 
 /// Embedding dimension (384 for all-MiniLM-L6-v2).
 pub const EMBEDDING_DIM: usize = 384;
 
+/// Neighbors fetched per probe when looking for near-duplicates.
+const NEAR_DUPLICATE_PROBE_K: usize = 8;
+
 /// Error type for vector index operations.
 #[derive(Error, Debug)]
 pub enum VectorError {
@@ -29,18 +32,119 @@ pub enum VectorError {
 
     #[error("ID not found: {0}")]
     NotFound(String),
+
+    #[error("Metric mismatch: index uses {found}, expected {expected}")]
+    MetricMismatch { expected: Metric, found: Metric },
+
+    #[error("Dimension mismatch: expected {expected}, got {got}")]
+    DimensionMismatch { expected: usize, got: usize },
 }
 
 /// Result alias for vector index operations.
 pub type Result<T> = std::result::Result<T, VectorError>;
 
+/// A vector to add: application-level ID, embedding, and optional metadata
+/// (see [`VectorIndex::add`]).
+pub type VectorEntry = (String, Vec<f32>, Option<String>);
+
+/// Distance metric used by the HNSW graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Cosine similarity (default; correct for L2-normalized embeddings)
+    #[default]
+    Cosine,
+    /// Raw inner product (for un-normalized embeddings)
+    InnerProduct,
+    /// Squared Euclidean distance
+    L2,
+}
+
+impl Metric {
+    /// Get the string representation of the metric.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Cosine => "cosine",
+            Metric::InnerProduct => "inner_product",
+            Metric::L2 => "l2",
+        }
+    }
+
+    fn to_metric_kind(self) -> MetricKind {
+        match self {
+            Metric::Cosine => MetricKind::Cos,
+            Metric::InnerProduct => MetricKind::IP,
+            Metric::L2 => MetricKind::L2sq,
+        }
+    }
+
+    /// Map a raw usearch distance onto a 0..1 similarity (higher is better).
+    fn distance_to_similarity(self, distance: f32) -> f32 {
+        match self {
+            // usearch reports both as `1 - similarity`; anti-correlated
+            // vectors are clamped to 0
+            Metric::Cosine | Metric::InnerProduct => (1.0 - distance).clamp(0.0, 1.0),
+            Metric::L2 => 1.0 / (1.0 + distance.max(0.0)),
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Scalar type used to store vectors inside the index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// Full-precision 32-bit floats (default)
+    #[default]
+    F32,
+    /// 8-bit signed integers (~4x smaller, negligible recall loss for MiniLM)
+    I8,
+}
+
+impl Quantization {
+    fn to_scalar_kind(self) -> ScalarKind {
+        match self {
+            Quantization::F32 => ScalarKind::F32,
+            Quantization::I8 => ScalarKind::I8,
+        }
+    }
+}
+
+/// HNSW graph construction and query parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HnswConfig {
+    /// M parameter: edges per node in the graph
+    pub connectivity: usize,
+    /// ef_construction: candidate list size while inserting
+    pub expansion_add: usize,
+    /// ef_search: candidate list size while querying
+    pub expansion_search: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            connectivity: 16,
+            expansion_add: 128,
+            expansion_search: 64,
+        }
+    }
+}
+
 /// Search result from vector index.
 #[derive(Debug, Clone)]
 pub struct VectorSearchResult {
     /// The ID of the matching item
     pub id: String,
-    /// Similarity score (0-1, higher is better)
-    pub score: f32,
+    /// Raw distance reported by the index (lower is better)
+    pub distance: f32,
+    /// Similarity normalized to 0-1 (higher is better)
+    pub similarity: f32,
 }
 
 /// HNSW-based vector index.
@@ -52,33 +156,82 @@ pub struct VectorIndex {
     id_map: HashMap<u64, String>,
     /// Reverse map from string ID to usearch key
     key_map: HashMap<String, u64>,
+    /// Optional metadata per usearch key (e.g. a project), for filtering
+    metadata: HashMap<u64, String>,
     /// Counter for generating unique keys
     next_key: AtomicU64,
     /// Dimension of embeddings
     dim: usize,
+    /// Distance metric of the HNSW graph
+    metric: Metric,
+    /// HNSW parameters the graph was built with
+    hnsw: HnswConfig,
+    /// Scalar type vectors are stored as
+    quantization: Quantization,
+    /// Whether the index changed since it was created, loaded, or saved
+    dirty: AtomicBool,
 }
 
 impl VectorIndex {
-    /// Create a new empty vector index.
+    /// Create a new empty vector index using cosine similarity.
     ///
     /// # Arguments
     /// - `dim`: Embedding dimension for vectors in the index.
+    /// - `hnsw`: Optional HNSW parameters (defaults to `HnswConfig::default()`).
     ///
     /// # Returns
     /// Empty vector index configured for the provided dimension.
     ///
     /// # Errors
     /// Returns `VectorError` if the index cannot be initialized.
-    pub fn new(dim: usize) -> Result<Self> {
-        let options = IndexOptions {
-            dimensions: dim,
-            metric: MetricKind::Cos, // Cosine similarity
-            quantization: ScalarKind::F32,
-            connectivity: 16,     // M parameter for HNSW
-            expansion_add: 128,   // ef_construction
-            expansion_search: 64, // ef_search
-            multi: false,         // Single vector per key
-        };
+    pub fn new(dim: usize, hnsw: Option<HnswConfig>) -> Result<Self> {
+        Self::with_options(
+            dim,
+            Metric::Cosine,
+            hnsw.unwrap_or_default(),
+            Quantization::F32,
+        )
+    }
+
+    /// Create a new empty vector index with an explicit distance metric.
+    ///
+    /// # Arguments
+    /// - `dim`: Embedding dimension for vectors in the index.
+    /// - `metric`: Distance metric for the HNSW graph.
+    ///
+    /// # Returns
+    /// Empty vector index configured for the provided dimension and metric.
+    ///
+    /// # Errors
+    /// Returns `VectorError` if the index cannot be initialized.
+    pub fn with_metric(dim: usize, metric: Metric) -> Result<Self> {
+        Self::with_options(dim, metric, HnswConfig::default(), Quantization::F32)
+    }
+
+    /// Create a new empty vector index with explicit metric, HNSW parameters
+    /// and storage quantization.
+    ///
+    /// Vectors are always added and queried as `f32`; with
+    /// `Quantization::I8` usearch quantizes them natively on insert.
+    ///
+    /// # Arguments
+    /// - `dim`: Embedding dimension for vectors in the index.
+    /// - `metric`: Distance metric for the HNSW graph.
+    /// - `hnsw`: Graph construction and query parameters.
+    /// - `quantization`: Scalar type used for stored vectors.
+    ///
+    /// # Returns
+    /// Empty vector index with the requested configuration.
+    ///
+    /// # Errors
+    /// Returns `VectorError` if the index cannot be initialized.
+    pub fn with_options(
+        dim: usize,
+        metric: Metric,
+        hnsw: HnswConfig,
+        quantization: Quantization,
+    ) -> Result<Self> {
+        let options = index_options(dim, metric, hnsw, quantization);
 
         let index = Index::new(&options).map_err(|e| VectorError::IndexError(e.to_string()))?;
 
@@ -86,8 +239,13 @@ impl VectorIndex {
             index,
             id_map: HashMap::new(),
             key_map: HashMap::new(),
+            metadata: HashMap::new(),
             next_key: AtomicU64::new(0),
             dim,
+            metric,
+            hnsw,
+            quantization,
+            dirty: AtomicBool::new(false),
         })
     }
 
@@ -99,50 +257,111 @@ impl VectorIndex {
     /// # Errors
     /// Returns `VectorError` if the index cannot be initialized.
     pub fn new_default() -> Result<Self> {
-        Self::new(EMBEDDING_DIM)
+        Self::new(EMBEDDING_DIM, None)
     }
 
     /// Add a vector with the given ID.
     ///
-    /// If the ID already exists, it will be updated.
+    /// If the ID already exists, it will be updated, metadata included.
     ///
     /// # Arguments
     /// - `id`: Application-level identifier for the embedding.
     /// - `embedding`: Embedding vector with length equal to `dim`.
+    /// - `metadata`: Optional value for [`Self::filtered_search`]
+    ///   predicates (e.g. the project the vector belongs to).
     ///
     /// # Errors
-    /// Returns `VectorError` if the embedding size is incorrect or the
-    /// underlying index update fails.
-    pub fn add(&mut self, id: &str, embedding: &[f32]) -> Result<()> {
-        assert_eq!(
-            embedding.len(),
-            self.dim,
-            "Embedding dimension mismatch: expected {}, got {}",
-            self.dim,
-            embedding.len()
-        );
+    /// Returns `VectorError::DimensionMismatch` if the embedding size is
+    /// incorrect, or another `VectorError` if the underlying index update fails.
+    pub fn add(&mut self, id: &str, embedding: &[f32], metadata: Option<&str>) -> Result<()> {
+        if embedding.len() != self.dim {
+            return Err(VectorError::DimensionMismatch {
+                expected: self.dim,
+                got: embedding.len(),
+            });
+        }
+
+        // Reserve capacity if needed (usearch requires this before adding)
+        let current_capacity = self.index.capacity();
+        if current_capacity <= self.id_map.len() {
+            // Reserve more capacity (double or at least 16)
+            self.reserve((current_capacity * 2).max(16))?;
+        }
 
+        self.insert(id, embedding, metadata)
+    }
+
+    /// Add many vectors at once, reserving capacity a single time.
+    ///
+    /// Existing IDs are updated. Items whose embedding has the wrong
+    /// dimension are skipped (and logged) rather than aborting the batch.
+    ///
+    /// # Arguments
+    /// - `items`: Application-level ID, embedding and optional metadata
+    ///   (see [`Self::add`]).
+    ///
+    /// # Returns
+    /// Number of vectors added; `items.len()` minus this is the skip count.
+    ///
+    /// # Errors
+    /// Returns `VectorError` if reserving capacity or the underlying index
+    /// update fails.
+    pub fn add_batch(&mut self, items: &[VectorEntry]) -> Result<usize> {
+        let required = self.id_map.len() + items.len();
+        if self.index.capacity() < required {
+            self.reserve(required.max(16))?;
+        }
+
+        let mut added = 0;
+        let mut skipped = 0;
+        for (id, embedding, metadata) in items {
+            if embedding.len() != self.dim {
+                debug!(
+                    "Skipping {}: dimension mismatch (expected {}, got {})",
+                    id,
+                    self.dim,
+                    embedding.len()
+                );
+                skipped += 1;
+                continue;
+            }
+            self.insert(id, embedding, metadata.as_deref())?;
+            added += 1;
+        }
+
+        if skipped > 0 {
+            warn!(
+                "Batch add skipped {} of {} vectors with wrong dimension",
+                skipped,
+                items.len()
+            );
+        }
+        debug!("Batch added {} vectors", added);
+        Ok(added)
+    }
+
+    /// Reserve capacity in the underlying usearch index.
+    fn reserve(&mut self, capacity: usize) -> Result<()> {
+        self.index
+            .reserve(capacity)
+            .map_err(|e| VectorError::IndexError(format!("Failed to reserve capacity: {}", e)))
+    }
+
+    /// Insert a vector, assuming capacity has already been reserved.
+    fn insert(&mut self, id: &str, embedding: &[f32], metadata: Option<&str>) -> Result<()> {
         // Check if ID already exists
         if let Some(&existing_key) = self.key_map.get(id) {
             // Remove old entry
             self.index
                 .remove(existing_key)
                 .map_err(|e| VectorError::IndexError(e.to_string()))?;
+            self.id_map.remove(&existing_key);
+            self.metadata.remove(&existing_key);
         }
 
         // Generate new key
         let key = self.next_key.fetch_add(1, Ordering::SeqCst);
 
-        // Reserve capacity if needed (usearch requires this before adding)
-        let current_capacity = self.index.capacity();
-        if current_capacity <= self.id_map.len() {
-            // Reserve more capacity (double or at least 16)
-            let new_capacity = (current_capacity * 2).max(16);
-            self.index.reserve(new_capacity).map_err(|e| {
-                VectorError::IndexError(format!("Failed to reserve capacity: {}", e))
-            })?;
-        }
-
         // Add to index
         self.index
             .add(key, embedding)
@@ -151,6 +370,10 @@ impl VectorIndex {
         // Update maps
         self.id_map.insert(key, id.to_string());
         self.key_map.insert(id.to_string(), key);
+        if let Some(metadata) = metadata {
+            self.metadata.insert(key, metadata.to_string());
+        }
+        self.dirty.store(true, Ordering::Release);
 
         debug!("Added vector for ID: {} (key: {})", id, key);
         Ok(())
@@ -166,16 +389,15 @@ impl VectorIndex {
     /// Results sorted by similarity (highest first).
     ///
     /// # Errors
-    /// Returns `VectorError` if the query dimension is incorrect or the
-    /// search fails.
+    /// Returns `VectorError::DimensionMismatch` if the query size is
+    /// incorrect, or another `VectorError` if the search fails.
     pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<VectorSearchResult>> {
-        assert_eq!(
-            query.len(),
-            self.dim,
-            "Query dimension mismatch: expected {}, got {}",
-            self.dim,
-            query.len()
-        );
+        if query.len() != self.dim {
+            return Err(VectorError::DimensionMismatch {
+                expected: self.dim,
+                got: query.len(),
+            });
+        }
 
         if self.is_empty() {
             return Ok(vec![]);
@@ -186,21 +408,72 @@ impl VectorIndex {
             .search(query, k)
             .map_err(|e| VectorError::IndexError(e.to_string()))?;
 
-        let results: Vec<VectorSearchResult> = matches
-            .keys
-            .iter()
-            .zip(matches.distances.iter())
+        let results = self.to_results(&matches.keys, &matches.distances);
+        debug!("Search returned {} results", results.len());
+        Ok(results)
+    }
+
+    /// Search for the `k` most similar vectors that satisfy a predicate.
+    ///
+    /// The predicate runs during graph traversal, so up to `k` matching
+    /// vectors are returned even when most near neighbors are rejected.
+    ///
+    /// # Arguments
+    /// - `query`: Query embedding with length equal to `dim`.
+    /// - `k`: Number of neighbors to return.
+    /// - `predicate`: Called with each candidate's ID and metadata (`None`
+    ///   if it was added without any); returns whether to keep it.
+    ///
+    /// # Returns
+    /// Matching results sorted by similarity (highest first).
+    ///
+    /// # Errors
+    /// Returns `VectorError::DimensionMismatch` if the query size is
+    /// incorrect, or another `VectorError` if the search fails.
+    pub fn filtered_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        predicate: impl Fn(&str, Option<&str>) -> bool,
+    ) -> Result<Vec<VectorSearchResult>> {
+        if query.len() != self.dim {
+            return Err(VectorError::DimensionMismatch {
+                expected: self.dim,
+                got: query.len(),
+            });
+        }
+
+        if self.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let matches = self
+            .index
+            .filtered_search(query, k, |key| {
+                self.id_map
+                    .get(&key)
+                    .is_some_and(|id| predicate(id, self.metadata.get(&key).map(String::as_str)))
+            })
+            .map_err(|e| VectorError::IndexError(e.to_string()))?;
+
+        let results = self.to_results(&matches.keys, &matches.distances);
+        debug!("Filtered search returned {} results", results.len());
+        Ok(results)
+    }
+
+    /// Map usearch keys and distances to results, dropping keys without an
+    /// ID.
+    fn to_results(&self, keys: &[u64], distances: &[f32]) -> Vec<VectorSearchResult> {
+        keys.iter()
+            .zip(distances.iter())
             .filter_map(|(&key, &distance)| {
                 self.id_map.get(&key).map(|id| VectorSearchResult {
                     id: id.clone(),
-                    // Convert cosine distance to similarity (1 - distance for normalized vectors)
-                    score: 1.0 - distance,
+                    distance,
+                    similarity: self.metric.distance_to_similarity(distance),
                 })
             })
-            .collect();
-
-        debug!("Search returned {} results", results.len());
-        Ok(results)
+            .collect()
     }
 
     /// Remove a vector by ID.
@@ -208,19 +481,125 @@ impl VectorIndex {
     /// # Arguments
     /// - `id`: Identifier to remove.
     ///
+    /// # Returns
+    /// True if the vector was removed, false if the ID was not present.
+    ///
     /// # Errors
-    /// Returns `VectorError::NotFound` if the ID is missing.
-    pub fn remove(&mut self, id: &str) -> Result<()> {
-        if let Some(key) = self.key_map.remove(id) {
-            self.index
-                .remove(key)
-                .map_err(|e| VectorError::IndexError(e.to_string()))?;
-            self.id_map.remove(&key);
-            debug!("Removed vector for ID: {}", id);
-            Ok(())
-        } else {
-            Err(VectorError::NotFound(id.to_string()))
+    /// Returns `VectorError` if the underlying index removal fails.
+    pub fn remove(&mut self, id: &str) -> Result<bool> {
+        let Some(&key) = self.key_map.get(id) else {
+            return Ok(false);
+        };
+
+        self.index
+            .remove(key)
+            .map_err(|e| VectorError::IndexError(e.to_string()))?;
+        self.key_map.remove(id);
+        self.id_map.remove(&key);
+        self.metadata.remove(&key);
+        self.dirty.store(true, Ordering::Release);
+        debug!("Removed vector for ID: {}", id);
+        Ok(true)
+    }
+
+    /// Find pairs of stored vectors that are at least `threshold` similar.
+    ///
+    /// Probes every vector's nearest neighbors with the index's own search,
+    /// so this costs one query per vector; see
+    /// [`Self::find_near_duplicates_sampled`] to bound it on large indexes.
+    ///
+    /// # Arguments
+    /// - `threshold`: Minimum similarity (0-1) for a pair to be reported.
+    ///
+    /// # Returns
+    /// `(id, id, similarity)` triples, most similar first. Each pair is
+    /// listed once with the smaller ID first, and never pairs a vector with
+    /// itself.
+    pub fn find_near_duplicates(&self, threshold: f32) -> Vec<(String, String, f32)> {
+        self.find_near_duplicates_sampled(threshold, None)
+    }
+
+    /// Like [`Self::find_near_duplicates`], probing at most `sample` vectors.
+    ///
+    /// The probed vectors are spread evenly over the sorted IDs, so repeated
+    /// runs probe the same ones. Their neighbors are still searched in the
+    /// whole index, so a pair is found if either side is probed.
+    ///
+    /// # Arguments
+    /// - `threshold`: Minimum similarity (0-1) for a pair to be reported.
+    /// - `sample`: Maximum vectors to probe (`None` probes all).
+    ///
+    /// # Returns
+    /// `(id, id, similarity)` triples, most similar first.
+    pub fn find_near_duplicates_sampled(
+        &self,
+        threshold: f32,
+        sample: Option<usize>,
+    ) -> Vec<(String, String, f32)> {
+        let mut keys: Vec<(&str, u64)> = self
+            .key_map
+            .iter()
+            .map(|(id, &key)| (id.as_str(), key))
+            .collect();
+        keys.sort_unstable();
+        let probes = sample.map_or(keys.len(), |n| n.min(keys.len()));
+
+        let mut pairs: HashMap<(String, String), f32> = HashMap::new();
+        let mut vector = vec![0.0f32; self.dim];
+        for i in 0..probes {
+            let (id, key) = keys[i * keys.len() / probes];
+            if !matches!(self.index.get(key, &mut vector), Ok(n) if n > 0) {
+                continue;
+            }
+            // The vector itself is normally the top hit
+            let Ok(neighbors) = self.search(&vector, NEAR_DUPLICATE_PROBE_K) else {
+                continue;
+            };
+            for hit in neighbors {
+                if hit.id == id || hit.similarity < threshold {
+                    continue;
+                }
+                let pair = if id < hit.id.as_str() {
+                    (id.to_string(), hit.id)
+                } else {
+                    (hit.id, id.to_string())
+                };
+                pairs.entry(pair).or_insert(hit.similarity);
+            }
         }
+
+        let mut pairs: Vec<(String, String, f32)> =
+            pairs.into_iter().map(|((a, b), sim)| (a, b, sim)).collect();
+        pairs.sort_by(|x, y| {
+            y.2.total_cmp(&x.2)
+                .then_with(|| x.0.cmp(&y.0))
+                .then_with(|| x.1.cmp(&y.1))
+        });
+        pairs
+    }
+
+    /// Copy every stored vector out of the index.
+    ///
+    /// Entries that were removed, or whose vector usearch can no longer
+    /// return, are left out.
+    ///
+    /// # Returns
+    /// `(id, vector)` pairs sorted by ID. Quantized indexes return their
+    /// vectors converted back to f32.
+    pub fn export_vectors(&self) -> Vec<(String, Vec<f32>)> {
+        let mut entries: Vec<(&String, u64)> =
+            self.key_map.iter().map(|(id, &key)| (id, key)).collect();
+        entries.sort_unstable();
+        entries
+            .into_iter()
+            .filter_map(|(id, key)| {
+                let mut vector = vec![0.0f32; self.dim];
+                match self.index.get(key, &mut vector) {
+                    Ok(n) if n > 0 => Some((id.clone(), vector)),
+                    _ => None,
+                }
+            })
+            .collect()
     }
 
     /// Check if the index contains an ID.
@@ -234,6 +613,11 @@ impl VectorIndex {
         self.key_map.contains_key(id)
     }
 
+    /// Iterate over the IDs stored in the index, in no particular order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.key_map.keys().map(String::as_str)
+    }
+
     /// Get the number of vectors in the index.
     ///
     /// # Returns
@@ -258,6 +642,52 @@ impl VectorIndex {
         self.dim
     }
 
+    /// Get the distance metric.
+    ///
+    /// # Returns
+    /// Metric the index was created (or loaded) with.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Get the storage quantization.
+    ///
+    /// # Returns
+    /// Scalar type vectors are stored as.
+    pub fn quantization(&self) -> Quantization {
+        self.quantization
+    }
+
+    /// Get the HNSW parameters.
+    ///
+    /// # Returns
+    /// Parameters currently in effect (including any `set_ef_search` change).
+    pub fn hnsw_config(&self) -> HnswConfig {
+        self.hnsw
+    }
+
+    /// Change the query-time candidate list size (ef_search).
+    ///
+    /// Takes effect immediately and is persisted on the next `save()`;
+    /// the graph does not need to be rebuilt.
+    ///
+    /// # Arguments
+    /// - `ef`: New ef_search value (higher = better recall, slower queries).
+    pub fn set_ef_search(&mut self, ef: usize) {
+        self.index.change_expansion_search(ef);
+        self.hnsw.expansion_search = ef;
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Check whether the index has unsaved changes.
+    ///
+    /// # Returns
+    /// True if vectors were added or removed (or settings changed) since the
+    /// index was created, loaded, or last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
     /// Save the index to disk.
     ///
     /// Saves both the usearch index and the ID mappings.
@@ -287,12 +717,17 @@ impl VectorIndex {
         let meta = IndexMetadata {
             id_map: self.id_map.clone(),
             key_map: self.key_map.clone(),
+            metadata: self.metadata.clone(),
             next_key: self.next_key.load(Ordering::SeqCst),
             dim: self.dim,
+            metric: self.metric,
+            hnsw: self.hnsw,
+            quantization: self.quantization,
         };
         let meta_path = path.with_extension("json");
         let meta_json = serde_json::to_string_pretty(&meta)?;
         fs::write(&meta_path, meta_json)?;
+        self.dirty.store(false, Ordering::Release);
 
         info!(
             "Saved vector index: {} vectors to {:?}",
@@ -304,6 +739,9 @@ impl VectorIndex {
 
     /// Load an index from disk.
     ///
+    /// The metric is taken from the persisted metadata (indexes saved before
+    /// metrics were recorded are treated as cosine).
+    ///
     /// # Arguments
     /// - `path`: Base path for index artifacts.
     ///
@@ -313,21 +751,42 @@ impl VectorIndex {
     /// # Errors
     /// Returns `VectorError` if loading fails.
     pub fn load(path: &Path) -> Result<Self> {
+        Self::load_inner(path, None)
+    }
+
+    /// Load an index from disk, requiring a specific metric.
+    ///
+    /// # Arguments
+    /// - `path`: Base path for index artifacts.
+    /// - `metric`: Metric the caller expects the index to use.
+    ///
+    /// # Returns
+    /// Loaded vector index with mappings restored.
+    ///
+    /// # Errors
+    /// Returns `VectorError::MetricMismatch` if the persisted metric differs,
+    /// or another `VectorError` if loading fails.
+    pub fn load_with_metric(path: &Path, metric: Metric) -> Result<Self> {
+        Self::load_inner(path, Some(metric))
+    }
+
+    fn load_inner(path: &Path, expected_metric: Option<Metric>) -> Result<Self> {
         // Load metadata first to get dimensions
         let meta_path = path.with_extension("json");
         let meta_json = fs::read_to_string(&meta_path)?;
         let meta: IndexMetadata = serde_json::from_str(&meta_json)?;
 
+        if let Some(expected) = expected_metric {
+            if expected != meta.metric {
+                return Err(VectorError::MetricMismatch {
+                    expected,
+                    found: meta.metric,
+                });
+            }
+        }
+
         // Create index with correct options
-        let options = IndexOptions {
-            dimensions: meta.dim,
-            metric: MetricKind::Cos,
-            quantization: ScalarKind::F32,
-            connectivity: 16,
-            expansion_add: 128,
-            expansion_search: 64,
-            multi: false,
-        };
+        let options = index_options(meta.dim, meta.metric, meta.hnsw, meta.quantization);
 
         let index = Index::new(&options).map_err(|e| VectorError::IndexError(e.to_string()))?;
 
@@ -351,8 +810,13 @@ impl VectorIndex {
             index,
             id_map: meta.id_map,
             key_map: meta.key_map,
+            metadata: meta.metadata,
             next_key: AtomicU64::new(meta.next_key),
             dim: meta.dim,
+            metric: meta.metric,
+            hnsw: meta.hnsw,
+            quantization: meta.quantization,
+            dirty: AtomicBool::new(false),
         })
     }
 
@@ -373,8 +837,34 @@ impl VectorIndex {
 struct IndexMetadata {
     id_map: HashMap<u64, String>,
     key_map: HashMap<String, u64>,
+    #[serde(default)]
+    metadata: HashMap<u64, String>,
     next_key: u64,
     dim: usize,
+    #[serde(default)]
+    metric: Metric,
+    #[serde(default)]
+    hnsw: HnswConfig,
+    #[serde(default)]
+    quantization: Quantization,
+}
+
+/// Build usearch options from our metric, HNSW and quantization settings.
+fn index_options(
+    dim: usize,
+    metric: Metric,
+    hnsw: HnswConfig,
+    quantization: Quantization,
+) -> IndexOptions {
+    IndexOptions {
+        dimensions: dim,
+        metric: metric.to_metric_kind(),
+        quantization: quantization.to_scalar_kind(),
+        connectivity: hnsw.connectivity,   // M parameter for HNSW
+        expansion_add: hnsw.expansion_add, // ef_construction
+        expansion_search: hnsw.expansion_search, // ef_search
+        multi: false,                      // Single vector per key
+    }
 }
 
 #[cfg(test)]
@@ -396,9 +886,15 @@ mod tests {
         let mut index = VectorIndex::new_default().unwrap();
 
         // Add some vectors
-        index.add("doc1", &create_test_embedding(1.0)).unwrap();
-        index.add("doc2", &create_test_embedding(2.0)).unwrap();
-        index.add("doc3", &create_test_embedding(3.0)).unwrap();
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+        index
+            .add("doc2", &create_test_embedding(2.0), None)
+            .unwrap();
+        index
+            .add("doc3", &create_test_embedding(3.0), None)
+            .unwrap();
 
         assert_eq!(index.len(), 3);
 
@@ -407,30 +903,317 @@ mod tests {
         assert_eq!(results.len(), 2);
 
         // First result should have highest similarity
-        assert!(results[0].score >= results[1].score);
+        assert!(results[0].similarity >= results[1].similarity);
+        assert!(results[0].distance <= results[1].distance);
+    }
+
+    #[test]
+    fn test_similarity_bounds() {
+        let mut a = vec![0.0; EMBEDDING_DIM];
+        a[0] = 1.0;
+        let mut b = vec![0.0; EMBEDDING_DIM];
+        b[1] = 1.0;
+
+        let mut index = VectorIndex::new_default().unwrap();
+        index.add("a", &a, None).unwrap();
+        index.add("b", &b, None).unwrap();
+
+        let results = index.search(&a, 2).unwrap();
+        let identical = results.iter().find(|r| r.id == "a").unwrap();
+        let orthogonal = results.iter().find(|r| r.id == "b").unwrap();
+
+        assert!((identical.similarity - 1.0).abs() < 1e-3);
+        assert!(identical.distance.abs() < 1e-3);
+        assert!(orthogonal.similarity.abs() < 1e-3);
+        assert!((orthogonal.distance - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_add_rejects_wrong_dimension() {
+        let mut index = VectorIndex::new_default().unwrap();
+        let err = index.add("doc1", &[1.0, 0.0, 0.0], None).unwrap_err();
+        assert!(matches!(
+            err,
+            VectorError::DimensionMismatch {
+                expected: EMBEDDING_DIM,
+                got: 3
+            }
+        ));
+        assert_eq!(index.len(), 0);
+    }
+
+    #[test]
+    fn test_search_rejects_wrong_dimension() {
+        let mut index = VectorIndex::new_default().unwrap();
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+
+        let err = index.search(&[1.0, 0.0, 0.0], 5).unwrap_err();
+        assert!(matches!(
+            err,
+            VectorError::DimensionMismatch {
+                expected: EMBEDDING_DIM,
+                got: 3
+            }
+        ));
+        let err = index
+            .filtered_search(&[1.0, 0.0, 0.0], 5, |_, _| true)
+            .unwrap_err();
+        assert!(matches!(err, VectorError::DimensionMismatch { got: 3, .. }));
+    }
+
+    #[test]
+    fn test_add_batch_skips_bad_dimensions() {
+        let mut index = VectorIndex::new_default().unwrap();
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+
+        let items = vec![
+            ("doc1".to_string(), create_test_embedding(4.0), None),
+            ("doc2".to_string(), create_test_embedding(2.0), None),
+            ("bad".to_string(), vec![0.5; EMBEDDING_DIM - 1], None),
+            ("doc3".to_string(), create_test_embedding(3.0), None),
+        ];
+
+        let added = index.add_batch(&items).unwrap();
+        assert_eq!(added, 3);
+        assert_eq!(index.len(), 3);
+        assert!(index.contains("doc1"));
+        assert!(!index.contains("bad"));
+    }
+
+    #[test]
+    fn test_hnsw_config_persists() {
+        let dir = std::env::temp_dir().join(format!("diachron-vector-hnsw-{}", std::process::id()));
+        let path = dir.join("exchanges");
+
+        let config = HnswConfig {
+            connectivity: 32,
+            expansion_add: 200,
+            expansion_search: 100,
+        };
+        let mut index = VectorIndex::new(EMBEDDING_DIM, Some(config)).unwrap();
+        assert_eq!(index.hnsw_config(), config);
+
+        index.set_ef_search(256);
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+        index.save(&path).unwrap();
+
+        let loaded = VectorIndex::load(&path).unwrap();
+        assert_eq!(
+            loaded.hnsw_config(),
+            HnswConfig {
+                expansion_search: 256,
+                ..config
+            }
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Deterministic pseudo-random unit vector (xorshift) for size tests.
+    fn random_embedding(seed: u64) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        let embedding: Vec<f32> = (0..EMBEDDING_DIM)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 2000) as f32 / 1000.0 - 1.0
+            })
+            .collect();
+        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        embedding.iter().map(|x| x / norm).collect()
+    }
+
+    #[test]
+    fn test_find_near_duplicates_lists_each_pair_once() {
+        let mut index = VectorIndex::new_default().unwrap();
+        index.add("a", &random_embedding(1), None).unwrap();
+        index.add("b", &random_embedding(1), None).unwrap();
+        index.add("c", &random_embedding(2), None).unwrap();
+        index.add("d", &random_embedding(3), None).unwrap();
+
+        let pairs = index.find_near_duplicates(0.99);
+        assert_eq!(pairs.len(), 1);
+        let (x, y, similarity) = &pairs[0];
+        assert_eq!((x.as_str(), y.as_str()), ("a", "b"));
+        assert!(*similarity > 0.99);
+
+        // Probing only "a" still finds its neighbor
+        assert_eq!(index.find_near_duplicates_sampled(0.99, Some(1)).len(), 1);
+        assert!(index.find_near_duplicates(1.01).is_empty());
+    }
+
+    #[test]
+    fn test_i8_quantized_index_is_smaller() {
+        let dir =
+            std::env::temp_dir().join(format!("diachron-vector-quant-{}", std::process::id()));
+        let items: Vec<VectorEntry> = (0..1000)
+            .map(|i| (format!("doc{}", i), random_embedding(i), None))
+            .collect();
+
+        let mut f32_index = VectorIndex::new_default().unwrap();
+        f32_index.add_batch(&items).unwrap();
+        f32_index.save(&dir.join("f32")).unwrap();
+
+        let mut i8_index = VectorIndex::with_options(
+            EMBEDDING_DIM,
+            Metric::Cosine,
+            HnswConfig::default(),
+            Quantization::I8,
+        )
+        .unwrap();
+        i8_index.add_batch(&items).unwrap();
+        i8_index.save(&dir.join("i8")).unwrap();
+
+        let f32_size = fs::metadata(dir.join("f32.usearch")).unwrap().len();
+        let i8_size = fs::metadata(dir.join("i8.usearch")).unwrap().len();
+        assert!(
+            f32_size >= i8_size * 3,
+            "expected >=3x reduction, got {} -> {} bytes",
+            f32_size,
+            i8_size
+        );
+
+        // Quantization survives a reload and search still finds the exact match
+        let loaded = VectorIndex::load(&dir.join("i8")).unwrap();
+        assert_eq!(loaded.quantization(), Quantization::I8);
+        let results = loaded.search(&items[42].1, 1).unwrap();
+        assert_eq!(results[0].id, "doc42");
+
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
     fn test_remove() {
         let mut index = VectorIndex::new_default().unwrap();
 
-        index.add("doc1", &create_test_embedding(1.0)).unwrap();
-        index.add("doc2", &create_test_embedding(2.0)).unwrap();
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+        index
+            .add("doc2", &create_test_embedding(2.0), None)
+            .unwrap();
 
         assert_eq!(index.len(), 2);
         assert!(index.contains("doc1"));
 
-        index.remove("doc1").unwrap();
+        assert!(index.remove("doc1").unwrap());
 
         assert_eq!(index.len(), 1);
         assert!(!index.contains("doc1"));
         assert!(index.contains("doc2"));
     }
 
+    #[test]
+    fn test_remove_missing_and_persist() {
+        let dir =
+            std::env::temp_dir().join(format!("diachron-vector-remove-{}", std::process::id()));
+        let path = dir.join("events");
+
+        let mut index = VectorIndex::new_default().unwrap();
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+        index
+            .add("doc2", &create_test_embedding(2.0), None)
+            .unwrap();
+
+        assert!(!index.remove("missing").unwrap());
+        assert!(index.remove("doc1").unwrap());
+        assert!(!index.remove("doc1").unwrap());
+        assert!(index.is_dirty());
+        index.save(&path).unwrap();
+        assert!(!index.is_dirty());
+
+        let loaded = VectorIndex::load(&path).unwrap();
+        assert!(!loaded.is_dirty());
+        assert_eq!(loaded.len(), 1);
+        assert!(!loaded.contains("doc1"));
+        assert!(loaded.contains("doc2"));
+
+        let results = loaded.search(&create_test_embedding(1.0), 10).unwrap();
+        assert!(results.iter().all(|r| r.id != "doc1"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_filtered_search_uses_persisted_metadata() {
+        let dir =
+            std::env::temp_dir().join(format!("diachron-vector-filter-{}", std::process::id()));
+        let path = dir.join("exchanges");
+
+        // Every "b" vector is closer to the query than the "a" one
+        let query = random_embedding(1);
+        let mut index = VectorIndex::new_default().unwrap();
+        for i in 0..5 {
+            index
+                .add(&format!("b{}", i), &query, Some("project-b"))
+                .unwrap();
+        }
+        index
+            .add("a", &random_embedding(2), Some("project-a"))
+            .unwrap();
+        index.add("untagged", &query, None).unwrap();
+        index.save(&path).unwrap();
+
+        let mut loaded = VectorIndex::load(&path).unwrap();
+        let results = loaded
+            .filtered_search(&query, 2, |_, metadata| metadata == Some("project-a"))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a");
+
+        // Re-adding without metadata clears it
+        loaded.add("a", &random_embedding(2), None).unwrap();
+        let results = loaded
+            .filtered_search(&query, 2, |_, metadata| metadata.is_some())
+            .unwrap();
+        assert!(results.iter().all(|r| r.id.starts_with('b')));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_empty_search() {
         let index = VectorIndex::new_default().unwrap();
         let results = index.search(&create_test_embedding(1.0), 10).unwrap();
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_metric_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("diachron-vector-metric-{}", std::process::id()));
+        let path = dir.join("l2");
+
+        let mut index = VectorIndex::with_metric(EMBEDDING_DIM, Metric::L2).unwrap();
+        assert_eq!(index.metric(), Metric::L2);
+        index
+            .add("doc1", &create_test_embedding(1.0), None)
+            .unwrap();
+        index.save(&path).unwrap();
+
+        let loaded = VectorIndex::load(&path).unwrap();
+        assert_eq!(loaded.metric(), Metric::L2);
+        assert_eq!(loaded.len(), 1);
+
+        assert!(VectorIndex::load_with_metric(&path, Metric::L2).is_ok());
+        let err = VectorIndex::load_with_metric(&path, Metric::Cosine);
+        assert!(matches!(
+            err,
+            Err(VectorError::MetricMismatch {
+                expected: Metric::Cosine,
+                found: Metric::L2
+            })
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -3,6 +3,8 @@
 //! Runs periodic operations like:
 //! - Indexing new conversations
 //! - Index maintenance
+//! - Saving vector indexes with unsaved changes
+//! - Daily hash-chain checkpoints, signed with the device key
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,12 +12,18 @@ use std::time::Duration;
 use tokio::time::interval;
 use tracing::{debug, info, warn};
 
+use diachron_core::ChainCheckpoint;
+
 use crate::indexer;
+use crate::reindex;
 use crate::DaemonState;
 
 /// Default interval between background index checks (30 minutes)
 const DEFAULT_INDEX_INTERVAL_MINS: u64 = 30;
 
+/// Interval between checks for a due chain checkpoint (1 hour)
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 /// Run the background indexing task
 ///
 /// This task periodically checks for new conversation archives
@@ -58,8 +66,93 @@ pub async fn background_indexing_task(state: Arc<DaemonState>) {
     }
 }
 
+/// Periodically save vector indexes that changed since the last save.
+///
+/// Vectors live only in memory until saved, so without this a daemon that
+/// is killed loses every vector added since startup or the last explicit
+/// save, even though the database rows survive.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `period`: Time between checks.
+pub async fn autosave_task(state: Arc<DaemonState>, period: Duration) {
+    let mut ticker = interval(period);
+    // The first tick completes immediately; nothing has changed yet
+    ticker.tick().await;
+    info!("Index autosave started (every {}s)", period.as_secs());
+
+    loop {
+        ticker.tick().await;
+        if state.should_shutdown() {
+            break;
+        }
+
+        let save_state = Arc::clone(&state);
+        match tokio::task::spawn_blocking(move || save_state.save_indexes()).await {
+            Ok(Ok(saved)) if saved > 0 => debug!("Autosaved {} vector indexes", saved),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Index autosave failed: {}", e),
+            Err(e) => warn!("Index autosave task failed: {}", e),
+        }
+    }
+}
+
+/// Checkpoint the hash chain once a day.
+///
+/// Each checkpoint is signed with `signing.key` in the diachron directory
+/// (written by `diachron keygen`) when it exists, so `diachron verify` can
+/// tell a checkpoint this device made from one forged after the fact. The
+/// key is read on every check, so a key created while the daemon runs is
+/// used from the next checkpoint on.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `period`: Time between checks.
+pub async fn checkpoint_task(state: Arc<DaemonState>, period: Duration) {
+    let mut ticker = interval(period);
+    loop {
+        ticker.tick().await;
+        if state.should_shutdown() {
+            break;
+        }
+
+        let checkpoint_state = Arc::clone(&state);
+        match tokio::task::spawn_blocking(move || checkpoint_today(&checkpoint_state)).await {
+            Ok(Ok(Some(checkpoint))) => info!(
+                "Chain checkpoint #{} covers {} events ({})",
+                checkpoint.id,
+                checkpoint.event_count,
+                if checkpoint.signature.is_some() {
+                    "signed"
+                } else {
+                    "unsigned"
+                }
+            ),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => warn!("Chain checkpoint failed: {}", e),
+            Err(e) => warn!("Chain checkpoint task failed: {}", e),
+        }
+    }
+}
+
+/// Record today's chain checkpoint if one is due.
+///
+/// # Returns
+/// The new checkpoint, or `None` if today has one or nothing changed.
+///
+/// # Errors
+/// Returns an error if the signing key exists but cannot be read (no
+/// unsigned checkpoint is written in its place) or the database fails.
+fn checkpoint_today(state: &DaemonState) -> anyhow::Result<Option<ChainCheckpoint>> {
+    let key_path = state.diachron_home.join("signing.key");
+    let signing_key = diachron_core::load_signing_key(&key_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read signing key {:?}: {}", key_path, e))?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    Ok(state.db.checkpoint_chain(&date, signing_key.as_ref())?)
+}
+
 /// Run incremental indexing (returns count of new exchanges indexed)
-async fn run_incremental_index(state: &DaemonState) -> anyhow::Result<u64> {
+async fn run_incremental_index(state: &Arc<DaemonState>) -> anyhow::Result<u64> {
     // Get Claude archives directory
     let claude_dir = match dirs::home_dir() {
         Some(home) => home.join(".claude"),
@@ -110,7 +203,8 @@ async fn run_incremental_index(state: &DaemonState) -> anyhow::Result<u64> {
             .map(|p| p.last_line.saturating_add(1))
             .unwrap_or(0);
 
-        let exchanges = match indexer::parse_archive(&archive_path, start_line) {
+        let exchanges = match indexer::parse_archive(&archive_path, start_line, &state.config.index)
+        {
             Ok(ex) => ex,
             Err(e) => {
                 warn!("Failed to parse archive {}: {}", path_str, e);
@@ -122,11 +216,10 @@ async fn run_incremental_index(state: &DaemonState) -> anyhow::Result<u64> {
         for exchange in &exchanges {
             // Generate embedding
             let embed_text = indexer::build_exchange_embed_text(exchange);
-            let embedding = if let Ok(mut engine_guard) = state.embedding_engine.write() {
-                engine_guard.as_mut().and_then(|e| e.embed(&embed_text).ok())
-            } else {
-                None
-            };
+            let embedding = state
+                .embedding_engines
+                .get()
+                .and_then(|mut e| e.embed(&embed_text).ok());
 
             // Save to database
             if let Err(e) = state.db.save_exchange(exchange, embedding.as_deref()) {
@@ -136,8 +229,15 @@ async fn run_incremental_index(state: &DaemonState) -> anyhow::Result<u64> {
 
             // Add to vector index
             if let Some(ref emb) = embedding {
-                if let Ok(mut idx) = state.exchanges_index.write() {
-                    let _ = idx.add(&format!("exchange:{}", exchange.id), emb);
+                let added = state.exchanges_index.write().map(|mut idx| {
+                    idx.add(
+                        &format!("exchange:{}", exchange.id),
+                        emb,
+                        exchange.project.as_deref(),
+                    )
+                });
+                if let Ok(Err(e)) = added {
+                    reindex::note_add_error(state, &e);
                 }
             }
 
@@ -165,3 +265,73 @@ async fn run_incremental_index(state: &DaemonState) -> anyhow::Result<u64> {
 
     Ok(total_indexed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::temp_dir;
+    use diachron_core::{CaptureEvent, Operation, VectorIndex, EMBEDDING_DIM};
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_autosave_persists_vectors_without_shutdown() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let mut embedding = vec![0.0; EMBEDDING_DIM];
+        embedding[0] = 1.0;
+        state
+            .events_index
+            .write()
+            .unwrap()
+            .add("event:1", &embedding, None)
+            .unwrap();
+        assert!(state.events_index.read().unwrap().is_dirty());
+
+        tokio::spawn(autosave_task(Arc::clone(&state), Duration::from_millis(20)));
+
+        // No Shutdown message: only the autosave tick writes the index
+        let path = state.indexes_path().join("events");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while state.events_index.read().unwrap().is_dirty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        state.request_shutdown();
+
+        let reloaded = VectorIndex::load(&path).unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert!(reloaded.contains("event:1"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_daily_checkpoint_is_signed_with_the_device_key() {
+        let dir = temp_dir();
+        let state = DaemonState::new_for_tests(dir.join("diachron.db")).unwrap();
+
+        // Nothing to cover yet
+        assert!(checkpoint_today(&state).unwrap().is_none());
+
+        let key = diachron_core::generate_signing_key();
+        diachron_core::save_signing_key(&dir.join("signing.key"), &key).unwrap();
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/a.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        let id = state.db.save_event(&event, None, None, None).unwrap();
+
+        let checkpoint = checkpoint_today(&state).unwrap().unwrap();
+        assert_eq!(checkpoint.last_event_id, Some(id));
+        assert!(diachron_core::verify_checkpoint_signature(
+            &checkpoint,
+            &key.verifying_key()
+        ));
+
+        // One checkpoint a day
+        state.db.save_event(&event, None, None, None).unwrap();
+        assert!(checkpoint_today(&state).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
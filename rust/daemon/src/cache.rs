@@ -1,14 +1,23 @@
 use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 
-use diachron_core::SearchResult;
+use diachron_core::{FileMatch, SearchPreference, SearchResult, StoredEvent};
 
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub struct CacheKey {
     pub query: String,
     pub limit: usize,
+    /// Leading results the cross-encoder re-ordered
+    pub rerank_depth: usize,
     pub source_filter: Option<u8>,
+    /// Window start resolved to an absolute timestamp
     pub since: Option<String>,
+    /// Window end resolved to an absolute timestamp
+    pub until: Option<String>,
     pub project: Option<String>,
+    pub tool: Option<String>,
+    pub prefer: Option<SearchPreference>,
+    /// `Database::search_version` when the results were computed
     pub db_version: String,
 }
 
@@ -18,13 +27,37 @@ pub struct CacheEntry {
     pub embedding_used: bool,
 }
 
-pub struct SearchCache {
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct TimelineKey {
+    /// Window start resolved to an absolute timestamp
+    pub since: Option<String>,
+    pub file_filter: Option<String>,
+    pub file_match: FileMatch,
+    pub project: Option<String>,
+    pub operation: Option<String>,
+    pub category: Option<String>,
+    pub before_id: Option<i64>,
+    pub limit: usize,
+    /// `Database::search_version` when the page was read
+    pub db_version: String,
+}
+
+#[derive(Clone)]
+pub struct TimelineEntry {
+    pub events: Vec<StoredEvent>,
+    pub next_cursor: Option<i64>,
+}
+
+pub type SearchCache = LruCache<CacheKey, CacheEntry>;
+pub type TimelineCache = LruCache<TimelineKey, TimelineEntry>;
+
+pub struct LruCache<K, V> {
     capacity: usize,
-    map: HashMap<CacheKey, CacheEntry>,
-    order: VecDeque<CacheKey>,
+    map: HashMap<K, V>,
+    order: VecDeque<K>,
 }
 
-impl SearchCache {
+impl<K: Clone + Hash + Eq, V: Clone> LruCache<K, V> {
     pub fn new(capacity: usize) -> Self {
         Self {
             capacity: capacity.max(1),
@@ -33,7 +66,7 @@ impl SearchCache {
         }
     }
 
-    pub fn get(&mut self, key: &CacheKey) -> Option<CacheEntry> {
+    pub fn get(&mut self, key: &K) -> Option<V> {
         if let Some(entry) = self.map.get(key).cloned() {
             self.touch(key);
             return Some(entry);
@@ -41,7 +74,12 @@ impl SearchCache {
         None
     }
 
-    pub fn insert(&mut self, key: CacheKey, entry: CacheEntry) {
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order.clear();
+    }
+
+    pub fn insert(&mut self, key: K, entry: V) {
         if self.map.contains_key(&key) {
             self.touch(&key);
             self.map.insert(key, entry);
@@ -60,7 +98,7 @@ impl SearchCache {
         }
     }
 
-    fn touch(&mut self, key: &CacheKey) {
+    fn touch(&mut self, key: &K) {
         if let Some(pos) = self.order.iter().position(|k| k == key) {
             self.order.remove(pos);
         }
@@ -0,0 +1,452 @@
+//! Batched capture persistence
+//!
+//! During a multi-file refactor the hook can fire dozens of captures within a
+//! second. Embedding and writing each one synchronously ties up the embedding
+//! engines, so instead the capture handler enqueues events on a
+//! bounded channel and returns. A writer task drains the channel in batches,
+//! embeds each batch with a single `embed_batch` call, and writes it in one
+//! transaction.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use diachron_core::{compute_fingerprint, CaptureEvent, HunkFingerprint, VectorEntry};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, error, info, warn};
+
+use crate::config::CaptureConfig;
+use crate::db::NewEvent;
+use crate::handlers::{build_event_embed_text, fingerprint_content};
+use crate::reindex;
+use crate::DaemonState;
+
+/// Embeds a batch of texts, returning one vector per text, or `None` if no
+/// embeddings are available.
+pub type BatchEmbedder = fn(&DaemonState, &[&str]) -> Option<Vec<Vec<f32>>>;
+
+/// Work item for the writer task.
+enum Command {
+    Capture(Box<CaptureEvent>),
+    /// Reply once everything enqueued before this command is written
+    Flush(oneshot::Sender<()>),
+}
+
+/// Result of persisting one capture event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// Inserted with this row ID
+    Saved(i64),
+    /// Not written: an edit whose new content is the same as its old
+    /// content (`capture.skip_noop_edits`)
+    SkippedNoop,
+}
+
+/// Handle to the capture writer task.
+#[derive(Default)]
+pub struct CaptureQueue {
+    tx: OnceLock<mpsc::Sender<Command>>,
+}
+
+impl CaptureQueue {
+    /// Enqueue an event for the writer task.
+    ///
+    /// Waits if the queue is full.
+    ///
+    /// # Arguments
+    /// - `event`: Capture event to persist.
+    ///
+    /// # Errors
+    /// Returns the event if no writer is running, so the caller can write
+    /// it inline.
+    pub async fn push(&self, event: CaptureEvent) -> Result<(), CaptureEvent> {
+        let Some(tx) = self.tx.get() else {
+            return Err(event);
+        };
+        tx.send(Command::Capture(Box::new(event))).await.map_err(
+            |mpsc::error::SendError(command)| match command {
+                Command::Capture(event) => *event,
+                Command::Flush(_) => unreachable!("sent a capture"),
+            },
+        )
+    }
+
+    /// Wait until every event enqueued so far has been written.
+    pub async fn flush(&self) {
+        let Some(tx) = self.tx.get() else {
+            return;
+        };
+        let (done_tx, done_rx) = oneshot::channel();
+        if tx.send(Command::Flush(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// Start the writer task that drains the capture queue.
+///
+/// # Arguments
+/// - `state`: Shared daemon state; its `[capture]` config sizes the batches.
+/// - `embed`: Embeds each batch (normally [`embed_with_engine`]).
+pub fn spawn_writer(state: Arc<DaemonState>, embed: BatchEmbedder) {
+    let config = state.config.capture.clone();
+    let (tx, rx) = mpsc::channel(config.queue_capacity.max(1));
+    if state.capture_queue.tx.set(tx).is_err() {
+        warn!("Capture writer already running");
+        return;
+    }
+
+    info!(
+        "Capture writer started (batch size {}, flush interval {}ms)",
+        config.batch_size, config.flush_interval_ms
+    );
+    tokio::spawn(run_writer(state, rx, config, embed));
+}
+
+/// Collect captures into batches and persist them until the queue closes.
+async fn run_writer(
+    state: Arc<DaemonState>,
+    mut rx: mpsc::Receiver<Command>,
+    config: CaptureConfig,
+    embed: BatchEmbedder,
+) {
+    let batch_size = config.batch_size.max(1);
+    let interval = Duration::from_millis(config.flush_interval_ms);
+
+    while let Some(first) = rx.recv().await {
+        let deadline = Instant::now() + interval;
+        let mut batch = Vec::new();
+        let mut flushed = None;
+
+        let mut next = Some(first);
+        while let Some(command) = next.take() {
+            match command {
+                Command::Capture(event) => batch.push(*event),
+                Command::Flush(done) => {
+                    flushed = Some(done);
+                    break;
+                }
+            }
+            if batch.len() >= batch_size {
+                break;
+            }
+            next = timeout_at(deadline, rx.recv()).await.ok().flatten();
+        }
+
+        if !batch.is_empty() {
+            let count = batch.len();
+            let writer_state = Arc::clone(&state);
+            let written =
+                tokio::task::spawn_blocking(move || persist_captures(&writer_state, &batch, embed))
+                    .await;
+            match written {
+                Ok(outcomes) => debug!("Wrote {} of {} queued captures", outcomes.len(), count),
+                Err(e) => error!("Capture writer failed on a batch of {}: {}", count, e),
+            }
+        }
+
+        if let Some(done) = flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Embed, fingerprint, and save a batch of capture events.
+///
+/// The batch is written in one transaction; if that fails, events are
+/// written one at a time so a single bad event cannot drop the rest. With
+/// `capture.skip_noop_edits`, edits that leave their text unchanged are
+/// skipped rather than saved.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `events`: Events to persist, in arrival order.
+/// - `embed`: Embeds the event texts and fingerprinted content.
+///
+/// # Returns
+/// Outcomes of the events that were saved or skipped; events that failed
+/// to write are left out.
+pub fn persist_captures(
+    state: &Arc<DaemonState>,
+    events: &[CaptureEvent],
+    embed: BatchEmbedder,
+) -> Vec<SaveOutcome> {
+    let noop: Vec<bool> = events
+        .iter()
+        .map(|event| state.config.capture.skip_noop_edits && is_noop_edit(state, event))
+        .collect();
+    let kept: Vec<&CaptureEvent> = events
+        .iter()
+        .zip(&noop)
+        .filter(|(_, &noop)| !noop)
+        .map(|(event, _)| event)
+        .collect();
+
+    // One embed call covers both the event texts and any fingerprinted content
+    let embed_texts: Vec<String> = kept.iter().map(|e| build_event_embed_text(e)).collect();
+    let contents: Vec<Option<&str>> = kept.iter().map(|e| fingerprint_content(e)).collect();
+    let mut texts: Vec<&str> = embed_texts.iter().map(String::as_str).collect();
+    texts.extend(contents.iter().flatten());
+
+    let mut embeddings: Vec<Option<Vec<f32>>> = embed(state, &texts)
+        .filter(|embeddings| embeddings.len() == texts.len())
+        .map(|embeddings| embeddings.into_iter().map(Some).collect())
+        .unwrap_or_else(|| vec![None; texts.len()]);
+    let mut content_embeddings = embeddings.split_off(kept.len()).into_iter();
+
+    let fingerprints: Vec<Option<HunkFingerprint>> = contents
+        .iter()
+        .zip(&kept)
+        .map(|(content, event)| {
+            content.map(|content| {
+                let options = state
+                    .config
+                    .fingerprint
+                    .options_for(event.file_path.as_deref());
+                compute_fingerprint(content, None, content_embeddings.next().flatten(), &options)
+            })
+        })
+        .collect();
+
+    let new_events: Vec<NewEvent<'_>> = kept
+        .iter()
+        .zip(&embeddings)
+        .zip(&fingerprints)
+        .map(|((event, embedding), fingerprint)| NewEvent {
+            event,
+            embedding: embedding.as_deref(),
+            fingerprint: fingerprint.as_ref(),
+        })
+        .collect();
+
+    let ids: Vec<Option<i64>> = match state.db.save_events(&new_events) {
+        Ok(ids) => ids.into_iter().map(Some).collect(),
+        Err(e) => {
+            warn!(
+                "Failed to write {} captures in one transaction, retrying individually: {}",
+                new_events.len(),
+                e
+            );
+            new_events
+                .iter()
+                .map(|e| {
+                    state
+                        .db
+                        .save_events(std::slice::from_ref(e))
+                        .map_err(|err| error!("Failed to save event: {}", err))
+                        .ok()?
+                        .pop()
+                })
+                .collect()
+        }
+    };
+
+    let vectors: Vec<VectorEntry> = ids
+        .iter()
+        .zip(embeddings)
+        .zip(&kept)
+        .filter_map(|((id, embedding), event)| {
+            Some((
+                format!("event:{}", (*id)?),
+                embedding?,
+                event.file_path.clone(),
+            ))
+        })
+        .collect();
+    if !vectors.is_empty() {
+        let added = state
+            .events_index
+            .write()
+            .map(|mut idx| idx.add_batch(&vectors));
+        match added {
+            Ok(Ok(added)) if added < vectors.len() => {
+                reindex::note_dimension_mismatches(state, (vectors.len() - added) as u64);
+            }
+            Ok(Err(e)) => warn!("Failed to add captures to vector index: {}", e),
+            _ => {}
+        }
+    }
+
+    let saved: Vec<i64> = ids.iter().flatten().copied().collect();
+    for _ in &saved {
+        state.increment_events();
+    }
+    state.event_feed.publish(&state.db, &saved);
+
+    let mut ids = ids.into_iter();
+    noop.into_iter()
+        .filter_map(|noop| {
+            if noop {
+                debug!("Skipped no-op edit");
+                Some(SaveOutcome::SkippedNoop)
+            } else {
+                ids.next().flatten().map(SaveOutcome::Saved)
+            }
+        })
+        .collect()
+}
+
+/// Whether an edit leaves its text unchanged.
+///
+/// Compares the full old and new content, hashed with the file's
+/// fingerprint options, so only events that carry both sides can be
+/// recognized. `raw_input` is not used: it is truncated and holds only the
+/// new side, so equal `raw_input` says nothing about whether an edit
+/// changed anything.
+fn is_noop_edit(state: &DaemonState, event: &CaptureEvent) -> bool {
+    let (Some(old), Some(new)) = (&event.old_content, &event.new_content) else {
+        return false;
+    };
+    let options = state
+        .config
+        .fingerprint
+        .options_for(event.file_path.as_deref());
+    compute_fingerprint(old, None, None, &options).content_hash
+        == compute_fingerprint(new, None, None, &options).content_hash
+}
+
+/// Embed a batch with the daemon's embedding engine.
+///
+/// # Returns
+/// One embedding per text, or `None` if the engine is not loaded or fails.
+pub fn embed_with_engine(state: &DaemonState, texts: &[&str]) -> Option<Vec<Vec<f32>>> {
+    let mut engine = state.embedding_engines.get()?;
+    match engine.embed_batch(texts) {
+        Ok(embeddings) => Some(embeddings),
+        Err(e) => {
+            warn!("Failed to embed {} capture texts: {}", texts.len(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::temp_dir;
+    use diachron_core::{Operation, EMBEDDING_DIM};
+
+    /// Deterministic stand-in for the ONNX model.
+    fn fake_embed(_state: &DaemonState, texts: &[&str]) -> Option<Vec<Vec<f32>>> {
+        Some(
+            texts
+                .iter()
+                .map(|text| {
+                    let mut v = vec![0.0; EMBEDDING_DIM];
+                    v[text.len() % EMBEDDING_DIM] = 1.0;
+                    v
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rapid_captures_all_persist_with_embeddings() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        spawn_writer(Arc::clone(&state), fake_embed);
+
+        for i in 0..100 {
+            let event = CaptureEvent {
+                tool_name: "Edit".to_string(),
+                file_path: Some(format!("src/file_{}.rs", i)),
+                operation: Operation::Modify,
+                diff_summary: Some("+1 lines".to_string()),
+                raw_input: Some(format!("fn f{}() {{}}", i)),
+                ..Default::default()
+            };
+            let response =
+                crate::handlers::handle_message(diachron_core::IpcMessage::Capture(event), &state)
+                    .await;
+            assert!(matches!(response, diachron_core::IpcResponse::Ok));
+        }
+        state.capture_queue.flush().await;
+
+        assert_eq!(state.db.event_count().unwrap(), 100);
+        assert_eq!(state.events_count(), 100);
+        let (embedded, fingerprinted): (i64, i64) = state
+            .db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT COUNT(embedding), COUNT(semantic_sig) FROM events",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+            })
+            .unwrap();
+        assert_eq!(embedded, 100);
+        assert_eq!(fingerprinted, 100);
+        assert_eq!(state.events_index.read().unwrap().len(), 100);
+        assert!(
+            diachron_core::verify_chain(&state.db.read().unwrap())
+                .unwrap()
+                .valid
+        );
+    }
+
+    #[test]
+    fn test_noop_edits_are_skipped_by_their_own_content() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let edit = |old: Option<&str>, new: &str| CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Modify,
+            raw_input: Some(new.to_string()),
+            metadata: Some(r#"{"session_id":"s1"}"#.to_string()),
+            old_content: old.map(str::to_string),
+            new_content: old.map(|_| new.to_string()),
+            ..Default::default()
+        };
+
+        let outcomes = persist_captures(
+            &state,
+            &[
+                // The same snippet written over two places is two changes
+                edit(Some("fn a() {}"), "fn log() {}"),
+                edit(Some("fn b() {}"), "fn log() {}"),
+                // Without both sides there is nothing to compare
+                edit(None, "fn log() {}"),
+                // Only trailing whitespace differs
+                edit(Some("fn c() {}  "), "fn c() {}"),
+                edit(Some("fn d() {}"), "fn e() {}"),
+            ],
+            fake_embed,
+        );
+        assert!(matches!(outcomes[0], SaveOutcome::Saved(_)));
+        assert!(matches!(outcomes[1], SaveOutcome::Saved(_)));
+        assert!(matches!(outcomes[2], SaveOutcome::Saved(_)));
+        assert_eq!(outcomes[3], SaveOutcome::SkippedNoop);
+        assert!(matches!(outcomes[4], SaveOutcome::Saved(_)));
+
+        assert_eq!(state.db.event_count().unwrap(), 4);
+        assert_eq!(state.events_count(), 4);
+        assert_eq!(state.events_index.read().unwrap().len(), 4);
+        assert!(
+            diachron_core::verify_chain(&state.db.read().unwrap())
+                .unwrap()
+                .valid
+        );
+
+        // Identical MultiEdit hunks each change their own part of the file
+        let hunk = |hunk: usize| CaptureEvent {
+            tool_name: "MultiEdit".to_string(),
+            metadata: Some(format!(r#"{{"session_id":"s1","hunk":{}}}"#, hunk)),
+            ..edit(Some("let x = 1;"), "let x = 2;")
+        };
+        let outcomes = persist_captures(&state, &[hunk(0), hunk(1)], fake_embed);
+        assert!(matches!(
+            outcomes[..],
+            [SaveOutcome::Saved(_), SaveOutcome::Saved(_)]
+        ));
+        assert_eq!(state.db.event_count().unwrap(), 6);
+
+        let mut state = DaemonState::new_for_tests(dir.join("disabled.db")).unwrap();
+        state.config.capture.skip_noop_edits = false;
+        let state = Arc::new(state);
+        let outcomes =
+            persist_captures(&state, &[edit(Some("fn a() {}"), "fn a() {}")], fake_embed);
+        assert!(matches!(outcomes[..], [SaveOutcome::Saved(_)]));
+    }
+}
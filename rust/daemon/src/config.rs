@@ -0,0 +1,508 @@
+//! Daemon configuration loaded from `~/.diachron/config.toml`.
+//!
+//! Every section is optional; missing keys fall back to defaults so an
+//! absent or partial config file never prevents the daemon from starting.
+
+use diachron_core::{ExchangeFtsWeights, FingerprintOptions, Lang, EMBEDDING_DIM};
+use diachron_embeddings::{CrossEncoderConfig, EmbeddingConfig, ModelPaths};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Default `k` constant for Reciprocal Rank Fusion.
+const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Top-level daemon configuration.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DaemonConfig {
+    /// Hybrid search tuning (`[search]` section)
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// Embedding model selection (`[embedding]` section)
+    #[serde(default)]
+    pub embedding: EmbeddingSettings,
+
+    /// Capture batching (`[capture]` section)
+    #[serde(default)]
+    pub capture: CaptureConfig,
+
+    /// Conversation indexing (`[index]` section)
+    #[serde(default)]
+    pub index: IndexConfig,
+
+    /// Prometheus endpoint (`[metrics]` section)
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// Pruning by `diachron maintenance` (`[retention]` section)
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Content normalization before fingerprint hashing (`[fingerprint]`
+    /// section)
+    #[serde(default)]
+    pub fingerprint: FingerprintConfig,
+}
+
+/// Hybrid search configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchConfig {
+    /// RRF smoothing constant; larger values flatten rank differences
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: f32,
+
+    /// Full-text ranking (`[search.fts]` section)
+    #[serde(default)]
+    pub fts: FtsConfig,
+
+    /// Re-order the top `3 * limit` fused results with a cross-encoder
+    #[serde(default)]
+    pub rerank: bool,
+
+    /// Cross-encoders loaded for concurrent searches; each adds roughly the
+    /// model's size (about 90 MB) to the daemon's memory
+    #[serde(default = "default_rerank_pool_size")]
+    pub rerank_pool_size: usize,
+
+    /// Directory containing the cross-encoder's `model.onnx` and
+    /// `tokenizer.json`; the downloaded ms-marco-MiniLM-L-6-v2 is used when
+    /// unset
+    #[serde(default)]
+    pub rerank_model_dir: Option<PathBuf>,
+
+    /// Weight of vector similarity in a blended score; setting either
+    /// weight replaces reciprocal rank fusion with the weighted blend
+    #[serde(default)]
+    pub vector_weight: Option<f32>,
+
+    /// Weight of the normalized full-text score in a blended score
+    #[serde(default)]
+    pub fts_weight: Option<f32>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            rrf_k: default_rrf_k(),
+            fts: FtsConfig::default(),
+            rerank: false,
+            rerank_pool_size: default_rerank_pool_size(),
+            rerank_model_dir: None,
+            vector_weight: None,
+            fts_weight: None,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Build the cross-encoder configuration for the re-ranking model.
+    ///
+    /// # Returns
+    /// Configuration pointing at `rerank_model_dir`, or at the default
+    /// re-ranking model directory when unset.
+    pub fn reranker_config(&self) -> CrossEncoderConfig {
+        let paths = match &self.rerank_model_dir {
+            Some(model_dir) => ModelPaths::in_dir(model_dir.clone()),
+            None => ModelPaths::reranker(),
+        };
+        CrossEncoderConfig::from_paths(&paths)
+    }
+
+    /// Blend weights as `(vector_weight, fts_weight)`.
+    ///
+    /// # Returns
+    /// `None` when neither weight is set (use reciprocal rank fusion). A
+    /// missing weight is one minus the other.
+    pub fn blend_weights(&self) -> Option<(f32, f32)> {
+        match (self.vector_weight, self.fts_weight) {
+            (None, None) => None,
+            (Some(vector), None) => Some((vector, 1.0 - vector)),
+            (None, Some(fts)) => Some((1.0 - fts, fts)),
+            (Some(vector), Some(fts)) => Some((vector, fts)),
+        }
+    }
+}
+
+fn default_rrf_k() -> f32 {
+    DEFAULT_RRF_K
+}
+
+fn default_rerank_pool_size() -> usize {
+    2
+}
+
+/// BM25 column weights for exchange full-text search.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FtsConfig {
+    /// Weight of the user's message
+    pub user_weight: f64,
+    /// Weight of the assistant's reply
+    pub assistant_weight: f64,
+    /// Weight of the exchange summary
+    pub summary_weight: f64,
+}
+
+impl Default for FtsConfig {
+    fn default() -> Self {
+        let weights = ExchangeFtsWeights::default();
+        Self {
+            user_weight: weights.user_message,
+            assistant_weight: weights.assistant_message,
+            summary_weight: weights.summary,
+        }
+    }
+}
+
+impl FtsConfig {
+    /// Column weights to pass to `fts_search_exchanges`.
+    pub fn exchange_weights(&self) -> ExchangeFtsWeights {
+        ExchangeFtsWeights {
+            user_message: self.user_weight,
+            assistant_message: self.assistant_weight,
+            summary: self.summary_weight,
+        }
+    }
+}
+
+/// Embedding model configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingSettings {
+    /// Directory containing `model.onnx` and `tokenizer.json`; the
+    /// downloaded all-MiniLM-L6-v2 model is used when unset
+    pub model_dir: Option<PathBuf>,
+    /// Output dimension of the model
+    pub dim: usize,
+    /// Engines loaded so searches can embed in parallel; each one holds its
+    /// own copy of the model weights (about 90 MB of RSS for the default)
+    pub pool_size: usize,
+    /// Report the daemon ready without a model, serving full-text search
+    /// only
+    pub allow_degraded: bool,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            model_dir: None,
+            dim: EMBEDDING_DIM,
+            pool_size: 2,
+            allow_degraded: false,
+        }
+    }
+}
+
+impl EmbeddingSettings {
+    /// Build the engine configuration for the configured model.
+    ///
+    /// # Returns
+    /// Configuration pointing at `model_dir`, or at the default model
+    /// directory when unset, producing `dim`-sized embeddings.
+    pub fn engine_config(&self) -> EmbeddingConfig {
+        let paths = match &self.model_dir {
+            Some(model_dir) => ModelPaths {
+                model_path: model_dir.join("model.onnx"),
+                tokenizer_path: model_dir.join("tokenizer.json"),
+                model_dir: model_dir.clone(),
+            },
+            None => ModelPaths::default(),
+        };
+        EmbeddingConfig {
+            embedding_dim: self.dim,
+            ..EmbeddingConfig::from_paths(&paths)
+        }
+    }
+}
+
+/// Batching of incoming capture events.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    /// Captures buffered before the hook has to wait for the writer
+    pub queue_capacity: usize,
+    /// Maximum captures embedded and written together
+    pub batch_size: usize,
+    /// Longest a capture waits for its batch to fill, in milliseconds
+    pub flush_interval_ms: u64,
+    /// Drop edits whose new content is the same as their old content
+    pub skip_noop_edits: bool,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1024,
+            batch_size: 32,
+            flush_interval_ms: 50,
+            skip_noop_edits: true,
+        }
+    }
+}
+
+/// Conversation indexing configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Cosine similarity above which a new exchange is treated as a
+    /// near-duplicate of an indexed one and left out of the vector index.
+    /// Unset disables deduplication.
+    pub dedup_threshold: Option<f32>,
+    /// Seconds between saves of vector indexes with unsaved changes;
+    /// 0 disables autosave
+    pub autosave_secs: u64,
+    /// Re-add vectors missing from the indexes and drop entries for
+    /// deleted rows when the daemon starts
+    pub reconcile_on_start: bool,
+    /// Index the assistant's thinking blocks, after its reply text
+    pub include_thinking: bool,
+    /// Index tool results as `[Result: ...]`
+    pub include_tool_results: bool,
+    /// Bytes kept of each tool result before it is cut off with `...`
+    pub tool_result_max_chars: usize,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            dedup_threshold: None,
+            autosave_secs: 60,
+            reconcile_on_start: true,
+            include_thinking: false,
+            include_tool_results: true,
+            tool_result_max_chars: 200,
+        }
+    }
+}
+
+/// Prometheus metrics configuration.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Port for `GET /metrics` on 127.0.0.1. Unset opens no port.
+    pub port: Option<u16>,
+}
+
+/// Default retention for `Maintenance` requests that don't set their own.
+///
+/// Zero keeps that data forever.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Days to keep code events
+    pub events_days: u32,
+    /// Days to keep conversation exchanges
+    pub exchanges_days: u32,
+    /// Days to keep events and exchanges of a project, keyed by project
+    /// name substring (`[retention.projects]`)
+    pub projects: HashMap<String, u32>,
+}
+
+/// Normalization applied to captured and blamed content before hashing.
+///
+/// Captures and blame lookups hash with the same settings, so changing them
+/// only gives exact matches for events captured afterwards; older events
+/// still match by context or semantic similarity. With
+/// `capture.skip_noop_edits`, an edit that only reformats (and carries both
+/// sides) is then skipped as a no-op.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FingerprintConfig {
+    /// Ignore indentation, runs of spaces, and blank lines
+    pub normalize_whitespace: bool,
+    /// Drop line comments, for languages known by file extension
+    pub strip_comments: bool,
+}
+
+impl FingerprintConfig {
+    /// Fingerprint options for content of `file_path`.
+    ///
+    /// # Arguments
+    /// - `file_path`: File the content belongs to, if any; its extension
+    ///   picks the comment syntax.
+    pub fn options_for(&self, file_path: Option<&str>) -> FingerprintOptions {
+        FingerprintOptions {
+            normalize_whitespace: self.normalize_whitespace,
+            strip_comments: self.strip_comments,
+            language: file_path.and_then(Lang::from_path),
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// Load configuration from `config.toml` in the diachron home directory.
+    ///
+    /// # Arguments
+    /// - `diachron_home`: Directory containing `config.toml`.
+    ///
+    /// # Returns
+    /// Parsed configuration, or defaults if the file is missing or invalid.
+    pub fn load(diachron_home: &Path) -> Self {
+        let config_file = diachron_home.join("config.toml");
+        let Ok(content) = std::fs::read_to_string(&config_file) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Invalid {:?}, using defaults: {}", config_file, e);
+                Self::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_when_section_missing() {
+        let config: DaemonConfig = toml::from_str("[summarization]\nenabled = false\n").unwrap();
+        assert_eq!(config.search.rrf_k, DEFAULT_RRF_K);
+    }
+
+    #[test]
+    fn test_search_section_overrides() {
+        let config: DaemonConfig = toml::from_str("[search]\nrrf_k = 10.0\n").unwrap();
+        assert_eq!(config.search.rrf_k, 10.0);
+        assert!(!config.search.rerank);
+        assert_eq!(config.search.rerank_pool_size, 2);
+
+        let config: DaemonConfig =
+            toml::from_str("[search]\nrerank = true\nrerank_model_dir = \"/models/ce\"\n").unwrap();
+        assert!(config.search.rerank);
+        assert_eq!(
+            config.search.reranker_config().model_path,
+            PathBuf::from("/models/ce/model.onnx")
+        );
+    }
+
+    #[test]
+    fn test_blend_weights() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert_eq!(config.search.blend_weights(), None);
+
+        let config: DaemonConfig = toml::from_str(
+            "[search]
+fts_weight = 0.75
+",
+        )
+        .unwrap();
+        assert_eq!(config.search.blend_weights(), Some((0.25, 0.75)));
+    }
+
+    #[test]
+    fn test_embedding_section() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert!(config.embedding.model_dir.is_none());
+        assert_eq!(config.embedding.pool_size, 2);
+        assert_eq!(
+            config.embedding.engine_config().embedding_dim,
+            EMBEDDING_DIM
+        );
+
+        let config: DaemonConfig =
+            toml::from_str("[embedding]\nmodel_dir = \"/models/bge\"\ndim = 768\n").unwrap();
+        let engine = config.embedding.engine_config();
+        assert_eq!(engine.embedding_dim, 768);
+        assert_eq!(engine.model_path, PathBuf::from("/models/bge/model.onnx"));
+        assert_eq!(
+            engine.tokenizer_path,
+            PathBuf::from("/models/bge/tokenizer.json")
+        );
+
+        let config: DaemonConfig = toml::from_str("[embedding]\npool_size = 4\n").unwrap();
+        assert_eq!(config.embedding.pool_size, 4);
+        assert_eq!(config.embedding.dim, EMBEDDING_DIM);
+    }
+
+    #[test]
+    fn test_default_dim_matches_default_model_output() {
+        // Only measurable where the model has been downloaded
+        if !ModelPaths::default().exists() {
+            return;
+        }
+        let config = EmbeddingSettings::default().engine_config();
+        let mut engine = diachron_embeddings::EmbeddingEngine::new(config).unwrap();
+        let embedding = engine.embed("fix the login redirect").unwrap();
+        assert_eq!(embedding.len(), EMBEDDING_DIM);
+    }
+
+    #[test]
+    fn test_capture_section_partial_override() {
+        let config: DaemonConfig = toml::from_str("[capture]\nbatch_size = 8\n").unwrap();
+        assert_eq!(config.capture.batch_size, 8);
+        assert_eq!(
+            config.capture.flush_interval_ms,
+            CaptureConfig::default().flush_interval_ms
+        );
+        assert!(config.capture.skip_noop_edits);
+
+        let config: DaemonConfig = toml::from_str("[capture]\nskip_noop_edits = false\n").unwrap();
+        assert!(!config.capture.skip_noop_edits);
+    }
+
+    #[test]
+    fn test_index_dedup_threshold() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert!(config.index.dedup_threshold.is_none());
+
+        let config: DaemonConfig = toml::from_str("[index]\ndedup_threshold = 0.98\n").unwrap();
+        assert_eq!(config.index.dedup_threshold, Some(0.98));
+        assert_eq!(config.index.autosave_secs, 60);
+        assert!(config.index.reconcile_on_start);
+    }
+
+    #[test]
+    fn test_retention_section() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert_eq!(config.retention.events_days, 0);
+        assert!(config.retention.projects.is_empty());
+
+        let config: DaemonConfig = toml::from_str(
+            "[retention]
+exchanges_days = 30
+
+[retention.projects]
+diachron = 365
+",
+        )
+        .unwrap();
+        assert_eq!(config.retention.events_days, 0);
+        assert_eq!(config.retention.exchanges_days, 30);
+        assert_eq!(config.retention.projects.get("diachron"), Some(&365));
+    }
+
+    #[test]
+    fn test_fingerprint_section() {
+        let config: DaemonConfig = toml::from_str("").unwrap();
+        assert_eq!(
+            config.fingerprint.options_for(Some("src/lib.rs")),
+            FingerprintOptions {
+                language: Some(Lang::Rust),
+                ..Default::default()
+            }
+        );
+
+        let config: DaemonConfig =
+            toml::from_str("[fingerprint]\nnormalize_whitespace = true\n").unwrap();
+        let options = config.fingerprint.options_for(None);
+        assert!(options.normalize_whitespace && !options.strip_comments);
+        assert_eq!(options.language, None);
+    }
+
+    #[test]
+    fn test_fts_weights_override() {
+        let config: DaemonConfig = toml::from_str("[search.fts]\nuser_weight = 5.0\n").unwrap();
+        let weights = config.search.fts.exchange_weights();
+        assert_eq!(weights.user_message, 5.0);
+        assert_eq!(
+            weights.assistant_message,
+            ExchangeFtsWeights::default().assistant_message
+        );
+    }
+}
@@ -2,33 +2,233 @@
 //!
 //! Handles event storage and queries for the unified database.
 //!
-//! Uses a mutex-wrapped connection for thread-safe access in async context.
+//! Writes go through a single mutex-held connection, which keeps the hash
+//! chain serialized. Reads take a connection from a small pool of read-only
+//! connections; with the database in WAL mode they run alongside the writer
+//! and each other.
 
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OpenFlags};
+use rusqlite::{
+    params, params_from_iter, Connection, OpenFlags, OptionalExtension, Transaction,
+    TransactionBehavior,
+};
 use tracing::debug;
 
+use diachron_core::fingerprint::HunkFingerprint;
+use diachron_core::time;
 use diachron_core::{
-    compute_event_hash, get_last_event_hash, CaptureEvent, EventHashInput, Exchange, StoredEvent,
-    GENESIS_HASH,
+    compute_event_hash, compute_field_hashes, create_checkpoint, get_last_event_hash,
+    project_from_metadata, Bucket, CaptureEvent, ChainCheckpoint, EventDetail, EventHashInput,
+    Exchange, ExportedEvent, ImportCounts, SessionSummary, SigningKey, StatBucket, StoredEvent,
+    TableIntegrity, VectorEntry, BUSY_TIMEOUT, GENESIS_HASH,
 };
 
+use crate::path_filter::PathFilter;
+
+/// Idle read connections kept open for reuse.
+const MAX_IDLE_READERS: usize = 4;
+
+/// Numbers the in-memory databases opened by this process, see
+/// [`connection_uri`].
+static MEMORY_DATABASES: AtomicU64 = AtomicU64::new(0);
+
 /// Database handle for the daemon.
 ///
-/// The connection is wrapped in a `Mutex` because `rusqlite::Connection`
-/// is not `Send`/`Sync`, but we share it across async tasks.
+/// Connections are wrapped in a `Mutex` because `rusqlite::Connection`
+/// is not `Sync`, but we share them across async tasks.
 pub struct Database {
     /// Path to the database file
     path: PathBuf,
-    /// Thread-safe connection wrapper (pub for handler access)
-    pub conn: Mutex<Connection>,
+    /// Name every connection opens, see [`connection_uri`]
+    uri: String,
+    /// The only connection that writes, see [`Self::write`]
+    writer: Mutex<Connection>,
+    /// Idle read-only connections, see [`Self::read`]
+    readers: Mutex<Vec<Connection>>,
     /// Read-only connection for data version tracking
     version_conn: Mutex<Connection>,
 }
 
+/// A read connection borrowed from [`Database::read`]; goes back to the
+/// pool when dropped.
+///
+/// It never holds the writer, so a read made while [`Database::write`] is
+/// locked on the same thread cannot deadlock.
+pub struct ReadConn<'a> {
+    conn: Option<Connection>,
+    pool: &'a Mutex<Vec<Connection>>,
+}
+
+impl Deref for ReadConn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken")
+    }
+}
+
+impl Drop for ReadConn<'_> {
+    fn drop(&mut self) {
+        let mut idle = self.pool.lock().unwrap();
+        if idle.len() < MAX_IDLE_READERS {
+            idle.extend(self.conn.take());
+        }
+    }
+}
+
+/// A capture event ready to be written, with its embedding and fingerprint.
+pub struct NewEvent<'a> {
+    pub event: &'a CaptureEvent,
+    pub embedding: Option<&'a [f32]>,
+    pub fingerprint: Option<&'a HunkFingerprint>,
+}
+
+/// Filters for [`Database::query_events`]; unset fields match everything.
+///
+/// # Fields
+/// - `since`: Time filter (relative or ISO).
+/// - `file`: File path filter (substring, glob, or regex).
+/// - `project`: Project name substring (case-insensitive). Events without a
+///   recorded project only match when this is `None`.
+/// - `operation`: Exact operation (e.g. `modify`, `execute`).
+/// - `category`: Bash command category (e.g. `test`, `build`), matched
+///   against `command_category` in the event metadata.
+/// - `before_id`: Paging cursor: only events after this one in timeline
+///   order (older, or as old with a lower ID).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventFilter<'a> {
+    pub since: Option<&'a str>,
+    pub file: Option<&'a PathFilter>,
+    pub project: Option<&'a str>,
+    pub operation: Option<&'a str>,
+    pub category: Option<&'a str>,
+    pub before_id: Option<i64>,
+}
+
+/// Filters for [`Database::forget_exchanges`]; unset fields match everything.
+///
+/// # Fields
+/// - `project`: Project name substring (case-insensitive).
+/// - `session`: Exact session ID.
+/// - `before`: Only exchanges with an earlier timestamp (already resolved,
+///   in the exchanges' UTC format).
+/// - `query`: FTS5 query over the indexed exchange text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExchangeFilter<'a> {
+    pub project: Option<&'a str>,
+    pub session: Option<&'a str>,
+    pub before: Option<&'a str>,
+    pub query: Option<&'a str>,
+}
+
+/// How long [`Database::prune_old_events`] and
+/// [`Database::prune_old_exchanges`] keep rows.
+///
+/// # Fields
+/// - `days`: Days to keep rows (0 = forever).
+/// - `projects`: Days to keep rows of a project (0 = forever), keyed by
+///   project name substring (case-insensitive). When several match, the
+///   longest retention wins; rows without a project use `days`.
+#[derive(Debug, Clone, Copy)]
+pub struct Retention<'a> {
+    pub days: u32,
+    pub projects: &'a HashMap<String, u32>,
+}
+
+impl Retention<'_> {
+    /// Days to keep a row of `project` (0 = forever).
+    fn days_for(&self, project: Option<&str>) -> u32 {
+        // Orders retentions by length, with forever the longest
+        let span = |days: u32| if days == 0 { u32::MAX } else { days };
+        let Some(project) = project.map(str::to_lowercase) else {
+            return self.days;
+        };
+        self.projects
+            .iter()
+            .filter(|(name, _)| project.contains(&name.to_lowercase()))
+            .map(|(_, &days)| days)
+            .max_by_key(|&days| span(days))
+            .unwrap_or(self.days)
+    }
+}
+
+/// An exchange's ID, user message, assistant message and project.
+pub type ExchangeMessages = (String, String, String, Option<String>);
+
+/// A table whose rows are mirrored in a vector index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexedTable {
+    Events,
+    Exchanges,
+}
+
+impl IndexedTable {
+    fn table(self) -> &'static str {
+        match self {
+            IndexedTable::Events => "events",
+            IndexedTable::Exchanges => "exchanges",
+        }
+    }
+
+    /// Column stored as vector index metadata: the file path of events and
+    /// the project of exchanges, the fields search's project filter matches.
+    fn metadata_column(self) -> &'static str {
+        match self {
+            IndexedTable::Events => "file_path",
+            IndexedTable::Exchanges => "project",
+        }
+    }
+
+    /// Prefix of this table's IDs in the vector index (`event:`, `exchange:`).
+    pub fn key_prefix(self) -> &'static str {
+        match self {
+            IndexedTable::Events => "event:",
+            IndexedTable::Exchanges => "exchange:",
+        }
+    }
+}
+
+/// Name the connections of a database at `path` open: the path itself, or
+/// a URI for an in-memory database.
+///
+/// A plain `:memory:` database is private to one connection, so the read
+/// and version connections would each see an empty database of their own.
+/// In-memory databases instead get a uniquely named shared-cache database
+/// that lives as long as the writer.
+fn connection_uri(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.is_empty() || path == ":memory:" {
+        let n = MEMORY_DATABASES.fetch_add(1, Ordering::Relaxed);
+        format!(
+            "file:diachron-{}-{}?mode=memory&cache=shared",
+            std::process::id(),
+            n
+        )
+    } else {
+        path.into_owned()
+    }
+}
+
+/// Open a read-only connection that waits out the writer's locks.
+fn open_read_only(uri: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(
+        uri,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // A shared-cache (in-memory) database locks whole tables instead of
+    // using WAL snapshots; without this a read during a write transaction
+    // fails with SQLITE_LOCKED. File databases ignore it.
+    conn.pragma_update(None, "read_uncommitted", true)?;
+    Ok(conn)
+}
+
 impl Database {
     /// Open or create a database at the given path.
     ///
@@ -44,25 +244,63 @@ impl Database {
         }
 
         // Open connection and initialize schema
-        let conn = Connection::open(&path).context("Failed to open database")?;
-        diachron_core::schema::init_schema(&conn).context("Failed to initialize schema")?;
-
-        let version_conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
-            .context("Failed to open version connection")?;
+        let uri = connection_uri(&path);
+        let conn = Connection::open(&uri).context("Failed to open database")?;
+        // Wait out other processes' locks (the CLI's connections) instead
+        // of failing with "database is locked"
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .context("Failed to set busy timeout")?;
+        // WAL lets the read connections run while the writer commits
+        // (in-memory databases stay in `memory` mode); NORMAL sync survives
+        // a daemon crash, only an OS crash can lose the last commits
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .context("Failed to enable WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .context("Failed to set synchronous mode")?;
+        let version =
+            diachron_core::schema::migrate(&conn).context("Failed to initialize schema")?;
+        debug!("Database schema at version {}", version);
+
+        let version_conn = open_read_only(&uri).context("Failed to open version connection")?;
 
         Ok(Self {
             path,
-            conn: Mutex::new(conn),
+            uri,
+            writer: Mutex::new(conn),
+            readers: Mutex::new(Vec::new()),
             version_conn: Mutex::new(version_conn),
         })
     }
 
-    /// Access the connection via a mutex lock.
+    /// Lock the write connection.
+    ///
+    /// Every insert, update and delete goes through this one connection,
+    /// so hash-chain writes never interleave.
+    pub fn write(&self) -> MutexGuard<'_, Connection> {
+        self.writer.lock().unwrap()
+    }
+
+    /// Borrow a read-only connection from the pool, opening one if none
+    /// is idle.
     ///
-    /// Use this for FTS queries that need direct connection access.
+    /// # Errors
+    /// Returns `rusqlite::Error` if a new connection cannot be opened.
+    pub fn read(&self) -> rusqlite::Result<ReadConn<'_>> {
+        let idle = self.readers.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open_read_only(&self.uri)?,
+        };
+        Ok(ReadConn {
+            conn: Some(conn),
+            pool: &self.readers,
+        })
+    }
+
+    /// Access the write connection via a mutex lock.
     ///
     /// # Arguments
-    /// - `f`: Callback executed with a locked connection.
+    /// - `f`: Callback executed with the locked write connection.
     ///
     /// # Errors
     /// Returns any `rusqlite::Error` from the callback.
@@ -70,24 +308,67 @@ impl Database {
     where
         F: FnOnce(&Connection) -> Result<R, rusqlite::Error>,
     {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write();
         f(&conn)
     }
 
     /// Return the current data version for cache invalidation.
     ///
-    /// Uses max row identifiers so writes on this connection are reflected.
+    /// Reads `PRAGMA data_version` on a connection that never writes; SQLite
+    /// bumps it there whenever another connection (the daemon's writer or
+    /// another process) commits a change.
+    ///
+    /// # Errors
+    /// Returns any `rusqlite::Error` from the pragma; callers should then
+    /// skip caching rather than guess a version.
     pub fn search_version(&self) -> Result<String, rusqlite::Error> {
         let conn = self.version_conn.lock().unwrap();
         let version: i64 = conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
         Ok(version.to_string())
     }
 
-    /// Open a new read-only connection for parallel queries.
+    /// Record a checkpoint of the hash chain for `date`.
     ///
-    /// This avoids blocking the primary mutex-held connection during FTS.
-    pub fn open_readonly(&self) -> Result<Connection, rusqlite::Error> {
-        Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+    /// Holds the writer throughout, so the checkpoint covers exactly the
+    /// events committed before it.
+    ///
+    /// # Arguments
+    /// - `date`: Local date of the checkpoint (`YYYY-MM-DD`).
+    /// - `signing_key`: Device key to sign the checkpoint with, if any.
+    ///
+    /// # Returns
+    /// The new checkpoint, or `None` if `date` already has one or no
+    /// chained event was written since the last checkpoint.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the chain cannot be read or the
+    /// checkpoint cannot be stored.
+    pub fn checkpoint_chain(
+        &self,
+        date: &str,
+        signing_key: Option<&SigningKey>,
+    ) -> rusqlite::Result<Option<ChainCheckpoint>> {
+        let conn = self.write();
+        let latest: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT date, COALESCE(last_event_id, 0) FROM chain_checkpoints
+                 ORDER BY id DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let newest_event: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM events WHERE event_hash IS NOT NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        let covered = latest
+            .as_ref()
+            .map_or(0, |(_, last_event_id)| *last_event_id);
+        if latest.is_some_and(|(latest_date, _)| latest_date == date) || newest_event <= covered {
+            return Ok(None);
+        }
+        create_checkpoint(&conn, date, signing_key).map(Some)
     }
 
     /// Save a capture event to the database with hash-chain integrity.
@@ -99,6 +380,7 @@ impl Database {
     /// - `event`: Capture event data.
     /// - `session_id`: Optional session identifier.
     /// - `embedding`: Optional embedding vector (stored as f32 blob).
+    /// - `fingerprint`: Optional fingerprint of the written content (for blame).
     ///
     /// # Returns
     /// Inserted row ID.
@@ -110,106 +392,51 @@ impl Database {
         event: &CaptureEvent,
         session_id: Option<&str>,
         embedding: Option<&[f32]>,
+        fingerprint: Option<&HunkFingerprint>,
     ) -> rusqlite::Result<i64> {
-        let timestamp = chrono::Local::now();
-        let timestamp_iso = timestamp.format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
-
-        // Use actual system timezone (e.g., PST, EST, UTC, etc.)
-        let tz_name = timestamp.format("%Z").to_string();
-        let timestamp_display = timestamp
-            .format(&format!("%m/%d/%Y %I:%M %p {}", tz_name))
-            .to_string();
-
-        // Build metadata JSON - preserve metadata from event (includes git_branch)
-        // and merge with any additional fields
-        let metadata = if let Some(ref existing_meta) = event.metadata {
-            // Try to parse and merge with existing metadata
-            if let Ok(mut meta) = serde_json::from_str::<serde_json::Value>(existing_meta) {
-                // Add command_category if not already present
-                if let Some(category) = event.command_category.as_ref() {
-                    if meta.get("command_category").is_none() {
-                        meta["command_category"] = serde_json::json!(category.as_str());
-                    }
-                }
-                meta
-            } else {
-                // Couldn't parse, create fresh metadata
-                serde_json::json!({
-                    "command_category": event.command_category.as_ref().map(|c| c.as_str()),
-                })
-            }
-        } else {
-            serde_json::json!({
-                "command_category": event.command_category.as_ref().map(|c| c.as_str()),
-            })
-        };
-
-        // Convert embedding to blob if present
-        let embedding_blob: Option<Vec<u8>> =
-            embedding.map(|emb| emb.iter().flat_map(|f| f.to_le_bytes()).collect());
-
-        let conn = self.conn.lock().unwrap();
-
-        // Get the previous event's hash for chain linkage
-        let prev_hash = get_last_event_hash(&conn).unwrap_or(GENESIS_HASH);
-
-        // Determine the next event ID (needed for hash computation)
-        let next_id: i64 = conn
-            .query_row(
-                "SELECT COALESCE(MAX(id), 0) + 1 FROM events",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(1);
-
-        // Build hash input with all event data
-        let hash_input = EventHashInput {
-            id: next_id,
-            timestamp: timestamp_iso.clone(),
-            tool_name: event.tool_name.clone(),
-            file_path: event.file_path.clone(),
-            operation: event.operation.as_str().to_string(),
-            diff_summary: event.diff_summary.clone(),
-            raw_input: event.raw_input.clone(),
-            session_id: session_id.map(|s| s.to_string()),
-            git_commit_sha: event.git_commit_sha.clone(),
-            metadata: Some(metadata.to_string()),
-        };
-
-        // Compute event hash
-        let event_hash = compute_event_hash(&hash_input, &prev_hash);
-
-        conn.execute(
-            "INSERT INTO events (
-                timestamp, timestamp_display, session_id, tool_name, file_path,
-                operation, diff_summary, raw_input, git_commit_sha, metadata, embedding,
-                prev_hash, event_hash
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            params![
-                timestamp_iso,
-                timestamp_display,
-                session_id,
-                event.tool_name,
-                event.file_path,
-                event.operation.as_str(),
-                event.diff_summary,
-                event.raw_input,
-                event.git_commit_sha,
-                metadata.to_string(),
-                embedding_blob,
-                prev_hash.as_slice(),
-                event_hash.as_slice(),
-            ],
-        )?;
+        let conn = self.write();
+        // Lock up front: a deferred transaction reads the chain head before
+        // writing, and that upgrade fails at once if another process is
+        // writing rather than waiting for it
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let id = insert_event(&tx, event, session_id, embedding, fingerprint)?;
+        tx.commit()?;
+        Ok(id)
+    }
 
-        Ok(conn.last_insert_rowid())
+    /// Save a batch of capture events in a single transaction.
+    ///
+    /// Events are chained in slice order, exactly as if each had been
+    /// passed to `save_event` in turn.
+    ///
+    /// # Arguments
+    /// - `events`: Events with their optional embedding and fingerprint.
+    ///
+    /// # Returns
+    /// Inserted row IDs, in the same order as `events`.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if any insert fails; the batch is rolled back.
+    pub fn save_events(&self, events: &[NewEvent<'_>]) -> rusqlite::Result<Vec<i64>> {
+        let conn = self.write();
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let ids = events
+            .iter()
+            .map(|e| insert_event(&tx, e.event, None, e.embedding, e.fingerprint))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(ids)
     }
 
     /// Query events with optional filters.
     ///
+    /// Events are ordered newest first, with ties on the timestamp broken
+    /// by ID, so a `before_id` cursor resumes exactly where a previous page
+    /// ended.
+    ///
     /// # Arguments
-    /// - `since`: Optional time filter (relative or ISO).
-    /// - `file_filter`: Optional file path substring.
+    /// - `filter`: Time, path, project, operation, category, and cursor
+    ///   filters.
     /// - `limit`: Maximum number of events to return.
     ///
     /// # Returns
@@ -219,21 +446,21 @@ impl Database {
     /// Returns `rusqlite::Error` if the query fails.
     pub fn query_events(
         &self,
-        since: Option<&str>,
-        file_filter: Option<&str>,
+        filter: &EventFilter<'_>,
         limit: usize,
     ) -> rusqlite::Result<Vec<StoredEvent>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.read()?;
 
         // Build query dynamically
         let mut sql = String::from(
             "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
-                    operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata
+                    operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                    json_extract(metadata, '$.command_category')
              FROM events WHERE 1=1",
         );
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
-        if let Some(since) = since {
+        if let Some(since) = filter.since {
             // Parse relative time or ISO date
             if let Some(timestamp) = parse_time_filter(since) {
                 sql.push_str(" AND timestamp >= ?");
@@ -241,13 +468,59 @@ impl Database {
             }
         }
 
-        if let Some(file) = file_filter {
-            sql.push_str(" AND file_path LIKE ?");
-            params.push(Box::new(format!("%{}%", file)));
+        if let Some(file) = filter.file {
+            let (condition, pattern) = file.sql_condition();
+            sql.push_str(" AND ");
+            sql.push_str(condition);
+            params.push(Box::new(pattern));
         }
+        // Globs and regexes are finished in Rust, so the limit applies after
+        let post_filter = filter.file.filter(|f| f.needs_post_filter());
 
-        sql.push_str(" ORDER BY timestamp DESC LIMIT ?");
-        params.push(Box::new(limit as i64));
+        if let Some(project) = filter.project {
+            sql.push_str(" AND project LIKE ?");
+            params.push(Box::new(format!("%{}%", project)));
+        }
+
+        if let Some(operation) = filter.operation {
+            sql.push_str(" AND operation = ?");
+            params.push(Box::new(operation.to_string()));
+        }
+
+        if let Some(category) = filter.category {
+            // Same expression as idx_events_command_category
+            sql.push_str(" AND json_extract(metadata, '$.command_category') = ?");
+            params.push(Box::new(category.to_string()));
+        }
+
+        if let Some(before_id) = filter.before_id {
+            let cursor: Option<String> = conn
+                .query_row(
+                    "SELECT timestamp FROM events WHERE id = ?1",
+                    [before_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match cursor {
+                Some(timestamp) => {
+                    sql.push_str(" AND (timestamp < ? OR (timestamp = ? AND id < ?))");
+                    params.push(Box::new(timestamp.clone()));
+                    params.push(Box::new(timestamp));
+                    params.push(Box::new(before_id));
+                }
+                // The cursor event is gone (pruned); IDs follow insertion order
+                None => {
+                    sql.push_str(" AND id < ?");
+                    params.push(Box::new(before_id));
+                }
+            }
+        }
+
+        sql.push_str(" ORDER BY timestamp DESC, id DESC");
+        if post_filter.is_none() {
+            sql.push_str(" LIMIT ?");
+            params.push(Box::new(limit as i64));
+        }
 
         debug!("Query: {} with {} params", sql, params.len());
 
@@ -271,14 +544,229 @@ impl Database {
                     ai_summary: row.get(9)?,
                     git_commit_sha: row.get(10)?,
                     metadata: row.get(11)?,
+                    command_category: row.get(12)?,
                 })
             })?
             .filter_map(|r| r.ok())
+            .filter(|event| post_filter.is_none_or(|f| f.matches(event)))
+            .take(limit)
             .collect();
 
         Ok(events)
     }
 
+    /// Query the events that went into a git commit.
+    ///
+    /// Matches the commit event(s) recorded with this SHA, plus every event
+    /// in the same session after the previous commit and up to this one.
+    ///
+    /// # Arguments
+    /// - `sha`: Full or abbreviated commit SHA (prefix match, case-insensitive).
+    ///
+    /// # Returns
+    /// Events ordered by timestamp (ascending); empty if no event recorded
+    /// the commit.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn query_events_by_commit(&self, sha: &str) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.read()?;
+        let sha_pattern = format!("{}%", sha.to_lowercase());
+
+        // The earliest event recording the commit anchors the window
+        let commit: Option<(String, Option<String>)> = conn
+            .query_row(
+                "SELECT timestamp, session_id FROM events
+                 WHERE LOWER(git_commit_sha) LIKE ?1
+                 ORDER BY timestamp ASC LIMIT 1",
+                params![sha_pattern],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((commit_ts, session_id)) = commit else {
+            return Ok(Vec::new());
+        };
+
+        let previous_commit_ts: Option<String> = conn.query_row(
+            "SELECT MAX(timestamp) FROM events
+             WHERE git_commit_sha IS NOT NULL
+               AND LOWER(git_commit_sha) NOT LIKE ?1
+               AND session_id IS ?2
+               AND timestamp < ?3",
+            params![sha_pattern, session_id, commit_ts],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
+                    operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                    json_extract(metadata, '$.command_category')
+             FROM events
+             WHERE LOWER(git_commit_sha) LIKE ?1
+                OR (session_id IS ?2
+                    AND timestamp <= ?3
+                    AND (?4 IS NULL OR timestamp > ?4))
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+
+        let events = stmt
+            .query_map(
+                params![sha_pattern, session_id, commit_ts, previous_commit_ts],
+                |row| {
+                    Ok(StoredEvent {
+                        id: row.get(0)?,
+                        timestamp: row.get(1)?,
+                        timestamp_display: row.get(2)?,
+                        session_id: row.get(3)?,
+                        tool_name: row.get(4)?,
+                        file_path: row.get(5)?,
+                        operation: row.get(6)?,
+                        diff_summary: row.get(7)?,
+                        raw_input: row.get(8)?,
+                        ai_summary: row.get(9)?,
+                        git_commit_sha: row.get(10)?,
+                        metadata: row.get(11)?,
+                        command_category: row.get(12)?,
+                    })
+                },
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Walk an event's parent chain back to the first edit of its file in
+    /// the session.
+    ///
+    /// # Arguments
+    /// - `id`: Event ID to start from.
+    ///
+    /// # Returns
+    /// The chain ordered oldest first and ending with `id`; empty if no
+    /// event has the ID.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn query_event_lineage(&self, id: i64) -> rusqlite::Result<Vec<StoredEvent>> {
+        let conn = self.read()?;
+        // Parents always have lower IDs, so the walk cannot loop
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE lineage(id, parent) AS (
+                 SELECT id, parent_event_id FROM events WHERE id = ?1
+                 UNION ALL
+                 SELECT e.id, e.parent_event_id
+                 FROM events e JOIN lineage l ON e.id = l.parent
+                 WHERE e.id < l.id
+             )
+             SELECT e.id, e.timestamp, e.timestamp_display, e.session_id, e.tool_name,
+                    e.file_path, e.operation, e.diff_summary, e.raw_input, e.ai_summary,
+                    e.git_commit_sha, e.metadata, json_extract(e.metadata, '$.command_category')
+             FROM events e JOIN lineage l ON e.id = l.id
+             ORDER BY e.id ASC",
+        )?;
+
+        let events = stmt
+            .query_map(params![id], |row| {
+                Ok(StoredEvent {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    timestamp_display: row.get(2)?,
+                    session_id: row.get(3)?,
+                    tool_name: row.get(4)?,
+                    file_path: row.get(5)?,
+                    operation: row.get(6)?,
+                    diff_summary: row.get(7)?,
+                    raw_input: row.get(8)?,
+                    ai_summary: row.get(9)?,
+                    git_commit_sha: row.get(10)?,
+                    metadata: row.get(11)?,
+                    command_category: row.get(12)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(events)
+    }
+
+    /// Get one event with its stored fingerprint and edit content.
+    ///
+    /// # Arguments
+    /// - `id`: Event ID.
+    ///
+    /// # Returns
+    /// The event and its fingerprint hashes, or `None` if no event has the ID.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn get_event(&self, id: i64) -> rusqlite::Result<Option<EventDetail>> {
+        let conn = self.read()?;
+        let to_hex = |blob: Option<Vec<u8>>| {
+            blob.filter(|b| !b.is_empty()).map(|b| {
+                b.iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            })
+        };
+
+        conn.query_row(
+            "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
+                    operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                    content_hash, context_hash, semantic_sig, old_content, new_content,
+                    json_extract(metadata, '$.command_category')
+             FROM events
+             WHERE id = ?1",
+            params![id],
+            |row| {
+                let semantic_sig: Option<Vec<u8>> = row.get(14)?;
+                Ok(EventDetail {
+                    event: StoredEvent {
+                        id: row.get(0)?,
+                        timestamp: row.get(1)?,
+                        timestamp_display: row.get(2)?,
+                        session_id: row.get(3)?,
+                        tool_name: row.get(4)?,
+                        file_path: row.get(5)?,
+                        operation: row.get(6)?,
+                        diff_summary: row.get(7)?,
+                        raw_input: row.get(8)?,
+                        ai_summary: row.get(9)?,
+                        git_commit_sha: row.get(10)?,
+                        metadata: row.get(11)?,
+                        command_category: row.get(17)?,
+                    },
+                    content_hash: to_hex(row.get(12)?),
+                    context_hash: to_hex(row.get(13)?),
+                    semantic_sig_dim: semantic_sig.map(|blob| blob.len() / 4),
+                    old_content: row.get(15)?,
+                    new_content: row.get(16)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    /// Import events and exchanges from an export document.
+    ///
+    /// # Arguments
+    /// - `events`: Exported events, in export order.
+    /// - `exchanges`: Exported exchanges.
+    ///
+    /// # Returns
+    /// Counts of imported and already-present rows.
+    ///
+    /// # Errors
+    /// Returns `diachron_core::Error` if an insert fails; the batch is rolled back.
+    pub fn import_batch(
+        &self,
+        events: &[ExportedEvent],
+        exchanges: &[Exchange],
+    ) -> Result<ImportCounts, diachron_core::Error> {
+        let conn = self.write();
+        diachron_core::import_batch(&conn, events, exchanges)
+    }
+
     /// Get total event count.
     ///
     /// # Returns
@@ -287,11 +775,67 @@ impl Database {
     /// # Errors
     /// Returns `rusqlite::Error` if the query fails.
     pub fn event_count(&self) -> rusqlite::Result<u64> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.read()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))?;
         Ok(count as u64)
     }
 
+    /// Aggregate event and exchange activity into time buckets.
+    ///
+    /// # Arguments
+    /// - `bucket`: Bucket width.
+    /// - `since`: Optional time filter (relative or ISO).
+    ///
+    /// # Returns
+    /// One entry per bucket from the first to the last active bucket, oldest
+    /// first. Buckets without activity are included with zero counts so the
+    /// series is evenly spaced.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn stats(&self, bucket: Bucket, since: Option<&str>) -> rusqlite::Result<Vec<StatBucket>> {
+        let conn = self.read()?;
+        let since = since.and_then(parse_time_filter).unwrap_or_default();
+        let key = bucket_sql(bucket);
+        let mut buckets: BTreeMap<String, StatBucket> = BTreeMap::new();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {key} AS bucket, COALESCE(operation, 'unknown'), tool_name, COUNT(*)
+             FROM events
+             WHERE timestamp >= ?1
+             GROUP BY bucket, operation, tool_name"
+        ))?;
+        let rows = stmt.query_map([&since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)? as u64,
+            ))
+        })?;
+        for (start, operation, tool, count) in rows.filter_map(|r| r.ok()) {
+            let entry = buckets.entry(start).or_default();
+            entry.event_count += count;
+            *entry.by_operation.entry(operation).or_default() += count;
+            *entry.by_tool.entry(tool).or_default() += count;
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {key} AS bucket, COUNT(*)
+             FROM exchanges
+             WHERE timestamp >= ?1
+             GROUP BY bucket"
+        ))?;
+        let rows = stmt.query_map([&since], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        for (start, count) in rows.filter_map(|r| r.ok()) {
+            buckets.entry(start).or_default().exchange_count += count;
+        }
+
+        Ok(fill_bucket_gaps(buckets, bucket))
+    }
+
     /// Save a conversation exchange to the database.
     ///
     /// Uses INSERT OR REPLACE to handle re-indexing gracefully.
@@ -311,7 +855,7 @@ impl Database {
         let embedding_blob: Option<Vec<u8>> =
             embedding.map(|emb| emb.iter().flat_map(|f| f.to_le_bytes()).collect());
 
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write();
         conn.execute(
             "INSERT OR REPLACE INTO exchanges (
                 id, timestamp, project, session_id, user_message,
@@ -336,59 +880,324 @@ impl Database {
             ],
         )?;
 
-        debug!("Saved exchange: {}", exchange.id);
-        Ok(())
+        debug!("Saved exchange: {}", exchange.id);
+        Ok(())
+    }
+
+    /// Get total exchange count.
+    ///
+    /// # Returns
+    /// Total number of exchanges in the database.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn exchange_count(&self) -> rusqlite::Result<u64> {
+        let conn = self.read()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM exchanges", [], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    /// Get exchanges without summaries for summarization.
+    ///
+    /// # Arguments
+    /// - `limit`: Maximum number of exchanges to return.
+    ///
+    /// # Returns
+    /// Vector of (id, user_message, assistant_message) tuples.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn get_exchanges_without_summary(
+        &self,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<(String, String, String)>> {
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, user_message, assistant_message
+             FROM exchanges
+             WHERE summary IS NULL OR summary = ''
+             ORDER BY timestamp DESC
+             LIMIT ?",
+        )?;
+
+        let rows = stmt.query_map([limit], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Get the messages of every indexed exchange, for re-embedding.
+    ///
+    /// Exchanges marked as near-duplicates are left out, so a rebuild keeps
+    /// them out of the vector index.
+    ///
+    /// # Returns
+    /// Vector of (id, user_message, assistant_message, project) tuples.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn get_exchange_messages(&self) -> rusqlite::Result<Vec<ExchangeMessages>> {
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, user_message, assistant_message, project FROM exchanges
+             WHERE duplicate_of IS NULL",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Pick out the deletions among the given events.
+    ///
+    /// # Arguments
+    /// - `ids`: Event IDs; IDs that are not integers are ignored.
+    ///
+    /// # Returns
+    /// IDs of the events whose operation is `delete`.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn delete_event_ids(&self, ids: &[&str]) -> rusqlite::Result<HashSet<String>> {
+        let ids: Vec<i64> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM events WHERE operation = 'delete' AND id IN ({})",
+            vec!["?"; ids.len()].join(", ")
+        ))?;
+        let rows = stmt.query_map(params_from_iter(ids), |row| {
+            Ok(row.get::<_, i64>(0)?.to_string())
+        })?;
+        rows.collect()
+    }
+
+    /// List every row ID of a table and whether it should be in the vector
+    /// index.
+    ///
+    /// A row belongs in the index if it has a stored embedding; exchanges
+    /// marked as near-duplicates are kept out.
+    ///
+    /// # Arguments
+    /// - `table`: Table to list.
+    ///
+    /// # Returns
+    /// Vector of (id, indexable) pairs.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn indexable_rows(&self, table: IndexedTable) -> rusqlite::Result<Vec<(String, bool)>> {
+        let indexable = match table {
+            IndexedTable::Events => "embedding IS NOT NULL",
+            IndexedTable::Exchanges => "embedding IS NOT NULL AND duplicate_of IS NULL",
+        };
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT CAST(id AS TEXT), {} FROM {}",
+            indexable,
+            table.table()
+        ))?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Load the stored embeddings of the given rows.
+    ///
+    /// # Arguments
+    /// - `table`: Table holding the rows.
+    /// - `ids`: Row IDs, without the index key prefix.
+    ///
+    /// # Returns
+    /// Vector of (id, embedding, metadata) tuples for rows that have an
+    /// embedding, where metadata is the row's file path (events) or project
+    /// (exchanges) for the vector index.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn stored_embeddings(
+        &self,
+        table: IndexedTable,
+        ids: &[&str],
+    ) -> rusqlite::Result<Vec<VectorEntry>> {
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT embedding, {} FROM {} WHERE id = ?1 AND embedding IS NOT NULL",
+            table.metadata_column(),
+            table.table()
+        ))?;
+
+        let mut results = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let row: Option<(Vec<u8>, Option<String>)> = stmt
+                .query_row([id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?;
+            if let Some((blob, metadata)) = row {
+                let embedding = blob
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+                results.push((id.to_string(), embedding, metadata));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Visit every stored embedding of the rows that belong in the vector
+    /// index, in row order.
+    ///
+    /// Rows are read in pages of `page_size`, and the connection is released
+    /// between pages so captures are not held up. Blobs whose length is not
+    /// a multiple of 4 bytes cannot be decoded and are skipped.
+    ///
+    /// # Arguments
+    /// - `table`: Table to read.
+    /// - `page_size`: Rows read per query.
+    /// - `f`: Called with each row ID (without the index key prefix), its
+    ///   embedding, and its vector index metadata (see
+    ///   [`Self::stored_embeddings`]).
+    ///
+    /// # Returns
+    /// Number of corrupt blobs skipped.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn for_each_stored_embedding(
+        &self,
+        table: IndexedTable,
+        page_size: usize,
+        mut f: impl FnMut(String, Vec<f32>, Option<String>),
+    ) -> rusqlite::Result<u64> {
+        let indexable = match table {
+            IndexedTable::Events => "embedding IS NOT NULL",
+            IndexedTable::Exchanges => "embedding IS NOT NULL AND duplicate_of IS NULL",
+        };
+        let sql = format!(
+            "SELECT rowid, CAST(id AS TEXT), embedding, {} FROM {} \
+             WHERE rowid > ?1 AND {} ORDER BY rowid LIMIT ?2",
+            table.metadata_column(),
+            table.table(),
+            indexable
+        );
+
+        let mut corrupt = 0;
+        let mut after = 0i64;
+        loop {
+            let page: Vec<(i64, String, Vec<u8>, Option<String>)> = {
+                let conn = self.read()?;
+                let mut stmt = conn.prepare(&sql)?;
+                let rows = stmt.query_map(params![after, page_size as i64], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            let Some((last, _, _, _)) = page.last() else {
+                return Ok(corrupt);
+            };
+            after = *last;
+
+            for (_, id, blob, metadata) in page {
+                if !blob.len().is_multiple_of(4) {
+                    corrupt += 1;
+                    continue;
+                }
+                let embedding = blob
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+                f(id, embedding, metadata);
+            }
+        }
     }
 
-    /// Get total exchange count.
+    /// Count a table's rows and the documents in its full-text index.
+    ///
+    /// # Arguments
+    /// - `table`: Table to count.
     ///
     /// # Returns
-    /// Total number of exchanges in the database.
+    /// Counts with `rows` and `fts_rows` set; the vector index fields are
+    /// left for the caller.
     ///
     /// # Errors
-    /// Returns `rusqlite::Error` if the query fails.
-    pub fn exchange_count(&self) -> rusqlite::Result<u64> {
-        let conn = self.conn.lock().unwrap();
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM exchanges", [], |row| row.get(0))?;
-        Ok(count as u64)
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn integrity_counts(&self, table: IndexedTable) -> rusqlite::Result<TableIntegrity> {
+        let conn = self.read()?;
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0));
+        // One docsize row per indexed document; the FTS table itself reads
+        // through to its content table
+        Ok(TableIntegrity {
+            rows: count(&format!("SELECT COUNT(*) FROM {}", table.table()))? as u64,
+            fts_rows: count(&format!(
+                "SELECT COUNT(*) FROM {}_fts_docsize",
+                table.table()
+            ))? as u64,
+            ..Default::default()
+        })
     }
 
-    /// Get exchanges without summaries for summarization.
+    /// List rows that should be in the vector index but have no stored
+    /// embedding.
     ///
     /// # Arguments
-    /// - `limit`: Maximum number of exchanges to return.
+    /// - `table`: Table to list.
     ///
     /// # Returns
-    /// Vector of (id, user_message, assistant_message) tuples.
+    /// Row IDs, without the index key prefix.
     ///
     /// # Errors
     /// Returns `rusqlite::Error` if the query fails.
-    pub fn get_exchanges_without_summary(
-        &self,
-        limit: usize,
-    ) -> rusqlite::Result<Vec<(String, String, String)>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, user_message, assistant_message
-             FROM exchanges
-             WHERE summary IS NULL OR summary = ''
-             ORDER BY timestamp DESC
-             LIMIT ?",
-        )?;
-
-        let rows = stmt.query_map([limit], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })?;
+    pub fn unembedded_rows(&self, table: IndexedTable) -> rusqlite::Result<Vec<String>> {
+        let condition = match table {
+            IndexedTable::Events => "embedding IS NULL",
+            IndexedTable::Exchanges => "embedding IS NULL AND duplicate_of IS NULL",
+        };
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT CAST(id AS TEXT) FROM {} WHERE {}",
+            table.table(),
+            condition
+        ))?;
+
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
 
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
-        }
-        Ok(results)
+    /// Count events written without hash-chain fields.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn events_without_hash(&self) -> rusqlite::Result<u64> {
+        let conn = self.read()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM events WHERE event_hash IS NULL OR prev_hash IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
     }
 
     /// Update an exchange's summary.
@@ -400,13 +1209,29 @@ impl Database {
     /// # Errors
     /// Returns `rusqlite::Error` if the update fails.
     pub fn update_exchange_summary(&self, id: &str, summary: &str) -> rusqlite::Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write();
         conn.execute(
             "UPDATE exchanges SET summary = ? WHERE id = ?",
             params![summary, id],
         )
     }
 
+    /// Mark an exchange as a near-duplicate of an indexed exchange.
+    ///
+    /// # Arguments
+    /// - `id`: Exchange left out of the vector index.
+    /// - `canonical_id`: Indexed exchange it duplicates.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the update fails.
+    pub fn mark_exchange_duplicate(&self, id: &str, canonical_id: &str) -> rusqlite::Result<usize> {
+        let conn = self.write();
+        conn.execute(
+            "UPDATE exchanges SET duplicate_of = ? WHERE id = ?",
+            params![canonical_id, id],
+        )
+    }
+
     /// Get the database file size in bytes.
     pub fn file_size(&self) -> u64 {
         std::fs::metadata(&self.path)
@@ -422,109 +1247,522 @@ impl Database {
     /// # Errors
     /// Returns `rusqlite::Error` if maintenance operations fail.
     pub fn vacuum_and_analyze(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write();
         conn.execute("VACUUM", [])?;
         conn.execute("ANALYZE", [])?;
         Ok(())
     }
 
-    /// Prune events older than a given number of days.
+    /// Prune events older than their retention.
     ///
     /// # Arguments
-    /// - `days`: Delete events older than this many days.
+    /// - `retention`: How long to keep events, overall and per project.
     ///
     /// # Returns
-    /// Number of events deleted.
+    /// IDs of the deleted events (so callers can drop their vectors).
     ///
     /// # Errors
     /// Returns `rusqlite::Error` if the delete fails.
-    pub fn prune_old_events(&self, days: u32) -> rusqlite::Result<u64> {
-        let conn = self.conn.lock().unwrap();
-        let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
-        let cutoff_str = cutoff.format("%Y-%m-%dT%H:%M:%S").to_string();
-
-        let deleted = conn.execute(
-            "DELETE FROM events WHERE timestamp < ?",
-            params![cutoff_str],
-        )?;
-        Ok(deleted as u64)
+    pub fn prune_old_events(&self, retention: &Retention<'_>) -> rusqlite::Result<Vec<i64>> {
+        prune_table(&self.write(), "events", retention)
+    }
+
+    /// Delete exchanges matching a filter, or only list them.
+    ///
+    /// # Arguments
+    /// - `filter`: Which exchanges to delete.
+    /// - `dry_run`: List the matches without deleting them.
+    ///
+    /// # Returns
+    /// ID and project of each matched exchange (so callers can drop their
+    /// vectors).
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the FTS query is malformed or the delete
+    /// fails.
+    pub fn forget_exchanges(
+        &self,
+        filter: &ExchangeFilter<'_>,
+        dry_run: bool,
+    ) -> rusqlite::Result<Vec<(String, Option<String>)>> {
+        let conn = self.write();
+
+        let mut sql = String::from(if dry_run {
+            "SELECT id, project FROM exchanges WHERE 1=1"
+        } else {
+            "DELETE FROM exchanges WHERE 1=1"
+        });
+        let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+
+        if let Some(project) = &filter.project {
+            sql.push_str(" AND project LIKE '%' || ? || '%'");
+            params.push(project);
+        }
+        if let Some(session) = &filter.session {
+            sql.push_str(" AND session_id = ?");
+            params.push(session);
+        }
+        if let Some(before) = &filter.before {
+            sql.push_str(" AND timestamp < ?");
+            params.push(before);
+        }
+        if let Some(query) = &filter.query {
+            sql.push_str(
+                " AND rowid IN (SELECT rowid FROM exchanges_fts WHERE exchanges_fts MATCH ?)",
+            );
+            params.push(query);
+        }
+        if !dry_run {
+            sql.push_str(" RETURNING id, project");
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params.as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
     }
 
-    /// Prune exchanges older than a given number of days.
+    /// Prune exchanges older than their retention.
     ///
     /// # Arguments
-    /// - `days`: Delete exchanges older than this many days.
+    /// - `retention`: How long to keep exchanges, overall and per project.
     ///
     /// # Returns
-    /// Number of exchanges deleted.
+    /// IDs of the deleted exchanges (so callers can drop their vectors).
     ///
     /// # Errors
     /// Returns `rusqlite::Error` if the delete fails.
-    pub fn prune_old_exchanges(&self, days: u32) -> rusqlite::Result<u64> {
-        let conn = self.conn.lock().unwrap();
-        let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
-        let cutoff_str = cutoff.format("%Y-%m-%dT%H:%M:%S").to_string();
-
-        let deleted = conn.execute(
-            "DELETE FROM exchanges WHERE timestamp < ?",
-            params![cutoff_str],
+    pub fn prune_old_exchanges(&self, retention: &Retention<'_>) -> rusqlite::Result<Vec<String>> {
+        prune_table(&self.write(), "exchanges", retention)
+    }
+
+    /// Find session IDs starting with a prefix.
+    ///
+    /// # Arguments
+    /// - `prefix`: Session ID or its first characters.
+    /// - `limit`: Maximum IDs to return.
+    ///
+    /// # Returns
+    /// Matching session IDs that have events.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the query fails.
+    pub fn find_sessions(&self, prefix: &str, limit: usize) -> rusqlite::Result<Vec<String>> {
+        let conn = self.read()?;
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT session_id FROM events
+             WHERE substr(session_id, 1, length(?1)) = ?1
+             LIMIT ?2",
         )?;
-        Ok(deleted as u64)
+        let ids = stmt
+            .query_map(params![prefix, limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
     }
-}
 
-/// Parse a time filter string into an ISO timestamp
-fn parse_time_filter(filter: &str) -> Option<String> {
-    let now = chrono::Local::now();
+    /// Summarize one coding session from its events.
+    ///
+    /// # Arguments
+    /// - `session_id`: Full session ID.
+    ///
+    /// # Returns
+    /// The summary, or `None` if no event has this session ID.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn session_summary(&self, session_id: &str) -> rusqlite::Result<Option<SessionSummary>> {
+        let conn = self.read()?;
+        summarize_session(&conn, session_id)
+    }
 
-    // Handle relative times like "1h", "2d", "yesterday"
-    let filter_lower = filter.to_lowercase();
+    /// Summarize the most recently active sessions.
+    ///
+    /// Each summary covers the whole session, including events before
+    /// `since`.
+    ///
+    /// # Arguments
+    /// - `since`: Only sessions with events in this window (relative or ISO).
+    /// - `limit`: Maximum sessions to return.
+    ///
+    /// # Returns
+    /// Summaries ordered by last activity, most recent first.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if a query fails.
+    pub fn recent_sessions(
+        &self,
+        since: Option<&str>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<SessionSummary>> {
+        let conn = self.read()?;
+        let since = since.and_then(parse_time_filter).unwrap_or_default();
 
-    if filter_lower == "yesterday" {
-        let yesterday = now - chrono::Duration::days(1);
-        return Some(yesterday.format("%Y-%m-%dT00:00:00").to_string());
+        let mut stmt = conn.prepare(
+            "SELECT session_id FROM events
+             WHERE session_id IS NOT NULL AND timestamp >= ?1
+             GROUP BY session_id
+             ORDER BY MAX(timestamp) DESC, MAX(id) DESC
+             LIMIT ?2",
+        )?;
+        let ids = stmt
+            .query_map(params![since, limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        let mut sessions = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(summary) = summarize_session(&conn, &id)? {
+                sessions.push(summary);
+            }
+        }
+        Ok(sessions)
     }
+}
+
+/// Aggregate the events of one session.
+///
+/// The intent comes from the conversation before the first file change
+/// (create, modify, or delete), or before the first event if nothing was
+/// changed.
+fn summarize_session(
+    conn: &Connection,
+    session_id: &str,
+) -> rusqlite::Result<Option<SessionSummary>> {
+    let (started_at, ended_at, event_count): (Option<String>, Option<String>, i64) = conn
+        .query_row(
+            "SELECT MIN(timestamp), MAX(timestamp), COUNT(*) FROM events WHERE session_id = ?1",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+    let (Some(started_at), Some(ended_at)) = (started_at, ended_at) else {
+        return Ok(None);
+    };
+
+    let distinct = |column: &str| -> rusqlite::Result<Vec<String>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {column} FROM events
+             WHERE session_id = ?1 AND {column} IS NOT NULL
+             GROUP BY {column}
+             ORDER BY MIN(timestamp), MIN(id)"
+        ))?;
+        let values = stmt
+            .query_map(params![session_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(values)
+    };
+    let files_touched = distinct("file_path")?;
+    let commits = distinct("git_commit_sha")?;
 
-    if filter_lower == "today" {
-        return Some(now.format("%Y-%m-%dT00:00:00").to_string());
+    let mut stmt = conn.prepare(
+        "SELECT tool_name, COUNT(*) FROM events WHERE session_id = ?1 GROUP BY tool_name",
+    )?;
+    let tools = stmt
+        .query_map(params![session_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let first_change = conn
+        .query_row(
+            "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
+                    operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                    json_extract(metadata, '$.command_category')
+             FROM events
+             WHERE session_id = ?1
+             ORDER BY operation IN ('create', 'modify', 'delete') DESC, timestamp, id
+             LIMIT 1",
+            params![session_id],
+            |row| {
+                Ok(StoredEvent {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    timestamp_display: row.get(2)?,
+                    session_id: row.get(3)?,
+                    tool_name: row.get(4)?,
+                    file_path: row.get(5)?,
+                    operation: row.get(6)?,
+                    diff_summary: row.get(7)?,
+                    raw_input: row.get(8)?,
+                    ai_summary: row.get(9)?,
+                    git_commit_sha: row.get(10)?,
+                    metadata: row.get(11)?,
+                    command_category: row.get(12)?,
+                })
+            },
+        )
+        .optional()?;
+    let intent = first_change.and_then(|event| find_intent_for_event(conn, &event, 5));
+
+    Ok(Some(SessionSummary {
+        session_id: session_id.to_string(),
+        started_at,
+        ended_at,
+        event_count: event_count as u64,
+        files_touched,
+        commits,
+        tools,
+        intent,
+    }))
+}
+
+/// Format of `StatBucket::bucket_start`.
+const BUCKET_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// SQL expression truncating `timestamp` to the start of its bucket.
+fn bucket_sql(bucket: Bucket) -> &'static str {
+    match bucket {
+        Bucket::Hour => "substr(timestamp, 1, 13) || ':00:00'",
+        Bucket::Day => "substr(timestamp, 1, 10) || 'T00:00:00'",
+        // Monday on or before the day: step back six days, then forward to a Monday
+        Bucket::Week => "date(substr(timestamp, 1, 10), '-6 days', 'weekday 1') || 'T00:00:00'",
     }
+}
 
-    // Parse "1h", "2d", "30m" etc
-    if let Some(num_str) = filter_lower.strip_suffix('h') {
-        if let Ok(hours) = num_str.parse::<i64>() {
-            let past = now - chrono::Duration::hours(hours);
-            return Some(past.format("%Y-%m-%dT%H:%M:%S").to_string());
+/// Insert zero-count buckets between the first and last active bucket.
+fn fill_bucket_gaps(mut buckets: BTreeMap<String, StatBucket>, bucket: Bucket) -> Vec<StatBucket> {
+    let step = match bucket {
+        Bucket::Hour => chrono::Duration::hours(1),
+        Bucket::Day => chrono::Duration::days(1),
+        Bucket::Week => chrono::Duration::weeks(1),
+    };
+    let parse = |s: &str| chrono::NaiveDateTime::parse_from_str(s, BUCKET_FORMAT).ok();
+    let first = buckets.keys().next().and_then(|s| parse(s));
+    let last = buckets.keys().next_back().and_then(|s| parse(s));
+
+    if let (Some(first), Some(last)) = (first, last) {
+        let mut current = first;
+        while current < last {
+            current += step;
+            let start = current.format(BUCKET_FORMAT).to_string();
+            buckets.entry(start).or_default();
         }
     }
 
-    if let Some(num_str) = filter_lower.strip_suffix('d') {
-        if let Ok(days) = num_str.parse::<i64>() {
-            let past = now - chrono::Duration::days(days);
-            return Some(past.format("%Y-%m-%dT%H:%M:%S").to_string());
+    buckets
+        .into_iter()
+        .map(|(start, mut stat)| {
+            stat.bucket_start = start;
+            stat
+        })
+        .collect()
+}
+
+/// Delete rows of `table` (`events` or `exchanges`) older than their
+/// retention, returning their IDs.
+///
+/// Only rows older than the shortest retention in `retention` are read;
+/// each is then checked against the retention of its own project.
+fn prune_table<T: rusqlite::types::FromSql + rusqlite::ToSql>(
+    conn: &Connection,
+    table: &str,
+    retention: &Retention<'_>,
+) -> rusqlite::Result<Vec<T>> {
+    let shortest = std::iter::once(retention.days)
+        .chain(retention.projects.values().copied())
+        .filter(|&days| days > 0)
+        .min();
+    let Some(shortest) = shortest else {
+        return Ok(Vec::new());
+    };
+    let now = chrono::Local::now();
+    let cutoff = |days: u32| {
+        (now - chrono::Duration::days(days as i64))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string()
+    };
+
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+    let rows = tx
+        .prepare(&format!(
+            "SELECT id, timestamp, project FROM {} WHERE timestamp < ?",
+            table
+        ))?
+        .query_map(params![cutoff(shortest)], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<rusqlite::Result<Vec<(T, String, Option<String>)>>>()?;
+
+    let mut ids = Vec::new();
+    {
+        let mut delete = tx.prepare(&format!("DELETE FROM {} WHERE id = ?", table))?;
+        for (id, timestamp, project) in rows {
+            let days = retention.days_for(project.as_deref());
+            if days > 0 && timestamp < cutoff(days) {
+                delete.execute(params![id])?;
+                ids.push(id);
+            }
         }
     }
+    tx.commit()?;
+    Ok(ids)
+}
 
-    if let Some(num_str) = filter_lower.strip_suffix('m') {
-        if let Ok(minutes) = num_str.parse::<i64>() {
-            let past = now - chrono::Duration::minutes(minutes);
-            return Some(past.format("%Y-%m-%dT%H:%M:%S").to_string());
+/// Insert a capture event, linking it to the current chain head.
+///
+/// The row is inserted before its hash is computed, so the hashed ID is
+/// the one SQLite assigned. Callers run this inside a transaction so the
+/// row is never visible without its hash.
+fn insert_event(
+    conn: &Connection,
+    event: &CaptureEvent,
+    session_id: Option<&str>,
+    embedding: Option<&[f32]>,
+    fingerprint: Option<&HunkFingerprint>,
+) -> rusqlite::Result<i64> {
+    let timestamp = chrono::Local::now();
+    let timestamp_iso = timestamp.format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
+
+    // Use actual system timezone (e.g., PST, EST, UTC, etc.)
+    let tz_name = timestamp.format("%Z").to_string();
+    let timestamp_display = timestamp
+        .format(&format!("%m/%d/%Y %I:%M %p {}", tz_name))
+        .to_string();
+
+    // Build metadata JSON - preserve metadata from event (includes git_branch)
+    // and merge with any additional fields
+    let mut metadata = if let Some(ref existing_meta) = event.metadata {
+        // Try to parse and merge with existing metadata
+        if let Ok(mut meta) = serde_json::from_str::<serde_json::Value>(existing_meta) {
+            // Add command_category if not already present
+            if let Some(category) = event.command_category.as_ref() {
+                if meta.get("command_category").is_none() {
+                    meta["command_category"] = serde_json::json!(category.as_str());
+                }
+            }
+            meta
+        } else {
+            // Couldn't parse, create fresh metadata
+            serde_json::json!({
+                "command_category": event.command_category.as_ref().map(|c| c.as_str()),
+            })
         }
-    }
+    } else {
+        serde_json::json!({
+            "command_category": event.command_category.as_ref().map(|c| c.as_str()),
+        })
+    };
 
-    // Try parsing as ISO date
-    if filter.len() >= 10 {
-        // Assume it's a date like "2024-01-01"
-        return Some(format!("{}T00:00:00", &filter[..10]));
+    // Record the tool outcome (and error excerpt for failures) if not already present
+    if metadata.is_object() {
+        if let Some(outcome) = event.outcome {
+            if metadata.get("outcome").is_none() {
+                metadata["outcome"] = serde_json::json!(outcome.as_str());
+            }
+        }
+        if let Some(excerpt) = event.error_excerpt.as_ref() {
+            if metadata.get("error_excerpt").is_none() {
+                metadata["error_excerpt"] = serde_json::json!(excerpt);
+            }
+        }
+        // Blame only treats a trailing `...` as a cut when this says so
+        if event.raw_input_truncated {
+            metadata["raw_input_truncated"] = serde_json::json!(true);
+        }
     }
 
-    None
+    let project = project_from_metadata(&metadata.to_string());
+
+    // Hooks report the agent's session in the metadata
+    let session_id: Option<String> = session_id.map(str::to_string).or_else(|| {
+        metadata
+            .get("session_id")
+            .and_then(|s| s.as_str())
+            .map(str::to_string)
+    });
+
+    // Chain edits to the same file within a session
+    let parent_event_id: Option<i64> = match (session_id.as_deref(), event.file_path.as_deref()) {
+        (Some(session), Some(path)) => conn.query_row(
+            "SELECT MAX(id) FROM events WHERE session_id = ?1 AND file_path = ?2",
+            params![session, path],
+            |row| row.get(0),
+        )?,
+        _ => None,
+    };
+
+    // Convert embedding to blob if present
+    let embedding_blob: Option<Vec<u8>> =
+        embedding.map(|emb| emb.iter().flat_map(|f| f.to_le_bytes()).collect());
+    let content_hash = fingerprint.map(|fp| fp.content_hash.to_vec());
+    let semantic_sig_blob: Option<Vec<u8>> = fingerprint
+        .and_then(|fp| fp.semantic_sig.as_ref())
+        .map(|sig| sig.iter().flat_map(|f| f.to_le_bytes()).collect());
+
+    // Get the previous event's hash for chain linkage
+    let prev_hash = get_last_event_hash(conn).unwrap_or(GENESIS_HASH);
+
+    // Insert first so SQLite assigns the ID the hash covers; the hash
+    // columns are filled in below, in the caller's transaction
+    conn.execute(
+        "INSERT INTO events (
+            timestamp, timestamp_display, session_id, tool_name, file_path,
+            operation, diff_summary, raw_input, git_commit_sha, metadata, embedding,
+            prev_hash, content_hash, semantic_sig, project, parent_event_id,
+            old_content, new_content
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        params![
+            timestamp_iso,
+            timestamp_display,
+            session_id,
+            event.tool_name,
+            event.file_path,
+            event.operation.as_str(),
+            event.diff_summary,
+            event.raw_input,
+            event.git_commit_sha,
+            metadata.to_string(),
+            embedding_blob,
+            prev_hash.as_slice(),
+            content_hash,
+            semantic_sig_blob,
+            project,
+            parent_event_id,
+            event.old_content,
+            event.new_content,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+
+    // Build hash input with all event data
+    let hash_input = EventHashInput {
+        id,
+        timestamp: timestamp_iso,
+        tool_name: event.tool_name.clone(),
+        file_path: event.file_path.clone(),
+        operation: event.operation.as_str().to_string(),
+        diff_summary: event.diff_summary.clone(),
+        raw_input: event.raw_input.clone(),
+        session_id,
+        git_commit_sha: event.git_commit_sha.clone(),
+        metadata: Some(metadata.to_string()),
+    };
+
+    // Compute event hash (plus per-field digests for tamper diagnostics)
+    let event_hash = compute_event_hash(&hash_input, &prev_hash);
+    let field_hashes = serde_json::to_string(&compute_field_hashes(&hash_input)).ok();
+
+    conn.execute(
+        "UPDATE events SET event_hash = ?1, field_hashes = ?2 WHERE id = ?3",
+        params![event_hash.as_slice(), field_hashes, id],
+    )?;
+
+    Ok(id)
+}
+
+/// Parse a time filter into a cutoff comparable with stored event
+/// timestamps, which are local time without an offset.
+fn parse_time_filter(filter: &str) -> Option<String> {
+    time::parse_since(filter).map(time::format_local)
 }
 
 /// Query events that modified a specific file
 ///
+/// A relative `file_path` matches stored paths ending in it at a directory
+/// boundary, so `auth.rs` does not match `oauth.rs`.
+///
 /// # Arguments
 /// - `conn`: Database connection
-/// - `file_path`: Path to the file (can be partial match)
+/// - `file_path`: Path to the file (absolute, or relative to any directory)
 /// - `limit`: Maximum number of events to return
 ///
 /// # Returns
@@ -536,15 +1774,75 @@ pub fn query_events_for_file(
 ) -> rusqlite::Result<Vec<StoredEvent>> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, timestamp_display, session_id, tool_name, file_path,
-                operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata
+                operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                json_extract(metadata, '$.command_category')
          FROM events
-         WHERE file_path LIKE ?1
+         WHERE file_path = ?1 OR (substr(?1, 1, 1) != '/' AND file_path LIKE '%/' || ?1)
          ORDER BY timestamp DESC
          LIMIT ?2",
     )?;
 
     let events = stmt
-        .query_map(params![format!("%{}", file_path), limit as i64], |row| {
+        .query_map(params![file_path, limit as i64], |row| {
+            Ok(StoredEvent {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                timestamp_display: row.get(2)?,
+                session_id: row.get(3)?,
+                tool_name: row.get(4)?,
+                file_path: row.get(5)?,
+                operation: row.get(6)?,
+                diff_summary: row.get(7)?,
+                raw_input: row.get(8)?,
+                ai_summary: row.get(9)?,
+                git_commit_sha: row.get(10)?,
+                metadata: row.get(11)?,
+                command_category: row.get(12)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(events)
+}
+
+/// Query the events around an event in its session.
+///
+/// # Arguments
+/// - `conn`: Database connection
+/// - `event`: Event to look around; without a session there is no context
+/// - `n`: Events to take on each side
+///
+/// # Returns
+/// Up to `n` earlier and `n` later events of the same session, ordered by
+/// ID, without `event` itself. Fewer are returned near the session's ends.
+pub fn query_session_context(
+    conn: &Connection,
+    event: &StoredEvent,
+    n: usize,
+) -> rusqlite::Result<Vec<StoredEvent>> {
+    let Some(session_id) = event.session_id.as_deref() else {
+        return Ok(Vec::new());
+    };
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+
+    let columns = "id, timestamp, timestamp_display, session_id, tool_name, file_path,
+                   operation, diff_summary, raw_input, ai_summary, git_commit_sha, metadata,
+                   json_extract(metadata, '$.command_category')";
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM (
+             SELECT * FROM (SELECT {columns} FROM events
+                            WHERE session_id = ?1 AND id < ?2 ORDER BY id DESC LIMIT ?3)
+             UNION ALL
+             SELECT * FROM (SELECT {columns} FROM events
+                            WHERE session_id = ?1 AND id > ?2 ORDER BY id ASC LIMIT ?3)
+         ) ORDER BY id ASC"
+    ))?;
+
+    let events = stmt
+        .query_map(params![session_id, event.id, n as i64], |row| {
             Ok(StoredEvent {
                 id: row.get(0)?,
                 timestamp: row.get(1)?,
@@ -558,6 +1856,7 @@ pub fn query_events_for_file(
                 ai_summary: row.get(9)?,
                 git_commit_sha: row.get(10)?,
                 metadata: row.get(11)?,
+                command_category: row.get(12)?,
             })
         })?
         .filter_map(|r| r.ok())
@@ -573,40 +1872,51 @@ pub fn query_events_for_file(
 /// - `events`: Events to get fingerprints for
 ///
 /// # Returns
-/// Vector of (event_id, HunkFingerprint) tuples for events that have fingerprints stored
+/// Vector of (event_id, HunkFingerprint) tuples for events that have a content
+/// hash or semantic signature stored. Missing hashes are left zeroed.
 pub fn get_event_fingerprints(
     conn: &Connection,
     events: &[StoredEvent],
-) -> Vec<(i64, diachron_core::fingerprint::HunkFingerprint)> {
-    use diachron_core::fingerprint::HunkFingerprint;
+) -> Vec<(i64, HunkFingerprint)> {
+    let to_hash = |blob: Option<Vec<u8>>| {
+        blob.and_then(|b| <[u8; 32]>::try_from(b.as_slice()).ok())
+            .unwrap_or([0u8; 32])
+    };
 
     let mut fingerprints = Vec::new();
 
     for event in events {
-        // Query for stored fingerprint hashes
-        let result: rusqlite::Result<(Vec<u8>, Vec<u8>)> = conn.query_row(
-            "SELECT content_hash, context_hash FROM events WHERE id = ?1",
+        // Query for stored fingerprint hashes and semantic signature
+        let result = conn.query_row(
+            "SELECT content_hash, context_hash, semantic_sig FROM events WHERE id = ?1",
             params![event.id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| {
+                let content_hash: Option<Vec<u8>> = row.get(0)?;
+                let context_hash: Option<Vec<u8>> = row.get(1)?;
+                let semantic_sig: Option<Vec<u8>> = row.get(2)?;
+                Ok((content_hash, context_hash, semantic_sig))
+            },
         );
 
-        if let Ok((content_hash, context_hash)) = result {
-            // Convert blobs to fixed-size arrays
-            if content_hash.len() == 32 && context_hash.len() == 32 {
-                let mut ch = [0u8; 32];
-                let mut xh = [0u8; 32];
-                ch.copy_from_slice(&content_hash);
-                xh.copy_from_slice(&context_hash);
-
-                fingerprints.push((
-                    event.id,
-                    HunkFingerprint {
-                        content_hash: ch,
-                        context_hash: xh,
-                        semantic_sig: None, // Not stored in DB yet
-                    },
-                ));
+        if let Ok((content_hash, context_hash, semantic_sig)) = result {
+            if content_hash.is_none() && semantic_sig.is_none() {
+                continue;
             }
+
+            let semantic_sig = semantic_sig.map(|blob| {
+                blob.chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect()
+            });
+
+            fingerprints.push((
+                event.id,
+                HunkFingerprint {
+                    content_hash: to_hash(content_hash),
+                    context_hash: to_hash(context_hash),
+                    semantic_sig,
+                },
+            ));
         }
     }
 
@@ -765,20 +2075,77 @@ pub fn find_intent_for_event(
         return None;
     }
 
-    // Score each exchange and find the best match
-    let mut scored: Vec<(u32, &Exchange)> = exchanges
-        .iter()
-        .map(|ex| (score_intent_match(ex, event), ex))
-        .collect();
-
-    // Sort by score descending, then by timestamp descending (most recent)
-    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    // Score each exchange and find the best match
+    let mut scored: Vec<(u32, &Exchange)> = exchanges
+        .iter()
+        .map(|ex| (score_intent_match(ex, event), ex))
+        .collect();
+
+    // Sort by score descending, then by timestamp descending (most recent)
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+    // Take the best-scoring exchange
+    let (_, best_exchange) = scored.first()?;
+
+    // Extract intent summary from user message
+    Some(extract_intent_summary(&best_exchange.user_message, 150))
+}
+
+/// Find the user intent behind a group of events, such as a PR's commits.
+///
+/// For each session involved, scores the exchanges before the session's
+/// last event against all of that session's events, and keeps the
+/// highest-scoring user message overall. Ties go to the earlier message,
+/// which usually states the task.
+///
+/// # Arguments
+/// - `conn`: Database connection
+/// - `events`: The events to find intent for
+/// - `max_exchanges`: Maximum exchanges to consider per session
+///
+/// # Returns
+/// Extracted intent string, or None if no involved session has exchanges
+pub fn find_intent_for_events<'a>(
+    conn: &Connection,
+    events: impl IntoIterator<Item = &'a StoredEvent>,
+    max_exchanges: usize,
+) -> Option<String> {
+    let mut sessions: BTreeMap<&str, Vec<&StoredEvent>> = BTreeMap::new();
+    for event in events {
+        if let Some(session_id) = event.session_id.as_deref() {
+            sessions.entry(session_id).or_default().push(event);
+        }
+    }
+
+    let mut best: Option<(u32, Exchange)> = None;
+    for (session_id, session_events) in sessions {
+        let Some(last) = session_events.iter().map(|e| e.timestamp.as_str()).max() else {
+            continue;
+        };
+        let Ok(exchanges) = query_exchanges_for_intent(conn, session_id, last, max_exchanges)
+        else {
+            continue;
+        };
 
-    // Take the best-scoring exchange
-    let (_, best_exchange) = scored.first()?;
+        for exchange in exchanges {
+            let score = session_events
+                .iter()
+                .map(|event| score_intent_match(&exchange, event))
+                .sum();
+            let better = match &best {
+                None => true,
+                Some((best_score, best_exchange)) => {
+                    score > *best_score
+                        || (score == *best_score && exchange.timestamp < best_exchange.timestamp)
+                }
+            };
+            if better {
+                best = Some((score, exchange));
+            }
+        }
+    }
 
-    // Extract intent summary from user message
-    Some(extract_intent_summary(&best_exchange.user_message, 150))
+    best.map(|(_, exchange)| extract_intent_summary(&exchange.user_message, 150))
 }
 
 /// Extract the core intent from a user message.
@@ -867,7 +2234,8 @@ pub fn extract_intent_summary(user_message: &str, max_chars: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use diachron_core::Operation;
+    use crate::handlers::tests::temp_dir;
+    use diachron_core::{CommandCategory, FileMatch, Operation, Outcome};
 
     #[test]
     fn test_save_and_query() {
@@ -878,21 +2246,65 @@ mod tests {
             file_path: Some("test.txt".to_string()),
             operation: Operation::Create,
             diff_summary: Some("+10 lines".to_string()),
-            raw_input: None,
-            metadata: None,
-            git_commit_sha: None,
-            command_category: None,
+            ..Default::default()
         };
 
         // Third parameter is now embedding (None = no embedding)
-        let id = db.save_event(&event, Some("test-session"), None).unwrap();
+        let id = db
+            .save_event(&event, Some("test-session"), None, None)
+            .unwrap();
         assert!(id > 0);
 
-        let events = db.query_events(None, None, 10).unwrap();
+        let events = db.query_events(&EventFilter::default(), 10).unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].tool_name, "Write");
     }
 
+    #[test]
+    fn test_event_fingerprint_semantic_sig_round_trip() {
+        use diachron_core::fingerprint::{
+            compute_fingerprint, match_fingerprint, FingerprintOptions, MatchType,
+        };
+
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/auth.rs".to_string()),
+            operation: Operation::Create,
+            diff_summary: Some("+1 lines".to_string()),
+            raw_input: Some("fn login() {}".to_string()),
+            ..Default::default()
+        };
+        let stored = compute_fingerprint(
+            "fn login() {}",
+            None,
+            Some(vec![0.6, 0.8, 0.0]),
+            &FingerprintOptions::default(),
+        );
+        db.save_event(&event, None, None, Some(&stored)).unwrap();
+        // Events without a fingerprint are not candidates
+        db.save_event(&event, None, None, None).unwrap();
+
+        let events = db.query_events(&EventFilter::default(), 10).unwrap();
+        let conn = db.write();
+        let candidates = get_event_fingerprints(&conn, &events);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].1.content_hash, stored.content_hash);
+        assert_eq!(candidates[0].1.semantic_sig, stored.semantic_sig);
+
+        // A refactored line misses both hashes but is semantically close
+        let current = compute_fingerprint(
+            "fn sign_in() {}",
+            Some("ctx"),
+            Some(vec![0.6, 0.7, 0.1]),
+            &FingerprintOptions::default(),
+        );
+        let m = match_fingerprint(&current, &candidates, 0.8).unwrap();
+        assert_eq!(m.match_type, MatchType::SemanticSimilarity);
+        assert!(m.similarity > 0.9 && m.similarity < 1.0);
+    }
+
     #[test]
     fn test_save_exchange() {
         let db = Database::open(PathBuf::from(":memory:")).unwrap();
@@ -933,6 +2345,773 @@ mod tests {
         // Test INSERT OR REPLACE (re-save same ID should not increase count)
         db.save_exchange(&exchange, None).unwrap();
         assert_eq!(db.exchange_count().unwrap(), 2);
+
+        // Duplicates stay in the table but are not re-embedded on rebuild
+        db.mark_exchange_duplicate("test-exchange-002", "test-exchange-001")
+            .unwrap();
+        let ids: Vec<String> = db
+            .get_exchange_messages()
+            .unwrap()
+            .into_iter()
+            .map(|(id, _, _, _)| id)
+            .collect();
+        assert_eq!(ids, vec!["test-exchange-001".to_string()]);
+        assert_eq!(db.exchange_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_prune_old_events_returns_ids() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("old.txt".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        let old_id = db.save_event(&event, None, None, None).unwrap();
+        let new_id = db.save_event(&event, None, None, None).unwrap();
+
+        db.write()
+            .execute(
+                "UPDATE events SET timestamp = '2000-01-01T00:00:00.000' WHERE id = ?",
+                params![old_id],
+            )
+            .unwrap();
+
+        let pruned = db
+            .prune_old_events(&Retention {
+                days: 30,
+                projects: &HashMap::new(),
+            })
+            .unwrap();
+        assert_eq!(pruned, vec![old_id]);
+        assert_eq!(db.event_count().unwrap(), 1);
+
+        let remaining = db.query_events(&EventFilter::default(), 10).unwrap();
+        assert_eq!(remaining[0].id, new_id);
+    }
+
+    #[test]
+    fn test_prune_old_exchanges_honors_project_overrides() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        for (id, project) in [
+            ("a", "-Users-me-app"),
+            ("b", "-Users-me-Diachron"),
+            ("c", ""),
+        ] {
+            let exchange = Exchange {
+                id: id.to_string(),
+                timestamp: "2000-01-01T00:00:00Z".to_string(),
+                project: (!project.is_empty()).then(|| project.to_string()),
+                session_id: None,
+                user_message: "question".to_string(),
+                assistant_message: "answer".to_string(),
+                tool_calls: None,
+                archive_path: None,
+                line_start: None,
+                line_end: None,
+                embedding: None,
+                summary: None,
+                git_branch: None,
+                cwd: None,
+            };
+            db.save_exchange(&exchange, None).unwrap();
+        }
+
+        // Diachron's exchanges are kept forever; the 50-year override for
+        // "me" loses to it but still outlasts the default
+        let projects = HashMap::from([("diachron".to_string(), 0), ("me".to_string(), 365 * 50)]);
+        let mut pruned = db
+            .prune_old_exchanges(&Retention {
+                days: 30,
+                projects: &projects,
+            })
+            .unwrap();
+        pruned.sort();
+        assert_eq!(pruned, vec!["c".to_string()]);
+        assert_eq!(db.exchange_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_save_event_records_failure_outcome() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let event = CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Modify,
+            metadata: Some(r#"{"git_branch":"main"}"#.to_string()),
+            outcome: Some(Outcome::Failure),
+            error_excerpt: Some("String to replace not found in file.".to_string()),
+            ..Default::default()
+        };
+        db.save_event(&event, None, None, None).unwrap();
+        let ok = CaptureEvent {
+            outcome: Some(Outcome::Success),
+            error_excerpt: None,
+            ..event.clone()
+        };
+        db.save_event(&ok, None, None, None).unwrap();
+
+        let events = db.query_events(&EventFilter::default(), 10).unwrap();
+        let failed = events
+            .iter()
+            .find(|e| e.outcome() == Some(Outcome::Failure))
+            .unwrap();
+        assert_eq!(
+            failed.error_excerpt().as_deref(),
+            Some("String to replace not found in file.")
+        );
+        assert!(failed.metadata.as_deref().unwrap().contains("git_branch"));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.outcome() == Some(Outcome::Success))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_query_events_project_filter() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/main.rs".to_string()),
+            operation: Operation::Create,
+            metadata: Some(r#"{"cwd":"/Users/me/diachron"}"#.to_string()),
+            ..Default::default()
+        };
+        let tagged = db.save_event(&event, None, None, None).unwrap();
+        let other = CaptureEvent {
+            metadata: Some(r#"{"cwd":"/Users/me/website"}"#.to_string()),
+            ..event.clone()
+        };
+        db.save_event(&other, None, None, None).unwrap();
+        let untagged = CaptureEvent {
+            metadata: None,
+            ..event.clone()
+        };
+        db.save_event(&untagged, None, None, None).unwrap();
+
+        let all = db.query_events(&EventFilter::default(), 10).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let filter = EventFilter {
+            project: Some("Diachron"),
+            ..Default::default()
+        };
+        let filtered = db.query_events(&filter, 10).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, tagged);
+    }
+
+    #[test]
+    fn test_query_events_operation_and_category_filters() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let bash = |command: &str, category: CommandCategory| CaptureEvent {
+            tool_name: "Bash".to_string(),
+            operation: Operation::Execute,
+            raw_input: Some(command.to_string()),
+            command_category: Some(category),
+            ..Default::default()
+        };
+        let save = |event: &CaptureEvent| db.save_event(event, None, None, None).unwrap();
+        let build = save(&bash("cargo build", CommandCategory::Build));
+        let test = save(&bash("cargo test", CommandCategory::Test));
+        let edit = save(&CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Modify,
+            raw_input: None,
+            command_category: None,
+            ..bash("", CommandCategory::Unknown)
+        });
+
+        let ids = |operation, category| -> Vec<i64> {
+            let filter = EventFilter {
+                operation,
+                category,
+                ..Default::default()
+            };
+            db.query_events(&filter, 10)
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect()
+        };
+        assert_eq!(ids(Some("modify"), None), vec![edit]);
+        assert_eq!(ids(Some("execute"), None).len(), 2);
+        assert_eq!(ids(None, Some("build")), vec![build]);
+        assert_eq!(ids(Some("execute"), Some("test")), vec![test]);
+        assert!(ids(Some("modify"), Some("test")).is_empty());
+        assert!(ids(None, Some("deploy")).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_operation_and_category_read_as_unknown() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        db.write()
+            .execute(
+                "INSERT INTO events (timestamp, tool_name, file_path, operation, metadata)
+                 VALUES ('2024-01-15T10:00:00', 'Edit', 'src/lib.rs', 'edit',
+                         '{\"command_category\":\"lint\"}')",
+                [],
+            )
+            .unwrap();
+        let current_id = db
+            .save_event(
+                &CaptureEvent {
+                    tool_name: "Bash".to_string(),
+                    operation: Operation::Execute,
+                    raw_input: Some("mv a b".to_string()),
+                    command_category: Some(CommandCategory::FileOps),
+                    ..Default::default()
+                },
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let events = db.query_events(&EventFilter::default(), 10).unwrap();
+        assert_eq!(events.len(), 2);
+        let legacy = events.iter().find(|e| e.id != current_id).unwrap();
+        assert_eq!(legacy.operation, Some(Operation::Unknown));
+        assert_eq!(legacy.command_category, Some(CommandCategory::Unknown));
+        let current = events.iter().find(|e| e.id == current_id).unwrap();
+        assert_eq!(current.operation, Some(Operation::Execute));
+        assert_eq!(current.command_category, Some(CommandCategory::FileOps));
+
+        assert_eq!("Modify".parse::<Operation>(), Ok(Operation::Modify));
+        assert!(Operation::try_from("edit").is_err());
+        assert_eq!(Operation::from_stored("edit"), Operation::Unknown);
+    }
+
+    #[test]
+    fn test_query_events_glob_filter_applies_limit_after_matching() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let edit = |path: &str| CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Modify,
+            ..Default::default()
+        };
+        // Newest first: the non-matching paths would fill a SQL LIMIT
+        for path in [
+            "src/main.rs",
+            "src/sub/x.rs",
+            "vendor/src/lib.rs",
+            "src/sub/y.rs",
+        ] {
+            db.save_event(&edit(path), None, None, None).unwrap();
+        }
+
+        let paths = |pattern: &str, mode, limit| -> Vec<String> {
+            let file = PathFilter::new(pattern, mode).unwrap();
+            let filter = EventFilter {
+                file: Some(&file),
+                ..Default::default()
+            };
+            db.query_events(&filter, limit)
+                .unwrap()
+                .into_iter()
+                .filter_map(|e| e.file_path)
+                .collect()
+        };
+        assert_eq!(paths("src/*.rs", FileMatch::Glob, 1), vec!["src/main.rs"]);
+        assert_eq!(paths("src/**/*.rs", FileMatch::Glob, 10).len(), 3);
+        assert_eq!(paths(r"sub/[xy]\.rs$", FileMatch::Regex, 10).len(), 2);
+        assert_eq!(paths("src/", FileMatch::Substring, 10).len(), 4);
+
+        // Blame matches the requested path at a directory boundary
+        let conn = db.write();
+        assert_eq!(query_events_for_file(&conn, "lib.rs", 10).unwrap().len(), 1);
+        assert!(query_events_for_file(&conn, "b/lib.rs", 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_session_summary_aggregates_events() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let event = |tool: &str, file: Option<&str>, operation, sha: Option<&str>| CaptureEvent {
+            tool_name: tool.to_string(),
+            file_path: file.map(str::to_string),
+            operation,
+            git_commit_sha: sha.map(str::to_string),
+            ..Default::default()
+        };
+        let (a, b) = ("sess-abc123", "sess-xyz789");
+        let sha = Some("deadbeef");
+        for (e, session) in [
+            (event("Bash", None, Operation::Execute, None), a),
+            (event("Edit", Some("src/a.rs"), Operation::Modify, None), a),
+            (event("Write", Some("src/b.rs"), Operation::Create, None), a),
+            (event("Edit", Some("src/a.rs"), Operation::Modify, sha), a),
+            (event("Edit", Some("src/c.rs"), Operation::Modify, None), b),
+        ] {
+            db.save_event(&e, Some(session), None, None).unwrap();
+        }
+        let exchange = Exchange {
+            id: "ex-1".to_string(),
+            timestamp: "2020-01-01T00:00:00Z".to_string(),
+            project: None,
+            session_id: Some(a.to_string()),
+            user_message: "Add retry logic to the HTTP client.".to_string(),
+            assistant_message: "Done.".to_string(),
+            tool_calls: None,
+            archive_path: None,
+            line_start: None,
+            line_end: None,
+            embedding: None,
+            summary: None,
+            git_branch: None,
+            cwd: None,
+        };
+        db.save_exchange(&exchange, None).unwrap();
+
+        let summary = db.session_summary(a).unwrap().unwrap();
+        assert_eq!(summary.event_count, 4);
+        assert!(summary.started_at <= summary.ended_at);
+        assert_eq!(summary.files_touched, vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!(summary.commits, vec!["deadbeef"]);
+        assert_eq!(summary.tools.get("Edit"), Some(&2));
+        assert_eq!(summary.tools.get("Bash"), Some(&1));
+        assert!(summary.intent.unwrap().contains("retry logic"));
+        assert!(db.session_summary("sess-missing").unwrap().is_none());
+
+        assert_eq!(db.find_sessions("sess-a", 2).unwrap(), vec![a]);
+        assert_eq!(db.find_sessions("sess-", 2).unwrap().len(), 2);
+
+        let recent = db.recent_sessions(Some("1d"), 10).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].session_id, b);
+        assert_eq!(recent[1].files_touched.len(), 2);
+        assert_eq!(db.recent_sessions(None, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_stats_fills_gaps_and_omits_empty_operations() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let write = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("a.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        let edit = CaptureEvent {
+            tool_name: "Edit".to_string(),
+            operation: Operation::Modify,
+            ..write.clone()
+        };
+        let ids = [
+            db.save_event(&write, None, None, None).unwrap(),
+            db.save_event(&edit, None, None, None).unwrap(),
+            db.save_event(&edit, None, None, None).unwrap(),
+        ];
+        let timestamps = [
+            "2024-01-01T09:15:00.000",
+            "2024-01-01T17:40:00.000",
+            "2024-01-04T08:00:00.000",
+        ];
+        {
+            let conn = db.write();
+            for (id, ts) in ids.iter().zip(timestamps) {
+                conn.execute(
+                    "UPDATE events SET timestamp = ? WHERE id = ?",
+                    params![ts, id],
+                )
+                .unwrap();
+            }
+        }
+
+        let exchange = Exchange {
+            id: "ex-1".to_string(),
+            timestamp: "2024-01-02T12:00:00Z".to_string(),
+            project: None,
+            session_id: None,
+            user_message: "hi".to_string(),
+            assistant_message: "hello".to_string(),
+            tool_calls: None,
+            archive_path: None,
+            line_start: None,
+            line_end: None,
+            embedding: None,
+            summary: None,
+            git_branch: None,
+            cwd: None,
+        };
+        db.save_exchange(&exchange, None).unwrap();
+
+        let stats = db.stats(Bucket::Day, None).unwrap();
+        let starts: Vec<&str> = stats.iter().map(|s| s.bucket_start.as_str()).collect();
+        assert_eq!(
+            starts,
+            vec![
+                "2024-01-01T00:00:00",
+                "2024-01-02T00:00:00",
+                "2024-01-03T00:00:00",
+                "2024-01-04T00:00:00",
+            ]
+        );
+
+        assert_eq!(stats[0].event_count, 2);
+        assert_eq!(stats[0].by_operation.get("create"), Some(&1));
+        assert_eq!(stats[0].by_tool.get("Edit"), Some(&1));
+        assert_eq!(stats[1].event_count, 0);
+        assert_eq!(stats[1].exchange_count, 1);
+        assert!(stats[2].by_operation.is_empty());
+        assert_eq!(stats[3].by_operation.len(), 1);
+        assert!(!stats[3].by_operation.contains_key("create"));
+
+        // 2024-01-01 is a Monday, so everything lands in one week
+        let weekly = db.stats(Bucket::Week, None).unwrap();
+        assert_eq!(weekly.len(), 1);
+        assert_eq!(weekly[0].bucket_start, "2024-01-01T00:00:00");
+        assert_eq!(weekly[0].event_count, 3);
+
+        let hourly = db.stats(Bucket::Hour, Some("2024-01-04")).unwrap();
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].bucket_start, "2024-01-04T08:00:00");
+    }
+
+    #[test]
+    fn test_query_events_by_commit() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+
+        let save = |file: &str, sha: Option<&str>, session: &str, timestamp: &str| {
+            let event = CaptureEvent {
+                tool_name: if sha.is_some() { "Bash" } else { "Edit" }.to_string(),
+                file_path: Some(file.to_string()),
+                operation: if sha.is_some() {
+                    Operation::Commit
+                } else {
+                    Operation::Modify
+                },
+                git_commit_sha: sha.map(str::to_string),
+                ..Default::default()
+            };
+            let id = db.save_event(&event, Some(session), None, None).unwrap();
+            db.with_conn(|conn| {
+                conn.execute(
+                    "UPDATE events SET timestamp = ? WHERE id = ?",
+                    params![timestamp, id],
+                )
+            })
+            .unwrap();
+        };
+
+        save("a.rs", None, "s1", "2024-01-01T10:00:00.000");
+        save("", Some("aaa1111"), "s1", "2024-01-01T10:05:00.000");
+        save("b.rs", None, "s1", "2024-01-01T10:10:00.000");
+        save("other.rs", None, "s2", "2024-01-01T10:12:00.000");
+        save("c.rs", None, "s1", "2024-01-01T10:15:00.000");
+        save("", Some("bbb2222"), "s1", "2024-01-01T10:20:00.000");
+        save("d.rs", None, "s1", "2024-01-01T10:25:00.000");
+
+        let files = |sha: &str| -> Vec<String> {
+            db.query_events_by_commit(sha)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.file_path.unwrap_or_default())
+                .collect()
+        };
+
+        assert_eq!(files("BBB2"), vec!["b.rs", "c.rs", ""]);
+        assert_eq!(files("aaa1111"), vec!["a.rs", ""]);
+        assert!(files("ccc3").is_empty());
+    }
+
+    #[test]
+    fn test_get_event_with_fingerprint() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        let content = "fn main() {\n    println!(\"hi\");\n}";
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/main.rs".to_string()),
+            operation: Operation::Create,
+            diff_summary: Some("+3 lines".to_string()),
+            raw_input: Some(content.to_string()),
+            ..Default::default()
+        };
+        let fingerprint = diachron_core::compute_fingerprint(
+            content,
+            None,
+            Some(vec![0.5; 4]),
+            &diachron_core::FingerprintOptions::default(),
+        );
+        let id = db
+            .save_event(&event, None, None, Some(&fingerprint))
+            .unwrap();
+
+        let detail = db.get_event(id).unwrap().unwrap();
+        assert_eq!(detail.event.raw_input.as_deref(), Some(content));
+        assert_eq!(
+            detail.content_hash.as_deref().map(str::len),
+            Some(64),
+            "content hash is hex-encoded SHA-256"
+        );
+        assert_eq!(detail.semantic_sig_dim, Some(4));
+
+        assert!(db.get_event(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_edit_content_round_trips() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        let event = CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Modify,
+            diff_summary: Some("+1 lines".to_string()),
+            raw_input: Some("let x = 2;\nlet y = 3;".to_string()),
+            old_content: Some("let x = 1;".to_string()),
+            new_content: Some("let x = 2;\nlet y = 3;".to_string()),
+            ..Default::default()
+        };
+        let id = db.save_event(&event, None, None, None).unwrap();
+
+        let detail = db.get_event(id).unwrap().unwrap();
+        assert_eq!(detail.old_content, event.old_content);
+        assert_eq!(detail.new_content, event.new_content);
+
+        // Events captured with the setting off have no content
+        let without = CaptureEvent {
+            old_content: None,
+            new_content: None,
+            ..event
+        };
+        let id = db.save_event(&without, None, None, None).unwrap();
+        let detail = db.get_event(id).unwrap().unwrap();
+        assert!(detail.old_content.is_none() && detail.new_content.is_none());
+    }
+
+    #[test]
+    fn test_sequential_edits_form_lineage_chain() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        let edit = |path: &str, session: &str, diff: &str| CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Modify,
+            diff_summary: Some(diff.to_string()),
+            metadata: Some(format!(r#"{{"session_id":"{}"}}"#, session)),
+            ..Default::default()
+        };
+
+        let save = |path, session, diff| {
+            db.save_event(&edit(path, session, diff), None, None, None)
+                .unwrap()
+        };
+
+        let first = save("src/auth.rs", "s1", "+1");
+        // Interleaved edits to another file and from another session
+        save("src/db.rs", "s1", "+9");
+        save("src/auth.rs", "s2", "+7");
+        let second = save("src/auth.rs", "s1", "+2");
+        let third = save("src/auth.rs", "s1", "+3");
+
+        let lineage = db.query_event_lineage(third).unwrap();
+        assert_eq!(
+            lineage.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![first, second, third]
+        );
+        assert!(lineage
+            .iter()
+            .all(|e| e.session_id.as_deref() == Some("s1")));
+        assert_eq!(
+            lineage
+                .iter()
+                .map(|e| e.diff_summary.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("+1"), Some("+2"), Some("+3")]
+        );
+
+        assert_eq!(db.query_event_lineage(first).unwrap().len(), 1);
+        assert!(db.query_event_lineage(third + 1).unwrap().is_empty());
+        // Session IDs from metadata are part of the hashed record
+        assert!(diachron_core::verify_chain(&db.write()).unwrap().valid);
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_chain_valid() {
+        let db = std::sync::Arc::new(Database::open(PathBuf::from(":memory:")).unwrap());
+        let handles: Vec<_> = (0..8)
+            .map(|worker| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    (0..25)
+                        .map(|i| {
+                            let event = CaptureEvent {
+                                tool_name: "Write".to_string(),
+                                file_path: Some(format!("src/w{}.rs", worker)),
+                                operation: Operation::Create,
+                                diff_summary: Some(format!("+{}", i)),
+                                ..Default::default()
+                            };
+                            db.save_event(&event, Some("s1"), None, None).unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids, (1..=200).collect::<Vec<_>>());
+
+        let result = diachron_core::verify_chain(&db.write()).unwrap();
+        assert!(result.valid, "chain broke at {:?}", result.break_point);
+        assert_eq!(result.events_checked, 200);
+    }
+
+    #[test]
+    fn test_reads_run_while_writer_holds_transaction() {
+        let dir = temp_dir();
+        let db = Database::open(dir.join("diachron.db")).unwrap();
+        let mode: String = db
+            .write()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/a.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        db.save_event(&event, Some("s1"), None, None).unwrap();
+
+        // An uncommitted write holds the writer; readers see the last commit
+        let writer = db.write();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        writer.execute("DELETE FROM events", []).unwrap();
+        std::thread::scope(|scope| {
+            let reader = scope.spawn(|| db.query_events(&EventFilter::default(), 10).unwrap());
+            assert_eq!(reader.join().unwrap().len(), 1);
+        });
+        assert_eq!(db.event_count().unwrap(), 1);
+        writer.execute_batch("ROLLBACK").unwrap();
+        drop(writer);
+
+        // Finished reads return their connection to the pool
+        assert_eq!(db.readers.lock().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_in_memory_reads_share_the_writers_data() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        let other = Database::open(PathBuf::from(":memory:")).unwrap();
+        let version = db.search_version().unwrap();
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/a.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        db.save_event(&event, None, None, None).unwrap();
+
+        assert_eq!(db.event_count().unwrap(), 1);
+        assert_eq!(other.event_count().unwrap(), 0);
+        assert_ne!(db.search_version().unwrap(), version);
+
+        // A read nested inside a held write neither deadlocks nor fails
+        let writer = db.write();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        assert_eq!(db.event_count().unwrap(), 1);
+        writer.execute_batch("ROLLBACK").unwrap();
+    }
+
+    #[test]
+    fn test_second_connection_waits_for_write_lock() {
+        let dir = temp_dir();
+        let path = dir.join("diachron.db");
+        let db = Database::open(path.clone()).unwrap();
+
+        let writer = db.write();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        std::thread::scope(|scope| {
+            // Another process's handle: without a busy timeout this fails
+            // at once with "database is locked"
+            let other = scope.spawn(|| {
+                let started = std::time::Instant::now();
+                let other = Database::open(path.clone())?;
+                let event = CaptureEvent {
+                    tool_name: "Write".to_string(),
+                    file_path: Some("src/a.rs".to_string()),
+                    operation: Operation::Create,
+                    ..Default::default()
+                };
+                other.save_event(&event, Some("s1"), None, None)?;
+                anyhow::Ok(started.elapsed())
+            });
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            writer.execute_batch("COMMIT").unwrap();
+            let waited = other.join().unwrap().unwrap();
+            assert!(
+                waited >= std::time::Duration::from_millis(250),
+                "{:?}",
+                waited
+            );
+        });
+        drop(writer);
+
+        assert_eq!(db.event_count().unwrap(), 1);
+        assert!(diachron_core::verify_chain(&db.write()).unwrap().valid);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_session_context_around_event() {
+        let db = Database::open(PathBuf::from(":memory:")).unwrap();
+        let save = |path: &str, session: &str| {
+            let event = CaptureEvent {
+                tool_name: "Edit".to_string(),
+                file_path: Some(path.to_string()),
+                operation: Operation::Modify,
+                metadata: Some(format!(r#"{{"session_id":"{}"}}"#, session)),
+                ..Default::default()
+            };
+            db.save_event(&event, None, None, None).unwrap()
+        };
+
+        let first = save("a.rs", "s1");
+        let second = save("b.rs", "s1");
+        save("x.rs", "s2");
+        let third = save("c.rs", "s1");
+        let fourth = save("d.rs", "s1");
+        let fifth = save("e.rs", "s1");
+
+        let conn = db.write();
+        let event = |path| query_events_for_file(&conn, path, 1).unwrap().remove(0);
+        let ids = |events: Vec<StoredEvent>| events.iter().map(|e| e.id).collect::<Vec<_>>();
+
+        // Other sessions are skipped
+        let around = query_session_context(&conn, &event("c.rs"), 2).unwrap();
+        assert_eq!(ids(around), vec![first, second, fourth, fifth]);
+
+        // The session's first event has nothing before it
+        let at_start = query_session_context(&conn, &event("a.rs"), 2).unwrap();
+        assert_eq!(ids(at_start), vec![second, third]);
+
+        assert!(query_session_context(&conn, &event("a.rs"), 0)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
@@ -1003,12 +3182,13 @@ mod tests {
             session_id: Some("sess-1".to_string()),
             tool_name: "Edit".to_string(),
             file_path: Some("/project/src/handlers.rs".to_string()),
-            operation: Some("modify".to_string()),
+            operation: Some(Operation::Modify),
             diff_summary: None,
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: Some(r#"{"git_branch": "main"}"#.to_string()),
+            command_category: None,
         };
 
         let score = score_intent_match(&exchange, &event);
@@ -1042,12 +3222,13 @@ mod tests {
             session_id: Some("sess-1".to_string()),
             tool_name: "Write".to_string(),
             file_path: Some("/project/src/auth.rs".to_string()),
-            operation: Some("create".to_string()),
+            operation: Some(Operation::Create),
             diff_summary: None,
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: Some(r#"{"git_branch": "main"}"#.to_string()),
+            command_category: None,
         };
 
         let score = score_intent_match(&exchange, &event);
@@ -1085,16 +3266,17 @@ mod tests {
             session_id: Some("session-intent".to_string()),
             tool_name: "Edit".to_string(),
             file_path: Some("/src/auth/token.rs".to_string()),
-            operation: Some("modify".to_string()),
+            operation: Some(Operation::Modify),
             diff_summary: None,
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: None,
+            command_category: None,
         };
 
         // Find intent
-        let conn = db.conn.lock().unwrap();
+        let conn = db.write();
         let intent = find_intent_for_event(&conn, &event, 5);
 
         assert!(intent.is_some());
@@ -1113,15 +3295,16 @@ mod tests {
             session_id: None, // No session
             tool_name: "Edit".to_string(),
             file_path: Some("/src/main.rs".to_string()),
-            operation: Some("modify".to_string()),
+            operation: Some(Operation::Modify),
             diff_summary: None,
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: None,
+            command_category: None,
         };
 
-        let conn = db.conn.lock().unwrap();
+        let conn = db.write();
         let intent = find_intent_for_event(&conn, &event, 5);
 
         assert!(intent.is_none());
@@ -1139,15 +3322,16 @@ mod tests {
             session_id: Some("orphan-session".to_string()),
             tool_name: "Edit".to_string(),
             file_path: Some("/src/main.rs".to_string()),
-            operation: Some("modify".to_string()),
+            operation: Some(Operation::Modify),
             diff_summary: None,
             raw_input: None,
             ai_summary: None,
             git_commit_sha: None,
             metadata: None,
+            command_category: None,
         };
 
-        let conn = db.conn.lock().unwrap();
+        let conn = db.write();
         let intent = find_intent_for_event(&conn, &event, 5);
 
         assert!(intent.is_none());
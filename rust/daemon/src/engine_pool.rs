@@ -0,0 +1,436 @@
+//! Pool of embedding engines
+//!
+//! ONNX inference dominates search latency, and `EmbeddingEngine::embed`
+//! needs `&mut self`. With a single engine every concurrent search, capture,
+//! and indexing pass queues behind one session. The pool holds several
+//! engines loaded from the same model and hands each caller an idle one,
+//! blocking only when all of them are busy.
+//!
+//! Each engine owns its own ONNX session, tokenizer, and embedding cache, so
+//! every extra engine adds roughly the model's size to the daemon's RSS
+//! (about 90 MB for all-MiniLM-L6-v2).
+//!
+//! The engines can be replaced while the daemon runs. Callers holding an
+//! engine from the old set finish with it; it is dropped when returned.
+//!
+//! The first inference on a fresh ONNX session is several times slower than
+//! the rest while the runtime optimizes the graph and allocates its buffers.
+//! [`EnginePool::warm_up_in_background`] pays that cost at startup instead
+//! of in the first search.
+//!
+//! The pool is generic over the engine so the re-ranking cross-encoders
+//! share the same checkout logic; warm-up, the dimension probe, and the
+//! embedding metrics only apply to embedding engines.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::Instant;
+
+use diachron_embeddings::EmbeddingEngine;
+use tracing::{info, warn};
+
+use crate::metrics::Histogram;
+
+/// Fixed set of engines shared by the daemon.
+pub struct EnginePool<E = EmbeddingEngine> {
+    /// Engines of the current model; replaced whole by [`Self::replace`]
+    engines: RwLock<Arc<Engines<E>>>,
+    /// Texts embedded through the pool
+    embedded: AtomicU64,
+    /// Duration of each `embed`/`embed_batch` call
+    embed_latency: Histogram,
+}
+
+/// Engines loaded from one model.
+struct Engines<E> {
+    /// Each slot's engine; taken out while checked out
+    slots: Vec<Mutex<Option<E>>>,
+    /// Indexes of slots not checked out
+    idle: Mutex<Vec<usize>>,
+    returned: Condvar,
+    /// Length of the embeddings the model produces, measured by the first
+    /// inference; the configured dimension may not match the model
+    dim: OnceLock<usize>,
+    /// Every engine has run an inference
+    warm: AtomicBool,
+}
+
+impl<E> Engines<E> {
+    fn new(engines: Vec<E>, warm: bool) -> Self {
+        let idle = (0..engines.len()).rev().collect();
+        Self {
+            slots: engines.into_iter().map(|e| Mutex::new(Some(e))).collect(),
+            idle: Mutex::new(idle),
+            returned: Condvar::new(),
+            dim: OnceLock::new(),
+            warm: AtomicBool::new(warm),
+        }
+    }
+
+    /// Check out every engine at once, waiting for busy ones; callers
+    /// asking for an engine meanwhile wait as if all were busy.
+    fn check_out_all(&self) -> CheckedOut<'_, E> {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        while idle.len() < self.slots.len() {
+            idle = self
+                .returned
+                .wait(idle)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        CheckedOut {
+            engines: self,
+            slots: std::mem::take(&mut *idle),
+        }
+    }
+}
+
+impl Engines<EmbeddingEngine> {
+    /// Run one throwaway inference on every engine, then mark the set warm.
+    ///
+    /// Checks out all engines at once, waiting for busy ones.
+    ///
+    /// # Errors
+    /// Returns the first inference error; the set stays cold but usable.
+    fn warm_up(&self) -> diachron_embeddings::Result<()> {
+        let checked_out = self.check_out_all();
+        for &slot in &checked_out.slots {
+            let mut engine = self.slots[slot]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(engine) = engine.as_mut() {
+                let embedding = engine.embed("warm-up")?;
+                let _ = self.dim.set(embedding.len());
+            }
+        }
+        self.warm.store(true, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Slots taken off the idle list by [`Engines::check_out_all`]; put back
+/// when dropped, even if an inference panics.
+struct CheckedOut<'a, E> {
+    engines: &'a Engines<E>,
+    slots: Vec<usize>,
+}
+
+impl<E> Drop for CheckedOut<'_, E> {
+    fn drop(&mut self) {
+        let mut idle = self
+            .engines
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        idle.append(&mut self.slots);
+        self.engines.returned.notify_all();
+    }
+}
+
+/// An engine checked out of the pool; returned when dropped.
+pub struct PooledEngine<'a, E = EmbeddingEngine> {
+    pool: &'a EnginePool<E>,
+    /// Set the engine was taken from, which may since have been replaced
+    engines: Arc<Engines<E>>,
+    slot: usize,
+    engine: Option<E>,
+}
+
+impl<E> EnginePool<E> {
+    /// Create a pool from already-loaded engines.
+    ///
+    /// # Arguments
+    /// - `engines`: Engines loaded from the same model; may be empty.
+    pub fn new(engines: Vec<E>) -> Self {
+        Self {
+            engines: RwLock::new(Arc::new(Engines::new(engines, false))),
+            embedded: AtomicU64::new(0),
+            embed_latency: Histogram::new(),
+        }
+    }
+
+    /// Create a pool with no engines (the feature it serves is disabled).
+    pub fn empty() -> Self {
+        Self::new(Vec::new())
+    }
+
+    /// Replace every engine with engines loaded from another model.
+    ///
+    /// Engines checked out of the old set stay usable and are dropped when
+    /// returned; callers waiting for one of them are served from the old set.
+    ///
+    /// # Arguments
+    /// - `engines`: Engines loaded from the same model; may be empty.
+    /// - `warm`: Whether each engine has already run an inference.
+    pub fn replace(&self, engines: Vec<E>, warm: bool) {
+        let engines = Arc::new(Engines::new(engines, warm));
+        *self.engines.write().unwrap_or_else(PoisonError::into_inner) = engines;
+    }
+
+    fn current(&self) -> Arc<Engines<E>> {
+        Arc::clone(&self.engines.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Number of engines in the pool.
+    pub fn size(&self) -> usize {
+        self.current().slots.len()
+    }
+
+    /// Whether at least one engine is loaded.
+    pub fn is_loaded(&self) -> bool {
+        !self.current().slots.is_empty()
+    }
+
+    /// Whether every engine has run its warm-up inference.
+    ///
+    /// # Returns
+    /// `false` if the pool is empty, still warming up, or warm-up failed.
+    pub fn is_warm(&self) -> bool {
+        let engines = self.current();
+        !engines.slots.is_empty() && engines.warm.load(Ordering::Acquire)
+    }
+
+    /// Check out an idle engine, waiting for one if all are busy.
+    ///
+    /// # Returns
+    /// An exclusive engine handle, or `None` if the pool is empty or its
+    /// locks are poisoned.
+    pub fn get(&self) -> Option<PooledEngine<'_, E>> {
+        let engines = self.current();
+        if engines.slots.is_empty() {
+            return None;
+        }
+
+        let mut idle = engines.idle.lock().ok()?;
+        let slot = loop {
+            if let Some(slot) = idle.pop() {
+                break slot;
+            }
+            idle = engines.returned.wait(idle).ok()?;
+        };
+        drop(idle);
+
+        // An earlier caller panicked while returning its engine; the session
+        // itself holds no state between runs, so keep using it
+        let engine = engines.slots[slot]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        Some(PooledEngine {
+            pool: self,
+            engines,
+            slot,
+            engine,
+        })
+    }
+}
+
+impl EnginePool<EmbeddingEngine> {
+    /// Warm up the current engines on a background thread.
+    ///
+    /// Does nothing for an empty pool. A failed warm-up is logged and leaves
+    /// the engines cold; they still serve requests.
+    pub fn warm_up_in_background(&self) {
+        let engines = self.current();
+        if engines.slots.is_empty() {
+            return;
+        }
+        let spawned = std::thread::Builder::new()
+            .name("embedding-warm-up".to_string())
+            .spawn(move || {
+                let started = Instant::now();
+                match engines.warm_up() {
+                    Ok(()) => info!(
+                        "Warmed up {} embedding engine(s) in {:?}",
+                        engines.slots.len(),
+                        started.elapsed()
+                    ),
+                    Err(e) => warn!("Embedding warm-up failed; engines stay cold: {}", e),
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to start embedding warm-up: {}", e);
+        }
+    }
+
+    /// Output dimension of the pooled engines.
+    ///
+    /// Measured from a real embedding rather than taken from the config, so
+    /// a model whose output disagrees with `[embedding] dim` is caught.
+    /// Until warm-up has recorded it, the first call runs a probe inference
+    /// (waiting for an idle engine).
+    ///
+    /// # Returns
+    /// `None` if the pool is empty or the probe inference fails.
+    pub fn dim(&self) -> Option<usize> {
+        let engines = self.current();
+        if let Some(&dim) = engines.dim.get() {
+            return Some(dim);
+        }
+        let mut engine = self.get()?;
+        // Not the warm-up text, which would then be served from the cache
+        match engine.deref_mut().embed("dimension probe") {
+            Ok(embedding) => Some(*engine.engines.dim.get_or_init(|| embedding.len())),
+            Err(e) => {
+                warn!("Embedding dimension probe failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Embedding cache statistics summed across all engines.
+    ///
+    /// Waits for busy engines to be returned.
+    ///
+    /// # Returns
+    /// Tuple of (hits, misses).
+    pub fn cache_stats(&self) -> (u64, u64) {
+        let engines = self.current();
+        // Checked out together, every engine stays in its slot while read
+        let checked_out = engines.check_out_all();
+        checked_out
+            .slots
+            .iter()
+            .filter_map(|&slot| {
+                let engine = engines.slots[slot]
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                engine.as_ref().map(EmbeddingEngine::cache_stats)
+            })
+            .fold((0, 0), |(hits, misses), (h, m)| (hits + h, misses + m))
+    }
+
+    /// Number of texts embedded through the pool.
+    pub fn embedded_texts(&self) -> u64 {
+        self.embedded.load(Ordering::Relaxed)
+    }
+
+    /// Latency of embedding calls made through the pool.
+    pub fn embed_latency(&self) -> &Histogram {
+        &self.embed_latency
+    }
+
+    fn record(&self, texts: usize, started: Instant) {
+        self.embedded.fetch_add(texts as u64, Ordering::Relaxed);
+        self.embed_latency.observe(started.elapsed());
+    }
+}
+
+impl PooledEngine<'_, EmbeddingEngine> {
+    /// Embed one text, recording it in the pool's metrics.
+    ///
+    /// Shadows [`EmbeddingEngine::embed`] so every caller is counted.
+    pub fn embed(&mut self, text: &str) -> diachron_embeddings::Result<Vec<f32>> {
+        let started = Instant::now();
+        let result = self.deref_mut().embed(text);
+        self.pool.record(1, started);
+        result
+    }
+
+    /// Embed a batch of texts, recording it in the pool's metrics.
+    ///
+    /// Shadows [`EmbeddingEngine::embed_batch`] so every caller is counted.
+    pub fn embed_batch(&mut self, texts: &[&str]) -> diachron_embeddings::Result<Vec<Vec<f32>>> {
+        let started = Instant::now();
+        let result = self.deref_mut().embed_batch(texts);
+        self.pool.record(texts.len(), started);
+        result
+    }
+}
+
+impl<E> Deref for PooledEngine<'_, E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.engine.as_ref().expect("engine held until drop")
+    }
+}
+
+impl<E> DerefMut for PooledEngine<'_, E> {
+    fn deref_mut(&mut self) -> &mut E {
+        self.engine.as_mut().expect("engine held until drop")
+    }
+}
+
+impl<E> Drop for PooledEngine<'_, E> {
+    fn drop(&mut self) {
+        // Put the engine back before advertising the slot as idle
+        *self.engines.slots[self.slot]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = self.engine.take();
+        if let Ok(mut idle) = self.engines.idle.lock() {
+            idle.push(self.slot);
+            // Wake `cache_stats` as well as callers waiting for an engine
+            self.engines.returned.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Long enough for a thread that is not blocked to get through.
+    const SETTLE: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_empty_pool_has_no_engine() {
+        let pool = EnginePool::<u32>::empty();
+        assert!(pool.get().is_none());
+        assert!(!pool.is_loaded());
+    }
+
+    #[test]
+    fn test_get_waits_for_a_returned_engine() {
+        let pool = EnginePool::new(vec![7u32]);
+        let held = pool.get().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| tx.send(*pool.get().unwrap()).unwrap());
+
+            assert!(rx.recv_timeout(SETTLE).is_err());
+            drop(held);
+            assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(7));
+        });
+    }
+
+    #[test]
+    fn test_engine_from_replaced_set_returns_to_it() {
+        let pool = EnginePool::new(vec![1u32]);
+        let old = pool.current();
+        let held = pool.get().unwrap();
+
+        pool.replace(vec![2], false);
+        assert_eq!(*pool.get().unwrap(), 2);
+
+        drop(held);
+        assert_eq!(*old.slots[0].lock().unwrap(), Some(1));
+        assert_eq!(*old.idle.lock().unwrap(), vec![0]);
+        assert_eq!(*pool.get().unwrap(), 2);
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[test]
+    fn test_check_out_all_waits_for_busy_engines() {
+        let pool = EnginePool::new(vec![1u32, 2]);
+        let held = pool.get().unwrap();
+
+        std::thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            let engines = pool.current();
+            scope.spawn(move || tx.send(engines.check_out_all().slots.len()).unwrap());
+
+            assert!(rx.recv_timeout(SETTLE).is_err());
+            drop(held);
+            assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(2));
+        });
+
+        // Dropping the guard hands every engine back
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        assert_ne!(*first, *second);
+    }
+}
@@ -0,0 +1,113 @@
+//! Live event feed for `Subscribe` connections
+//!
+//! `diachron watch` keeps one connection open and receives each captured
+//! event as it is written, instead of polling the timeline. Saved events are
+//! published on a broadcast channel; every subscribed connection holds a
+//! receiver and forwards what it gets. With no subscribers publishing is a
+//! no-op, so captures pay nothing for the feature.
+
+use std::time::Duration;
+
+use diachron_core::StoredEvent;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::db::Database;
+
+/// Events buffered per subscriber before the slowest one starts missing
+/// events.
+const FEED_CAPACITY: usize = 1024;
+
+/// Interval between heartbeats on an idle subscription, so clients can tell
+/// a quiet daemon from a dead connection.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Broadcasts newly saved events to subscribers.
+pub struct EventFeed {
+    tx: broadcast::Sender<StoredEvent>,
+}
+
+impl Default for EventFeed {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(FEED_CAPACITY).0,
+        }
+    }
+}
+
+impl EventFeed {
+    /// Start receiving events saved from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<StoredEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Push saved events to every subscriber.
+    ///
+    /// Events are read back from the database so subscribers get the same
+    /// `StoredEvent` a timeline query returns. Skipped entirely when nobody
+    /// is subscribed.
+    ///
+    /// # Arguments
+    /// - `db`: Database the events were written to.
+    /// - `ids`: Row IDs of the saved events, in capture order.
+    pub fn publish(&self, db: &Database, ids: &[i64]) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        for &id in ids {
+            match db.get_event(id) {
+                // Only fails once the last subscriber has gone
+                Ok(Some(detail)) => {
+                    let _ = self.tx.send(detail.event);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load event {} for subscribers: {}", id, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{persist_captures, SaveOutcome};
+    use crate::handlers::tests::temp_dir;
+    use crate::DaemonState;
+    use diachron_core::{CaptureEvent, Operation};
+    use std::sync::Arc;
+
+    fn no_embed(_state: &DaemonState, _texts: &[&str]) -> Option<Vec<Vec<f32>>> {
+        None
+    }
+
+    #[test]
+    fn test_saved_captures_reach_subscribers() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let event = |path: &str| CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+
+        // Nothing is buffered for connections that subscribe later
+        persist_captures(&state, &[event("src/before.rs")], no_embed);
+        let mut rx = state.event_feed.subscribe();
+
+        let saved = persist_captures(&state, &[event("src/a.rs"), event("src/b.rs")], no_embed);
+        let ids: Vec<i64> = saved
+            .into_iter()
+            .map(|outcome| match outcome {
+                SaveOutcome::Saved(id) => id,
+                other => panic!("not saved: {:?}", other),
+            })
+            .collect();
+        let pushed: Vec<StoredEvent> = (0..2).map(|_| rx.try_recv().unwrap()).collect();
+        assert_eq!(pushed.iter().map(|e| e.id).collect::<Vec<_>>(), ids);
+        assert_eq!(pushed[0].file_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(pushed[1].tool_name, "Write");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,25 +1,51 @@
 //! Message handlers for the daemon
 
-use std::collections::HashSet;
-use std::sync::Arc;
-
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use chrono::{DateTime, DurationRound, Local, Utc};
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
 use diachron_core::{
-    fts_search_events, fts_search_exchanges, DiagnosticInfo, IpcMessage, IpcResponse, SearchResult, SearchSource,
+    cosine_similarity, fts_search_events, fts_search_exchanges, lookup_event_hits,
+    lookup_exchange_hits, BlameConfidence, DaemonError, DiagnosticInfo, FingerprintOptions,
+    FtsOptions, IpcMessage, IpcResponse, Operation, SearchCursor, SearchExplanation, SearchFilter,
+    SearchPreference, SearchResult, SearchSource, VectorEntry, VectorIndex, CAPABILITIES,
+    PROTOCOL_VERSION,
 };
+use diachron_embeddings::CrossEncoder;
 
-use crate::cache::{CacheEntry, CacheKey};
+use crate::cache::{CacheEntry, CacheKey, TimelineEntry, TimelineKey};
+use crate::capture::{embed_with_engine, persist_captures, SaveOutcome};
+use crate::config::DaemonConfig;
+use crate::db::{EventFilter, ExchangeFilter, Retention};
 
 use crate::indexer::{
     build_exchange_embed_text, discover_archives, get_mtime, parse_archive, safe_truncate,
     ArchiveState, IndexState,
 };
+use crate::path_filter::PathFilter;
+use crate::reindex;
+use crate::summarization;
+use crate::vector_export;
 use crate::DaemonState;
 
 /// Handle an incoming IPC message
 pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcResponse {
     match msg {
+        IpcMessage::Hello { client_version } => {
+            debug!("Hello from client v{}", client_version);
+            IpcResponse::Hello {
+                daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+            }
+        }
+
         IpcMessage::Ping => {
             debug!("Ping received");
 
@@ -32,9 +58,22 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             }
         }
 
+        IpcMessage::Health => {
+            debug!("Health check received");
+            health_report(state)
+        }
+
+        // Served by the connection loop, which keeps the stream open
+        IpcMessage::Subscribe => IpcResponse::Error(DaemonError::InvalidRequest(
+            "Subscribe requires a dedicated connection".to_string(),
+        )),
+
         IpcMessage::Shutdown => {
             info!("Shutdown requested via IPC");
 
+            // Write queued captures so their vectors make it into the saved index
+            state.capture_queue.flush().await;
+
             // Save vector indexes before shutdown
             if let Err(e) = state.save_indexes() {
                 error!("Failed to save indexes on shutdown: {}", e);
@@ -44,22 +83,79 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             IpcResponse::Ok
         }
 
-        IpcMessage::Maintenance { retention_days } => {
-            info!("Maintenance requested (retention: {} days)", retention_days);
+        IpcMessage::Maintenance {
+            retention_days,
+            events_retention_days,
+            exchanges_retention_days,
+            project_retention_days,
+        } => {
+            let config = &state.config.retention;
+            let uniform = (retention_days > 0).then_some(retention_days);
+            let events_days = events_retention_days
+                .or(uniform)
+                .unwrap_or(config.events_days);
+            let exchanges_days = exchanges_retention_days
+                .or(uniform)
+                .unwrap_or(config.exchanges_days);
+            let projects = if project_retention_days.is_empty() {
+                &config.projects
+            } else {
+                &project_retention_days
+            };
+            info!(
+                "Maintenance requested (retention: events {} days, exchanges {} days, {} project overrides)",
+                events_days,
+                exchanges_days,
+                projects.len()
+            );
             let start = std::time::Instant::now();
 
             // Get size before
             let size_before = state.db.file_size();
 
-            // Prune old data if retention is set
-            let (events_pruned, exchanges_pruned) = if retention_days > 0 {
-                let events = state.db.prune_old_events(retention_days).unwrap_or(0);
-                let exchanges = state.db.prune_old_exchanges(retention_days).unwrap_or(0);
-                info!("Pruned {} events and {} exchanges", events, exchanges);
-                (events, exchanges)
-            } else {
-                (0, 0)
-            };
+            // Prune old data; a retention of 0 keeps everything of that type
+            let event_ids = state
+                .db
+                .prune_old_events(&Retention {
+                    days: events_days,
+                    projects,
+                })
+                .unwrap_or_default();
+            let exchange_ids = state
+                .db
+                .prune_old_exchanges(&Retention {
+                    days: exchanges_days,
+                    projects,
+                })
+                .unwrap_or_default();
+            info!(
+                "Pruned {} events and {} exchanges",
+                event_ids.len(),
+                exchange_ids.len()
+            );
+
+            // Drop vectors for pruned rows so the indexes stay in sync
+            if let Ok(mut idx) = state.events_index.write() {
+                for id in &event_ids {
+                    if let Err(e) = idx.remove(&format!("event:{}", id)) {
+                        warn!("Failed to remove event {} from vector index: {}", id, e);
+                    }
+                }
+            }
+            if let Ok(mut idx) = state.exchanges_index.write() {
+                for id in &exchange_ids {
+                    if let Err(e) = idx.remove(&format!("exchange:{}", id)) {
+                        warn!("Failed to remove exchange {} from vector index: {}", id, e);
+                    }
+                }
+            }
+            if !event_ids.is_empty() || !exchange_ids.is_empty() {
+                if let Err(e) = state.save_indexes() {
+                    error!("Failed to save indexes after pruning: {}", e);
+                }
+            }
+            let events_pruned = event_ids.len() as u64;
+            let exchanges_pruned = exchange_ids.len() as u64;
 
             // Run VACUUM and ANALYZE
             match state.db.vacuum_and_analyze() {
@@ -84,62 +180,210 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                         size_after,
                         events_pruned,
                         exchanges_pruned,
+                        events_retention_days: events_days,
+                        exchanges_retention_days: exchanges_days,
+                        project_overrides: projects.len() as u64,
                         duration_ms,
                     }
                 }
                 Err(e) => {
                     error!("Maintenance failed: {}", e);
-                    IpcResponse::Error(format!("Maintenance failed: {}", e))
+                    IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Maintenance failed: {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        IpcMessage::Integrity => {
+            info!("Integrity check requested");
+            match reindex::integrity_report(state) {
+                Ok(report) => IpcResponse::IntegrityReport(report),
+                Err(e) => {
+                    error!("Integrity check failed: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Integrity check failed: {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        IpcMessage::Forget {
+            project,
+            session,
+            before,
+            query,
+            dry_run,
+        } => {
+            info!(
+                "Forget requested (project: {:?}, session: {:?}, before: {:?}, query: {:?}, dry run: {})",
+                project, session, before, query, dry_run
+            );
+            let filter = ExchangeFilter {
+                project: project.as_deref(),
+                session: session.as_deref(),
+                before: before.as_deref(),
+                query: query.as_deref(),
+            };
+            forget_exchanges(state, filter, dry_run).unwrap_or_else(|e| {
+                error!("Forget failed: {}", e);
+                IpcResponse::Error(e)
+            })
+        }
+
+        IpcMessage::DedupReport { threshold, sample } => {
+            info!(
+                "Dedup report requested (threshold: {}, sample: {:?})",
+                threshold, sample
+            );
+            if !(0.0..=1.0).contains(&threshold) {
+                return IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                    "Threshold must be between 0 and 1, got {}",
+                    threshold
+                )));
+            }
+
+            // One index query per probed vector; keep it off the runtime
+            let state = Arc::clone(state);
+            let report = tokio::task::spawn_blocking(move || {
+                let index = state.exchanges_index.read().unwrap();
+                let indexed = index.len();
+                let probed = sample.map_or(indexed, |n| n.min(indexed));
+                let pairs = index.find_near_duplicates_sampled(threshold, sample);
+                (indexed, probed, pairs)
+            })
+            .await;
+
+            match report {
+                Ok((indexed, probed, pairs)) => {
+                    let clusters = cluster_pairs(&pairs);
+                    let reclaimable = clusters.iter().map(|c| c.len() as u64 - 1).sum();
+                    IpcResponse::DedupReport {
+                        indexed: indexed as u64,
+                        probed: probed as u64,
+                        pairs: pairs.len() as u64,
+                        clusters,
+                        reclaimable,
+                    }
+                }
+                Err(e) => IpcResponse::Error(DaemonError::Internal(format!(
+                    "Dedup report task panicked: {}",
+                    e
+                ))),
+            }
+        }
+
+        IpcMessage::ExportVectors { dir, format } => {
+            info!(
+                "Vector export requested ({:?} to {})",
+                format,
+                dir.display()
+            );
+            // Clients may connect over TCP, so they only choose a directory
+            // under the exports dir; a relative `dir` is taken from there
+            let exports = state.exports_path();
+            let dir = exports.join(dir);
+            if !vector_export::is_within(&exports, &dir) {
+                return IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                    "Export directory must be inside {}, got {}",
+                    exports.display(),
+                    dir.display()
+                )));
+            }
+
+            // Copies every vector out of both indexes; keep it off the runtime
+            let state = Arc::clone(state);
+            let exported = tokio::task::spawn_blocking(move || {
+                let events = state
+                    .events_index
+                    .read()
+                    .map_err(|_| anyhow::anyhow!("events index lock poisoned"))?;
+                let (event_count, mut files) =
+                    vector_export::export_index(&events, &dir.join("events"), format)?;
+                let dim = events.dim();
+                drop(events);
+                let exchanges = state
+                    .exchanges_index
+                    .read()
+                    .map_err(|_| anyhow::anyhow!("exchanges index lock poisoned"))?;
+                let (exchange_count, exchange_files) =
+                    vector_export::export_index(&exchanges, &dir.join("exchanges"), format)?;
+                files.extend(exchange_files);
+                anyhow::Ok(IpcResponse::VectorsExported {
+                    dim,
+                    events: event_count,
+                    exchanges: exchange_count,
+                    files,
+                })
+            })
+            .await;
+
+            match exported {
+                Ok(Ok(response)) => response,
+                Ok(Err(e)) => {
+                    error!("Vector export failed: {:#}", e);
+                    IpcResponse::Error(DaemonError::Internal(format!(
+                        "Vector export failed: {:#}",
+                        e
+                    )))
                 }
+                Err(e) => IpcResponse::Error(DaemonError::Internal(format!(
+                    "Vector export task panicked: {}",
+                    e
+                ))),
             }
         }
 
         IpcMessage::Capture(event) => {
             debug!("Capture event: {:?}", event.tool_name);
 
-            // Build text for embedding from event data
-            let embed_text = build_event_embed_text(&event);
-
-            // Try to generate embedding if engine is available
-            let embedding = if let Ok(mut engine_guard) = state.embedding_engine.write() {
-                if let Some(ref mut engine) = *engine_guard {
-                    match engine.embed(&embed_text) {
-                        Ok(emb) => Some(emb),
-                        Err(e) => {
-                            warn!("Failed to generate embedding: {}", e);
-                            None
-                        }
+            // Hand off to the batch writer; write inline if it isn't running
+            match state.capture_queue.push(event).await {
+                Ok(()) => IpcResponse::Ok,
+                Err(event) => {
+                    if persist_captures(state, &[event], embed_with_engine).is_empty() {
+                        IpcResponse::Error(DaemonError::DatabaseError(
+                            "Database error: failed to save event".to_string(),
+                        ))
+                    } else {
+                        IpcResponse::Ok
                     }
-                } else {
-                    None
                 }
-            } else {
-                None
-            };
+            }
+        }
 
-            // Save to database (with embedding if available)
-            match state.db.save_event(&event, None, embedding.as_deref()) {
-                Ok(id) => {
-                    debug!("Saved event with id: {}", id);
-
-                    // Add to vector index if we have an embedding
-                    if let Some(ref emb) = embedding {
-                        if let Ok(mut idx) = state.events_index.write() {
-                            let event_id = format!("event:{}", id);
-                            if let Err(e) = idx.add(&event_id, emb) {
-                                warn!("Failed to add to vector index: {}", e);
-                            } else {
-                                debug!("Added event {} to vector index", id);
-                            }
-                        }
+        IpcMessage::CaptureBatch(events) => {
+            debug!("Capture batch of {} events", events.len());
+
+            // Queued captures go first so the hash chain keeps arrival order
+            state.capture_queue.flush().await;
+            let count = events.len() as u64;
+            let writer_state = Arc::clone(state);
+            let written = tokio::task::spawn_blocking(move || {
+                persist_captures(&writer_state, &events, embed_with_engine)
+            })
+            .await;
+            match written {
+                Ok(outcomes) => {
+                    let saved = outcomes
+                        .iter()
+                        .filter(|o| matches!(o, SaveOutcome::Saved(_)))
+                        .count() as u64;
+                    let skipped = outcomes.len() as u64 - saved;
+                    IpcResponse::CaptureStats {
+                        saved,
+                        skipped,
+                        failed: count - saved - skipped,
                     }
-
-                    state.increment_events();
-                    IpcResponse::Ok
                 }
                 Err(e) => {
-                    error!("Failed to save event: {}", e);
-                    IpcResponse::Error(format!("Database error: {}", e))
+                    error!("Capture batch of {} failed: {}", count, e);
+                    IpcResponse::Error(DaemonError::Internal(format!(
+                        "Capture batch failed: {}",
+                        e
+                    )))
                 }
             }
         }
@@ -149,17 +393,96 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             limit,
             source_filter,
             since,
+            until,
             project,
+            tool,
+            after,
+            prefer,
+            explain,
+            ..
         } => {
+            let filters = SearchFilters {
+                source: source_filter,
+                since: since.as_deref(),
+                until: until.as_deref(),
+                project: project.as_deref(),
+                tool: tool.as_deref(),
+                prefer,
+                explain,
+            };
             debug!(
-                "Search: {} (limit: {}, filter: {:?}, since: {:?}, project: {:?})",
-                query, limit, source_filter, since, project
+                "Search: {} (limit: {}, after: {:?}, {:?})",
+                query, limit, after, filters
             );
 
-            let results = hybrid_search(state, &query, limit, source_filter, since.as_deref(), project.as_deref()).await;
+            let started = Instant::now();
+            let results = search_page(state, &query, limit, after.as_ref(), filters).await;
+            state.metrics.record_search(started.elapsed());
             IpcResponse::SearchResults(results)
         }
 
+        IpcMessage::Stats { bucket, since } => {
+            debug!("Stats requested (bucket: {:?}, since: {:?})", bucket, since);
+            match state.db.stats(bucket, since.as_deref()) {
+                Ok(stats) => IpcResponse::Stats(stats),
+                Err(e) => {
+                    error!("Failed to compute stats: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Stats query failed: {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        IpcMessage::SessionSummary { session_id } => {
+            debug!("SessionSummary: session={}", session_id);
+
+            let session_id = session_id.trim();
+            if session_id.is_empty() {
+                return IpcResponse::Error(DaemonError::InvalidRequest(
+                    "Session ID is empty".to_string(),
+                ));
+            }
+
+            // Timeline shows short IDs, so a unique prefix is enough
+            let summary = match state.db.find_sessions(session_id, 2) {
+                Ok(ids) if ids.len() > 1 => {
+                    return IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                        "Session prefix '{}' matches more than one session",
+                        session_id
+                    )));
+                }
+                Ok(ids) => match ids.first() {
+                    Some(id) => state.db.session_summary(id),
+                    None => Ok(None),
+                },
+                Err(e) => Err(e),
+            };
+            match summary {
+                Ok(Some(summary)) => IpcResponse::SessionSummary(summary),
+                Ok(None) => IpcResponse::Error(DaemonError::NotFound(format!(
+                    "Session {} not found",
+                    session_id
+                ))),
+                Err(e) => {
+                    error!("Failed to summarize session: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
+                }
+            }
+        }
+
+        IpcMessage::Sessions { since, limit } => {
+            debug!("Sessions: since={:?}, limit={}", since, limit);
+            match state.db.recent_sessions(since.as_deref(), limit) {
+                Ok(sessions) => IpcResponse::Sessions(sessions),
+                Err(e) => {
+                    error!("Failed to list sessions: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
+                }
+            }
+        }
+
         IpcMessage::DoctorInfo => {
             debug!("DoctorInfo requested");
             let info = gather_diagnostic_info(state);
@@ -170,24 +493,144 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             since,
             file_filter,
             limit,
+            file_match,
+            project,
+            operation,
+            category,
+            before_id,
         } => {
             debug!(
-                "Timeline: since={:?}, file={:?}, limit={}",
-                since, file_filter, limit
+                "Timeline: since={:?}, file={:?} ({:?}), limit={}, project={:?}, operation={:?}, category={:?}, before={:?}",
+                since, file_filter, file_match, limit, project, operation, category, before_id
             );
 
-            // Query events from database
-            match state
+            // Resolve relative windows ("1h") now, so the query and the cache
+            // key both see the same absolute cut-off
+            let since = since.map(|s| resolve_timeline_since(s, Local::now()));
+            // Without a data version a cached page could never be invalidated
+            let cache_key = state
                 .db
-                .query_events(since.as_deref(), file_filter.as_deref(), limit)
+                .search_version()
+                .ok()
+                .map(|db_version| TimelineKey {
+                    since: since.clone(),
+                    file_filter: file_filter.clone(),
+                    file_match,
+                    project: project.clone(),
+                    operation: operation.clone(),
+                    category: category.clone(),
+                    before_id,
+                    limit,
+                    db_version,
+                });
+            if let Some(key) = &cache_key {
+                if let Ok(mut cache) = state.timeline_cache.write() {
+                    if let Some(entry) = cache.get(key) {
+                        debug!("Found {} events (cache: hit)", entry.events.len());
+                        return IpcResponse::EventsPage {
+                            events: entry.events,
+                            next_cursor: entry.next_cursor,
+                        };
+                    }
+                }
+            }
+
+            let file = match file_filter
+                .as_deref()
+                .map(|f| PathFilter::new(f, file_match))
+                .transpose()
             {
-                Ok(events) => {
+                Ok(file) => file,
+                Err(e) => return IpcResponse::Error(DaemonError::InvalidRequest(e)),
+            };
+            let filter = EventFilter {
+                since: since.as_deref(),
+                file: file.as_ref(),
+                project: project.as_deref(),
+                operation: operation.as_deref(),
+                category: category.as_deref(),
+                before_id,
+            };
+            // One extra row tells whether another page follows
+            match state.db.query_events(&filter, limit.saturating_add(1)) {
+                Ok(mut events) => {
+                    let next_cursor = if events.len() > limit {
+                        events.truncate(limit);
+                        events.last().map(|e| e.id)
+                    } else {
+                        None
+                    };
                     debug!("Found {} events", events.len());
-                    IpcResponse::Events(events)
+                    if let (Some(key), Ok(mut cache)) = (cache_key, state.timeline_cache.write()) {
+                        cache.insert(
+                            key,
+                            TimelineEntry {
+                                events: events.clone(),
+                                next_cursor,
+                            },
+                        );
+                    }
+                    IpcResponse::EventsPage {
+                        events,
+                        next_cursor,
+                    }
                 }
                 Err(e) => {
                     error!("Failed to query events: {}", e);
-                    IpcResponse::Error(format!("Database error: {}", e))
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
+                }
+            }
+        }
+
+        IpcMessage::EventsForCommit { sha } => {
+            debug!("EventsForCommit: sha={}", sha);
+
+            let sha = sha.trim();
+            if sha.len() < 4 || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                return IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                    "Invalid commit SHA '{}': expected at least 4 hex characters",
+                    sha
+                )));
+            }
+
+            match state.db.query_events_by_commit(sha) {
+                Ok(events) => {
+                    debug!("Found {} events for commit {}", events.len(), sha);
+                    IpcResponse::Events(events)
+                }
+                Err(e) => {
+                    error!("Failed to query events for commit: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
+                }
+            }
+        }
+
+        IpcMessage::Lineage { id } => {
+            debug!("Lineage: id={}", id);
+
+            match state.db.query_event_lineage(id) {
+                Ok(events) if events.is_empty() => {
+                    IpcResponse::Error(DaemonError::NotFound(format!("Event {} not found", id)))
+                }
+                Ok(events) => IpcResponse::Events(events),
+                Err(e) => {
+                    error!("Failed to query event lineage: {}", e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
+                }
+            }
+        }
+
+        IpcMessage::GetEvent { id } => {
+            debug!("GetEvent: id={}", id);
+
+            match state.db.get_event(id) {
+                Ok(Some(detail)) => IpcResponse::Event(detail),
+                Ok(None) => {
+                    IpcResponse::Error(DaemonError::NotFound(format!("Event {} not found", id)))
+                }
+                Err(e) => {
+                    error!("Failed to get event {}: {}", id, e);
+                    IpcResponse::Error(DaemonError::DatabaseError(format!("Database error: {}", e)))
                 }
             }
         }
@@ -200,9 +643,9 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                 Some(home) => home.join(".claude"),
                 None => {
                     error!("Could not determine home directory for archive discovery");
-                    return IpcResponse::Error(
+                    return IpcResponse::Error(DaemonError::Internal(
                         "Could not determine home directory for archive discovery".to_string(),
-                    );
+                    ));
                 }
             };
             let archives = discover_archives(&claude_dir);
@@ -215,6 +658,9 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             let mut total_indexed: u64 = 0;
             let mut archives_processed: u64 = 0;
             let mut errors: u64 = 0;
+            let mut mismatched: u64 = 0;
+            let mut deduplicated: u64 = 0;
+            let dedup_threshold = state.config.index.dedup_threshold;
 
             // 3. Process each archive
             for archive_path in archives {
@@ -234,13 +680,14 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                 };
 
                 // 4. Parse exchanges from archive
-                match parse_archive(&archive_path, start_line) {
+                match parse_archive(&archive_path, start_line, &state.config.index) {
                     Ok(exchanges) => {
                         if exchanges.is_empty() {
                             continue;
                         }
 
                         let mut last_line: u64 = start_line;
+                        let mut batch: Vec<VectorEntry> = Vec::new();
 
                         for exchange in &exchanges {
                             // Track last line for checkpoint
@@ -250,22 +697,15 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
 
                             // 5. Generate embedding
                             let embed_text = build_exchange_embed_text(exchange);
-                            let embedding =
-                                if let Ok(mut engine_guard) = state.embedding_engine.write() {
-                                    if let Some(ref mut engine) = *engine_guard {
-                                        match engine.embed(&embed_text) {
-                                            Ok(emb) => Some(emb),
-                                            Err(e) => {
-                                                warn!("Failed to embed exchange: {}", e);
-                                                None
-                                            }
-                                        }
-                                    } else {
+                            let embedding = state.embedding_engines.get().and_then(|mut engine| {
+                                match engine.embed(&embed_text) {
+                                    Ok(emb) => Some(emb),
+                                    Err(e) => {
+                                        warn!("Failed to embed exchange: {}", e);
                                         None
                                     }
-                                } else {
-                                    None
-                                };
+                                }
+                            });
 
                             // 6. Save to database
                             if let Err(e) = state.db.save_exchange(exchange, embedding.as_deref()) {
@@ -274,19 +714,56 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                                 continue;
                             }
 
-                            // 7. Add to vector index
-                            if let Some(ref emb) = embedding {
-                                if let Ok(mut idx) = state.exchanges_index.write() {
-                                    let exchange_id = format!("exchange:{}", exchange.id);
-                                    if let Err(e) = idx.add(&exchange_id, emb) {
-                                        warn!("Failed to add to vector index: {}", e);
+                            // 7. Queue for the vector index, unless it repeats an
+                            // exchange that is already indexed or queued
+                            if let Some(emb) = embedding {
+                                let key = format!("exchange:{}", exchange.id);
+                                let canonical = dedup_threshold.and_then(|threshold| {
+                                    let idx = state.exchanges_index.read().ok()?;
+                                    find_near_duplicate(&idx, &batch, &key, &emb, threshold)
+                                });
+                                if let Some(canonical) = canonical {
+                                    debug!(
+                                        "Exchange {} duplicates {}; not indexing",
+                                        exchange.id, canonical
+                                    );
+                                    if let Err(e) =
+                                        state.db.mark_exchange_duplicate(&exchange.id, &canonical)
+                                    {
+                                        warn!("Failed to mark exchange {}: {}", exchange.id, e);
                                     }
+                                    deduplicated += 1;
+                                    continue;
                                 }
+                                batch.push((key, emb, exchange.project.clone()));
                             }
 
                             total_indexed += 1;
                         }
 
+                        // Flush this archive's vectors under a single write lock
+                        if !batch.is_empty() {
+                            if let Ok(mut idx) = state.exchanges_index.write() {
+                                match idx.add_batch(&batch) {
+                                    Ok(added) => {
+                                        let skipped = (batch.len() - added) as u64;
+                                        if skipped > 0 {
+                                            warn!(
+                                                "Skipped {} vectors from {} (wrong dimension)",
+                                                skipped, path_str
+                                            );
+                                            errors += skipped;
+                                            mismatched += skipped;
+                                        }
+                                    }
+                                    Err(e) => {
+                                        warn!("Failed to add batch to vector index: {}", e);
+                                        errors += 1;
+                                    }
+                                }
+                            }
+                        }
+
                         // 8. Update checkpoint for this archive
                         index_state
                             .archives
@@ -312,18 +789,140 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                 error!("Failed to save vector indexes: {}", e);
             }
 
+            if mismatched > 0 {
+                reindex::note_dimension_mismatches(state, mismatched);
+            }
+
             info!(
-                "Indexing complete: {} exchanges from {} archives ({} errors)",
-                total_indexed, archives_processed, errors
+                "Indexing complete: {} exchanges from {} archives ({} deduplicated, {} errors)",
+                total_indexed, archives_processed, deduplicated, errors
             );
 
             IpcResponse::IndexStats {
                 exchanges_indexed: total_indexed,
                 archives_processed,
                 errors,
+                deduplicated,
+            }
+        }
+
+        IpcMessage::ImportBatch { events, exchanges } => {
+            match state.db.import_batch(&events, &exchanges) {
+                Ok(counts) => {
+                    info!(
+                        "Imported {} events ({} skipped), {} exchanges ({} skipped)",
+                        counts.events_imported,
+                        counts.events_skipped,
+                        counts.exchanges_imported,
+                        counts.exchanges_skipped
+                    );
+                    IpcResponse::ImportStats {
+                        events_imported: counts.events_imported,
+                        events_skipped: counts.events_skipped,
+                        exchanges_imported: counts.exchanges_imported,
+                        exchanges_skipped: counts.exchanges_skipped,
+                    }
+                }
+                Err(e) => {
+                    error!("Import failed: {}", e);
+                    IpcResponse::Error(DaemonError::from(e).context("Import failed"))
+                }
+            }
+        }
+
+        IpcMessage::Reindex { force } => {
+            if !force && !reindex::indexes_mismatched(state) {
+                let dim = state.events_index.read().map(|idx| idx.dim()).unwrap_or(0);
+                info!("Vector indexes already match the embedding model; skipping rebuild");
+                return IpcResponse::ReindexStats {
+                    rebuilt: false,
+                    dim,
+                    events_indexed: 0,
+                    exchanges_indexed: 0,
+                };
+            }
+
+            info!("Rebuilding vector indexes (force: {})...", force);
+            let state = Arc::clone(state);
+            match tokio::task::spawn_blocking(move || reindex::rebuild_indexes(&state)).await {
+                Ok(Ok(stats)) => {
+                    info!(
+                        "Reindex complete: {} events, {} exchanges at {} dimensions",
+                        stats.events_indexed, stats.exchanges_indexed, stats.dim
+                    );
+                    IpcResponse::ReindexStats {
+                        rebuilt: true,
+                        dim: stats.dim,
+                        events_indexed: stats.events_indexed,
+                        exchanges_indexed: stats.exchanges_indexed,
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Reindex failed: {}", e);
+                    IpcResponse::Error(DaemonError::from(e).context("Reindex failed"))
+                }
+                Err(e) => IpcResponse::Error(DaemonError::Internal(format!(
+                    "Reindex task panicked: {}",
+                    e
+                ))),
+            }
+        }
+
+        IpcMessage::Compact => {
+            info!("Compacting vector indexes...");
+            let state = Arc::clone(state);
+            match tokio::task::spawn_blocking(move || reindex::compact_indexes(&state)).await {
+                Ok(Ok((events, exchanges))) => IpcResponse::CompactStats { events, exchanges },
+                Ok(Err(e)) => {
+                    error!("Compaction failed: {}", e);
+                    IpcResponse::Error(DaemonError::from(e).context("Compaction failed"))
+                }
+                Err(e) => IpcResponse::Error(DaemonError::Internal(format!(
+                    "Compaction task panicked: {}",
+                    e
+                ))),
+            }
+        }
+
+        IpcMessage::Reconcile => {
+            info!("Reconciling vector indexes with the database...");
+            let state = Arc::clone(state);
+            let reconciled = tokio::task::spawn_blocking(move || {
+                let stats = reindex::reconcile_indexes(&state)?;
+                if stats.restored > 0 || stats.removed > 0 {
+                    state.save_indexes()?;
+                    if let Ok(mut cache) = state.search_cache.write() {
+                        cache.clear();
+                    }
+                }
+                Ok::<_, anyhow::Error>(stats)
+            })
+            .await;
+            match reconciled {
+                Ok(Ok(stats)) => {
+                    info!(
+                        "Reconciled vector indexes: restored {} vectors, removed {} stale entries",
+                        stats.restored, stats.removed
+                    );
+                    IpcResponse::ReconcileStats {
+                        restored: stats.restored,
+                        removed: stats.removed,
+                        deferred: stats.deferred,
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Reconciliation failed: {}", e);
+                    IpcResponse::Error(DaemonError::from(e).context("Reconciliation failed"))
+                }
+                Err(e) => IpcResponse::Error(DaemonError::Internal(format!(
+                    "Reconciliation task panicked: {}",
+                    e
+                ))),
             }
         }
 
+        IpcMessage::ReloadModel { model_dir } => reload_model(state, model_dir).await,
+
         IpcMessage::SummarizeExchanges { limit } => {
             info!("Starting exchange summarization (limit: {})...", limit);
 
@@ -331,17 +930,38 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             let summarizer = match &state.summarizer {
                 Some(s) => s,
                 None => {
-                    return IpcResponse::Error(
-                        "Summarization unavailable. Set ANTHROPIC_API_KEY env var or add api_key to ~/.diachron/config.toml".to_string()
-                    );
+                    return IpcResponse::Error(DaemonError::Internal(
+                        "Summarization unavailable. Set ANTHROPIC_API_KEY (or configure provider = \"openai\" / \"ollama\" under [summarization]) in ~/.diachron/config.toml".to_string()
+                    ));
                 }
             };
 
+            // Probing a local provider blocks, so keep it off the runtime threads
+            let probe_state = Arc::clone(state);
+            let ready = tokio::task::spawn_blocking(move || {
+                probe_state
+                    .summarizer
+                    .as_ref()
+                    .is_some_and(summarization::Summarizer::is_available)
+            })
+            .await
+            .unwrap_or(false);
+            if !ready {
+                return IpcResponse::Error(DaemonError::Internal(format!(
+                    "Summarization unavailable ({}). {}.",
+                    summarizer.provider_name(),
+                    summarizer.setup_hint()
+                )));
+            }
+
             // Get exchanges without summaries
             let exchanges = match state.db.get_exchanges_without_summary(limit) {
                 Ok(e) => e,
                 Err(e) => {
-                    return IpcResponse::Error(format!("Database error: {}", e));
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
                 }
             };
 
@@ -350,28 +970,62 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                 return IpcResponse::SummarizeStats {
                     summarized: 0,
                     skipped: 0,
-                    errors: 0,
+                    retried: 0,
+                    failed: 0,
                 };
             }
 
             info!("Found {} exchanges to summarize", exchanges.len());
 
+            let max_retries = summarizer.max_retries();
             let mut summarized: u64 = 0;
             let mut skipped: u64 = 0;
-            let mut errors: u64 = 0;
+            let mut retried: u64 = 0;
+            let mut failed: u64 = 0;
 
+            // Each summary is saved as soon as it arrives, so stopping between
+            // exchanges loses nothing; the rest are picked up by the next run
             for (id, user_msg, assistant_msg) in exchanges {
+                if state.should_shutdown() {
+                    info!("Shutdown requested; stopping summarization early");
+                    break;
+                }
+
                 // Skip if messages are too short to be meaningful
                 if user_msg.len() < 10 || assistant_msg.len() < 10 {
                     skipped += 1;
                     continue;
                 }
 
-                match summarizer.summarize(&user_msg, &assistant_msg) {
+                let mut attempt = 0;
+                let result = loop {
+                    state.metrics.record_summarization();
+                    match summarizer.summarize(&user_msg, &assistant_msg) {
+                        Err(e)
+                            if e.is_retryable()
+                                && attempt < max_retries
+                                && !state.should_shutdown() =>
+                        {
+                            attempt += 1;
+                            let delay = summarization::backoff_delay(attempt);
+                            warn!(
+                                "Summarizing {} failed ({}); retry {}/{} in {:?}",
+                                id, e, attempt, max_retries, delay
+                            );
+                            tokio::time::sleep(delay).await;
+                        }
+                        result => break result,
+                    }
+                };
+                if attempt > 0 {
+                    retried += 1;
+                }
+
+                match result {
                     Ok(summary) => {
                         if let Err(e) = state.db.update_exchange_summary(&id, &summary) {
                             warn!("Failed to save summary for {}: {}", id, e);
-                            errors += 1;
+                            failed += 1;
                         } else {
                             debug!("Summarized {}: {}", id, &summary[..summary.len().min(50)]);
                             summarized += 1;
@@ -379,20 +1033,21 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                     }
                     Err(e) => {
                         warn!("Failed to summarize {}: {}", id, e);
-                        errors += 1;
+                        failed += 1;
                     }
                 }
             }
 
             info!(
-                "Summarization complete: {} summarized, {} skipped, {} errors",
-                summarized, skipped, errors
+                "Summarization complete: {} summarized, {} skipped, {} retried, {} failed",
+                summarized, skipped, retried, failed
             );
 
             IpcResponse::SummarizeStats {
                 summarized,
                 skipped,
-                errors,
+                retried,
+                failed,
             }
         }
 
@@ -402,95 +1057,55 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             content,
             context,
             mode,
+            session_context,
         } => {
-            use diachron_core::fingerprint::{compute_fingerprint, match_fingerprint};
-
             info!(
                 "Blame request: {}:{} mode={}",
                 file_path, line_number, mode
             );
 
-            // Compute fingerprint for the current line content
-            let current_fp = compute_fingerprint(&content, Some(&context), None);
-
-            // Query events that modified this file
-            let conn = state.db.conn.lock().unwrap();
-            let events = match crate::db::query_events_for_file(&conn, &file_path, 100) {
-                Ok(e) => e,
+            let (events, candidates) = match blame_candidates(state, &file_path) {
+                Ok(found) => found,
                 Err(e) => {
-                    return IpcResponse::Error(format!("Database error: {}", e));
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
                 }
             };
-            drop(conn);
 
             if events.is_empty() {
                 return IpcResponse::BlameNotFound {
-                    reason: format!(
-                        "No Diachron events found for file: {}. The line may have been written before Diachron was enabled.",
-                        file_path
-                    ),
+                    reason: no_events_reason(&file_path),
                 };
             }
 
-            // Build fingerprint candidates from events with content_hash
-            let conn = state.db.conn.lock().unwrap();
-            let candidates = crate::db::get_event_fingerprints(&conn, &events);
-            drop(conn);
-
-            // Try fingerprint matching first
-            if !candidates.is_empty() {
-                if let Some(fp_match) = match_fingerprint(&current_fp, &candidates, 0.8) {
-                    // Find the matching event
-                    if let Some(matched_event) = events.iter().find(|e| e.id == fp_match.event_id) {
-                        let confidence = match fp_match.match_type {
-                            diachron_core::fingerprint::MatchType::ContentHash => "high",
-                            diachron_core::fingerprint::MatchType::ContextHash => "medium",
-                            diachron_core::fingerprint::MatchType::SemanticSimilarity => "low",
-                        };
-
-                        // Apply mode filtering
-                        let should_return = match mode.as_str() {
-                            "strict" => confidence == "high",
-                            "best-effort" => confidence == "high" || confidence == "medium",
-                            _ => true, // "inferred" accepts all
-                        };
-
-                        if should_return {
-                            // Extract intent from conversation history (v0.5)
-                            let intent = {
-                                let conn = state.db.conn.lock().unwrap();
-                                crate::db::find_intent_for_event(&conn, matched_event, 5)
-                            };
-
-                            return IpcResponse::BlameResult(diachron_core::BlameMatch {
-                                event: matched_event.clone(),
-                                confidence: confidence.to_string(),
-                                match_type: format!("{:?}", fp_match.match_type),
-                                similarity: fp_match.similarity,
-                                intent,
-                            });
-                        }
+            let options = state.config.fingerprint.options_for(Some(&file_path));
+            if let Some(mut blame) = blame_line(
+                state,
+                &content,
+                &context,
+                &mode,
+                &events,
+                &candidates,
+                &options,
+            ) {
+                // Extract intent from conversation history (v0.5)
+                let conn = match state.db.read() {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                            "Database error: {}",
+                            e
+                        )));
                     }
+                };
+                blame.intent = crate::db::find_intent_for_event(&conn, &blame.event, 5);
+                match crate::db::query_session_context(&conn, &blame.event, session_context) {
+                    Ok(events) => blame.context_events = events,
+                    Err(e) => warn!("Failed to load session context: {}", e),
                 }
-            }
-
-            // Fallback to file-path heuristic (inferred confidence)
-            if mode != "strict" {
-                if let Some(best_match) = events.first() {
-                    // Extract intent from conversation history (v0.5)
-                    let intent = {
-                        let conn = state.db.conn.lock().unwrap();
-                        crate::db::find_intent_for_event(&conn, best_match, 5)
-                    };
-
-                    return IpcResponse::BlameResult(diachron_core::BlameMatch {
-                        event: best_match.clone(),
-                        confidence: "inferred".to_string(),
-                        match_type: "file_path".to_string(),
-                        similarity: 0.5,
-                        intent,
-                    });
-                }
+                return IpcResponse::BlameResult(blame);
             }
 
             IpcResponse::BlameNotFound {
@@ -501,48 +1116,230 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
             }
         }
 
-        IpcMessage::CorrelateEvidence {
-            pr_id,
-            commits,
-            branch,
-            start_time,
-            end_time,
-            intent,
+        IpcMessage::BlameRange {
+            file_path,
+            start,
+            end,
+            lines,
+            lines_start,
+            mode,
+            session_context,
         } => {
-            use diachron_core::pr_correlation::correlate_events_to_pr;
-            use diachron_core::{
-                CommitEvidenceResult, EvidencePackResult, EvidenceSummary, VerificationStatusResult,
-            };
+            use diachron_core::fingerprint::{extract_context, DEFAULT_CONTEXT_LINES};
 
-            debug!(
-                "CorrelateEvidence: PR #{}, {} commits, branch={}",
-                pr_id,
-                commits.len(),
-                branch
+            info!(
+                "Blame range request: {}:{}-{} mode={}",
+                file_path, start, end, mode
             );
 
-            // Get database connection
-            let conn = state.db.conn.lock().unwrap();
+            if start == 0 || end < start || start < lines_start {
+                return IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                    "Invalid line range: {}-{}",
+                    start, end
+                )));
+            }
 
-            // Correlate events to commits
-            match correlate_events_to_pr(&conn, pr_id, &commits, &branch, &start_time, &end_time) {
-                Ok(pr_evidence) => {
-                    // Generate summary
-                    let summary = pr_evidence.summary();
+            let (events, candidates) = match blame_candidates(state, &file_path) {
+                Ok(found) => found,
+                Err(e) => {
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
+                }
+            };
 
-                    // Check verification status from events
-                    let mut tests_executed = false;
-                    let mut build_succeeded = false;
+            if events.is_empty() {
+                return IpcResponse::BlameNotFound {
+                    reason: no_events_reason(&file_path),
+                };
+            }
 
-                    for commit in &pr_evidence.commits {
-                        for event in &commit.events {
-                            if event.tool_name == "Bash" {
-                                if let Some(ref metadata) = event.metadata {
-                                    if let Ok(meta) =
-                                        serde_json::from_str::<serde_json::Value>(metadata)
-                                    {
-                                        if let Some(category) =
-                                            meta.get("command_category").and_then(|c| c.as_str())
+            let window = lines.join("\n");
+            let options = state.config.fingerprint.options_for(Some(&file_path));
+            let mut runs: Vec<diachron_core::BlameRun> = Vec::new();
+            for line_number in start..=end {
+                let idx = (line_number - lines_start) as usize;
+                let content = lines.get(idx).map(String::as_str).unwrap_or("");
+                let context = extract_context(&window, idx, DEFAULT_CONTEXT_LINES);
+                let blame = blame_line(
+                    state,
+                    content,
+                    &context,
+                    &mode,
+                    &events,
+                    &candidates,
+                    &options,
+                );
+
+                let matched_id = blame.as_ref().map(|b| b.event.id);
+                match runs.last_mut() {
+                    Some(run) if run.blame.as_ref().map(|b| b.event.id) == matched_id => {
+                        run.end_line = line_number;
+                        // A run is only as trustworthy as its weakest line
+                        if let (Some(new), Some(existing)) = (&blame, &run.blame) {
+                            if new.score < existing.score {
+                                run.blame = blame;
+                            }
+                        }
+                    }
+                    _ => runs.push(diachron_core::BlameRun {
+                        start_line: line_number,
+                        end_line: line_number,
+                        blame,
+                    }),
+                }
+            }
+
+            if runs.iter().all(|run| run.blame.is_none()) {
+                return IpcResponse::BlameNotFound {
+                    reason: format!(
+                        "No matching event found for {}:{}-{} with mode '{}'",
+                        file_path, start, end, mode
+                    ),
+                };
+            }
+
+            // Extract intent once per run rather than per line (v0.5)
+            let conn = match state.db.read() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
+                }
+            };
+            for blame in runs.iter_mut().filter_map(|run| run.blame.as_mut()) {
+                blame.intent = crate::db::find_intent_for_event(&conn, &blame.event, 5);
+                match crate::db::query_session_context(&conn, &blame.event, session_context) {
+                    Ok(events) => blame.context_events = events,
+                    Err(e) => warn!("Failed to load session context: {}", e),
+                }
+            }
+            drop(conn);
+
+            IpcResponse::BlameRangeResult(runs)
+        }
+
+        IpcMessage::BlameFile { file_path, content } => {
+            info!("Blame file request: {}", file_path);
+
+            let (events, candidates) = match blame_candidates(state, &file_path) {
+                Ok(found) => found,
+                Err(e) => {
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
+                }
+            };
+
+            if events.is_empty() {
+                return IpcResponse::BlameNotFound {
+                    reason: no_events_reason(&file_path),
+                };
+            }
+
+            let options = state.config.fingerprint.options_for(Some(&file_path));
+            let mut runs = blame_file(&content, &events, &candidates, &options);
+            if runs.is_empty() {
+                return IpcResponse::BlameNotFound {
+                    reason: format!("No stored content of {} is in the file", file_path),
+                };
+            }
+
+            // Several runs can belong to one event; look its intent up once
+            let conn = match state.db.read() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
+                }
+            };
+            let mut intents: HashMap<i64, Option<String>> = HashMap::new();
+            for blame in runs.iter_mut().filter_map(|run| run.blame.as_mut()) {
+                blame.intent = intents
+                    .entry(blame.event.id)
+                    .or_insert_with(|| crate::db::find_intent_for_event(&conn, &blame.event, 5))
+                    .clone();
+            }
+            drop(conn);
+
+            IpcResponse::BlameFileResult(runs)
+        }
+
+        IpcMessage::CorrelateEvidence {
+            pr_id,
+            commits,
+            branch,
+            start_time,
+            end_time,
+            intent,
+            time_window_secs,
+            require_file_match,
+            commit_files,
+        } => {
+            use diachron_core::pr_correlation::{correlate_events_to_pr, CorrelationConfig};
+            use diachron_core::{
+                CommitEvidenceResult, EvidencePackResult, EvidenceSummary, VerificationStatusResult,
+            };
+
+            debug!(
+                "CorrelateEvidence: PR #{}, {} commits, branch={}",
+                pr_id,
+                commits.len(),
+                branch
+            );
+
+            let config = CorrelationConfig {
+                time_window_secs: time_window_secs
+                    .unwrap_or(diachron_core::DEFAULT_TIME_WINDOW_SECS),
+                require_file_match,
+                commit_files,
+                ..CorrelationConfig::default()
+            };
+
+            // Get database connection
+            let conn = match state.db.read() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    return IpcResponse::Error(DaemonError::DatabaseError(format!(
+                        "Database error: {}",
+                        e
+                    )));
+                }
+            };
+
+            // Correlate events to commits
+            match correlate_events_to_pr(
+                &conn,
+                pr_id,
+                &commits,
+                &branch,
+                &start_time,
+                &end_time,
+                &config,
+            ) {
+                Ok(pr_evidence) => {
+                    // Generate summary
+                    let summary = pr_evidence.summary();
+
+                    // Check verification status from events
+                    let mut tests_executed = false;
+                    let mut build_succeeded = false;
+
+                    for commit in &pr_evidence.commits {
+                        for event in &commit.events {
+                            if event.tool_name == "Bash" {
+                                if let Some(ref metadata) = event.metadata {
+                                    if let Ok(meta) =
+                                        serde_json::from_str::<serde_json::Value>(metadata)
+                                    {
+                                        if let Some(category) =
+                                            meta.get("command_category").and_then(|c| c.as_str())
                                         {
                                             if category == "test" {
                                                 tests_executed = true;
@@ -557,6 +1354,16 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                         }
                     }
 
+                    // Without a caller-supplied intent, derive one from the
+                    // conversations behind the correlated events
+                    let intent = intent.or_else(|| {
+                        crate::db::find_intent_for_events(
+                            &conn,
+                            pr_evidence.commits.iter().flat_map(|c| &c.events),
+                            10,
+                        )
+                    });
+
                     // Verify hash chain
                     let chain_verified = {
                         match diachron_core::verify_chain(&conn) {
@@ -601,6 +1408,7 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                         intent,
                         coverage_pct: pr_evidence.coverage_pct,
                         unmatched_count: pr_evidence.unmatched_events.len(),
+                        unmatched_events: pr_evidence.unmatched_events,
                         total_events: pr_evidence.total_events,
                     };
 
@@ -609,404 +1417,2982 @@ pub async fn handle_message(msg: IpcMessage, state: &Arc<DaemonState>) -> IpcRes
                 Err(e) => {
                     drop(conn);
                     error!("Failed to correlate events: {}", e);
-                    IpcResponse::Error(format!("Correlation failed: {}", e))
+                    IpcResponse::Error(DaemonError::Internal(format!("Correlation failed: {}", e)))
                 }
             }
         }
     }
 }
 
-/// Build text for embedding from event data
-fn build_event_embed_text(event: &diachron_core::CaptureEvent) -> String {
-    let mut parts = Vec::new();
+/// Run a search and stream its page to `tx`, one result per message.
+///
+/// The page is the one a batched search returns: fused, blended by
+/// `prefer`, boosted for removals, and re-ranked when re-ranking is on, so
+/// streamed and batched pages of the same query hold the same results in
+/// the same order. Each result goes out as soon as the ranking is known,
+/// letting the client print it while the rest are still being sent.
+///
+/// Sends one `SearchResultChunk` per result, then `SearchResultsEnd`, and
+/// stops early if the receiver is dropped (client gone).
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `query`: Search query text.
+/// - `limit`: Maximum number of results.
+/// - `after`: Cursor from the last result of the previous page.
+/// - `filters`: Source, time, project, and tool filters.
+/// - `tx`: Channel the connection handler forwards to the client.
+pub async fn stream_search(
+    state: &Arc<DaemonState>,
+    query: &str,
+    limit: usize,
+    after: Option<&SearchCursor>,
+    filters: SearchFilters<'_>,
+    tx: mpsc::Sender<IpcResponse>,
+) {
+    debug!(
+        "Streaming search: {} (limit: {}, after: {:?}, {:?})",
+        query, limit, after, filters
+    );
 
-    parts.push(format!("Tool: {}", event.tool_name));
+    let started = Instant::now();
+    let results = search_page(state, query, limit, after, filters).await;
+    state.metrics.record_search(started.elapsed());
 
-    if let Some(ref path) = event.file_path {
-        parts.push(format!("File: {}", path));
+    for result in results {
+        if tx
+            .send(IpcResponse::SearchResultChunk(result))
+            .await
+            .is_err()
+        {
+            return;
+        }
     }
+    let _ = tx.send(IpcResponse::SearchResultsEnd).await;
+}
 
-    parts.push(format!("Operation: {}", event.operation.as_str()));
+/// Delete the exchanges a `Forget` request matches, with their vectors.
+///
+/// Afterwards the indexes are reconciled with the database and saved, and
+/// the search cache is cleared so forgotten text cannot come back in results.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the database and indexes.
+/// - `filter`: Which exchanges to delete; `before` is still unparsed.
+/// - `dry_run`: Count the matches without deleting anything.
+///
+/// # Returns
+/// `ForgetStats` with the matched exchanges and their vectors.
+///
+/// # Errors
+/// Returns `DaemonError::InvalidRequest` if no filter is set or `before` is
+/// not a recognized time, and `DaemonError::DatabaseError` if the delete
+/// fails.
+fn forget_exchanges(
+    state: &DaemonState,
+    filter: ExchangeFilter<'_>,
+    dry_run: bool,
+) -> Result<IpcResponse, DaemonError> {
+    if filter.project.is_none()
+        && filter.session.is_none()
+        && filter.before.is_none()
+        && filter.query.is_none()
+    {
+        return Err(DaemonError::InvalidRequest(
+            "Forget requires at least one of project, session, before, or query".to_string(),
+        ));
+    }
+    let before = match filter.before {
+        Some(before) => Some(parse_time_filter(before).ok_or_else(|| {
+            DaemonError::InvalidRequest(format!("Unrecognized time filter '{}'", before))
+        })?),
+        None => None,
+    };
+    let filter = ExchangeFilter {
+        before: before.as_deref(),
+        ..filter
+    };
 
-    if let Some(ref diff) = event.diff_summary {
-        parts.push(format!("Changes: {}", diff));
+    let matched = state.db.forget_exchanges(&filter, dry_run)?;
+    let mut vectors = 0;
+    {
+        let mut idx = state
+            .exchanges_index
+            .write()
+            .map_err(|_| DaemonError::Internal("exchanges index lock poisoned".to_string()))?;
+        for (id, _) in &matched {
+            let key = format!("exchange:{}", id);
+            if dry_run {
+                vectors += idx.contains(&key) as u64;
+                continue;
+            }
+            match idx.remove(&key) {
+                Ok(removed) => vectors += removed as u64,
+                // Reconciling below drops it, since its row is gone
+                Err(e) => warn!("Failed to remove exchange {} from vector index: {}", id, e),
+            }
+        }
     }
 
-    if let Some(ref raw) = event.raw_input {
-        // Truncate raw input to avoid overwhelming the embedding
-        // Uses shared safe_truncate for UTF-8 char boundary handling
-        let truncated = safe_truncate(raw, 500);
-        parts.push(format!("Content: {}", truncated));
+    if !dry_run && !matched.is_empty() {
+        info!("Forgot {} exchanges ({} vectors)", matched.len(), vectors);
+        if let Err(e) = reindex::reconcile_indexes(state) {
+            warn!(
+                "Failed to reconcile indexes after forgetting exchanges: {}",
+                e
+            );
+        }
+        state
+            .save_indexes()
+            .map_err(|e| DaemonError::from(e).context("Failed to save indexes"))?;
+        if let Ok(mut cache) = state.search_cache.write() {
+            cache.clear();
+        }
     }
 
-    parts.join("\n")
+    let exchanges = matched.len() as u64;
+    let projects: BTreeSet<String> = matched.into_iter().filter_map(|(_, p)| p).collect();
+    Ok(IpcResponse::ForgetStats {
+        exchanges,
+        vectors,
+        projects: projects.into_iter().collect(),
+        dry_run,
+    })
 }
 
-/// Perform hybrid search combining vector and FTS results
-async fn hybrid_search(
-    state: &Arc<DaemonState>,
-    query: &str,
-    limit: usize,
-    source_filter: Option<SearchSource>,
-    since: Option<&str>,
-    project: Option<&str>,
-) -> Vec<SearchResult> {
-    // Parse since filter to timestamp if provided
-    let since_timestamp = since.and_then(|s| parse_time_filter(s));
+/// Load the embedding model again and swap it into the engine pool.
+///
+/// The new engines are loaded and warmed up off the async runtime, then
+/// replace the old ones in one step. Searches holding an old engine finish
+/// with it. A model whose dimension does not match the vector indexes is
+/// discarded and the old engines stay in place.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `model_dir`: Model directory overriding `embedding.model_dir`.
+///
+/// # Returns
+/// `ModelReloaded`, or an error if the model cannot be loaded or the
+/// indexes are being rebuilt.
+async fn reload_model(state: &Arc<DaemonState>, model_dir: Option<PathBuf>) -> IpcResponse {
+    let busy = || {
+        IpcResponse::Error(DaemonError::Busy(
+            "Vector indexes are being rebuilt; reload the model once the rebuild finishes"
+                .to_string(),
+        ))
+    };
+    if state.reindexing.load(Ordering::Acquire) {
+        return busy();
+    }
 
-    debug!("Hybrid search with since={:?}, project={:?}", since_timestamp, project);
+    // Read the config file again so an edited `[embedding]` section applies
+    let mut settings = DaemonConfig::load(&state.diachron_home).embedding;
+    if model_dir.is_some() {
+        settings.model_dir = model_dir;
+    }
+    info!(
+        "Reloading embedding model from {}",
+        settings
+            .model_dir
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| "the default model directory".to_string())
+    );
 
-    let db_version = state.db.search_version().unwrap_or_else(|_| "e0:x0".to_string());
-    let cache_key = CacheKey {
-        query: query.to_string(),
-        limit,
-        source_filter: source_filter.map(|s| match s {
-            SearchSource::Event => 0,
-            SearchSource::Exchange => 1,
-        }),
-        since: since.map(str::to_string),
-        project: project.map(str::to_string),
-        db_version,
+    let loaded = tokio::task::spawn_blocking(move || {
+        let mut engines = crate::load_embedding_engines(&settings)?;
+        // Run one inference per engine so the first search is not a cold
+        // start; its length is what the model really produces, whatever
+        // the config says
+        let mut dim = 0;
+        for engine in &mut engines {
+            dim = engine.embed("warm-up")?.len();
+        }
+        Ok::<_, diachron_embeddings::EmbeddingError>((engines, dim))
+    })
+    .await;
+    let (engines, dim) = match loaded {
+        Ok(Ok(loaded)) => loaded,
+        Ok(Err(e)) => {
+            error!("Model reload failed: {}", e);
+            return IpcResponse::Error(DaemonError::ModelNotLoaded(format!(
+                "Failed to load embedding model: {}",
+                e
+            )));
+        }
+        Err(e) => {
+            return IpcResponse::Error(DaemonError::Internal(format!(
+                "Model reload task panicked: {}",
+                e
+            )))
+        }
     };
 
-    if let Ok(mut cache) = state.search_cache.write() {
-        if let Some(entry) = cache.get(&cache_key) {
-            debug!(
-                "Hybrid search returned {} results (vector: {}, fts: {}, cache: hit)",
-                entry.results.len(),
-                entry.embedding_used,
-                true
-            );
-            return entry.results;
+    let pool_size = engines.len();
+    let index_dims = [&state.events_index, &state.exchanges_index]
+        .map(|lock| lock.read().map(|idx| idx.dim()).unwrap_or(0));
+    let index_dim = index_dims.into_iter().find(|&d| d != dim).unwrap_or(dim);
+    let swapped = index_dim == dim;
+    if swapped {
+        if state.reindexing.load(Ordering::Acquire) {
+            return busy();
         }
+        state.embedding_engines.replace(engines, true);
+        if let Ok(mut cache) = state.search_cache.write() {
+            cache.clear();
+        }
+        info!(
+            "Embedding model reloaded ({}-dim, pool of {})",
+            dim, pool_size
+        );
+    } else {
+        warn!(
+            "Reloaded model produces {}-dim embeddings but the indexes hold {}-dim vectors; \
+             keeping the current model",
+            dim, index_dim
+        );
     }
 
-    let query_vec = query.to_string();
-    let query_fts = query_vec.clone();
-    let source_filter_vec = source_filter;
-    let source_filter_fts = source_filter_vec;
+    IpcResponse::ModelReloaded {
+        swapped,
+        dim,
+        index_dim,
+        pool_size,
+    }
+}
 
-    let state_for_vector = Arc::clone(state);
-    let vector_handle = tokio::task::spawn_blocking(move || {
-        let mut results = Vec::new();
-        let mut embedding_used = false;
+/// Check whether the daemon can serve semantic search.
+///
+/// An index is usable when its lock is healthy, no rebuild is running, and
+/// its dimension matches the loaded model. Without a model the daemon is
+/// ready only if `[embedding] allow_degraded` is set.
+fn health_report(state: &DaemonState) -> IpcResponse {
+    let mut warnings = Vec::new();
+    let model_dim = state.embedding_engines.dim();
+    let model_loaded = model_dim.is_some();
+    let model_warm = state.embedding_engines.is_warm();
+    if !model_loaded {
+        warnings
+            .push("Embedding model not loaded; search returns full-text results only".to_string());
+    } else if !model_warm {
+        warnings
+            .push("Embedding engines not warmed up yet; the first search may be slow".to_string());
+    }
 
-        let events_empty = state_for_vector
-            .events_index
-            .read()
-            .map(|idx| idx.is_empty())
-            .unwrap_or(true);
-        let exchanges_empty = state_for_vector
-            .exchanges_index
-            .read()
-            .map(|idx| idx.is_empty())
-            .unwrap_or(true);
-        let should_embed = match source_filter_vec {
-            Some(SearchSource::Event) => !events_empty,
-            Some(SearchSource::Exchange) => !exchanges_empty,
-            None => !(events_empty && exchanges_empty),
+    let rebuilding = state.reindexing.load(Ordering::Acquire);
+    if rebuilding {
+        warnings.push("Vector indexes are being rebuilt".to_string());
+    }
+
+    let mut index_ok = |name: &str, lock: &RwLock<VectorIndex>| {
+        let Ok(idx) = lock.read() else {
+            warnings.push(format!("The {} vector index lock is poisoned", name));
+            return false;
         };
+        match model_dim {
+            Some(dim) if idx.dim() != dim => {
+                warnings.push(format!(
+                    "The {} vector index has {}-dim vectors but the model produces {}; \
+                     run 'diachron memory reindex'",
+                    name,
+                    idx.dim(),
+                    dim
+                ));
+                false
+            }
+            _ => !rebuilding,
+        }
+    };
+    let events_index_ok = index_ok("events", &state.events_index);
+    let exchanges_index_ok = index_ok("exchanges", &state.exchanges_index);
 
-        if !should_embed {
-            return (results, false);
+    let degraded_ok = !model_loaded && state.config.embedding.allow_degraded;
+    if degraded_ok {
+        warnings.push("Running degraded (embedding.allow_degraded)".to_string());
+    }
+
+    IpcResponse::Health {
+        ready: (model_loaded || degraded_ok) && events_index_ok && exchanges_index_ok,
+        model_loaded,
+        model_warm,
+        events_index_ok,
+        exchanges_index_ok,
+        warnings,
+    }
+}
+
+/// Build text for embedding from event data
+pub(crate) fn build_event_embed_text(event: &diachron_core::CaptureEvent) -> String {
+    event_embed_text(
+        &event.tool_name,
+        event.file_path.as_deref(),
+        event.operation.as_str(),
+        event.diff_summary.as_deref(),
+        event.raw_input.as_deref(),
+    )
+}
+
+/// Build text for embedding from the stored fields of an event
+///
+/// Deletions carry little besides the path, so theirs leads the text along
+/// with the file's name, which is what a query about the removal mentions.
+pub(crate) fn event_embed_text(
+    tool_name: &str,
+    file_path: Option<&str>,
+    operation: &str,
+    diff_summary: Option<&str>,
+    raw_input: Option<&str>,
+) -> String {
+    let mut parts = Vec::new();
+
+    match file_path {
+        Some(path) if operation == Operation::Delete.as_str() => {
+            let name = std::path::Path::new(path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(path);
+            parts.push(format!("Deleted file: {} ({})", path, name));
+            parts.push(format!("Tool: {}", tool_name));
+        }
+        Some(path) => {
+            parts.push(format!("Tool: {}", tool_name));
+            parts.push(format!("File: {}", path));
         }
+        None => parts.push(format!("Tool: {}", tool_name)),
+    }
 
-        let query_embedding = if let Ok(mut engine_guard) = state_for_vector.embedding_engine.write()
-        {
-            if let Some(ref mut engine) = *engine_guard {
-                match engine.embed(&query_vec) {
-                    Ok(emb) => {
-                        embedding_used = true;
-                        Some(emb)
-                    }
-                    Err(e) => {
-                        warn!("Failed to embed query: {}", e);
-                        None
-                    }
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+    parts.push(format!("Operation: {}", operation));
 
-        if let Some(ref emb) = query_embedding {
-            if source_filter_vec.is_none() || source_filter_vec == Some(SearchSource::Event) {
-                if let Ok(idx) = state_for_vector.events_index.read() {
-                    match idx.search(emb, limit) {
-                        Ok(vector_results) => {
-                            for vr in vector_results {
-                                if let Some(id_str) = vr.id.strip_prefix("event:") {
-                                    results.push(SearchResult {
-                                        id: id_str.to_string(),
-                                        score: vr.score,
-                                        source: SearchSource::Event,
-                                        snippet: String::new(),
-                                        timestamp: String::new(),
-                                        project: None,
-                                    });
-                                }
-                            }
-                        }
-                        Err(e) => warn!("Vector search failed: {}", e),
-                    }
+    if let Some(diff) = diff_summary {
+        parts.push(format!("Changes: {}", diff));
+    }
+
+    if let Some(raw) = raw_input {
+        // Truncate raw input to avoid overwhelming the embedding
+        // Uses shared safe_truncate for UTF-8 char boundary handling
+        let truncated = safe_truncate(raw, 500);
+        parts.push(format!("Content: {}", truncated));
+    }
+
+    parts.join("\n")
+}
+
+/// Events that touched a file, plus the stored fingerprints of those that have one.
+type BlameCandidates = (
+    Vec<diachron_core::StoredEvent>,
+    Vec<(i64, diachron_core::fingerprint::HunkFingerprint)>,
+);
+
+/// Load the events that touched a file along with their stored fingerprints.
+fn blame_candidates(state: &DaemonState, file_path: &str) -> rusqlite::Result<BlameCandidates> {
+    let conn = state.db.read()?;
+    let events = crate::db::query_events_for_file(&conn, file_path, 100)?;
+    let candidates = crate::db::get_event_fingerprints(&conn, &events);
+    Ok((events, candidates))
+}
+
+fn no_events_reason(file_path: &str) -> String {
+    format!(
+        "No Diachron events found for file: {}. The line may have been written before Diachron was enabled.",
+        file_path
+    )
+}
+
+/// Similarity reported for a line attributed by file path alone.
+const INFERRED_SIMILARITY: f32 = 0.5;
+
+/// Added to a match's score when its event is from the session that last
+/// edited the file.
+const SAME_SESSION_BONUS: f32 = 0.05;
+
+/// Attribute one line to an event.
+///
+/// Tries fingerprint matching first, then falls back to the most recent
+/// event for the file (inferred confidence) unless `mode` is strict.
+/// The returned match has no intent attached. `options` must be those the
+/// file's events were fingerprinted with.
+fn blame_line(
+    state: &DaemonState,
+    content: &str,
+    context: &str,
+    mode: &str,
+    events: &[diachron_core::StoredEvent],
+    candidates: &[(i64, diachron_core::fingerprint::HunkFingerprint)],
+    options: &FingerprintOptions,
+) -> Option<diachron_core::BlameMatch> {
+    use diachron_core::fingerprint::{compute_fingerprint, match_fingerprint, MatchType};
+
+    // Compute fingerprint for the current line content (with a semantic
+    // signature when the embedding engine is loaded)
+    let embedding = if content.trim().is_empty() {
+        None
+    } else {
+        embed_content(state, content)
+    };
+    let current_fp = compute_fingerprint(content, Some(context), embedding, options);
+
+    // Try fingerprint matching first
+    if let Some(fp_match) = match_fingerprint(&current_fp, candidates, 0.8) {
+        if let Some(matched_event) = events.iter().find(|e| e.id == fp_match.event_id) {
+            let (confidence, reason) = match fp_match.match_type {
+                MatchType::ContentHash => (BlameConfidence::High, "content hash exact".to_string()),
+                MatchType::ContextHash => {
+                    (BlameConfidence::Medium, "context hash match".to_string())
                 }
-            }
+                MatchType::SemanticSimilarity => (
+                    BlameConfidence::Low,
+                    format!("semantic similarity {:.2}", fp_match.similarity),
+                ),
+            };
 
-            if source_filter_vec.is_none() || source_filter_vec == Some(SearchSource::Exchange) {
-                if let Ok(idx) = state_for_vector.exchanges_index.read() {
-                    match idx.search(emb, limit) {
-                        Ok(vector_results) => {
-                            for vr in vector_results {
-                                if let Some(id_str) = vr.id.strip_prefix("exchange:") {
-                                    results.push(SearchResult {
-                                        id: id_str.to_string(),
-                                        score: vr.score,
-                                        source: SearchSource::Exchange,
-                                        snippet: String::new(),
-                                        timestamp: String::new(),
-                                        project: None,
-                                    });
-                                }
-                            }
-                        }
-                        Err(e) => warn!("Vector search failed: {}", e),
-                    }
+            // Apply mode filtering
+            let should_return = match mode {
+                "strict" => confidence == BlameConfidence::High,
+                "best-effort" => confidence >= BlameConfidence::Medium,
+                _ => true, // "inferred" accepts all
+            };
+
+            if should_return {
+                let mut reasons = vec![reason, "file path match".to_string()];
+                let mut score = confidence.weight() * fp_match.similarity;
+                // Nothing from a later session has touched the file since
+                let latest_session = events.first().and_then(|e| e.session_id.as_deref());
+                if matched_event.session_id.is_some()
+                    && matched_event.session_id.as_deref() == latest_session
+                {
+                    reasons.push("same session as latest edit".to_string());
+                    score = (score + SAME_SESSION_BONUS).min(1.0);
                 }
+                return Some(diachron_core::BlameMatch {
+                    event: matched_event.clone(),
+                    confidence,
+                    score,
+                    reasons,
+                    match_type: format!("{:?}", fp_match.match_type),
+                    similarity: fp_match.similarity,
+                    intent: None,
+                    context_events: Vec::new(),
+                });
             }
         }
+    }
 
-        (results, embedding_used)
-    });
+    // Fallback to file-path heuristic (inferred confidence)
+    if mode == "strict" {
+        return None;
+    }
+    events.first().map(|best_match| diachron_core::BlameMatch {
+        event: best_match.clone(),
+        confidence: BlameConfidence::Inferred,
+        score: BlameConfidence::Inferred.weight() * INFERRED_SIMILARITY,
+        reasons: vec![
+            "file path match".to_string(),
+            "most recent event for file".to_string(),
+        ],
+        match_type: "file_path".to_string(),
+        similarity: INFERRED_SIMILARITY,
+        intent: None,
+        context_events: Vec::new(),
+    })
+}
 
-    let state_for_fts = Arc::clone(state);
-    let fts_handle = tokio::task::spawn_blocking(move || {
-        let mut results = Vec::new();
-        let conn = match state_for_fts.db.open_readonly() {
-            Ok(conn) => conn,
-            Err(e) => {
-                warn!("Failed to open read-only connection for FTS: {}", e);
-                return results;
-            }
+/// Attribute a whole file's lines to the events whose content is still in it.
+///
+/// Each event's fingerprinted content is located once: lines equal to its
+/// first line are tried as the start of a block, confirmed against the
+/// stored content hash. Content truncated at capture (`raw_input` cut at
+/// `capture.max_raw_input`) cannot match its hash, so its captured lines
+/// are matched as a prefix instead, at medium confidence. Events are
+/// tried newest first and claim only unclaimed lines. Content that starts
+/// or ends mid-line is not located. With whitespace normalization, lines
+/// are compared ignoring indentation, so reindented blocks are still found
+/// (as long as no lines were added or removed).
+///
+/// # Arguments
+/// - `content`: Current file content.
+/// - `events`: Events for the file, newest first.
+/// - `candidates`: Stored fingerprints of those events.
+/// - `options`: Normalization the events were fingerprinted with.
+///
+/// # Returns
+/// Runs of lines attributed to one event, in file order, each with a
+/// match; lines no event wrote are left out. Matches have no intent.
+fn blame_file(
+    content: &str,
+    events: &[diachron_core::StoredEvent],
+    candidates: &[(i64, diachron_core::fingerprint::HunkFingerprint)],
+    options: &FingerprintOptions,
+) -> Vec<diachron_core::BlameRun> {
+    use diachron_core::fingerprint::compute_fingerprint;
+
+    let normalize = |line: &str| {
+        if options.normalize_whitespace {
+            line.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            line.trim_end().to_string()
+        }
+    };
+    let lines: Vec<String> = content.lines().map(normalize).collect();
+    let mut starts: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        starts.entry(line).or_default().push(i);
+    }
+    let fingerprints: HashMap<i64, _> = candidates.iter().map(|(id, fp)| (*id, fp)).collect();
+    let latest_session = events.first().and_then(|e| e.session_id.as_deref());
+
+    // Index into `events` and confidence of each line's owner
+    let mut owners: Vec<Option<(usize, BlameConfidence)>> = vec![None; lines.len()];
+    for (idx, event) in events.iter().enumerate() {
+        let (Some(fingerprint), Some(raw)) = (fingerprints.get(&event.id), &event.raw_input) else {
+            continue;
+        };
+        let truncated = event.raw_input_truncated();
+        let stored = captured_content(raw, truncated);
+        let block: Vec<String> = stored.lines().map(normalize).collect();
+        let Some(first) = block.first() else {
+            continue;
         };
 
-        if source_filter_fts.is_none() || source_filter_fts == Some(SearchSource::Event) {
-            match fts_search_events(&conn, &query_fts, limit) {
-                Ok(fts_results) => {
-                    for fts in fts_results {
-                        results.push(SearchResult {
-                            id: fts.id,
-                            score: (-fts.score) as f32,
-                            source: SearchSource::Event,
-                            snippet: fts.snippet,
-                            timestamp: fts.timestamp,
-                            project: fts.context,
-                        });
-                    }
-                }
-                Err(e) => warn!("FTS events search failed: {}", e),
+        for &start in starts.get(first.as_str()).into_iter().flatten() {
+            let Some(window) = lines.get(start..start + block.len()) else {
+                continue;
+            };
+            let confidence = if compute_fingerprint(&window.join("\n"), None, None, options)
+                .content_hash
+                == fingerprint.content_hash
+            {
+                BlameConfidence::High
+            } else if truncated
+                && window[..block.len() - 1] == block[..block.len() - 1]
+                && window[block.len() - 1].starts_with(&block[block.len() - 1])
+            {
+                BlameConfidence::Medium
+            } else {
+                continue;
+            };
+            for owner in &mut owners[start..start + block.len()] {
+                owner.get_or_insert((idx, confidence));
             }
         }
+    }
 
-        if source_filter_fts.is_none() || source_filter_fts == Some(SearchSource::Exchange) {
-            match fts_search_exchanges(&conn, &query_fts, limit) {
+    let mut runs: Vec<diachron_core::BlameRun> = Vec::new();
+    for (i, owner) in owners.into_iter().enumerate() {
+        let Some((idx, confidence)) = owner else {
+            continue;
+        };
+        let line_number = i as u32 + 1;
+        if let Some(run) = runs.last_mut() {
+            let blame = run.blame.as_ref().expect("file runs are matched");
+            if run.end_line + 1 == line_number
+                && blame.event.id == events[idx].id
+                && blame.confidence == confidence
+            {
+                run.end_line = line_number;
+                continue;
+            }
+        }
+
+        let event = &events[idx];
+        let mut reasons = vec![
+            match confidence {
+                BlameConfidence::High => "content hash exact",
+                _ => "captured prefix match",
+            }
+            .to_string(),
+            "file path match".to_string(),
+        ];
+        let mut score = confidence.weight();
+        if event.session_id.is_some() && event.session_id.as_deref() == latest_session {
+            reasons.push("same session as latest edit".to_string());
+            score = (score + SAME_SESSION_BONUS).min(1.0);
+        }
+        runs.push(diachron_core::BlameRun {
+            start_line: line_number,
+            end_line: line_number,
+            blame: Some(diachron_core::BlameMatch {
+                event: event.clone(),
+                confidence,
+                score,
+                reasons,
+                match_type: match confidence {
+                    BlameConfidence::High => "ContentHash",
+                    _ => "ContentPrefix",
+                }
+                .to_string(),
+                similarity: 1.0,
+                intent: None,
+                context_events: Vec::new(),
+            }),
+        });
+    }
+    runs
+}
+
+/// Content written by a file-modifying event, used for its blame fingerprint.
+pub(crate) fn fingerprint_content(event: &diachron_core::CaptureEvent) -> Option<&str> {
+    event.file_path.as_ref()?;
+    if event.command_category.is_some() {
+        return None;
+    }
+    event
+        .raw_input
+        .as_deref()
+        .map(|raw| captured_content(raw, event.raw_input_truncated))
+        .filter(|content| !content.trim().is_empty())
+}
+
+/// Captured text of `raw_input`, without the `...` a truncated capture
+/// ends in.
+fn captured_content(raw: &str, truncated: bool) -> &str {
+    if truncated {
+        raw.strip_suffix("...").unwrap_or(raw)
+    } else {
+        raw
+    }
+}
+
+/// Find an exchange whose vector is nearly identical to a new one.
+///
+/// Probes the exchange index and the vectors still waiting to be added.
+/// A previously indexed copy of the same exchange (`key`) is ignored.
+///
+/// # Arguments
+/// - `index`: Exchange vector index.
+/// - `pending`: Vectors queued for the index but not yet added.
+/// - `key`: Index key of the new exchange.
+/// - `embedding`: Embedding of the new exchange.
+/// - `threshold`: Minimum cosine similarity to count as a duplicate.
+///
+/// # Returns
+/// ID of the canonical exchange, or `None` if nothing is similar enough.
+fn find_near_duplicate(
+    index: &VectorIndex,
+    pending: &[VectorEntry],
+    key: &str,
+    embedding: &[f32],
+    threshold: f32,
+) -> Option<String> {
+    let indexed = if index.dim() == embedding.len() {
+        index
+            .search(embedding, 2)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|hit| hit.id != key && hit.similarity >= threshold)
+            .map(|hit| hit.id)
+    } else {
+        None
+    };
+
+    indexed
+        .or_else(|| {
+            pending
+                .iter()
+                .find(|(id, vector, _)| {
+                    id != key
+                        && vector.len() == embedding.len()
+                        && cosine_similarity(vector, embedding) >= threshold
+                })
+                .map(|(id, _, _)| id.clone())
+        })
+        .map(|id| id.trim_start_matches("exchange:").to_string())
+}
+
+/// Group near-duplicate pairs into clusters of connected exchange IDs.
+///
+/// # Arguments
+/// - `pairs`: `(key, key, similarity)` triples from the exchanges index.
+///
+/// # Returns
+/// Clusters of exchange IDs (without the `exchange:` key prefix), each
+/// sorted, largest cluster first.
+fn cluster_pairs(pairs: &[(String, String, f32)]) -> Vec<Vec<String>> {
+    fn root(parent: &HashMap<String, String>, id: &str) -> String {
+        let mut id = id.to_string();
+        while let Some(next) = parent.get(&id).filter(|p| **p != id) {
+            id = next.clone();
+        }
+        id
+    }
+
+    let mut parent: HashMap<String, String> = HashMap::new();
+    for (a, b, _) in pairs {
+        for id in [a, b] {
+            parent.entry(id.clone()).or_insert_with(|| id.clone());
+        }
+        let (ra, rb) = (root(&parent, a), root(&parent, b));
+        if ra != rb {
+            let (keep, merge) = if ra < rb { (ra, rb) } else { (rb, ra) };
+            parent.insert(merge, keep);
+        }
+    }
+
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    for id in parent.keys() {
+        groups
+            .entry(root(&parent, id))
+            .or_default()
+            .push(id.trim_start_matches("exchange:").to_string());
+    }
+
+    let mut clusters: Vec<Vec<String>> = groups.into_values().collect();
+    for cluster in &mut clusters {
+        cluster.sort();
+    }
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    clusters
+}
+
+/// Embed text for a semantic signature, if the embedding engine is loaded.
+fn embed_content(state: &DaemonState, text: &str) -> Option<Vec<f32>> {
+    let mut engine = state.embedding_engines.get()?;
+    match engine.embed(text) {
+        Ok(emb) => Some(emb),
+        Err(e) => {
+            warn!("Failed to embed content for fingerprint: {}", e);
+            None
+        }
+    }
+}
+
+/// Filters of a search request, as sent by the client.
+///
+/// `since` and `until` accept the formats of [`parse_time_filter`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchFilters<'a> {
+    pub source: Option<SearchSource>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    /// Project name (substring, case-insensitive)
+    pub project: Option<&'a str>,
+    /// Event tool name; excludes exchanges
+    pub tool: Option<&'a str>,
+    /// Ranking preference, overriding the configured blend weights
+    pub prefer: Option<SearchPreference>,
+    /// Keep each result's retrieval provenance
+    pub explain: bool,
+}
+
+impl SearchFilters<'_> {
+    fn includes_events(&self) -> bool {
+        self.source.is_none() || self.source == Some(SearchSource::Event)
+    }
+
+    fn includes_exchanges(&self) -> bool {
+        self.tool.is_none()
+            && (self.source.is_none() || self.source == Some(SearchSource::Exchange))
+    }
+}
+
+/// Score multiplier for delete events when the query asks about a removal.
+const DELETE_QUERY_BOOST: f32 = 1.25;
+
+/// Query words that ask about a removal.
+const REMOVAL_TERMS: &[&str] = &[
+    "delete", "deleted", "deleting", "deletion", "remove", "removed", "removing", "removal", "rm",
+    "drop", "dropped", "dropping",
+];
+
+/// Vector hits fetched per requested result while SQL filters are active,
+/// since the filters drop some of the nearest neighbours.
+const FILTERED_VECTOR_OVERSAMPLE: usize = 4;
+
+/// Depth of the ranking search pages are cut from. Every page of a query
+/// ranks the same candidates, so its scores and order are the same on each
+/// request and a cursor picks up exactly where the previous page ended.
+const SEARCH_RANK_DEPTH: usize = 100;
+
+/// Fused results the cross-encoder re-scores per requested result. Every
+/// candidate is a full model inference, so the re-ranked head follows the
+/// page size rather than [`SEARCH_RANK_DEPTH`].
+const RERANK_OVERSAMPLE: usize = 3;
+
+/// One page of hybrid search results.
+///
+/// RRF scores depend on how deep each retriever goes, so every page ranks
+/// to the same depth, [`SEARCH_RANK_DEPTH`] (or `limit` if larger), rather
+/// than deepening the search for later pages. With re-ranking on, the
+/// cross-encoder re-orders the top `RERANK_OVERSAMPLE * limit` of that
+/// ranking, the same head for every page of the same size. The page starts
+/// after the cursor's result; if that result has dropped out of the
+/// ranking, after the first result that sorts below the cursor's score and
+/// key. Pages end with the ranking.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `query`: Search query text.
+/// - `limit`: Maximum number of results.
+/// - `after`: Cursor from the last result of the previous page.
+/// - `filters`: Source, time, project, and tool filters.
+///
+/// # Returns
+/// Up to `limit` results following `after` in the fused ranking, with
+/// provenance only if `filters.explain` is set.
+async fn search_page(
+    state: &Arc<DaemonState>,
+    query: &str,
+    limit: usize,
+    after: Option<&SearchCursor>,
+    filters: SearchFilters<'_>,
+) -> Vec<SearchResult> {
+    let depth = SEARCH_RANK_DEPTH.max(limit);
+    let rerank_depth = limit.saturating_mul(RERANK_OVERSAMPLE).min(depth);
+    let ranked = hybrid_search(state, query, depth, rerank_depth, filters).await;
+    let start = after.map_or(0, |cursor| page_start(&ranked, cursor));
+    ranked
+        .into_iter()
+        .skip(start)
+        .take(limit)
+        .map(|mut result| {
+            if !filters.explain {
+                result.explain = None;
+            }
+            result
+        })
+        .collect()
+}
+
+/// Index of the first result after `cursor` in a ranking.
+fn page_start(ranked: &[SearchResult], cursor: &SearchCursor) -> usize {
+    if let Some(i) = ranked
+        .iter()
+        .position(|r| r.source == cursor.source && r.id == cursor.id)
+    {
+        return i + 1;
+    }
+    let cursor_key = rank_key(cursor.source, &cursor.id);
+    ranked
+        .iter()
+        .position(|r| {
+            r.score < cursor.score || (r.score == cursor.score && result_key(r) > cursor_key)
+        })
+        .unwrap_or(ranked.len())
+}
+
+/// Perform hybrid search combining vector and FTS results
+///
+/// Every result carries its provenance in `explain`, whether or not the
+/// request asked for it, so explained and plain searches share cache
+/// entries.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `query`: Search query text.
+/// - `limit`: Maximum number of results.
+/// - `rerank_depth`: How many leading fused results the cross-encoder
+///   re-orders when re-ranking is on.
+/// - `filters`: Source, time, project, and tool filters.
+async fn hybrid_search(
+    state: &Arc<DaemonState>,
+    query: &str,
+    limit: usize,
+    rerank_depth: usize,
+    filters: SearchFilters<'_>,
+) -> Vec<SearchResult> {
+    // Resolve the time window to instants; both retrieval paths filter on it
+    // in SQL, formatted to match how each table stores timestamps
+    let since_timestamp = filters.since.and_then(diachron_core::time::parse_since);
+    let until_timestamp = filters.until.and_then(diachron_core::time::parse_since);
+    let tool = filters.tool.map(str::to_string);
+    let project = filters.project;
+
+    debug!(
+        "Hybrid search with since={:?}, until={:?}, project={:?}, tool={:?}",
+        since_timestamp, until_timestamp, project, tool
+    );
+
+    // Keyed on the resolved window, so "1h" asked an hour apart misses; not
+    // cached at all without a data version to invalidate the entry
+    let cache_key = state.db.search_version().ok().map(|db_version| CacheKey {
+        query: query.to_string(),
+        limit,
+        rerank_depth,
+        source_filter: filters.source.map(|s| match s {
+            SearchSource::Event => 0,
+            SearchSource::Exchange => 1,
+        }),
+        since: since_timestamp.map(diachron_core::time::format_utc),
+        until: until_timestamp.map(diachron_core::time::format_utc),
+        project: project.map(str::to_string),
+        tool: tool.clone(),
+        prefer: filters.prefer,
+        db_version,
+    });
+
+    if let Some(key) = &cache_key {
+        if let Ok(mut cache) = state.search_cache.write() {
+            if let Some(entry) = cache.get(key) {
+                debug!(
+                    "Hybrid search returned {} results (vector: {}, fts: {}, cache: hit)",
+                    entry.results.len(),
+                    entry.embedding_used,
+                    true
+                );
+                return entry.results;
+            }
+        }
+    }
+
+    let sources = (filters.includes_events(), filters.includes_exchanges());
+    let window = (since_timestamp, until_timestamp, tool);
+    let vector_handle = spawn_vector_search(state, query, limit, sources, window.clone(), project);
+    let fts_handle = spawn_fts_search(state, query, limit, sources, window);
+
+    let (vector_results, embedding_used) = match vector_handle.await {
+        Ok((results, used)) => (results, used),
+        Err(e) => {
+            warn!("Vector search task failed: {}", e);
+            (Vec::new(), false)
+        }
+    };
+    let fts_results = match fts_handle.await {
+        Ok(results) => results,
+        Err(e) => {
+            warn!("FTS search task failed: {}", e);
+            Vec::new()
+        }
+    };
+
+    let blend = filters
+        .prefer
+        .map(SearchPreference::weights)
+        .or_else(|| state.config.search.blend_weights());
+    let mut results = match blend {
+        Some((vector_weight, fts_weight)) => {
+            weighted_fusion(vector_results, fts_results, vector_weight, fts_weight)
+        }
+        None => {
+            reciprocal_rank_fusion(vec![vector_results, fts_results], state.config.search.rrf_k)
+        }
+    };
+
+    // Favor deletions when the query asks about one
+    if mentions_removal(query) {
+        let event_ids: Vec<&str> = results
+            .iter()
+            .filter(|r| r.source == SearchSource::Event)
+            .map(|r| r.id.as_str())
+            .collect();
+        match state.db.delete_event_ids(&event_ids) {
+            Ok(deletes) => boost_deletions(&mut results, &deletes),
+            Err(e) => warn!("Failed to look up delete events: {}", e),
+        }
+    }
+
+    // 3. Filter by project
+    retain_project(&mut results, project);
+
+    // 4. Re-rank the head with a pooled cross-encoder, if enabled
+    if state.reranker.is_loaded() {
+        let state_for_rerank = Arc::clone(state);
+        let query = query.to_string();
+        let fused = results.clone();
+        match tokio::task::spawn_blocking(move || {
+            let Some(mut reranker) = state_for_rerank.reranker.get() else {
+                return fused;
+            };
+            rerank(fused, rerank_depth, |snippets| {
+                reranker.score_batch(&query, snippets)
+            })
+        })
+        .await
+        {
+            Ok(reranked) => results = reranked,
+            Err(e) => warn!("Re-ranking task failed, keeping fused order: {}", e),
+        }
+    }
+
+    // 5. Limit (already sorted)
+    results.truncate(limit);
+    for result in &mut results {
+        if let Some(explain) = &mut result.explain {
+            explain.final_score = result.score;
+        }
+    }
+
+    if let (Some(key), Ok(mut cache)) = (cache_key, state.search_cache.write()) {
+        cache.insert(
+            key,
+            CacheEntry {
+                results: results.clone(),
+                embedding_used,
+            },
+        );
+    }
+
+    debug!(
+        "Hybrid search returned {} results (vector: {}, fts: {}, cache: miss)",
+        results.len(),
+        embedding_used,
+        true
+    );
+
+    results
+}
+
+/// Resolved `since` and `until` instants and tool name of a search,
+/// owned so the retrieval tasks can take them.
+type SearchWindow = (Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<String>);
+
+/// Start the vector half of a search on the blocking pool.
+///
+/// Embeds the query with a pooled engine, unless no index to search has
+/// any vectors.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `query`: Search query text.
+/// - `limit`: Maximum hits per source.
+/// - `sources`: Whether to search (events, exchanges).
+/// - `window`: Time window and tool filter.
+/// - `project`: Project filter applied inside the indexes.
+///
+/// # Returns
+/// A task resolving to the hits, ordered by similarity per source, and
+/// whether the query was embedded.
+fn spawn_vector_search(
+    state: &Arc<DaemonState>,
+    query: &str,
+    limit: usize,
+    sources: (bool, bool),
+    window: SearchWindow,
+    project: Option<&str>,
+) -> tokio::task::JoinHandle<(Vec<SearchResult>, bool)> {
+    let state_for_vector = Arc::clone(state);
+    let query_vec = query.to_string();
+    let project_for_vector = project.map(str::to_string);
+    let (include_events, include_exchanges) = sources;
+    tokio::task::spawn_blocking(move || {
+        let events_empty = state_for_vector
+            .events_index
+            .read()
+            .map(|idx| idx.is_empty())
+            .unwrap_or(true);
+        let exchanges_empty = state_for_vector
+            .exchanges_index
+            .read()
+            .map(|idx| idx.is_empty())
+            .unwrap_or(true);
+        let should_embed =
+            (include_events && !events_empty) || (include_exchanges && !exchanges_empty);
+
+        if !should_embed {
+            return (Vec::new(), false);
+        }
+
+        let Some(mut engine) = state_for_vector.embedding_engines.get() else {
+            return (Vec::new(), false);
+        };
+        let query_embedding = match engine.embed(&query_vec) {
+            Ok(emb) => emb,
+            Err(e) => {
+                warn!("Failed to embed query: {}", e);
+                return (Vec::new(), false);
+            }
+        };
+        drop(engine);
+
+        let (since, until, tool) = &window;
+        let filter = SearchFilter {
+            since: *since,
+            until: *until,
+            tool: tool.as_deref(),
+        };
+        let results = vector_search(
+            &state_for_vector,
+            &query_embedding,
+            limit,
+            (include_events, include_exchanges),
+            &filter,
+            project_for_vector.as_deref(),
+        );
+        (results, true)
+    })
+}
+
+/// Start the full-text half of a search on the blocking pool.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `query`: Search query text.
+/// - `limit`: Maximum hits per source.
+/// - `sources`: Whether to search (events, exchanges).
+/// - `window`: Time window and tool filter.
+///
+/// # Returns
+/// A task resolving to the hits, scored by [`bm25_to_similarity`], events
+/// first.
+fn spawn_fts_search(
+    state: &Arc<DaemonState>,
+    query: &str,
+    limit: usize,
+    sources: (bool, bool),
+    window: SearchWindow,
+) -> tokio::task::JoinHandle<Vec<SearchResult>> {
+    let state_for_fts = Arc::clone(state);
+    let query_fts = query.to_string();
+    let (include_events, include_exchanges) = sources;
+    let exchange_weights = state.config.search.fts.exchange_weights();
+    tokio::task::spawn_blocking(move || {
+        let mut results = Vec::new();
+        let conn = match state_for_fts.db.read() {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Failed to open read-only connection for FTS: {}", e);
+                return results;
+            }
+        };
+        let (since, until, tool) = &window;
+        let filter = SearchFilter {
+            since: *since,
+            until: *until,
+            tool: tool.as_deref(),
+        };
+
+        if include_events {
+            match fts_search_events(&conn, &query_fts, limit, &FtsOptions::events(), &filter) {
+                Ok(fts_results) => {
+                    for fts in fts_results {
+                        results.push(SearchResult {
+                            id: fts.id,
+                            score: bm25_to_similarity(fts.score),
+                            source: SearchSource::Event,
+                            snippet: fts.snippet,
+                            timestamp: fts.timestamp,
+                            project: fts.context,
+                            explain: Some(SearchExplanation::fts(bm25_to_similarity(fts.score))),
+                        });
+                    }
+                }
+                Err(e) => warn!("FTS events search failed: {}", e),
+            }
+        }
+
+        if include_exchanges {
+            match fts_search_exchanges(
+                &conn,
+                &query_fts,
+                limit,
+                &FtsOptions::exchanges(),
+                &exchange_weights,
+                &filter,
+            ) {
                 Ok(fts_results) => {
                     for fts in fts_results {
                         results.push(SearchResult {
                             id: fts.id,
-                            score: (-fts.score) as f32,
+                            score: bm25_to_similarity(fts.score),
                             source: SearchSource::Exchange,
                             snippet: fts.snippet,
                             timestamp: fts.timestamp,
                             project: fts.context,
+                            explain: Some(SearchExplanation::fts(bm25_to_similarity(fts.score))),
                         });
                     }
                 }
-                Err(e) => warn!("FTS exchanges search failed: {}", e),
+                Err(e) => warn!("FTS exchanges search failed: {}", e),
+            }
+        }
+
+        results
+    })
+}
+
+/// Keep only results whose project contains `project` (case-insensitive).
+fn retain_project(results: &mut Vec<SearchResult>, project: Option<&str>) {
+    if let Some(proj) = project {
+        let proj = proj.to_lowercase();
+        results.retain(|r| {
+            r.project
+                .as_ref()
+                .is_some_and(|result_proj| result_proj.to_lowercase().contains(&proj))
+        });
+    }
+}
+
+/// Scores documents against a query for re-ranking.
+pub trait RelevanceScorer: Send {
+    /// Score several documents against the same query.
+    ///
+    /// # Returns
+    /// One score per document in input order; higher is more relevant.
+    ///
+    /// # Errors
+    /// Returns `EmbeddingError` if tokenization or inference fails.
+    fn score_batch(&mut self, query: &str, docs: &[&str]) -> diachron_embeddings::Result<Vec<f32>>;
+}
+
+impl RelevanceScorer for CrossEncoder {
+    fn score_batch(&mut self, query: &str, docs: &[&str]) -> diachron_embeddings::Result<Vec<f32>> {
+        CrossEncoder::score_batch(self, query, docs)
+    }
+}
+
+/// Re-order the first `depth` results by a relevance score of their snippets.
+///
+/// Results past `depth` follow in fused order, and equal scores keep their
+/// fused order. If scoring fails the fused order is kept.
+///
+/// # Arguments
+/// - `results`: Fused results, best first.
+/// - `depth`: How many leading results to re-rank.
+/// - `score`: Scores the snippets against the query; higher is better.
+///
+/// # Returns
+/// The results with their head re-ordered.
+fn rerank<E: std::fmt::Display>(
+    mut results: Vec<SearchResult>,
+    depth: usize,
+    score: impl FnOnce(&[&str]) -> Result<Vec<f32>, E>,
+) -> Vec<SearchResult> {
+    let depth = depth.min(results.len());
+    if depth < 2 {
+        return results;
+    }
+
+    let snippets: Vec<&str> = results[..depth]
+        .iter()
+        .map(|r| r.snippet.as_str())
+        .collect();
+    let scores = match score(&snippets) {
+        Ok(scores) if scores.len() == depth => scores,
+        Ok(scores) => {
+            warn!(
+                "Re-ranker returned {} scores for {} results, keeping fused order",
+                scores.len(),
+                depth
+            );
+            return results;
+        }
+        Err(e) => {
+            warn!("Re-ranking failed, keeping fused order: {}", e);
+            return results;
+        }
+    };
+
+    let mut head: Vec<(f32, SearchResult)> =
+        scores.into_iter().zip(results.drain(..depth)).collect();
+    head.sort_by(|a, b| b.0.total_cmp(&a.0));
+    head.into_iter()
+        .map(|(_, result)| result)
+        .chain(results)
+        .collect()
+}
+
+/// Nearest neighbours of a query embedding in the event and exchange indexes.
+///
+/// The indexes only hold IDs, so hits are looked up in the database to fill
+/// in their timestamp and project and to apply `filter`. Hits whose rows are
+/// gone or fail the filter are dropped.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the indexes and database.
+/// - `embedding`: Query embedding.
+/// - `limit`: Maximum hits per source.
+/// - `sources`: Whether to search (events, exchanges).
+/// - `filter`: Time window and tool filter.
+/// - `project`: Project filter, applied inside the index to vectors that
+///   carry metadata (event file path, exchange project), so hits from
+///   other projects do not use up the `limit`. Vectors without metadata
+///   pass and are left to the caller's project filter.
+///
+/// # Returns
+/// Event hits followed by exchange hits, each ordered by similarity.
+fn vector_search(
+    state: &DaemonState,
+    embedding: &[f32],
+    limit: usize,
+    (include_events, include_exchanges): (bool, bool),
+    filter: &SearchFilter,
+    project: Option<&str>,
+) -> Vec<SearchResult> {
+    let k = if filter.is_active() {
+        limit * FILTERED_VECTOR_OVERSAMPLE
+    } else {
+        limit
+    };
+    let conn = match state.db.read() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Failed to open read-only connection for vector hits: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let project = project.map(str::to_lowercase);
+    let mut results = Vec::new();
+    for (include, index, prefix, source) in [
+        (
+            include_events,
+            &state.events_index,
+            "event:",
+            SearchSource::Event,
+        ),
+        (
+            include_exchanges,
+            &state.exchanges_index,
+            "exchange:",
+            SearchSource::Exchange,
+        ),
+    ] {
+        if !include {
+            continue;
+        }
+        let searched = match index.read() {
+            // An index built with another model cannot be searched with
+            // this embedding
+            Ok(idx) if idx.dim() != embedding.len() => {
+                debug!(
+                    "Skipping {:?} index: dimension {} does not match query {}",
+                    source,
+                    idx.dim(),
+                    embedding.len()
+                );
+                continue;
+            }
+            Ok(idx) => match &project {
+                // Vectors stored without metadata pass here; the project
+                // filter after fusion decides for them
+                Some(project) => idx.filtered_search(embedding, k, |_, metadata| {
+                    metadata.is_none_or(|metadata| metadata.to_lowercase().contains(project))
+                }),
+                None => idx.search(embedding, k),
+            },
+            Err(_) => continue,
+        };
+        let hits: Vec<(String, f32)> = match searched {
+            Ok(hits) => hits
+                .into_iter()
+                .filter_map(|vr| Some((vr.id.strip_prefix(prefix)?.to_string(), vr.similarity)))
+                .collect(),
+            Err(e) => {
+                warn!("Vector search failed: {}", e);
+                continue;
+            }
+        };
+
+        let ids: Vec<&str> = hits.iter().map(|(id, _)| id.as_str()).collect();
+        let looked_up = match source {
+            SearchSource::Event => lookup_event_hits(&conn, &ids, filter),
+            SearchSource::Exchange => lookup_exchange_hits(&conn, &ids, filter),
+        };
+        let mut info = match looked_up {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Failed to look up vector hits: {}", e);
+                continue;
+            }
+        };
+
+        results.extend(
+            hits.into_iter()
+                .filter_map(|(id, score)| {
+                    let hit = info.remove(&id)?;
+                    Some(SearchResult {
+                        id,
+                        score,
+                        source,
+                        snippet: String::new(),
+                        timestamp: hit.timestamp,
+                        project: hit.context,
+                        explain: Some(SearchExplanation::vector(score)),
+                    })
+                })
+                .take(limit),
+        );
+    }
+    results
+}
+
+/// Whether a query contains one of the [`REMOVAL_TERMS`].
+fn mentions_removal(query: &str) -> bool {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| REMOVAL_TERMS.contains(&word.to_lowercase().as_str()))
+}
+
+/// Multiply the scores of delete events by [`DELETE_QUERY_BOOST`] and
+/// re-sort.
+///
+/// # Arguments
+/// - `results`: Fused results, sorted by score.
+/// - `deletes`: IDs of the event results that are deletions.
+fn boost_deletions(results: &mut [SearchResult], deletes: &HashSet<String>) {
+    if deletes.is_empty() {
+        return;
+    }
+    for result in results.iter_mut() {
+        if result.source == SearchSource::Event && deletes.contains(&result.id) {
+            result.score *= DELETE_QUERY_BOOST;
+        }
+    }
+    sort_fused(results);
+}
+
+/// Merge ranked result lists with Reciprocal Rank Fusion.
+///
+/// Each list is ranked by its own score; a document's fused score is
+/// `sum(1 / (k + rank))` over every list it appears in (ranks start at 1),
+/// so hits found by both retrieval paths rise above single-path hits.
+/// Duplicates are merged, filling empty snippet/timestamp/project fields
+/// from whichever list has them.
+///
+/// # Arguments
+/// - `lists`: Result lists from independent retrievers.
+/// - `k`: Smoothing constant (60 is the usual default).
+///
+/// # Returns
+/// Deduplicated results sorted by fused score (highest first).
+fn reciprocal_rank_fusion(lists: Vec<Vec<SearchResult>>, k: f32) -> Vec<SearchResult> {
+    let mut merged: Vec<SearchResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for mut list in lists {
+        list.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        for (rank, result) in list.into_iter().enumerate() {
+            let contribution = 1.0 / (k + (rank + 1) as f32);
+            add_contribution(&mut merged, &mut positions, result, contribution);
+        }
+    }
+
+    sort_fused(&mut merged);
+    merged
+}
+
+/// Merge vector and full-text results by a weighted blend of their scores.
+///
+/// Full-text scores are min-max normalized to 0..1 within `fts`, so the
+/// best keyword match scores 1 like an identical vector. A document found
+/// by both scores `vector_weight * similarity + fts_weight * fts_score`;
+/// one found by a single retriever keeps only that retriever's term.
+///
+/// # Arguments
+/// - `vector`: Vector hits, scored by cosine similarity.
+/// - `fts`: Full-text hits, scored by [`bm25_to_similarity`].
+/// - `vector_weight`: Weight of vector similarity.
+/// - `fts_weight`: Weight of the normalized full-text score.
+///
+/// # Returns
+/// Deduplicated results sorted by blended score (highest first).
+fn weighted_fusion(
+    vector: Vec<SearchResult>,
+    mut fts: Vec<SearchResult>,
+    vector_weight: f32,
+    fts_weight: f32,
+) -> Vec<SearchResult> {
+    let (min, max) = fts.iter().fold((f32::MAX, f32::MIN), |(lo, hi), r| {
+        (lo.min(r.score), hi.max(r.score))
+    });
+    for result in &mut fts {
+        // A lone hit, or hits that all score alike, count as full matches
+        result.score = if max > min {
+            (result.score - min) / (max - min)
+        } else {
+            1.0
+        };
+    }
+
+    let mut merged: Vec<SearchResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (list, weight) in [(vector, vector_weight), (fts, fts_weight)] {
+        for result in list {
+            let contribution = weight * result.score;
+            add_contribution(&mut merged, &mut positions, result, contribution);
+        }
+    }
+
+    sort_fused(&mut merged);
+    merged
+}
+
+/// Add one retriever's score for a result to the fused list.
+///
+/// A result already in the list gets `contribution` added to its score,
+/// its empty snippet/timestamp/project fields are filled from `result`,
+/// and the two results' provenance is merged.
+fn add_contribution(
+    merged: &mut Vec<SearchResult>,
+    positions: &mut HashMap<String, usize>,
+    result: SearchResult,
+    contribution: f32,
+) {
+    let key = match result.source {
+        SearchSource::Event => format!("event:{}", result.id),
+        SearchSource::Exchange => format!("exchange:{}", result.id),
+    };
+
+    match positions.get(&key) {
+        Some(&pos) => {
+            let existing = &mut merged[pos];
+            existing.score += contribution;
+            if existing.snippet.is_empty() {
+                existing.snippet = result.snippet;
+            }
+            if existing.timestamp.is_empty() {
+                existing.timestamp = result.timestamp;
+            }
+            if existing.project.is_none() {
+                existing.project = result.project;
+            }
+            match (&mut existing.explain, result.explain) {
+                (Some(explain), Some(other)) => explain.merge(&other),
+                (explain @ None, other) => *explain = other,
+                _ => {}
+            }
+        }
+        None => {
+            positions.insert(key, merged.len());
+            merged.push(SearchResult {
+                score: contribution,
+                ..result
+            });
+        }
+    }
+}
+
+/// Sort fused results by score, highest first.
+///
+/// Ties are broken by source and ID so paging sees a stable order.
+fn sort_fused(merged: &mut [SearchResult]) {
+    merged.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| result_key(a).cmp(&result_key(b)))
+    });
+}
+
+/// Deterministic order for results with equal scores: events before
+/// exchanges, then by numeric ID.
+fn result_key(result: &SearchResult) -> (bool, Option<i64>, &str) {
+    rank_key(result.source, &result.id)
+}
+
+fn rank_key(source: SearchSource, id: &str) -> (bool, Option<i64>, &str) {
+    (source == SearchSource::Exchange, id.parse().ok(), id)
+}
+
+/// Map an FTS5 bm25 rank (negative, lower is better) onto 0..1 so it is
+/// comparable with vector similarity.
+fn bm25_to_similarity(rank: f64) -> f32 {
+    let relevance = (-rank).max(0.0);
+    (relevance / (1.0 + relevance)) as f32
+}
+
+/// Resolve a timeline `since` filter to a UTC timestamp.
+///
+/// Relative windows are measured from `now` rounded down to the minute, so
+/// a client polling `since=1h` keeps hitting the same cache entry until the
+/// minute turns, at the cost of a window up to a minute longer. Absolute
+/// times are kept to the second; unrecognized filters pass through for the
+/// query to reject.
+///
+/// # Arguments
+/// - `since`: Filter text from the request.
+/// - `now`: Current local time.
+fn resolve_timeline_since(since: String, now: DateTime<Local>) -> String {
+    let now = now
+        .duration_trunc(chrono::Duration::minutes(1))
+        .unwrap_or(now);
+    diachron_core::time::parse_since_at(&since, now)
+        .map(diachron_core::time::format_utc)
+        .unwrap_or(since)
+}
+
+/// Parse a time filter into a UTC timestamp for the search window.
+///
+/// Accepts every format of [`diachron_core::time::parse_since`].
+fn parse_time_filter(filter: &str) -> Option<String> {
+    diachron_core::time::parse_since(filter).map(diachron_core::time::format_utc)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        boost_deletions, build_event_embed_text, cluster_pairs, find_near_duplicate,
+        fingerprint_content, handle_message, hybrid_search, mentions_removal,
+        reciprocal_rank_fusion, rerank, resolve_timeline_since, search_page, stream_search,
+        vector_search, weighted_fusion, RelevanceScorer, SearchFilters, DELETE_QUERY_BOOST,
+    };
+    use crate::cache::{TimelineEntry, TimelineKey};
+    use crate::DaemonState;
+    use chrono::{Local, TimeZone, Timelike};
+    use diachron_core::{
+        compute_fingerprint, BlameConfidence, CaptureEvent, DaemonError, Exchange,
+        FingerprintOptions, IpcMessage, IpcResponse, Operation, SearchCursor, SearchExplanation,
+        SearchFilter, SearchPreference, SearchResult, SearchSource, VectorExportFormat,
+        VectorIndex, EMBEDDING_DIM,
+    };
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio::sync::mpsc;
+
+    /// Fresh directory for one test's database and index files.
+    pub(crate) fn temp_dir() -> PathBuf {
+        // Tests run in parallel; the counter keeps same-instant calls apart
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "diachron-test-{}-{}",
+            nanos,
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn ranked(ids: &[&str]) -> Vec<SearchResult> {
+        ids.iter()
+            .enumerate()
+            .map(|(i, id)| SearchResult {
+                id: id.to_string(),
+                score: 1.0 - i as f32 * 0.1,
+                source: SearchSource::Event,
+                snippet: String::new(),
+                timestamp: String::new(),
+                project: None,
+                explain: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rrf_prefers_docs_in_both_lists() {
+        // "both" is mid-ranked in each list; "vec_top" and "fts_top" each
+        // lead a single list.
+        let vector = ranked(&["vec_top", "a", "both", "b"]);
+        let fts = ranked(&["fts_top", "c", "both", "d"]);
+
+        let fused = reciprocal_rank_fusion(vec![vector, fts], 60.0);
+
+        assert_eq!(fused[0].id, "both");
+        assert!((fused[0].score - 2.0 / 63.0).abs() < 1e-6);
+        assert!(fused[1].id == "vec_top" || fused[1].id == "fts_top");
+        assert!((fused[1].score - 1.0 / 61.0).abs() < 1e-6);
+
+        // Dedup: "both" appears only once
+        assert_eq!(fused.iter().filter(|r| r.id == "both").count(), 1);
+        assert_eq!(fused.len(), 7);
+    }
+
+    #[test]
+    fn test_fusion_explains_docs_found_by_both_retrievers() {
+        let explained = |mut results: Vec<SearchResult>, explain: fn(f32) -> SearchExplanation| {
+            for result in &mut results {
+                result.explain = Some(explain(result.score));
+            }
+            results
+        };
+        let vector = explained(ranked(&["vec_only", "both"]), SearchExplanation::vector);
+        let fts = explained(ranked(&["both", "fts_only"]), SearchExplanation::fts);
+
+        for fused in [
+            reciprocal_rank_fusion(vec![vector.clone(), fts.clone()], 60.0),
+            weighted_fusion(vector.clone(), fts.clone(), 0.5, 0.5),
+        ] {
+            let explain = |id: &str| {
+                fused
+                    .iter()
+                    .find(|r| r.id == id)
+                    .and_then(|r| r.explain)
+                    .unwrap()
+            };
+            let both = explain("both");
+            assert!(both.from_vector && both.from_fts);
+            assert_eq!(both.vector_sim, Some(0.9));
+            assert_eq!(both.fts_score, Some(1.0));
+
+            let vec_only = explain("vec_only");
+            assert!(vec_only.from_vector && !vec_only.from_fts);
+            assert_eq!(vec_only.fts_score, None);
+            assert!(!explain("fts_only").from_vector);
+        }
+    }
+
+    #[test]
+    fn test_keyword_preference_ranks_exact_match_first() {
+        // "semantic" is the closest vector but has no keyword match;
+        // "exact" is the best keyword match but a weak vector
+        let mut vector = ranked(&["semantic", "exact"]);
+        vector[0].score = 0.9;
+        vector[1].score = 0.3;
+        let mut fts = ranked(&["exact", "other"]);
+        fts[0].score = 0.5;
+        fts[1].score = 0.2;
+
+        let (vw, fw) = SearchPreference::Semantic.weights();
+        let fused = weighted_fusion(vector.clone(), fts.clone(), vw, fw);
+        assert_eq!(fused[0].id, "semantic");
+
+        let (vw, fw) = SearchPreference::Keyword.weights();
+        let fused = weighted_fusion(vector, fts, vw, fw);
+        assert_eq!(fused[0].id, "exact");
+        // 0.2 * 0.3 vector + 0.8 * 1.0 normalized keyword score
+        assert!((fused[0].score - 0.86).abs() < 1e-6);
+        assert_eq!(fused.len(), 3);
+    }
+
+    #[test]
+    fn test_removal_queries_boost_delete_events() {
+        assert!(mentions_removal("when did we remove the old auth module?"));
+        assert!(!mentions_removal("removable media"));
+
+        let mut results = ranked(&["edit", "delete"]);
+        let deletes = HashSet::from(["delete".to_string()]);
+        boost_deletions(&mut results, &deletes);
+        assert_eq!(results[0].id, "delete");
+        assert!((results[0].score - 0.9 * DELETE_QUERY_BOOST).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rrf_fills_missing_fields_from_later_lists() {
+        let vector = ranked(&["x"]);
+        let mut fts = ranked(&["x"]);
+        fts[0].snippet = "matched <b>text</b>".to_string();
+        fts[0].timestamp = "2026-01-01T00:00:00".to_string();
+
+        let fused = reciprocal_rank_fusion(vec![vector, fts], 60.0);
+        assert_eq!(fused.len(), 1);
+        assert_eq!(fused[0].snippet, "matched <b>text</b>");
+        assert_eq!(fused[0].timestamp, "2026-01-01T00:00:00");
+    }
+
+    #[test]
+    fn test_rrf_breaks_score_ties_by_source_and_id() {
+        let mut exchange = ranked(&["1"]);
+        exchange[0].source = SearchSource::Exchange;
+
+        // Each list's leader gets the same fused score
+        let fused = reciprocal_rank_fusion(
+            vec![ranked(&["10"]), exchange, ranked(&["9"]), ranked(&["abc"])],
+            60.0,
+        );
+        let order: Vec<(&str, SearchSource)> =
+            fused.iter().map(|r| (r.id.as_str(), r.source)).collect();
+        assert_eq!(
+            order,
+            vec![
+                ("abc", SearchSource::Event),
+                ("9", SearchSource::Event),
+                ("10", SearchSource::Event),
+                ("1", SearchSource::Exchange),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeline_cache_invalidated_by_writes() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let event = |path: &str| CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        let first = state
+            .db
+            .save_event(&event("src/a.rs"), None, None, None)
+            .unwrap();
+
+        let timeline = || IpcMessage::Timeline {
+            since: None,
+            file_filter: Some("src/".to_string()),
+            limit: 10,
+            file_match: diachron_core::FileMatch::Substring,
+            project: None,
+            operation: None,
+            category: None,
+            before_id: None,
+        };
+        let ids = |response: IpcResponse| match response {
+            IpcResponse::EventsPage { events, .. } => {
+                events.iter().map(|e| e.id).collect::<Vec<_>>()
+            }
+            _ => panic!("expected a page of events"),
+        };
+
+        assert_eq!(ids(handle_message(timeline(), &state).await), vec![first]);
+        let key = TimelineKey {
+            since: None,
+            file_filter: Some("src/".to_string()),
+            file_match: diachron_core::FileMatch::Substring,
+            project: None,
+            operation: None,
+            category: None,
+            before_id: None,
+            limit: 10,
+            db_version: state.db.search_version().unwrap(),
+        };
+        assert!(state.timeline_cache.write().unwrap().get(&key).is_some());
+        assert_eq!(ids(handle_message(timeline(), &state).await), vec![first]);
+
+        let second = state
+            .db
+            .save_event(&event("src/b.rs"), None, None, None)
+            .unwrap();
+        assert_ne!(state.db.search_version().unwrap(), key.db_version);
+        assert_eq!(
+            ids(handle_message(timeline(), &state).await),
+            vec![second, first]
+        );
+    }
+
+    #[test]
+    fn test_timeline_since_resolves_against_the_minute() {
+        let at = |h: u32, m: u32, sec: u32| Local.with_ymd_and_hms(2026, 3, 10, h, m, sec).unwrap();
+
+        let early = resolve_timeline_since("1h".to_string(), at(10, 30, 5));
+        assert_eq!(
+            early,
+            resolve_timeline_since("1h".to_string(), at(10, 30, 59))
+        );
+        assert_ne!(
+            early,
+            resolve_timeline_since("1h".to_string(), at(10, 31, 0))
+        );
+
+        let absolute = "2024-01-15T10:30:45Z".to_string();
+        assert_eq!(
+            resolve_timeline_since(absolute.clone(), at(10, 30, 5)),
+            absolute
+        );
+        assert_eq!(
+            resolve_timeline_since("soon".to_string(), at(10, 30, 5)),
+            "soon"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeline_cache_hits_relative_window_across_seconds() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let timeline = || IpcMessage::Timeline {
+            since: Some("1h".to_string()),
+            file_filter: None,
+            limit: 10,
+            file_match: diachron_core::FileMatch::Substring,
+            project: None,
+            operation: None,
+            category: None,
+            before_id: None,
+        };
+        let until_next_second = || {
+            let millis = Local::now().timestamp_subsec_millis().min(999);
+            Duration::from_millis(u64::from(1000 - millis) + 10)
+        };
+
+        // Both requests must fall in the same minute
+        if Local::now().second() >= 58 {
+            tokio::time::sleep(until_next_second() * 3).await;
+        }
+        let response = handle_message(timeline(), &state).await;
+        assert!(matches!(response, IpcResponse::EventsPage { .. }));
+
+        // Mark the cached page, so a hit is told apart from a fresh read
+        let key = TimelineKey {
+            since: Some(resolve_timeline_since("1h".to_string(), Local::now())),
+            file_filter: None,
+            file_match: diachron_core::FileMatch::Substring,
+            project: None,
+            operation: None,
+            category: None,
+            before_id: None,
+            limit: 10,
+            db_version: state.db.search_version().unwrap(),
+        };
+        {
+            let mut cache = state.timeline_cache.write().unwrap();
+            assert!(cache.get(&key).is_some());
+            cache.insert(
+                key,
+                TimelineEntry {
+                    events: Vec::new(),
+                    next_cursor: Some(-1),
+                },
+            );
+        }
+
+        tokio::time::sleep(until_next_second()).await;
+        match handle_message(timeline(), &state).await {
+            IpcResponse::EventsPage { next_cursor, .. } => assert_eq!(next_cursor, Some(-1)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capture_batch_writes_events_in_order() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let event = |path: &str| CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+
+        let batch = ["src/a.rs", "src/b.rs", "src/c.rs"].map(event).to_vec();
+        match handle_message(IpcMessage::CaptureBatch(batch), &state).await {
+            IpcResponse::CaptureStats {
+                saved,
+                skipped,
+                failed,
+            } => assert_eq!((saved, skipped, failed), (3, 0, 0)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let events = state
+            .db
+            .query_events(&crate::db::EventFilter::default(), 10)
+            .unwrap();
+        let mut paths: Vec<_> = events
+            .iter()
+            .map(|e| (e.id, e.file_path.clone().unwrap()))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths.into_iter().map(|(_, p)| p).collect::<Vec<_>>(),
+            vec!["src/a.rs", "src/b.rs", "src/c.rs"]
+        );
+        assert_eq!(state.events_count(), 3);
+        let chain = state.db.with_conn(diachron_core::verify_chain).unwrap();
+        assert!(chain.valid);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_cursor_pages_without_overlap_or_gaps() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let mut saved = Vec::new();
+        for i in 0..7 {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(format!("src/page_{}.rs", i)),
+                operation: Operation::Create,
+                ..Default::default()
+            };
+            saved.push(state.db.save_event(&event, None, None, None).unwrap());
+        }
+        saved.reverse();
+
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        let mut cursor = None;
+        loop {
+            let msg = IpcMessage::Timeline {
+                since: None,
+                file_filter: None,
+                limit: 3,
+                file_match: diachron_core::FileMatch::Substring,
+                project: None,
+                operation: None,
+                category: None,
+                before_id: cursor,
+            };
+            let IpcResponse::EventsPage {
+                events,
+                next_cursor,
+            } = handle_message(msg, &state).await
+            else {
+                panic!("expected a page of events");
+            };
+            pages += 1;
+            seen.extend(events.iter().map(|e| e.id));
+            match next_cursor {
+                Some(next) => {
+                    assert_eq!(Some(next), events.last().map(|e| e.id));
+                    cursor = Some(next);
+                }
+                None => break,
+            }
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(seen, saved);
+    }
+
+    #[test]
+    fn test_find_near_duplicate_checks_index_and_pending() {
+        let mut index = VectorIndex::new(3, None).unwrap();
+        index.add("exchange:a", &[1.0, 0.0, 0.0], None).unwrap();
+        let pending = vec![("exchange:b".to_string(), vec![0.0, 1.0, 0.0], None)];
+
+        let near_a = [0.99, 0.05, 0.0];
+        assert_eq!(
+            find_near_duplicate(&index, &pending, "exchange:new", &near_a, 0.98).as_deref(),
+            Some("a")
+        );
+        let near_b = [0.0, 0.99, 0.05];
+        assert_eq!(
+            find_near_duplicate(&index, &pending, "exchange:new", &near_b, 0.98).as_deref(),
+            Some("b")
+        );
+        // Unrelated vectors and re-indexed copies of the same exchange are kept
+        let unrelated = [0.0, 0.0, 1.0];
+        assert!(find_near_duplicate(&index, &pending, "exchange:new", &unrelated, 0.98).is_none());
+        assert!(find_near_duplicate(&index, &[], "exchange:a", &[1.0, 0.0, 0.0], 0.98).is_none());
+    }
+
+    #[test]
+    fn test_cluster_pairs_joins_chains() {
+        let pair = |a: &str, b: &str| (format!("exchange:{}", a), format!("exchange:{}", b), 0.99);
+        let clusters = cluster_pairs(&[pair("d", "e"), pair("a", "b"), pair("b", "c")]);
+        assert_eq!(clusters, vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+        assert!(cluster_pairs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_rerank_promotes_buried_answer() {
+        let result = |id: &str, snippet: &str| SearchResult {
+            id: id.to_string(),
+            score: 0.0,
+            source: SearchSource::Exchange,
+            snippet: snippet.to_string(),
+            timestamp: String::new(),
+            project: None,
+            explain: None,
+        };
+        let fused = vec![
+            result("1", "api docs index"),
+            result("2", "key bindings for the editor"),
+            result("3", "rotate log files daily"),
+            result("4", "how to rotate the api key without downtime"),
+            result("5", "api key"),
+        ];
+        let ids = |results: &[SearchResult]| -> Vec<String> {
+            results.iter().map(|r| r.id.clone()).collect()
+        };
+
+        // Scores by how many query words a snippet contains
+        let query = ["rotate", "api", "key"];
+        let overlap = |snippets: &[&str]| -> Result<Vec<f32>, String> {
+            Ok(snippets
+                .iter()
+                .map(|s| query.iter().filter(|w| s.contains(*w)).count() as f32)
+                .collect())
+        };
+
+        let reranked = rerank(fused.clone(), 4, overlap);
+        assert_eq!(ids(&reranked), ["4", "1", "2", "3", "5"]);
+
+        // The tail beyond `depth` is never scored or moved
+        let reranked = rerank(fused.clone(), 2, overlap);
+        assert_eq!(ids(&reranked), ["1", "2", "3", "4", "5"]);
+
+        let failed = rerank(fused.clone(), 4, |_: &[&str]| -> Result<Vec<f32>, String> {
+            Err("model unavailable".to_string())
+        });
+        assert_eq!(ids(&failed), ids(&fused));
+    }
+
+    #[tokio::test]
+    async fn test_forget_dry_run_then_delete_with_vectors() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let dim = state.config.embedding.dim;
+
+        for (i, (id, project, message)) in [
+            ("ex-a1", "-home-me-alpha", "rotate the api key"),
+            ("ex-a2", "-home-me-alpha", "fix the login form"),
+            ("ex-b1", "-home-me-beta", "rotate the api key"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let exchange = Exchange {
+                id: id.to_string(),
+                timestamp: format!("2026-01-0{}T00:00:00Z", i + 1),
+                project: Some(project.to_string()),
+                session_id: None,
+                user_message: message.to_string(),
+                assistant_message: "done".to_string(),
+                tool_calls: None,
+                archive_path: None,
+                line_start: None,
+                line_end: None,
+                embedding: None,
+                summary: None,
+                git_branch: None,
+                cwd: None,
+            };
+            let mut vector = vec![0.0; dim];
+            vector[i] = 1.0;
+            state.db.save_exchange(&exchange, Some(&vector)).unwrap();
+            state
+                .exchanges_index
+                .write()
+                .unwrap()
+                .add(&format!("exchange:{}", id), &vector, None)
+                .unwrap();
+        }
+        state.save_indexes().unwrap();
+
+        let forget = |dry_run| IpcMessage::Forget {
+            project: Some("alpha".to_string()),
+            session: None,
+            before: None,
+            query: Some("rotate".to_string()),
+            dry_run,
+        };
+
+        // A dry run reports the match and leaves everything in place
+        match handle_message(forget(true), &state).await {
+            IpcResponse::ForgetStats {
+                exchanges,
+                vectors,
+                projects,
+                dry_run,
+            } => {
+                assert_eq!((exchanges, vectors, dry_run), (1, 1, true));
+                assert_eq!(projects, vec!["-home-me-alpha".to_string()]);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert_eq!(state.exchanges_index.read().unwrap().len(), 3);
+
+        match handle_message(forget(false), &state).await {
+            IpcResponse::ForgetStats {
+                exchanges, vectors, ..
+            } => assert_eq!((exchanges, vectors), (1, 1)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        let reloaded = VectorIndex::load(&state.indexes_path().join("exchanges")).unwrap();
+        assert!(!reloaded.contains("exchange:ex-a1"));
+        assert!(reloaded.contains("exchange:ex-a2") && reloaded.contains("exchange:ex-b1"));
+        let results = hybrid_search(&state, "rotate", 10, 30, SearchFilters::default()).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "ex-b1");
+
+        // Without a filter nothing is deleted
+        let everything = IpcMessage::Forget {
+            project: None,
+            session: None,
+            before: None,
+            query: None,
+            dry_run: false,
+        };
+        match handle_message(everything, &state).await {
+            IpcResponse::Error(e) => assert!(matches!(e, DaemonError::InvalidRequest(_))),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert_eq!(state.exchanges_index.read().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_golden_output_and_cache_invalidation() {
+        let dir = temp_dir();
+        let db_path = dir.join("diachron.db");
+        let state = DaemonState::new_for_tests(db_path).expect("test state");
+        let state = Arc::new(state);
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/auth.rs".to_string()),
+            operation: Operation::Create,
+            diff_summary: Some("only_event_token".to_string()),
+            raw_input: Some("auth token added".to_string()),
+            ..Default::default()
+        };
+        let first_id = state
+            .db
+            .save_event(&event, Some("session-1"), None, None)
+            .unwrap();
+
+        let exchange = Exchange {
+            id: "ex-1".to_string(),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            project: Some("test-project".to_string()),
+            session_id: Some("session-1".to_string()),
+            user_message: "only_exchange_token".to_string(),
+            assistant_message: "response".to_string(),
+            tool_calls: None,
+            archive_path: None,
+            line_start: None,
+            line_end: None,
+            embedding: None,
+            summary: None,
+            git_branch: None,
+            cwd: None,
+        };
+        state.db.save_exchange(&exchange, None).unwrap();
+
+        let event_filters = SearchFilters {
+            source: Some(SearchSource::Event),
+            ..SearchFilters::default()
+        };
+        let results = hybrid_search(&state, "only_event_token", 10, 30, event_filters).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].source, SearchSource::Event);
+        assert_eq!(results[0].id, first_id.to_string());
+
+        // Insert another matching event to ensure cache invalidates.
+        let event2 = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/auth2.rs".to_string()),
+            operation: Operation::Modify,
+            diff_summary: Some("only_event_token".to_string()),
+            raw_input: Some("auth token updated".to_string()),
+            ..Default::default()
+        };
+        let second_id = state
+            .db
+            .save_event(&event2, Some("session-2"), None, None)
+            .unwrap();
+
+        let results_after = hybrid_search(&state, "only_event_token", 10, 30, event_filters).await;
+
+        let ids: HashSet<String> = results_after.into_iter().map(|r| r.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&first_id.to_string()));
+        assert!(ids.contains(&second_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_vector_hits_respect_since_window() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let mut ids = Vec::new();
+        for file in ["src/old.rs", "src/recent.rs"] {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(file.to_string()),
+                operation: Operation::Create,
+                ..Default::default()
+            };
+            ids.push(state.db.save_event(&event, None, None, None).unwrap());
+        }
+        state
+            .db
+            .with_conn(|conn| {
+                conn.execute(
+                    "UPDATE events SET timestamp = '2020-01-01T00:00:00Z' WHERE id = ?",
+                    [ids[0]],
+                )
+            })
+            .unwrap();
+
+        // Both events are equally close to the query, so only the filter separates them
+        let mut vector = vec![0.0; EMBEDDING_DIM];
+        vector[0] = 1.0;
+        {
+            let mut idx = state.events_index.write().unwrap();
+            for id in &ids {
+                idx.add(&format!("event:{}", id), &vector, None).unwrap();
+            }
+        }
+
+        let since = diachron_core::time::parse_since("7d").unwrap();
+        let filter = SearchFilter {
+            since: Some(since),
+            ..SearchFilter::default()
+        };
+        let results = vector_search(&state, &vector, 10, (true, false), &filter, None);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, ids[1].to_string());
+        assert!(results[0].timestamp >= diachron_core::time::format_local(since));
+        assert_eq!(results[0].project.as_deref(), Some("src/recent.rs"));
+
+        let unfiltered = vector_search(
+            &state,
+            &vector,
+            10,
+            (true, false),
+            &SearchFilter::default(),
+            None,
+        );
+        assert_eq!(unfiltered.len(), 2);
+        assert!(unfiltered.iter().all(|r| !r.timestamp.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_skips_index_of_other_dimension() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
+        };
+        let id = state.db.save_event(&event, None, None, None).unwrap();
+        let mut vector = vec![0.0; EMBEDDING_DIM];
+        vector[0] = 1.0;
+        state
+            .events_index
+            .write()
+            .unwrap()
+            .add(&format!("event:{}", id), &vector, None)
+            .unwrap();
+
+        // A query from a model with a different dimension finds nothing
+        // instead of failing the search
+        let filter = SearchFilter::default();
+        let results = vector_search(&state, &[1.0, 0.0, 0.0], 10, (true, true), &filter, None);
+        assert!(results.is_empty());
+
+        let results = vector_search(&state, &vector, 10, (true, true), &filter, None);
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_vector_project_filter_applies_before_limit() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let mut vector = vec![0.0; EMBEDDING_DIM];
+        vector[0] = 1.0;
+        let mut further = vec![0.0; EMBEDDING_DIM];
+        further[0] = 0.8;
+        further[1] = 0.6;
+
+        // Four events from another project sit closer to the query than
+        // the one event from the project being searched
+        let files = ["other/a.rs", "other/b.rs", "other/c.rs", "other/d.rs"];
+        for (file, embedding) in files
+            .iter()
+            .map(|file| (*file, &vector))
+            .chain([("mine/lib.rs", &further)])
+        {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(file.to_string()),
+                operation: Operation::Create,
+                ..Default::default()
+            };
+            let id = state.db.save_event(&event, None, None, None).unwrap();
+            state
+                .events_index
+                .write()
+                .unwrap()
+                .add(&format!("event:{}", id), embedding, Some(file))
+                .unwrap();
+        }
+
+        let filter = SearchFilter::default();
+        let results = vector_search(&state, &vector, 2, (true, false), &filter, Some("Mine"));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].project.as_deref(), Some("mine/lib.rs"));
+
+        let unfiltered = vector_search(&state, &vector, 2, (true, false), &filter, None);
+        assert_eq!(unfiltered.len(), 2);
+        assert!(unfiltered.iter().all(|r| r.project != results[0].project));
+    }
+
+    #[tokio::test]
+    async fn test_delete_event_is_found_by_file_name() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        // A deletion records nothing but its path
+        let event = CaptureEvent {
+            tool_name: "Bash".to_string(),
+            file_path: Some("src/legacy.rs".to_string()),
+            operation: Operation::Delete,
+            ..Default::default()
+        };
+        let id = state.db.save_event(&event, None, None, None).unwrap();
+
+        let text = build_event_embed_text(&event);
+        assert!(text.starts_with("Deleted file: src/legacy.rs (legacy)"));
+
+        let results = hybrid_search(&state, "legacy", 10, 30, SearchFilters::default()).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, id.to_string());
+        assert_eq!(results[0].source, SearchSource::Event);
+    }
+
+    #[tokio::test]
+    async fn test_search_cursor_pages_without_overlap_or_gaps() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        // Varying term counts give distinct scores, repeats give ties
+        for i in 0..25 {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(format!("src/file_{}.rs", i)),
+                operation: Operation::Create,
+                diff_summary: Some(vec!["paged_token"; 1 + i % 4].join(" ")),
+                ..Default::default()
+            };
+            state.db.save_event(&event, None, None, None).unwrap();
+        }
+        let search = |limit: usize, after: Option<SearchCursor>| IpcMessage::Search {
+            query: "paged_token".to_string(),
+            limit,
+            source_filter: None,
+            since: None,
+            until: None,
+            project: None,
+            tool: None,
+            stream: false,
+            after,
+            prefer: None,
+            explain: false,
+        };
+        let results = |response: IpcResponse| match response {
+            IpcResponse::SearchResults(results) => results,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        let all = results(handle_message(search(25, None), &state).await);
+        assert_eq!(all.len(), 25);
+        let mut paged = Vec::new();
+        let mut after = None;
+        loop {
+            let page = results(handle_message(search(10, after.clone()), &state).await);
+            let Some(last) = page.last() else {
+                break;
+            };
+            // Cursors survive their text form
+            after = Some(SearchCursor::after(last).to_string().parse().unwrap());
+            paged.extend(page);
+        }
+        let key = |r: &SearchResult| r.id.clone();
+        assert_eq!(
+            paged.iter().map(key).collect::<Vec<_>>(),
+            all.iter().map(key).collect::<Vec<_>>()
+        );
+
+        // A cursor whose result is gone resumes below its score and key;
+        // exchanges sort after events with the same score
+        let gone = SearchCursor {
+            source: SearchSource::Exchange,
+            ..SearchCursor::after(&all[12])
+        };
+        let below = all.iter().position(|r| r.score < all[12].score).unwrap();
+        let resumed = results(handle_message(search(25, Some(gone)), &state).await);
+        assert_eq!(resumed[0].id, all[below].id);
+    }
+
+    /// Scores candidates in reverse of the order it receives them,
+    /// recording each batch size.
+    struct ReversingScorer(Arc<Mutex<Vec<usize>>>);
+
+    impl RelevanceScorer for ReversingScorer {
+        fn score_batch(
+            &mut self,
+            _query: &str,
+            docs: &[&str],
+        ) -> diachron_embeddings::Result<Vec<f32>> {
+            self.0.lock().unwrap().push(docs.len());
+            Ok((0..docs.len()).map(|i| i as f32).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_reranks_a_head_sized_to_the_page() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        for i in 0..12 {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(format!("src/file_{}.rs", i)),
+                operation: Operation::Create,
+                diff_summary: Some(vec!["rerank_token"; 1 + i % 5].join(" ")),
+                ..Default::default()
+            };
+            state.db.save_event(&event, None, None, None).unwrap();
+        }
+        let search = |limit: usize| IpcMessage::Search {
+            query: "rerank_token".to_string(),
+            limit,
+            source_filter: None,
+            since: None,
+            until: None,
+            project: None,
+            tool: None,
+            stream: false,
+            after: None,
+            prefer: None,
+            explain: false,
+        };
+        let ids = |response: IpcResponse| match response {
+            IpcResponse::SearchResults(results) => {
+                results.into_iter().map(|r| r.id).collect::<Vec<_>>()
+            }
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        let fused = ids(handle_message(search(12), &state).await);
+        assert_eq!(fused.len(), 12);
+
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let scorer: Box<dyn RelevanceScorer> = Box::new(ReversingScorer(Arc::clone(&batches)));
+        state.reranker.replace(vec![scorer], true);
+
+        // A page of 2 re-ranks the top 6 of the 100-deep ranking, not all of it
+        let page = ids(handle_message(search(2), &state).await);
+        assert_eq!(*batches.lock().unwrap(), [6]);
+        assert_eq!(page, [fused[5].clone(), fused[4].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_search_sends_chunks_then_end() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        for file in ["src/a.rs", "src/b.rs"] {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(file.to_string()),
+                operation: Operation::Create,
+                diff_summary: Some("streamed_token".to_string()),
+                ..Default::default()
+            };
+            state
+                .db
+                .save_event(&event, Some("session-1"), None, None)
+                .unwrap();
+        }
+
+        let (tx, mut rx) = mpsc::channel(16);
+        stream_search(
+            &state,
+            "streamed_token",
+            10,
+            None,
+            SearchFilters::default(),
+            tx,
+        )
+        .await;
+
+        let mut chunks = 0;
+        let mut ended = false;
+        while let Some(response) = rx.recv().await {
+            match response {
+                IpcResponse::SearchResultChunk(_) => {
+                    assert!(!ended, "chunk after end");
+                    chunks += 1;
+                }
+                IpcResponse::SearchResultsEnd => ended = true,
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+        assert_eq!(chunks, 2);
+        assert!(ended);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_and_batched_pages_match() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        // Different match strengths so the ranking has an order to keep
+        for (i, summary) in [
+            "paged_token",
+            "paged_token paged_token",
+            "paged_token and other words around it",
+            "paged_token paged_token paged_token",
+            "one paged_token among many unrelated words in a longer summary",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some(format!("src/{}.rs", i)),
+                operation: Operation::Create,
+                diff_summary: Some(summary.to_string()),
+                ..Default::default()
+            };
+            state.db.save_event(&event, None, None, None).unwrap();
+        }
+
+        let filters = || SearchFilters {
+            prefer: Some(SearchPreference::Keyword),
+            ..Default::default()
+        };
+        let streamed = |after: Option<SearchCursor>| {
+            let state = Arc::clone(&state);
+            async move {
+                let (tx, mut rx) = mpsc::channel(16);
+                stream_search(&state, "paged_token", 2, after.as_ref(), filters(), tx).await;
+                let mut results = Vec::new();
+                while let Some(response) = rx.recv().await {
+                    match response {
+                        IpcResponse::SearchResultChunk(result) => results.push(result),
+                        IpcResponse::SearchResultsEnd => break,
+                        other => panic!("unexpected response: {:?}", other),
+                    }
+                }
+                results
             }
+        };
+        let ids = |results: &[SearchResult]| -> Vec<String> {
+            results.iter().map(|r| r.id.clone()).collect()
+        };
+
+        let mut after = None;
+        let mut seen = Vec::new();
+        loop {
+            let streamed = streamed(after.clone()).await;
+            let batched = search_page(&state, "paged_token", 2, after.as_ref(), filters()).await;
+            assert_eq!(ids(&streamed), ids(&batched));
+            assert!(streamed.iter().all(|r| r.explain.is_none()));
+            let Some(last) = streamed.last() else {
+                break;
+            };
+            after = Some(SearchCursor::after(last));
+            seen.extend(ids(&streamed));
         }
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen.iter().collect::<HashSet<_>>().len(), 5);
+    }
 
-        results
-    });
+    #[tokio::test]
+    async fn test_reload_model_failure_keeps_current_engines() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
 
-    let (vector_results, embedding_used) = match vector_handle.await {
-        Ok((results, used)) => (results, used),
-        Err(e) => {
-            warn!("Vector search task failed: {}", e);
-            (Vec::new(), false)
+        let msg = IpcMessage::ReloadModel {
+            model_dir: Some(dir.join("no-such-model")),
+        };
+        match handle_message(msg, &state).await {
+            IpcResponse::Error(e) => assert!(matches!(e, DaemonError::ModelNotLoaded(_))),
+            other => panic!("unexpected response: {:?}", other),
         }
-    };
-    let fts_results = match fts_handle.await {
-        Ok(results) => results,
-        Err(e) => {
-            warn!("FTS search task failed: {}", e);
-            Vec::new()
+        assert!(!state.embedding_engines.is_loaded());
+
+        // A rebuild in progress blocks the swap
+        state.reindexing.store(true, Ordering::Release);
+        match handle_message(IpcMessage::ReloadModel { model_dir: None }, &state).await {
+            IpcResponse::Error(e) => assert!(matches!(e, DaemonError::Busy(_))),
+            other => panic!("unexpected response: {:?}", other),
         }
-    };
+    }
 
-    let mut results = Vec::new();
-    let mut seen_ids = HashSet::new();
+    #[tokio::test]
+    async fn test_hello_announces_protocol_and_capabilities() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
 
-    for result in vector_results {
-        let key = match result.source {
-            SearchSource::Event => format!("event:{}", result.id),
-            SearchSource::Exchange => format!("exchange:{}", result.id),
+        let hello = IpcMessage::Hello {
+            client_version: "0.0.1".to_string(),
         };
-        if seen_ids.insert(key) {
-            results.push(result);
+        match handle_message(hello, &state).await {
+            IpcResponse::Hello {
+                daemon_version,
+                protocol_version,
+                capabilities,
+            } => {
+                assert_eq!(daemon_version, env!("CARGO_PKG_VERSION"));
+                assert_eq!(protocol_version, diachron_core::PROTOCOL_VERSION);
+                assert!(capabilities.iter().any(|c| c == diachron_core::CAP_STATS));
+                assert!(capabilities
+                    .iter()
+                    .any(|c| c == diachron_core::CAP_STREAMING_SEARCH));
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_without_engine() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        // Nothing to compare against, so an unforced reindex is a no-op
+        match handle_message(IpcMessage::Reindex { force: false }, &state).await {
+            IpcResponse::ReindexStats { rebuilt, dim, .. } => {
+                assert!(!rebuilt);
+                assert_eq!(dim, diachron_core::EMBEDDING_DIM);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        // A forced rebuild cannot re-embed without a model
+        match handle_message(IpcMessage::Reindex { force: true }, &state).await {
+            IpcResponse::Error(e) => {
+                assert!(matches!(e, DaemonError::ModelNotLoaded(_)));
+                assert!(e
+                    .message()
+                    .starts_with("Reindex failed: Embedding engine unavailable"));
+            }
+            other => panic!("unexpected response: {:?}", other),
         }
     }
 
-    for result in fts_results {
-        let key = match result.source {
-            SearchSource::Event => format!("event:{}", result.id),
-            SearchSource::Exchange => format!("exchange:{}", result.id),
+    #[tokio::test]
+    async fn test_reconcile_restores_unindexed_embedding() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let mut embedding = vec![0.0; state.config.embedding.dim];
+        embedding[0] = 1.0;
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/a.rs".to_string()),
+            operation: Operation::Create,
+            ..Default::default()
         };
-        if seen_ids.insert(key) {
-            results.push(result);
+        // Embedded and saved, but its vector never reached the index
+        let id = state
+            .db
+            .save_event(&event, None, Some(&embedding), None)
+            .unwrap();
+
+        match handle_message(IpcMessage::Reconcile, &state).await {
+            IpcResponse::ReconcileStats {
+                restored,
+                removed,
+                deferred,
+            } => assert_eq!((restored, removed, deferred), (1, 0, 0)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert!(state
+            .events_index
+            .read()
+            .unwrap()
+            .contains(&format!("event:{}", id)));
+        // Restored vectors are written out right away
+        assert!(VectorIndex::exists(&state.indexes_path().join("events")));
+
+        match handle_message(IpcMessage::Reconcile, &state).await {
+            IpcResponse::ReconcileStats { restored, .. } => assert_eq!(restored, 0),
+            other => panic!("unexpected response: {:?}", other),
         }
     }
 
-    // 3. Filter by since and project
-    if since_timestamp.is_some() || project.is_some() {
-        results.retain(|r| {
-            // Filter by timestamp if since is set
-            if let Some(ref since_ts) = since_timestamp {
-                if r.timestamp < *since_ts {
-                    return false;
-                }
+    #[tokio::test]
+    async fn test_health_requires_model_unless_degraded() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        match handle_message(IpcMessage::Health, &state).await {
+            IpcResponse::Health {
+                ready,
+                model_loaded,
+                model_warm,
+                events_index_ok,
+                exchanges_index_ok,
+                warnings,
+            } => {
+                assert!(!ready);
+                assert!(!model_loaded);
+                assert!(!model_warm);
+                assert!(events_index_ok && exchanges_index_ok);
+                assert!(warnings[0].contains("not loaded"));
             }
-            // Filter by project if set
-            if let Some(proj) = project {
-                if let Some(ref result_proj) = r.project {
-                    if !result_proj.to_lowercase().contains(&proj.to_lowercase()) {
-                        return false;
-                    }
-                } else {
-                    return false; // No project info, exclude if filtering
-                }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let mut degraded = DaemonState::new_for_tests(dir.join("degraded.db")).unwrap();
+        degraded.config.embedding.allow_degraded = true;
+        let degraded = Arc::new(degraded);
+        match handle_message(IpcMessage::Health, &degraded).await {
+            IpcResponse::Health {
+                ready, warnings, ..
+            } => {
+                assert!(ready);
+                assert_eq!(warnings.len(), 2);
             }
-            true
-        });
+            other => panic!("unexpected response: {:?}", other),
+        }
     }
 
-    // 4. Sort by score and limit
-    results.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    results.truncate(limit);
-
-    if let Ok(mut cache) = state.search_cache.write() {
-        cache.insert(
-            cache_key,
-            CacheEntry {
-                results: results.clone(),
-                embedding_used,
-            },
-        );
+    #[tokio::test]
+    async fn test_warm_up_without_model_leaves_engines_cold() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        // Startup with an absent model: warm-up is a no-op, not an error
+        state.embedding_engines.warm_up_in_background();
+        assert!(!state.embedding_engines.is_warm());
+
+        match handle_message(IpcMessage::Health, &state).await {
+            IpcResponse::Health {
+                model_loaded,
+                model_warm,
+                warnings,
+                ..
+            } => {
+                assert!(!model_loaded);
+                assert!(!model_warm);
+                assert!(!warnings.iter().any(|w| w.contains("warmed up")));
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
     }
 
-    debug!(
-        "Hybrid search returned {} results (vector: {}, fts: {}, cache: miss)",
-        results.len(),
-        embedding_used,
-        true
-    );
+    #[tokio::test]
+    async fn test_evidence_pack_derives_intent_from_conversation() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
 
-    results
-}
+        let event = CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some("src/auth.rs".to_string()),
+            operation: Operation::Modify,
+            diff_summary: Some("+3 lines".to_string()),
+            git_commit_sha: Some("abc123".to_string()),
+            ..Default::default()
+        };
+        state
+            .db
+            .save_event(&event, Some("session-1"), None, None)
+            .unwrap();
+
+        for (id, minute, message) in [
+            (
+                "ex-1",
+                0,
+                "Fix the token refresh race in auth.rs so sessions stop expiring.",
+            ),
+            ("ex-2", 1, "Looks good, thanks."),
+        ] {
+            let exchange = Exchange {
+                id: id.to_string(),
+                timestamp: format!("2020-01-01T00:{:02}:00Z", minute),
+                project: None,
+                session_id: Some("session-1".to_string()),
+                user_message: message.to_string(),
+                assistant_message: "Done.".to_string(),
+                tool_calls: None,
+                archive_path: None,
+                line_start: None,
+                line_end: None,
+                embedding: None,
+                summary: None,
+                git_branch: None,
+                cwd: None,
+            };
+            state.db.save_exchange(&exchange, None).unwrap();
+        }
 
-/// Parse a time filter string into an ISO timestamp
-/// Supports: "1h", "2d", "7d", "1w", "30d", ISO dates, etc.
-fn parse_time_filter(filter: &str) -> Option<String> {
-    use chrono::{Duration, Utc};
-
-    let filter = filter.trim().to_lowercase();
-
-    // Handle relative time formats
-    let duration = if filter.ends_with('h') {
-        filter[..filter.len()-1].parse::<i64>().ok().map(Duration::hours)
-    } else if filter.ends_with('d') {
-        filter[..filter.len()-1].parse::<i64>().ok().map(Duration::days)
-    } else if filter.ends_with('w') {
-        filter[..filter.len()-1].parse::<i64>().ok().map(Duration::weeks)
-    } else if filter == "yesterday" {
-        Some(Duration::days(1))
-    } else if filter == "today" {
-        Some(Duration::hours(0)) // Current time
-    } else {
-        None
-    };
+        let correlate = |intent: Option<&str>| IpcMessage::CorrelateEvidence {
+            pr_id: 7,
+            commits: vec!["abc123".to_string()],
+            branch: "fix-auth".to_string(),
+            start_time: "2000-01-01T00:00:00".to_string(),
+            end_time: "2100-01-01T00:00:00".to_string(),
+            intent: intent.map(str::to_string),
+            time_window_secs: None,
+            require_file_match: false,
+            commit_files: Default::default(),
+        };
+        let intent = |msg| async {
+            match handle_message(msg, &state).await {
+                IpcResponse::EvidenceResult(result) => result.intent,
+                other => panic!("unexpected response: {:?}", other),
+            }
+        };
 
-    if let Some(dur) = duration {
-        let since = Utc::now() - dur;
-        return Some(since.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        let derived = intent(correlate(None)).await.unwrap();
+        assert!(derived.contains("token refresh race"), "{}", derived);
+        assert_eq!(
+            intent(correlate(Some("Given intent"))).await.as_deref(),
+            Some("Given intent")
+        );
     }
 
-    // Try parsing as ISO date directly
-    if filter.contains('-') && filter.len() >= 10 {
-        // Assume it's already an ISO date, validate format roughly
-        if filter.chars().take(10).filter(|c| c.is_ascii_digit() || *c == '-').count() >= 10 {
-            return Some(if filter.len() == 10 {
-                format!("{}T00:00:00Z", filter)
-            } else {
-                filter.to_string()
-            });
+    #[tokio::test]
+    async fn test_blame_range_groups_contiguous_lines_by_event() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let mut ids = Vec::new();
+        for content in ["fn login() {}", "fn logout() {}"] {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some("src/auth.rs".to_string()),
+                operation: Operation::Modify,
+                diff_summary: Some("+1 lines".to_string()),
+                raw_input: Some(content.to_string()),
+                ..Default::default()
+            };
+            let fingerprint =
+                compute_fingerprint(content, None, None, &FingerprintOptions::default());
+            ids.push(
+                state
+                    .db
+                    .save_event(&event, None, None, Some(&fingerprint))
+                    .unwrap(),
+            );
         }
-    }
-
-    None
-}
 
-#[cfg(test)]
-mod tests {
-    use super::hybrid_search;
-    use crate::DaemonState;
-    use diachron_core::{CaptureEvent, Exchange, Operation, SearchSource};
-    use std::collections::HashSet;
-    use std::path::PathBuf;
-    use std::sync::Arc;
-    use std::time::{SystemTime, UNIX_EPOCH};
+        let msg = IpcMessage::BlameRange {
+            file_path: "src/auth.rs".to_string(),
+            start: 2,
+            end: 5,
+            lines: [
+                "// auth",
+                "fn login() {}",
+                "fn login() {}",
+                "fn logout() {}",
+                "}",
+            ]
+            .map(String::from)
+            .to_vec(),
+            lines_start: 1,
+            mode: "strict".to_string(),
+            session_context: 0,
+        };
+        let runs = match handle_message(msg, &state).await {
+            IpcResponse::BlameRangeResult(runs) => runs,
+            other => panic!("unexpected response: {:?}", other),
+        };
 
-    fn temp_dir() -> PathBuf {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let dir = std::env::temp_dir().join(format!("diachron-test-{}", nanos));
-        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
-        dir
+        let summary: Vec<(u32, u32, Option<i64>)> = runs
+            .iter()
+            .map(|run| {
+                (
+                    run.start_line,
+                    run.end_line,
+                    run.blame.as_ref().map(|b| b.event.id),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(2, 3, Some(ids[0])), (4, 4, Some(ids[1])), (5, 5, None)]
+        );
+        let blame = runs[0].blame.as_ref().unwrap();
+        assert_eq!(blame.confidence, BlameConfidence::High);
+        assert!(blame.reasons.iter().any(|r| r == "content hash exact"));
+        assert!(blame.score > 0.9);
     }
 
     #[tokio::test]
-    async fn test_search_golden_output_and_cache_invalidation() {
+    async fn test_maintenance_prunes_exchanges_but_keeps_events_of_same_age() {
         let dir = temp_dir();
-        let db_path = dir.join("diachron.db");
-        let state = DaemonState::new_for_tests(db_path).expect("test state");
-        let state = Arc::new(state);
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+        let dim = state.config.embedding.dim;
 
         let event = CaptureEvent {
             tool_name: "Write".to_string(),
-            file_path: Some("src/auth.rs".to_string()),
+            file_path: Some("src/old.rs".to_string()),
             operation: Operation::Create,
-            diff_summary: Some("only_event_token".to_string()),
-            raw_input: Some("auth token added".to_string()),
-            metadata: None,
-            git_commit_sha: None,
-            command_category: None,
+            ..Default::default()
         };
-        let first_id = state.db.save_event(&event, Some("session-1"), None).unwrap();
+        let event_id = state.db.save_event(&event, None, None, None).unwrap();
+        state
+            .db
+            .write()
+            .execute(
+                "UPDATE events SET timestamp = '2000-01-01T00:00:00.000' WHERE id = ?",
+                [event_id],
+            )
+            .unwrap();
 
         let exchange = Exchange {
-            id: "ex-1".to_string(),
-            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            id: "ex-old".to_string(),
+            timestamp: "2000-01-01T00:00:00Z".to_string(),
             project: Some("test-project".to_string()),
-            session_id: Some("session-1".to_string()),
-            user_message: "only_exchange_token".to_string(),
-            assistant_message: "response".to_string(),
+            session_id: None,
+            user_message: "question".to_string(),
+            assistant_message: "answer".to_string(),
             tool_calls: None,
             archive_path: None,
             line_start: None,
@@ -1016,48 +4402,211 @@ mod tests {
             git_branch: None,
             cwd: None,
         };
-        state.db.save_exchange(&exchange, None).unwrap();
+        let vector = vec![1.0; dim];
+        state.db.save_exchange(&exchange, Some(&vector)).unwrap();
+        state
+            .exchanges_index
+            .write()
+            .unwrap()
+            .add("exchange:ex-old", &vector, None)
+            .unwrap();
+
+        let msg = IpcMessage::Maintenance {
+            retention_days: 0,
+            events_retention_days: None,
+            exchanges_retention_days: Some(30),
+            project_retention_days: Default::default(),
+        };
+        match handle_message(msg, &state).await {
+            IpcResponse::MaintenanceStats {
+                events_pruned,
+                exchanges_pruned,
+                events_retention_days,
+                exchanges_retention_days,
+                ..
+            } => {
+                assert_eq!((events_pruned, exchanges_pruned), (0, 1));
+                assert_eq!((events_retention_days, exchanges_retention_days), (0, 30));
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert_eq!(state.db.event_count().unwrap(), 1);
+        assert_eq!(state.db.exchange_count().unwrap(), 0);
+        assert!(!state
+            .exchanges_index
+            .read()
+            .unwrap()
+            .contains("exchange:ex-old"));
+    }
 
-        let results = hybrid_search(
-            &state,
-            "only_event_token",
-            10,
-            Some(SearchSource::Event),
-            None,
-            None,
-        )
-        .await;
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].source, SearchSource::Event);
-        assert_eq!(results[0].id, first_id.to_string());
+    #[tokio::test]
+    async fn test_blame_file_annotates_only_written_blocks() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
 
-        // Insert another matching event to ensure cache invalidates.
-        let event2 = CaptureEvent {
+        let written = "fn login() {\n    check();\n}";
+        let event = CaptureEvent {
             tool_name: "Write".to_string(),
-            file_path: Some("src/auth2.rs".to_string()),
+            file_path: Some("src/auth.rs".to_string()),
             operation: Operation::Modify,
-            diff_summary: Some("only_event_token".to_string()),
-            raw_input: Some("auth token updated".to_string()),
-            metadata: None,
-            git_commit_sha: None,
-            command_category: None,
+            diff_summary: Some("+3 lines".to_string()),
+            raw_input: Some(written.to_string()),
+            ..Default::default()
+        };
+        let fingerprint = compute_fingerprint(written, None, None, &FingerprintOptions::default());
+        let id = state
+            .db
+            .save_event(&event, None, None, Some(&fingerprint))
+            .unwrap();
+
+        let msg = IpcMessage::BlameFile {
+            file_path: "src/auth.rs".to_string(),
+            content:
+                "// auth\nuse crate::check;\n\nfn login() {\n    check();\n}\n\nfn logout() {}\n"
+                    .to_string(),
+        };
+        let runs = match handle_message(msg, &state).await {
+            IpcResponse::BlameFileResult(runs) => runs,
+            other => panic!("unexpected response: {:?}", other),
         };
-        let second_id = state.db.save_event(&event2, Some("session-2"), None).unwrap();
 
-        let results_after = hybrid_search(
-            &state,
-            "only_event_token",
-            10,
-            Some(SearchSource::Event),
-            None,
-            None,
-        )
-        .await;
+        assert_eq!(runs.len(), 1, "{:?}", runs);
+        assert_eq!((runs[0].start_line, runs[0].end_line), (4, 6));
+        let blame = runs[0].blame.as_ref().unwrap();
+        assert_eq!(blame.event.id, id);
+        assert_eq!(blame.confidence, BlameConfidence::High);
+        assert!(blame.reasons.iter().any(|r| r == "content hash exact"));
+    }
 
-        let ids: HashSet<String> = results_after.into_iter().map(|r| r.id).collect();
-        assert_eq!(ids.len(), 2);
-        assert!(ids.contains(&first_id.to_string()));
-        assert!(ids.contains(&second_id.to_string()));
+    #[tokio::test]
+    async fn test_blame_file_finds_reindented_block_with_normalized_fingerprints() {
+        let dir = temp_dir();
+        let mut state = DaemonState::new_for_tests(dir.join("diachron.db")).unwrap();
+        state.config.fingerprint.normalize_whitespace = true;
+        state.config.fingerprint.strip_comments = true;
+        let state = Arc::new(state);
+
+        let written = "fn login() {\n    check(); // first\n}";
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/auth.rs".to_string()),
+            operation: Operation::Modify,
+            diff_summary: Some("+3 lines".to_string()),
+            raw_input: Some(written.to_string()),
+            ..Default::default()
+        };
+        let options = state.config.fingerprint.options_for(Some("src/auth.rs"));
+        let fingerprint = compute_fingerprint(written, None, None, &options);
+        let id = state
+            .db
+            .save_event(&event, None, None, Some(&fingerprint))
+            .unwrap();
+
+        // Moved into an impl block, reindented, and its comment reworded
+        let msg = IpcMessage::BlameFile {
+            file_path: "src/auth.rs".to_string(),
+            content: "impl Auth {\n    fn login() {\n        check(); // then\n    }\n}\n"
+                .to_string(),
+        };
+        let runs = match handle_message(msg, &state).await {
+            IpcResponse::BlameFileResult(runs) => runs,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        assert_eq!(runs.len(), 1, "{:?}", runs);
+        assert_eq!((runs[0].start_line, runs[0].end_line), (2, 4));
+        let blame = runs[0].blame.as_ref().unwrap();
+        assert_eq!(blame.event.id, id);
+        assert_eq!(blame.confidence, BlameConfidence::High);
+    }
+
+    #[tokio::test]
+    async fn test_blame_file_tells_truncated_captures_from_trailing_dots() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let save = |raw: &str, truncated: bool| {
+            let event = CaptureEvent {
+                tool_name: "Write".to_string(),
+                file_path: Some("src/wait.rs".to_string()),
+                operation: Operation::Modify,
+                raw_input: Some(raw.to_string()),
+                raw_input_truncated: truncated,
+                ..Default::default()
+            };
+            let content = fingerprint_content(&event).unwrap();
+            let fingerprint =
+                compute_fingerprint(content, None, None, &FingerprintOptions::default());
+            state
+                .db
+                .save_event(&event, None, None, Some(&fingerprint))
+                .unwrap()
+        };
+        // Written in full; the dots are part of the comment
+        let whole = save("fn wait() {}\n// more to come...", false);
+        // Cut at capture in the middle of a line
+        let cut = save("fn cut() {\n    let long = 12...", true);
+
+        let msg = IpcMessage::BlameFile {
+            file_path: "src/wait.rs".to_string(),
+            content: "fn wait() {}\n// more to come...\n\nfn cut() {\n    let long = 12345;\n}\n"
+                .to_string(),
+        };
+        let runs = match handle_message(msg, &state).await {
+            IpcResponse::BlameFileResult(runs) => runs,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        let owners: Vec<(u32, u32, i64, BlameConfidence)> = runs
+            .iter()
+            .map(|run| {
+                let blame = run.blame.as_ref().unwrap();
+                (
+                    run.start_line,
+                    run.end_line,
+                    blame.event.id,
+                    blame.confidence,
+                )
+            })
+            .collect();
+        assert_eq!(
+            owners,
+            [
+                (1, 2, whole, BlameConfidence::High),
+                (4, 5, cut, BlameConfidence::Medium)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_vectors_stays_in_exports_dir() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        for outside in [dir.join("elsewhere"), PathBuf::from("../indexes")] {
+            let msg = IpcMessage::ExportVectors {
+                dir: outside,
+                format: VectorExportFormat::Npy,
+            };
+            match handle_message(msg, &state).await {
+                IpcResponse::Error(DaemonError::InvalidRequest(_)) => {}
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+        assert!(!dir.join("elsewhere").exists());
+
+        let msg = IpcMessage::ExportVectors {
+            dir: PathBuf::from("run-1"),
+            format: VectorExportFormat::Npy,
+        };
+        match handle_message(msg, &state).await {
+            IpcResponse::VectorsExported { files, .. } => {
+                let target = state.exports_path().join("run-1");
+                assert!(files.iter().all(|file| file.starts_with(&target)));
+                assert!(target.join("events/embeddings.npy").exists());
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
     }
 }
 
@@ -1071,6 +4620,18 @@ fn gather_diagnostic_info(state: &Arc<DaemonState>) -> DiagnosticInfo {
     let events_index_count = state.events_index.read().map(|idx| idx.len()).unwrap_or(0);
     let exchanges_index_count = state.exchanges_index.read().map(|idx| idx.len()).unwrap_or(0);
 
+    // Get live HNSW parameters
+    let events_index_hnsw = state
+        .events_index
+        .read()
+        .map(|idx| idx.hnsw_config())
+        .unwrap_or_default();
+    let exchanges_index_hnsw = state
+        .exchanges_index
+        .read()
+        .map(|idx| idx.hnsw_config())
+        .unwrap_or_default();
+
     // Get file sizes
     let db_path = state.diachron_home.join("diachron.db");
     let database_size_bytes = std::fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
@@ -1082,7 +4643,11 @@ fn gather_diagnostic_info(state: &Arc<DaemonState>) -> DiagnosticInfo {
     let exchanges_index_size_bytes = std::fs::metadata(&exchanges_index_path).map(|m| m.len()).unwrap_or(0);
 
     // Check if model is loaded
-    let model_loaded = state.embedding_engine.read().map(|e| e.is_some()).unwrap_or(false);
+    let model_loaded = state.embedding_engines.is_loaded();
+    let embedding_pool_size = state.embedding_engines.size();
+
+    // Embedding cache effectiveness
+    let (embedding_cache_hits, embedding_cache_misses) = state.embedding_engines.cache_stats();
 
     // Get model file size
     let model_path = state.diachron_home.join("models/all-MiniLM-L6-v2/model.onnx");
@@ -1103,6 +4668,11 @@ fn gather_diagnostic_info(state: &Arc<DaemonState>) -> DiagnosticInfo {
         model_loaded,
         model_size_bytes,
         memory_rss_bytes,
+        events_index_hnsw,
+        exchanges_index_hnsw,
+        embedding_cache_hits,
+        embedding_cache_misses,
+        embedding_pool_size,
     }
 }
 
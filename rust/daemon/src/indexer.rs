@@ -17,6 +17,8 @@ use tracing::{debug, warn};
 
 use diachron_core::Exchange;
 
+use crate::config::IndexConfig;
+
 /// Raw JSONL message from a Claude Code archive line.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -200,27 +202,38 @@ fn generate_exchange_id(project: &str, timestamp: &str, user_prefix: &str) -> St
 }
 
 /// Extract text from content (handles both string and array formats)
-fn extract_text_content(content: &serde_json::Value) -> String {
+///
+/// Which blocks are kept follows the `[index]` config. Thinking goes after
+/// the reply so it never crowds the reply out of the truncated embed text.
+fn extract_text_content(content: &serde_json::Value, config: &IndexConfig) -> String {
     match content {
         serde_json::Value::String(s) => s.clone(),
         serde_json::Value::Array(blocks) => {
             let mut texts = Vec::new();
+            let mut thoughts = Vec::new();
             for block in blocks {
                 if let Ok(cb) = serde_json::from_value::<ContentBlock>(block.clone()) {
                     match cb {
                         ContentBlock::Text { text } => texts.push(text),
-                        ContentBlock::Thinking { thinking: _ } => {
-                            // Skip thinking blocks (internal reasoning)
+                        ContentBlock::Thinking { thinking } => {
+                            // Internal reasoning, skipped unless asked for
+                            if config.include_thinking && !thinking.trim().is_empty() {
+                                thoughts.push(format!("[Thinking: {}]", thinking));
+                            }
                         }
                         ContentBlock::ToolUse { name, .. } => {
                             texts.push(format!("[Tool: {}]", name));
                         }
                         ContentBlock::ToolResult { content, .. } => {
+                            if !config.include_tool_results {
+                                continue;
+                            }
                             if let Some(s) = content.as_str() {
                                 // Truncate tool results as they can be very long
                                 // Use safe_truncate to handle UTF-8 char boundaries
-                                let truncated = if s.len() > 200 {
-                                    format!("{}...", safe_truncate(s, 200))
+                                let max = config.tool_result_max_chars;
+                                let truncated = if s.len() > max {
+                                    format!("{}...", safe_truncate(s, max))
                                 } else {
                                     s.to_string()
                                 };
@@ -231,6 +244,7 @@ fn extract_text_content(content: &serde_json::Value) -> String {
                     }
                 }
             }
+            texts.extend(thoughts);
             texts.join("\n")
         }
         _ => String::new(),
@@ -268,13 +282,18 @@ fn extract_tool_calls(content: &serde_json::Value) -> Option<String> {
 /// # Arguments
 /// - `archive_path`: Path to the JSONL archive.
 /// - `start_line`: Line offset to resume parsing from.
+/// - `config`: `[index]` settings choosing which content blocks are kept.
 ///
 /// # Returns
 /// Vector of exchanges found starting from `start_line`.
 ///
 /// # Errors
 /// Returns `anyhow::Error` if the archive cannot be read.
-pub fn parse_archive(archive_path: &Path, start_line: u64) -> anyhow::Result<Vec<Exchange>> {
+pub fn parse_archive(
+    archive_path: &Path,
+    start_line: u64,
+    config: &IndexConfig,
+) -> anyhow::Result<Vec<Exchange>> {
     let file = File::open(archive_path)?;
     let reader = BufReader::new(file);
 
@@ -330,8 +349,9 @@ pub fn parse_archive(archive_path: &Path, start_line: u64) -> anyhow::Result<Vec
                     if let (Some(user_content), Some(assistant_content)) =
                         (&user_msg.message, &msg.message)
                     {
-                        let user_text = extract_text_content(&user_content.content);
-                        let assistant_text = extract_text_content(&assistant_content.content);
+                        let user_text = extract_text_content(&user_content.content, config);
+                        let assistant_text =
+                            extract_text_content(&assistant_content.content, config);
 
                         // Skip if both are empty (keep exchanges with at least one side)
                         if user_text.is_empty() && assistant_text.is_empty() {
@@ -422,17 +442,29 @@ pub fn safe_truncate(s: &str, max_bytes: usize) -> &str {
 /// # Returns
 /// Concatenated user/assistant text with truncation applied.
 pub fn build_exchange_embed_text(exchange: &Exchange) -> String {
+    exchange_embed_text(&exchange.user_message, &exchange.assistant_message)
+}
+
+/// Build embed text from the messages of a stored exchange.
+///
+/// # Arguments
+/// - `user_message`: The user's message.
+/// - `assistant_message`: The assistant's reply.
+///
+/// # Returns
+/// Concatenated user/assistant text with truncation applied.
+pub fn exchange_embed_text(user_message: &str, assistant_message: &str) -> String {
     // Truncate each to ~1000 chars for 2000 total
-    let user_truncated = if exchange.user_message.len() > 1000 {
-        format!("{}...", safe_truncate(&exchange.user_message, 1000))
+    let user_truncated = if user_message.len() > 1000 {
+        format!("{}...", safe_truncate(user_message, 1000))
     } else {
-        exchange.user_message.clone()
+        user_message.to_string()
     };
 
-    let assistant_truncated = if exchange.assistant_message.len() > 900 {
-        format!("{}...", safe_truncate(&exchange.assistant_message, 900))
+    let assistant_truncated = if assistant_message.len() > 900 {
+        format!("{}...", safe_truncate(assistant_message, 900))
     } else {
-        exchange.assistant_message.clone()
+        assistant_message.to_string()
     };
 
     format!(
@@ -462,7 +494,10 @@ mod tests {
     #[test]
     fn test_extract_text_from_string() {
         let content = serde_json::json!("Hello, world!");
-        assert_eq!(extract_text_content(&content), "Hello, world!");
+        assert_eq!(
+            extract_text_content(&content, &IndexConfig::default()),
+            "Hello, world!"
+        );
     }
 
     #[test]
@@ -471,7 +506,7 @@ mod tests {
             {"type": "text", "text": "First part"},
             {"type": "text", "text": "Second part"}
         ]);
-        let text = extract_text_content(&content);
+        let text = extract_text_content(&content, &IndexConfig::default());
         assert!(text.contains("First part"));
         assert!(text.contains("Second part"));
     }
@@ -514,6 +549,36 @@ mod tests {
         assert!(text.contains("Assistant: You can use OAuth2"));
     }
 
+    #[test]
+    fn test_thinking_in_embed_text_only_when_enabled() {
+        let content = serde_json::json!([
+            {"type": "thinking", "thinking": "The token expires before the retry fires"},
+            {"type": "text", "text": "Refresh the token before retrying."},
+            {"type": "tool_result", "tool_use_id": "t1", "content": "ok"}
+        ]);
+        let embed_text = |config: &IndexConfig| {
+            exchange_embed_text(
+                "Why does the retry fail?",
+                &extract_text_content(&content, config),
+            )
+        };
+
+        let default = embed_text(&IndexConfig::default());
+        assert!(!default.contains("token expires"));
+        assert!(default.contains("[Result: ok]"));
+
+        let config = IndexConfig {
+            include_thinking: true,
+            include_tool_results: false,
+            ..Default::default()
+        };
+        let text = embed_text(&config);
+        assert!(text.contains("[Thinking: The token expires before the retry fires]"));
+        // The reply still leads the assistant text
+        assert!(text.contains("Assistant: Refresh the token"));
+        assert!(!text.contains("[Result:"));
+    }
+
     #[test]
     fn test_index_state_roundtrip() {
         let mut state = IndexState::default();
@@ -7,30 +7,52 @@
 //!
 //! Architecture:
 //! - Unix socket listener at ~/.diachron/diachron.sock
-//! - JSON-RPC style messages (IpcMessage/IpcResponse)
+//! - Optional TCP listener (`DIACHRON_LISTEN=127.0.0.1:7700`)
+//! - JSON-RPC style messages (IpcMessage/IpcResponse), newline or length-prefixed
 //! - Keeps ONNX model hot in memory for fast embeddings
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
 use anyhow::Result;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tracing::{info, warn};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
 
 mod cache;
+mod capture;
 mod background;
+mod config;
 mod db;
+mod engine_pool;
+mod feed;
 mod handlers;
 mod indexer;
+mod metrics;
+mod path_filter;
+mod reindex;
 mod server;
 mod summarization;
+mod vector_export;
 
+use cache::{SearchCache, TimelineCache};
+use capture::CaptureQueue;
+use config::{DaemonConfig, EmbeddingSettings, SearchConfig};
 pub use db::Database;
-use cache::SearchCache;
-use diachron_core::{IpcMessage, IpcResponse, VectorIndex, EMBEDDING_DIM};
-use diachron_embeddings::EmbeddingEngine;
+use diachron_core::{
+    is_framed_start, DaemonError, IpcMessage, IpcResponse, VectorIndex, MAX_FRAME_LEN,
+};
+use diachron_embeddings::{
+    ensure_model_exists, ensure_reranker_model_exists, CrossEncoder, EmbeddingEngine,
+};
+use engine_pool::EnginePool;
+use feed::EventFeed;
+use handlers::RelevanceScorer;
+use metrics::Metrics;
 use summarization::Summarizer;
 
 /// Global state for the daemon.
@@ -47,11 +69,14 @@ pub struct DaemonState {
     /// Path to the global diachron directory
     diachron_home: PathBuf,
 
+    /// Settings from config.toml
+    pub config: DaemonConfig,
+
     /// Database handle
     pub db: Database,
 
-    /// Embedding engine (loaded lazily, may be None if model not available)
-    pub embedding_engine: RwLock<Option<EmbeddingEngine>>,
+    /// Embedding engines (empty if the model is not available)
+    pub embedding_engines: EnginePool,
 
     /// Vector index for events
     pub events_index: RwLock<VectorIndex>,
@@ -62,8 +87,29 @@ pub struct DaemonState {
     /// Summarizer for conversation exchanges (optional)
     pub summarizer: Option<Summarizer>,
 
+    /// Cross-encoders for `search.rerank` (empty when disabled or not loaded)
+    pub reranker: EnginePool<Box<dyn RelevanceScorer>>,
+
     /// Cache for search results
     pub search_cache: RwLock<SearchCache>,
+
+    /// Cache for timeline pages
+    pub timeline_cache: RwLock<TimelineCache>,
+
+    /// Queue feeding the batched capture writer
+    pub capture_queue: CaptureQueue,
+
+    /// Saved events pushed to `Subscribe` connections
+    pub event_feed: EventFeed,
+
+    /// Vector inserts rejected for the wrong dimension since the last rebuild
+    dimension_mismatches: AtomicU64,
+
+    /// Whether a vector index rebuild is running
+    reindexing: AtomicBool,
+
+    /// Search and summarization counters for the metrics endpoint
+    pub metrics: Metrics,
 }
 
 impl DaemonState {
@@ -80,81 +126,79 @@ impl DaemonState {
         std::fs::create_dir_all(&diachron_home)?;
         std::fs::create_dir_all(diachron_home.join("indexes"))?;
 
+        let config = DaemonConfig::load(&diachron_home);
+
         // Open database
         let db_path = diachron_home.join("diachron.db");
         let db = Database::open(db_path)?;
 
-        // Try to load embedding engine (may fail if model not downloaded)
-        let embedding_engine = match EmbeddingEngine::new_default() {
-            Ok(engine) => {
-                info!("Embedding engine loaded successfully");
-                Some(engine)
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to load embedding engine: {}. Semantic search will be unavailable.",
-                    e
-                );
-                warn!("Run 'diachron download-model' to download the embedding model.");
-                None
-            }
-        };
+        // Try to load embedding engines (may fail if model not downloaded)
+        let embedding_engines = load_embedding_pool(&config.embedding);
+        // Take the first-inference cost now rather than in the first search
+        embedding_engines.warm_up_in_background();
 
-        // Load or create vector indexes
-        let events_index_path = diachron_home.join("indexes").join("events");
-        let events_index = if VectorIndex::exists(&events_index_path) {
-            match VectorIndex::load(&events_index_path) {
-                Ok(idx) => {
-                    info!("Loaded events vector index ({} vectors)", idx.len());
-                    idx
-                }
-                Err(e) => {
-                    warn!("Failed to load events index, creating new: {}", e);
-                    VectorIndex::new(EMBEDDING_DIM)?
-                }
-            }
+        let reranker = if config.search.rerank {
+            load_reranker_pool(&config.search)
         } else {
-            info!("Creating new events vector index");
-            VectorIndex::new(EMBEDDING_DIM)?
+            EnginePool::empty()
         };
 
-        let exchanges_index_path = diachron_home.join("indexes").join("exchanges");
-        let exchanges_index = if VectorIndex::exists(&exchanges_index_path) {
-            match VectorIndex::load(&exchanges_index_path) {
-                Ok(idx) => {
-                    info!("Loaded exchanges vector index ({} vectors)", idx.len());
-                    idx
-                }
-                Err(e) => {
-                    warn!("Failed to load exchanges index, creating new: {}", e);
-                    VectorIndex::new(EMBEDDING_DIM)?
-                }
-            }
-        } else {
-            info!("Creating new exchanges vector index");
-            VectorIndex::new(EMBEDDING_DIM)?
-        };
+        // Size indexes to the model that will fill them
+        let dim = embedding_engines.dim().unwrap_or(config.embedding.dim);
 
-        // Initialize summarizer (optional - depends on API key availability)
+        // Load or create vector indexes
+        let events_index = load_or_create_index(&diachron_home.join("indexes"), "events", dim)?;
+        let exchanges_index =
+            load_or_create_index(&diachron_home.join("indexes"), "exchanges", dim)?;
+
+        // Initialize summarizer (optional - depends on the provider's settings).
+        // Whether a local server is up is checked when summarizing, since the
+        // probe blocks and this runs on the async runtime.
         let summarizer = Summarizer::new(&diachron_home);
-        if summarizer.is_available() {
-            info!("Summarizer available (API key found)");
+        if summarizer.is_configured() {
+            info!("Summarizer configured ({})", summarizer.provider_name());
         } else {
-            info!("Summarizer unavailable (no API key). Set ANTHROPIC_API_KEY to enable.");
+            info!(
+                "Summarizer unavailable ({}). {} to enable.",
+                summarizer.provider_name(),
+                summarizer.setup_hint()
+            );
         }
 
-        Ok(Self {
+        let state = Self {
             start_time: Instant::now(),
             events_count: AtomicU64::new(0),
             shutdown: AtomicBool::new(false),
             diachron_home,
+            config,
             db,
-            embedding_engine: RwLock::new(embedding_engine),
+            embedding_engines,
             events_index: RwLock::new(events_index),
             exchanges_index: RwLock::new(exchanges_index),
-            summarizer: if summarizer.is_available() { Some(summarizer) } else { None },
+            summarizer: summarizer.is_configured().then_some(summarizer),
+            reranker,
             search_cache: RwLock::new(SearchCache::new(256)),
-        })
+            timeline_cache: RwLock::new(TimelineCache::new(64)),
+            capture_queue: CaptureQueue::default(),
+            event_feed: EventFeed::default(),
+            dimension_mismatches: AtomicU64::new(0),
+            reindexing: AtomicBool::new(false),
+            metrics: Metrics::default(),
+        };
+
+        // Repair index/database drift left by an unclean shutdown
+        if state.config.index.reconcile_on_start {
+            match reindex::reconcile_indexes(&state) {
+                Ok(stats) if stats.restored > 0 || stats.removed > 0 => info!(
+                    "Reconciled vector indexes: restored {} vectors, removed {} stale entries",
+                    stats.restored, stats.removed
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Vector index reconciliation failed: {}", e),
+            }
+        }
+
+        Ok(state)
     }
 
     /// Get uptime in seconds.
@@ -195,6 +239,7 @@ impl DaemonState {
     ///
     /// # Returns
     /// Path to the Unix socket used by the daemon.
+    #[cfg(unix)]
     pub fn socket_path(&self) -> PathBuf {
         self.diachron_home.join("diachron.sock")
     }
@@ -207,30 +252,201 @@ impl DaemonState {
         self.diachron_home.join("indexes")
     }
 
+    /// Get the path to the vector exports directory.
+    ///
+    /// # Returns
+    /// Directory `ExportVectors` is confined to.
+    pub fn exports_path(&self) -> PathBuf {
+        self.diachron_home.join("exports")
+    }
+
     /// Save vector indexes to disk.
     ///
+    /// Only indexes changed since they were loaded or last saved are
+    /// written, so calling this often (autosave, shutdown) is cheap.
+    ///
+    /// # Returns
+    /// Number of indexes written.
+    ///
     /// # Errors
     /// Returns `anyhow::Error` if index persistence fails.
-    pub fn save_indexes(&self) -> anyhow::Result<()> {
+    pub fn save_indexes(&self) -> anyhow::Result<usize> {
         let indexes_path = self.indexes_path();
+        let mut saved = 0;
+
+        for (name, lock) in [
+            ("events", &self.events_index),
+            ("exchanges", &self.exchanges_index),
+        ] {
+            if let Ok(idx) = lock.read() {
+                // An emptied index is still written over its old files
+                let path = indexes_path.join(name);
+                if idx.is_dirty() && (idx.len() > 0 || VectorIndex::exists(&path)) {
+                    idx.save(&path)?;
+                    info!("Saved {} index ({} vectors)", name, idx.len());
+                    saved += 1;
+                }
+            }
+        }
 
-        // Save events index
-        if let Ok(idx) = self.events_index.read() {
-            if idx.len() > 0 {
-                idx.save(&indexes_path.join("events"))?;
-                info!("Saved events index ({} vectors)", idx.len());
+        Ok(saved)
+    }
+}
+
+/// Load `pool_size` embedding engines for the `[embedding]` config section.
+///
+/// If the first engine fails to load the pool is empty and semantic search
+/// is disabled; a later failure just leaves the pool smaller.
+fn load_embedding_pool(settings: &EmbeddingSettings) -> EnginePool {
+    match load_embedding_engines(settings) {
+        Ok(engines) => {
+            let pool_size = engines.len();
+            let pool = EnginePool::new(engines);
+            match pool.dim() {
+                Some(dim) => info!(
+                    "Embedding engine loaded successfully ({}-dim, pool of {})",
+                    dim, pool_size
+                ),
+                None => warn!("Embedding engine loaded but failed to embed a probe text"),
             }
+            pool
         }
+        Err(e) => {
+            warn!(
+                "Failed to load embedding engine: {}. Semantic search will be unavailable.",
+                e
+            );
+            if settings.model_dir.is_none() {
+                warn!("Run 'diachron doctor --fix' to download and load the embedding model.");
+            }
+            EnginePool::empty()
+        }
+    }
+}
 
-        // Save exchanges index
-        if let Ok(idx) = self.exchanges_index.read() {
-            if idx.len() > 0 {
-                idx.save(&indexes_path.join("exchanges"))?;
-                info!("Saved exchanges index ({} vectors)", idx.len());
+/// Load up to `pool_size` engines for the `[embedding]` config section.
+///
+/// # Arguments
+/// - `settings`: Embedding model configuration.
+///
+/// # Returns
+/// At least one engine; a failure after the first leaves fewer than
+/// `pool_size`.
+///
+/// # Errors
+/// Returns `EmbeddingError` if the first engine fails to load.
+pub(crate) fn load_embedding_engines(
+    settings: &EmbeddingSettings,
+) -> diachron_embeddings::Result<Vec<EmbeddingEngine>> {
+    let size = settings.pool_size.max(1);
+    let mut engines = vec![load_embedding_engine(settings)?];
+    while engines.len() < size {
+        match load_embedding_engine(settings) {
+            Ok(engine) => engines.push(engine),
+            Err(e) => {
+                warn!(
+                    "Failed to load embedding engine {} of {}: {}. Continuing with {}.",
+                    engines.len() + 1,
+                    size,
+                    e,
+                    engines.len()
+                );
+                break;
             }
         }
+    }
+    Ok(engines)
+}
 
-        Ok(())
+/// Load the embedding engine described by the `[embedding]` config section.
+///
+/// The default model is downloaded if missing; a custom `model_dir` must
+/// already contain `model.onnx` and `tokenizer.json`.
+fn load_embedding_engine(
+    settings: &EmbeddingSettings,
+) -> diachron_embeddings::Result<EmbeddingEngine> {
+    if settings.model_dir.is_none() {
+        ensure_model_exists()?;
+    }
+    EmbeddingEngine::new(settings.engine_config())
+}
+
+/// Load up to `rerank_pool_size` cross-encoders for `search.rerank`.
+///
+/// The default model is downloaded if missing. Without it, search keeps the
+/// fused order.
+fn load_reranker_pool(search: &SearchConfig) -> EnginePool<Box<dyn RelevanceScorer>> {
+    if search.rerank_model_dir.is_none() {
+        if let Err(e) = ensure_reranker_model_exists() {
+            warn!(
+                "Failed to load re-ranking model: {}. Search results keep their fused order.",
+                e
+            );
+            return EnginePool::empty();
+        }
+    }
+
+    let size = search.rerank_pool_size.max(1);
+    let mut rerankers: Vec<Box<dyn RelevanceScorer>> = Vec::with_capacity(size);
+    while rerankers.len() < size {
+        match CrossEncoder::new(search.reranker_config()) {
+            Ok(reranker) => rerankers.push(Box::new(reranker)),
+            Err(e) if rerankers.is_empty() => {
+                warn!(
+                    "Failed to load re-ranking model: {}. Search results keep their fused order.",
+                    e
+                );
+                return EnginePool::empty();
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to load re-ranking model {} of {}: {}. Continuing with {}.",
+                    rerankers.len() + 1,
+                    size,
+                    e,
+                    rerankers.len()
+                );
+                break;
+            }
+        }
+    }
+    info!("Re-ranking model loaded ({} engines)", rerankers.len());
+    EnginePool::new(rerankers)
+}
+
+/// Load a persisted vector index, or create an empty one of `dim` dimensions.
+///
+/// An index built with a different dimension than the configured model is
+/// not loaded, since none of its vectors could be compared against new
+/// query embeddings.
+fn load_or_create_index(indexes_dir: &Path, name: &str, dim: usize) -> anyhow::Result<VectorIndex> {
+    let path = indexes_dir.join(name);
+    if !VectorIndex::exists(&path) {
+        info!("Creating new {} vector index", name);
+        return Ok(VectorIndex::new(dim, None)?);
+    }
+
+    match VectorIndex::load(&path) {
+        Ok(idx) if idx.dim() != dim => {
+            error!(
+                "The {} vector index at {:?} has {}-dim vectors but the configured embedding \
+                 model produces {}-dim vectors; refusing to load it. Run \
+                 'diachron memory reindex --force' to rebuild it from stored history.",
+                name,
+                path.with_extension("usearch"),
+                idx.dim(),
+                dim
+            );
+            Ok(VectorIndex::new(dim, None)?)
+        }
+        Ok(idx) => {
+            info!("Loaded {} vector index ({} vectors)", name, idx.len());
+            Ok(idx)
+        }
+        Err(e) => {
+            warn!("Failed to load {} index, creating new: {}", name, e);
+            Ok(VectorIndex::new(dim, None)?)
+        }
     }
 }
 
@@ -243,21 +459,30 @@ impl DaemonState {
             .unwrap_or_else(|| PathBuf::from("/tmp/.diachron-test"));
         std::fs::create_dir_all(&diachron_home)?;
 
+        let config = DaemonConfig::default();
         let db = Database::open(db_path)?;
-        let events_index = VectorIndex::new(EMBEDDING_DIM)?;
-        let exchanges_index = VectorIndex::new(EMBEDDING_DIM)?;
+        let events_index = VectorIndex::new(config.embedding.dim, None)?;
+        let exchanges_index = VectorIndex::new(config.embedding.dim, None)?;
 
         Ok(Self {
             start_time: Instant::now(),
             events_count: AtomicU64::new(0),
             shutdown: AtomicBool::new(false),
             diachron_home,
+            config,
             db,
-            embedding_engine: RwLock::new(None),
+            embedding_engines: EnginePool::empty(),
             events_index: RwLock::new(events_index),
             exchanges_index: RwLock::new(exchanges_index),
             summarizer: None,
+            reranker: EnginePool::empty(),
             search_cache: RwLock::new(SearchCache::new(16)),
+            timeline_cache: RwLock::new(TimelineCache::new(16)),
+            capture_queue: CaptureQueue::default(),
+            event_feed: EventFeed::default(),
+            dimension_mismatches: AtomicU64::new(0),
+            reindexing: AtomicBool::new(false),
+            metrics: Metrics::default(),
         })
     }
 }
@@ -291,35 +516,218 @@ async fn main() -> Result<()> {
     let state = Arc::new(DaemonState::new()?);
 
     // Remove stale socket
-    let socket_path = state.socket_path();
-    if socket_path.exists() {
-        std::fs::remove_file(&socket_path)?;
+    #[cfg(unix)]
+    {
+        let socket_path = state.socket_path();
+        if socket_path.exists() {
+            std::fs::remove_file(&socket_path)?;
+        }
     }
 
     // Start the server
     server::run(state).await
 }
 
-/// Handle a single client connection
-async fn handle_client(mut stream: tokio::net::UnixStream, state: Arc<DaemonState>) -> Result<()> {
-    let (reader, mut writer) = stream.split();
+/// Handle a single client connection (Unix socket or TCP)
+///
+/// The first byte selects the framing for the whole connection: `{` means
+/// newline-delimited JSON, anything else a 4-byte big-endian length prefix.
+async fn handle_client<S>(stream: S, state: Arc<DaemonState>) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
     let mut reader = BufReader::new(reader);
-    let mut line = String::new();
 
-    while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcMessage>(&line) {
-            Ok(msg) => handlers::handle_message(msg, &state).await,
-            Err(e) => {
-                warn!("Invalid message: {}", e);
-                IpcResponse::Error(format!("Invalid message: {}", e))
-            }
+    let framed = match reader.fill_buf().await?.first() {
+        Some(&byte) => is_framed_start(byte),
+        None => return Ok(()),
+    };
+
+    while let Some(request) = read_request(&mut reader, framed).await? {
+        let msg: serde_json::Result<IpcMessage> = serde_json::from_slice(&request);
+
+        if let Ok(IpcMessage::Subscribe) = msg {
+            // The connection carries nothing but the feed from here on
+            return forward_feed(&mut writer, &state, framed).await;
+        }
+
+        if let Ok(IpcMessage::Search {
+            query,
+            limit,
+            source_filter,
+            since,
+            until,
+            project,
+            tool,
+            stream: true,
+            after,
+            prefer,
+            explain,
+        }) = msg
+        {
+            // Forward chunks to the client while the search produces them
+            let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+            let filters = handlers::SearchFilters {
+                source: source_filter,
+                since: since.as_deref(),
+                until: until.as_deref(),
+                project: project.as_deref(),
+                tool: tool.as_deref(),
+                prefer,
+                explain,
+            };
+            let search =
+                handlers::stream_search(&state, &query, limit, after.as_ref(), filters, tx);
+            let forward = async {
+                while let Some(response) = rx.recv().await {
+                    write_response(&mut writer, &response, framed).await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            };
+            let ((), forwarded) = tokio::join!(search, forward);
+            forwarded?;
+            continue;
+        }
+
+        let response = dispatch(msg, &state).await;
+        write_response(&mut writer, &response, framed).await?;
+    }
+
+    Ok(())
+}
+
+/// Push saved events to a subscribed client until it disconnects
+///
+/// Acknowledges with `Ok`, then writes an `EventPushed` per capture and an
+/// `Ok` heartbeat whenever the feed has been quiet for a while.
+async fn forward_feed<W>(writer: &mut W, state: &DaemonState, framed: bool) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut events = state.event_feed.subscribe();
+    write_response(writer, &IpcResponse::Ok, framed).await?;
+
+    let mut heartbeat = tokio::time::interval(feed::HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+    loop {
+        let response = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    heartbeat.reset();
+                    IpcResponse::EventPushed(event)
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Subscriber fell behind and missed {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = heartbeat.tick() => IpcResponse::Ok,
         };
+        write_response(writer, &response, framed).await?;
+    }
+}
 
-        let response_json = serde_json::to_string(&response)? + "\n";
-        writer.write_all(response_json.as_bytes()).await?;
+/// Bytes reserved for a frame before its body starts arriving
+const FRAME_PREALLOC: usize = 64 * 1024;
+
+/// Read one request (a line or a length-delimited frame); `None` at EOF
+async fn read_request<R>(reader: &mut R, framed: bool) -> Result<Option<Vec<u8>>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    if !framed {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(None);
+        }
+        return Ok(Some(line));
+    }
 
-        line.clear();
+    let len = match reader.read_u32().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > MAX_FRAME_LEN {
+        anyhow::bail!("Frame of {} bytes exceeds limit", len);
+    }
+    // Any local or TCP peer can declare a huge length; grow the buffer with
+    // the bytes that actually arrive instead of allocating it up front
+    let mut payload = Vec::with_capacity(len.min(FRAME_PREALLOC));
+    (&mut *reader)
+        .take(len as u64)
+        .read_to_end(&mut payload)
+        .await?;
+    if payload.len() < len {
+        anyhow::bail!(
+            "Connection closed after {} of {} frame bytes",
+            payload.len(),
+            len
+        );
     }
+    Ok(Some(payload))
+}
 
+/// Write one response using the connection's framing
+async fn write_response<W>(writer: &mut W, response: &IpcResponse, framed: bool) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut response_json = serde_json::to_vec(response)?;
+    if framed {
+        writer.write_u32(response_json.len() as u32).await?;
+    } else {
+        response_json.push(b'\n');
+    }
+    writer.write_all(&response_json).await?;
+    writer.flush().await?;
     Ok(())
 }
+
+/// Route a decoded message to its handler, reporting decode failures
+async fn dispatch(msg: serde_json::Result<IpcMessage>, state: &Arc<DaemonState>) -> IpcResponse {
+    match msg {
+        Ok(msg) => handlers::handle_message(msg, state).await,
+        Err(e) => {
+            warn!("Invalid message: {}", e);
+            IpcResponse::Error(DaemonError::InvalidRequest(format!(
+                "Invalid message: {}",
+                e
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_truncated_frame_is_an_error() {
+        // Declares the largest allowed frame, then hangs up after 3 bytes
+        let mut bytes = (MAX_FRAME_LEN as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(b"{}\n");
+        let mut reader = BufReader::new(bytes.as_slice());
+
+        let err = read_request(&mut reader, true).await.unwrap_err();
+        assert!(err.to_string().contains("after 3 of"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_framed_requests_read_back_to_back() {
+        let mut bytes = Vec::new();
+        for payload in [&b"{\"type\":\"Ping\"}"[..], b"{}"] {
+            bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(payload);
+        }
+        let mut reader = BufReader::new(bytes.as_slice());
+
+        let first = read_request(&mut reader, true).await.unwrap().unwrap();
+        assert_eq!(first, b"{\"type\":\"Ping\"}");
+        let second = read_request(&mut reader, true).await.unwrap().unwrap();
+        assert_eq!(second, b"{}");
+        assert!(read_request(&mut reader, true).await.unwrap().is_none());
+    }
+}
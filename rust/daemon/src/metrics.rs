@@ -0,0 +1,294 @@
+//! Prometheus metrics endpoint
+//!
+//! When `[metrics] port` is set, the daemon serves `GET /metrics` on
+//! `127.0.0.1:<port>` in the Prometheus text exposition format. Counters and
+//! histograms are plain atomics updated on the request path, so recording
+//! costs nothing measurable when no one scrapes. Without the config key no
+//! port is opened.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info};
+
+use crate::DaemonState;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Largest request head read before answering.
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Cumulative latency histogram with fixed buckets.
+pub struct Histogram {
+    /// Observations at or below each bucket bound
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Histogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Self {
+            buckets: LATENCY_BUCKETS.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Record one observation.
+    pub fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Append the histogram's exposition lines.
+    pub fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{le=\"{}\"}} {}",
+                name,
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(
+            out,
+            "{}_sum {}",
+            name,
+            self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// Request counters and latency histograms for the metrics endpoint.
+///
+/// Captured events are counted by `DaemonState::events_count` and
+/// embeddings by the engine pool, which sees every embedding call.
+#[derive(Default)]
+pub struct Metrics {
+    searches: AtomicU64,
+    summarization_calls: AtomicU64,
+    search_latency: Histogram,
+}
+
+impl Metrics {
+    /// Record a served search.
+    ///
+    /// # Arguments
+    /// - `elapsed`: Time from request to ranked results.
+    pub fn record_search(&self, elapsed: Duration) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        self.search_latency.observe(elapsed);
+    }
+
+    /// Record a request to the summarization provider, including retries.
+    pub fn record_summarization(&self) {
+        self.summarization_calls.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Render all metrics in the Prometheus text format.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the counters.
+///
+/// # Returns
+/// Exposition text, one sample per line.
+pub fn render(state: &DaemonState) -> String {
+    let metrics = &state.metrics;
+    let pool = &state.embedding_engines;
+    let mut out = String::new();
+
+    let counters = [
+        (
+            "diachron_events_captured_total",
+            "Events captured since the daemon started",
+            state.events_count(),
+        ),
+        (
+            "diachron_searches_total",
+            "Searches served",
+            metrics.searches.load(Ordering::Relaxed),
+        ),
+        (
+            "diachron_embeddings_total",
+            "Texts embedded",
+            pool.embedded_texts(),
+        ),
+        (
+            "diachron_summarization_calls_total",
+            "Requests sent to the summarization provider",
+            metrics.summarization_calls.load(Ordering::Relaxed),
+        ),
+    ];
+    for (name, help, value) in counters {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+
+    metrics.search_latency.render(
+        &mut out,
+        "diachron_search_latency_seconds",
+        "Search latency",
+    );
+    pool.embed_latency().render(
+        &mut out,
+        "diachron_embedding_latency_seconds",
+        "Latency of embedding calls (single texts and batches)",
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP diachron_uptime_seconds Seconds since the daemon started"
+    );
+    let _ = writeln!(out, "# TYPE diachron_uptime_seconds gauge");
+    let _ = writeln!(out, "diachron_uptime_seconds {}", state.uptime_secs());
+    out
+}
+
+/// Bind the metrics listener if `[metrics] port` is configured.
+///
+/// Bind failures are logged; the daemon keeps running without metrics.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+pub async fn spawn_server(state: Arc<DaemonState>) {
+    let Some(port) = state.config.metrics.port else {
+        return;
+    };
+    match TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => {
+            info!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+            tokio::spawn(serve(listener, state));
+        }
+        Err(e) => error!("Failed to bind metrics listener on port {}: {}", port, e),
+    }
+}
+
+/// Answer metrics scrapes until the daemon exits.
+pub async fn serve(listener: TcpListener, state: Arc<DaemonState>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream, &state).await {
+                        debug!("Metrics request failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => error!("Metrics accept error: {}", e),
+        }
+    }
+}
+
+/// Serve one HTTP/1.1 request and close the connection.
+async fn respond(mut stream: TcpStream, state: &DaemonState) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = String::from_utf8_lossy(&head);
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render(state)),
+        (Some("GET"), Some(_)) => ("404 Not Found", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "Method not allowed\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::temp_dir;
+    use diachron_core::{CaptureEvent, IpcMessage, IpcResponse, Operation};
+
+    async fn scrape(addr: std::net::SocketAddr) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        response
+    }
+
+    fn sample(body: &str, name: &str) -> Option<u64> {
+        body.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .and_then(|value| value.parse().ok())
+    }
+
+    #[tokio::test]
+    async fn test_capture_counter_increments() {
+        let dir = temp_dir();
+        let state = Arc::new(DaemonState::new_for_tests(dir.join("diachron.db")).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::clone(&state)));
+
+        let before = scrape(addr).await;
+        assert_eq!(sample(&before, "diachron_events_captured_total"), Some(0));
+        assert!(before.contains("diachron_search_latency_seconds_bucket{le=\"+Inf\"} 0"));
+
+        let event = CaptureEvent {
+            tool_name: "Write".to_string(),
+            file_path: Some("src/lib.rs".to_string()),
+            operation: Operation::Create,
+            diff_summary: Some("+1 lines".to_string()),
+            raw_input: Some("pub fn answer() -> u32 { 42 }".to_string()),
+            ..Default::default()
+        };
+        let response = crate::handlers::handle_message(IpcMessage::Capture(event), &state).await;
+        assert!(matches!(response, IpcResponse::Ok));
+
+        let after = scrape(addr).await;
+        assert_eq!(sample(&after, "diachron_events_captured_total"), Some(1));
+    }
+}
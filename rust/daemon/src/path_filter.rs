@@ -0,0 +1,211 @@
+//! File path filters for timeline queries.
+//!
+//! Substring filters run entirely in SQL. Globs and regexes cannot be
+//! expressed exactly in SQLite, so the query narrows candidates with a SQL
+//! pattern where one can be derived and the rest is matched here.
+
+use std::path::Path;
+
+use diachron_core::{FileMatch, StoredEvent};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+
+/// Characters with special meaning in a glob.
+const GLOB_META: &[char] = &['*', '?', '[', ']', '{', '}', '\\'];
+
+/// A compiled file path filter.
+#[derive(Debug, Clone)]
+pub enum PathFilter {
+    /// Case-insensitive substring, matched in SQL
+    Substring(String),
+    /// Glob pattern
+    Glob {
+        /// Pattern as given
+        pattern: String,
+        /// Matcher for the pattern as written
+        anchored: GlobMatcher,
+        /// Matcher allowing any leading directories, for paths that cannot
+        /// be made relative to a working directory
+        unanchored: GlobMatcher,
+    },
+    /// Regular expression searched anywhere in the path
+    Regex(Regex),
+}
+
+impl PathFilter {
+    /// Compile a file filter.
+    ///
+    /// # Arguments
+    /// - `pattern`: Substring, glob, or regex, depending on `mode`.
+    /// - `mode`: How the pattern is interpreted.
+    ///
+    /// # Errors
+    /// Returns a description of the problem if the glob or regex is invalid.
+    pub fn new(pattern: &str, mode: FileMatch) -> Result<Self, String> {
+        match mode {
+            FileMatch::Substring => Ok(Self::Substring(pattern.to_string())),
+            FileMatch::Glob => {
+                let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+                let glob = |p: &str| {
+                    GlobBuilder::new(p)
+                        .literal_separator(true)
+                        .build()
+                        .map(|g| g.compile_matcher())
+                        .map_err(|e| format!("Invalid glob '{}': {}", pattern, e))
+                };
+                Ok(Self::Glob {
+                    pattern: pattern.to_string(),
+                    anchored: glob(pattern)?,
+                    unanchored: glob(&format!("**/{}", pattern.trim_start_matches('/')))?,
+                })
+            }
+            FileMatch::Regex => Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(|e| format!("Invalid regex '{}': {}", pattern, e)),
+        }
+    }
+
+    /// SQL condition on `file_path` that every matching event satisfies.
+    ///
+    /// # Returns
+    /// The condition and its single parameter.
+    pub fn sql_condition(&self) -> (&'static str, String) {
+        match self {
+            Self::Substring(s) => ("file_path LIKE ?", format!("%{}%", s)),
+            Self::Glob { pattern, .. } => {
+                // The literal run before the first wildcard has no SQL GLOB
+                // metacharacters either
+                let literal: String = pattern
+                    .chars()
+                    .take_while(|c| !GLOB_META.contains(c))
+                    .collect();
+                if Path::new(pattern).is_absolute() {
+                    ("file_path GLOB ?", format!("{}*", literal))
+                } else {
+                    ("file_path GLOB ?", format!("*{}*", literal))
+                }
+            }
+            // Any non-null path
+            Self::Regex(_) => ("file_path GLOB ?", "*".to_string()),
+        }
+    }
+
+    /// Whether rows passing [`Self::sql_condition`] still need [`Self::matches`].
+    pub fn needs_post_filter(&self) -> bool {
+        !matches!(self, Self::Substring(_))
+    }
+
+    /// Check a stored event's path against the filter.
+    ///
+    /// A relative glob matches the path relative to the working directory
+    /// the event was captured in, so `src/*.rs` does not match
+    /// `vendor/src/lib.rs`. Without a recorded working directory it matches
+    /// at any directory boundary.
+    ///
+    /// # Arguments
+    /// - `event`: Stored event; events without a file path never match.
+    pub fn matches(&self, event: &StoredEvent) -> bool {
+        let Some(path) = event.file_path.as_deref() else {
+            return false;
+        };
+        match self {
+            Self::Substring(s) => path.to_lowercase().contains(&s.to_lowercase()),
+            Self::Glob {
+                pattern,
+                anchored,
+                unanchored,
+            } => {
+                if Path::new(pattern).is_absolute() {
+                    return anchored.is_match(path);
+                }
+                let relative = event.cwd().and_then(|cwd| {
+                    Path::new(path)
+                        .strip_prefix(cwd)
+                        .ok()
+                        .map(Path::to_path_buf)
+                });
+                match relative {
+                    Some(relative) => anchored.is_match(relative),
+                    None if Path::new(path).is_absolute() => unanchored.is_match(path),
+                    None => anchored.is_match(path),
+                }
+            }
+            Self::Regex(re) => re.is_match(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diachron_core::Operation;
+
+    fn event(path: &str, cwd: Option<&str>) -> StoredEvent {
+        StoredEvent {
+            id: 1,
+            timestamp: "2024-01-15T10:00:00".to_string(),
+            timestamp_display: None,
+            session_id: None,
+            tool_name: "Edit".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Some(Operation::Modify),
+            diff_summary: None,
+            raw_input: None,
+            ai_summary: None,
+            git_commit_sha: None,
+            metadata: cwd.map(|cwd| serde_json::json!({ "cwd": cwd }).to_string()),
+            command_category: None,
+        }
+    }
+
+    #[test]
+    fn test_glob_star_stays_within_directory() {
+        let filter = PathFilter::new("src/*.rs", FileMatch::Glob).unwrap();
+        assert!(filter.matches(&event("src/main.rs", None)));
+        assert!(!filter.matches(&event("src/sub/x.rs", None)));
+
+        let filter = PathFilter::new("src/**/*.rs", FileMatch::Glob).unwrap();
+        assert!(filter.matches(&event("src/main.rs", None)));
+        assert!(filter.matches(&event("src/sub/x.rs", None)));
+        assert!(!filter.matches(&event("src/sub/x.py", None)));
+    }
+
+    #[test]
+    fn test_relative_glob_is_anchored_at_cwd() {
+        let filter = PathFilter::new("src/*.rs", FileMatch::Glob).unwrap();
+        let proj = Some("/home/me/proj");
+        assert!(filter.matches(&event("/home/me/proj/src/main.rs", proj)));
+        assert!(!filter.matches(&event("/home/me/proj/vendor/src/lib.rs", proj)));
+        // No recorded cwd: match at any directory boundary
+        assert!(filter.matches(&event("/home/me/proj/src/main.rs", None)));
+
+        let absolute = PathFilter::new("/home/me/*/src/*.rs", FileMatch::Glob).unwrap();
+        assert!(absolute.matches(&event("/home/me/proj/src/main.rs", proj)));
+        assert!(!absolute.matches(&event("/tmp/proj/src/main.rs", proj)));
+    }
+
+    #[test]
+    fn test_sql_condition_narrows_by_literal_prefix() {
+        let glob = PathFilter::new("src/*.rs", FileMatch::Glob).unwrap();
+        assert_eq!(
+            glob.sql_condition(),
+            ("file_path GLOB ?", "*src/*".to_string())
+        );
+        let absolute = PathFilter::new("/a/[bc]/*.rs", FileMatch::Glob).unwrap();
+        assert_eq!(absolute.sql_condition().1, "/a/*");
+        assert!(glob.needs_post_filter());
+        assert!(!PathFilter::new("src/", FileMatch::Substring)
+            .unwrap()
+            .needs_post_filter());
+    }
+
+    #[test]
+    fn test_regex_and_invalid_patterns() {
+        let filter = PathFilter::new(r"\.(rs|toml)$", FileMatch::Regex).unwrap();
+        assert!(filter.matches(&event("/p/Cargo.toml", None)));
+        assert!(!filter.matches(&event("/p/README.md", None)));
+
+        assert!(PathFilter::new("src/[", FileMatch::Glob).is_err());
+        assert!(PathFilter::new("(", FileMatch::Regex).is_err());
+    }
+}
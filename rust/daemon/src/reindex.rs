@@ -0,0 +1,816 @@
+//! Vector index rebuilds after an embedding model change.
+//!
+//! Vectors from one model cannot be added to an index built for another
+//! model's dimension. A rebuild moves the old index files aside, swaps in
+//! empty indexes at the engine's dimension, and re-embeds the event and
+//! exchange text stored in the database.
+//!
+//! Index saves and database writes are not atomic, so after an unclean
+//! shutdown an index can lag behind the database or keep entries for rows
+//! that are gone. Startup reconciliation repairs both from the embeddings
+//! stored in the database, without re-embedding anything. An integrity
+//! report measures the same drift without repairing it, and compaction
+//! rebuilds the indexes from the stored embeddings to drop removed vectors.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use diachron_core::{
+    verify_chain, DaemonError, IndexCompaction, IntegrityReport, VectorEntry, VectorError,
+    VectorIndex,
+};
+use tracing::{error, info, warn};
+
+use crate::db::{EventFilter, IndexedTable};
+use crate::handlers::event_embed_text;
+use crate::indexer::exchange_embed_text;
+use crate::DaemonState;
+
+/// Dimension mismatches tolerated before a rebuild is triggered.
+const MISMATCH_REBUILD_THRESHOLD: u64 = 3;
+
+/// Texts embedded per embedding engine checkout.
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// Stored embeddings read and added per batch by a compaction.
+const COMPACT_BATCH_SIZE: usize = 1024;
+
+/// Vectors restored per index by one reconciliation pass. Anything beyond
+/// this is picked up on the next start.
+const MAX_RECONCILE_ADDS: usize = 50_000;
+
+/// Vectors written by a rebuild.
+#[derive(Debug, Clone, Copy)]
+pub struct RebuildStats {
+    /// Dimension of the rebuilt indexes
+    pub dim: usize,
+    pub events_indexed: u64,
+    pub exchanges_indexed: u64,
+}
+
+/// Changes made by a startup reconciliation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileStats {
+    /// Stored embeddings re-added to an index
+    pub restored: u64,
+    /// Index entries dropped because their row no longer exists
+    pub removed: u64,
+    /// Missing vectors left for a later pass because of the per-start cap
+    pub deferred: u64,
+}
+
+/// Clears the in-progress flag when a rebuild finishes or fails.
+struct RebuildGuard<'a>(&'a DaemonState);
+
+impl<'a> RebuildGuard<'a> {
+    fn acquire(state: &'a DaemonState) -> Option<Self> {
+        state
+            .reindexing
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self(state))
+    }
+}
+
+impl Drop for RebuildGuard<'_> {
+    fn drop(&mut self) {
+        self.0.reindexing.store(false, Ordering::Release);
+    }
+}
+
+/// Check whether either vector index disagrees with the embedding engine.
+///
+/// Compares against the length of a real embedding, not the configured
+/// dimension, so a rebuild settles on the model's actual output size.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the engine and indexes.
+///
+/// # Returns
+/// True if an engine is loaded and an index was built for another dimension.
+pub fn indexes_mismatched(state: &DaemonState) -> bool {
+    let Some(dim) = engine_dim(state) else {
+        return false;
+    };
+    [&state.events_index, &state.exchanges_index]
+        .iter()
+        .any(|lock| lock.read().map(|idx| idx.dim() != dim).unwrap_or(false))
+}
+
+/// Record a failed vector index insert.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `err`: Error returned by `VectorIndex::add`.
+pub fn note_add_error(state: &Arc<DaemonState>, err: &VectorError) {
+    if matches!(err, VectorError::DimensionMismatch { .. }) {
+        note_dimension_mismatches(state, 1);
+    }
+}
+
+/// Count vectors rejected for having the wrong dimension.
+///
+/// Once mismatches repeat, a rebuild is spawned in the background so later
+/// inserts land in an index of the right size.
+///
+/// # Arguments
+/// - `state`: Shared daemon state.
+/// - `count`: Number of rejected vectors.
+pub fn note_dimension_mismatches(state: &Arc<DaemonState>, count: u64) {
+    let mismatches = state
+        .dimension_mismatches
+        .fetch_add(count, Ordering::Relaxed)
+        + count;
+    if mismatches < MISMATCH_REBUILD_THRESHOLD || state.reindexing.load(Ordering::Acquire) {
+        return;
+    }
+
+    warn!(
+        "{} vector inserts failed with a dimension mismatch; rebuilding indexes",
+        mismatches
+    );
+    let state = Arc::clone(state);
+    tokio::task::spawn_blocking(move || match rebuild_indexes(&state) {
+        Ok(stats) => info!(
+            "Rebuilt vector indexes at {} dimensions ({} events, {} exchanges)",
+            stats.dim, stats.events_indexed, stats.exchanges_indexed
+        ),
+        Err(e) => error!("Vector index rebuild failed: {}", e),
+    });
+}
+
+/// Rebuild both vector indexes at the embedding engine's dimension.
+///
+/// The previous index files are kept as `*.usearch.bak` / `*.json.bak`;
+/// the rebuild refuses to start while an earlier backup is still there.
+/// Only one rebuild runs at a time.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the engine, database, and indexes.
+///
+/// # Returns
+/// Counts of re-embedded events and exchanges.
+///
+/// # Errors
+/// Returns `anyhow::Error` if a rebuild is already running
+/// ([`DaemonError::Busy`]), no embedding engine is loaded
+/// ([`DaemonError::ModelNotLoaded`]), a backup from an earlier rebuild is in
+/// the way, or the database or index files cannot be accessed.
+pub fn rebuild_indexes(state: &DaemonState) -> anyhow::Result<RebuildStats> {
+    let _guard = RebuildGuard::acquire(state)
+        .ok_or_else(|| DaemonError::Busy("A vector index rebuild is already running".into()))?;
+    let dim = engine_dim(state).ok_or_else(|| {
+        DaemonError::ModelNotLoaded(
+            "Embedding engine unavailable; cannot re-embed stored text".into(),
+        )
+    })?;
+
+    let indexes_dir = state.indexes_path();
+    let names = ["events", "exchanges"];
+    // Check every backup before moving anything, so a refusal changes nothing
+    for name in names {
+        if let Some(backup) = existing_backup(&indexes_dir.join(name)) {
+            return Err(anyhow!(
+                "{} is left from an earlier rebuild; move it away to rebuild again",
+                backup.display()
+            ));
+        }
+    }
+    for (name, lock) in names
+        .into_iter()
+        .zip([&state.events_index, &state.exchanges_index])
+    {
+        move_aside(&indexes_dir.join(name))?;
+        let mut idx = lock
+            .write()
+            .map_err(|_| anyhow!("{} index lock poisoned", name))?;
+        *idx = VectorIndex::new(dim, None)?;
+    }
+    state.dimension_mismatches.store(0, Ordering::Relaxed);
+    info!("Rebuilding vector indexes at {} dimensions", dim);
+
+    let event_count = state.db.event_count()?;
+    let events: Vec<(String, String, Option<String>)> = state
+        .db
+        .query_events(&EventFilter::default(), event_count as usize)?
+        .into_iter()
+        .map(|event| {
+            let text = event_embed_text(
+                &event.tool_name,
+                event.file_path.as_deref(),
+                event.operation.map_or("unknown", |op| op.as_str()),
+                event.diff_summary.as_deref(),
+                event.raw_input.as_deref(),
+            );
+            (format!("event:{}", event.id), text, event.file_path)
+        })
+        .collect();
+    let events_indexed = embed_into(state, &state.events_index, &events)?;
+
+    let exchanges: Vec<(String, String, Option<String>)> = state
+        .db
+        .get_exchange_messages()?
+        .into_iter()
+        .map(|(id, user, assistant, project)| {
+            (
+                format!("exchange:{}", id),
+                exchange_embed_text(&user, &assistant),
+                project,
+            )
+        })
+        .collect();
+    let exchanges_indexed = embed_into(state, &state.exchanges_index, &exchanges)?;
+
+    state.save_indexes()?;
+    if let Ok(mut cache) = state.search_cache.write() {
+        cache.clear();
+    }
+
+    Ok(RebuildStats {
+        dim,
+        events_indexed,
+        exchanges_indexed,
+    })
+}
+
+/// Rebuild both vector indexes from the embeddings stored in the database.
+///
+/// usearch leaves removed vectors in place, so an index with many deletes
+/// keeps growing. Compaction adds the stored embeddings to a fresh index
+/// with the same settings, without re-embedding. The new index is built
+/// while the old one keeps serving; under the write lock, rows saved in the
+/// meantime are added, the files are written next to the old ones and
+/// renamed over them, and the index is swapped in memory.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the database and indexes.
+///
+/// # Returns
+/// Vector counts and file sizes of each index before and after.
+///
+/// # Errors
+/// Returns `anyhow::Error` if a rebuild is already running
+/// ([`DaemonError::Busy`]), or the database or index files cannot be
+/// accessed.
+pub fn compact_indexes(state: &DaemonState) -> anyhow::Result<(IndexCompaction, IndexCompaction)> {
+    let _guard = RebuildGuard::acquire(state)
+        .ok_or_else(|| DaemonError::Busy("A vector index rebuild is already running".into()))?;
+
+    let indexes_dir = state.indexes_path();
+    let staging = indexes_dir.join("compact");
+    let mut results = Vec::with_capacity(2);
+    for (name, table, lock) in [
+        ("events", IndexedTable::Events, &state.events_index),
+        ("exchanges", IndexedTable::Exchanges, &state.exchanges_index),
+    ] {
+        let base = indexes_dir.join(name);
+        let mut stats = IndexCompaction {
+            bytes_before: index_files_size(&base),
+            ..Default::default()
+        };
+        let mut fresh = {
+            let idx = lock
+                .read()
+                .map_err(|_| anyhow!("{} index lock poisoned", name))?;
+            stats.vectors_before = idx.len() as u64;
+            VectorIndex::with_options(
+                idx.dim(),
+                idx.metric(),
+                idx.hnsw_config(),
+                idx.quantization(),
+            )?
+        };
+
+        let prefix = table.key_prefix();
+        let mut batch = Vec::with_capacity(COMPACT_BATCH_SIZE);
+        let mut add_error = None;
+        stats.corrupt = state.db.for_each_stored_embedding(
+            table,
+            COMPACT_BATCH_SIZE,
+            |id, embedding, metadata| {
+                if add_error.is_some() {
+                    return;
+                }
+                if embedding.len() != fresh.dim() {
+                    stats.wrong_dim += 1;
+                    return;
+                }
+                batch.push((format!("{}{}", prefix, id), embedding, metadata));
+                if batch.len() == COMPACT_BATCH_SIZE {
+                    if let Err(e) = fresh.add_batch(&batch) {
+                        add_error = Some(e);
+                    }
+                    batch.clear();
+                }
+            },
+        )?;
+        if let Some(e) = add_error {
+            return Err(e.into());
+        }
+        fresh.add_batch(&batch)?;
+
+        let mut idx = lock
+            .write()
+            .map_err(|_| anyhow!("{} index lock poisoned", name))?;
+        // Rows saved while the new index was being built
+        let missing: Vec<String> = state
+            .db
+            .indexable_rows(table)?
+            .into_iter()
+            .filter(|(id, indexable)| *indexable && !fresh.contains(&format!("{}{}", prefix, id)))
+            .map(|(id, _)| id)
+            .collect();
+        let missing: Vec<&str> = missing.iter().map(String::as_str).collect();
+        let catch_up: Vec<VectorEntry> = state
+            .db
+            .stored_embeddings(table, &missing)?
+            .into_iter()
+            .filter(|(_, embedding, _)| embedding.len() == fresh.dim())
+            .map(|(id, embedding, metadata)| (format!("{}{}", prefix, id), embedding, metadata))
+            .collect();
+        fresh.add_batch(&catch_up)?;
+
+        // As with saves, an index that was never written stays unwritten
+        if !fresh.is_empty() || VectorIndex::exists(&base) {
+            let staged = staging.join(name);
+            fresh.save(&staged)?;
+            for ext in ["usearch", "json"] {
+                std::fs::rename(staged.with_extension(ext), base.with_extension(ext))?;
+            }
+        }
+        stats.vectors_after = fresh.len() as u64;
+        stats.bytes_after = index_files_size(&base);
+        *idx = fresh;
+        drop(idx);
+
+        info!(
+            "Compacted {} index: {} -> {} vectors, {} -> {} bytes",
+            name, stats.vectors_before, stats.vectors_after, stats.bytes_before, stats.bytes_after
+        );
+        if stats.corrupt > 0 {
+            warn!(
+                "Skipped {} {} with a corrupt stored embedding",
+                stats.corrupt, name
+            );
+        }
+        results.push(stats);
+    }
+    std::fs::remove_dir(&staging).ok();
+
+    if let Ok(mut cache) = state.search_cache.write() {
+        cache.clear();
+    }
+    let exchanges = results.pop().unwrap_or_default();
+    let events = results.pop().unwrap_or_default();
+    Ok((events, exchanges))
+}
+
+/// Bring both vector indexes back in line with the database.
+///
+/// Rows with a stored embedding that are missing from their index are
+/// re-added from the stored blob, at most [`MAX_RECONCILE_ADDS`] per index.
+/// Index entries whose row was deleted are removed. Stored embeddings of
+/// another dimension than the index are skipped; a rebuild handles those.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the database and indexes.
+///
+/// # Returns
+/// Counts of restored, removed, and deferred vectors.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the database cannot be read or an index lock
+/// is poisoned.
+pub fn reconcile_indexes(state: &DaemonState) -> anyhow::Result<ReconcileStats> {
+    let mut stats = ReconcileStats::default();
+    for (name, table, lock) in [
+        ("events", IndexedTable::Events, &state.events_index),
+        ("exchanges", IndexedTable::Exchanges, &state.exchanges_index),
+    ] {
+        let prefix = table.key_prefix();
+        let rows = state.db.indexable_rows(table)?;
+        let mut idx = lock
+            .write()
+            .map_err(|_| anyhow!("{} index lock poisoned", name))?;
+
+        let existing: HashSet<&str> = rows.iter().map(|(id, _)| id.as_str()).collect();
+        let stale: Vec<String> = idx
+            .ids()
+            .filter(|key| {
+                key.strip_prefix(prefix)
+                    .is_some_and(|id| !existing.contains(id))
+            })
+            .map(str::to_string)
+            .collect();
+        for key in &stale {
+            if idx.remove(key)? {
+                stats.removed += 1;
+            }
+        }
+
+        let missing: Vec<&str> = rows
+            .iter()
+            .filter(|(id, indexable)| *indexable && !idx.contains(&format!("{}{}", prefix, id)))
+            .map(|(id, _)| id.as_str())
+            .collect();
+        let restore = &missing[..missing.len().min(MAX_RECONCILE_ADDS)];
+        stats.deferred += (missing.len() - restore.len()) as u64;
+
+        let vectors: Vec<VectorEntry> = state
+            .db
+            .stored_embeddings(table, restore)?
+            .into_iter()
+            .map(|(id, embedding, metadata)| (format!("{}{}", prefix, id), embedding, metadata))
+            .collect();
+        stats.restored += idx.add_batch(&vectors)? as u64;
+    }
+
+    if stats.deferred > 0 {
+        warn!(
+            "{} stored embeddings are still missing from the vector indexes; \
+             they will be restored on the next start",
+            stats.deferred
+        );
+    }
+    Ok(stats)
+}
+
+/// Report how far the vector indexes, full-text indexes, and hash chain
+/// have drifted from the database, without changing anything.
+///
+/// # Arguments
+/// - `state`: Daemon state holding the database and indexes.
+///
+/// # Returns
+/// Per-table counts plus a full hash-chain verification.
+///
+/// # Errors
+/// Returns `anyhow::Error` if the database cannot be read or an index lock
+/// is poisoned.
+pub fn integrity_report(state: &DaemonState) -> anyhow::Result<IntegrityReport> {
+    let mut tables = Vec::with_capacity(2);
+    for (name, table, lock) in [
+        ("events", IndexedTable::Events, &state.events_index),
+        ("exchanges", IndexedTable::Exchanges, &state.exchanges_index),
+    ] {
+        let prefix = table.key_prefix();
+        let mut counts = state.db.integrity_counts(table)?;
+        let rows = state.db.indexable_rows(table)?;
+        let unembedded = state.db.unembedded_rows(table)?;
+        let idx = lock
+            .read()
+            .map_err(|_| anyhow!("{} index lock poisoned", name))?;
+        let indexed = |id: &str| idx.contains(&format!("{}{}", prefix, id));
+
+        let existing: HashSet<&str> = rows.iter().map(|(id, _)| id.as_str()).collect();
+        counts.orphaned_vectors = idx
+            .ids()
+            .filter(|key| {
+                key.strip_prefix(prefix)
+                    .is_some_and(|id| !existing.contains(id))
+            })
+            .count() as u64;
+        counts.missing_from_index = rows
+            .iter()
+            .filter(|(id, indexable)| *indexable && !indexed(id))
+            .count() as u64;
+        // A rebuild embeds stored text without saving the embedding, so
+        // only rows that are also absent from the index are unsearchable
+        counts.without_embedding = unembedded.iter().filter(|id| !indexed(id)).count() as u64;
+        tables.push(counts);
+    }
+    let exchanges = tables.pop().unwrap_or_default();
+    let events = tables.pop().unwrap_or_default();
+
+    Ok(IntegrityReport {
+        events,
+        exchanges,
+        events_without_hash: state.db.events_without_hash()?,
+        chain: verify_chain(&*state.db.read()?)?,
+    })
+}
+
+fn engine_dim(state: &DaemonState) -> Option<usize> {
+    state.embedding_engines.dim()
+}
+
+/// Combined size of an index's `.usearch` and `.json` files (0 if missing).
+fn index_files_size(base: &Path) -> u64 {
+    ["usearch", "json"]
+        .iter()
+        .filter_map(|ext| std::fs::metadata(base.with_extension(ext)).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// First `*.bak` file of an index that exists, if any.
+fn existing_backup(base: &Path) -> Option<PathBuf> {
+    ["usearch", "json"]
+        .iter()
+        .map(|ext| base.with_extension(format!("{}.bak", ext)))
+        .find(|path| path.exists())
+}
+
+/// Rename an index's files to `*.bak`.
+///
+/// # Errors
+/// Returns `std::io::Error` with kind `AlreadyExists` rather than replace a
+/// backup, or if a rename fails.
+fn move_aside(base: &Path) -> std::io::Result<()> {
+    if let Some(backup) = existing_backup(base) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", backup.display()),
+        ));
+    }
+    for ext in ["usearch", "json"] {
+        let path = base.with_extension(ext);
+        if path.exists() {
+            std::fs::rename(&path, base.with_extension(format!("{}.bak", ext)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Embed texts in batches and add them to an index.
+///
+/// Items are (key, text, metadata); batches that fail to embed are logged
+/// and skipped.
+fn embed_into(
+    state: &DaemonState,
+    index: &RwLock<VectorIndex>,
+    items: &[(String, String, Option<String>)],
+) -> anyhow::Result<u64> {
+    let mut indexed = 0u64;
+    for chunk in items.chunks(EMBED_BATCH_SIZE) {
+        let texts: Vec<&str> = chunk.iter().map(|(_, text, _)| text.as_str()).collect();
+        let embeddings = {
+            let mut engine = state.embedding_engines.get().ok_or_else(|| {
+                DaemonError::ModelNotLoaded("Embedding engine unavailable during rebuild".into())
+            })?;
+            match engine.embed_batch(&texts) {
+                Ok(embeddings) => embeddings,
+                Err(e) => {
+                    warn!(
+                        "Failed to embed {} items during rebuild: {}",
+                        chunk.len(),
+                        e
+                    );
+                    continue;
+                }
+            }
+        };
+
+        let batch: Vec<VectorEntry> = chunk
+            .iter()
+            .zip(embeddings)
+            .map(|((id, _, metadata), embedding)| (id.clone(), embedding, metadata.clone()))
+            .collect();
+        let mut idx = index
+            .write()
+            .map_err(|_| anyhow!("Vector index lock poisoned"))?;
+        indexed += idx.add_batch(&batch)? as u64;
+    }
+    Ok(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::temp_dir;
+    use diachron_core::{CaptureEvent, Exchange, Operation};
+
+    fn embedding(dim: usize, hot: usize) -> Vec<f32> {
+        let mut v = vec![0.0; dim];
+        v[hot % dim] = 1.0;
+        v
+    }
+
+    #[test]
+    fn test_reconcile_restores_desynced_indexes() {
+        let dir = temp_dir();
+        let state = DaemonState::new_for_tests(dir.join("diachron.db")).unwrap();
+        let dim = state.config.embedding.dim;
+
+        // Events written to the database whose index save never happened
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let event = CaptureEvent {
+                tool_name: "Edit".to_string(),
+                file_path: Some(format!("src/file_{}.rs", i)),
+                operation: Operation::Modify,
+                ..Default::default()
+            };
+            // The last event was captured without an embedding
+            let emb = (i < 3).then(|| embedding(dim, i));
+            ids.push(
+                state
+                    .db
+                    .save_event(&event, None, emb.as_deref(), None)
+                    .unwrap(),
+            );
+        }
+        let exchange = Exchange {
+            id: "ex-1".to_string(),
+            timestamp: "2026-01-10T12:00:00Z".to_string(),
+            project: None,
+            session_id: None,
+            user_message: "How do I reconcile?".to_string(),
+            assistant_message: "Re-add the stored vectors.".to_string(),
+            tool_calls: None,
+            archive_path: None,
+            line_start: None,
+            line_end: None,
+            embedding: None,
+            summary: None,
+            git_branch: None,
+            cwd: None,
+        };
+        state
+            .db
+            .save_exchange(&exchange, Some(&embedding(dim, 7)))
+            .unwrap();
+
+        {
+            let mut idx = state.events_index.write().unwrap();
+            idx.add(&format!("event:{}", ids[0]), &embedding(dim, 0), None)
+                .unwrap();
+            // Left over from a row that was deleted
+            idx.add("event:999999", &embedding(dim, 9), None).unwrap();
+        }
+
+        let stats = reconcile_indexes(&state).unwrap();
+        assert_eq!(
+            stats,
+            ReconcileStats {
+                restored: 3,
+                removed: 1,
+                deferred: 0
+            }
+        );
+
+        let events = state.events_index.read().unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(ids[..3]
+            .iter()
+            .all(|id| events.contains(&format!("event:{}", id))));
+        assert!(!events.contains("event:999999"));
+        drop(events);
+        assert!(state
+            .exchanges_index
+            .read()
+            .unwrap()
+            .contains("exchange:ex-1"));
+
+        // A second pass finds nothing to do
+        assert_eq!(
+            reconcile_indexes(&state).unwrap(),
+            ReconcileStats::default()
+        );
+    }
+
+    #[test]
+    fn test_integrity_report_flags_unindexed_embedding() {
+        let dir = temp_dir();
+        let state = DaemonState::new_for_tests(dir.join("diachron.db")).unwrap();
+        let dim = state.config.embedding.dim;
+
+        let event = |path: &str| CaptureEvent {
+            tool_name: "Edit".to_string(),
+            file_path: Some(path.to_string()),
+            operation: Operation::Modify,
+            ..Default::default()
+        };
+        let indexed = state
+            .db
+            .save_event(&event("src/a.rs"), None, Some(&embedding(dim, 0)), None)
+            .unwrap();
+        state
+            .events_index
+            .write()
+            .unwrap()
+            .add(&format!("event:{}", indexed), &embedding(dim, 0), None)
+            .unwrap();
+        let report = integrity_report(&state).unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+
+        // Embedded and saved, but its vector never reached the index
+        state
+            .db
+            .save_event(&event("src/b.rs"), None, Some(&embedding(dim, 1)), None)
+            .unwrap();
+        // Captured while the model was unavailable
+        state
+            .db
+            .save_event(&event("src/c.rs"), None, None, None)
+            .unwrap();
+
+        let report = integrity_report(&state).unwrap();
+        assert_eq!(report.events.rows, 3);
+        assert_eq!(report.events.missing_from_index, 1);
+        assert_eq!(report.events.without_embedding, 1);
+        assert_eq!(report.events.orphaned_vectors, 0);
+        assert!(!report.events.fts_stale());
+        assert!(report.chain.valid);
+        assert_eq!(report.events_without_hash, 0);
+        assert!(report.needs_reindex());
+        assert!(!report.is_clean());
+
+        // The check leaves the index alone
+        assert_eq!(state.events_index.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_compact_drops_removed_vectors_and_skips_corrupt_blobs() {
+        let dir = temp_dir();
+        let state = DaemonState::new_for_tests(dir.join("diachron.db")).unwrap();
+        let dim = state.config.embedding.dim;
+
+        let mut ids = Vec::new();
+        for i in 0..6 {
+            let event = CaptureEvent {
+                tool_name: "Edit".to_string(),
+                file_path: Some(format!("src/file_{}.rs", i)),
+                operation: Operation::Modify,
+                ..Default::default()
+            };
+            let emb = embedding(dim, i);
+            let id = state.db.save_event(&event, None, Some(&emb), None).unwrap();
+            state
+                .events_index
+                .write()
+                .unwrap()
+                .add(&format!("event:{}", id), &emb, None)
+                .unwrap();
+            ids.push(id);
+        }
+        // Deleted rows leave removed vectors behind in the index
+        {
+            let mut idx = state.events_index.write().unwrap();
+            for id in &ids[..2] {
+                idx.remove(&format!("event:{}", id)).unwrap();
+            }
+        }
+        state
+            .db
+            .with_conn(|conn| {
+                conn.execute(
+                    "DELETE FROM events WHERE id IN (?1, ?2)",
+                    rusqlite::params![ids[0], ids[1]],
+                )?;
+                // A truncated blob that cannot be decoded
+                conn.execute(
+                    "UPDATE events SET embedding = X'0000803F00' WHERE id = ?1",
+                    [ids[5]],
+                )
+            })
+            .unwrap();
+        state.save_indexes().unwrap();
+
+        let (events, exchanges) = compact_indexes(&state).unwrap();
+        assert_eq!(events.vectors_before, 4);
+        assert_eq!(events.vectors_after, 3);
+        assert_eq!(events.corrupt, 1);
+        assert_eq!(events.wrong_dim, 0);
+        assert!(events.bytes_before > 0 && events.bytes_after > 0);
+        assert_eq!(exchanges, IndexCompaction::default());
+
+        let idx = state.events_index.read().unwrap();
+        assert_eq!(idx.len(), 3);
+        assert!(ids[2..5]
+            .iter()
+            .all(|id| idx.contains(&format!("event:{}", id))));
+        drop(idx);
+
+        // The compacted files are what loads next time
+        let loaded = VectorIndex::load(&state.indexes_path().join("events")).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert!(!state.indexes_path().join("compact").exists());
+    }
+
+    #[test]
+    fn test_move_aside_never_replaces_a_backup() {
+        let dir = temp_dir();
+        let base = dir.join("events");
+
+        std::fs::write(base.with_extension("usearch"), "first").unwrap();
+        move_aside(&base).unwrap();
+        assert!(!base.with_extension("usearch").exists());
+        assert_eq!(
+            existing_backup(&base),
+            Some(base.with_extension("usearch.bak"))
+        );
+
+        std::fs::write(base.with_extension("usearch"), "second").unwrap();
+        let err = move_aside(&base).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            std::fs::read_to_string(base.with_extension("usearch.bak")).unwrap(),
+            "first"
+        );
+        assert!(base.with_extension("usearch").exists());
+    }
+}
@@ -1,20 +1,66 @@
-//! Unix socket server for the daemon
+//! Socket server for the daemon (Unix socket or Windows named pipe, plus
+//! optional TCP)
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
-use tokio::net::UnixListener;
-use tracing::{error, info};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{error, info, warn};
 
-use crate::{background, handle_client, DaemonState};
+use crate::{background, capture, handle_client, metrics, DaemonState};
+
+/// Environment variable enabling the TCP listener (e.g. `127.0.0.1:7700`)
+pub const LISTEN_ENV_VAR: &str = "DIACHRON_LISTEN";
 
 /// Run the daemon server
+///
+/// The local transport (Unix socket, or named pipe on Windows) is always
+/// attempted; a TCP listener is added when `DIACHRON_LISTEN` is set. A bind
+/// failure on one transport is logged and the other keeps serving; only if
+/// neither binds does this return an error.
 pub async fn run(state: Arc<DaemonState>) -> Result<()> {
-    let socket_path = state.socket_path();
+    // Create local listener
+    let mut local_listener = match LocalListener::bind(&state) {
+        Ok(listener) => {
+            info!("Listening on {}", listener.describe());
+            Some(listener)
+        }
+        Err(e) => {
+            error!("Failed to bind local IPC endpoint: {}", e);
+            None
+        }
+    };
+
+    // Create optional TCP listener
+    let tcp_listener = match std::env::var(LISTEN_ENV_VAR) {
+        Ok(addr) if !addr.is_empty() => match TcpListener::bind(&addr).await {
+            Ok(listener) => {
+                info!("Listening on tcp://{}", addr);
+                Some(listener)
+            }
+            Err(e) => {
+                error!("Failed to bind TCP listener {}: {}", addr, e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    if local_listener.is_none() && tcp_listener.is_none() {
+        anyhow::bail!("No IPC transport could be bound");
+    }
+    if tcp_listener.is_some() {
+        warn!("TCP listener has no authentication; bind it to a trusted interface only");
+    }
 
-    // Create Unix socket listener
-    let listener = UnixListener::bind(&socket_path)?;
-    info!("Listening on {:?}", socket_path);
+    // Start the batched capture writer
+    capture::spawn_writer(Arc::clone(&state), capture::embed_with_engine);
+
+    // Start the metrics endpoint if `[metrics] port` is configured
+    metrics::spawn_server(Arc::clone(&state)).await;
 
     // Start background indexing task
     let bg_state = Arc::clone(&state);
@@ -22,6 +68,24 @@ pub async fn run(state: Arc<DaemonState>) -> Result<()> {
         background::background_indexing_task(bg_state).await;
     });
 
+    // Save changed vector indexes periodically so a killed daemon loses little
+    let autosave_secs = state.config.index.autosave_secs;
+    if autosave_secs > 0 {
+        tokio::spawn(background::autosave_task(
+            Arc::clone(&state),
+            Duration::from_secs(autosave_secs),
+        ));
+    }
+
+    // Checkpoint the hash chain daily, signed when a device key exists
+    tokio::spawn(background::checkpoint_task(
+        Arc::clone(&state),
+        background::CHECKPOINT_INTERVAL,
+    ));
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
     // Accept connections
     loop {
         if state.should_shutdown() {
@@ -30,9 +94,9 @@ pub async fn run(state: Arc<DaemonState>) -> Result<()> {
         }
 
         tokio::select! {
-            result = listener.accept() => {
+            result = accept_local(local_listener.as_mut()) => {
                 match result {
-                    Ok((stream, _addr)) => {
+                    Ok(stream) => {
                         let state = Arc::clone(&state);
                         tokio::spawn(async move {
                             if let Err(e) = handle_client(stream, state).await {
@@ -45,19 +109,157 @@ pub async fn run(state: Arc<DaemonState>) -> Result<()> {
                     }
                 }
             }
-            _ = tokio::signal::ctrl_c() => {
-                info!("Received Ctrl+C, shutting down");
+            result = accept_tcp(tcp_listener.as_ref()) => {
+                match result {
+                    Ok(stream) => {
+                        let state = Arc::clone(&state);
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_client(stream, state).await {
+                                error!("TCP client error: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        error!("TCP accept error: {}", e);
+                    }
+                }
+            }
+            signal = &mut shutdown => {
+                info!("Received {}, shutting down", signal);
                 state.request_shutdown();
                 break;
             }
         }
     }
 
+    // Write queued captures and any unsaved vectors before exiting
+    state.capture_queue.flush().await;
+    if let Err(e) = state.save_indexes() {
+        error!("Failed to save indexes on shutdown: {}", e);
+    }
+
     // Cleanup
-    if socket_path.exists() {
-        std::fs::remove_file(&socket_path)?;
+    if let Some(listener) = local_listener {
+        listener.close()?;
     }
 
     info!("Daemon stopped");
     Ok(())
 }
+
+/// Wait for Ctrl+C or, on Unix, SIGTERM (sent by launchd and systemd on stop)
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "Ctrl+C",
+                _ = sigterm.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                warn!("Failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "Ctrl+C"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+/// Accept on the local listener, or wait forever if it isn't bound
+async fn accept_local(listener: Option<&mut LocalListener>) -> std::io::Result<LocalStream> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Accept on the TCP listener, or wait forever if it isn't bound
+async fn accept_tcp(listener: Option<&TcpListener>) -> std::io::Result<TcpStream> {
+    match listener {
+        Some(listener) => listener.accept().await.map(|(stream, _)| stream),
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(unix)]
+type LocalStream = UnixStream;
+
+/// Unix socket listener that removes its socket file on close
+#[cfg(unix)]
+struct LocalListener {
+    listener: UnixListener,
+    path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl LocalListener {
+    fn bind(state: &DaemonState) -> std::io::Result<Self> {
+        let path = state.socket_path();
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self { listener, path })
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.path)
+    }
+
+    async fn accept(&mut self) -> std::io::Result<LocalStream> {
+        self.listener.accept().await.map(|(stream, _)| stream)
+    }
+
+    fn close(self) -> std::io::Result<()> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+type LocalStream = tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// Named pipe listener; always keeps one instance waiting for a client
+#[cfg(windows)]
+struct LocalListener {
+    name: String,
+    next: LocalStream,
+}
+
+#[cfg(windows)]
+impl LocalListener {
+    fn bind(_state: &DaemonState) -> std::io::Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let name = diachron_core::pipe_name();
+        // Refuse to share the name with another running daemon
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)?;
+        Ok(Self { name, next })
+    }
+
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn accept(&mut self) -> std::io::Result<LocalStream> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        self.next.connect().await?;
+        // Create the next instance before handing this one off, so clients
+        // never find the pipe missing
+        let next = ServerOptions::new().create(&self.name)?;
+        Ok(std::mem::replace(&mut self.next, next))
+    }
+
+    fn close(self) -> std::io::Result<()> {
+        // Pipe instances disappear with their handles
+        Ok(())
+    }
+}
@@ -1,23 +1,43 @@
-//! Conversation summarization via Anthropic API
+//! Conversation summarization via a configurable LLM provider
 //!
 //! Generates concise summaries of conversation exchanges for better
 //! semantic search and retrieval.
 //!
-//! Credential resolution order:
+//! The provider is chosen by `summarization.provider` in
+//! `~/.diachron/config.toml`:
+//! - `anthropic` (default): Anthropic Messages API
+//! - `openai`: OpenAI Chat Completions API (or a compatible server via `base_url`)
+//! - `ollama`: local Ollama server, `http://localhost:11434` by default
+//!
+//! API key resolution order (hosted providers):
 //! 1. Config file `api_key` (if set in ~/.diachron/config.toml)
-//! 2. `ANTHROPIC_API_KEY` environment variable
-//! 3. Claude Code's internal credentials (future)
+//! 2. `ANTHROPIC_API_KEY` or `OPENAI_API_KEY` environment variable
+//! 3. Claude Code's internal credentials (future, Anthropic only)
 
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, warn};
 
 /// Default model for summarization (fast + cheap)
 const DEFAULT_MODEL: &str = "claude-3-haiku-20240307";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.2";
 const MAX_TOKENS: u32 = 300;
 const API_BASE_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// How long to wait for Ollama when checking that it is running
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_RETRIES: u32 = 3;
 
 #[derive(Error, Debug)]
 pub enum SummarizationError {
@@ -25,41 +45,130 @@ pub enum SummarizationError {
     NoApiKey,
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
+    #[error("HTTP {status}: {body}")]
+    Status { status: u16, body: String },
     #[error("API error: {0}")]
     Api(String),
     #[error("Config error: {0}")]
     Config(String),
 }
 
+impl SummarizationError {
+    /// Whether the request may succeed if sent again.
+    ///
+    /// Timeouts, connection failures, rate limits (429), and server errors
+    /// (5xx) are transient; other client errors (4xx), a missing key, and
+    /// malformed responses are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SummarizationError::Http(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.status().is_some_and(|status| status.is_server_error())
+            }
+            SummarizationError::Status { status, .. } => *status == 429 || *status >= 500,
+            SummarizationError::NoApiKey
+            | SummarizationError::Api(_)
+            | SummarizationError::Config(_) => false,
+        }
+    }
+}
+
+/// Delay before a retry, doubling per attempt up to [`RETRY_MAX_DELAY`].
+///
+/// # Arguments
+/// - `attempt`: Retry number, starting at 1.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY)
+}
+
+/// LLM service that writes the summaries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Anthropic,
+    #[serde(alias = "open_ai")]
+    OpenAi,
+    Ollama,
+}
+
+impl ProviderKind {
+    /// Model used when the config does not name one.
+    fn default_model(self) -> &'static str {
+        match self {
+            ProviderKind::Anthropic => DEFAULT_MODEL,
+            ProviderKind::OpenAi => DEFAULT_OPENAI_MODEL,
+            ProviderKind::Ollama => DEFAULT_OLLAMA_MODEL,
+        }
+    }
+
+    /// Environment variable holding the API key, for hosted providers.
+    fn api_key_env(self) -> Option<&'static str> {
+        match self {
+            ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            ProviderKind::OpenAi => Some("OPENAI_API_KEY"),
+            ProviderKind::Ollama => None,
+        }
+    }
+}
+
 /// Configuration for summarization
 #[derive(Debug, Clone, Deserialize)]
 pub struct SummarizationConfig {
+    /// Provider to summarize with (default: anthropic)
+    #[serde(default)]
+    pub provider: ProviderKind,
     /// API key override (optional)
+    #[serde(alias = "anthropic_api_key")]
     pub api_key: Option<String>,
-    /// Model to use (default: claude-3-haiku)
-    #[serde(default = "default_model")]
-    pub model: String,
+    /// Model to use (default depends on the provider; claude-3-haiku for Anthropic)
+    pub model: Option<String>,
+    /// Endpoint override, e.g. a remote Ollama host or an OpenAI-compatible server
+    pub base_url: Option<String>,
     /// Max tokens for summary (default: 300)
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
     /// Whether summarization is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Retries per exchange after a transient error (default: 3)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
 }
 
 impl Default for SummarizationConfig {
     fn default() -> Self {
         Self {
+            provider: ProviderKind::default(),
             api_key: None,
-            model: default_model(),
+            model: None,
+            base_url: None,
             max_tokens: default_max_tokens(),
             enabled: default_enabled(),
+            max_retries: default_max_retries(),
         }
     }
 }
 
-fn default_model() -> String {
-    DEFAULT_MODEL.to_string()
+impl SummarizationConfig {
+    /// Model to request, falling back to the provider's default.
+    pub fn model(&self) -> &str {
+        self.model
+            .as_deref()
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| self.provider.default_model())
+    }
+
+    /// Base URL with any trailing slash removed, or `default` if unset.
+    fn base_url_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.base_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .unwrap_or(default)
+            .trim_end_matches('/')
+    }
 }
 
 fn default_max_tokens() -> u32 {
@@ -70,12 +179,69 @@ fn default_enabled() -> bool {
     true
 }
 
-/// Anthropic API request structure
-#[derive(Serialize)]
-struct ApiRequest {
-    model: String,
-    max_tokens: u32,
-    messages: Vec<ApiMessage>,
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+/// A service that can summarize one conversation exchange.
+pub trait SummaryProvider: Send + Sync {
+    /// Summarize an exchange in 1-2 sentences.
+    ///
+    /// # Errors
+    /// Returns `SummarizationError` if the request fails or the response
+    /// holds no text.
+    fn summarize(&self, user: &str, assistant: &str) -> Result<String, SummarizationError>;
+
+    /// Whether the provider has what it needs to send requests (an API key
+    /// for hosted providers). Never touches the network.
+    fn is_configured(&self) -> bool;
+
+    /// Whether the provider can take requests right now.
+    ///
+    /// Defaults to [`Self::is_configured`]; a local server is probed, so
+    /// this may block on the network.
+    fn is_ready(&self) -> bool {
+        self.is_configured()
+    }
+
+    /// Provider name for logs.
+    fn name(&self) -> &'static str;
+}
+
+/// Build the summarization prompt, truncating long messages to stay within
+/// context limits.
+fn build_prompt(user_message: &str, assistant_message: &str) -> String {
+    let user_truncated = truncate_to_chars(user_message, 2000);
+    let assistant_truncated = truncate_to_chars(assistant_message, 2000);
+
+    format!(
+        "Summarize this Claude Code conversation exchange in 1-2 concise sentences. \
+        Focus on what was accomplished or discussed.\n\n\
+        User: {}\n\n\
+        Assistant: {}",
+        user_truncated, assistant_truncated
+    )
+}
+
+/// Fail on a non-success HTTP status, keeping the body for the error message.
+fn check_status(
+    response: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response, SummarizationError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status().as_u16();
+    let body = response.text().unwrap_or_default();
+    Err(SummarizationError::Status { status, body })
+}
+
+/// Reject empty summaries and trim surrounding whitespace.
+fn non_empty(summary: String) -> Result<String, SummarizationError> {
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err(SummarizationError::Api("Empty response".to_string()));
+    }
+    Ok(summary.to_string())
 }
 
 #[derive(Serialize)]
@@ -84,6 +250,14 @@ struct ApiMessage {
     content: String,
 }
 
+/// Anthropic API request structure
+#[derive(Serialize)]
+struct ApiRequest {
+    model: String,
+    max_tokens: u32,
+    messages: Vec<ApiMessage>,
+}
+
 /// Anthropic API response structure
 #[derive(Deserialize)]
 struct ApiResponse {
@@ -104,33 +278,305 @@ struct ApiError {
     message: String,
 }
 
+/// Summaries from Anthropic's Messages API.
+pub struct AnthropicProvider {
+    client: reqwest::blocking::Client,
+    api_key: Option<String>,
+    model: String,
+    max_tokens: u32,
+    url: String,
+}
+
+impl AnthropicProvider {
+    /// Create a provider from config, resolving the API key.
+    pub fn new(client: reqwest::blocking::Client, config: &SummarizationConfig) -> Self {
+        Self {
+            client,
+            api_key: resolve_api_key(config),
+            model: config.model().to_string(),
+            max_tokens: config.max_tokens,
+            url: config.base_url_or(API_BASE_URL).to_string(),
+        }
+    }
+}
+
+impl SummaryProvider for AnthropicProvider {
+    fn summarize(&self, user: &str, assistant: &str) -> Result<String, SummarizationError> {
+        let api_key = self.api_key.as_ref().ok_or(SummarizationError::NoApiKey)?;
+
+        let request = ApiRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            messages: vec![ApiMessage {
+                role: "user".to_string(),
+                content: build_prompt(user, assistant),
+            }],
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .json(&request)
+            .send()?;
+        let api_response: ApiResponse = check_status(response)?.json()?;
+
+        if let Some(error) = api_response.error {
+            return Err(SummarizationError::Api(error.message));
+        }
+
+        // Extract text from response
+        let summary = api_response
+            .content
+            .into_iter()
+            .filter(|c| c.content_type == "text")
+            .filter_map(|c| c.text)
+            .collect::<Vec<_>>()
+            .join(" ");
+        non_empty(summary)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+}
+
+/// OpenAI Chat Completions request structure
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    max_tokens: u32,
+    messages: Vec<ApiMessage>,
+}
+
+/// OpenAI Chat Completions response structure
+#[derive(Deserialize)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    error: Option<ApiError>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Summaries from OpenAI's Chat Completions API or a compatible server.
+pub struct OpenAiProvider {
+    client: reqwest::blocking::Client,
+    api_key: Option<String>,
+    model: String,
+    max_tokens: u32,
+    url: String,
+}
+
+impl OpenAiProvider {
+    /// Create a provider from config, resolving the API key.
+    pub fn new(client: reqwest::blocking::Client, config: &SummarizationConfig) -> Self {
+        Self {
+            client,
+            api_key: resolve_api_key(config),
+            model: config.model().to_string(),
+            max_tokens: config.max_tokens,
+            url: format!("{}/chat/completions", config.base_url_or(OPENAI_BASE_URL)),
+        }
+    }
+}
+
+impl SummaryProvider for OpenAiProvider {
+    fn summarize(&self, user: &str, assistant: &str) -> Result<String, SummarizationError> {
+        let api_key = self.api_key.as_ref().ok_or(SummarizationError::NoApiKey)?;
+
+        let request = ChatRequest {
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            messages: vec![ApiMessage {
+                role: "user".to_string(),
+                content: build_prompt(user, assistant),
+            }],
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(api_key)
+            .json(&request)
+            .send()?;
+        let chat: ChatResponse = check_status(response)?.json()?;
+
+        if let Some(error) = chat.error {
+            return Err(SummarizationError::Api(error.message));
+        }
+
+        let summary = chat
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default();
+        non_empty(summary)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+}
+
+/// Ollama `/api/generate` request structure
+#[derive(Serialize)]
+struct GenerateRequest {
+    model: String,
+    prompt: String,
+    stream: bool,
+    options: GenerateOptions,
+}
+
+#[derive(Serialize)]
+struct GenerateOptions {
+    num_predict: u32,
+}
+
+/// Ollama `/api/generate` response structure
+#[derive(Deserialize)]
+struct GenerateResponse {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Summaries from a local Ollama server.
+pub struct OllamaProvider {
+    client: reqwest::blocking::Client,
+    model: String,
+    max_tokens: u32,
+    base_url: String,
+}
+
+impl OllamaProvider {
+    /// Create a provider from config; no API key is needed.
+    pub fn new(client: reqwest::blocking::Client, config: &SummarizationConfig) -> Self {
+        Self {
+            client,
+            model: config.model().to_string(),
+            max_tokens: config.max_tokens,
+            base_url: config.base_url_or(OLLAMA_BASE_URL).to_string(),
+        }
+    }
+}
+
+impl SummaryProvider for OllamaProvider {
+    fn summarize(&self, user: &str, assistant: &str) -> Result<String, SummarizationError> {
+        let request = GenerateRequest {
+            model: self.model.clone(),
+            prompt: build_prompt(user, assistant),
+            stream: false,
+            options: GenerateOptions {
+                num_predict: self.max_tokens,
+            },
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&request)
+            .send()?;
+        let generated: GenerateResponse = check_status(response)?.json()?;
+
+        if let Some(error) = generated.error {
+            return Err(SummarizationError::Api(error));
+        }
+        non_empty(generated.response)
+    }
+
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn is_ready(&self) -> bool {
+        let reachable = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .timeout(OLLAMA_PROBE_TIMEOUT)
+            .send()
+            .is_ok_and(|response| response.status().is_success());
+        if !reachable {
+            debug!("Ollama not reachable at {}", self.base_url);
+        }
+        reachable
+    }
+
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+}
+
+/// Resolve the API key for a hosted provider from config or environment
+fn resolve_api_key(config: &SummarizationConfig) -> Option<String> {
+    // 1. Check config file
+    if let Some(ref key) = config.api_key {
+        if !key.is_empty() {
+            debug!("Using API key from config file");
+            return Some(key.clone());
+        }
+    }
+
+    // 2. Check the provider's environment variable
+    let var = config.provider.api_key_env()?;
+    if let Ok(key) = std::env::var(var) {
+        if !key.is_empty() {
+            debug!("Using API key from {} env var", var);
+            return Some(key);
+        }
+    }
+
+    // 3. Future: Try Claude Code's internal credentials
+    // This would require integration with the Claude Code SDK
+
+    None
+}
+
 /// Summarizer for conversation exchanges
 pub struct Summarizer {
-    client: reqwest::blocking::Client,
+    provider: Box<dyn SummaryProvider>,
     config: SummarizationConfig,
-    api_key: Option<String>,
 }
 
 impl Summarizer {
     /// Create a new summarizer with config from file or defaults
     pub fn new(config_path: &Path) -> Self {
         let config = Self::load_config(config_path).unwrap_or_default();
-        let api_key = Self::resolve_api_key(&config);
-
-        if api_key.is_none() {
-            warn!("No Anthropic API key found. Set ANTHROPIC_API_KEY env var or add to ~/.diachron/config.toml");
-        }
 
         let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+            .timeout(Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
 
-        Self {
-            client,
-            config,
-            api_key,
-        }
+        let provider: Box<dyn SummaryProvider> = match config.provider {
+            ProviderKind::Anthropic => Box::new(AnthropicProvider::new(client, &config)),
+            ProviderKind::OpenAi => Box::new(OpenAiProvider::new(client, &config)),
+            ProviderKind::Ollama => Box::new(OllamaProvider::new(client, &config)),
+        };
+
+        Self { provider, config }
     }
 
     /// Load configuration from TOML file
@@ -148,37 +594,52 @@ impl Summarizer {
             summarization: SummarizationConfig,
         }
 
-        let parsed: ConfigFile = toml::from_str(&content).ok()?;
-        Some(parsed.summarization)
-    }
-
-    /// Resolve API key from config or environment
-    fn resolve_api_key(config: &SummarizationConfig) -> Option<String> {
-        // 1. Check config file
-        if let Some(ref key) = config.api_key {
-            if !key.is_empty() {
-                debug!("Using API key from config file");
-                return Some(key.clone());
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(parsed) => Some(parsed.summarization),
+            Err(e) => {
+                warn!("Invalid [summarization] config, using defaults: {}", e);
+                None
             }
         }
+    }
 
-        // 2. Check environment variable
-        if let Ok(key) = std::env::var("ANTHROPIC_API_KEY") {
-            if !key.is_empty() {
-                debug!("Using API key from ANTHROPIC_API_KEY env var");
-                return Some(key);
-            }
+    /// Name of the configured provider.
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    /// Explain what the configured provider needs to become available.
+    pub fn setup_hint(&self) -> String {
+        match self.config.provider.api_key_env() {
+            Some(var) => format!(
+                "Set {} env var or add api_key to [summarization] in ~/.diachron/config.toml",
+                var
+            ),
+            None => format!(
+                "Start Ollama at {} (ollama serve) and pull the '{}' model",
+                self.config.base_url_or(OLLAMA_BASE_URL),
+                self.config.model()
+            ),
         }
+    }
 
-        // 3. Future: Try Claude Code's internal credentials
-        // This would require integration with the Claude Code SDK
+    /// Retries allowed per exchange after a transient error.
+    pub fn max_retries(&self) -> u32 {
+        self.config.max_retries
+    }
 
-        None
+    /// Check if summarization is set up (enabled, and the provider has its
+    /// API key). Never blocks, so it is safe on the async runtime.
+    pub fn is_configured(&self) -> bool {
+        self.config.enabled && self.provider.is_configured()
     }
 
-    /// Check if summarization is available (has API key and enabled)
+    /// Check if summarization is available (enabled and the provider is ready)
+    ///
+    /// Probing a local server can block for a couple of seconds; call this
+    /// from a blocking task, not on the async runtime.
     pub fn is_available(&self) -> bool {
-        self.api_key.is_some() && self.config.enabled
+        self.config.enabled && self.provider.is_ready()
     }
 
     /// Summarize a conversation exchange
@@ -189,67 +650,7 @@ impl Summarizer {
         user_message: &str,
         assistant_message: &str,
     ) -> Result<String, SummarizationError> {
-        let api_key = self.api_key.as_ref().ok_or(SummarizationError::NoApiKey)?;
-
-        // Truncate long messages to stay within context limits
-        let user_truncated = truncate_to_chars(user_message, 2000);
-        let assistant_truncated = truncate_to_chars(assistant_message, 2000);
-
-        let prompt = format!(
-            "Summarize this Claude Code conversation exchange in 1-2 concise sentences. \
-            Focus on what was accomplished or discussed.\n\n\
-            User: {}\n\n\
-            Assistant: {}",
-            user_truncated, assistant_truncated
-        );
-
-        let request = ApiRequest {
-            model: self.config.model.clone(),
-            max_tokens: self.config.max_tokens,
-            messages: vec![ApiMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-        };
-
-        let response = self
-            .client
-            .post(API_BASE_URL)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(SummarizationError::Api(format!(
-                "HTTP {}: {}",
-                status, body
-            )));
-        }
-
-        let api_response: ApiResponse = response.json()?;
-
-        if let Some(error) = api_response.error {
-            return Err(SummarizationError::Api(error.message));
-        }
-
-        // Extract text from response
-        let summary = api_response
-            .content
-            .into_iter()
-            .filter(|c| c.content_type == "text")
-            .filter_map(|c| c.text)
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        if summary.is_empty() {
-            return Err(SummarizationError::Api("Empty response".to_string()));
-        }
-
-        Ok(summary)
+        self.provider.summarize(user_message, assistant_message)
     }
 
     /// Batch summarize multiple exchanges
@@ -314,10 +715,65 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = SummarizationConfig::default();
-        assert_eq!(config.model, DEFAULT_MODEL);
+        assert_eq!(config.provider, ProviderKind::Anthropic);
+        assert_eq!(config.model(), DEFAULT_MODEL);
         assert_eq!(config.max_tokens, MAX_TOKENS);
         assert!(config.enabled);
         assert!(config.api_key.is_none());
+        assert_eq!(config.max_retries, DEFAULT_MAX_RETRIES);
+    }
+
+    #[test]
+    fn test_provider_config() {
+        let config: SummarizationConfig =
+            toml::from_str("provider = \"ollama\"\nbase_url = \"http://gpu-box:11434/\"\n")
+                .unwrap();
+        assert_eq!(config.provider, ProviderKind::Ollama);
+        assert_eq!(config.model(), DEFAULT_OLLAMA_MODEL);
+        assert_eq!(config.base_url_or(OLLAMA_BASE_URL), "http://gpu-box:11434");
+        // Set up without contacting the server; reachability is probed later
+        let provider = OllamaProvider::new(reqwest::blocking::Client::new(), &config);
+        assert!(provider.is_configured());
+
+        // Keys written for the Anthropic-only summarizer still load
+        let config: SummarizationConfig = toml::from_str(
+            "anthropic_api_key = \"sk-ant-test\"\nmodel = \"claude-3-5-haiku-latest\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.provider, ProviderKind::Anthropic);
+        assert_eq!(config.api_key.as_deref(), Some("sk-ant-test"));
+        assert_eq!(config.model(), "claude-3-5-haiku-latest");
+
+        let config: SummarizationConfig =
+            toml::from_str("provider = \"openai\"\napi_key = \"sk-test\"\n").unwrap();
+        let provider = OpenAiProvider::new(reqwest::blocking::Client::new(), &config);
+        assert!(provider.is_configured());
+        assert!(provider.is_ready());
+        assert_eq!(provider.model, DEFAULT_OPENAI_MODEL);
+        assert_eq!(provider.url, "https://api.openai.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        let status = |status| SummarizationError::Status {
+            status,
+            body: String::new(),
+        };
+        assert!(status(500).is_retryable());
+        assert!(status(503).is_retryable());
+        assert!(status(429).is_retryable());
+        assert!(!status(400).is_retryable());
+        assert!(!status(401).is_retryable());
+        assert!(!SummarizationError::NoApiKey.is_retryable());
+        assert!(!SummarizationError::Api("Empty response".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        assert_eq!(backoff_delay(1), RETRY_BASE_DELAY);
+        assert_eq!(backoff_delay(2), RETRY_BASE_DELAY * 2);
+        assert_eq!(backoff_delay(3), RETRY_BASE_DELAY * 4);
+        assert_eq!(backoff_delay(40), RETRY_MAX_DELAY);
     }
 
     #[test]
@@ -328,7 +784,7 @@ mod tests {
         // Set test key
         std::env::set_var("ANTHROPIC_API_KEY", "test-key-123");
         let config = SummarizationConfig::default();
-        let key = Summarizer::resolve_api_key(&config);
+        let key = resolve_api_key(&config);
         assert_eq!(key, Some("test-key-123".to_string()));
 
         // Restore original
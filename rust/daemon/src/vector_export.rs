@@ -0,0 +1,207 @@
+//! Export of the vector indexes to flat files.
+//!
+//! usearch's own files need usearch and the ID map to read. The export
+//! writes one `(N, dim)` f32 matrix per index, with its IDs, in formats
+//! NumPy and Arrow readers load directly.
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow_array::{ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, StringArray};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema};
+use diachron_core::{VectorExportFormat, VectorIndex};
+
+/// Whether `dir` is `root` or a directory below it.
+///
+/// Compares path components without touching the filesystem, so a `..`
+/// below `root` is refused rather than resolved.
+pub fn is_within(root: &Path, dir: &Path) -> bool {
+    dir.strip_prefix(root).is_ok_and(|rest| {
+        rest.components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    })
+}
+
+/// Write one index's vectors to `dir`.
+///
+/// # Arguments
+/// - `index`: Index to export; removed entries are skipped.
+/// - `dir`: Directory for this index's files (created if missing).
+/// - `format`: `Npy` writes `embeddings.npy` and `ids.txt`; `Arrow` writes
+///   `embeddings.arrow`.
+///
+/// # Returns
+/// Vectors written and the files created.
+///
+/// # Errors
+/// Returns `anyhow::Error` if a file cannot be written.
+pub fn export_index(
+    index: &VectorIndex,
+    dir: &Path,
+    format: VectorExportFormat,
+) -> anyhow::Result<(u64, Vec<PathBuf>)> {
+    let vectors = index.export_vectors();
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let files = match format {
+        VectorExportFormat::Npy => {
+            let matrix = dir.join("embeddings.npy");
+            let ids = dir.join("ids.txt");
+            write_npy(&matrix, &vectors, index.dim())?;
+            write_ids(&ids, &vectors)?;
+            vec![matrix, ids]
+        }
+        VectorExportFormat::Arrow => {
+            let path = dir.join("embeddings.arrow");
+            write_arrow(&path, &vectors, index.dim())?;
+            vec![path]
+        }
+    };
+    Ok((vectors.len() as u64, files))
+}
+
+/// Create (or truncate) `path` for buffered writing.
+fn create(path: &Path) -> anyhow::Result<BufWriter<fs::File>> {
+    let file =
+        fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Write vectors as a little-endian f32 `.npy` matrix (format version 1.0).
+fn write_npy(path: &Path, vectors: &[(String, Vec<f32>)], dim: usize) -> anyhow::Result<()> {
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+        vectors.len(),
+        dim
+    );
+    // Magic, version and length take 10 bytes; the header is padded with
+    // spaces so the data starts on a 64-byte boundary
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut out = create(path)?;
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    out.write_all(&(header.len() as u16).to_le_bytes())?;
+    out.write_all(header.as_bytes())?;
+    for (_, vector) in vectors {
+        for value in vector {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Write one ID per line, in matrix row order.
+fn write_ids(path: &Path, vectors: &[(String, Vec<f32>)]) -> anyhow::Result<()> {
+    let mut out = create(path)?;
+    for (id, _) in vectors {
+        writeln!(out, "{}", id)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Write vectors as an Arrow IPC file holding a single record batch.
+fn write_arrow(path: &Path, vectors: &[(String, Vec<f32>)], dim: usize) -> anyhow::Result<()> {
+    let item = Arc::new(Field::new("item", DataType::Float32, false));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new(
+            "embedding",
+            DataType::FixedSizeList(item.clone(), dim as i32),
+            false,
+        ),
+    ]));
+
+    let ids = StringArray::from_iter_values(vectors.iter().map(|(id, _)| id.as_str()));
+    let values =
+        Float32Array::from_iter_values(vectors.iter().flat_map(|(_, v)| v.iter().copied()));
+    let embeddings = FixedSizeListArray::try_new(item, dim as i32, Arc::new(values), None)?;
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(ids) as ArrayRef, Arc::new(embeddings) as ArrayRef],
+    )?;
+
+    let mut writer = FileWriter::try_new(create(path)?, &schema)?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::tests::temp_dir;
+
+    fn small_index() -> VectorIndex {
+        let mut index = VectorIndex::new(4, None).unwrap();
+        index.add("b", &[0.0, 1.0, 0.0, 0.0], None).unwrap();
+        index.add("a", &[1.0, 0.0, 0.0, 0.0], None).unwrap();
+        index.add("gone", &[0.0, 0.0, 1.0, 0.0], None).unwrap();
+        index.remove("gone").unwrap();
+        index
+    }
+
+    #[test]
+    fn test_npy_export_round_trips_shape() {
+        let dir = temp_dir();
+        let (count, files) = export_index(&small_index(), &dir, VectorExportFormat::Npy).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(files.len(), 2);
+
+        let bytes = fs::read(dir.join("embeddings.npy")).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains("'shape': (2, 4)"));
+
+        let data: Vec<f32> = bytes[10 + header_len..]
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(data.len(), 2 * 4);
+        // Rows follow ids.txt, which is sorted and skips the removed entry
+        let ids = fs::read_to_string(dir.join("ids.txt")).unwrap();
+        assert_eq!(ids, "a\nb\n");
+        assert!((data[0] - 1.0).abs() < 1e-6);
+        assert!((data[5] - 1.0).abs() < 1e-6);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_arrow_export_has_id_and_embedding_columns() {
+        let dir = temp_dir();
+        export_index(&small_index(), &dir, VectorExportFormat::Arrow).unwrap();
+
+        let file = fs::File::open(dir.join("embeddings.arrow")).unwrap();
+        let reader = arrow_ipc::reader::FileReader::try_new(file, None).unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.schema().field(1).data_type(),
+            &DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, false)), 4)
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_is_within_refuses_paths_leaving_the_root() {
+        let root = Path::new("/home/user/.diachron/exports");
+        assert!(is_within(root, root));
+        assert!(is_within(root, &root.join("vectors/run-1")));
+        assert!(!is_within(root, Path::new("/home/user/.ssh")));
+        assert!(!is_within(root, &root.join("../indexes")));
+        assert!(!is_within(root, &root.join("vectors/../../indexes")));
+    }
+}
@@ -0,0 +1,133 @@
+//! LRU cache for embeddings keyed by a hash of the input text.
+//!
+//! Search queries and session-start context injection repeat the same
+//! handful of texts, so caching avoids re-running ONNX inference for them.
+
+use std::collections::{HashMap, VecDeque};
+
+use twox_hash::XxHash64;
+
+/// Bounded LRU cache mapping text hashes to embeddings.
+pub(crate) struct EmbeddingCache {
+    capacity: usize,
+    map: HashMap<u64, Vec<f32>>,
+    order: VecDeque<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+impl EmbeddingCache {
+    /// Create a cache holding up to `capacity` embeddings (0 disables it).
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            map: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Hash text into a cache key.
+    ///
+    /// Uses XxHash64 with a fixed seed so keys are stable across runs.
+    pub(crate) fn key(text: &str) -> u64 {
+        XxHash64::oneshot(0, text.as_bytes())
+    }
+
+    /// Look up an embedding, recording a hit or miss.
+    pub(crate) fn get(&mut self, key: u64) -> Option<Vec<f32>> {
+        if self.capacity == 0 {
+            self.misses += 1;
+            return None;
+        }
+
+        if let Some(embedding) = self.map.get(&key).cloned() {
+            self.hits += 1;
+            self.touch(key);
+            Some(embedding)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    /// Insert an embedding, evicting the least recently used entry if full.
+    pub(crate) fn insert(&mut self, key: u64, embedding: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.map.insert(key, embedding).is_some() {
+            self.touch(key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.map.len() > self.capacity {
+            if let Some(old_key) = self.order.pop_front() {
+                self.map.remove(&old_key);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Cache hit and miss counts since creation.
+    pub(crate) fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+
+    fn touch(&mut self, key: u64) {
+        if let Some(pos) = self.order.iter().position(|&k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_and_miss_counts() {
+        let mut cache = EmbeddingCache::new(2);
+        let key = EmbeddingCache::key("hello");
+
+        assert!(cache.get(key).is_none());
+        cache.insert(key, vec![1.0, 2.0]);
+        assert_eq!(cache.get(key), Some(vec![1.0, 2.0]));
+        assert_eq!(cache.stats(), (1, 1));
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = EmbeddingCache::new(2);
+        let (a, b, c) = (
+            EmbeddingCache::key("a"),
+            EmbeddingCache::key("b"),
+            EmbeddingCache::key("c"),
+        );
+
+        cache.insert(a, vec![1.0]);
+        cache.insert(b, vec![2.0]);
+        // Touch `a` so `b` becomes least recently used
+        assert!(cache.get(a).is_some());
+        cache.insert(c, vec![3.0]);
+
+        assert!(cache.get(a).is_some());
+        assert!(cache.get(b).is_none());
+        assert!(cache.get(c).is_some());
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let mut cache = EmbeddingCache::new(0);
+        let key = EmbeddingCache::key("hello");
+
+        cache.insert(key, vec![1.0]);
+        assert!(cache.get(key).is_none());
+        assert_eq!(cache.stats(), (0, 1));
+    }
+}
@@ -1,6 +1,7 @@
 //! Model download functionality
 //!
-//! Downloads the all-MiniLM-L6-v2 model from HuggingFace Hub.
+//! Downloads the all-MiniLM-L6-v2 embedding model and the
+//! ms-marco-MiniLM-L-6-v2 re-ranking model from HuggingFace Hub.
 
 use std::fs;
 use std::io::Write;
@@ -15,6 +16,10 @@ const MODEL_URL: &str =
     "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/onnx/model.onnx";
 const TOKENIZER_URL: &str =
     "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/tokenizer.json";
+const RERANKER_MODEL_URL: &str =
+    "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/onnx/model.onnx";
+const RERANKER_TOKENIZER_URL: &str =
+    "https://huggingface.co/cross-encoder/ms-marco-MiniLM-L-6-v2/resolve/main/tokenizer.json";
 
 /// Paths to model files.
 #[derive(Debug, Clone)]
@@ -37,12 +42,42 @@ impl ModelPaths {
             .join("all-MiniLM-L6-v2")
     }
 
+    /// Get the default re-ranking model directory.
+    ///
+    /// # Returns
+    /// Path to `~/.diachron/models/ms-marco-MiniLM-L-6-v2`.
+    pub fn reranker_dir() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(".diachron")
+            .join("models")
+            .join("ms-marco-MiniLM-L-6-v2")
+    }
+
     /// Create paths for the default directory.
     ///
     /// # Returns
     /// Model paths for the default directory layout.
     pub fn default() -> Self {
-        let model_dir = Self::default_dir();
+        Self::in_dir(Self::default_dir())
+    }
+
+    /// Create paths for the default re-ranking model directory.
+    ///
+    /// # Returns
+    /// Model paths for the re-ranking model.
+    pub fn reranker() -> Self {
+        Self::in_dir(Self::reranker_dir())
+    }
+
+    /// Create paths for `model.onnx` and `tokenizer.json` in a directory.
+    ///
+    /// # Arguments
+    /// - `model_dir`: Directory holding the model files.
+    ///
+    /// # Returns
+    /// Model paths inside `model_dir`.
+    pub fn in_dir(model_dir: PathBuf) -> Self {
         Self {
             model_path: model_dir.join("model.onnx"),
             tokenizer_path: model_dir.join("tokenizer.json"),
@@ -67,21 +102,42 @@ impl ModelPaths {
 /// # Errors
 /// Returns `EmbeddingError` if download fails.
 pub fn ensure_model_exists() -> Result<ModelPaths> {
-    let paths = ModelPaths::default();
+    ensure_downloaded(ModelPaths::default(), MODEL_URL, TOKENIZER_URL)
+}
 
+/// Ensure the re-ranking model exists, downloading if necessary.
+///
+/// # Returns
+/// Paths to the re-ranking model files.
+///
+/// # Errors
+/// Returns `EmbeddingError` if download fails.
+pub fn ensure_reranker_model_exists() -> Result<ModelPaths> {
+    ensure_downloaded(
+        ModelPaths::reranker(),
+        RERANKER_MODEL_URL,
+        RERANKER_TOKENIZER_URL,
+    )
+}
+
+fn ensure_downloaded(
+    paths: ModelPaths,
+    model_url: &str,
+    tokenizer_url: &str,
+) -> Result<ModelPaths> {
     if paths.exists() {
         info!("Model already exists at {:?}", paths.model_dir);
         return Ok(paths);
     }
 
     info!("Model not found, downloading...");
-    download_model(&paths)?;
+    download_model(&paths, model_url, tokenizer_url)?;
 
     Ok(paths)
 }
 
 /// Download the model files from HuggingFace Hub
-fn download_model(paths: &ModelPaths) -> Result<()> {
+fn download_model(paths: &ModelPaths, model_url: &str, tokenizer_url: &str) -> Result<()> {
     // Create model directory
     fs::create_dir_all(&paths.model_dir).map_err(|e| {
         EmbeddingError::DownloadFailed(format!("Failed to create model directory: {}", e))
@@ -89,11 +145,11 @@ fn download_model(paths: &ModelPaths) -> Result<()> {
 
     // Download model.onnx (~90MB)
     info!("Downloading model.onnx (this may take a minute)...");
-    download_file(MODEL_URL, &paths.model_path)?;
+    download_file(model_url, &paths.model_path)?;
 
     // Download tokenizer.json (~700KB)
     info!("Downloading tokenizer.json...");
-    download_file(TOKENIZER_URL, &paths.tokenizer_path)?;
+    download_file(tokenizer_url, &paths.tokenizer_path)?;
 
     info!("Model download complete!");
     Ok(())
@@ -169,5 +225,9 @@ mod tests {
         let paths = ModelPaths::default();
         assert!(paths.model_path.ends_with("model.onnx"));
         assert!(paths.tokenizer_path.ends_with("tokenizer.json"));
+
+        let reranker = ModelPaths::reranker();
+        assert!(reranker.model_dir.ends_with("ms-marco-MiniLM-L-6-v2"));
+        assert!(reranker.model_path.ends_with("model.onnx"));
     }
 }
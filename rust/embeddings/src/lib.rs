@@ -6,22 +6,37 @@
 //!
 //! Features:
 //! - Automatic model download from HuggingFace Hub
-//! - BERT tokenization with truncation
+//! - BERT tokenization with char-safe, code-aware truncation
 //! - Mean pooling with L2 normalization
 //! - Batch embedding support
+//! - LRU cache for repeated texts
+//! - Cross-encoder re-ranking (ms-marco-MiniLM-L-6-v2)
+//! - Symmetric int8 quantization helpers
+//! - Optional CoreML / CUDA execution providers (`coreml` / `cuda` features)
 
+mod cache;
 mod download;
+mod rerank;
+mod truncate;
 
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
 use ndarray::Array2;
-use ort::session::{builder::GraphOptimizationLevel, Session};
+use ort::session::{
+    builder::{GraphOptimizationLevel, SessionBuilder},
+    Session,
+};
 use ort::value::Tensor;
 use thiserror::Error;
 use tokenizers::Tokenizer;
-use tracing::{debug, info};
 
-pub use download::{ensure_model_exists, ModelPaths};
+use cache::EmbeddingCache;
+use tracing::{debug, info, warn};
+
+pub use download::{ensure_model_exists, ensure_reranker_model_exists, ModelPaths};
+pub use rerank::{CrossEncoder, CrossEncoderConfig};
+pub use truncate::{truncate_text, TruncationMode, ELISION_MARKER};
 
 /// Embedding dimension for all-MiniLM-L6-v2.
 pub const EMBEDDING_DIM: usize = 384;
@@ -32,6 +47,13 @@ pub const MAX_SEQ_LENGTH: usize = 512;
 /// Maximum text length before truncation (chars).
 pub const MAX_TEXT_LENGTH: usize = 2000;
 
+/// Default number of embeddings kept in the in-process LRU cache.
+pub const DEFAULT_CACHE_SIZE: usize = 1024;
+
+/// Environment variable overriding the configured execution providers
+/// (comma-separated, e.g. `coreml,cpu`).
+pub const PROVIDER_ENV_VAR: &str = "DIACHRON_ORT_PROVIDER";
+
 /// Error type for embedding operations.
 #[derive(Error, Debug)]
 pub enum EmbeddingError {
@@ -54,6 +76,105 @@ pub enum EmbeddingError {
 /// Result alias for embedding operations.
 pub type Result<T> = std::result::Result<T, EmbeddingError>;
 
+/// ONNX Runtime execution provider to run inference on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionProvider {
+    /// Default CPU provider (always available)
+    Cpu,
+    /// Apple CoreML (requires the `coreml` feature)
+    CoreMl,
+    /// NVIDIA CUDA (requires the `cuda` feature)
+    Cuda,
+}
+
+impl ExecutionProvider {
+    /// Get the string representation of the provider.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionProvider::Cpu => "cpu",
+            ExecutionProvider::CoreMl => "coreml",
+            ExecutionProvider::Cuda => "cuda",
+        }
+    }
+
+    /// Parse a provider name (case-insensitive).
+    ///
+    /// # Arguments
+    /// - `s`: Provider name such as `cpu`, `coreml` or `cuda`.
+    ///
+    /// # Returns
+    /// Matching provider, or `None` if the name is unknown.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cpu" => Some(ExecutionProvider::Cpu),
+            "coreml" => Some(ExecutionProvider::CoreMl),
+            "cuda" => Some(ExecutionProvider::Cuda),
+            _ => None,
+        }
+    }
+
+    /// Read the provider list from `DIACHRON_ORT_PROVIDER`, if set.
+    ///
+    /// Unknown names are logged and ignored.
+    ///
+    /// # Returns
+    /// Providers in the order given, or `None` if the variable is unset or
+    /// contains no recognised names.
+    pub fn from_env() -> Option<Vec<Self>> {
+        let value = std::env::var(PROVIDER_ENV_VAR).ok()?;
+        let providers: Vec<Self> = value
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .filter_map(|name| {
+                let provider = Self::parse(name);
+                if provider.is_none() {
+                    warn!("Ignoring unknown execution provider '{}'", name.trim());
+                }
+                provider
+            })
+            .collect();
+
+        if providers.is_empty() {
+            None
+        } else {
+            Some(providers)
+        }
+    }
+
+    /// Register this provider on a session builder.
+    ///
+    /// CPU is ONNX Runtime's built-in fallback, so registering it is a no-op.
+    fn register(self, builder: &mut SessionBuilder) -> std::result::Result<(), String> {
+        match self {
+            ExecutionProvider::Cpu => Ok(()),
+            #[cfg(feature = "coreml")]
+            ExecutionProvider::CoreMl => {
+                ort::ep::ExecutionProvider::register(&ort::ep::CoreML::default(), builder)
+                    .map_err(|e| e.to_string())
+            }
+            #[cfg(feature = "cuda")]
+            ExecutionProvider::Cuda => {
+                ort::ep::ExecutionProvider::register(&ort::ep::CUDA::default(), builder)
+                    .map_err(|e| e.to_string())
+            }
+            #[allow(unreachable_patterns)]
+            other => {
+                let _ = builder;
+                Err(format!(
+                    "diachron-embeddings was built without the `{}` feature",
+                    other.as_str()
+                ))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutionProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Embedding engine configuration.
 #[derive(Debug, Clone)]
 pub struct EmbeddingConfig {
@@ -69,8 +190,17 @@ pub struct EmbeddingConfig {
     /// Maximum sequence length
     pub max_length: usize,
 
-    /// Maximum text length before truncation
+    /// Maximum text length before truncation (chars)
     pub max_text_length: usize,
+
+    /// How texts longer than `max_text_length` are shortened
+    pub truncation: TruncationMode,
+
+    /// Execution providers to try, in priority order (CPU is always the fallback)
+    pub execution_providers: Vec<ExecutionProvider>,
+
+    /// Maximum embeddings kept in the LRU cache (0 disables caching)
+    pub cache_size: usize,
 }
 
 impl Default for EmbeddingConfig {
@@ -87,6 +217,9 @@ impl Default for EmbeddingConfig {
             embedding_dim: EMBEDDING_DIM,
             max_length: MAX_SEQ_LENGTH,
             max_text_length: MAX_TEXT_LENGTH,
+            truncation: TruncationMode::default(),
+            execution_providers: vec![ExecutionProvider::Cpu],
+            cache_size: DEFAULT_CACHE_SIZE,
         }
     }
 }
@@ -113,6 +246,7 @@ pub struct EmbeddingEngine {
     session: Session,
     tokenizer: Tokenizer,
     config: EmbeddingConfig,
+    cache: EmbeddingCache,
 }
 
 impl EmbeddingEngine {
@@ -142,13 +276,7 @@ impl EmbeddingEngine {
             });
         }
 
-        // Load ONNX session with optimizations
-        let session = Session::builder()?
-            .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(4)?
-            .commit_from_file(&config.model_path)?;
-
-        info!("ONNX session loaded successfully");
+        let session = build_session(&config.model_path, &config.execution_providers)?;
 
         // Load tokenizer
         let tokenizer = Tokenizer::from_file(&config.tokenizer_path)
@@ -159,6 +287,7 @@ impl EmbeddingEngine {
         Ok(Self {
             session,
             tokenizer,
+            cache: EmbeddingCache::new(config.cache_size),
             config,
         })
     }
@@ -175,6 +304,26 @@ impl EmbeddingEngine {
         Self::new(config)
     }
 
+    /// Create a new embedding engine with default paths on a specific
+    /// execution provider.
+    ///
+    /// Downloads the model if not present. `DIACHRON_ORT_PROVIDER` still
+    /// takes precedence when set.
+    ///
+    /// # Arguments
+    /// - `provider`: Execution provider to try before falling back to CPU.
+    ///
+    /// # Errors
+    /// Returns `EmbeddingError` if the model cannot be downloaded or loaded.
+    pub fn new_with_provider(provider: ExecutionProvider) -> Result<Self> {
+        let paths = ensure_model_exists()?;
+        let config = EmbeddingConfig {
+            execution_providers: vec![provider],
+            ..EmbeddingConfig::from_paths(&paths)
+        };
+        Self::new(config)
+    }
+
     /// Generate an embedding for a single text.
     ///
     /// # Arguments
@@ -192,7 +341,8 @@ impl EmbeddingEngine {
 
     /// Generate embeddings for multiple texts (batch).
     ///
-    /// More efficient than calling `embed()` multiple times.
+    /// More efficient than calling `embed()` multiple times. Texts already in
+    /// the LRU cache are served from it; only misses run through the model.
     ///
     /// # Arguments
     /// - `texts`: Slice of input texts.
@@ -207,24 +357,46 @@ impl EmbeddingEngine {
             return Ok(vec![]);
         }
 
+        let keys: Vec<u64> = texts.iter().map(|t| EmbeddingCache::key(t)).collect();
+        let mut results: Vec<Option<Vec<f32>>> =
+            keys.iter().map(|&key| self.cache.get(key)).collect();
+
+        let misses: Vec<usize> = (0..texts.len()).filter(|&i| results[i].is_none()).collect();
+        if !misses.is_empty() {
+            let miss_texts: Vec<&str> = misses.iter().map(|&i| texts[i]).collect();
+            let computed = self.infer_batch(&miss_texts)?;
+            for (i, embedding) in misses.into_iter().zip(computed) {
+                self.cache.insert(keys[i], embedding.clone());
+                results[i] = Some(embedding);
+            }
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Get embedding cache statistics.
+    ///
+    /// # Returns
+    /// `(hits, misses)` since the engine was created.
+    pub fn cache_stats(&self) -> (u64, u64) {
+        self.cache.stats()
+    }
+
+    /// Run the model on a batch of texts, bypassing the cache.
+    fn infer_batch(&mut self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
         // Truncate texts to max length
-        let truncated: Vec<&str> = texts
+        let truncated: Vec<Cow<'_, str>> = texts
             .iter()
-            .map(|t| {
-                if t.len() > self.config.max_text_length {
-                    &t[..self.config.max_text_length]
-                } else {
-                    *t
-                }
-            })
+            .map(|t| truncate_text(t, self.config.max_text_length, self.config.truncation))
             .collect();
 
         debug!("Embedding {} texts", truncated.len());
 
         // Tokenize all texts
+        let inputs: Vec<&str> = truncated.iter().map(|t| t.as_ref()).collect();
         let encodings = self
             .tokenizer
-            .encode_batch(truncated.clone(), true)
+            .encode_batch(inputs, true)
             .map_err(|e| EmbeddingError::TokenizerError(e.to_string()))?;
 
         // Find max length in batch
@@ -322,6 +494,31 @@ impl EmbeddingEngine {
     }
 }
 
+/// Load an ONNX session with optimizations on the requested providers.
+///
+/// `DIACHRON_ORT_PROVIDER` takes precedence over `providers`; providers that
+/// fail to register fall back to CPU.
+fn build_session(model_path: &Path, providers: &[ExecutionProvider]) -> Result<Session> {
+    let mut builder = Session::builder()?
+        .with_optimization_level(GraphOptimizationLevel::Level3)?
+        .with_intra_threads(4)?;
+
+    let providers = ExecutionProvider::from_env().unwrap_or_else(|| providers.to_vec());
+    for provider in providers {
+        match provider.register(&mut builder) {
+            Ok(()) => debug!("Registered {} execution provider", provider),
+            Err(e) => warn!(
+                "Failed to register {} execution provider, falling back to CPU: {}",
+                provider, e
+            ),
+        }
+    }
+
+    let session = builder.commit_from_file(model_path)?;
+    info!("ONNX session loaded successfully");
+    Ok(session)
+}
+
 /// Mean pooling for a single item in the batch from flat tensor data
 ///
 /// The hidden_state is a flat array of shape [batch, seq_len, hidden_size]
@@ -371,6 +568,10 @@ fn l2_normalize(vec: &[f32]) -> Vec<f32> {
 
 /// Compute cosine similarity between two embeddings.
 ///
+/// Embeddings from [`EmbeddingEngine`] are L2-normalized, so this is their
+/// dot product, computed by the same kernel as
+/// `diachron_core::cosine_similarity`.
+///
 /// # Arguments
 /// - `a`: First embedding.
 /// - `b`: Second embedding.
@@ -382,11 +583,42 @@ fn l2_normalize(vec: &[f32]) -> Vec<f32> {
 /// Panics if the embedding lengths do not match.
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     assert_eq!(a.len(), b.len(), "Embedding dimensions must match");
+    diachron_core::simd::dot(a, b)
+}
+
+/// Quantize an embedding to int8 using symmetric per-vector scaling.
+///
+/// # Arguments
+/// - `embedding`: Embedding to quantize.
+///
+/// # Returns
+/// Quantized values and the scale factor needed to dequantize them
+/// (`value as f32 * scale`).
+pub fn quantize_embedding(embedding: &[f32]) -> (Vec<i8>, f32) {
+    let max_abs = embedding.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+    if max_abs == 0.0 {
+        return (vec![0; embedding.len()], 1.0);
+    }
 
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let scale = max_abs / 127.0;
+    let quantized = embedding
+        .iter()
+        .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8)
+        .collect();
 
-    // If vectors are already L2 normalized, dot product is cosine similarity
-    dot
+    (quantized, scale)
+}
+
+/// Reconstruct an approximate `f32` embedding from int8 values.
+///
+/// # Arguments
+/// - `quantized`: Values produced by `quantize_embedding`.
+/// - `scale`: Scale factor returned alongside them.
+///
+/// # Returns
+/// Dequantized embedding.
+pub fn dequantize_embedding(quantized: &[i8], scale: f32) -> Vec<f32> {
+    quantized.iter().map(|&q| q as f32 * scale).collect()
 }
 
 #[cfg(test)]
@@ -415,6 +647,45 @@ mod tests {
         assert!((sim - 1.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_quantize_round_trip() {
+        let a = l2_normalize(&[0.3, -1.2, 0.05, 2.0, -0.7]);
+        let (q, scale) = quantize_embedding(&a);
+
+        assert_eq!(q.len(), a.len());
+        assert_eq!(q.iter().map(|x| x.unsigned_abs()).max(), Some(127));
+
+        let restored = dequantize_embedding(&q, scale);
+        for (x, y) in a.iter().zip(restored.iter()) {
+            assert!((x - y).abs() <= scale);
+        }
+        assert!(cosine_similarity(&a, &l2_normalize(&restored)) > 0.999);
+    }
+
+    #[test]
+    fn test_quantize_zero_vector() {
+        let (q, scale) = quantize_embedding(&[0.0; 4]);
+        assert_eq!(q, vec![0; 4]);
+        assert_eq!(dequantize_embedding(&q, scale), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_execution_provider_parse() {
+        assert_eq!(
+            ExecutionProvider::parse("CoreML"),
+            Some(ExecutionProvider::CoreMl)
+        );
+        assert_eq!(
+            ExecutionProvider::parse(" cuda "),
+            Some(ExecutionProvider::Cuda)
+        );
+        assert_eq!(
+            ExecutionProvider::parse("cpu"),
+            Some(ExecutionProvider::Cpu)
+        );
+        assert_eq!(ExecutionProvider::parse("tpu"), None);
+    }
+
     #[test]
     fn test_cosine_similarity_orthogonal() {
         let a = l2_normalize(&vec![1.0, 0.0]);
@@ -0,0 +1,229 @@
+//! Cross-encoder re-ranking
+//!
+//! Model: ms-marco-MiniLM-L-6-v2 (22M params, one relevance logit per pair)
+//!
+//! Unlike the embedding model, a cross-encoder reads the query and a document
+//! together, so it cannot be precomputed into an index. It is too slow for
+//! retrieval but orders a short candidate list more sharply.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use ort::session::Session;
+use ort::value::Tensor;
+use tokenizers::{EncodeInput, Tokenizer};
+use tracing::{debug, info};
+
+use crate::{
+    build_session, truncate_text, EmbeddingError, ExecutionProvider, ModelPaths, Result,
+    TruncationMode, MAX_SEQ_LENGTH, MAX_TEXT_LENGTH,
+};
+
+/// Cross-encoder configuration.
+#[derive(Debug, Clone)]
+pub struct CrossEncoderConfig {
+    /// Path to the ONNX model file
+    pub model_path: PathBuf,
+
+    /// Path to the tokenizer.json file
+    pub tokenizer_path: PathBuf,
+
+    /// Maximum sequence length of a query/document pair
+    pub max_length: usize,
+
+    /// Maximum document length before truncation (chars)
+    pub max_text_length: usize,
+
+    /// Execution providers to try, in priority order (CPU is always the fallback)
+    pub execution_providers: Vec<ExecutionProvider>,
+}
+
+impl Default for CrossEncoderConfig {
+    fn default() -> Self {
+        Self::from_paths(&ModelPaths::reranker())
+    }
+}
+
+impl CrossEncoderConfig {
+    /// Create config from model paths.
+    ///
+    /// # Arguments
+    /// - `paths`: Model file paths.
+    ///
+    /// # Returns
+    /// Configuration with model/tokenizer paths set.
+    pub fn from_paths(paths: &ModelPaths) -> Self {
+        Self {
+            model_path: paths.model_path.clone(),
+            tokenizer_path: paths.tokenizer_path.clone(),
+            max_length: MAX_SEQ_LENGTH,
+            max_text_length: MAX_TEXT_LENGTH,
+            execution_providers: vec![ExecutionProvider::Cpu],
+        }
+    }
+}
+
+/// Query/document relevance scorer using ONNX Runtime.
+pub struct CrossEncoder {
+    session: Session,
+    tokenizer: Tokenizer,
+    config: CrossEncoderConfig,
+}
+
+impl CrossEncoder {
+    /// Create a new cross-encoder.
+    ///
+    /// Use `ensure_reranker_model_exists()` to download the model first if
+    /// needed.
+    ///
+    /// # Arguments
+    /// - `config`: Cross-encoder configuration.
+    ///
+    /// # Errors
+    /// Returns `EmbeddingError` if model files are missing or inference
+    /// components fail to initialize.
+    pub fn new(config: CrossEncoderConfig) -> Result<Self> {
+        info!("Loading re-ranking model from {:?}", config.model_path);
+
+        for path in [&config.model_path, &config.tokenizer_path] {
+            if !path.exists() {
+                return Err(EmbeddingError::ModelNotFound {
+                    path: path.display().to_string(),
+                });
+            }
+        }
+
+        let session = build_session(&config.model_path, &config.execution_providers)?;
+        let tokenizer = Tokenizer::from_file(&config.tokenizer_path)
+            .map_err(|e| EmbeddingError::TokenizerError(e.to_string()))?;
+
+        Ok(Self {
+            session,
+            tokenizer,
+            config,
+        })
+    }
+
+    /// Score how well one document answers a query.
+    ///
+    /// # Arguments
+    /// - `query`: Search query.
+    /// - `doc`: Candidate document text.
+    ///
+    /// # Returns
+    /// Relevance logit; higher is more relevant.
+    ///
+    /// # Errors
+    /// Returns `EmbeddingError` if tokenization or inference fails.
+    pub fn score(&mut self, query: &str, doc: &str) -> Result<f32> {
+        let scores = self.score_batch(query, &[doc])?;
+        Ok(scores[0])
+    }
+
+    /// Score several documents against the same query in one batch.
+    ///
+    /// # Arguments
+    /// - `query`: Search query.
+    /// - `docs`: Candidate document texts.
+    ///
+    /// # Returns
+    /// Relevance logits in input order; higher is more relevant.
+    ///
+    /// # Errors
+    /// Returns `EmbeddingError` if tokenization or inference fails.
+    pub fn score_batch(&mut self, query: &str, docs: &[&str]) -> Result<Vec<f32>> {
+        if docs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let truncated: Vec<Cow<'_, str>> = docs
+            .iter()
+            .map(|d| truncate_text(d, self.config.max_text_length, TruncationMode::default()))
+            .collect();
+        let pairs: Vec<EncodeInput<'_>> = truncated
+            .iter()
+            .map(|doc| (query, doc.as_ref()).into())
+            .collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| EmbeddingError::TokenizerError(e.to_string()))?;
+
+        let batch_size = encodings.len();
+        let max_len = encodings
+            .iter()
+            .map(|e| e.get_ids().len())
+            .max()
+            .unwrap_or(0)
+            .min(self.config.max_length);
+        debug!("Scoring {} pairs (max {} tokens)", batch_size, max_len);
+
+        let mut input_ids = vec![0i64; batch_size * max_len];
+        let mut attention_mask = vec![0i64; batch_size * max_len];
+        let mut token_type_ids = vec![0i64; batch_size * max_len];
+        for (i, encoding) in encodings.iter().enumerate() {
+            let ids = encoding.get_ids();
+            let mask = encoding.get_attention_mask();
+            let types = encoding.get_type_ids();
+            for j in 0..ids.len().min(max_len) {
+                input_ids[i * max_len + j] = ids[j] as i64;
+                attention_mask[i * max_len + j] = mask[j] as i64;
+                token_type_ids[i * max_len + j] = types[j] as i64;
+            }
+        }
+
+        let shape = [batch_size, max_len];
+        let outputs = self.session.run(ort::inputs![
+            "input_ids" => Tensor::from_array((shape, input_ids))?,
+            "attention_mask" => Tensor::from_array((shape, attention_mask))?,
+            "token_type_ids" => Tensor::from_array((shape, token_type_ids))?,
+        ])?;
+
+        // Logits of shape (batch_size, 1)
+        let (shape, logits) = outputs[0].try_extract_tensor::<f32>()?;
+        first_logits(
+            logits,
+            batch_size,
+            shape.get(1).copied().unwrap_or(1) as usize,
+        )
+    }
+}
+
+/// Take the first logit of each row of a row-major `(rows, width)` tensor.
+fn first_logits(logits: &[f32], rows: usize, width: usize) -> Result<Vec<f32>> {
+    if width == 0 || logits.len() < rows * width {
+        return Err(EmbeddingError::InferenceFailed(format!(
+            "Expected {} rows of {} logits, got {} values",
+            rows,
+            width,
+            logits.len()
+        )));
+    }
+    Ok(logits.chunks(width).take(rows).map(|row| row[0]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_logits() {
+        assert_eq!(first_logits(&[0.5, -1.0], 2, 1).unwrap(), vec![0.5, -1.0]);
+        assert_eq!(
+            first_logits(&[2.0, 9.0, 3.0, 9.0], 2, 2).unwrap(),
+            vec![2.0, 3.0]
+        );
+        assert!(first_logits(&[1.0], 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_missing_model_is_reported() {
+        let config = CrossEncoderConfig::from_paths(&ModelPaths::in_dir(PathBuf::from(
+            "/nonexistent/diachron-reranker",
+        )));
+        assert!(matches!(
+            CrossEncoder::new(config),
+            Err(EmbeddingError::ModelNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+//! Truncation of long texts before tokenization.
+//!
+//! Text past the model's sequence limit is dropped anyway, but long inputs
+//! still cost tokenizer time, so they are cut to `max_text_length`
+//! characters first. For code, keeping only the head loses the end of the
+//! function (its return value, closing logic), so code-like text keeps both
+//! ends around an elision marker instead.
+
+use std::borrow::Cow;
+
+/// Marker inserted where the middle of a code-like text was removed.
+pub const ELISION_MARKER: &str = "\n...\n";
+
+/// Fraction of non-blank lines that must look like code for the head/tail
+/// split to apply.
+const CODE_LINE_RATIO: f32 = 0.3;
+
+/// How texts longer than the limit are shortened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationMode {
+    /// Keep the first characters only
+    Head,
+    /// Keep the first and last characters of code-like text around
+    /// [`ELISION_MARKER`]; other text keeps its head
+    #[default]
+    HeadTail,
+}
+
+impl TruncationMode {
+    /// Get the string representation of the mode.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TruncationMode::Head => "head",
+            TruncationMode::HeadTail => "head_tail",
+        }
+    }
+
+    /// Parse a mode name (case-insensitive).
+    ///
+    /// # Arguments
+    /// - `s`: Mode name, `head` or `head_tail`.
+    ///
+    /// # Returns
+    /// Matching mode, or `None` if the name is unknown.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "head" => Some(TruncationMode::Head),
+            "head_tail" => Some(TruncationMode::HeadTail),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TruncationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Shorten text to at most `max_chars` characters.
+///
+/// Cuts always fall on `char` boundaries, so multibyte input is safe.
+///
+/// # Arguments
+/// - `text`: Input text.
+/// - `max_chars`: Maximum length in characters.
+/// - `mode`: Truncation strategy.
+///
+/// # Returns
+/// The text unchanged if it fits, otherwise a shortened copy.
+pub fn truncate_text(text: &str, max_chars: usize, mode: TruncationMode) -> Cow<'_, str> {
+    let Some(head_end) = char_offset(text, max_chars) else {
+        return Cow::Borrowed(text);
+    };
+
+    let marker_len = ELISION_MARKER.chars().count();
+    if mode == TruncationMode::HeadTail && max_chars > marker_len && looks_like_code(text) {
+        let keep = max_chars - marker_len;
+        let head_chars = keep.div_ceil(2);
+        let tail_chars = keep - head_chars;
+
+        let head_end = char_offset(text, head_chars).unwrap_or(text.len());
+        let tail_start = text
+            .char_indices()
+            .rev()
+            .nth(tail_chars.saturating_sub(1))
+            .map_or(text.len(), |(i, _)| i);
+        let tail = if tail_chars == 0 {
+            ""
+        } else {
+            &text[tail_start..]
+        };
+
+        return Cow::Owned(format!("{}{}{}", &text[..head_end], ELISION_MARKER, tail));
+    }
+
+    Cow::Borrowed(&text[..head_end])
+}
+
+/// Byte offset of the `n`th character, or `None` if the text is shorter.
+fn char_offset(text: &str, n: usize) -> Option<usize> {
+    text.char_indices().nth(n).map(|(i, _)| i)
+}
+
+/// Heuristic check for source code: enough lines end in block or statement
+/// punctuation, or open with a common declaration keyword.
+fn looks_like_code(text: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "fn ",
+        "pub ",
+        "let ",
+        "const ",
+        "def ",
+        "class ",
+        "function ",
+        "return",
+        "import ",
+        "from ",
+        "use ",
+        "if ",
+        "for ",
+        "while ",
+        "#include",
+        "package ",
+        "func ",
+    ];
+
+    let mut lines = 0usize;
+    let mut code_lines = 0usize;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        lines += 1;
+        let ends_like_code = line.ends_with(['{', '}', ';', ')', ':', ',']);
+        if ends_like_code || KEYWORDS.iter().any(|kw| line.starts_with(kw)) {
+            code_lines += 1;
+        }
+    }
+
+    lines >= 2 && code_lines as f32 / lines as f32 >= CODE_LINE_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multibyte_text_truncates_on_char_boundary() {
+        let prose = "é漢🦀".repeat(1000);
+        let code = "let s = \"é漢🦀\";\n".repeat(500);
+        for text in [&prose, &code] {
+            for mode in [TruncationMode::Head, TruncationMode::HeadTail] {
+                let truncated = truncate_text(text, 2000, mode);
+                assert_eq!(truncated.chars().count(), 2000);
+            }
+        }
+        assert!(prose.starts_with(truncate_text(&prose, 2000, TruncationMode::HeadTail).as_ref()));
+    }
+
+    #[test]
+    fn test_code_keeps_signature_and_return() {
+        let body = "    let x = compute();\n".repeat(200);
+        let code = format!(
+            "fn important(a: u32) -> u32 {{\n{}    return a + x;\n}}\n",
+            body
+        );
+        let truncated = truncate_text(&code, 200, TruncationMode::HeadTail);
+
+        assert!(truncated.chars().count() <= 200);
+        assert!(truncated.starts_with("fn important(a: u32) -> u32 {"));
+        assert!(truncated.contains(ELISION_MARKER));
+        assert!(truncated.ends_with("return a + x;\n}\n"));
+
+        let head_only = truncate_text(&code, 200, TruncationMode::Head);
+        assert!(!head_only.contains("return a + x;"));
+    }
+
+    #[test]
+    fn test_prose_and_short_text_keep_head() {
+        let prose = "The quick brown fox jumps over the lazy dog. ".repeat(100);
+        let truncated = truncate_text(&prose, 100, TruncationMode::HeadTail);
+        assert_eq!(truncated, &prose[..100]);
+
+        assert!(matches!(
+            truncate_text("short", 100, TruncationMode::HeadTail),
+            Cow::Borrowed("short")
+        ));
+    }
+}
@@ -11,20 +11,26 @@
 //! - Git branch capture on every event
 //! - Commit SHA for git commit events
 //! - Semantic command categories (git, test, build, deploy, file_ops)
+//! - Tool call outcome, with an error excerpt when the tool output shows a failure
 
 use chrono::Local;
+use regex::Regex;
 use rusqlite::{params, Connection};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::env;
 use std::fs;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // Import shared types from core
-use diachron_core::{send_to_daemon, CaptureEvent, CommandCategory, IpcError, Operation};
+use diachron_core::{
+    compute_fingerprint, default_transport, send_to_daemon, CaptureEvent, CommandCategory,
+    FingerprintOptions, IpcError, Operation, Outcome, BUSY_TIMEOUT,
+};
 
 // ============================================================================
 // HOOK INPUT PARSING
@@ -34,14 +40,159 @@ use diachron_core::{send_to_daemon, CaptureEvent, CommandCategory, IpcError, Ope
 struct HookInput {
     tool_name: String,
     tool_input: Value,
-    tool_result: Option<String>,
-    #[allow(dead_code)]
+    /// Tool output: a string, or an object for hooks that send `tool_response`
+    #[serde(alias = "tool_response")]
+    tool_result: Option<Value>,
     session_id: Option<String>,
     #[allow(dead_code)]
     timestamp: Option<String>,
     cwd: Option<String>,
 }
 
+impl HookInput {
+    /// Tool output as text (objects are flattened to their JSON form).
+    fn tool_result_text(&self) -> Option<String> {
+        match self.tool_result.as_ref()? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+/// Why a hook run captures nothing.
+///
+/// Normal runs exit silently on any of these; a dry run prints them.
+#[derive(Debug, PartialEq)]
+enum SkipReason {
+    /// Nothing on stdin
+    EmptyInput,
+    /// Stdin is not hook JSON
+    InvalidInput(String),
+    /// No `cwd` in the input and the current directory is unavailable
+    NoWorkingDirectory,
+    /// No ancestor of this directory has a root marker
+    NoProjectRoot(PathBuf),
+    /// A tool whose calls are not captured (Read, Grep, ...)
+    UntrackedTool(String),
+    /// An edit that leaves the content unchanged
+    NoopEdit,
+    /// A read-only or skipped Bash command
+    SkippedCommand(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::EmptyInput => write!(f, "empty input"),
+            SkipReason::InvalidInput(e) => write!(f, "input is not hook JSON: {}", e),
+            SkipReason::NoWorkingDirectory => write!(f, "no cwd in input and no current directory"),
+            SkipReason::NoProjectRoot(dir) => write!(
+                f,
+                "no project root above {} (markers: {})",
+                dir.display(),
+                root_markers().join(", ")
+            ),
+            SkipReason::UntrackedTool(tool) => write!(f, "{} calls are not captured", tool),
+            SkipReason::NoopEdit => write!(f, "edit leaves the content unchanged"),
+            SkipReason::SkippedCommand(cmd) => {
+                write!(f, "read-only or skipped command: {}", cmd)
+            }
+        }
+    }
+}
+
+/// Parse the JSON the hook receives on stdin.
+fn parse_input(input: &str) -> Result<HookInput, SkipReason> {
+    if input.trim().is_empty() {
+        return Err(SkipReason::EmptyInput);
+    }
+    serde_json::from_str(input).map_err(|e| SkipReason::InvalidInput(e.to_string()))
+}
+
+// ============================================================================
+// FAILURE DETECTION
+// ============================================================================
+
+/// Tag Claude Code wraps around the output of a failed tool call
+const TOOL_USE_ERROR_TAG: &str = "<tool_use_error>";
+
+/// Maximum length of the stored error excerpt
+const ERROR_EXCERPT_CHARS: usize = 200;
+
+/// Classify a tool call from its output.
+///
+/// Returns `None` when there is no output to inspect, otherwise the outcome
+/// and, for failures, a short excerpt of the error.
+fn detect_outcome(hook: &HookInput) -> Option<(Outcome, Option<String>)> {
+    // Structured responses may flag errors or exit codes directly
+    if let Some(Value::Object(response)) = &hook.tool_result {
+        let is_error = response.get("is_error").and_then(Value::as_bool) == Some(true)
+            || response.get("success").and_then(Value::as_bool) == Some(false);
+        let nonzero_exit = ["exit_code", "exitCode", "returncode"]
+            .iter()
+            .filter_map(|key| response.get(*key).and_then(Value::as_i64))
+            .any(|code| code != 0);
+        if is_error || nonzero_exit {
+            let detail = ["error", "stderr", "message"]
+                .iter()
+                .filter_map(|key| response.get(*key).and_then(Value::as_str))
+                .find(|s| !s.trim().is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| Value::Object(response.clone()).to_string());
+            return Some((Outcome::Failure, Some(error_excerpt(&detail))));
+        }
+    }
+
+    let text = hook.tool_result_text()?;
+
+    // Output is file contents or program text that may mention any error
+    // phrase, so only the tag leading the output (or a Bash exit code)
+    // marks a failure
+    let failed = text.trim_start().starts_with(TOOL_USE_ERROR_TAG)
+        || (hook.tool_name == "Bash"
+            && bash_exit_code(&text.to_lowercase()).is_some_and(|c| c != 0));
+
+    if failed {
+        Some((Outcome::Failure, Some(error_excerpt(&text))))
+    } else {
+        Some((Outcome::Success, None))
+    }
+}
+
+/// Exit code embedded in Bash output as "Exit code N" / "exit status N"
+fn bash_exit_code(lower: &str) -> Option<i64> {
+    ["exit code", "exit status"].iter().find_map(|marker| {
+        let rest = &lower[lower.find(marker)? + marker.len()..];
+        let digits: String = rest
+            .trim_start_matches([' ', ':'])
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '-')
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+/// First non-empty lines of an error, stripped of tool_use_error tags and
+/// truncated to `ERROR_EXCERPT_CHARS`.
+fn error_excerpt(text: &str) -> String {
+    let cleaned = text
+        .replace(TOOL_USE_ERROR_TAG, "")
+        .replace("</tool_use_error>", "");
+    let joined = cleaned
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if joined.chars().count() > ERROR_EXCERPT_CHARS {
+        let truncated: String = joined.chars().take(ERROR_EXCERPT_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        joined
+    }
+}
+
 // ============================================================================
 // GIT HELPERS
 // ============================================================================
@@ -128,11 +279,113 @@ const SKIP_PREFIXES: &[&str] = &[
     "hyperfine", // Don't capture benchmark commands
 ];
 
-fn classify_bash_command(cmd: &str) -> (Operation, Option<String>, CommandCategory) {
+/// How a user rule matches a command.
+#[derive(Debug)]
+enum CommandPattern {
+    /// Case-insensitive prefix
+    Prefix(String),
+    /// Regex searched in the command as typed
+    Regex(Regex),
+}
+
+/// A compiled entry of `capture.command_rules`.
+#[derive(Debug)]
+struct CommandRule {
+    pattern: CommandPattern,
+    operation: Operation,
+    category: CommandCategory,
+}
+
+impl CommandRule {
+    fn matches(&self, cmd: &str, cmd_lower: &str) -> bool {
+        match &self.pattern {
+            CommandPattern::Prefix(prefix) => cmd_lower.starts_with(prefix.as_str()),
+            CommandPattern::Regex(re) => re.is_match(cmd),
+        }
+    }
+}
+
+/// User classification rules merged ahead of the built-in ones.
+#[derive(Debug, Default)]
+struct CommandRules {
+    rules: Vec<CommandRule>,
+    /// Lowercased prefixes skipped in addition to `SKIP_PREFIXES`
+    skip_prefixes: Vec<String>,
+}
+
+impl CommandRules {
+    /// Compile the rules in the `[capture]` section.
+    ///
+    /// A rule with an invalid regex, operation, or category is dropped so a
+    /// typo never stops capture.
+    fn from_config(capture: &CaptureSection) -> Self {
+        let rules = capture
+            .command_rules
+            .iter()
+            .flatten()
+            .filter_map(|rule| {
+                let pattern = if rule.regex {
+                    CommandPattern::Regex(Regex::new(&rule.pattern).ok()?)
+                } else {
+                    CommandPattern::Prefix(rule.pattern.to_lowercase())
+                };
+                let operation = match &rule.operation {
+                    Some(op) => op.parse().ok()?,
+                    None => Operation::Execute,
+                };
+                let category = match &rule.category {
+                    Some(category) => category.parse().ok()?,
+                    None => CommandCategory::Unknown,
+                };
+                Some(CommandRule {
+                    pattern,
+                    operation,
+                    category,
+                })
+            })
+            .collect();
+        let skip_prefixes = capture
+            .skip_prefixes
+            .iter()
+            .flatten()
+            .map(|p| p.to_lowercase())
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self {
+            rules,
+            skip_prefixes,
+        }
+    }
+}
+
+/// Rules from the diachron config, compiled once per hook run.
+fn command_rules() -> &'static CommandRules {
+    static RULES: OnceLock<CommandRules> = OnceLock::new();
+    RULES.get_or_init(|| CommandRules::from_config(&hook_config().capture))
+}
+
+/// Classify a Bash command.
+///
+/// User rules are tried first, in order, then the skip prefixes (built-in
+/// and user), then the built-in rules. `Operation::Unknown` means the
+/// command is not captured.
+fn classify_bash_command(
+    cmd: &str,
+    user_rules: &CommandRules,
+) -> (Operation, Option<String>, CommandCategory) {
     let cmd_lower = cmd.to_lowercase();
 
+    if let Some(rule) = user_rules
+        .rules
+        .iter()
+        .find(|rule| rule.matches(cmd, &cmd_lower))
+    {
+        return (rule.operation, None, rule.category);
+    }
+
     // Skip read-only commands
-    for prefix in SKIP_PREFIXES {
+    let user_skips = user_rules.skip_prefixes.iter().map(String::as_str);
+    for prefix in SKIP_PREFIXES.iter().copied().chain(user_skips) {
         if cmd_lower.starts_with(prefix) {
             return (Operation::Unknown, None, CommandCategory::Unknown);
         }
@@ -266,7 +519,7 @@ fn parse_write_event(hook: &HookInput) -> CaptureEvent {
         .unwrap_or("");
 
     // Determine operation based on result
-    let operation = match &hook.tool_result {
+    let operation = match hook.tool_result_text() {
         Some(result) if result.to_lowercase().contains("overwritten") => Operation::Modify,
         _ => Operation::Create,
     };
@@ -275,14 +528,7 @@ fn parse_write_event(hook: &HookInput) -> CaptureEvent {
     let line_count = content.lines().count().max(1);
     let diff_summary = Some(format!("+{} lines", line_count));
 
-    // Truncate raw input
-    let raw_input = if content.len() > 500 {
-        Some(format!("{}...", &content[..500]))
-    } else if !content.is_empty() {
-        Some(content.to_string())
-    } else {
-        None
-    };
+    let (raw_input, raw_input_truncated) = truncate_raw_input(content, max_raw_input());
 
     CaptureEvent {
         tool_name: "Write".to_string(),
@@ -293,27 +539,81 @@ fn parse_write_event(hook: &HookInput) -> CaptureEvent {
         metadata: None,
         git_commit_sha: None,
         command_category: None,
+        outcome: None,
+        error_excerpt: None,
+        old_content: None,
+        new_content: None,
+        raw_input_truncated,
     }
 }
 
-fn parse_edit_event(hook: &HookInput) -> CaptureEvent {
+fn parse_edit_event(hook: &HookInput) -> Result<CaptureEvent, SkipReason> {
+    let file_path = hook
+        .tool_input
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    edit_event("Edit", file_path, &hook.tool_input)
+}
+
+/// One event per hunk of a `MultiEdit` call, all on the call's file.
+///
+/// Each event records its position in `edits` as `hunk` metadata, so
+/// identical hunks stay distinct events. Hunks that leave the content
+/// unchanged are dropped like no-op edits; if every hunk is one, the call
+/// is skipped.
+fn parse_multiedit_event(hook: &HookInput) -> Result<Vec<CaptureEvent>, SkipReason> {
     let file_path = hook
         .tool_input
         .get("file_path")
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    let old_string = hook
+    let events: Vec<CaptureEvent> = hook
         .tool_input
+        .get("edits")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(hunk, edit)| {
+            let mut event = edit_event("MultiEdit", file_path.clone(), edit).ok()?;
+            event.metadata = Some(json!({ "hunk": hunk }).to_string());
+            Some(event)
+        })
+        .collect();
+
+    if events.is_empty() {
+        return Err(SkipReason::NoopEdit);
+    }
+    Ok(events)
+}
+
+/// Build an edit event from an `old_string` / `new_string` pair.
+fn edit_event(
+    tool_name: &str,
+    file_path: Option<String>,
+    edit: &Value,
+) -> Result<CaptureEvent, SkipReason> {
+    let old_string = edit
         .get("old_string")
         .and_then(|v| v.as_str())
         .unwrap_or("");
-    let new_string = hook
-        .tool_input
+    let new_string = edit
         .get("new_string")
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
+    // Skip edits that leave the content unchanged (up to trailing whitespace)
+    let options = FingerprintOptions::default();
+    if hook_config().capture.skip_noop_edits.unwrap_or(true)
+        && compute_fingerprint(old_string, None, None, &options).content_hash
+            == compute_fingerprint(new_string, None, None, &options).content_hash
+    {
+        return Err(SkipReason::NoopEdit);
+    }
+
     let old_lines = old_string.lines().count().max(1);
     let new_lines = new_string.lines().count().max(1);
     let diff = new_lines as i64 - old_lines as i64;
@@ -326,30 +626,178 @@ fn parse_edit_event(hook: &HookInput) -> CaptureEvent {
         Some("modified (same line count)".to_string())
     };
 
-    CaptureEvent {
-        tool_name: "Edit".to_string(),
+    // Full before/after text for `diachron revert-preview`, untruncated
+    let store_content = hook_config().capture.store_edit_content.unwrap_or(false);
+
+    // New content is kept so the daemon can fingerprint it for blame
+    let (raw_input, raw_input_truncated) = truncate_raw_input(new_string, max_raw_input());
+
+    Ok(CaptureEvent {
+        tool_name: tool_name.to_string(),
         file_path,
         operation: Operation::Modify,
         diff_summary,
-        raw_input: None,
+        raw_input,
+        metadata: None,
+        git_commit_sha: None,
+        command_category: None,
+        outcome: None,
+        error_excerpt: None,
+        old_content: store_content.then(|| old_string.to_string()),
+        new_content: store_content.then(|| new_string.to_string()),
+        raw_input_truncated,
+    })
+}
+
+/// A `NotebookEdit` call, recorded as a change to the notebook file.
+///
+/// Deleting a cell modifies the notebook, so it is not a `delete` event.
+fn parse_notebook_event(hook: &HookInput) -> CaptureEvent {
+    let input = &hook.tool_input;
+    let file_path = input
+        .get("notebook_path")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let new_source = input
+        .get("new_source")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let cell_type = input
+        .get("cell_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("code");
+    let cell = input
+        .get("cell_id")
+        .and_then(|v| v.as_str())
+        .map(|id| format!("cell {}", id))
+        .unwrap_or_else(|| "cell".to_string());
+
+    let lines = new_source.lines().count().max(1);
+    let diff_summary = match input.get("edit_mode").and_then(|v| v.as_str()) {
+        Some("insert") => format!("inserted {} {} (+{} lines)", cell_type, cell, lines),
+        Some("delete") => format!("deleted {}", cell),
+        _ => format!("replaced {} ({} lines)", cell, lines),
+    };
+    let (raw_input, raw_input_truncated) = truncate_raw_input(new_source, max_raw_input());
+
+    CaptureEvent {
+        tool_name: "NotebookEdit".to_string(),
+        file_path,
+        operation: Operation::Modify,
+        diff_summary: Some(diff_summary),
+        raw_input,
         metadata: None,
         git_commit_sha: None,
         command_category: None,
+        outcome: None,
+        error_excerpt: None,
+        old_content: None,
+        new_content: None,
+        raw_input_truncated,
+    }
+}
+
+/// Default cap on stored `raw_input`, in bytes.
+const DEFAULT_MAX_RAW_INPUT: usize = 500;
+
+/// `[capture]` section of `~/.diachron/config.toml`, as far as the hook
+/// needs it.
+#[derive(Debug, Default, Deserialize)]
+struct HookConfig {
+    #[serde(default)]
+    capture: CaptureSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CaptureSection {
+    /// Bytes of written content or command text kept; 0 keeps everything
+    max_raw_input: Option<usize>,
+    /// Drop edits that do not change the content (default true)
+    skip_noop_edits: Option<bool>,
+    /// Keep the full old and new text of edits (default false)
+    store_edit_content: Option<bool>,
+    /// Names marking a project root (default `.diachron`)
+    root_markers: Option<Vec<String>>,
+    /// Bash classification rules tried before the built-in ones
+    command_rules: Option<Vec<CommandRuleConfig>>,
+    /// Read-only command prefixes to skip on top of the built-in list
+    skip_prefixes: Option<Vec<String>>,
+}
+
+/// One entry of `capture.command_rules`.
+#[derive(Debug, Deserialize)]
+struct CommandRuleConfig {
+    /// Command prefix, or a regex when `regex` is set
+    pattern: String,
+    /// Treat `pattern` as a regex (default false)
+    #[serde(default)]
+    regex: bool,
+    /// Operation recorded for matching commands (default `execute`)
+    operation: Option<String>,
+    /// Category recorded for matching commands (default `unknown`)
+    category: Option<String>,
+}
+
+/// Load the diachron config once per hook run.
+///
+/// A missing or invalid config reads as all defaults.
+fn hook_config() -> &'static HookConfig {
+    static CONFIG: OnceLock<HookConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        dirs::home_dir()
+            .map(|home| home.join(".diachron").join("config.toml"))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml::from_str::<HookConfig>(&content).ok())
+            .unwrap_or_default()
+    })
+}
+
+/// Read `capture.max_raw_input` from the diachron config.
+///
+/// A missing or invalid config falls back to 500 bytes; `0` means no limit.
+fn max_raw_input() -> usize {
+    let limit = hook_config()
+        .capture
+        .max_raw_input
+        .unwrap_or(DEFAULT_MAX_RAW_INPUT);
+    if limit == 0 {
+        usize::MAX
+    } else {
+        limit
+    }
+}
+
+/// Truncate content to `max` bytes (on a char boundary) for `raw_input`.
+///
+/// # Returns
+/// The text to store, ending in `...` if it was cut, and whether it was
+/// cut; content that really ends in `...` is not mistaken for a cut.
+fn truncate_raw_input(content: &str, max: usize) -> (Option<String>, bool) {
+    if content.len() > max {
+        let mut end = max;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        (Some(format!("{}...", &content[..end])), true)
+    } else if !content.is_empty() {
+        (Some(content.to_string()), false)
+    } else {
+        (None, false)
     }
 }
 
-fn parse_bash_event(hook: &HookInput, project_root: &PathBuf) -> Option<CaptureEvent> {
+fn parse_bash_event(hook: &HookInput, project_root: &PathBuf) -> Result<CaptureEvent, SkipReason> {
     let command = hook
         .tool_input
         .get("command")
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
-    let (operation, detail, category) = classify_bash_command(command);
+    let (operation, detail, category) = classify_bash_command(command, command_rules());
 
     // Skip uninteresting commands
     if operation == Operation::Unknown {
-        return None;
+        return Err(SkipReason::SkippedCommand(command.to_string()));
     }
 
     // Capture commit SHA after git commit commands
@@ -358,43 +806,92 @@ fn parse_bash_event(hook: &HookInput, project_root: &PathBuf) -> Option<CaptureE
     } else {
         None
     };
+    let (raw_input, raw_input_truncated) = truncate_raw_input(command, max_raw_input());
 
-    Some(CaptureEvent {
+    Ok(CaptureEvent {
         tool_name: "Bash".to_string(),
         file_path: None,
         operation,
         diff_summary: detail,
-        // [*TO-DO:P2*] Raw input truncated to 500 chars with no indicator
-        // Consider: adding "..." suffix, increasing limit, or storing full command
-        raw_input: Some(command.chars().take(500).collect()),
+        raw_input,
         metadata: None,
         git_commit_sha,
         command_category: Some(category),
+        outcome: None,
+        error_excerpt: None,
+        old_content: None,
+        new_content: None,
+        raw_input_truncated,
     })
 }
 
-fn parse_hook_input(hook: &HookInput, project_root: &PathBuf) -> Option<CaptureEvent> {
-    let mut event = match hook.tool_name.as_str() {
-        "Write" => Some(parse_write_event(hook)),
-        "Edit" => Some(parse_edit_event(hook)),
-        "Bash" => parse_bash_event(hook, project_root),
-        _ => None,
-    }?;
+/// Parse a hook call into the events it records.
+///
+/// Most tools record one event; `MultiEdit` records one per hunk.
+fn parse_hook_input(
+    hook: &HookInput,
+    project_root: &PathBuf,
+) -> Result<Vec<CaptureEvent>, SkipReason> {
+    let mut events = match hook.tool_name.as_str() {
+        "Write" => vec![parse_write_event(hook)],
+        "Edit" => vec![parse_edit_event(hook)?],
+        "MultiEdit" => parse_multiedit_event(hook)?,
+        "NotebookEdit" => vec![parse_notebook_event(hook)],
+        "Bash" => vec![parse_bash_event(hook, project_root)?],
+        other => return Err(SkipReason::UntrackedTool(other.to_string())),
+    };
 
-    // Add git branch metadata
+    let outcome = detect_outcome(hook);
     let git_branch = get_current_branch(project_root);
-    if git_branch.is_some() || event.command_category.is_some() {
-        let mut meta = json!({});
-        if let Some(branch) = &git_branch {
-            meta["git_branch"] = json!(branch);
+
+    for event in &mut events {
+        if let Some((outcome, excerpt)) = &outcome {
+            event.outcome = Some(*outcome);
+            event.error_excerpt = excerpt.clone();
         }
-        if let Some(category) = &event.command_category {
-            meta["command_category"] = json!(category.as_str());
+
+        // Add git branch, working directory, session and outcome metadata
+        // (the local DB fallback only sees what ends up in here)
+        if event.metadata.is_some()
+            || git_branch.is_some()
+            || hook.cwd.is_some()
+            || hook.session_id.is_some()
+            || event.command_category.is_some()
+            || event.outcome.is_some()
+            || event.raw_input_truncated
+        {
+            let mut meta = event
+                .metadata
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or_else(|| json!({}));
+            if let Some(branch) = &git_branch {
+                meta["git_branch"] = json!(branch);
+            }
+            if let Some(cwd) = &hook.cwd {
+                meta["cwd"] = json!(cwd);
+            }
+            // Lets the daemon chain edits to the same file within a session
+            if let Some(session_id) = &hook.session_id {
+                meta["session_id"] = json!(session_id);
+            }
+            if let Some(category) = &event.command_category {
+                meta["command_category"] = json!(category.as_str());
+            }
+            if let Some(outcome) = &event.outcome {
+                meta["outcome"] = json!(outcome.as_str());
+            }
+            if let Some(excerpt) = &event.error_excerpt {
+                meta["error_excerpt"] = json!(excerpt);
+            }
+            if event.raw_input_truncated {
+                meta["raw_input_truncated"] = json!(true);
+            }
+            event.metadata = Some(meta.to_string());
         }
-        event.metadata = Some(meta.to_string());
     }
 
-    Some(event)
+    Ok(events)
 }
 
 // ============================================================================
@@ -487,6 +984,8 @@ fn save_to_local_db(event: &CaptureEvent, project_root: &PathBuf) -> rusqlite::R
     let db_path = diachron_dir.join("events.db");
 
     let conn = Connection::open(&db_path)?;
+    // Parallel tool calls run concurrent hooks against the same file
+    conn.busy_timeout(BUSY_TIMEOUT)?;
     init_schema(&conn)?;
 
     let (timestamp_iso, timestamp_display) = get_timestamp();
@@ -518,19 +1017,124 @@ fn save_to_local_db(event: &CaptureEvent, project_root: &PathBuf) -> rusqlite::R
 // PROJECT ROOT DETECTION
 // ============================================================================
 
-fn find_project_root(start_path: Option<PathBuf>) -> Option<PathBuf> {
-    let mut current = start_path.or_else(|| env::current_dir().ok())?;
+/// Environment variable overriding `capture.root_markers` (comma-separated)
+const ROOT_MARKERS_ENV_VAR: &str = "DIACHRON_ROOT_MARKERS";
+
+/// Marker used when none are configured
+const DEFAULT_ROOT_MARKER: &str = ".diachron";
+
+/// Names whose presence marks a Diachron-enabled project root.
+///
+/// `DIACHRON_ROOT_MARKERS` takes precedence over `capture.root_markers`;
+/// both fall back to `.diachron`.
+fn root_markers() -> Vec<String> {
+    let parse = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    env::var(ROOT_MARKERS_ENV_VAR)
+        .ok()
+        .map(|list| parse(&list))
+        .filter(|markers| !markers.is_empty())
+        .or_else(|| {
+            hook_config()
+                .capture
+                .root_markers
+                .clone()
+                .filter(|markers| !markers.is_empty())
+        })
+        .unwrap_or_else(|| vec![DEFAULT_ROOT_MARKER.to_string()])
+}
+
+/// Find the outermost ancestor of `start` containing one of `markers`.
+///
+/// Nested repositories (a `.git` in a submodule or vendored package) do not
+/// stop the walk, so edits inside them are attributed to the enclosing
+/// project. The home directory is never a root: `~/.diachron` holds the
+/// daemon's data, not a project marker.
+fn find_project_root(start: &Path, markers: &[String], home: Option<&Path>) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .filter(|dir| Some(*dir) != home)
+        .filter(|dir| markers.iter().any(|m| dir.join(m).exists()))
+        .last()
+        .map(Path::to_path_buf)
+}
+
+/// Resolve the project a hook call belongs to, from its `cwd` or the
+/// current directory.
+fn resolve_project_root(hook: &HookInput) -> Result<PathBuf, SkipReason> {
+    let start_path = match hook.cwd.as_ref().map(PathBuf::from) {
+        Some(p) => p,
+        None => env::current_dir().map_err(|_| SkipReason::NoWorkingDirectory)?,
+    };
+    let home = dirs::home_dir();
+    find_project_root(&start_path, &root_markers(), home.as_deref())
+        .ok_or(SkipReason::NoProjectRoot(start_path))
+}
 
-    loop {
-        if current.join(".diachron").exists() {
-            return Some(current);
+// ============================================================================
+// DRY RUN
+// ============================================================================
+
+/// Environment variable enabling the dry run (`DIACHRON_HOOK_DEBUG=1`)
+const DEBUG_ENV_VAR: &str = "DIACHRON_HOOK_DEBUG";
+
+/// Whether this run only reports what it would capture.
+fn is_dry_run() -> bool {
+    env::args().skip(1).any(|arg| arg == "--dry-run")
+        || env::var(DEBUG_ENV_VAR).is_ok_and(|v| v == "1")
+}
+
+/// Print what a normal run would do with `input`, without writing anything.
+///
+/// Goes to stderr so the hook's stdout stays empty.
+fn dry_run(input: &str) {
+    eprintln!("diachron-hook dry run (nothing is written)");
+    let hook = match parse_input(input) {
+        Ok(hook) => hook,
+        Err(reason) => {
+            eprintln!("Skipped: {}", reason);
+            return;
+        }
+    };
+    eprintln!("Tool: {}", hook.tool_name);
+
+    let project_root = match resolve_project_root(&hook) {
+        Ok(root) => root,
+        Err(reason) => {
+            eprintln!("Project root: none");
+            eprintln!("Skipped: {}", reason);
+            return;
         }
-        if current.join(".git").exists() {
-            // Found git root but no .diachron
-            return None;
+    };
+    eprintln!("Project root: {}", project_root.display());
+
+    match parse_hook_input(&hook, &project_root) {
+        Ok(events) => {
+            for event in &events {
+                let json = serde_json::to_string_pretty(event).unwrap_or_default();
+                eprintln!("Event:\n{}", json);
+            }
         }
-        if !current.pop() {
-            return None;
+        Err(reason) => {
+            eprintln!("Skipped: {}", reason);
+            return;
+        }
+    }
+
+    let transport = default_transport();
+    match transport.connect() {
+        Ok(_) => eprintln!("Daemon: reachable at {}", transport.describe()),
+        Err(e) => {
+            eprintln!("Daemon: not reachable at {} ({})", transport.describe(), e);
+            eprintln!(
+                "Fallback: would write to {}",
+                project_root.join(".diachron").join("events.db").display()
+            );
         }
     }
 }
@@ -546,48 +1150,356 @@ fn main() {
         std::process::exit(0);
     }
 
-    // Skip empty input
-    if input.trim().is_empty() {
+    if is_dry_run() {
+        dry_run(&input);
         std::process::exit(0);
     }
 
-    // Parse hook input
-    let hook: HookInput = match serde_json::from_str(&input) {
+    // Parse hook input (skip empty or malformed input)
+    let hook = match parse_input(&input) {
         Ok(h) => h,
         Err(_) => std::process::exit(0),
     };
 
     // Determine project root from cwd in hook or current directory
-    let start_path = hook.cwd.as_ref().map(PathBuf::from);
-    let project_root = match find_project_root(start_path) {
-        Some(p) => p,
-        None => std::process::exit(0), // Not in a Diachron-enabled project
+    let project_root = match resolve_project_root(&hook) {
+        Ok(p) => p,
+        Err(_) => std::process::exit(0), // Not in a Diachron-enabled project
     };
 
-    // Parse event
-    let event = match parse_hook_input(&hook, &project_root) {
-        Some(e) => e,
-        None => std::process::exit(0), // Event should be skipped
+    // Parse events
+    let events = match parse_hook_input(&hook, &project_root) {
+        Ok(e) => e,
+        Err(_) => std::process::exit(0), // Event should be skipped
     };
 
-    // Try sending to daemon first (preferred path)
-    match send_to_daemon(event.clone()) {
-        Ok(()) => {
-            // Daemon handled the event - success!
-            std::process::exit(0);
+    for event in events {
+        // Try sending to daemon first (preferred path)
+        match send_to_daemon(event.clone()) {
+            Ok(()) => {
+                // Daemon handled the event - success!
+                continue;
+            }
+            Err(IpcError::DaemonNotRunning) => {
+                // Daemon not available - fall back to local DB
+                // This is the expected path when daemon hasn't been started
+            }
+            Err(_) => {
+                // Other error - fall back to local DB
+                // Could log this error somewhere for debugging
+            }
         }
-        Err(IpcError::DaemonNotRunning) => {
-            // Daemon not available - fall back to local DB
-            // This is the expected path when daemon hasn't been started
+
+        // Fallback: Save directly to local project database
+        let _ = save_to_local_db(&event, &project_root);
+    }
+
+    std::process::exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = env::temp_dir().join(format!("diachron-{}-test-{}", name, nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn markers() -> Vec<String> {
+        vec![DEFAULT_ROOT_MARKER.to_string()]
+    }
+
+    #[test]
+    fn test_single_repo_root() {
+        let root = temp_dir("root-single");
+        fs::create_dir_all(root.join(".diachron")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+
+        assert_eq!(
+            find_project_root(&root.join("src/bin"), &markers(), None),
+            Some(root.clone())
+        );
+        assert_eq!(
+            find_project_root(&root, &markers(), None),
+            Some(root.clone())
+        );
+
+        // A repository without .diachron is not captured
+        let plain = temp_dir("root-plain");
+        fs::create_dir_all(plain.join(".git")).unwrap();
+        assert_eq!(find_project_root(&plain, &markers(), None), None);
+
+        fs::remove_dir_all(&root).ok();
+        fs::remove_dir_all(&plain).ok();
+    }
+
+    #[test]
+    fn test_submodule_resolves_to_enclosing_root() {
+        let root = temp_dir("root-submodule");
+        fs::create_dir_all(root.join(".diachron")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        let submodule = root.join("vendor/lib");
+        fs::create_dir_all(submodule.join("src")).unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+
+        assert_eq!(
+            find_project_root(&submodule.join("src"), &markers(), None),
+            Some(root.clone())
+        );
+
+        // The outermost .diachron wins over one inside the submodule
+        fs::create_dir_all(submodule.join(".diachron")).unwrap();
+        assert_eq!(
+            find_project_root(&submodule.join("src"), &markers(), None),
+            Some(root.clone())
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_custom_markers_and_home_exclusion() {
+        let home = temp_dir("root-home");
+        fs::create_dir_all(home.join(".diachron")).unwrap();
+        let project = home.join("code/app");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join(".diachron-root"), "").unwrap();
+
+        // ~/.diachron is the data directory, not a project marker
+        assert_eq!(
+            find_project_root(&project.join("src"), &markers(), Some(&home)),
+            None
+        );
+
+        let custom = vec![".diachron".to_string(), ".diachron-root".to_string()];
+        assert_eq!(
+            find_project_root(&project.join("src"), &custom, Some(&home)),
+            Some(project.clone())
+        );
+
+        fs::remove_dir_all(&home).ok();
+    }
+
+    #[test]
+    fn test_user_command_rules_and_skip_prefixes() {
+        let config: HookConfig = toml::from_str(
+            r#"
+            [capture]
+            skip_prefixes = ["just fmt"]
+            command_rules = [
+                { pattern = "just test", category = "test" },
+                { pattern = "^pnpm (i|install|add)\\b", regex = true, category = "package" },
+                { pattern = "make deploy", category = "deploy" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let rules = CommandRules::from_config(&config.capture);
+
+        let (operation, _, category) = classify_bash_command("just test --all", &rules);
+        assert_eq!(operation, Operation::Execute);
+        assert_eq!(category, CommandCategory::Test);
+        let (_, _, category) = classify_bash_command("pnpm add zod", &rules);
+        assert_eq!(category, CommandCategory::Package);
+        // User rules win over the built-in `make` => Build
+        let (_, _, category) = classify_bash_command("make deploy", &rules);
+        assert_eq!(category, CommandCategory::Deploy);
+
+        // Skipped commands classify as Unknown and are not captured
+        let (operation, _, _) = classify_bash_command("just fmt", &rules);
+        assert_eq!(operation, Operation::Unknown);
+        let (operation, _, _) = classify_bash_command("just fmt", &CommandRules::default());
+        assert_eq!(operation, Operation::Execute);
+        // Built-in skips still apply
+        let (operation, _, _) = classify_bash_command("git status", &rules);
+        assert_eq!(operation, Operation::Unknown);
+    }
+
+    #[test]
+    fn test_read_only_command_reports_skip_reason() {
+        let root = temp_dir("dry-run");
+        fs::create_dir_all(root.join(".diachron")).unwrap();
+        let input = json!({
+            "tool_name": "Bash",
+            "tool_input": { "command": "git status --short" },
+            "cwd": root.join("src").display().to_string(),
+        })
+        .to_string();
+
+        let hook = parse_input(&input).unwrap();
+        let project_root = resolve_project_root(&hook).unwrap();
+        assert_eq!(project_root, root);
+
+        let reason = parse_hook_input(&hook, &project_root).unwrap_err();
+        assert_eq!(
+            reason,
+            SkipReason::SkippedCommand("git status --short".to_string())
+        );
+        assert!(reason
+            .to_string()
+            .starts_with("read-only or skipped command"));
+
+        assert_eq!(parse_input("  \n").unwrap_err(), SkipReason::EmptyInput);
+        let outside = json!({ "tool_name": "Bash", "tool_input": {}, "cwd": "/" }).to_string();
+        assert!(matches!(
+            resolve_project_root(&parse_input(&outside).unwrap()),
+            Err(SkipReason::NoProjectRoot(_))
+        ));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_outcome_trusts_only_the_error_tag_and_exit_codes() {
+        let outcome = |tool: &str, result: Value| {
+            let input = json!({ "tool_name": tool, "tool_input": {}, "tool_result": result });
+            detect_outcome(&parse_input(&input.to_string()).unwrap())
+        };
+
+        // Error phrases inside file contents or program output are not failures
+        for (tool, result) in [
+            (
+                "Write",
+                "File created: docs/errors.md (Permission denied, No such file or directory)",
+            ),
+            (
+                "Edit",
+                "Updated src/main.rs: \"old_string not found\" is now a comment",
+            ),
+            ("Read", "const TAG: &str = \"<tool_use_error>\";"),
+            (
+                "Bash",
+                "grep: foo: No such file or directory is expected here",
+            ),
+        ] {
+            assert_eq!(
+                outcome(tool, json!(result)),
+                Some((Outcome::Success, None)),
+                "{}",
+                result
+            );
         }
-        Err(_) => {
-            // Other error - fall back to local DB
-            // Could log this error somewhere for debugging
+
+        let (failed, excerpt) = outcome(
+            "Edit",
+            json!("<tool_use_error>String to replace not found in file.</tool_use_error>"),
+        )
+        .unwrap();
+        assert_eq!(failed, Outcome::Failure);
+        assert_eq!(
+            excerpt.as_deref(),
+            Some("String to replace not found in file.")
+        );
+
+        let (failed, _) = outcome("Bash", json!("Exit code 2\nerror[E0425]")).unwrap();
+        assert_eq!(failed, Outcome::Failure);
+        let (failed, excerpt) =
+            outcome("Write", json!({ "is_error": true, "error": "disk full" })).unwrap();
+        assert_eq!(failed, Outcome::Failure);
+        assert_eq!(excerpt.as_deref(), Some("disk full"));
+        assert_eq!(outcome("Write", Value::Null), None);
+    }
+
+    #[test]
+    fn test_multiedit_records_one_event_per_hunk() {
+        let root = temp_dir("multiedit");
+        let input = json!({
+            "tool_name": "MultiEdit",
+            "tool_input": {
+                "file_path": "/repo/src/auth.rs",
+                "edits": [
+                    { "old_string": "fn login() {}", "new_string": "fn login() {\n    refresh();\n}" },
+                    { "old_string": "let retries = 1;", "new_string": "let retries = 3;" },
+                    { "old_string": "// same", "new_string": "// same" },
+                ],
+            },
+            "session_id": "s1",
+        })
+        .to_string();
+
+        let hook = parse_input(&input).unwrap();
+        let events = parse_hook_input(&hook, &root).unwrap();
+        assert_eq!(events.len(), 2);
+        for event in &events {
+            assert_eq!(event.tool_name, "MultiEdit");
+            assert_eq!(event.file_path.as_deref(), Some("/repo/src/auth.rs"));
+            assert_eq!(event.operation, Operation::Modify);
+            assert!(event
+                .metadata
+                .as_deref()
+                .unwrap()
+                .contains("\"session_id\":\"s1\""));
         }
+        assert_eq!(events[0].diff_summary.as_deref(), Some("+2 lines"));
+        assert_eq!(events[1].raw_input.as_deref(), Some("let retries = 3;"));
+
+        // Repeated hunks are told apart by their position in the call
+        let repeated = json!({
+            "tool_name": "MultiEdit",
+            "tool_input": {
+                "file_path": "a.rs",
+                "edits": [
+                    { "old_string": "x", "new_string": "y" },
+                    { "old_string": "x", "new_string": "y" },
+                ],
+            },
+        })
+        .to_string();
+        let events = parse_hook_input(&parse_input(&repeated).unwrap(), &root).unwrap();
+        let hunks: Vec<Value> = events
+            .iter()
+            .map(|e| {
+                serde_json::from_str::<Value>(e.metadata.as_deref().unwrap()).unwrap()["hunk"]
+                    .clone()
+            })
+            .collect();
+        assert_eq!(hunks, [json!(0), json!(1)]);
+
+        // A call made only of no-op hunks records nothing
+        let noop = json!({
+            "tool_name": "MultiEdit",
+            "tool_input": { "file_path": "a.rs", "edits": [{ "old_string": "x", "new_string": "x" }] },
+        })
+        .to_string();
+        assert_eq!(
+            parse_hook_input(&parse_input(&noop).unwrap(), &root).unwrap_err(),
+            SkipReason::NoopEdit
+        );
+
+        fs::remove_dir_all(&root).ok();
     }
 
-    // Fallback: Save directly to local project database
-    let _ = save_to_local_db(&event, &project_root);
+    #[test]
+    fn test_notebook_edit_modifies_the_notebook() {
+        let root = temp_dir("notebook");
+        let input = json!({
+            "tool_name": "NotebookEdit",
+            "tool_input": {
+                "notebook_path": "/repo/analysis.ipynb",
+                "cell_id": "c3",
+                "new_source": "import pandas as pd\ndf = pd.read_csv('x.csv')",
+                "cell_type": "code",
+                "edit_mode": "insert",
+            },
+        })
+        .to_string();
 
-    std::process::exit(0);
+        let events = parse_hook_input(&parse_input(&input).unwrap(), &root).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].file_path.as_deref(), Some("/repo/analysis.ipynb"));
+        assert_eq!(events[0].operation, Operation::Modify);
+        assert_eq!(
+            events[0].diff_summary.as_deref(),
+            Some("inserted code cell c3 (+2 lines)")
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
 }
@@ -34,8 +34,11 @@ fn create_test_db() -> Connection {
             metadata TEXT,
             prev_hash BLOB,
             event_hash BLOB,
+            field_hashes TEXT,
             content_hash BLOB,
-            context_hash BLOB
+            context_hash BLOB,
+            semantic_sig BLOB,
+            project TEXT
         );
         CREATE INDEX idx_events_hash ON events(event_hash);
         CREATE INDEX idx_events_timestamp ON events(timestamp);
@@ -46,12 +49,14 @@ fn create_test_db() -> Connection {
             date TEXT NOT NULL,
             event_count INTEGER NOT NULL,
             final_hash BLOB NOT NULL,
+            merkle_root BLOB,
+            last_event_id INTEGER,
             signature BLOB,
             created_at TEXT NOT NULL
         );
 
         CREATE TABLE schema_version (version INTEGER PRIMARY KEY);
-        INSERT INTO schema_version VALUES (4);
+        INSERT INTO schema_version VALUES (9);
         ",
     )
     .expect("Failed to create schema");
@@ -229,7 +234,8 @@ fn test_checkpoint_creation() {
     );
 
     // Create checkpoint
-    let checkpoint = create_checkpoint(&conn, "2026-01-11").expect("Failed to create checkpoint");
+    let checkpoint =
+        create_checkpoint(&conn, "2026-01-11", None).expect("Failed to create checkpoint");
 
     assert_eq!(checkpoint.date, "2026-01-11");
     assert_eq!(checkpoint.event_count, 2);
@@ -247,7 +253,9 @@ fn test_checkpoint_creation() {
 
 #[test]
 fn test_pr_correlation_direct_match() {
-    use diachron_core::pr_correlation::{correlate_events_to_pr, MatchConfidence};
+    use diachron_core::pr_correlation::{
+        correlate_events_to_pr, CorrelationConfig, MatchConfidence,
+    };
 
     let conn = create_test_db();
 
@@ -279,6 +287,7 @@ fn test_pr_correlation_direct_match() {
         "feat/auth",
         "2026-01-10T00:00:00.000",
         "2026-01-12T00:00:00.000",
+        &CorrelationConfig::default(),
     )
     .expect("Failed to correlate");
 
@@ -292,7 +301,9 @@ fn test_pr_correlation_direct_match() {
 
 #[test]
 fn test_pr_correlation_session_match() {
-    use diachron_core::pr_correlation::{correlate_events_to_pr, MatchConfidence};
+    use diachron_core::pr_correlation::{
+        correlate_events_to_pr, CorrelationConfig, MatchConfidence,
+    };
 
     let conn = create_test_db();
 
@@ -324,6 +335,7 @@ fn test_pr_correlation_session_match() {
         "feat/auth",
         "2026-01-10T00:00:00.000",
         "2026-01-12T00:00:00.000",
+        &CorrelationConfig::default(),
     )
     .expect("Failed to correlate");
 
@@ -337,7 +349,9 @@ fn test_pr_correlation_session_match() {
 
 #[test]
 fn test_evidence_pack_generation() {
-    use diachron_core::pr_correlation::{correlate_events_to_pr, MatchConfidence, PRSummary};
+    use diachron_core::pr_correlation::{
+        correlate_events_to_pr, CorrelationConfig, MatchConfidence, PRSummary,
+    };
 
     let conn = create_test_db();
 
@@ -369,6 +383,7 @@ fn test_evidence_pack_generation() {
         "fix/auth",
         "2026-01-10T00:00:00.000",
         "2026-01-12T00:00:00.000",
+        &CorrelationConfig::default(),
     )
     .expect("Failed to correlate");
 
@@ -380,7 +395,7 @@ fn test_evidence_pack_generation() {
     assert!(!pack.commits.is_empty());
 
     // Render markdown
-    let markdown = render_markdown_narrative(&pack);
+    let markdown = render_markdown_narrative(&pack, "PR #42", None).unwrap();
 
     assert!(markdown.contains("## PR #42"));
     assert!(markdown.contains("Fix auth refresh"));
@@ -392,7 +407,7 @@ fn test_evidence_pack_generation() {
 fn test_markdown_rendering_with_verification() {
     use diachron_core::evidence_pack::{EvidencePack, VerificationStatus};
     use diachron_core::pr_correlation::{CommitEvidence, MatchConfidence, PRSummary};
-    use diachron_core::types::StoredEvent;
+    use diachron_core::types::{Operation, StoredEvent};
 
     let pack = EvidencePack {
         pr_id: 123,
@@ -415,12 +430,13 @@ fn test_markdown_rendering_with_verification() {
                 session_id: Some("session-1".to_string()),
                 tool_name: "Write".to_string(),
                 file_path: Some("src/auth/oauth.rs".to_string()),
-                operation: Some("create".to_string()),
+                operation: Some(Operation::Create),
                 diff_summary: Some("+50 lines".to_string()),
                 raw_input: None,
                 ai_summary: None,
                 git_commit_sha: Some("deadbeef12345678".to_string()),
                 metadata: None,
+                command_category: None,
             }],
             confidence: MatchConfidence::High,
         }],
@@ -433,9 +449,10 @@ fn test_markdown_rendering_with_verification() {
         intent: Some("Add OAuth2 login flow".to_string()),
         coverage_pct: 95.5,
         unmatched_count: 1,
+        unmatched_events: vec![],
     };
 
-    let md = render_markdown_narrative(&pack);
+    let md = render_markdown_narrative(&pack, "PR #123", None).unwrap();
 
     // Check header
     assert!(md.contains("## PR #123"));
@@ -468,12 +485,15 @@ fn test_markdown_rendering_with_verification() {
 
 #[test]
 fn test_fingerprint_matching() {
-    use diachron_core::fingerprint::{compute_fingerprint, cosine_similarity, match_fingerprint};
+    use diachron_core::fingerprint::{
+        compute_fingerprint, cosine_similarity, match_fingerprint, FingerprintOptions,
+    };
 
     // Create fingerprints for similar content
-    let fp1 = compute_fingerprint("fn hello() { println!(\"Hello\"); }", None, None);
-    let fp2 = compute_fingerprint("fn hello() { println!(\"Hello\"); }", None, None);
-    let fp3 = compute_fingerprint("fn goodbye() { println!(\"Bye\"); }", None, None);
+    let options = FingerprintOptions::default();
+    let fp1 = compute_fingerprint("fn hello() { println!(\"Hello\"); }", None, None, &options);
+    let fp2 = compute_fingerprint("fn hello() { println!(\"Hello\"); }", None, None, &options);
+    let fp3 = compute_fingerprint("fn goodbye() { println!(\"Bye\"); }", None, None, &options);
 
     // Identical content should have same hash
     assert_eq!(fp1.content_hash, fp2.content_hash);
@@ -513,6 +533,7 @@ fn test_json_export() {
         intent: None,
         coverage_pct: 100.0,
         unmatched_count: 0,
+        unmatched_events: vec![],
     };
 
     let json = export_json(&pack).expect("Failed to export JSON");
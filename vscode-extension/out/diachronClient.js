@@ -162,9 +162,9 @@ class DiachronClient {
                     limit: 100,
                 },
             });
-            if (response.type === 'Events' && response.payload) {
+            if (response.type === 'EventsPage' && response.payload) {
                 // Convert StoredEvent to BlameMatch format
-                return response.payload.map(event => ({
+                return response.payload.events.map(event => ({
                     event,
                     confidence: 'medium',
                     match_type: 'timeline',
@@ -181,17 +181,20 @@ export class DiachronClient {
             // Use Timeline message to get events filtered by file path
             const response = await this.sendMessage<{
                 type: string;
-                payload?: Array<{
-                    id: number;
-                    timestamp: string;
-                    tool_name: string;
-                    file_path: string;
-                    operation: string;
-                    diff_summary?: string;
-                    session_id?: string;
-                    git_branch?: string;
-                    git_commit_sha?: string;
-                }>;
+                payload?: {
+                    events: Array<{
+                        id: number;
+                        timestamp: string;
+                        tool_name: string;
+                        file_path: string;
+                        operation: string;
+                        diff_summary?: string;
+                        session_id?: string;
+                        git_branch?: string;
+                        git_commit_sha?: string;
+                    }>;
+                    next_cursor: number | null;
+                };
             }>({
                 type: 'Timeline',
                 payload: {
@@ -201,9 +204,9 @@ export class DiachronClient {
                 },
             });
 
-            if (response.type === 'Events' && response.payload) {
+            if (response.type === 'EventsPage' && response.payload) {
                 // Convert StoredEvent to BlameMatch format
-                return response.payload.map(event => ({
+                return response.payload.events.map(event => ({
                     event,
                     confidence: 'medium',
                     match_type: 'timeline',